@@ -201,6 +201,7 @@ impl Read for FileWithProgress {
 
 /// Returns path on success
 pub fn write_file(path: String, contents: String) -> Result<String> {
+    fs_err::create_dir_all(std::path::Path::new(&path).parent().unwrap())?;
     let mut file = File::create(&path)?;
     write!(file, "{}", contents)?;
     Ok(path)
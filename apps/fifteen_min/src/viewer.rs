@@ -262,6 +262,12 @@ fn options_to_controls(ctx: &mut EventCtx, opts: &Options) -> Widget {
                     .map(|(label, speed)| Choice::new(label, speed))
                     .collect(),
             ));
+            rows.push(Toggle::switch(
+                ctx,
+                "Ride public transit",
+                None,
+                opts.use_transit,
+            ));
 
             rows.push(ColorLegend::row(ctx, Color::BLUE, "unwalkable roads"));
         }
@@ -279,6 +285,9 @@ fn options_from_controls(panel: &Panel) -> MovementOptions {
             walking_speed: panel
                 .maybe_dropdown_value("speed")
                 .unwrap_or_else(WalkingOptions::default_speed),
+            use_transit: panel
+                .maybe_is_checked("Ride public transit")
+                .unwrap_or(false),
         })
     } else {
         MovementOptions::Biking
@@ -12,18 +12,19 @@ use geom::{Bounds, Circle, Distance, Duration, FindClosest, Polygon, Pt2D, Tesse
 use map_gui::colors::ColorScheme;
 use map_gui::options::Options;
 use map_gui::render::{DrawMap, DrawOptions};
-use map_gui::tools::CameraState;
+use map_gui::tools::{draw_scale_bar, CameraState};
 use map_model::AreaType;
 use map_model::{BufferType, IntersectionID, LaneType, Map, Traversable};
 use sim::{AgentID, Analytics, Sim, SimCallback, SimFlags, VehicleType};
 use synthpop::Scenario;
-use widgetry::mapspace::ToggleZoomed;
+use widgetry::mapspace::{DrawCache, ToggleZoomed};
 use widgetry::{Cached, Canvas, EventCtx, GfxCtx, Prerender, SharedAppState, State};
 
 use crate::challenges::HighScore;
-use crate::common::Warping;
+use crate::common::{Audio, Warping};
 use crate::edit::apply_map_edits;
 use crate::layer::Layer;
+use crate::plugins::InfoPanelRows;
 use crate::render::{unzoomed_agent_radius, AgentCache, GameRenderable};
 use crate::sandbox::dashboards::DashTab;
 use crate::sandbox::{GameplayMode, TutorialState};
@@ -49,6 +50,8 @@ pub struct App {
 
     /// Static data that lasts the entire session. Use sparingly.
     pub session: SessionState,
+
+    pub audio: Audio,
 }
 
 impl App {
@@ -133,22 +136,16 @@ impl App {
         if g.canvas.is_unzoomed() {
             let layers = show_objs.layers();
             if layers.show_areas {
-                g.redraw(&draw_map.draw_all_areas);
+                draw_map.draw_unzoomed_areas(g);
             }
             if layers.show_parking_lots {
-                g.redraw(&draw_map.draw_all_unzoomed_parking_lots);
+                draw_map.draw_unzoomed_parking_lots(g);
             }
             if layers.show_intersections || layers.show_lanes {
-                g.redraw(
-                    &self
-                        .primary
-                        .draw_map
-                        .draw_all_unzoomed_roads_and_intersections,
-                );
+                draw_map.draw_unzoomed_roads_and_intersections(g);
             }
             if layers.show_buildings {
-                g.redraw(&draw_map.draw_all_buildings);
-                g.redraw(&draw_map.draw_all_building_outlines);
+                draw_map.draw_unzoomed_buildings(g);
             }
 
             // Still show some shape selection when zoomed out.
@@ -165,13 +162,23 @@ impl App {
             }
 
             let mut cache = self.primary.agents.borrow_mut();
-            cache.draw_unzoomed_agents(
-                g,
-                &self.primary.map,
-                &self.primary.sim,
-                &self.cs,
-                &self.opts,
-            );
+            if let Some(resolution) = draw_map.lod_resolution(g.canvas.cam_zoom) {
+                cache.draw_agent_density(
+                    g,
+                    &self.primary.map,
+                    &self.primary.sim,
+                    &self.cs,
+                    resolution,
+                );
+            } else {
+                cache.draw_unzoomed_agents(
+                    g,
+                    &self.primary.map,
+                    &self.primary.sim,
+                    &self.cs,
+                    &self.opts,
+                );
+            }
 
             if let Some(a) = self
                 .primary
@@ -232,9 +239,16 @@ impl App {
             }
         }
 
+        if self.opts.day_night_lighting {
+            draw_map.draw_lighting(g, map, self.primary.sim.time());
+        }
+
         if let Some(i) = sample_intersection {
             g.set_screencap_naming_hint(i);
         }
+        if !g.is_screencap() {
+            draw_scale_bar(g, self);
+        }
     }
 
     /// Assumes some defaults.
@@ -474,6 +488,12 @@ impl App {
 }
 
 impl App {
+    /// Adds rows to the main tab of info panels, for any objects that `rows` describes. They're
+    /// shown after the built-in details.
+    pub fn add_info_panel_rows(&mut self, rows: InfoPanelRows) {
+        self.session.info_panel_rows.push(rows);
+    }
+
     /// If an intersection was clicked, return its ID.
     pub fn click_on_intersection<S: Into<String>>(
         &mut self,
@@ -639,6 +659,8 @@ pub struct PerMap {
     pub draw_map: DrawMap,
     pub sim: Sim,
     pub agents: RefCell<AgentCache>,
+    /// Uploaded layers, so toggling one again doesn't tessellate the map. Dirtied by map edits.
+    pub static_layers: RefCell<DrawCache<ToggleZoomed>>,
 
     pub current_selection: Option<ID>,
     pub current_flags: Flags,
@@ -654,6 +676,12 @@ pub struct PerMap {
     pub unedited_map: Option<Map>,
 
     pub layer: Option<Box<dyn Layer>>,
+    /// How opaque `layer` is drawn, from 0 to 1
+    pub layer_opacity: f64,
+    /// Another layer drawn underneath `layer`, so two can be viewed together. It doesn't respond to
+    /// events or update while it's underneath; only its contents are drawn.
+    pub background_layer: Option<Box<dyn Layer>>,
+    pub background_layer_opacity: f64,
     /// Only filled out in edit mode. Stored here once to avoid lots of clones. Used for preview.
     pub suspended_sim: Option<Sim>,
     /// Only exists in some gameplay modes. Must be carefully reset otherwise. Has the map and
@@ -696,6 +724,7 @@ impl PerMap {
             draw_map,
             sim,
             agents: RefCell::new(AgentCache::new()),
+            static_layers: RefCell::new(DrawCache::new()),
             current_selection: None,
             current_flags: flags,
             last_warped_from: None,
@@ -704,6 +733,9 @@ impl PerMap {
             has_modified_trips: false,
             unedited_map: None,
             layer: None,
+            layer_opacity: 1.0,
+            background_layer: None,
+            background_layer_opacity: 0.5,
             suspended_sim: None,
             prebaked: None,
             scenario: None,
@@ -796,6 +828,10 @@ pub struct SessionState {
     pub last_gmns_timing_csv: Option<String>,
     pub dash_tab: DashTab,
     pub buffer_lane_type: LaneType,
+    /// Syncing edits with other people, if they've joined a session
+    pub collab: Option<crate::edit::Collaboration>,
+    /// Extra rows that layers and analyses add to info panels
+    pub info_panel_rows: Vec<InfoPanelRows>,
 
     // Specific to the ungap tool
     pub elevation_contours: Cached<MapName, (FindClosest<Distance>, ToggleZoomed)>,
@@ -820,6 +856,8 @@ impl SessionState {
             last_gmns_timing_csv: None,
             dash_tab: DashTab::TripTable,
             buffer_lane_type: LaneType::Buffer(BufferType::Stripes),
+            collab: None,
+            info_panel_rows: vec![crate::layer::map::TRAFFIC_STRESS_ROWS],
 
             elevation_contours: Cached::new(),
             routing_preferences: crate::ungap::RoutingPreferences::default(),
@@ -876,10 +914,25 @@ impl SharedAppState for App {
         self.per_obj.reset();
     }
 
+    fn after_event(&mut self, ctx: &mut EventCtx) {
+        self.audio.event(ctx, &self.primary, &self.opts.volume);
+    }
+
     fn draw_default(&self, g: &mut GfxCtx) {
         self.draw(g, DrawOptions::new(), &ShowEverything::new());
     }
 
+    fn unsaved_work(&self) -> Option<String> {
+        if self.primary.map.unsaved_edits() {
+            Some(format!(
+                "Your proposal \"{}\" hasn't been saved.",
+                self.primary.map.get_edits().edits_name
+            ))
+        } else {
+            None
+        }
+    }
+
     fn dump_before_abort(&self, canvas: &Canvas) {
         println!();
         println!(
@@ -0,0 +1,117 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
+use geom::{LonLat, Polygon, Ring};
+use map_model::{BuildingID, IntersectionID, Map, RoadID};
+
+use crate::app::App;
+
+/// A named area, like a neighborhood, drawn by the player or imported from a boundary file. It can
+/// be used to filter dashboards, scope bulk edits, and summarize metrics.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NamedArea {
+    pub name: String,
+    /// Stored in GPS coordinates, so the area still makes sense after the map is regenerated.
+    pub boundary: Vec<LonLat>,
+}
+
+impl NamedArea {
+    pub fn new(name: String, polygon: &Polygon, map: &Map) -> NamedArea {
+        NamedArea {
+            name,
+            boundary: map
+                .get_gps_bounds()
+                .convert_back(polygon.get_outer_ring().points()),
+        }
+    }
+
+    /// None if the boundary is somehow invalid
+    pub fn polygon(&self, map: &Map) -> Option<Polygon> {
+        let mut pts = map.get_gps_bounds().convert(&self.boundary);
+        if pts.is_empty() {
+            return None;
+        }
+        if pts[0] != *pts.last().unwrap() {
+            pts.push(pts[0]);
+        }
+        Ring::deduping_new(pts).ok().map(|ring| ring.into_polygon())
+    }
+
+    /// Roads whose middle is inside the area
+    pub fn roads(&self, map: &Map) -> BTreeSet<RoadID> {
+        let mut roads = BTreeSet::new();
+        if let Some(polygon) = self.polygon(map) {
+            for r in map.all_roads() {
+                if polygon.contains_pt(r.center_pts.middle()) {
+                    roads.insert(r.id);
+                }
+            }
+        }
+        roads
+    }
+
+    pub fn intersections(&self, map: &Map) -> BTreeSet<IntersectionID> {
+        let mut intersections = BTreeSet::new();
+        if let Some(polygon) = self.polygon(map) {
+            for i in map.all_intersections() {
+                if polygon.contains_pt(i.polygon.center()) {
+                    intersections.insert(i.id);
+                }
+            }
+        }
+        intersections
+    }
+
+    pub fn buildings(&self, map: &Map) -> BTreeSet<BuildingID> {
+        let mut buildings = BTreeSet::new();
+        if let Some(polygon) = self.polygon(map) {
+            for b in map.all_buildings() {
+                if polygon.contains_pt(b.label_center) {
+                    buildings.insert(b.id);
+                }
+            }
+        }
+        buildings
+    }
+}
+
+/// All of the areas defined for one map, persisted as player data.
+#[derive(Default, Serialize, Deserialize)]
+pub struct NamedAreas {
+    pub areas: Vec<NamedArea>,
+}
+
+impl NamedAreas {
+    pub fn load(app: &App) -> NamedAreas {
+        abstio::maybe_read_json::<NamedAreas>(NamedAreas::path(app), &mut Timer::throwaway())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, app: &App) {
+        abstio::write_json(NamedAreas::path(app), self);
+    }
+
+    fn path(app: &App) -> String {
+        let name = app.primary.map.get_name();
+        abstio::path_player(format!(
+            "areas/{}/{}/{}.json",
+            name.city.country, name.city.city, name.map
+        ))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&NamedArea> {
+        self.areas.iter().find(|a| a.name == name)
+    }
+
+    /// Adds a new area, replacing any existing one with the same name.
+    pub fn add(&mut self, area: NamedArea) {
+        self.remove(&area.name);
+        self.areas.push(area);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.areas.retain(|a| a.name != name);
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use instant::Instant;
+use rodio::source::SineWave;
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+
+use abstutil::elapsed_seconds;
+use geom::{Duration, Time};
+use map_gui::options::AudioMixer;
+use map_model::IntersectionID;
+use sim::{AgentID, VehicleType};
+use widgetry::EventCtx;
+
+use crate::app::PerMap;
+
+/// Recalculating what's on screen every event is wasteful; the hum doesn't need to react instantly
+const UPDATE_FREQUENCY_SECONDS: f64 = 0.5;
+/// With this many vehicles on screen, the traffic hum is at full volume
+const VEHICLES_FOR_FULL_HUM: usize = 30;
+/// Honk when somebody's been stuck at an intersection this long
+const GRIDLOCK_DELAY: Duration = Duration::const_seconds(5.0 * 60.0);
+
+/// Optional sound: an ambient hum scaled by how much traffic is on screen, horns when an
+/// intersection becomes gridlocked, and clicks when any button is pressed. The audio device is only
+/// opened while the master volume is above 0.
+pub struct Audio {
+    inner: Option<Inner>,
+    /// Don't keep retrying if there's no audio device
+    failed: bool,
+    last_update: Instant,
+    last_sim_time: Time,
+    honked_at: BTreeSet<IntersectionID>,
+}
+
+struct Inner {
+    // Have to keep this alive for the background thread to continue
+    _stream: OutputStream,
+    stream_handle: OutputStreamHandle,
+    hum: Sink,
+}
+
+impl Audio {
+    pub fn new() -> Audio {
+        Audio {
+            inner: None,
+            failed: false,
+            last_update: Instant::now(),
+            last_sim_time: Time::START_OF_DAY,
+            honked_at: BTreeSet::new(),
+        }
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx, primary: &PerMap, volume: &AudioMixer) {
+        if volume.is_muted() {
+            // Release the audio device entirely
+            self.inner = None;
+            self.failed = false;
+            return;
+        }
+        if self.inner.is_none() && !self.failed {
+            match Inner::new() {
+                Ok(inner) => {
+                    self.inner = Some(inner);
+                }
+                Err(err) => {
+                    error!("No audio, sorry: {}", err);
+                    self.failed = true;
+                }
+            }
+        }
+        let inner = if let Some(ref inner) = self.inner {
+            inner
+        } else {
+            return;
+        };
+
+        if ctx.any_button_clicked() {
+            inner.play_effect(
+                SineWave::new(1200.0)
+                    .take_duration(std::time::Duration::from_millis(20))
+                    .amplify(0.3 * volume.effects_volume()),
+            );
+        }
+
+        if elapsed_seconds(self.last_update) < UPDATE_FREQUENCY_SECONDS {
+            return;
+        }
+        self.last_update = Instant::now();
+
+        let bounds = ctx.canvas.get_screen_bounds();
+        let vehicles = primary
+            .sim
+            .get_unzoomed_agents(&primary.map)
+            .into_iter()
+            .filter(|a| {
+                matches!(a.id, AgentID::Car(c) if c.vehicle_type != VehicleType::Bike)
+                    && bounds.contains(a.pos)
+            })
+            .count();
+        let traffic = (vehicles as f32 / VEHICLES_FOR_FULL_HUM as f32).min(1.0);
+        inner.hum.set_volume(traffic * volume.ambient_volume());
+
+        let now = primary.sim.time();
+        if now < self.last_sim_time {
+            // The simulation was reset
+            self.honked_at.clear();
+        }
+        self.last_sim_time = now;
+        let delayed: BTreeSet<IntersectionID> = primary
+            .sim
+            .delayed_intersections(GRIDLOCK_DELAY)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        // If an intersection clears up and jams again later, honk again
+        self.honked_at.retain(|i| delayed.contains(i));
+        if delayed.iter().any(|i| !self.honked_at.contains(i)) {
+            inner.play_effect(
+                SineWave::new(415.0)
+                    .mix(SineWave::new(523.0))
+                    .take_duration(std::time::Duration::from_millis(400))
+                    .amplify(0.15 * volume.effects_volume()),
+            );
+            self.honked_at.extend(delayed);
+        }
+    }
+}
+
+impl Inner {
+    fn new() -> Result<Inner> {
+        if cfg!(windows) {
+            bail!("Audio disabled on Windows: https://github.com/a-b-street/abstreet/issues/430");
+        }
+
+        let (stream, stream_handle) = OutputStream::try_default()?;
+        let hum = Sink::try_new(&stream_handle)?;
+        hum.set_volume(0.0);
+        hum.append(TrafficHum {
+            rng: 0x2545_F491,
+            level: 0.0,
+        });
+
+        Ok(Inner {
+            _stream: stream,
+            stream_handle,
+            hum,
+        })
+    }
+
+    fn play_effect<S: 'static + Source<Item = f32> + Send>(&self, sound: S) {
+        if let Err(err) = self.stream_handle.play_raw(sound) {
+            warn!("Couldn't play a sound: {}", err);
+        }
+    }
+}
+
+/// A low rumble, made by smoothing out random noise. Generating this avoids shipping an audio file.
+struct TrafficHum {
+    rng: u32,
+    level: f32,
+}
+
+impl Iterator for TrafficHum {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // xorshift
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        let white = (self.rng as f32 / u32::MAX as f32) * 2.0 - 1.0;
+        // Accumulating the noise keeps only the low frequencies. Leak a little, so it doesn't
+        // drift off.
+        self.level = (0.998 * self.level + 0.02 * white).clamp(-1.0, 1.0);
+        Some(self.level)
+    }
+}
+
+impl Source for TrafficHum {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        22050
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
@@ -1,24 +1,30 @@
 use std::collections::BTreeSet;
 
 use crate::ID;
-use geom::{Duration, Polygon, Time};
+use geom::{Angle, Duration, Polygon, Time};
 use sim::{AgentType, TripPhaseType};
 use widgetry::{
     lctrl, Color, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Panel, ScreenDims,
     ScreenPt, ScreenRectangle, Text, TextSpan, VerticalAlignment, Widget,
 };
 
+pub use self::areas::{NamedArea, NamedAreas};
+pub use self::audio::Audio;
 pub use self::route_sketcher::RouteSketcher;
 pub use self::select::RoadSelector;
+use self::tooltip::HoverTooltip;
 pub use self::warp::{warp_to_id, Warping};
 use crate::app::App;
 use crate::app::Transition;
 use crate::info::{ContextualActions, InfoPanel, Tab};
 use crate::sandbox::TimeWarpScreen;
 
+mod areas;
+mod audio;
 mod route_sketcher;
 mod select;
 pub mod share;
+mod tooltip;
 mod warp;
 
 // TODO This is now just used in two modes...
@@ -27,6 +33,7 @@ pub struct CommonState {
     info_panel: Option<InfoPanel>,
     // Just for drawing the OSD
     cached_actions: Vec<Key>,
+    tooltip: HoverTooltip,
 }
 
 impl CommonState {
@@ -34,6 +41,7 @@ impl CommonState {
         CommonState {
             info_panel: None,
             cached_actions: Vec::new(),
+            tooltip: HoverTooltip::new(),
         }
     }
 
@@ -74,6 +82,12 @@ impl CommonState {
             }
         }
 
+        if self.info_panel.is_some() {
+            self.tooltip.reset();
+        } else {
+            self.tooltip.event(ctx, app);
+        }
+
         if self.info_panel.is_none() {
             self.cached_actions.clear();
             if let Some(id) = app.primary.current_selection.clone() {
@@ -119,6 +133,10 @@ impl CommonState {
         }
 
         CommonState::draw_custom_osd(g, app, osd);
+
+        if self.info_panel.is_none() {
+            self.tooltip.draw(g, app);
+        }
     }
 
     fn osd_for(app: &App, id: ID) -> Text {
@@ -415,6 +433,23 @@ pub fn jump_to_time_upon_startup(
     })
 }
 
+/// Describes the direction of travel the way traffic engineers label movements, like "EB" for
+/// eastbound.
+pub fn cardinal_direction(angle: Angle) -> &'static str {
+    // Note Y inversion, as usual
+    let deg = angle.normalized_degrees();
+    if deg >= 335.0 || deg <= 45.0 {
+        return "EB";
+    }
+    if (45.0..=135.0).contains(&deg) {
+        return "SB";
+    }
+    if (135.0..=225.0).contains(&deg) {
+        return "WB";
+    }
+    "NB"
+}
+
 fn sync_abtest(ctx: &mut EventCtx, app: &mut App) {
     // If the other simulation is at a later time, catch up to it
     let other_time = app.secondary.as_ref().unwrap().sim.time();
@@ -236,6 +236,11 @@ impl RouteSketcher {
         roads
     }
 
+    /// All of the intersections along the route, in order.
+    pub fn all_intersections(&self) -> Vec<IntersectionID> {
+        self.route.full_path.clone()
+    }
+
     /// Has the user even picked a start point?
     pub fn is_route_started(&self) -> bool {
         !self.route.waypoints.is_empty()
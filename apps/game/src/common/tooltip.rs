@@ -0,0 +1,186 @@
+use instant::Instant;
+
+use abstutil::{elapsed_seconds, prettyprint_usize};
+use geom::{Distance, Duration};
+use map_model::Traversable;
+use sim::AgentID;
+use widgetry::{EventCtx, GfxCtx, Line, Text, UpdateType};
+
+use crate::app::App;
+use crate::ID;
+
+/// After the player hovers on something in the map for a moment, show a small card with its name
+/// and a few live stats, so they don't always have to open the info panel.
+pub struct HoverTooltip {
+    // What's hovered, and since when
+    hovering: Option<(ID, Instant)>,
+}
+
+impl HoverTooltip {
+    pub fn new() -> HoverTooltip {
+        HoverTooltip { hovering: None }
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &App) {
+        let delay = match app.opts.hover_tooltip_delay {
+            Some(delay) => delay,
+            None => {
+                self.hovering = None;
+                return;
+            }
+        };
+        if self.hovering.as_ref().map(|(id, _)| id) != app.primary.current_selection.as_ref() {
+            self.hovering = app
+                .primary
+                .current_selection
+                .clone()
+                .map(|id| (id, Instant::now()));
+        }
+        // Nothing else happens while the mouse rests, so keep updating until the tooltip appears
+        if let Some((_, since)) = self.hovering {
+            if elapsed_seconds(since) < delay.inner_seconds() {
+                ctx.request_update(UpdateType::Game);
+            }
+        }
+    }
+
+    /// Call this when the player is doing something else with the mouse, like opening an info
+    /// panel, to hide the tooltip until they hover on something new.
+    pub fn reset(&mut self) {
+        self.hovering = None;
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, app: &App) {
+        let delay = match app.opts.hover_tooltip_delay {
+            Some(delay) => delay,
+            None => {
+                return;
+            }
+        };
+        if let Some((ref id, since)) = self.hovering {
+            if Some(id) == app.primary.current_selection.as_ref()
+                && elapsed_seconds(since) >= delay.inner_seconds()
+            {
+                if let Some(txt) = tooltip_for(app, id) {
+                    g.draw_mouse_tooltip(txt);
+                }
+            }
+        }
+    }
+}
+
+fn tooltip_for(app: &App, id: &ID) -> Option<Text> {
+    let map = &app.primary.map;
+    let sim = &app.primary.sim;
+    let mut txt = Text::new();
+    match id {
+        ID::Lane(l) => {
+            let lane = map.get_l(*l);
+            let road = map.get_parent(*l);
+            txt.add_line(Line(road.get_name(app.opts.language.as_ref())).small_heading());
+            txt.add_line(Line(lane.lane_type.describe()));
+            txt.add_line(Line(format!(
+                "Speed limit: {}",
+                road.speed_limit.to_string(&app.opts.units)
+            )));
+            if lane.is_driving() || lane.is_biking() || lane.is_bus() {
+                let vehicles = sim.get_draw_cars(Traversable::Lane(*l), map).len();
+                txt.add_line(Line(format!(
+                    "Vehicles here now: {}",
+                    prettyprint_usize(vehicles)
+                )));
+                if let Some((reserved, total)) = sim.debug_queue_lengths(*l) {
+                    if total > Distance::ZERO {
+                        txt.add_line(Line(format!(
+                            "Queue: {:.0}% full",
+                            100.0 * (reserved / total).min(1.0)
+                        )));
+                    }
+                }
+            }
+        }
+        ID::Intersection(i) => {
+            let intersection = map.get_i(*i);
+            let mut names = intersection
+                .roads
+                .iter()
+                .map(|r| map.get_r(*r).get_name(app.opts.language.as_ref()))
+                .collect::<Vec<_>>();
+            names.sort();
+            names.dedup();
+            txt.add_line(Line(names.join(" & ")).small_heading());
+            if intersection.is_border() {
+                txt.add_line(Line("Border of the map"));
+                return Some(txt);
+            }
+            if intersection.is_traffic_signal() {
+                txt.add_line(Line("Traffic signal"));
+            } else {
+                txt.add_line(Line("Stop sign"));
+            }
+            let waiting = sim.get_waiting_agents(*i);
+            txt.add_line(Line(format!(
+                "Waiting here now: {}",
+                prettyprint_usize(waiting.len())
+            )));
+            if let Some(earliest) = waiting.iter().map(|(_, _, t)| *t).min() {
+                txt.add_line(Line(format!(
+                    "Longest current delay: {}",
+                    (sim.time() - earliest).to_string(&app.opts.units)
+                )));
+            }
+        }
+        ID::Building(b) => {
+            let bldg = map.get_b(*b);
+            txt.add_line(Line(&bldg.address).small_heading());
+            if let Some(ref names) = bldg.name {
+                txt.add_line(Line(names.get(app.opts.language.as_ref())));
+            }
+            txt.add_line(Line(format!(
+                "People inside: {}",
+                prettyprint_usize(sim.bldg_to_people(*b).len())
+            )));
+        }
+        ID::Car(c) => {
+            txt.add_line(Line(format!("A {}", c.vehicle_type)).small_heading());
+            if sim.lookup_parked_car(*c).is_some() {
+                txt.add_line(Line("Parked"));
+            } else {
+                agent_stats(app, &mut txt, AgentID::Car(*c));
+            }
+        }
+        ID::Pedestrian(p) => {
+            txt.add_line(Line("A pedestrian").small_heading());
+            agent_stats(app, &mut txt, AgentID::Pedestrian(*p));
+        }
+        ID::TransitStop(ts) => {
+            let stop = map.get_ts(*ts);
+            txt.add_line(Line(&stop.name).small_heading());
+            txt.add_line(Line(format!(
+                "People waiting: {}",
+                prettyprint_usize(sim.get_people_waiting_at_stop(*ts).len())
+            )));
+        }
+        // The OSD already describes everything else well enough
+        _ => {
+            return None;
+        }
+    }
+    Some(txt)
+}
+
+fn agent_stats(app: &App, txt: &mut Text, id: AgentID) {
+    let props = app.primary.sim.agent_properties(&app.primary.map, id);
+    if props.total_dist > Distance::ZERO {
+        txt.add_line(Line(format!(
+            "Progress on this leg of the trip: {:.0}%",
+            100.0 * (props.dist_crossed / props.total_dist).min(1.0)
+        )));
+    }
+    if props.waiting_here > Duration::ZERO {
+        txt.add_line(Line(format!(
+            "Waiting here for {}",
+            props.waiting_here.to_string(&app.opts.units)
+        )));
+    }
+}
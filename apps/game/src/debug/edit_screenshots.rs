@@ -0,0 +1,278 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+
+use abstutil::Timer;
+use geom::{Bounds, Distance};
+use map_model::{EditCmd, MapEdits};
+use widgetry::tools::{ChooseSomething, PopupMsg};
+use widgetry::{Choice, EventCtx, GfxCtx, State, UpdateType};
+
+use crate::app::{App, Transition};
+use crate::edit::apply_map_edits;
+
+/// Leave some space around each edited road or intersection, so the surroundings are recognizable
+const PADDING: Distance = Distance::const_meters(30.0);
+
+/// Renders a before and after screenshot of every place a proposal changes, so write-ups can
+/// include visuals without screenshotting each one by hand. Optionally writes an HTML page showing
+/// the pairs side-by-side.
+pub struct EditScreenshots {
+    edits: MapEdits,
+    orig_edits: MapEdits,
+    orig_camera: (f64, f64, f64),
+    locations: Vec<Location>,
+    dir: String,
+    gallery: bool,
+    /// When started from the command line, print the results and quit
+    exit_when_done: bool,
+    /// Every location is captured without edits first, then with them
+    next: usize,
+}
+
+struct Location {
+    /// Used in filenames
+    id: String,
+    label: String,
+    bounds: Bounds,
+}
+
+impl EditScreenshots {
+    /// Asks which saved edits to screenshot
+    pub fn choose(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let names = abstio::list_all_objects(abstio::path_all_edits(app.primary.map.get_name()));
+        if names.is_empty() {
+            return PopupMsg::new_state(
+                ctx,
+                "No edits",
+                vec!["Save some edits for this map first"],
+            );
+        }
+        ChooseSomething::new_state(
+            ctx,
+            "Screenshot which edits?",
+            Choice::strings(names),
+            Box::new(|name, ctx, app| {
+                let path = abstio::path_edits(app.primary.map.get_name(), &name);
+                let edits =
+                    match MapEdits::load_from_file(&app.primary.map, path, &mut Timer::throwaway())
+                    {
+                        Ok(edits) => edits,
+                        Err(err) => {
+                            return Transition::Replace(PopupMsg::new_state(
+                                ctx,
+                                "Error",
+                                vec![format!("Couldn't load {}: {}", name, err)],
+                            ));
+                        }
+                    };
+                Transition::Replace(ChooseSomething::new_state(
+                    ctx,
+                    "Also write an HTML gallery?",
+                    vec![
+                        Choice::new("images and a gallery", true),
+                        Choice::new("just images", false),
+                    ],
+                    Box::new(move |gallery, ctx, app| {
+                        Transition::Replace(EditScreenshots::new_state(
+                            ctx, app, edits, gallery, false,
+                        ))
+                    }),
+                ))
+            }),
+        )
+    }
+
+    /// Screenshots a proposal. If `exit_when_done` is set, prints the results and quits instead of
+    /// returning to the previous state.
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        edits: MapEdits,
+        gallery: bool,
+        exit_when_done: bool,
+    ) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let mut roads = BTreeSet::new();
+        let mut intersections = BTreeSet::new();
+        for cmd in &edits.commands {
+            match cmd {
+                EditCmd::ChangeRoad { r, .. } => {
+                    roads.insert(*r);
+                }
+                EditCmd::ChangeIntersection { i, .. }
+                | EditCmd::ChangeCrosswalks { i, .. }
+                | EditCmd::ChangeUTurns { i, .. } => {
+                    intersections.insert(*i);
+                }
+                // Transit changes are mostly schedules and routes, which don't look different
+                _ => {}
+            }
+        }
+
+        let mut locations = Vec::new();
+        for r in roads {
+            let road = map.get_r(r);
+            locations.push(Location {
+                id: format!("road_{}", r.0),
+                label: road.get_name(app.opts.language.as_ref()),
+                bounds: road.get_thick_polygon().get_bounds(),
+            });
+        }
+        for i in intersections {
+            locations.push(Location {
+                id: format!("intersection_{}", i.0),
+                label: format!("Intersection #{}", i.0),
+                bounds: map.get_i(i).polygon.get_bounds(),
+            });
+        }
+        if locations.is_empty() {
+            let msg = format!(
+                "{} doesn't change any roads or intersections",
+                edits.edits_name
+            );
+            if exit_when_done {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+            return PopupMsg::new_state(ctx, "Nothing to screenshot", vec![msg]);
+        }
+
+        let name = map.get_name();
+        let dir = format!(
+            "screenshots/edits/{}/{}/{}/{}",
+            name.city.country, name.city.city, name.map, edits.edits_name
+        );
+        // Don't mistake old files for new screenshots
+        for loc in &locations {
+            for suffix in ["before", "after"] {
+                let path = format!("{}/{}_{}.png", dir, loc.id, suffix);
+                if abstio::file_exists(&path) {
+                    abstio::delete_file(path);
+                }
+            }
+        }
+        Box::new(EditScreenshots {
+            edits,
+            orig_edits: map.get_edits().clone(),
+            orig_camera: (ctx.canvas.cam_x, ctx.canvas.cam_y, ctx.canvas.cam_zoom),
+            locations,
+            dir,
+            gallery,
+            exit_when_done,
+            next: 0,
+        })
+    }
+
+    fn write_gallery(&self) -> Result<()> {
+        let mut html = String::new();
+        html.push_str(&format!(
+            "<html>\n<head><title>{0}</title></head>\n<body>\n<h1>{0}</h1>\n<table>\n",
+            self.edits.get_title()
+        ));
+        html.push_str("<tr><th></th><th>Before</th><th>After</th></tr>\n");
+        for loc in &self.locations {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td><img src=\"{}_before.png\" width=\"100%\"></td><td><img \
+                 src=\"{}_after.png\" width=\"100%\"></td></tr>\n",
+                loc.label, loc.id, loc.id
+            ));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        abstio::write_file(format!("{}/index.html", self.dir), html)?;
+        Ok(())
+    }
+}
+
+impl State<App> for EditScreenshots {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let num = self.locations.len();
+        if self.next == 2 * num {
+            apply_map_edits(ctx, app, self.orig_edits.clone());
+            let (x, y, zoom) = self.orig_camera;
+            ctx.canvas.cam_x = x;
+            ctx.canvas.cam_y = y;
+            ctx.canvas.cam_zoom = zoom;
+
+            // Capturing happens after each event, so by now, every screenshot has been attempted.
+            // Failures are only logged there, so check what actually got written.
+            let mut missing = Vec::new();
+            for loc in &self.locations {
+                for suffix in ["before", "after"] {
+                    let path = format!("{}/{}_{}.png", self.dir, loc.id, suffix);
+                    if !abstio::file_exists(&path) {
+                        missing.push(path);
+                    }
+                }
+            }
+
+            let (title, mut lines) = if missing.is_empty() {
+                (
+                    "Screenshots done",
+                    vec![format!(
+                        "{} pairs of screenshots written to {}",
+                        num, self.dir
+                    )],
+                )
+            } else {
+                let mut lines = vec![format!(
+                    "{} of {} screenshots couldn't be written:",
+                    missing.len(),
+                    2 * num
+                )];
+                lines.extend(missing.iter().cloned());
+                ("Screenshots failed", lines)
+            };
+            if self.gallery && missing.is_empty() {
+                match self.write_gallery() {
+                    Ok(()) => {
+                        lines.push(format!("Open {}/index.html to see them all", self.dir));
+                    }
+                    Err(err) => {
+                        lines.push(format!("Couldn't write the gallery: {}", err));
+                    }
+                }
+            }
+
+            if self.exit_when_done {
+                for line in lines {
+                    println!("{}", line);
+                }
+                if !missing.is_empty() {
+                    std::process::exit(1);
+                }
+                return Transition::Pop;
+            }
+            return Transition::Replace(PopupMsg::new_state(ctx, title, lines));
+        }
+
+        if self.next == 0 {
+            apply_map_edits(ctx, app, app.primary.map.new_edits());
+        } else if self.next == num {
+            apply_map_edits(ctx, app, self.edits.clone());
+        }
+
+        let loc = &self.locations[self.next % num];
+        let mut bounds = loc.bounds;
+        bounds.add_buffer(PADDING);
+        let dims = ctx.canvas.get_window_dims();
+        let max_zoom = ctx.canvas.max_zoom();
+        // Fit the location on the screen, but stay zoomed in enough to see individual lanes
+        ctx.canvas.cam_zoom = (dims.width / bounds.width())
+            .min(dims.height / bounds.height())
+            .max(ctx.canvas.settings.min_zoom_for_detail)
+            .min(max_zoom);
+        ctx.canvas.center_on_map_pt(bounds.center());
+
+        let suffix = if self.next < num { "before" } else { "after" };
+        ctx.request_update(UpdateType::ScreenCaptureCurrentShot {
+            path: format!("{}/{}_{}.png", self.dir, loc.id, suffix),
+        });
+        // Keep events coming, so the next screenshot happens without waiting for input
+        ctx.request_update(UpdateType::Game);
+        self.next += 1;
+        Transition::Keep
+    }
+
+    fn draw(&self, _: &mut GfxCtx, _: &App) {}
+}
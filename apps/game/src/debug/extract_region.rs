@@ -0,0 +1,117 @@
+use abstio::MapName;
+use geom::Distance;
+use map_gui::load::MapLoader;
+use map_gui::tools::{find_exe, RunCommand};
+use widgetry::tools::{Lasso, PromptInput};
+use widgetry::{
+    DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, TextExt,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::{GameplayMode, SandboxMode};
+
+const BOUNDARY_PATH: &str = "region_boundary.geojson";
+
+/// Draw a boundary around part of the current map, then cut a smaller map out of the raw map. The
+/// new map loads much faster, which is handy for iterating on one place.
+pub struct ExtractRegion {
+    panel: Panel,
+    lasso: Lasso,
+}
+
+impl ExtractRegion {
+    pub fn new_state(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        Box::new(ExtractRegion {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Extract a region").small_heading().into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                "Click and drag to draw the boundary of the new map".text_widget(ctx),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            lasso: Lasso::new(Distance::meters(1.0)),
+        })
+    }
+}
+
+impl State<App> for ExtractRegion {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        if let Some(polygon) = self.lasso.event(ctx) {
+            let geojson = geojson::GeoJson::from(geojson::FeatureCollection {
+                bbox: None,
+                features: vec![geojson::Feature {
+                    bbox: None,
+                    geometry: Some(polygon.to_geojson(Some(app.primary.map.get_gps_bounds()))),
+                    id: None,
+                    properties: None,
+                    foreign_members: None,
+                }],
+                foreign_members: None,
+            });
+            abstio::write_json(BOUNDARY_PATH.to_string(), &geojson);
+
+            return Transition::Replace(PromptInput::new_state(
+                ctx,
+                "Name the new map",
+                format!("{}_region", app.primary.map.get_name().map),
+                Box::new(|name, ctx, app| {
+                    let name = name.replace(' ', "_");
+                    Transition::Replace(RunCommand::new_state(
+                        ctx,
+                        true,
+                        vec![
+                            find_exe("cli"),
+                            "extract-region".to_string(),
+                            abstio::path_raw_map(app.primary.map.get_name()),
+                            format!("--clip-path={}", BOUNDARY_PATH),
+                            format!("--map-name={}", name),
+                            "--skip-ch".to_string(),
+                        ],
+                        Box::new(move |ctx, app, success, _| {
+                            if !success {
+                                // The popup already explained the failure
+                                return Transition::Keep;
+                            }
+                            abstio::delete_file(BOUNDARY_PATH);
+                            let name = MapName::new("zz", "oneshot", &name);
+                            Transition::Clear(vec![MapLoader::new_state(
+                                ctx,
+                                app,
+                                name.clone(),
+                                Box::new(|_, app| {
+                                    Transition::Replace(SandboxMode::simple_new(
+                                        app,
+                                        GameplayMode::Freeform(name),
+                                    ))
+                                }),
+                            )])
+                        }),
+                    ))
+                }),
+            ));
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.lasso.draw(g);
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use geom::{Circle, Distance};
+use map_model::ImportWarning;
+use widgetry::tools::{open_browser, PopupMsg};
+use widgetry::{
+    Choice, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Panel, State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// Steps through problems found while importing the map, like disconnected sidewalks or unmatched
+/// transit stops, jumping to each one so people fixing OSM data know where to look.
+pub struct ImportWarnings {
+    panel: Panel,
+    category: Option<String>,
+    /// Indices into the map's import warnings, matching the category
+    warnings: Vec<usize>,
+    idx: usize,
+    draw_all: Drawable,
+}
+
+impl ImportWarnings {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        if app.primary.map.get_import_warnings().is_empty() {
+            return PopupMsg::new_state(
+                ctx,
+                "No import warnings",
+                vec!["Nothing suspicious was found while importing this map"],
+            );
+        }
+        let mut state = ImportWarnings {
+            panel: Panel::empty(ctx),
+            category: None,
+            warnings: Vec::new(),
+            idx: 0,
+            draw_all: Drawable::empty(ctx),
+        };
+        state.change_category(ctx, app, None);
+        Box::new(state)
+    }
+
+    fn change_category(&mut self, ctx: &mut EventCtx, app: &App, category: Option<String>) {
+        let all = app.primary.map.get_import_warnings();
+        self.warnings = all
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| category.as_ref().map(|c| &w.category == c).unwrap_or(true))
+            .map(|(idx, _)| idx)
+            .collect();
+        self.category = category;
+        self.idx = 0;
+
+        let mut batch = GeomBatch::new();
+        for idx in &self.warnings {
+            batch.push(
+                Color::RED.alpha(0.8),
+                Circle::new(all[*idx].pt, Distance::meters(5.0)).to_polygon(),
+            );
+        }
+        self.draw_all = ctx.upload(batch);
+        self.show_current(ctx, app);
+    }
+
+    fn current<'a>(&self, app: &'a App) -> &'a ImportWarning {
+        &app.primary.map.get_import_warnings()[self.warnings[self.idx]]
+    }
+
+    fn show_current(&mut self, ctx: &mut EventCtx, app: &App) {
+        let warning = self.current(app);
+        ctx.canvas.center_on_map_pt(warning.pt);
+        self.panel = self.make_panel(ctx, app, warning);
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx, app: &App, warning: &ImportWarning) -> Panel {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for w in app.primary.map.get_import_warnings() {
+            *counts.entry(w.category.clone()).or_insert(0) += 1;
+        }
+        let mut choices = vec![Choice::new(
+            format!("all ({})", app.primary.map.get_import_warnings().len()),
+            None,
+        )];
+        for (category, count) in counts {
+            choices.push(Choice::new(
+                format!("{} ({})", category, count),
+                Some(category),
+            ));
+        }
+
+        let mut txt = Text::new();
+        txt.add_line(Line(&warning.category).secondary());
+        txt.add_line(Line(&warning.message));
+
+        Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Import warnings").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::dropdown(ctx, "category", self.category.clone(), choices),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_prev()
+                    .hotkey(Key::LeftArrow)
+                    .disabled(self.idx == 0)
+                    .build_widget(ctx, "previous"),
+                format!("warning {}/{}", self.idx + 1, self.warnings.len())
+                    .text_widget(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_next()
+                    .hotkey(Key::RightArrow)
+                    .disabled(self.idx == self.warnings.len() - 1)
+                    .build_widget(ctx, "next"),
+            ])
+            .evenly_spaced(),
+            txt.wrap_to_pct(ctx, 30).into_widget(ctx),
+            if let Some(osm_id) = warning.osm_id {
+                ctx.style()
+                    .btn_plain
+                    .btn()
+                    .label_underlined_text(osm_id.to_string())
+                    .build_widget(ctx, "open in OSM")
+            } else {
+                Line("Not from OpenStreetMap").secondary().into_widget(ctx)
+            },
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .build(ctx)
+    }
+}
+
+impl State<App> for ImportWarnings {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "previous" => {
+                    self.idx -= 1;
+                    self.show_current(ctx, app);
+                }
+                "next" => {
+                    self.idx += 1;
+                    self.show_current(ctx, app);
+                }
+                "open in OSM" => {
+                    if let Some(osm_id) = self.current(app).osm_id {
+                        open_browser(osm_id.to_string());
+                    }
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                let category = self.panel.dropdown_value("category");
+                self.change_category(ctx, app, category);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw_all);
+        g.draw_polygon(
+            app.cs.selected,
+            Circle::new(self.current(app).pt, Distance::meters(10.0)).to_polygon(),
+        );
+        self.panel.draw(g);
+    }
+}
@@ -27,16 +27,25 @@ use crate::info::ContextualActions;
 use crate::sandbox::GameplayMode;
 use crate::ID;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::edit_screenshots::EditScreenshots;
+pub use self::import_warnings::ImportWarnings;
 pub use self::routes::PathCostDebugger;
 
 mod blocked_by;
 mod blockfinder;
+#[cfg(not(target_arch = "wasm32"))]
+mod edit_screenshots;
+#[cfg(not(target_arch = "wasm32"))]
+mod extract_region;
 mod floodfill;
+mod import_warnings;
 mod objects;
 pub mod path_counter;
 mod polygons;
 mod routes;
 mod select_roads;
+mod turn_conflicts;
 mod uber_turns;
 
 pub struct DebugMode {
@@ -165,6 +174,29 @@ impl DebugMode {
                         .btn_outline
                         .text("find bad intersection polygons")
                         .build_def(ctx),
+                    ctx.style()
+                        .btn_outline
+                        .text("browse import warnings")
+                        .build_def(ctx),
+                    // Screen capture only works natively
+                    if cfg!(not(target_arch = "wasm32")) {
+                        ctx.style()
+                            .btn_outline
+                            .text("screenshot edits before and after")
+                            .build_def(ctx)
+                    } else {
+                        Widget::nothing()
+                    },
+                    if cfg!(not(target_arch = "wasm32"))
+                        && abstio::file_exists(abstio::path_raw_map(app.primary.map.get_name()))
+                    {
+                        ctx.style()
+                            .btn_outline
+                            .text("extract a region")
+                            .build_def(ctx)
+                    } else {
+                        Widget::nothing()
+                    },
                     if cfg!(not(target_arch = "wasm32")) {
                         ctx.style()
                             .btn_outline
@@ -410,6 +442,17 @@ impl State<App> for DebugMode {
                     });
                     self.reset_info(ctx);
                 }
+                "browse import warnings" => {
+                    return Transition::Push(ImportWarnings::new_state(ctx, app));
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                "screenshot edits before and after" => {
+                    return Transition::Push(edit_screenshots::EditScreenshots::choose(ctx, app));
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                "extract a region" => {
+                    return Transition::Push(extract_region::ExtractRegion::new_state(ctx));
+                }
                 #[cfg(not(target_arch = "wasm32"))]
                 "undo all merged roads" => {
                     if let Err(err) =
@@ -635,6 +678,7 @@ impl ContextualActions for Actions {
                     actions.push((Key::R, "route from here".to_string()));
                 }
                 actions.push((Key::U, "explore uber-turns".to_string()));
+                actions.push((Key::O, "show turn conflicts".to_string()));
             }
             ID::Car(_) => {
                 actions.push((Key::Backspace, "forcibly delete this car".to_string()));
@@ -744,6 +788,9 @@ impl ContextualActions for Actions {
             (ID::Intersection(i), "explore uber-turns") => {
                 Transition::Push(uber_turns::UberTurnPicker::new_state(ctx, app, i))
             }
+            (ID::Intersection(i), "show turn conflicts") => {
+                Transition::Push(turn_conflicts::TurnConflictMatrix::new_state(ctx, app, i))
+            }
             (ID::Lane(l), "debug lane geometry") => {
                 Transition::Push(polygons::PolygonDebugger::new_state(
                     ctx,
@@ -1,9 +1,8 @@
 use crate::ID;
-use abstutil::Counter;
+use abstutil::{prettyprint_usize, Counter};
 use map_gui::tools::ColorNetwork;
 use map_model::{IntersectionID, PathStep, RoadID, Traversable};
 use widgetry::mapspace::ToggleZoomed;
-use widgetry::tools::ColorLegend;
 use widgetry::{
     Color, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text,
     VerticalAlignment, Widget,
@@ -71,11 +70,11 @@ impl PathCounter {
                         .into_widget(ctx),
                     ctx.style().btn_close_widget(ctx),
                 ]),
-                ColorLegend::gradient(
-                    ctx,
-                    &app.cs.good_to_bad_red,
-                    vec!["lowest count", "highest"],
-                ),
+                app.cs
+                    .good_to_bad_red
+                    .make_legend(ctx, 0.0, cnt.max() as f64, |x| {
+                        prettyprint_usize(x as usize)
+                    }),
             ]))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
             .build(ctx),
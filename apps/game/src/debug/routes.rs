@@ -5,15 +5,17 @@ use geom::{Duration, Polygon};
 use map_gui::colors::ColorSchemeChoice;
 use map_gui::tools::{cmp_count, ColorNetwork};
 use map_gui::AppLike;
+use map_model::connectivity::vehicle_cost;
 use map_model::{
-    DirectedRoadID, Direction, PathConstraints, PathRequest, PathStepV2, Pathfinder, RoadID,
-    RoutingParams, NORMAL_LANE_THICKNESS,
+    DirectedRoadID, Direction, PathConstraints, PathRequest, PathStepV2, PathV2, Pathfinder,
+    RoadID, RoutingParams, NORMAL_LANE_THICKNESS,
 };
 use synthpop::{TripEndpoint, TripMode};
 use widgetry::mapspace::ToggleZoomed;
+use widgetry::tools::PopupMsg;
 use widgetry::{
     Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
-    RoundedF64, Spinner, State, Text, TextExt, VerticalAlignment, Widget,
+    PanelDims, RoundedF64, Spinner, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, Transition};
@@ -43,6 +45,11 @@ impl RouteExplorer {
                     .text("All routes")
                     .hotkey(Key::A)
                     .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("Inspect costs")
+                    .hotkey(Key::I)
+                    .build_def(ctx),
                 params_to_controls(ctx, TripMode::Bike, app.primary.map.routing_params())
                     .named("params"),
             ]))
@@ -103,9 +110,40 @@ impl State<App> for RouteExplorer {
                     self.panel.replace(ctx, "params", controls);
                     self.recalc_paths(ctx, app);
                 }
+                "Use wheelchair profile" => {
+                    let controls =
+                        params_to_controls(ctx, TripMode::Walk, &RoutingParams::wheelchair());
+                    self.panel.replace(ctx, "params", controls);
+                    self.recalc_paths(ctx, app);
+                }
                 "All routes" => {
                     return Transition::Replace(AllRoutesExplorer::new_state(ctx, app));
                 }
+                "Inspect costs" => {
+                    let (mode, params) = controls_to_params(&self.panel);
+                    let goal = match self.goal {
+                        Some((goal, true, _)) => goal,
+                        _ => {
+                            return Transition::Push(PopupMsg::new_state(
+                                ctx,
+                                "Error",
+                                vec!["Click a destination first"],
+                            ));
+                        }
+                    };
+                    if let Some(state) =
+                        TripEndpoint::path_req(self.start, goal, mode, &app.primary.map).and_then(
+                            |req| PathCostDebugger::maybe_new_with_params(ctx, app, req, params),
+                        )
+                    {
+                        return Transition::Push(state);
+                    }
+                    return Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Error",
+                        vec!["No path for this mode"],
+                    ));
+                }
                 _ => unreachable!(),
             },
             Outcome::Changed(_) => {
@@ -278,6 +316,50 @@ fn params_to_controls(ctx: &mut EventCtx, mode: TripMode, params: &RoutingParams
                 0.1,
             ),
         ]));
+        rows.push(Widget::row(vec![
+            "Penalty per level of traffic stress:"
+                .text_widget(ctx)
+                .margin_right(20),
+            Spinner::f64_widget(
+                ctx,
+                "traffic_stress_penalty",
+                (1.0, 3.0),
+                params.traffic_stress_penalty,
+                0.1,
+            ),
+        ]));
+    }
+    if mode == TripMode::Walk {
+        rows.push(
+            ctx.style()
+                .btn_outline
+                .text("Use wheelchair profile")
+                .build_def(ctx),
+        );
+        rows.push(Toggle::checkbox(
+            ctx,
+            "avoid stairs",
+            None,
+            params.avoid_stairs,
+        ));
+        rows.push(Widget::row(vec![
+            "Avoid steep paths (>= 8%):"
+                .text_widget(ctx)
+                .margin_right(20),
+            Spinner::f64_widget(
+                ctx,
+                "steep_walking_penalty",
+                (1.0, 5.0),
+                params.steep_walking_penalty,
+                0.1,
+            ),
+        ]));
+        rows.push(Toggle::checkbox(
+            ctx,
+            "require curb ramps",
+            None,
+            params.require_curb_ramps,
+        ));
     }
     Widget::col(rows)
 }
@@ -289,6 +371,9 @@ fn controls_to_params(panel: &Panel) -> (TripMode, RoutingParams) {
         return (TripMode::Drive, params);
     }
     if !panel.is_button_enabled("pedestrians") {
+        params.avoid_stairs = panel.is_checked("avoid stairs");
+        params.steep_walking_penalty = panel.spinner::<RoundedF64>("steep_walking_penalty").0;
+        params.require_curb_ramps = panel.is_checked("require curb ramps");
         return (TripMode::Walk, params);
     }
     params.unprotected_turn_penalty = panel.spinner("unprotected_turn_penalty");
@@ -298,6 +383,7 @@ fn controls_to_params(panel: &Panel) -> (TripMode, RoutingParams) {
     params.avoid_steep_incline_penalty =
         panel.spinner::<RoundedF64>("avoid_steep_incline_penalty").0;
     params.avoid_high_stress = panel.spinner::<RoundedF64>("avoid_high_stress").0;
+    params.traffic_stress_penalty = panel.spinner::<RoundedF64>("traffic_stress_penalty").0;
     (TripMode::Bike, params)
 }
 
@@ -387,6 +473,11 @@ impl State<App> for AllRoutesExplorer {
                         params_to_controls(ctx, TripMode::Walk, app.primary.map.routing_params());
                     self.panel.replace(ctx, "params", controls);
                 }
+                "Use wheelchair profile" => {
+                    let controls =
+                        params_to_controls(ctx, TripMode::Walk, &RoutingParams::wheelchair());
+                    self.panel.replace(ctx, "params", controls);
+                }
                 "Calculate differential demand" => {
                     ctx.loading_screen(
                         "calculate differential demand due to routing params",
@@ -491,10 +582,12 @@ fn calculate_demand(
 }
 
 /// Evaluate why an alternative path wasn't chosen, by showing the cost to reach every road from
-/// one start.
+/// one start, the cost of crossing each road, and why the path didn't turn elsewhere.
 pub struct PathCostDebugger {
     draw_path: Drawable,
     costs: HashMap<DirectedRoadID, Duration>,
+    constraints: PathConstraints,
+    params: RoutingParams,
     tooltip: Option<Text>,
     panel: Panel,
 }
@@ -506,7 +599,51 @@ impl PathCostDebugger {
         req: PathRequest,
         draw_path: Polygon,
     ) -> Option<Box<dyn State<App>>> {
-        let (full_cost, all_costs) = app.primary.map.all_costs_from(req)?;
+        let (full_cost, all_costs) = app.primary.map.all_costs_from(req.clone())?;
+        let path = app.primary.map.pathfind_v2(req).ok()?;
+        let params = app.primary.map.routing_params().clone();
+        Some(PathCostDebugger::new_state(
+            ctx, app, path, params, full_cost, all_costs, draw_path,
+        ))
+    }
+
+    /// Like `maybe_new`, but with custom routing params
+    fn maybe_new_with_params(
+        ctx: &mut EventCtx,
+        app: &App,
+        req: PathRequest,
+        params: RoutingParams,
+    ) -> Option<Box<dyn State<App>>> {
+        let map = &app.primary.map;
+        let pathfinder = Pathfinder::new_dijkstra(
+            map,
+            params.clone(),
+            vec![req.constraints],
+            &mut Timer::throwaway(),
+        );
+        let (full_cost, all_costs) = pathfinder.all_costs_from(req.clone(), map)?;
+        let path = pathfinder.pathfind_v2(req, map)?;
+        let draw_path = path
+            .clone()
+            .into_v1(map)
+            .ok()?
+            .trace(map)?
+            .make_polygons(NORMAL_LANE_THICKNESS);
+        Some(PathCostDebugger::new_state(
+            ctx, app, path, params, full_cost, all_costs, draw_path,
+        ))
+    }
+
+    fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        path: PathV2,
+        params: RoutingParams,
+        full_cost: Duration,
+        all_costs: HashMap<DirectedRoadID, Duration>,
+        draw_path: Polygon,
+    ) -> Box<dyn State<App>> {
+        let scale = &app.cs.good_to_bad_red;
         let mut batch = GeomBatch::new();
         // Highlight all directed roads with a cost less than the cost of the chosen path. This
         // more or less shows "alternatives considered"; the boundary becomes the point where the
@@ -519,29 +656,162 @@ impl PathCostDebugger {
                     .get_r(dr.road)
                     .get_half_polygon(dr.dir, &app.primary.map)
                 {
-                    batch.push(Color::BLUE.alpha(0.5), p);
+                    let pct = if full_cost == Duration::ZERO {
+                        0.0
+                    } else {
+                        *cost / full_cost
+                    };
+                    batch.push(scale.eval(pct.clamp(0.0, 1.0)).alpha(0.7), p);
                 }
             }
         }
         batch.push(Color::PURPLE, draw_path);
 
-        Some(Box::new(PathCostDebugger {
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Path cost debugger").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            format!("Cost of chosen path: {}", full_cost).text_widget(ctx),
+            "Cost to reach each road explored".text_widget(ctx),
+            scale.make_legend(ctx, 0.0, full_cost.inner_seconds(), |x| {
+                Duration::seconds(x).to_string(&app.opts.units)
+            }),
+        ];
+        let alternatives = rejected_alternatives(app, &path, &params, &all_costs);
+        if !alternatives.is_empty() {
+            col.push(Line("Turns not taken").small_heading().into_widget(ctx));
+            let mut txt = Text::new();
+            for line in alternatives.iter().take(MAX_ALTERNATIVES) {
+                txt.add_line(Line(line).small());
+            }
+            if alternatives.len() > MAX_ALTERNATIVES {
+                txt.add_line(
+                    Line(format!(
+                        "... and {} more",
+                        alternatives.len() - MAX_ALTERNATIVES
+                    ))
+                    .small()
+                    .secondary(),
+                );
+            }
+            col.push(txt.wrap_to_pct(ctx, 25).into_widget(ctx));
+        }
+
+        Box::new(PathCostDebugger {
             draw_path: ctx.upload(batch),
             costs: all_costs,
+            constraints: path.get_req().constraints,
+            params,
             tooltip: None,
-            panel: Panel::new_builder(Widget::col(vec![
-                Widget::row(vec![
-                    Line("Path cost debugger").small_heading().into_widget(ctx),
-                    ctx.style().btn_close_widget(ctx),
-                ]),
-                format!("Cost of chosen path: {}", full_cost).text_widget(ctx),
-            ]))
-            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
-            .build(ctx),
-        }))
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .dims_height(PanelDims::MaxPercent(0.8))
+                .build(ctx),
+        })
+    }
+
+    fn road_tooltip(&self, app: &App, r: RoadID) -> Text {
+        let map = &app.primary.map;
+        // TODO In lieu of mousing over each half of a road, just show both costs.
+        let mut txt = Text::new();
+        for dir in [Direction::Fwd, Direction::Back] {
+            let dr = DirectedRoadID { road: r, dir };
+            if let Some(cost) = self.costs.get(&dr) {
+                txt.add_line(format!("Cost {:?}: {}", dir, cost));
+            } else {
+                txt.add_line(format!("No cost {:?}", dir));
+                continue;
+            }
+            if self.constraints == PathConstraints::Pedestrian {
+                continue;
+            }
+            // The cost of each edge leaving this road depends on the turn
+            let edge_costs: Vec<Duration> = map
+                .get_movements_for(dr, self.constraints)
+                .into_iter()
+                .filter_map(|m| vehicle_cost(dr, m, self.constraints, &self.params, map))
+                .collect();
+            match (edge_costs.iter().min(), edge_costs.iter().max()) {
+                (Some(min), Some(max)) if min == max => {
+                    txt.add_line(Line(format!("  Crossing and turning: {}", min)).secondary());
+                }
+                (Some(min), Some(max)) => {
+                    txt.add_line(
+                        Line(format!("  Crossing and turning: {} to {}", min, max)).secondary(),
+                    );
+                }
+                _ => {
+                    txt.add_line(Line("  No allowed turns from here").secondary());
+                }
+            }
+        }
+        txt
     }
 }
 
+/// At most this many rejected alternatives are listed in the panel
+const MAX_ALTERNATIVES: usize = 15;
+
+/// For every intersection along a vehicle's path, explain why the path didn't turn onto other
+/// roads.
+fn rejected_alternatives(
+    app: &App,
+    path: &PathV2,
+    params: &RoutingParams,
+    costs: &HashMap<DirectedRoadID, Duration>,
+) -> Vec<String> {
+    let map = &app.primary.map;
+    let constraints = path.get_req().constraints;
+    if constraints == PathConstraints::Pedestrian {
+        return Vec::new();
+    }
+
+    let mut lines = Vec::new();
+    for step in path.get_steps() {
+        let chosen = if let PathStepV2::Movement(m) = step {
+            *m
+        } else {
+            continue;
+        };
+        let chosen_cost = vehicle_cost(chosen.from, chosen, constraints, params, map);
+        let allowed = map.get_movements_for(chosen.from, constraints);
+        for alt in map.get_i(chosen.parent).movements.keys() {
+            if alt.from != chosen.from || alt.to == chosen.to || alt.crosswalk {
+                continue;
+            }
+            let reason = if !allowed.contains(alt) {
+                "this mode can't make that turn".to_string()
+            } else {
+                match (
+                    vehicle_cost(alt.from, *alt, constraints, params, map),
+                    chosen_cost,
+                ) {
+                    (None, _) => "the routing params avoid it".to_string(),
+                    (Some(alt_cost), Some(chosen_cost)) if alt_cost > chosen_cost => {
+                        format!("{} more expensive to turn there", alt_cost - chosen_cost)
+                    }
+                    _ => match costs.get(&alt.to) {
+                        Some(cost) => format!(
+                            "cheaper to turn there, but the rest of the route costs more \
+                             (reached after {})",
+                            cost
+                        ),
+                        None => "no route continues from there".to_string(),
+                    },
+                }
+            };
+            lines.push(format!(
+                "At {}, onto {}: {}",
+                chosen.parent,
+                map.get_r(alt.to.road).get_name(app.opts.language.as_ref()),
+                reason
+            ));
+        }
+    }
+    lines
+}
+
 impl State<App> for PathCostDebugger {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         ctx.canvas_movement();
@@ -549,16 +819,7 @@ impl State<App> for PathCostDebugger {
         if ctx.redo_mouseover() {
             self.tooltip = None;
             if let Some(ID::Road(r)) = app.mouseover_unzoomed_roads_and_intersections(ctx) {
-                // TODO In lieu of mousing over each half of a road, just show both costs.
-                let mut txt = Text::new();
-                for dir in [Direction::Fwd, Direction::Back] {
-                    if let Some(cost) = self.costs.get(&DirectedRoadID { road: r, dir }) {
-                        txt.add_line(format!("Cost {:?}: {}", dir, cost));
-                    } else {
-                        txt.add_line(format!("No cost {:?}", dir));
-                    }
-                }
-                self.tooltip = Some(txt);
+                self.tooltip = Some(self.road_tooltip(app, r));
             }
         }
 
@@ -0,0 +1,273 @@
+use geom::{ArrowCap, Circle, Distance, Polygon, Pt2D};
+use map_gui::render::BIG_ARROW_THICKNESS;
+use map_model::{IntersectionID, Map, Turn, TurnID, TurnPriority, TurnType};
+use widgetry::tools::ColorLegend;
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State,
+    Text, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+const CROSSING: Color = Color::RED;
+const SAME_DESTINATION: Color = Color::ORANGE;
+const NO_CONFLICT: Color = Color::grey(0.8);
+/// Both turns conflict, but a stop sign treats both as protected, so neither has to yield
+const UNRESOLVED: Color = Color::PURPLE;
+const ROW_TURN: Color = Color::BLUE;
+const COL_TURN: Color = Color::GREEN;
+
+/// Shows whether every pair of turns through an intersection conflicts, and why. Click a cell to
+/// see both turns on the map.
+pub struct TurnConflictMatrix {
+    panel: Panel,
+    i: IntersectionID,
+    turns: Vec<TurnID>,
+    cell_size: f64,
+    draw_selected: Drawable,
+}
+
+/// Why two turns conflict, following the same rules as `Turn::conflicts_with`
+enum Conflict {
+    None,
+    SameDestination,
+    Crossing(Option<Pt2D>),
+}
+
+impl Conflict {
+    fn between(t1: &Turn, t2: &Turn) -> Conflict {
+        if !t1.conflicts_with(t2) {
+            Conflict::None
+        } else if t1.geom.last_pt() == t2.geom.last_pt() {
+            Conflict::SameDestination
+        } else {
+            Conflict::Crossing(t1.geom.intersection(&t2.geom).map(|(pt, _)| pt))
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Conflict::None => "don't conflict",
+            Conflict::SameDestination => "end at the same point",
+            Conflict::Crossing(_) => "cross each other",
+        }
+    }
+}
+
+impl TurnConflictMatrix {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, i: IntersectionID) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        // Shared sidewalk corners never conflict with anything
+        let turns: Vec<TurnID> = map
+            .get_i(i)
+            .turns
+            .iter()
+            .filter(|t| t.turn_type != TurnType::SharedSidewalkCorner)
+            .map(|t| t.id)
+            .collect();
+        let cell_size = (400.0 / (turns.len().max(1) as f64)).clamp(4.0, 20.0);
+        let mut state = TurnConflictMatrix {
+            panel: Panel::empty(ctx),
+            i,
+            turns,
+            cell_size,
+            draw_selected: Drawable::empty(ctx),
+        };
+        state.panel = state.make_panel(ctx, app, None);
+        Box::new(state)
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx, app: &App, selected: Option<(usize, usize)>) -> Panel {
+        let map = &app.primary.map;
+        let mut col = vec![Widget::row(vec![
+            Line(format!("Turn conflicts at {}", self.i))
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        col.push(validation_summary(ctx, map, self.i));
+
+        if self.turns.is_empty() {
+            col.push(Line("No turns here").secondary().into_widget(ctx));
+        } else {
+            col.push(self.render_matrix(ctx, map, selected).named("matrix"));
+            col.push(ColorLegend::row(ctx, CROSSING, "cross each other"));
+            col.push(ColorLegend::row(
+                ctx,
+                SAME_DESTINATION,
+                "end at the same point",
+            ));
+            if map.maybe_get_stop_sign(self.i).is_some() {
+                col.push(ColorLegend::row(
+                    ctx,
+                    UNRESOLVED,
+                    "conflict, but neither has to stop",
+                ));
+            }
+            col.push(ColorLegend::row(ctx, NO_CONFLICT, "don't conflict"));
+        }
+
+        let details = if let Some((row, col)) = selected {
+            let t1 = map.get_t(self.turns[row]);
+            let t2 = map.get_t(self.turns[col]);
+            let mut txt = Text::new();
+            txt.add_line(Line(format!("Row: {}", describe_turn(map, t1))).fg(ROW_TURN));
+            txt.add_line(Line(format!("Column: {}", describe_turn(map, t2))).fg(COL_TURN));
+            txt.add_line(Line(format!(
+                "These turns {}",
+                Conflict::between(t1, t2).describe()
+            )));
+            txt.into_widget(ctx)
+        } else {
+            Line("Click a cell to see both turns")
+                .secondary()
+                .into_widget(ctx)
+        };
+        col.push(details);
+
+        Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx)
+    }
+
+    fn render_matrix(
+        &self,
+        ctx: &mut EventCtx,
+        map: &Map,
+        selected: Option<(usize, usize)>,
+    ) -> Widget {
+        let stop_sign = map.maybe_get_stop_sign(self.i);
+        let mut batch = GeomBatch::new();
+        for (row, t1) in self.turns.iter().enumerate() {
+            let t1 = map.get_t(*t1);
+            for (col, t2) in self.turns.iter().enumerate() {
+                let t2 = map.get_t(*t2);
+                let color = if row == col {
+                    Color::BLACK
+                } else {
+                    match Conflict::between(t1, t2) {
+                        Conflict::None => NO_CONFLICT,
+                        _ if stop_sign
+                            .map(|ss| {
+                                ss.get_priority(t1.id, map) == TurnPriority::Protected
+                                    && ss.get_priority(t2.id, map) == TurnPriority::Protected
+                            })
+                            .unwrap_or(false) =>
+                        {
+                            UNRESOLVED
+                        }
+                        Conflict::SameDestination => SAME_DESTINATION,
+                        Conflict::Crossing(_) => CROSSING,
+                    }
+                };
+                batch.push(
+                    color,
+                    Polygon::rectangle(self.cell_size - 1.0, self.cell_size - 1.0)
+                        .translate(col as f64 * self.cell_size, row as f64 * self.cell_size),
+                );
+            }
+        }
+        if let Some((row, col)) = selected {
+            let n = self.turns.len() as f64;
+            batch.push(
+                ROW_TURN.alpha(0.5),
+                Polygon::rectangle(n * self.cell_size, self.cell_size)
+                    .translate(0.0, row as f64 * self.cell_size),
+            );
+            batch.push(
+                COL_TURN.alpha(0.5),
+                Polygon::rectangle(self.cell_size, n * self.cell_size)
+                    .translate(col as f64 * self.cell_size, 0.0),
+            );
+        }
+        batch.into_widget(ctx)
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, app: &App, row: usize, col: usize) {
+        let map = &app.primary.map;
+        let t1 = map.get_t(self.turns[row]);
+        let t2 = map.get_t(self.turns[col]);
+
+        let mut batch = GeomBatch::new();
+        batch.push(
+            ROW_TURN.alpha(0.8),
+            t1.geom.make_arrow(BIG_ARROW_THICKNESS, ArrowCap::Triangle),
+        );
+        batch.push(
+            COL_TURN.alpha(0.8),
+            t2.geom.make_arrow(BIG_ARROW_THICKNESS, ArrowCap::Triangle),
+        );
+        // Show exactly where the geometry overlaps
+        let conflict_pt = match Conflict::between(t1, t2) {
+            Conflict::None => None,
+            Conflict::SameDestination => Some(t1.geom.last_pt()),
+            Conflict::Crossing(pt) => pt,
+        };
+        if let Some(pt) = conflict_pt {
+            batch.push(
+                Color::YELLOW,
+                Circle::new(pt, Distance::meters(1.0)).to_polygon(),
+            );
+        }
+        self.draw_selected = ctx.upload(batch);
+        self.panel = self.make_panel(ctx, app, Some((row, col)));
+    }
+}
+
+impl State<App> for TurnConflictMatrix {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        if !self.turns.is_empty() {
+            if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
+                let rect = self.panel.rect_of("matrix").clone();
+                if rect.contains(pt) && ctx.normal_left_click() {
+                    let n = self.turns.len() - 1;
+                    let row = (((pt.y - rect.y1) / self.cell_size) as usize).min(n);
+                    let col = (((pt.x - rect.x1) / self.cell_size) as usize).min(n);
+                    self.select(ctx, app, row, col);
+                }
+            }
+        }
+
+        ctx.canvas_movement();
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_selected);
+        self.panel.draw(g);
+    }
+}
+
+fn describe_turn(map: &Map, t: &Turn) -> String {
+    format!(
+        "{:?} from {} to {}",
+        t.turn_type,
+        map.get_parent(t.id.src).get_name(None),
+        map.get_parent(t.id.dst).get_name(None)
+    )
+}
+
+/// Explain whether the current traffic signal or stop sign passes validation
+fn validation_summary(ctx: &mut EventCtx, map: &Map, i: IntersectionID) -> Widget {
+    let intersection = map.get_i(i);
+    let txt = if let Some(signal) = map.maybe_get_traffic_signal(i) {
+        match signal.validate(intersection) {
+            Ok(()) => Text::from("The traffic signal is valid"),
+            Err(err) => Text::from(Line(format!("Invalid traffic signal: {}", err)).fg(CROSSING)),
+        }
+    } else if map.maybe_get_stop_sign(i).is_some() {
+        Text::from("Stop sign. Purple cells need one of the turns to stop or yield.")
+    } else {
+        Text::from("This intersection has no traffic control")
+    };
+    txt.wrap_to_pct(ctx, 25).into_widget(ctx)
+}
@@ -0,0 +1,191 @@
+use geom::{Distance, Pt2D};
+use map_model::Annotation;
+use widgetry::mapspace::{ToggleZoomed, ToggleZoomedBuilder};
+use widgetry::tools::PromptInput;
+use widgetry::{
+    EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text,
+    TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::edit::apply_map_edits;
+
+const PIN_ICON: &str = "system/assets/tools/pin.svg";
+/// The icon is 32 pixels square. Scale it to roughly this many meters, depending on zoom.
+const UNZOOMED_PIN_SIZE: f64 = 64.0;
+const ZOOMED_PIN_SIZE: f64 = 16.0;
+
+/// Adds a pin for every note in the current proposal
+pub fn draw_pins(ctx: &EventCtx, app: &App, draw: &mut ToggleZoomedBuilder) {
+    let gps_bounds = app.primary.map.get_gps_bounds();
+    for a in &app.primary.map.get_edits().annotations {
+        let pt = a.pt.to_pt(gps_bounds);
+        draw.unzoomed.append(pin(ctx, pt, UNZOOMED_PIN_SIZE));
+        draw.zoomed.append(pin(ctx, pt, ZOOMED_PIN_SIZE));
+    }
+}
+
+fn pin(ctx: &EventCtx, pt: Pt2D, size: f64) -> GeomBatch {
+    // The tip of the pin is at the bottom
+    GeomBatch::load_svg(ctx, PIN_ICON)
+        .scale(size / 32.0)
+        .centered_on(pt)
+        .translate(0.0, -size / 2.0)
+}
+
+/// If the cursor is over a pin, show its note
+pub fn draw_hovered_note(g: &mut GfxCtx, app: &App) {
+    let cursor = match g.get_cursor_in_map_space() {
+        Some(pt) => pt,
+        None => return,
+    };
+    let size = if g.canvas.is_unzoomed() {
+        UNZOOMED_PIN_SIZE
+    } else {
+        ZOOMED_PIN_SIZE
+    };
+    let gps_bounds = app.primary.map.get_gps_bounds();
+    for a in &app.primary.map.get_edits().annotations {
+        // The middle of the pin is above the point
+        let center = a.pt.to_pt(gps_bounds).offset(0.0, -size / 2.0);
+        if center.dist_to(cursor) <= Distance::meters(size / 2.0) {
+            g.draw_mouse_tooltip(Text::from(Line(&a.text)));
+            return;
+        }
+    }
+}
+
+/// Lists the notes in the current proposal, and lets the player add and remove them
+pub struct EditAnnotations {
+    panel: Panel,
+    draw: ToggleZoomed,
+    adding: bool,
+    edits_key: usize,
+}
+
+impl EditAnnotations {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut state = EditAnnotations {
+            panel: Panel::empty(ctx),
+            draw: ToggleZoomed::empty(ctx),
+            adding: false,
+            edits_key: app.primary.map.get_edits_change_key(),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let annotations = &app.primary.map.get_edits().annotations;
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Notes").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "Explain parts of your proposal to people you share it with".text_widget(ctx),
+        ];
+        for (idx, a) in annotations.iter().enumerate() {
+            col.push(Widget::row(vec![
+                ctx.style()
+                    .btn_plain
+                    .text(&a.text)
+                    .build_widget(ctx, format!("note #{}", idx + 1)),
+                ctx.style()
+                    .btn_plain_destructive
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, format!("delete note #{}", idx + 1))
+                    .align_right(),
+            ]));
+        }
+        col.push(if self.adding {
+            "Click the map to place the note".text_widget(ctx)
+        } else {
+            ctx.style()
+                .btn_solid_primary
+                .icon_text(PIN_ICON, "Add a note")
+                .hotkey(Key::N)
+                .build_def(ctx)
+        });
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx);
+
+        let mut draw = ToggleZoomed::builder();
+        draw_pins(ctx, app, &mut draw);
+        self.draw = draw.build(ctx);
+    }
+}
+
+impl State<App> for EditAnnotations {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let key = app.primary.map.get_edits_change_key();
+        if self.edits_key != key {
+            self.edits_key = key;
+            self.recalculate(ctx, app);
+        }
+
+        ctx.canvas_movement();
+
+        if self.adding && ctx.normal_left_click() {
+            if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                self.adding = false;
+                self.recalculate(ctx, app);
+                return Transition::Push(PromptInput::new_state(
+                    ctx,
+                    "What should people know about this place?",
+                    String::new(),
+                    Box::new(move |text, ctx, app| {
+                        let mut edits = app.primary.map.get_edits().clone();
+                        edits.annotations.push(Annotation {
+                            pt: pt.to_gps(app.primary.map.get_gps_bounds()),
+                            text,
+                        });
+                        apply_map_edits(ctx, app, edits);
+                        Transition::Pop
+                    }),
+                ));
+            }
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Add a note" => {
+                    self.adding = true;
+                    self.recalculate(ctx, app);
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("delete note #") {
+                        let idx = idx.parse::<usize>().unwrap() - 1;
+                        let mut edits = app.primary.map.get_edits().clone();
+                        edits.annotations.remove(idx);
+                        apply_map_edits(ctx, app, edits);
+                    } else {
+                        let idx = x["note #".len()..].parse::<usize>().unwrap() - 1;
+                        let pt = app.primary.map.get_edits().annotations[idx]
+                            .pt
+                            .to_pt(app.primary.map.get_gps_bounds());
+                        return Transition::Push(Warping::new_state(
+                            ctx,
+                            pt,
+                            Some(10.0),
+                            None,
+                            &mut app.primary,
+                        ));
+                    }
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.draw.draw(g);
+        self.panel.draw(g);
+        draw_hovered_note(g, app);
+    }
+}
@@ -0,0 +1,407 @@
+//! Lets several people edit the same proposal at once, by syncing edit commands through
+//! headless/src/bin/collab_server.rs. Everybody needs to be on the same map.
+//!
+//! Syncing only happens while edit mode is the active state. Local changes are pushed as the
+//! commands that turn the last synced edits into the current ones, so undoing or loading a
+//! different proposal is shared too. If somebody else changed the same object first, the server
+//! rejects the push and the local change is reverted.
+
+use std::collections::BTreeSet;
+
+use futures_channel::oneshot;
+use instant::Instant;
+use serde::{Deserialize, Serialize};
+
+use geom::{Bounds, Distance, Duration};
+use map_gui::tools::grey_out_map;
+use map_model::{EditCmd, PermanentEditCmd};
+use widgetry::tools::{Background, PopupMsg};
+use widgetry::{
+    Drawable, EventCtx, GeomBatch, GfxCtx, Key, Line, Panel, SimpleState, State, Text, TextBox,
+    TextExt, UpdateType, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::edit::apply_map_edits;
+
+/// How often to check for changes from other people
+const POLL_INTERVAL: Duration = Duration::const_seconds(1.0);
+
+pub struct Collaboration {
+    server: String,
+    session: String,
+    author: String,
+    /// Distinguishes this client from others using the same name
+    client: u64,
+    background: Background,
+    /// The last version of the shared log pulled
+    version: usize,
+    /// Versions this client pushed, which shouldn't be applied again
+    own_versions: BTreeSet<usize>,
+    /// The local commands that match the shared log. Anything beyond this hasn't been pushed yet.
+    synced: Vec<EditCmd>,
+    pending: Option<Pending>,
+    last_request: Instant,
+    collaborators: Vec<Presence>,
+    draw_collaborators: Drawable,
+}
+
+enum Pending {
+    Push {
+        /// The commands that'll be synced if the push succeeds
+        target: Vec<EditCmd>,
+        response: oneshot::Receiver<anyhow::Result<String>>,
+    },
+    Pull(oneshot::Receiver<anyhow::Result<String>>),
+}
+
+// These must match the JSON types in collab_server.rs
+
+#[derive(Serialize)]
+struct Push {
+    client: u64,
+    author: String,
+    base_version: usize,
+    touched: Vec<String>,
+    cmds: Vec<PermanentEditCmd>,
+}
+
+#[derive(Deserialize)]
+enum PushResponse {
+    Accepted { version: usize },
+    Conflict { author: String, object: String },
+}
+
+#[derive(Serialize)]
+struct Pull {
+    client: u64,
+    since: usize,
+    presence: Presence,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Presence {
+    author: String,
+    view: Bounds,
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    changes: Vec<Change>,
+    collaborators: Vec<Presence>,
+}
+
+#[derive(Deserialize)]
+struct Change {
+    version: usize,
+    author: String,
+    cmds: Vec<PermanentEditCmd>,
+}
+
+impl Collaboration {
+    /// Starts syncing the current proposal. Any edits already made get pushed to the session.
+    fn new(ctx: &EventCtx, server: String, session: String, author: String) -> Collaboration {
+        Collaboration {
+            server: server.trim_end_matches('/').to_string(),
+            session,
+            author,
+            client: rand::random(),
+            background: Background::new(),
+            version: 0,
+            own_versions: BTreeSet::new(),
+            synced: Vec::new(),
+            pending: None,
+            last_request: Instant::now(),
+            collaborators: Vec::new(),
+            draw_collaborators: Drawable::empty(ctx),
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw_collaborators);
+    }
+
+    fn url(&self, cmd: &str) -> String {
+        format!("{}/{}?session={}", self.server, cmd, self.session)
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<Box<dyn State<App>>> {
+        // Keep polling, even without input
+        ctx.request_update(UpdateType::Game);
+
+        match self.pending.take() {
+            Some(Pending::Push {
+                target,
+                mut response,
+            }) => match response.try_recv() {
+                Ok(None) => {
+                    self.pending = Some(Pending::Push { target, response });
+                }
+                Ok(Some(Ok(resp))) => match abstutil::from_json(resp.as_bytes()) {
+                    Ok(PushResponse::Accepted { version }) => {
+                        self.own_versions.insert(version);
+                        self.synced = target;
+                    }
+                    Ok(PushResponse::Conflict { author, object }) => {
+                        // Revert to what everybody agrees on. The conflicting change arrives with
+                        // the next pull.
+                        let mut edits = app.primary.map.get_edits().clone();
+                        edits.commands = self.synced.clone();
+                        apply_map_edits(ctx, app, edits);
+                        return Some(PopupMsg::new_state(
+                            ctx,
+                            "Conflicting edit",
+                            vec![
+                                format!("{} changed {} at the same time.", author, object),
+                                "Your change was undone. Try again once theirs appears."
+                                    .to_string(),
+                            ],
+                        ));
+                    }
+                    Err(err) => {
+                        warn!("Bad response from {}: {}", self.server, err);
+                    }
+                },
+                Ok(Some(Err(err))) => {
+                    warn!("Couldn't push edits to {}: {}", self.server, err);
+                }
+                Err(_) => {}
+            },
+            Some(Pending::Pull(mut response)) => match response.try_recv() {
+                Ok(None) => {
+                    self.pending = Some(Pending::Pull(response));
+                }
+                Ok(Some(Ok(resp))) => match abstutil::from_json(resp.as_bytes()) {
+                    Ok(resp) => {
+                        self.apply_pulled(ctx, app, resp);
+                    }
+                    Err(err) => {
+                        warn!("Bad response from {}: {}", self.server, err);
+                    }
+                },
+                Ok(Some(Err(err))) => {
+                    warn!("Couldn't pull edits from {}: {}", self.server, err);
+                }
+                Err(_) => {}
+            },
+            None => {}
+        }
+
+        if self.pending.is_none() && Duration::realtime_elapsed(self.last_request) >= POLL_INTERVAL
+        {
+            self.last_request = Instant::now();
+            if !self.push(app) {
+                self.pull(ctx);
+            }
+        }
+        None
+    }
+
+    /// If there are local changes, start pushing them and return true.
+    fn push(&mut self, app: &App) -> bool {
+        let map = &app.primary.map;
+        let cmds = self.local_changes(&map.get_edits().commands);
+        if cmds.is_empty() {
+            return false;
+        }
+
+        let mut touched = Vec::new();
+        for cmd in &cmds {
+            let object = touched_object(cmd);
+            if !touched.contains(&object) {
+                touched.push(object);
+            }
+        }
+
+        let body = abstutil::to_json(&Push {
+            client: self.client,
+            author: self.author.clone(),
+            base_version: self.version,
+            touched,
+            cmds: cmds.iter().map(|cmd| cmd.to_perma(map)).collect(),
+        });
+        let url = self.url("push");
+        self.pending = Some(Pending::Push {
+            target: current.clone(),
+            response: self
+                .background
+                .spawn(Box::pin(async move { abstio::http_post(url, body).await })),
+        });
+        true
+    }
+
+    fn pull(&mut self, ctx: &EventCtx) {
+        let body = abstutil::to_json(&Pull {
+            client: self.client,
+            since: self.version,
+            presence: Presence {
+                author: self.author.clone(),
+                view: ctx.canvas.get_screen_bounds(),
+            },
+        });
+        let url = self.url("pull");
+        self.pending =
+            Some(Pending::Pull(self.background.spawn(Box::pin(async move {
+                abstio::http_post(url, body).await
+            }))));
+    }
+
+    /// The commands that turn the last synced edits into the current ones. If something synced
+    /// was undone locally, this starts by undoing it.
+    fn local_changes(&self, current: &[EditCmd]) -> Vec<EditCmd> {
+        let common = self
+            .synced
+            .iter()
+            .zip(current.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let mut cmds: Vec<EditCmd> = self.synced[common..]
+            .iter()
+            .rev()
+            .map(|cmd| cmd.clone().undo())
+            .collect();
+        cmds.extend(current[common..].iter().cloned());
+        cmds
+    }
+
+    fn apply_pulled(&mut self, ctx: &mut EventCtx, app: &mut App, resp: PullResponse) {
+        let mut remote = Vec::new();
+        for change in resp.changes {
+            self.version = self.version.max(change.version);
+            if self.own_versions.contains(&change.version) {
+                continue;
+            }
+            for cmd in change.cmds {
+                match cmd.into_cmd(&app.primary.map) {
+                    Ok(cmd) => {
+                        remote.push(cmd);
+                    }
+                    Err(err) => {
+                        warn!("Skipping an edit from {}: {}", change.author, err);
+                    }
+                }
+            }
+        }
+
+        if !remote.is_empty() {
+            // Somebody may have edited locally while the pull was in flight. Keep those changes
+            // after the remote ones, so they're still the only unpushed part.
+            let mut edits = app.primary.map.get_edits().clone();
+            let local = self.local_changes(&edits.commands);
+            self.synced.extend(remote);
+            edits.commands = self.synced.clone();
+            edits.commands.extend(local);
+            apply_map_edits(ctx, app, edits);
+        }
+
+        self.collaborators = resp.collaborators;
+        let mut batch = GeomBatch::new();
+        for (idx, presence) in self.collaborators.iter().enumerate() {
+            let color = app.cs.rotating_color_plot(idx);
+            batch.push(
+                color,
+                presence
+                    .view
+                    .get_rectangle()
+                    .to_outline(Distance::meters(3.0)),
+            );
+            batch.append(
+                Text::from(Line(&presence.author))
+                    .bg(color)
+                    .render_autocropped(ctx)
+                    .scale(0.5)
+                    .translate(presence.view.min_x, presence.view.min_y),
+            );
+        }
+        self.draw_collaborators = ctx.upload(batch);
+    }
+}
+
+/// Call this from edit mode's `event`. A state may be returned to warn about conflicts.
+pub fn sync(ctx: &mut EventCtx, app: &mut App) -> Option<Box<dyn State<App>>> {
+    let mut collab = app.session.collab.take()?;
+    let result = collab.event(ctx, app);
+    app.session.collab = Some(collab);
+    result
+}
+
+/// Two people changing the same thing at once conflict
+fn touched_object(cmd: &EditCmd) -> String {
+    match cmd {
+        EditCmd::ChangeRoad { r, .. } => format!("road #{}", r.0),
+        EditCmd::ChangeIntersection { i, .. }
+        | EditCmd::ChangeCrosswalks { i, .. }
+        | EditCmd::ChangeUTurns { i, .. } => format!("intersection #{}", i.0),
+        EditCmd::ChangeRouteSchedule { id, .. }
+        | EditCmd::ChangeRouteStops { id, .. }
+        | EditCmd::ChangeRouteBoarding { id, .. } => format!("route {}", id),
+        EditCmd::ChangeTransitStop { id, .. } => format!("transit stop {}", id),
+    }
+}
+
+pub struct JoinSession;
+
+impl JoinSession {
+    pub fn new_state(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Edit together").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "Everybody in the same session shares one proposal.".text_widget(ctx),
+            "Any edits you've already made will be added to it.".text_widget(ctx),
+            Widget::row(vec![
+                "Server:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "server", "http://localhost:8080".to_string()),
+            ]),
+            Widget::row(vec![
+                "Session:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "session", "workshop".to_string()),
+            ]),
+            Widget::row(vec![
+                "Your name:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "author", String::new()),
+            ]),
+            ctx.style()
+                .btn_solid_primary
+                .text("Join")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+        ]))
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(JoinSession))
+    }
+}
+
+impl SimpleState<App> for JoinSession {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition {
+        match x {
+            "close" => Transition::Pop,
+            "Join" => {
+                let server = panel.text_box("server");
+                let session = panel.text_box("session");
+                let author = panel.text_box("author");
+                if server.is_empty() || session.is_empty() || author.is_empty() {
+                    return Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Missing information",
+                        vec!["Fill out the server, session, and your name"],
+                    ));
+                }
+                app.session.collab = Some(Collaboration::new(ctx, server, session, author));
+                Transition::Pop
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+    }
+}
@@ -0,0 +1,368 @@
+//! Publish proposals to a community gallery, and browse and load proposals other people published
+//! for the same map. See headless/src/bin/gallery_server.rs for the backend.
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Circle, Distance, Polygon};
+use map_gui::tools::grey_out_map;
+use map_model::MapEdits;
+use widgetry::tools::{FutureLoader, PopupMsg};
+use widgetry::{
+    EventCtx, GeomBatch, GfxCtx, Key, Line, Panel, SimpleState, State, Text, TextBox, TextExt,
+    Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::edit::apply_map_edits;
+use crate::sandbox::GameplayMode;
+
+// TODO Point this at a hosted server once there is one
+pub const GALLERY_HOST_URL: &str = "http://localhost:8081/v1";
+
+const THUMBNAIL_WIDTH: f64 = 200.0;
+
+// These must match the JSON types in gallery_server.rs
+
+#[derive(Serialize)]
+struct Submission {
+    map: String,
+    title: String,
+    author: String,
+    description: String,
+    thumbnail_svg: String,
+    version: String,
+    num_commands: usize,
+    edits: map_model::PermanentMapEdits,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    id: usize,
+    title: String,
+    author: String,
+    description: String,
+    thumbnail_svg: String,
+    version: String,
+    num_commands: usize,
+}
+
+pub struct PublishProposal;
+
+impl PublishProposal {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let edits = app.primary.map.get_edits();
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Publish to the community gallery")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            thumbnail(ctx, &thumbnail_svg(app)),
+            Widget::row(vec![
+                "Title:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "title", edits.edits_name.clone()),
+            ]),
+            Widget::row(vec![
+                "Your name:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "author", String::new()),
+            ]),
+            Widget::row(vec![
+                "Description:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "description", edits.proposal_description.join(" ")),
+            ]),
+            Text::from_multiline(vec![
+                Line("Anybody will be able to see and load this proposal."),
+                Line("You can't delete or change it after publishing."),
+            ])
+            .into_widget(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Publish")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+        ]))
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(PublishProposal))
+    }
+}
+
+impl SimpleState<App> for PublishProposal {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition {
+        match x {
+            "close" => Transition::Pop,
+            "Publish" => {
+                let map = &app.primary.map;
+                let submission = Submission {
+                    map: map.get_name().as_filename(),
+                    title: panel.text_box("title"),
+                    author: panel.text_box("author"),
+                    description: panel.text_box("description"),
+                    thumbnail_svg: thumbnail_svg(app),
+                    version: map_gui::tools::version().to_string(),
+                    num_commands: map.get_edits().commands.len(),
+                    edits: map.get_edits().to_permanent(map),
+                };
+                if submission.title.is_empty() {
+                    return Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Missing title",
+                        vec!["Give the proposal a title first"],
+                    ));
+                }
+                let body = abstutil::to_json(&submission);
+
+                let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
+                let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+                Transition::Replace(FutureLoader::<App, String>::new_state(
+                    ctx,
+                    Box::pin(async move {
+                        let id = abstio::http_post(format!("{}/publish", GALLERY_HOST_URL), body)
+                            .await?;
+                        let wrapper: Box<dyn Send + FnOnce(&App) -> String> = Box::new(move |_| id);
+                        Ok(wrapper)
+                    }),
+                    outer_progress_rx,
+                    inner_progress_rx,
+                    "Publishing proposal",
+                    Box::new(|ctx, _, result| {
+                        Transition::Replace(match result {
+                            Ok(id) => PopupMsg::new_state(
+                                ctx,
+                                "Published",
+                                vec![format!("Your proposal is #{} in the community gallery", id)],
+                            ),
+                            Err(err) => PopupMsg::new_state(
+                                ctx,
+                                "Failure",
+                                vec![format!("Couldn't publish proposal: {}", err)],
+                            ),
+                        })
+                    }),
+                ))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+    }
+}
+
+/// Downloads the list of proposals for the current map, then shows them
+pub fn browse(ctx: &mut EventCtx, app: &App, mode: GameplayMode) -> Box<dyn State<App>> {
+    let url = format!(
+        "{}/list?map={}",
+        GALLERY_HOST_URL,
+        app.primary.map.get_name().as_filename()
+    );
+    let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
+    let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+    FutureLoader::<App, Vec<u8>>::new_state(
+        ctx,
+        Box::pin(async move {
+            let bytes = abstio::http_get(url).await?;
+            let wrapper: Box<dyn Send + FnOnce(&App) -> Vec<u8>> = Box::new(move |_| bytes);
+            Ok(wrapper)
+        }),
+        outer_progress_rx,
+        inner_progress_rx,
+        "Loading the community gallery",
+        Box::new(move |ctx, _, result| {
+            match result.and_then(|bytes| abstutil::from_json::<Vec<Summary>>(&bytes)) {
+                Ok(list) => Transition::Replace(Gallery::new_state(ctx, list, mode)),
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Failure",
+                    vec![format!("Couldn't load the community gallery: {}", err)],
+                )),
+            }
+        }),
+    )
+}
+
+struct Gallery {
+    mode: GameplayMode,
+}
+
+impl Gallery {
+    fn new_state(
+        ctx: &mut EventCtx,
+        proposals: Vec<Summary>,
+        mode: GameplayMode,
+    ) -> Box<dyn State<App>> {
+        let mut col = vec![Widget::row(vec![
+            Line("Community gallery").small_heading().into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        if proposals.is_empty() {
+            col.push("Nobody has published a proposal for this map yet".text_widget(ctx));
+        }
+        for p in &proposals {
+            let mut txt = Text::new();
+            txt.add_line(Line(&p.title).small_heading());
+            txt.add_line(Line(format!("by {}", p.author)).secondary());
+            if !p.description.is_empty() {
+                txt.add_line(Line(&p.description));
+            }
+            txt.add_line(
+                Line(format!(
+                    "{} changes, made with version {}",
+                    p.num_commands, p.version
+                ))
+                .secondary(),
+            );
+            col.push(
+                Widget::row(vec![
+                    thumbnail(ctx, &p.thumbnail_svg),
+                    Widget::col(vec![
+                        txt.wrap_to_pct(ctx, 40).into_widget(ctx),
+                        ctx.style()
+                            .btn_outline
+                            .text("Load")
+                            .build_widget(ctx, format!("load proposal #{}", p.id)),
+                    ]),
+                ])
+                .padding(16)
+                .outline(ctx.style().section_outline),
+            );
+        }
+        let panel = Panel::new_builder(Widget::col(col))
+            .exact_size_percent(60, 80)
+            .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(Gallery { mode }))
+    }
+}
+
+impl SimpleState<App> for Gallery {
+    fn on_click(&mut self, ctx: &mut EventCtx, _: &mut App, x: &str, _: &mut Panel) -> Transition {
+        if x == "close" {
+            return Transition::Pop;
+        }
+        let id = x["load proposal #".len()..].parse::<usize>().unwrap();
+        let mode = self.mode.clone();
+
+        let url = format!("{}/get?id={}", GALLERY_HOST_URL, id);
+        let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
+        let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+        Transition::Replace(FutureLoader::<App, Vec<u8>>::new_state(
+            ctx,
+            Box::pin(async move {
+                let bytes = abstio::http_get(url).await?;
+                let wrapper: Box<dyn Send + FnOnce(&App) -> Vec<u8>> = Box::new(move |_| bytes);
+                Ok(wrapper)
+            }),
+            outer_progress_rx,
+            inner_progress_rx,
+            "Downloading proposal",
+            Box::new(move |ctx, app, result| {
+                // The map may have changed since the proposal was made. Load whatever still
+                // applies, and say what didn't.
+                let (edits, report) = match result.and_then(|bytes| {
+                    MapEdits::load_from_bytes_with_report(&app.primary.map, bytes)
+                }) {
+                    Ok(pair) => pair,
+                    Err(err) => {
+                        return Transition::Replace(PopupMsg::new_state(
+                            ctx,
+                            "Can't load proposal",
+                            vec![err.to_string()],
+                        ));
+                    }
+                };
+                if !mode.allows(&edits) {
+                    return Transition::Replace(PopupMsg::new_state(
+                        ctx,
+                        "Can't load proposal",
+                        vec![
+                            "The current gameplay mode restricts edits. This proposal has a \
+                             banned command.",
+                        ],
+                    ));
+                }
+                apply_map_edits(ctx, app, edits);
+                app.primary
+                    .sim
+                    .handle_live_edited_traffic_signals(&app.primary.map);
+                if report.is_clean() {
+                    Transition::Pop
+                } else {
+                    Transition::Replace(PopupMsg::new_state(
+                        ctx,
+                        "Some changes don't match this map",
+                        report.describe(),
+                    ))
+                }
+            }),
+        ))
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+    }
+}
+
+/// A small picture of the map boundary with changed roads and intersections highlighted. Only
+/// filled shapes are used, since that's all widgetry's SVG support handles.
+fn thumbnail_svg(app: &App) -> String {
+    let map = &app.primary.map;
+    let bounds = map.get_bounds();
+    let thickness = Distance::meters(bounds.width().max(bounds.height()) / 100.0);
+    let edits = map.get_edits();
+    let color = app.cs.edits_layer.as_hex();
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="{:.1} {:.1} {:.1} {:.1}">"#,
+        bounds.width(),
+        bounds.height(),
+        bounds.min_x,
+        bounds.min_y,
+        bounds.width(),
+        bounds.height()
+    );
+    svg.push_str(&svg_path(map.get_boundary_polygon(), "#CCCCCC"));
+    for r in &edits.changed_roads {
+        svg.push_str(&svg_path(
+            &map.get_r(*r).center_pts.make_polygons(thickness),
+            &color,
+        ));
+    }
+    for i in edits.original_intersections.keys() {
+        svg.push_str(&svg_path(
+            &Circle::new(map.get_i(*i).polygon.center(), thickness).to_polygon(),
+            &color,
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn svg_path(polygon: &Polygon, color: &str) -> String {
+    let pts = polygon
+        .get_outer_ring()
+        .points()
+        .iter()
+        .map(|pt| format!("{:.1} {:.1}", pt.x(), pt.y()))
+        .collect::<Vec<_>>();
+    format!(r#"<path d="M {} Z" fill="{}"/>"#, pts.join(" L "), color)
+}
+
+fn thumbnail(ctx: &EventCtx, svg: &str) -> Widget {
+    match GeomBatch::try_load_svg_bytes_uncached(svg.as_bytes()) {
+        Ok(batch) => batch
+            .autocrop()
+            .scale_to_fit_width(THUMBNAIL_WIDTH)
+            .into_widget(ctx),
+        Err(err) => {
+            warn!("Bad thumbnail: {}", err);
+            Widget::nothing()
+        }
+    }
+}
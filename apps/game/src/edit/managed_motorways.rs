@@ -0,0 +1,253 @@
+use std::collections::BTreeSet;
+
+use maplit::btreeset;
+
+use geom::{Duration, Speed, Time};
+use map_gui::tools::ColorDiscrete;
+use map_model::{RoadID, VariableSpeedLimit};
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::{
+    Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner,
+    State, Text, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::{CommonState, RoadSelector};
+use crate::edit::{apply_map_edits, speed_limit_choices};
+
+/// Select a group of roads and the logic controlling their variable speed limit signs.
+pub struct ManagedMotorwayEditor {
+    panel: Panel,
+    selector: RoadSelector,
+    draw: ToggleZoomed,
+
+    orig_members: BTreeSet<RoadID>,
+}
+
+impl ManagedMotorwayEditor {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App, start: RoadID) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let start = map.get_r(start);
+        let current = start.variable_speed_limit.clone();
+        let members: BTreeSet<RoadID> = if let Some(ref control) = current {
+            // Roads with exactly the same control are probably managed together
+            map.all_roads()
+                .iter()
+                .filter(|r| r.variable_speed_limit.as_ref() == Some(control))
+                .map(|r| r.id)
+                .collect()
+        } else {
+            btreeset! { start.id }
+        };
+
+        let default_limit = start.speed_limit.min(Speed::miles_per_hour(40.0));
+        let peak_periods = current
+            .as_ref()
+            .map(|c| peak_periods(&c.schedule))
+            .unwrap_or_else(Vec::new);
+        let peak_limit = current
+            .as_ref()
+            .and_then(|c| c.schedule.iter().find_map(|(_, limit)| *limit))
+            .unwrap_or(default_limit);
+        let congested_density = current
+            .as_ref()
+            .and_then(|c| c.congested_density)
+            .unwrap_or(0);
+        let congested_limit = current
+            .as_ref()
+            .map(|c| c.congested_limit)
+            .unwrap_or(default_limit);
+
+        let (draw, legend) = draw_roads(ctx, app, &members);
+        let orig_members = members.clone();
+        let selector = RoadSelector::new(ctx, app, members);
+
+        Box::new(ManagedMotorwayEditor {
+            panel: Panel::new_builder(Widget::col(vec![
+                Line("Editing a managed motorway")
+                    .small_heading()
+                    .into_widget(ctx),
+                selector.make_controls(ctx).named("selector"),
+                legend,
+                Text::from(
+                    "Overhead signs post a lower speed limit during peak hours, or when traffic \
+                     gets dense. Otherwise, each road's normal limit applies.",
+                )
+                .wrap_to_pct(ctx, 30)
+                .into_widget(ctx),
+                Widget::row(vec![
+                    Line("Peak hours").into_widget(ctx).centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "peak hours",
+                        peak_periods.clone(),
+                        peak_hour_choices(peak_periods),
+                    ),
+                    Line("limit").into_widget(ctx).centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "peak limit",
+                        peak_limit,
+                        speed_limit_choices(app, Some(peak_limit)),
+                    ),
+                ]),
+                Widget::row(vec![
+                    Line("When there are at least")
+                        .into_widget(ctx)
+                        .centered_vert(),
+                    Spinner::widget(ctx, "congested density", (0, 200), congested_density, 5),
+                    Line("vehicles per lane-km, post")
+                        .into_widget(ctx)
+                        .centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "congested limit",
+                        congested_limit,
+                        speed_limit_choices(app, Some(congested_limit)),
+                    ),
+                ]),
+                Line("(0 vehicles never lowers the limit)")
+                    .secondary()
+                    .into_widget(ctx),
+                Widget::custom_row(vec![
+                    ctx.style()
+                        .btn_solid_primary
+                        .text("Apply")
+                        .hotkey(Key::Enter)
+                        .build_def(ctx),
+                    ctx.style()
+                        .btn_solid_destructive
+                        .text("Cancel")
+                        .hotkey(Key::Escape)
+                        .build_def(ctx),
+                ])
+                .evenly_spaced(),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            selector,
+            draw,
+            orig_members,
+        })
+    }
+
+    /// None if the panel doesn't change the speed limit at all
+    fn chosen_control(&self) -> Option<VariableSpeedLimit> {
+        let periods: Vec<(Time, Time)> = self.panel.dropdown_value("peak hours");
+        let peak_limit: Speed = self.panel.dropdown_value("peak limit");
+        let congested_density = self.panel.spinner("congested density");
+        if periods.is_empty() && congested_density == 0 {
+            return None;
+        }
+        Some(VariableSpeedLimit {
+            schedule: periods
+                .into_iter()
+                .flat_map(|(start, end)| [(start, Some(peak_limit)), (end, None)])
+                .collect(),
+            congested_density: if congested_density == 0 {
+                None
+            } else {
+                Some(congested_density)
+            },
+            congested_limit: self.panel.dropdown_value("congested limit"),
+        })
+    }
+}
+
+impl State<App> for ManagedMotorwayEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "Apply" => {
+                    let control = self.chosen_control();
+                    let map = &app.primary.map;
+                    let mut edits = map.get_edits().clone();
+                    for r in self
+                        .orig_members
+                        .difference(&self.selector.roads)
+                        .chain(self.selector.roads.iter())
+                    {
+                        let new_control = if self.selector.roads.contains(r) {
+                            control.clone()
+                        } else {
+                            None
+                        };
+                        if map.get_r(*r).variable_speed_limit != new_control {
+                            edits.commands.push(map.edit_road_cmd(*r, |new| {
+                                new.variable_speed_limit = new_control.clone();
+                            }));
+                        }
+                    }
+
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Pop;
+                }
+                "Cancel" => {
+                    return Transition::Pop;
+                }
+                x => {
+                    if self.selector.event(ctx, app, Some(x)) {
+                        let new_controls = self.selector.make_controls(ctx);
+                        self.panel.replace(ctx, "selector", new_controls);
+                        self.draw = draw_roads(ctx, app, &self.selector.roads).0;
+                    }
+                }
+            },
+            _ => {
+                if self.selector.event(ctx, app, None) {
+                    let new_controls = self.selector.make_controls(ctx);
+                    self.panel.replace(ctx, "selector", new_controls);
+                    self.draw = draw_roads(ctx, app, &self.selector.roads).0;
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.draw.draw(g);
+        self.panel.draw(g);
+        self.selector.draw(g, app, false);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn draw_roads(ctx: &mut EventCtx, app: &App, members: &BTreeSet<RoadID>) -> (ToggleZoomed, Widget) {
+    let mut colorer = ColorDiscrete::new(app, vec![("managed motorway", Color::CYAN)]);
+    for r in members {
+        colorer.add_r(*r, "managed motorway");
+    }
+    colorer.build(ctx)
+}
+
+/// The editor only produces schedules lowering the limit during some periods, then going back to
+/// normal. Recover those periods, ignoring anything else.
+fn peak_periods(schedule: &[(Time, Option<Speed>)]) -> Vec<(Time, Time)> {
+    schedule
+        .chunks(2)
+        .filter_map(|pair| match pair {
+            [(start, Some(_)), (end, None)] => Some((*start, *end)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn peak_hour_choices(current: Vec<(Time, Time)>) -> Vec<Choice<Vec<(Time, Time)>>> {
+    let hours = |start: usize, end: usize| {
+        (
+            Time::START_OF_DAY + Duration::hours(start),
+            Time::START_OF_DAY + Duration::hours(end),
+        )
+    };
+    let mut choices = vec![
+        Choice::new("none", Vec::new()),
+        Choice::new("6 - 10am", vec![hours(6, 10)]),
+        Choice::new("3 - 7pm", vec![hours(15, 19)]),
+        Choice::new("6 - 10am and 3 - 7pm", vec![hours(6, 10), hours(15, 19)]),
+    ];
+    if !choices.iter().any(|c| c.data == current) {
+        choices.push(Choice::new("other schedule", current));
+    }
+    choices
+}
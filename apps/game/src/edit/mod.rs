@@ -1,19 +1,24 @@
+use std::rc::Rc;
+
 use maplit::btreeset;
 
 use crate::ID;
 use abstutil::{prettyprint_usize, Timer};
 use geom::Speed;
 use map_gui::options::OptionsPanel;
-use map_gui::render::DrawMap;
 use map_gui::tools::grey_out_map;
 use map_model::{EditCmd, IntersectionID, LaneID, MapEdits};
 use widgetry::mapspace::ToggleZoomed;
-use widgetry::tools::{ChooseSomething, ColorLegend, PopupMsg};
+use widgetry::tools::{
+    ChooseSomething, ColorLegend, Confirm, PopupMsg, Severity, Toast, Tour, TourStop,
+};
 use widgetry::{
-    lctrl, Choice, Color, ControlState, EventCtx, GfxCtx, HorizontalAlignment, Image, Key, Line,
-    Menu, Outcome, Panel, State, Text, TextBox, TextExt, VerticalAlignment, Widget,
+    lctrl, Choice, Color, ControlState, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Menu,
+    Outcome, Panel, State, Text, TextBox, TextExt, VerticalAlignment, Widget,
 };
 
+pub use self::annotations::{draw_hovered_note, draw_pins};
+pub use self::collab::Collaboration;
 pub use self::roads::RoadEditor;
 pub use self::routes::RouteEditor;
 pub use self::stop_signs::StopSignEditor;
@@ -24,7 +29,11 @@ use crate::common::{tool_panel, CommonState, Warping};
 use crate::debug::DebugMode;
 use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
 
+mod annotations;
+mod collab;
 mod crosswalks;
+mod gallery;
+mod managed_motorways;
 mod multiple_roads;
 mod roads;
 mod routes;
@@ -45,7 +54,7 @@ pub struct EditMode {
 
     map_edit_key: usize,
 
-    draw: ToggleZoomed,
+    draw: Rc<ToggleZoomed>,
 }
 
 impl EditMode {
@@ -89,7 +98,9 @@ impl EditMode {
                     .sim
                     .handle_live_edited_traffic_signals(&app.primary.map);
                 Transition::Pop
-            } else if app.primary.current_flags.live_map_edits {
+            } else if app.primary.current_flags.live_map_edits
+                && !transit_changed(&self.orig_edits, app.primary.map.get_edits())
+            {
                 app.primary.sim = old_sim;
                 app.primary.dirty_from_edits = true;
                 app.primary
@@ -154,6 +165,10 @@ impl State<App> for EditMode {
             return t;
         }
 
+        if let Some(state) = collab::sync(ctx, app) {
+            return Transition::Push(state);
+        }
+
         ctx.canvas_movement();
         // Restrict what can be selected.
         if ctx.redo_mouseover() {
@@ -214,6 +229,13 @@ impl State<App> for EditMode {
                             Choice::string("save this proposal as..."),
                             // TODO Disable if empty edits
                             Choice::string("share proposal"),
+                            Choice::string("publish to the community gallery"),
+                            Choice::string("browse the community gallery"),
+                            if app.session.collab.is_some() {
+                                Choice::string("leave the collaborative editing session")
+                            } else {
+                                Choice::string("join a collaborative editing session")
+                            },
                             Choice::string("delete this proposal and remove all edits")
                                 .fg(ctx.style().text_destructive_color),
                         ],
@@ -292,19 +314,49 @@ impl State<App> for EditMode {
                                     ctx, app, "--dev",
                                 ))
                             }
-                            "delete this proposal and remove all edits" => {
-                                abstio::delete_file(abstio::path_edits(
-                                    app.primary.map.get_name(),
-                                    &app.primary.map.get_edits().edits_name,
-                                ));
-                                apply_map_edits(ctx, app, app.primary.map.new_edits());
+                            "publish to the community gallery" => {
+                                Transition::Replace(gallery::PublishProposal::new_state(ctx, app))
+                            }
+                            "browse the community gallery" => {
+                                Transition::Replace(gallery::browse(ctx, app, mode))
+                            }
+                            "join a collaborative editing session" => {
+                                Transition::Replace(collab::JoinSession::new_state(ctx))
+                            }
+                            "leave the collaborative editing session" => {
+                                app.session.collab = None;
                                 Transition::Pop
                             }
+                            "delete this proposal and remove all edits" => {
+                                Transition::Replace(Confirm::new_state(
+                                    ctx,
+                                    "Delete proposal?",
+                                    vec![format!(
+                                        "\"{}\" will be deleted permanently.",
+                                        app.primary.map.get_edits().edits_name
+                                    )],
+                                    "Delete",
+                                    Box::new(|ctx, app| {
+                                        abstio::delete_file(abstio::path_edits(
+                                            app.primary.map.get_name(),
+                                            &app.primary.map.get_edits().edits_name,
+                                        ));
+                                        apply_map_edits(ctx, app, app.primary.map.new_edits());
+                                        Transition::Keep
+                                    }),
+                                ))
+                            }
                             _ => unreachable!(),
                         }),
                     ));
                 }
                 "load proposal" => {}
+                "tour changes" => {
+                    return Transition::Push(tour_changes(ctx, app));
+                }
+                "notes" => {
+                    return Transition::Push(annotations::EditAnnotations::new_state(ctx, app));
+                }
                 "undo" => {
                     let mut edits = app.primary.map.get_edits().clone();
                     let maybe_id = cmd_to_id(&edits.commands.pop().unwrap());
@@ -393,6 +445,10 @@ impl State<App> for EditMode {
         self.top_center.draw(g);
         self.changelist.draw(g);
         self.draw.draw(g);
+        if let Some(ref collab) = app.session.collab {
+            collab.draw(g);
+        }
+        draw_hovered_note(g, app);
         CommonState::draw_osd(g, app);
     }
 }
@@ -525,6 +581,11 @@ impl State<App> for SaveEdits {
                         .map
                         .must_apply_edits(edits, &mut Timer::throwaway());
                     app.primary.map.save_edits();
+                    ctx.show_toast(Toast::new(
+                        Severity::Success,
+                        "Proposal saved",
+                        vec![format!("Saved as \"{}\"", self.current_name)],
+                    ));
                     if self.reset {
                         apply_map_edits(ctx, app, app.primary.map.new_edits());
                     }
@@ -532,8 +593,16 @@ impl State<App> for SaveEdits {
                     return Transition::Pop;
                 }
                 "Discard proposal" => {
-                    apply_map_edits(ctx, app, app.primary.map.new_edits());
-                    return Transition::Pop;
+                    return Transition::Push(Confirm::new_state(
+                        ctx,
+                        "Discard proposal?",
+                        vec!["Your edits haven't been saved, and they'll be lost."],
+                        "Yes, discard",
+                        Box::new(|ctx, app| {
+                            apply_map_edits(ctx, app, app.primary.map.new_edits());
+                            Transition::Pop
+                        }),
+                    ));
                 }
                 "Cancel" => {
                     return self.cancel.take().unwrap();
@@ -635,14 +704,14 @@ impl State<App> for LoadEdits {
                             abstio::path_edits(app.primary.map.get_name(), path)
                         };
 
-                        match MapEdits::load_from_file(
+                        match MapEdits::load_from_file_with_report(
                             &app.primary.map,
                             path.clone(),
                             &mut Timer::throwaway(),
                         )
-                        .and_then(|edits| {
+                        .and_then(|(edits, report)| {
                             if self.mode.allows(&edits) {
-                                Ok(edits)
+                                Ok((edits, report))
                             } else {
                                 Err(anyhow!(
                                     "The current gameplay mode restricts edits. This proposal has \
@@ -650,12 +719,21 @@ impl State<App> for LoadEdits {
                                 ))
                             }
                         }) {
-                            Ok(edits) => {
+                            Ok((edits, report)) => {
                                 apply_map_edits(ctx, app, edits);
                                 app.primary
                                     .sim
                                     .handle_live_edited_traffic_signals(&app.primary.map);
-                                Transition::Pop
+                                if report.is_clean() {
+                                    Transition::Pop
+                                } else {
+                                    // The proposal was made for an older version of the map
+                                    Transition::Replace(PopupMsg::new_state(
+                                        ctx,
+                                        "Some changes don't match this map",
+                                        report.describe(),
+                                    ))
+                                }
                             }
                             // TODO Hack. Have to replace ourselves, because the Menu might be
                             // invalidated now that something was chosen.
@@ -756,15 +834,17 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
         timer.start("edit map");
         let effects = app.primary.map.must_apply_edits(edits, timer);
         timer.stop("edit map");
+        // Layers may depend on anything that was edited
+        app.primary.static_layers.borrow_mut().mark_dirty();
 
         if !effects.changed_roads.is_empty() || !effects.changed_intersections.is_empty() {
-            app.primary
-                .draw_map
-                .draw_all_unzoomed_roads_and_intersections = DrawMap::regenerate_unzoomed_layer(
+            app.primary.draw_map.update_unzoomed_layer(
                 ctx,
                 &app.primary.map,
                 &app.cs,
                 &app.opts,
+                &effects.changed_roads,
+                &effects.changed_intersections,
                 timer,
             );
         }
@@ -784,6 +864,12 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
             app.primary.draw_map.get_pl(pl).clear_rendering();
         }
 
+        for ts in effects.changed_transit_stops {
+            app.primary
+                .draw_map
+                .recreate_transit_stop(ctx, ts, &app.primary.map, &app.cs);
+        }
+
         if app.primary.layer.as_ref().and_then(|l| l.name()) == Some("map edits") {
             app.primary.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
         }
@@ -795,6 +881,25 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
     });
 }
 
+/// Live edits can't handle people waiting for or riding to stops that moved, so changing transit
+/// always resets the simulation.
+fn transit_changed(before: &MapEdits, after: &MapEdits) -> bool {
+    let stop_cmds = |edits: &MapEdits| -> Vec<EditCmd> {
+        edits
+            .commands
+            .iter()
+            .filter(|cmd| {
+                matches!(
+                    cmd,
+                    EditCmd::ChangeTransitStop { .. } | EditCmd::ChangeRouteStops { .. }
+                )
+            })
+            .cloned()
+            .collect()
+    };
+    stop_cmds(before) != stop_cmds(after)
+}
+
 pub fn can_edit_lane(app: &App, l: LaneID) -> bool {
     let map = &app.primary.map;
     let lane = map.get_l(l);
@@ -810,6 +915,30 @@ pub fn can_edit_lane(app: &App, l: LaneID) -> bool {
     true
 }
 
+/// A button to allow or ban U-turns at an intersection. They're always allowed at dead-ends.
+pub fn u_turns_button(ctx: &mut EventCtx, app: &App, i: IntersectionID) -> Widget {
+    let map = &app.primary.map;
+    let i = map.get_i(i);
+    ctx.style()
+        .btn_outline
+        .text(if i.allow_u_turns {
+            "ban U-turns"
+        } else {
+            "allow U-turns"
+        })
+        .disabled(i.is_deadend_for_driving(map))
+        .build_widget(ctx, "toggle U-turns")
+}
+
+pub fn toggle_u_turns(ctx: &mut EventCtx, app: &mut App, i: IntersectionID) {
+    let mut edits = app.primary.map.get_edits().clone();
+    let old = app.primary.map.get_i(i).allow_u_turns;
+    edits
+        .commands
+        .push(EditCmd::ChangeUTurns { i, old, new: !old });
+    apply_map_edits(ctx, app, edits);
+}
+
 pub fn speed_limit_choices(app: &App, preset: Option<Speed>) -> Vec<Choice<Speed>> {
     // Don't need anything higher than 70mph. Though now I kind of miss 3am drives on TX-71...
     let mut speeds = (10..=70)
@@ -880,11 +1009,19 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
                 .popup(&edits.edits_name)
                 .hotkey(lctrl(Key::P))
                 .build_widget(ctx, "manage proposals"),
-            "autosaved"
-                .text_widget(ctx)
-                .container()
-                .padding(10)
-                .bg(Color::hex("#5D9630")),
+            if app.primary.map.unsaved_edits() {
+                "not saved"
+                    .text_widget(ctx)
+                    .container()
+                    .padding(10)
+                    .bg(Color::hex("#EB3223"))
+            } else {
+                "autosaved"
+                    .text_widget(ctx)
+                    .container()
+                    .padding(10)
+                    .bg(Color::hex("#5D9630"))
+            },
         ]),
         ColorLegend::row(
             ctx,
@@ -895,7 +1032,17 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
                 edits.original_intersections.len()
             ),
         ),
+        ctx.style()
+            .btn_outline
+            .icon_text(
+                "system/assets/tools/pin.svg",
+                format!("{} notes", edits.annotations.len()),
+            )
+            .build_widget(ctx, "notes"),
     ];
+    if edits.commands.iter().any(|cmd| cmd_to_id(cmd).is_some()) {
+        col.push(ctx.style().btn_outline.text("tour changes").build_def(ctx));
+    }
 
     if edits.commands.len() > 5 {
         col.push(format!("{} more...", edits.commands.len() - 5).text_widget(ctx));
@@ -934,70 +1081,38 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
         .build(ctx)
 }
 
+fn tour_changes(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+    let map = &app.primary.map;
+    let edits = map.get_edits();
+    let mut stops = Vec::new();
+    for (idx, cmd) in edits.commands.iter().enumerate() {
+        if let Some(id) = cmd_to_id(cmd) {
+            let (summary, details) = cmd.describe(map);
+            let mut txt = Text::from(Line(format!("{}) {}", idx + 1, summary)).small_heading());
+            for line in details {
+                txt.add_line(Line(line).secondary());
+            }
+            stops.push(TourStop::new(
+                app.primary.canonical_point(id).unwrap(),
+                Some(10.0),
+                txt,
+            ));
+        }
+    }
+    Tour::new_state(ctx, &edits.edits_name, stops)
+}
+
 // TODO Ideally a Tab.
 fn cmd_to_id(cmd: &EditCmd) -> Option<ID> {
     match cmd {
         EditCmd::ChangeRoad { r, .. } => Some(ID::Road(*r)),
         EditCmd::ChangeIntersection { i, .. } => Some(ID::Intersection(*i)),
-        EditCmd::ChangeCrosswalks { i, .. } => Some(ID::Intersection(*i)),
-        EditCmd::ChangeRouteSchedule { .. } => None,
-    }
-}
-
-pub struct ConfirmDiscard {
-    panel: Panel,
-    discard: Box<dyn Fn(&mut App)>,
-}
-
-impl ConfirmDiscard {
-    pub fn new_state(ctx: &mut EventCtx, discard: Box<dyn Fn(&mut App)>) -> Box<dyn State<App>> {
-        Box::new(ConfirmDiscard {
-            discard,
-            panel: Panel::new_builder(Widget::col(vec![
-                Widget::row(vec![
-                    Image::from_path("system/assets/tools/alert.svg")
-                        .untinted()
-                        .into_widget(ctx)
-                        .container()
-                        .padding_top(6),
-                    Line("Alert").small_heading().into_widget(ctx),
-                    ctx.style().btn_close_widget(ctx),
-                ]),
-                "Are you sure you want to discard changes you made?".text_widget(ctx),
-                Widget::row(vec![
-                    ctx.style()
-                        .btn_outline
-                        .text("Cancel")
-                        .hotkey(Key::Escape)
-                        .build_def(ctx),
-                    ctx.style()
-                        .btn_solid_destructive
-                        .text("Yes, discard")
-                        .build_def(ctx),
-                ])
-                .align_right(),
-            ]))
-            .build(ctx),
-        })
-    }
-}
-
-impl State<App> for ConfirmDiscard {
-    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
-        match self.panel.event(ctx) {
-            Outcome::Clicked(x) => match x.as_ref() {
-                "close" | "Cancel" => Transition::Pop,
-                "Yes, discard" => {
-                    (self.discard)(app);
-                    Transition::Multi(vec![Transition::Pop, Transition::Pop])
-                }
-                _ => unreachable!(),
-            },
-            _ => Transition::Keep,
+        EditCmd::ChangeCrosswalks { i, .. } | EditCmd::ChangeUTurns { i, .. } => {
+            Some(ID::Intersection(*i))
         }
-    }
-
-    fn draw(&self, g: &mut GfxCtx, _: &App) {
-        self.panel.draw(g);
+        EditCmd::ChangeRouteSchedule { .. }
+        | EditCmd::ChangeRouteStops { .. }
+        | EditCmd::ChangeRouteBoarding { .. } => None,
+        EditCmd::ChangeTransitStop { id, .. } => Some(ID::Road(id.road)),
     }
 }
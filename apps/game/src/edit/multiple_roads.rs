@@ -8,13 +8,13 @@ use geom::Distance;
 use map_model::{EditRoad, MapEdits, RoadID};
 use widgetry::tools::PopupMsg;
 use widgetry::{
-    Color, Drawable, EventCtx, Fill, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
-    Panel, State, Text, TextExt, Texture, VerticalAlignment, Widget,
+    Choice, Color, Drawable, EventCtx, Fill, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, State, Text, TextExt, Texture, VerticalAlignment, Widget,
 };
 
 use crate::app::App;
 use crate::app::Transition;
-use crate::common::Warping;
+use crate::common::{NamedAreas, Warping};
 use crate::edit::apply_map_edits;
 
 pub struct SelectSegments {
@@ -27,6 +27,10 @@ pub struct SelectSegments {
     draw: Drawable,
     panel: Panel,
     selected: Option<RoadID>,
+
+    named_areas: NamedAreas,
+    /// Only select candidates inside this named area
+    area: Option<String>,
 }
 
 impl SelectSegments {
@@ -72,6 +76,9 @@ impl SelectSegments {
             draw: Drawable::empty(ctx),
             panel: Panel::empty(ctx),
             selected: None,
+
+            named_areas: NamedAreas::load(app),
+            area: None,
         };
         state.recalculate(ctx, app);
         Box::new(state)
@@ -134,6 +141,18 @@ impl SelectSegments {
                     .disabled(self.current.is_empty())
                     .build_def(ctx),
             ]),
+            if self.named_areas.areas.is_empty() {
+                Widget::nothing()
+            } else {
+                let mut choices = vec![Choice::new("anywhere", None)];
+                for area in &self.named_areas.areas {
+                    choices.push(Choice::new(area.name.clone(), Some(area.name.clone())));
+                }
+                Widget::row(vec![
+                    "Only select roads in".text_widget(ctx).centered_vert(),
+                    Widget::dropdown(ctx, "area", self.area.clone(), choices),
+                ])
+            },
             Widget::row(vec![
                 ctx.style()
                     .btn_solid_primary
@@ -154,8 +173,8 @@ impl SelectSegments {
 
 impl State<App> for SelectSegments {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
-        if let Outcome::Clicked(x) = self.panel.event(ctx) {
-            match x.as_ref() {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
                 "Apply" => {
                     let mut edits = std::mem::take(&mut self.base_edits);
                     for r in &self.current {
@@ -163,6 +182,8 @@ impl State<App> for SelectSegments {
                             .commands
                             .push(app.primary.map.edit_road_cmd(*r, |new| {
                                 new.lanes_ltr = self.new_state.lanes_ltr.clone();
+                                new.lane_restrictions = self.new_state.lane_restrictions.clone();
+                                new.reversible_lanes = self.new_state.reversible_lanes.clone();
                             }));
                     }
                     apply_map_edits(ctx, app, edits);
@@ -202,7 +223,27 @@ impl State<App> for SelectSegments {
                     ));
                 }
                 _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                self.area = self.panel.dropdown_value("area");
+                self.current = match self
+                    .area
+                    .as_ref()
+                    .and_then(|name| self.named_areas.get(name))
+                {
+                    Some(area) => {
+                        let roads = area.roads(&app.primary.map);
+                        self.candidates
+                            .iter()
+                            .filter(|r| roads.contains(r))
+                            .cloned()
+                            .collect()
+                    }
+                    None => self.candidates.clone(),
+                };
+                self.recalculate(ctx, app);
             }
+            _ => {}
         }
 
         if ctx.redo_mouseover() {
@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 
 use crate::ID;
-use geom::{Bounds, CornerRadii, Distance, Polygon, Pt2D, UnitFmt};
+use geom::{Bounds, CornerRadii, Distance, Duration, Polygon, Pt2D, Time, UnitFmt};
 use map_gui::render::{Renderable, OUTLINE_THICKNESS};
 use map_model::{
-    osm, BufferType, Direction, EditCmd, EditRoad, LaneID, LaneSpec, LaneType, MapEdits, Road,
-    RoadID,
+    osm, BufferType, Direction, EditCmd, EditRoad, Lane, LaneID, LaneRestriction,
+    LaneRestrictionKind, LaneReversalSchedule, LaneSpec, LaneType, Map, MapEdits, RampMeter, Road,
+    RoadID, TrafficCalming,
 };
 use widgetry::tools::PopupMsg;
 use widgetry::{
@@ -16,6 +17,7 @@ use widgetry::{
 
 use crate::app::{App, Transition};
 use crate::common::Warping;
+use crate::edit::managed_motorways::ManagedMotorwayEditor;
 use crate::edit::zones::ZoneEditor;
 use crate::edit::{apply_map_edits, can_edit_lane, speed_limit_choices};
 
@@ -282,7 +284,7 @@ impl State<App> for RoadEditor {
                     panels_need_recalc = true;
                 } else if x == "delete lane" {
                     return self.modify_current_lane(ctx, app, None, |new, idx| {
-                        new.lanes_ltr.remove(idx);
+                        new.remove_lane(idx);
                     });
                 } else if x == "flip direction" {
                     return self.modify_current_lane(ctx, app, Some(0), |new, idx| {
@@ -307,6 +309,10 @@ impl State<App> for RoadEditor {
                     return self.modify_current_lane(ctx, app, Some(0), |new, idx| {
                         new.lanes_ltr[idx].lt = lt;
                         new.lanes_ltr[idx].width = width;
+                        if lt != LaneType::Driving {
+                            new.lane_restrictions.remove(&idx);
+                            new.reversible_lanes.remove(&idx);
+                        }
                     });
                 } else if let Some(lt) = x.strip_prefix("add ") {
                     let lt = if lt == "buffer" {
@@ -342,6 +348,7 @@ impl State<App> for RoadEditor {
                             .unwrap(),
                         app.primary.map.get_config().driving_side,
                     );
+                    new.lane_inserted(idx);
                     edits.commands.push(EditCmd::ChangeRoad {
                         r: self.r,
                         old,
@@ -361,18 +368,27 @@ impl State<App> for RoadEditor {
                         apply_map_edits(ctx, app, edits);
                     }
                     return Transition::Replace(ZoneEditor::new_state(ctx, app, self.r));
+                } else if x == "Variable speed limit" {
+                    if let Some(edits) = self.compress_edits(app) {
+                        apply_map_edits(ctx, app, edits);
+                    }
+                    return Transition::Replace(ManagedMotorwayEditor::new_state(ctx, app, self.r));
                 } else {
                     unreachable!()
                 }
             }
             Outcome::Changed(x) => match x.as_ref() {
-                "speed limit" => {
-                    let speed_limit = self.main_panel.dropdown_value("speed limit");
-
+                "speed limit" | "traffic calming" | "ramp meter" => {
                     let mut edits = app.primary.map.get_edits().clone();
                     let old = app.primary.map.get_r_edit(self.r);
                     let mut new = old.clone();
-                    new.speed_limit = speed_limit;
+                    if x == "speed limit" {
+                        new.speed_limit = self.main_panel.dropdown_value("speed limit");
+                    } else if x == "traffic calming" {
+                        new.traffic_calming = self.main_panel.dropdown_value("traffic calming");
+                    } else {
+                        new.ramp_meter = self.main_panel.dropdown_value("ramp meter");
+                    }
                     edits.commands.push(EditCmd::ChangeRoad {
                         r: self.r,
                         old,
@@ -394,6 +410,33 @@ impl State<App> for RoadEditor {
                         new.lanes_ltr[idx].width = width;
                     });
                 }
+                "lane restriction" | "restriction hours" => {
+                    let restriction = self
+                        .main_panel
+                        .dropdown_value::<Option<LaneRestrictionKind>, _>("lane restriction")
+                        .map(|kind| LaneRestriction {
+                            kind,
+                            hours: self.main_panel.dropdown_value("restriction hours"),
+                        });
+                    return self.modify_current_lane(ctx, app, Some(0), move |new, idx| {
+                        if let Some(ref restriction) = restriction {
+                            new.lane_restrictions.insert(idx, restriction.clone());
+                        } else {
+                            new.lane_restrictions.remove(&idx);
+                        }
+                    });
+                }
+                "reversible schedule" => {
+                    let schedule: Option<LaneReversalSchedule> =
+                        self.main_panel.dropdown_value("reversible schedule");
+                    return self.modify_current_lane(ctx, app, Some(0), move |new, idx| {
+                        if let Some(ref schedule) = schedule {
+                            new.reversible_lanes.insert(idx, schedule.clone());
+                        } else {
+                            new.reversible_lanes.remove(&idx);
+                        }
+                    });
+                }
                 "width custom" => {
                     let width = self.main_panel.spinner("width custom");
                     return self.modify_current_lane(ctx, app, Some(0), |new, idx| {
@@ -444,8 +487,7 @@ impl State<App> for RoadEditor {
                     edits
                         .commands
                         .push(app.primary.map.edit_road_cmd(self.r, |new| {
-                            let spec = new.lanes_ltr.remove(old_idx);
-                            new.lanes_ltr.insert(new_idx, spec);
+                            new.move_lane(old_idx, new_idx);
                         }));
                     apply_map_edits(ctx, app, edits);
                     self.redo_stack.clear();
@@ -858,6 +900,14 @@ fn make_main_panel(
                 ])
                 .section(ctx),
             ]),
+            if lane.lane_type == LaneType::Driving {
+                Widget::col(vec![
+                    restriction_row(ctx, lane.get_restriction(map)),
+                    reversible_row(ctx, lane, map),
+                ])
+            } else {
+                Widget::nothing()
+            },
         ])
     } else {
         Widget::nothing()
@@ -901,7 +951,7 @@ fn make_main_panel(
         Widget::col(vec![line1, line2])
     };
 
-    let road_settings = Widget::row(vec![
+    let mut road_settings = vec![
         total_width,
         Line("Speed limit")
             .secondary()
@@ -914,12 +964,63 @@ fn make_main_panel(
             speed_limit_choices(app, Some(road.speed_limit)),
         )
         .centered_vert(),
+    ];
+    if road.is_speed_limit_inferred() {
+        // OSM doesn't say, so this came from the type of road
+        road_settings.push(
+            Line("(guessed)")
+                .secondary()
+                .into_widget(ctx)
+                .centered_vert(),
+        );
+    }
+    road_settings.push(
+        Line("Traffic calming")
+            .secondary()
+            .into_widget(ctx)
+            .centered_vert(),
+    );
+    road_settings.push(
+        Widget::dropdown(
+            ctx,
+            "traffic calming",
+            road.traffic_calming.clone(),
+            traffic_calming_choices(road),
+        )
+        .centered_vert(),
+    );
+    if road.is_on_ramp(map) {
+        road_settings.push(
+            Line("Ramp meter")
+                .secondary()
+                .into_widget(ctx)
+                .centered_vert(),
+        );
+        road_settings.push(
+            Widget::dropdown(
+                ctx,
+                "ramp meter",
+                road.ramp_meter.clone(),
+                ramp_meter_choices(road),
+            )
+            .centered_vert(),
+        );
+    }
+    road_settings.push(
         ctx.style()
             .btn_outline
             .text("Access restrictions")
             .build_def(ctx)
             .centered_vert(),
-    ]);
+    );
+    road_settings.push(
+        ctx.style()
+            .btn_outline
+            .text("Variable speed limit")
+            .build_def(ctx)
+            .centered_vert(),
+    );
+    let road_settings = Widget::row(road_settings);
 
     Panel::new_builder(
         Widget::custom_col(vec![
@@ -947,6 +1048,85 @@ fn make_main_panel(
     .build_custom(ctx)
 }
 
+fn restriction_row(ctx: &mut EventCtx, current: Option<&LaneRestriction>) -> Widget {
+    let current_hours = current.map(|r| r.hours.clone()).unwrap_or_default();
+    let mut hour_choices = vec![
+        Choice::new("all day", Vec::new()),
+        Choice::new("7 - 9am", vec![hours(7, 9)]),
+        Choice::new("4 - 6pm", vec![hours(16, 18)]),
+        Choice::new("7 - 9am and 4 - 6pm", vec![hours(7, 9), hours(16, 18)]),
+    ];
+    // Edits might've come from somewhere else with different hours
+    if !hour_choices.iter().any(|c| c.data == current_hours) {
+        hour_choices.push(Choice::new("other hours", current_hours.clone()));
+    }
+
+    Widget::row(vec![
+        Line("Restriction")
+            .secondary()
+            .into_widget(ctx)
+            .centered_vert(),
+        Widget::dropdown(
+            ctx,
+            "lane restriction",
+            current.map(|r| r.kind),
+            vec![
+                Choice::new("none", None),
+                Choice::new("bus only", Some(LaneRestrictionKind::BusOnly)),
+                Choice::new("HOV 2+", Some(LaneRestrictionKind::HighOccupancy(2))),
+                Choice::new("HOV 3+", Some(LaneRestrictionKind::HighOccupancy(3))),
+                Choice::new("turn only", Some(LaneRestrictionKind::TurnOnly)),
+            ],
+        ),
+        Widget::dropdown(ctx, "restriction hours", current_hours, hour_choices),
+    ])
+    .section(ctx)
+}
+
+fn reversible_row(ctx: &mut EventCtx, lane: &Lane, map: &Map) -> Widget {
+    let current = lane.get_reversal_schedule(map).cloned();
+    let normal = lane.dir;
+    let reversed_during = |ranges: &[(usize, usize)]| {
+        Some(LaneReversalSchedule {
+            switches: ranges
+                .iter()
+                .flat_map(|(start, end)| {
+                    let (start, end) = hours(*start, *end);
+                    [(start, normal.opposite()), (end, normal)]
+                })
+                .collect(),
+        })
+    };
+    let mut choices = vec![
+        Choice::new("never", None),
+        Choice::new("reversed 6 - 10am", reversed_during(&[(6, 10)])),
+        Choice::new("reversed 3 - 7pm", reversed_during(&[(15, 19)])),
+        Choice::new(
+            "reversed 6 - 10am and 3 - 7pm",
+            reversed_during(&[(6, 10), (15, 19)]),
+        ),
+    ];
+    if !choices.iter().any(|c| c.data == current) {
+        choices.push(Choice::new("other schedule", current.clone()));
+    }
+
+    Widget::row(vec![
+        Line("Reversible")
+            .secondary()
+            .into_widget(ctx)
+            .centered_vert(),
+        Widget::dropdown(ctx, "reversible schedule", current, choices),
+    ])
+    .section(ctx)
+}
+
+fn hours(start: usize, end: usize) -> (Time, Time) {
+    (
+        Time::START_OF_DAY + Duration::hours(start),
+        Time::START_OF_DAY + Duration::hours(end),
+    )
+}
+
 fn selected_lane_bg(ctx: &EventCtx) -> Color {
     ctx.style().btn_tab.bg_disabled
 }
@@ -1056,6 +1236,60 @@ fn fade_irrelevant(app: &App, r: RoadID) -> GeomBatch {
     }
 }
 
+/// Replace all traffic calming along a road with one kind of device, spaced out evenly.
+fn traffic_calming_choices(road: &Road) -> Vec<Choice<Vec<(Distance, TrafficCalming)>>> {
+    let length = road.length();
+    // About one device per block is typical
+    let count = (length / Distance::meters(100.0)).floor().max(1.0);
+    let spaced_out = |device: TrafficCalming| {
+        (1..=count as usize)
+            .map(|i| (length * (i as f64) / (count + 1.0), device))
+            .collect::<Vec<_>>()
+    };
+
+    let mut choices = vec![
+        Choice::new("none", Vec::new()),
+        Choice::new("speed humps", spaced_out(TrafficCalming::SpeedHump)),
+        Choice::new(
+            "raised crossings",
+            spaced_out(TrafficCalming::RaisedCrossing),
+        ),
+        Choice::new("chicanes", spaced_out(TrafficCalming::Chicane)),
+    ];
+    // Keep whatever's mapped in OSM as an option
+    if !choices.iter().any(|c| c.data == road.traffic_calming) {
+        choices.push(Choice::new("as mapped", road.traffic_calming.clone()));
+    }
+    choices
+}
+
+fn ramp_meter_choices(road: &Road) -> Vec<Choice<Option<RampMeter>>> {
+    let fixed = |secs: f64| {
+        Some(RampMeter {
+            cycle: Duration::seconds(secs),
+            target_density: None,
+        })
+    };
+    let mut choices = vec![
+        Choice::new("none", None),
+        Choice::new("one vehicle every 4s", fixed(4.0)),
+        Choice::new("one vehicle every 8s", fixed(8.0)),
+        Choice::new("one vehicle every 12s", fixed(12.0)),
+        Choice::new(
+            "responsive to motorway traffic",
+            Some(RampMeter {
+                cycle: Duration::seconds(4.0),
+                // Around where motorway flow starts to break down
+                target_density: Some(25),
+            }),
+        ),
+    ];
+    if !choices.iter().any(|c| c.data == road.ramp_meter) {
+        choices.push(Choice::new("other timing", road.ramp_meter.clone()));
+    }
+    choices
+}
+
 fn draw_drop_position(app: &App, r: RoadID, from: usize, to: usize) -> GeomBatch {
     let mut batch = GeomBatch::new();
     if from == to {
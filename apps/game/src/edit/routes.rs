@@ -1,52 +1,472 @@
-use geom::{Duration, Time};
-use map_model::{EditCmd, TransitRouteID};
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+
+use abstutil::{prettyprint_usize, Counter};
+use geom::{Circle, Distance, Duration, Time};
+use map_model::connectivity::{all_walking_costs_from, Spot, WalkingOptions};
+use map_model::{
+    BoardingConfig, EditCmd, EditTransitStop, FarePayment, Map, Path, PathRequest, Pathfinder,
+    Position, StopLayout, TransitRoute, TransitRouteID, TransitStop, TransitStopID,
+};
+use sim::TIME_TO_MERGE_FROM_PULL_OUT;
+use widgetry::tools::PopupMsg;
 use widgetry::{
-    EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner, State, TextExt,
-    VerticalAlignment, Widget,
+    Choice, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Panel, Spinner, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
 };
 
 use crate::app::App;
 use crate::app::Transition;
 use crate::edit::apply_map_edits;
+use crate::ID;
+
+/// Stops serve buildings within this walking time
+const WALKING_ACCESS: Duration = Duration::const_seconds(5.0 * 60.0);
 
 pub struct RouteEditor {
     panel: Panel,
     route: TransitRouteID,
+    mode: Mode,
+    /// The map's pathfinding isn't updated until leaving edit mode, so use a separate pathfinder
+    /// to check the route. Only stops change here, not the roads the route can use.
+    pathfinder: Pathfinder,
+    /// How the route performed before any edits. None if it couldn't be calculated.
+    baseline: Option<RouteStats>,
+    ridership: Ridership,
+    draw_route: Drawable,
+    hovering: Option<Position>,
+}
+
+enum Mode {
+    Browse,
+    AddStop,
+    MoveStop(TransitStopID),
 }
 
 impl RouteEditor {
     pub fn new_state(ctx: &mut EventCtx, app: &mut App, id: TransitRouteID) -> Box<dyn State<App>> {
         app.primary.current_selection = None;
 
-        let route = app.primary.map.get_tr(id);
-        Box::new(RouteEditor {
-            panel: Panel::new_builder(Widget::col(vec![
-                Widget::row(vec![
-                    Line("Route editor").small_heading().into_widget(ctx),
-                    ctx.style().btn_close_widget(ctx),
-                ]),
-                Line(&route.long_name).into_widget(ctx),
-                // TODO This UI needs design, just something to start plumbing the edits
-                Widget::row(vec![
-                    "Frequency".text_widget(ctx),
-                    Spinner::widget(
-                        ctx,
-                        "freq_mins",
-                        (Duration::minutes(1), Duration::hours(2)),
-                        Duration::hours(1),
-                        Duration::minutes(1),
-                    ),
-                ]),
-                ctx.style()
-                    .btn_solid_primary
-                    .text("Apply")
-                    .hotkey(Key::Enter)
-                    .build_def(ctx),
-            ]))
-            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
-            .build(ctx),
+        let map = &app.primary.map;
+        let pathfinder = ctx.loading_screen("prepare to edit route", |_, timer| {
+            Pathfinder::new_dijkstra(
+                map,
+                map.routing_params().clone(),
+                vec![map.get_tr(id).route_type],
+                timer,
+            )
+        });
+        let ridership = Ridership::new(app, id);
+        let baseline = match unedited_map(app) {
+            Some(unedited) => RouteStats::new(unedited, unedited.get_tr(id), &ridership, |req| {
+                unedited.pathfind(req)
+            }),
+            None => RouteStats::new(map, map.get_tr(id), &ridership, |req| {
+                pathfind(&pathfinder, map, req)
+            }),
+        }
+        .ok();
+
+        let mut editor = RouteEditor {
+            panel: Panel::empty(ctx),
             route: id,
-        })
+            mode: Mode::Browse,
+            pathfinder,
+            baseline,
+            ridership,
+            draw_route: Drawable::empty(ctx),
+            hovering: None,
+        };
+        editor.recalculate(ctx, app);
+        Box::new(editor)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let route = map.get_tr(self.route);
+
+        let mut batch = GeomBatch::new();
+        if let Ok(paths) = route.all_paths_using(map, |req| pathfind(&self.pathfinder, map, req)) {
+            for path in paths {
+                if let Some(pl) = path.trace(map) {
+                    batch.push(
+                        Color::CYAN.alpha(0.8),
+                        pl.make_polygons(Distance::meters(2.0)),
+                    );
+                }
+            }
+        }
+        for ts in &route.stops {
+            let color = match self.mode {
+                Mode::MoveStop(moving) if moving == *ts => Color::RED,
+                _ => Color::BLUE,
+            };
+            batch.push(
+                color,
+                Circle::new(map.get_ts(*ts).sidewalk_pos.pt(map), Distance::meters(3.0))
+                    .to_polygon(),
+            );
+        }
+        self.draw_route = ctx.upload(batch);
+
+        let mut stops = Vec::new();
+        for (idx, ts) in route.stops.iter().enumerate() {
+            let ts = map.get_ts(*ts);
+            stops.push(Widget::row(vec![
+                format!("{}. {}", idx + 1, ts.name)
+                    .text_widget(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_plain
+                    .text(match ts.layout {
+                        StopLayout::InLane => "in-lane",
+                        StopLayout::PullOut => "pull-out",
+                    })
+                    .tooltip("Change whether buses stop in the travel lane or pull out of it")
+                    .disabled(ts.is_train_stop)
+                    .build_widget(ctx, format!("change layout of stop {}", idx)),
+                ctx.style()
+                    .btn_plain
+                    .text("move")
+                    .build_widget(ctx, format!("move stop {}", idx)),
+                ctx.style()
+                    .btn_plain
+                    .text("remove")
+                    .disabled(route.stops.len() <= 2)
+                    .build_widget(ctx, format!("remove stop {}", idx)),
+            ]));
+        }
+
+        let instructions = match self.mode {
+            Mode::Browse => ctx.style().btn_outline.text("Add a stop").build_def(ctx),
+            Mode::AddStop => Widget::col(vec![
+                "Click a sidewalk to add a stop. It'll be served wherever it adds the least detour."
+                    .text_widget(ctx),
+                ctx.style().btn_outline.text("Cancel").build_def(ctx),
+            ]),
+            Mode::MoveStop(_) => Widget::col(vec![
+                "Click a sidewalk along the same road to move the stop".text_widget(ctx),
+                ctx.style().btn_outline.text("Cancel").build_def(ctx),
+            ]),
+        };
+
+        self.panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Route editor").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Line(&route.long_name).into_widget(ctx),
+            // TODO This UI needs design, just something to start plumbing the edits
+            Widget::row(vec![
+                "Frequency".text_widget(ctx),
+                Spinner::widget(
+                    ctx,
+                    "freq_mins",
+                    (Duration::minutes(1), Duration::hours(2)),
+                    Duration::hours(1),
+                    Duration::minutes(1),
+                ),
+            ]),
+            ctx.style()
+                .btn_solid_primary
+                .text("Apply")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+            Line("Vehicles").small_heading().into_widget(ctx),
+            Widget::row(vec![
+                "Fare payment".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "fare payment",
+                    route.boarding.fare_payment,
+                    vec![
+                        Choice::new("cash on board", FarePayment::Cash),
+                        Choice::new("smart card on board", FarePayment::SmartCard),
+                        Choice::new("before boarding", FarePayment::OffBoard),
+                    ],
+                ),
+            ]),
+            Widget::row(vec![
+                "Doors".text_widget(ctx).centered_vert(),
+                Spinner::widget(ctx, "doors", (1, 6), route.boarding.num_doors, 1),
+            ]),
+            Toggle::checkbox(ctx, "low floor", None, route.boarding.low_floor),
+            Line("Stops").small_heading().into_widget(ctx),
+            Widget::col(stops),
+            instructions,
+            Line("Impact").small_heading().into_widget(ctx),
+            self.impact(ctx, app),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+    }
+
+    fn impact(&self, ctx: &EventCtx, app: &App) -> Widget {
+        let map = &app.primary.map;
+        let current = match RouteStats::new(map, map.get_tr(self.route), &self.ridership, |req| {
+            pathfind(&self.pathfinder, map, req)
+        }) {
+            Ok(stats) => stats,
+            Err(err) => {
+                return format!("The route is broken: {}", err).text_widget(ctx);
+            }
+        };
+        let baseline = match self.baseline {
+            Some(ref stats) => stats,
+            None => &current,
+        };
+
+        let units = &app.opts.units;
+        let mut names = Text::from(Line("Per run").secondary());
+        let mut before = Text::from(Line("Before").secondary());
+        let mut after = Text::from(Line("Now").secondary());
+        for (name, x, y) in [
+            (
+                "Stops",
+                prettyprint_usize(baseline.num_stops),
+                prettyprint_usize(current.num_stops),
+            ),
+            (
+                "Dwell time",
+                baseline.dwell_time.to_string(units),
+                current.dwell_time.to_string(units),
+            ),
+            (
+                "Merging from pull-outs",
+                baseline.merge_time().to_string(units),
+                current.merge_time().to_string(units),
+            ),
+            (
+                "Driving time",
+                baseline.driving_time.to_string(units),
+                current.driving_time.to_string(units),
+            ),
+            (
+                "Total time",
+                baseline.total_time().to_string(units),
+                current.total_time().to_string(units),
+            ),
+            (
+                "Buildings within a 5 minute walk",
+                prettyprint_usize(baseline.buildings_served),
+                prettyprint_usize(current.buildings_served),
+            ),
+        ] {
+            names.add_line(name);
+            before.add_line(x);
+            after.add_line(y);
+        }
+        Widget::col(vec![
+            Widget::custom_row(
+                vec![names, before, after]
+                    .into_iter()
+                    .map(|txt| txt.into_widget(ctx).margin_right(16))
+                    .collect(),
+            ),
+            Text::from(
+                Line(
+                    "Dwell time assumes each stop has as many passengers as it did in the \
+                     simulation so far",
+                )
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 30)
+            .into_widget(ctx),
+        ])
+    }
+
+    /// Applies the commands, then makes sure every affected route can still reach all of its
+    /// stops. If not, reverts.
+    fn try_edit(&mut self, ctx: &mut EventCtx, app: &mut App, cmds: Vec<EditCmd>) -> Transition {
+        let mut changed_stops = BTreeSet::new();
+        for cmd in &cmds {
+            if let EditCmd::ChangeTransitStop { id, .. } = cmd {
+                changed_stops.insert(*id);
+            }
+        }
+
+        let orig_edits = app.primary.map.get_edits().clone();
+        let mut edits = orig_edits.clone();
+        edits.commands.extend(cmds);
+        apply_map_edits(ctx, app, edits);
+
+        let map = &app.primary.map;
+        let route_type = map.get_tr(self.route).route_type;
+        let mut problems = Vec::new();
+        for route in map.all_transit_routes() {
+            if route.route_type != route_type
+                || (route.id != self.route
+                    && !route.stops.iter().any(|ts| changed_stops.contains(ts)))
+            {
+                continue;
+            }
+            if let Err(err) = route.all_paths_using(map, |req| pathfind(&self.pathfinder, map, req))
+            {
+                problems.push(format!("{}: {}", route.long_name, err));
+            }
+        }
+
+        if !problems.is_empty() {
+            apply_map_edits(ctx, app, orig_edits);
+            self.recalculate(ctx, app);
+            return Transition::Push(PopupMsg::new_state(
+                ctx,
+                "Routes can't reach these stops",
+                problems,
+            ));
+        }
+        self.recalculate(ctx, app);
+        Transition::Keep
+    }
+
+    fn place_stop(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        sidewalk_pos: Position,
+    ) -> Transition {
+        let map = &app.primary.map;
+        let driving_pos = match TransitStop::find_driving_pos(sidewalk_pos, map) {
+            Some(pos) => pos,
+            None => {
+                return Transition::Push(PopupMsg::new_state(
+                    ctx,
+                    "Error",
+                    vec!["Buses can't use this road"],
+                ));
+            }
+        };
+
+        let cmds = match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => unreachable!(),
+            Mode::MoveStop(id) => {
+                let old = map.get_ts_edit(id).unwrap();
+                vec![EditCmd::ChangeTransitStop {
+                    id,
+                    old: Some(old),
+                    new: Some(EditTransitStop {
+                        sidewalk_pos,
+                        ..old
+                    }),
+                }]
+            }
+            Mode::AddStop => {
+                let id = map.new_transit_stop_id(sidewalk_pos.lane().road);
+                let route = map.get_tr(self.route);
+                let mut new_stops = route.stops.clone();
+                new_stops.insert(self.best_insertion(map, route, driving_pos), id);
+                vec![
+                    EditCmd::ChangeTransitStop {
+                        id,
+                        old: None,
+                        new: Some(EditTransitStop {
+                            sidewalk_pos,
+                            layout: StopLayout::InLane,
+                        }),
+                    },
+                    EditCmd::ChangeRouteStops {
+                        id: self.route,
+                        old: route.stops.clone(),
+                        new: new_stops,
+                    },
+                ]
+            }
+        };
+        self.try_edit(ctx, app, cmds)
+    }
+
+    fn change_layout(&mut self, ctx: &mut EventCtx, app: &mut App, idx: usize) -> Transition {
+        let map = &app.primary.map;
+        let id = map.get_tr(self.route).stops[idx];
+        let old = map.get_ts_edit(id).unwrap();
+        let layout = match old.layout {
+            StopLayout::InLane => StopLayout::PullOut,
+            StopLayout::PullOut => StopLayout::InLane,
+        };
+        self.try_edit(
+            ctx,
+            app,
+            vec![EditCmd::ChangeTransitStop {
+                id,
+                old: Some(old),
+                new: Some(EditTransitStop { layout, ..old }),
+            }],
+        )
+    }
+
+    fn change_boarding(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        let old = app.primary.map.get_tr(self.route).boarding;
+        let new = BoardingConfig {
+            fare_payment: self.panel.dropdown_value("fare payment"),
+            low_floor: self.panel.is_checked("low floor"),
+            num_doors: self.panel.spinner("doors"),
+        };
+        if old == new {
+            return;
+        }
+        let mut edits = app.primary.map.get_edits().clone();
+        edits.commands.push(EditCmd::ChangeRouteBoarding {
+            id: self.route,
+            old,
+            new,
+        });
+        apply_map_edits(ctx, app, edits);
+        self.recalculate(ctx, app);
+    }
+
+    fn remove_stop(&mut self, ctx: &mut EventCtx, app: &mut App, idx: usize) -> Transition {
+        let map = &app.primary.map;
+        let route = map.get_tr(self.route);
+        let mut new_stops = route.stops.clone();
+        let id = new_stops.remove(idx);
+        let mut cmds = vec![EditCmd::ChangeRouteStops {
+            id: self.route,
+            old: route.stops.clone(),
+            new: new_stops,
+        }];
+
+        // If the stop was created by edits and nothing else serves it, delete it entirely
+        let created = map.get_edits().original_transit_stops.get(&id) == Some(&None);
+        if created
+            && !map
+                .all_transit_routes()
+                .iter()
+                .any(|r| r.id != self.route && r.stops.contains(&id))
+        {
+            cmds.push(EditCmd::ChangeTransitStop {
+                id,
+                old: map.get_ts_edit(id),
+                new: None,
+            });
+        }
+        self.try_edit(ctx, app, cmds)
+    }
+
+    /// Where to insert a new stop in the route to add the least detour
+    fn best_insertion(&self, map: &Map, route: &TransitRoute, pos: Position) -> usize {
+        let cost = |from: Position, to: Position| {
+            self.pathfinder
+                .pathfind_v2(PathRequest::vehicle(from, to, route.route_type), map)
+                .map(|path| path.get_cost())
+        };
+        let detour = |idx: usize| {
+            let before = if idx == 0 {
+                Position::start(route.start)
+            } else {
+                map.get_ts(route.stops[idx - 1]).driving_pos
+            };
+            if idx == route.stops.len() {
+                return cost(before, pos);
+            }
+            let after = map.get_ts(route.stops[idx]).driving_pos;
+            Some(cost(before, pos)? + cost(pos, after)? - cost(before, after)?)
+        };
+        (0..=route.stops.len())
+            .filter_map(|idx| detour(idx).map(|x| (x, idx)))
+            .min()
+            .map(|(_, idx)| idx)
+            .unwrap_or(route.stops.len())
     }
 }
 
@@ -54,8 +474,37 @@ impl State<App> for RouteEditor {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         ctx.canvas_movement();
 
-        if let Outcome::Clicked(x) = self.panel.event(ctx) {
-            match x.as_ref() {
+        if ctx.redo_mouseover() {
+            self.hovering = None;
+            if !matches!(self.mode, Mode::Browse) {
+                app.recalculate_current_selection(ctx);
+                if let (Some(ID::Lane(l)), Some(pt)) = (
+                    app.primary.current_selection.take(),
+                    ctx.canvas.get_cursor_in_map_space(),
+                ) {
+                    let lane = app.primary.map.get_l(l);
+                    let same_road = match self.mode {
+                        Mode::MoveStop(ts) => ts.road == l.road,
+                        _ => true,
+                    };
+                    if lane.is_walkable() && same_road {
+                        let pl = &lane.lane_center_pts;
+                        if let Some((dist, _)) = pl.dist_along_of_point(pl.project_pt(pt)) {
+                            self.hovering = Some(Position::new(l, dist));
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(pos) = self.hovering {
+            if ctx.normal_left_click() {
+                self.hovering = None;
+                return self.place_stop(ctx, app, pos);
+            }
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
                 "close" => {
                     return Transition::Pop;
                 }
@@ -78,14 +527,175 @@ impl State<App> for RouteEditor {
 
                     return Transition::Pop;
                 }
-                _ => unreachable!(),
+                "Add a stop" => {
+                    self.mode = Mode::AddStop;
+                    self.recalculate(ctx, app);
+                }
+                "Cancel" => {
+                    self.mode = Mode::Browse;
+                    self.hovering = None;
+                    self.recalculate(ctx, app);
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("move stop ") {
+                        let idx = idx.parse::<usize>().unwrap();
+                        self.mode = Mode::MoveStop(app.primary.map.get_tr(self.route).stops[idx]);
+                        self.recalculate(ctx, app);
+                    } else if let Some(idx) = x.strip_prefix("change layout of stop ") {
+                        let idx = idx.parse::<usize>().unwrap();
+                        return self.change_layout(ctx, app, idx);
+                    } else if let Some(idx) = x.strip_prefix("remove stop ") {
+                        let idx = idx.parse::<usize>().unwrap();
+                        return self.remove_stop(ctx, app, idx);
+                    } else {
+                        unreachable!()
+                    }
+                }
+            },
+            Outcome::Changed(x) => {
+                if x != "freq_mins" {
+                    self.change_boarding(ctx, app);
+                }
             }
+            _ => {}
         }
 
         Transition::Keep
     }
 
-    fn draw(&self, g: &mut GfxCtx, _: &App) {
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw_route);
+        if let Some(pos) = self.hovering {
+            g.draw_polygon(
+                Color::GREEN,
+                Circle::new(pos.pt(&app.primary.map), Distance::meters(3.0)).to_polygon(),
+            );
+        }
         self.panel.draw(g);
     }
 }
+
+/// Summarizes one version of a route, to compare the effects of changing its stops.
+struct RouteStats {
+    num_stops: usize,
+    num_pull_outs: usize,
+    dwell_time: Duration,
+    /// From where a vehicle appears to where it vanishes, not counting time spent at stops or
+    /// delays from traffic
+    driving_time: Duration,
+    /// Buildings within a short walk of some stop
+    buildings_served: usize,
+}
+
+impl RouteStats {
+    fn new<F: Fn(PathRequest) -> Result<Path>>(
+        map: &Map,
+        route: &TransitRoute,
+        ridership: &Ridership,
+        pathfind: F,
+    ) -> Result<RouteStats> {
+        let mut driving_time = Duration::ZERO;
+        for path in route.all_paths_using(map, pathfind)? {
+            driving_time += path.estimate_duration(map, None);
+        }
+
+        let starts = route
+            .stops
+            .iter()
+            .map(|ts| {
+                Spot::DirectedRoad(
+                    map.get_l(map.get_ts(*ts).sidewalk_pos.lane())
+                        .get_directed_parent(),
+                )
+            })
+            .collect();
+        let buildings_served =
+            all_walking_costs_from(map, starts, WALKING_ACCESS, WalkingOptions::default()).len();
+
+        Ok(RouteStats {
+            num_stops: route.stops.len(),
+            num_pull_outs: route
+                .stops
+                .iter()
+                .filter(|ts| map.get_ts(**ts).layout == StopLayout::PullOut)
+                .count(),
+            dwell_time: route
+                .stops
+                .iter()
+                .map(|ts| {
+                    let (boardings, alightings) = ridership.per_run(*ts);
+                    route.boarding.dwell_time(boardings, alightings)
+                })
+                .sum(),
+            driving_time,
+            buildings_served,
+        })
+    }
+
+    /// Not counting any time spent waiting for a gap in traffic
+    fn merge_time(&self) -> Duration {
+        (self.num_pull_outs as f64) * TIME_TO_MERGE_FROM_PULL_OUT
+    }
+
+    fn total_time(&self) -> Duration {
+        self.dwell_time + self.merge_time() + self.driving_time
+    }
+}
+
+/// How many passengers boarded and alighted a route at each stop in the simulation so far
+struct Ridership {
+    runs: Counter<TransitStopID>,
+    boardings: Counter<TransitStopID>,
+    alightings: Counter<TransitStopID>,
+}
+
+impl Ridership {
+    fn new(app: &App, route: TransitRouteID) -> Ridership {
+        let analytics = app.primary.sim.get_analytics();
+        let mut runs = Counter::new();
+        for (_, _, r, ts) in &analytics.bus_arrivals {
+            if *r == route {
+                runs.inc(*ts);
+            }
+        }
+        let mut boardings = Counter::new();
+        for (ts, list) in &analytics.passengers_boarding {
+            boardings.add(*ts, list.iter().filter(|(_, r, _)| *r == route).count());
+        }
+        let mut alightings = Counter::new();
+        for (ts, list) in &analytics.passengers_alighting {
+            alightings.add(*ts, list.iter().filter(|(_, r)| *r == route).count());
+        }
+        Ridership {
+            runs,
+            boardings,
+            alightings,
+        }
+    }
+
+    /// The average (boardings, alightings) each time a vehicle serves the stop. Stops without any
+    /// data, like new ones, are assumed to have no passengers.
+    fn per_run(&self, ts: TransitStopID) -> (usize, usize) {
+        let runs = self.runs.get(ts);
+        if runs == 0 {
+            return (0, 0);
+        }
+        let avg = |cnt: usize| ((cnt as f64) / (runs as f64)).round() as usize;
+        (avg(self.boardings.get(ts)), avg(self.alightings.get(ts)))
+    }
+}
+
+fn pathfind(pathfinder: &Pathfinder, map: &Map, req: PathRequest) -> Result<Path> {
+    pathfinder
+        .pathfind_v2(req.clone(), map)
+        .ok_or_else(|| anyhow!("can't find path for {}", req))?
+        .into_v1(map)
+}
+
+/// If the map has been edited, the version before any edits
+fn unedited_map(app: &App) -> Option<&Map> {
+    app.primary
+        .unedited_map
+        .as_ref()
+        .or_else(|| app.secondary.as_ref().map(|per_map| &per_map.map))
+}
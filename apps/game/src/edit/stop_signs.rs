@@ -15,7 +15,10 @@ use widgetry::{
 use crate::app::App;
 use crate::app::Transition;
 use crate::common::CommonState;
-use crate::edit::{apply_map_edits, check_sidewalk_connectivity, TrafficSignalEditor};
+use crate::edit::{
+    apply_map_edits, check_sidewalk_connectivity, toggle_u_turns, u_turns_button,
+    TrafficSignalEditor,
+};
 use crate::sandbox::GameplayMode;
 
 // TODO For now, individual turns can't be manipulated. Banning turns could be useful, but I'm not
@@ -70,6 +73,7 @@ impl StopSignEditor {
                     .text("Change crosswalks")
                     .hotkey(Key::C)
                     .build_def(ctx),
+                u_turns_button(ctx, app, id),
             ]),
             Widget::row(vec![
                 ctx.style()
@@ -165,6 +169,15 @@ impl SimpleState<App> for StopSignEditor {
             "Change crosswalks" => Transition::Replace(
                 super::crosswalks::CrosswalkEditor::new_state(ctx, app, self.id),
             ),
+            "toggle U-turns" => {
+                toggle_u_turns(ctx, app, self.id);
+                Transition::Replace(StopSignEditor::new_state(
+                    ctx,
+                    app,
+                    self.id,
+                    self.mode.clone(),
+                ))
+            }
             _ => unreachable!(),
         }
     }
@@ -204,6 +204,8 @@ pub fn edit_entire_signal(
         .as_ref()
         .map(|x| format!("import from GMNS {}", x));
     let gmns_all = "import all traffic signals from a new GMNS timing.csv";
+    let json_all = "import all traffic signals from a JSON timing file";
+    let export_all = "export all traffic signals to a JSON timing file";
 
     let mut choices = vec![use_template.to_string()];
     if has_sidewalks {
@@ -221,6 +223,8 @@ pub fn edit_entire_signal(
         choices.push(x);
     }
     choices.push(gmns_all.to_string());
+    choices.push(json_all.to_string());
+    choices.push(export_all.to_string());
 
     ChooseSomething::new_state(
         ctx,
@@ -409,6 +413,39 @@ pub fn edit_entire_signal(
                     }
                 }),
             )),
+            x if x == json_all => Transition::Replace(FilePicker::new_state(
+                ctx,
+                None,
+                Box::new(move |ctx, app, maybe_path| {
+                    if let Ok(Some(path)) = maybe_path {
+                        // Same caveats as importing everything from GMNS
+                        Transition::Multi(vec![
+                            Transition::Pop,
+                            Transition::Pop,
+                            Transition::Push(
+                                crate::edit::traffic_signals::timing_plans::import_all(
+                                    ctx, app, &path,
+                                ),
+                            ),
+                        ])
+                    } else {
+                        Transition::Pop
+                    }
+                }),
+            )),
+            x if x == export_all => Transition::Replace(
+                match crate::edit::traffic_signals::timing_plans::export_all(app) {
+                    Ok(path) => PopupMsg::new_state(
+                        ctx,
+                        "Timing exported",
+                        vec![format!(
+                            "Timing for all traffic signals exported to {}",
+                            path
+                        )],
+                    ),
+                    Err(err) => PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()]),
+                },
+            ),
             _ => unreachable!(),
         }),
     )
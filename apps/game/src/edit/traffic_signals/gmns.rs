@@ -5,7 +5,7 @@ use std::collections::{BTreeMap, BTreeSet, HashMap};
 use anyhow::Result;
 use serde::{Deserialize, Deserializer};
 
-use geom::{Angle, Duration, LonLat, Pt2D};
+use geom::{Duration, LonLat, Pt2D};
 use map_model::{
     osm, ControlTrafficSignal, DirectedRoadID, DrivingSide, EditCmd, EditIntersection,
     IntersectionID, Map, Movement, MovementID, Stage, StageType, TurnPriority, TurnType,
@@ -13,6 +13,7 @@ use map_model::{
 use widgetry::tools::PopupMsg;
 use widgetry::{EventCtx, State};
 
+use crate::common::cardinal_direction;
 use crate::edit::apply_map_edits;
 use crate::App;
 
@@ -53,6 +54,8 @@ pub fn import(map: &Map, i: IntersectionID, path: &str) -> Result<ControlTraffic
                     protected_movements: BTreeSet::new(),
                     yield_movements: BTreeSet::new(),
                     stage_type: StageType::Fixed(Duration::seconds(rec.green_time as f64)),
+                    bike_movements: BTreeSet::new(),
+                    lead_lag: None,
                 });
             }
             std::cmp::Ordering::Less => {
@@ -317,21 +320,6 @@ impl Snapper {
     }
 }
 
-fn cardinal_direction(angle: Angle) -> &'static str {
-    // Note Y inversion, as usual
-    let deg = angle.normalized_degrees();
-    if deg >= 335.0 || deg <= 45.0 {
-        return "EB";
-    }
-    if (45.0..=135.0).contains(&deg) {
-        return "SB";
-    }
-    if (135.0..=225.0).contains(&deg) {
-        return "WB";
-    }
-    "NB"
-}
-
 // The GMNS input doesn't include crosswalks yet -- and even once it does, it's likely the two map
 // models will disagree about where sidewalks exist. Try to add all crosswalks to the stage where
 // they're compatible. Downgrade right turns from protected to permitted as needed.
@@ -3,23 +3,23 @@ use std::collections::{BTreeSet, VecDeque};
 use anyhow::Result;
 
 use abstutil::Timer;
-use geom::{Distance, Line, Polygon, Pt2D};
+use geom::{Distance, Duration, Line, Polygon, Pt2D};
 use map_gui::options::TrafficSignalStyle;
 use map_gui::render::{traffic_signal, DrawMovement, DrawOptions};
 use map_model::{
-    ControlTrafficSignal, EditCmd, EditIntersection, IntersectionID, MovementID, Stage, StageType,
-    TurnPriority,
+    ControlTrafficSignal, EditCmd, EditIntersection, IntersectionID, LeadLagInterval, MovementID,
+    Stage, StageType, TurnPriority,
 };
-use widgetry::tools::PopupMsg;
+use widgetry::tools::{Confirm, PopupMsg};
 use widgetry::{
-    include_labeled_bytes, lctrl, Color, ControlState, DragDrop, DrawBaselayer, Drawable, EventCtx,
-    GeomBatch, GeomBatchStack, GfxCtx, HorizontalAlignment, Image, Key, Line, Outcome, Panel,
-    RewriteColor, StackAxis, State, Text, TextExt, VerticalAlignment, Widget,
+    include_labeled_bytes, lctrl, Choice, Color, ControlState, DragDrop, DrawBaselayer, Drawable,
+    EventCtx, GeomBatch, GeomBatchStack, GfxCtx, HorizontalAlignment, Image, Key, Line, Outcome,
+    Panel, RewriteColor, StackAxis, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, ShowEverything, Transition};
 use crate::common::CommonState;
-use crate::edit::{apply_map_edits, ConfirmDiscard};
+use crate::edit::{apply_map_edits, toggle_u_turns, u_turns_button};
 use crate::sandbox::GameplayMode;
 
 mod edits;
@@ -27,6 +27,7 @@ mod gmns;
 mod offsets;
 mod picker;
 mod preview;
+mod timing_plans;
 
 // Welcome to one of the most overwhelmingly complicated parts of the UI...
 
@@ -37,6 +38,8 @@ pub struct TrafficSignalEditor {
     mode: GameplayMode,
     members: BTreeSet<IntersectionID>,
     current_stage: usize,
+    // Clicking movements toggles their bike signal, instead of the signal for general traffic
+    editing_bike_signals: bool,
 
     movements: Vec<DrawMovement>,
     // And the next priority to toggle to
@@ -74,10 +77,11 @@ impl TrafficSignalEditor {
         synced.apply(app);
 
         let mut editor = TrafficSignalEditor {
-            side_panel: make_side_panel(ctx, app, &members, 0),
-            top_panel: make_top_panel(ctx, app, false, false),
+            side_panel: make_side_panel(ctx, app, &members, 0, false),
+            top_panel: make_top_panel(ctx, app, &members, false, false),
             mode,
             current_stage: 0,
+            editing_bike_signals: false,
             movements: Vec::new(),
             movement_selected: None,
             draw_current: Drawable::empty(ctx),
@@ -95,12 +99,24 @@ impl TrafficSignalEditor {
 
     fn change_stage(&mut self, ctx: &mut EventCtx, app: &App, idx: usize) {
         if self.current_stage == idx {
-            let mut new = make_side_panel(ctx, app, &self.members, self.current_stage);
+            let mut new = make_side_panel(
+                ctx,
+                app,
+                &self.members,
+                self.current_stage,
+                self.editing_bike_signals,
+            );
             new.restore(ctx, &self.side_panel);
             self.side_panel = new;
         } else {
             self.current_stage = idx;
-            self.side_panel = make_side_panel(ctx, app, &self.members, self.current_stage);
+            self.side_panel = make_side_panel(
+                ctx,
+                app,
+                &self.members,
+                self.current_stage,
+                self.editing_bike_signals,
+            );
         }
 
         self.recalc_draw_current(ctx, app);
@@ -121,7 +137,7 @@ impl TrafficSignalEditor {
         }
         bundle.apply(app);
 
-        self.top_panel = make_top_panel(ctx, app, true, false);
+        self.top_panel = make_top_panel(ctx, app, &self.members, true, false);
         self.change_stage(ctx, app, idx);
     }
 
@@ -155,6 +171,14 @@ impl TrafficSignalEditor {
                 }
                 movements.push(m);
             }
+            if self.editing_bike_signals {
+                traffic_signal::draw_bike_movements(
+                    stage,
+                    app.primary.map.get_i(*i),
+                    &mut batch,
+                    app,
+                );
+            }
             traffic_signal::draw_stage_number(
                 ctx.prerender,
                 app.primary.map.get_i(*i),
@@ -177,6 +201,52 @@ impl TrafficSignalEditor {
         self.movements = movements;
     }
 
+    fn toggle_bike_signal(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        id: MovementID,
+        pri: TurnPriority,
+    ) -> Transition {
+        let mut txt = Text::new();
+        txt.add_line(Line(if pri == TurnPriority::Protected {
+            "No bike signal this stage"
+        } else {
+            "Green bike signal"
+        }));
+        txt.add_appended(vec![
+            Line("Click").fg(ctx.style().text_hotkey_color),
+            Line(if pri == TurnPriority::Protected {
+                " to give bikes their own green"
+            } else {
+                " to remove it"
+            }),
+        ]);
+        self.tooltip = Some(txt);
+        if app
+            .per_obj
+            .left_click(ctx, format!("toggle bike signal to {:?}", pri))
+        {
+            let idx = self.current_stage;
+            self.add_new_edit(ctx, app, idx, |ts| {
+                if ts.id != id.parent {
+                    return;
+                }
+                if pri == TurnPriority::Protected {
+                    ts.bike_signals.insert(id);
+                    ts.stages[idx].bike_movements.insert(id);
+                } else {
+                    ts.stages[idx].bike_movements.remove(&id);
+                    if !ts.stages.iter().any(|s| s.bike_movements.contains(&id)) {
+                        ts.bike_signals.remove(&id);
+                    }
+                }
+            });
+            return Transition::KeepWithMouseover;
+        }
+        Transition::Keep
+    }
+
     // We may have imported the signal configuration without validating it.
     fn validate_all_members(&self, app: &App) -> Result<()> {
         for i in &self.members {
@@ -246,6 +316,10 @@ impl State<App> for TrafficSignalEditor {
                     let idx = self.current_stage;
                     self.add_new_edit(ctx, app, 0, |ts| {
                         ts.stages.remove(idx);
+                        // Bike signals that are never green anymore go away
+                        let stages = &ts.stages;
+                        ts.bike_signals
+                            .retain(|m| stages.iter().any(|s| s.bike_movements.contains(m)));
                     });
                     return Transition::Keep;
                 }
@@ -272,6 +346,23 @@ impl State<App> for TrafficSignalEditor {
                     ts.stages.swap(old_idx, new_idx);
                 });
             }
+            Outcome::Changed(x) => match x.as_ref() {
+                "lead/lag interval" => {
+                    let idx = self.current_stage;
+                    let interval = self.side_panel.dropdown_value("lead/lag interval");
+                    self.add_new_edit(ctx, app, idx, |ts| {
+                        ts.stages[idx].lead_lag = interval;
+                    });
+                    return Transition::Keep;
+                }
+                "Edit bike signals" => {
+                    self.editing_bike_signals = self.side_panel.is_checked("Edit bike signals");
+                    self.movement_selected = None;
+                    self.change_stage(ctx, app, self.current_stage);
+                    return Transition::Keep;
+                }
+                _ => {}
+            },
             _ => {}
         }
 
@@ -283,7 +374,7 @@ impl State<App> for TrafficSignalEditor {
                         self.command_stack.push(bundle);
                         self.redo_stack.clear();
 
-                        self.top_panel = make_top_panel(ctx, app, true, false);
+                        self.top_panel = make_top_panel(ctx, app, &self.members, true, false);
                         self.change_stage(ctx, app, 0);
 
                         return Transition::Push(PopupMsg::new_state(
@@ -317,10 +408,14 @@ impl State<App> for TrafficSignalEditor {
                         return Transition::Pop;
                     }
                     let original = self.original.clone();
-                    return Transition::Push(ConfirmDiscard::new_state(
+                    return Transition::Push(Confirm::new_state(
                         ctx,
-                        Box::new(move |app| {
+                        "Discard changes?",
+                        vec!["Are you sure you want to discard changes you made?"],
+                        "Yes, discard",
+                        Box::new(move |_, app| {
                             original.apply(app);
+                            Transition::Pop
                         }),
                     ));
                 }
@@ -355,6 +450,17 @@ impl State<App> for TrafficSignalEditor {
                         );
                     }
                 }
+                "toggle U-turns" => {
+                    // The turns and signal are regenerated, so any changes here are lost
+                    self.original.apply(app);
+                    toggle_u_turns(ctx, app, *self.members.iter().next().unwrap());
+                    return Transition::Replace(TrafficSignalEditor::new_state(
+                        ctx,
+                        app,
+                        self.members.clone(),
+                        self.mode.clone(),
+                    ));
+                }
                 "Change crosswalks" => {
                     // TODO Probably need to follow everything Cancel does
                     return Transition::Replace(super::crosswalks::CrosswalkEditor::new_state(
@@ -380,7 +486,13 @@ impl State<App> for TrafficSignalEditor {
                     self.redo_stack
                         .push(BundleEdits::get_current(app, &self.members));
                     self.command_stack.pop().unwrap().apply(app);
-                    self.top_panel = make_top_panel(ctx, app, !self.command_stack.is_empty(), true);
+                    self.top_panel = make_top_panel(
+                        ctx,
+                        app,
+                        &self.members,
+                        !self.command_stack.is_empty(),
+                        true,
+                    );
                     self.change_stage(ctx, app, 0);
                     return Transition::Keep;
                 }
@@ -388,7 +500,8 @@ impl State<App> for TrafficSignalEditor {
                     self.command_stack
                         .push(BundleEdits::get_current(app, &self.members));
                     self.redo_stack.pop().unwrap().apply(app);
-                    self.top_panel = make_top_panel(ctx, app, true, !self.redo_stack.is_empty());
+                    self.top_panel =
+                        make_top_panel(ctx, app, &self.members, true, !self.redo_stack.is_empty());
                     self.change_stage(ctx, app, 0);
                     return Transition::Keep;
                 }
@@ -416,6 +529,18 @@ impl State<App> for TrafficSignalEditor {
                     let i = app.primary.map.get_i(signal.id);
                     if m.hitbox.contains_pt(pt) {
                         let stage = &signal.stages[self.current_stage];
+                        if self.editing_bike_signals {
+                            // Bike signals are either green or red
+                            if !m.id.crosswalk {
+                                let next_priority = if stage.bike_movements.contains(&m.id) {
+                                    TurnPriority::Banned
+                                } else {
+                                    TurnPriority::Protected
+                                };
+                                self.movement_selected = Some((m.id, Some(next_priority)));
+                            }
+                            break;
+                        }
                         let next_priority = match stage.get_priority_of_movement(m.id) {
                             TurnPriority::Banned => {
                                 if stage.could_be_protected(m.id, i) {
@@ -447,6 +572,9 @@ impl State<App> for TrafficSignalEditor {
         }
 
         if let Some((id, Some(pri))) = self.movement_selected {
+            if self.editing_bike_signals {
+                return self.toggle_bike_signal(ctx, app, id, pri);
+            }
             let signal = app.primary.map.get_traffic_signal(id.parent);
             let mut txt = Text::new();
             txt.add_line(Line(format!(
@@ -542,13 +670,23 @@ impl State<App> for TrafficSignalEditor {
     }
 }
 
-fn make_top_panel(ctx: &mut EventCtx, app: &App, can_undo: bool, can_redo: bool) -> Panel {
+fn make_top_panel(
+    ctx: &mut EventCtx,
+    app: &App,
+    members: &BTreeSet<IntersectionID>,
+    can_undo: bool,
+    can_redo: bool,
+) -> Panel {
     let mut second_row = vec![ctx
         .style()
         .btn_outline
         .text("Change crosswalks")
         .hotkey(Key::C)
         .build_def(ctx)];
+    // Changing U-turns regenerates the signal, so only offer it when editing one
+    if members.len() == 1 {
+        second_row.push(u_turns_button(ctx, app, *members.iter().next().unwrap()));
+    }
     if app.opts.dev {
         second_row.push(
             ctx.style()
@@ -620,6 +758,7 @@ fn make_side_panel(
     app: &App,
     members: &BTreeSet<IntersectionID>,
     selected: usize,
+    editing_bike_signals: bool,
 ) -> Panel {
     let map = &app.primary.map;
     // Use any member for stage duration
@@ -704,6 +843,14 @@ fn make_side_panel(
         .padding(10)
         .bg(app.cs.inner_panel_bg),
     );
+    col.push(Widget::row(vec![
+        Line("Head start")
+            .secondary()
+            .into_widget(ctx)
+            .centered_vert(),
+        lead_lag_dropdown(ctx, canonical_signal.stages[selected].lead_lag),
+        Toggle::switch(ctx, "Edit bike signals", Key::B, editing_bike_signals),
+    ]));
 
     let translations = squish_polygons_together(
         members
@@ -786,6 +933,27 @@ fn make_side_panel(
         .build(ctx)
 }
 
+fn lead_lag_dropdown(ctx: &mut EventCtx, current: Option<LeadLagInterval>) -> Widget {
+    let mut choices = vec![Choice::new("everyone starts together", None)];
+    for secs in [3.0, 5.0, 10.0] {
+        choices.push(Choice::new(
+            format!("walk and bike {}s early", secs),
+            Some(LeadLagInterval::Leading(Duration::seconds(secs))),
+        ));
+    }
+    for secs in [3.0, 5.0, 10.0] {
+        choices.push(Choice::new(
+            format!("walk and bike {}s late", secs),
+            Some(LeadLagInterval::Lagging(Duration::seconds(secs))),
+        ));
+    }
+    // The signal might've been imported with a different interval
+    if !choices.iter().any(|c| c.data == current) {
+        choices.push(Choice::new("other", current));
+    }
+    Widget::dropdown(ctx, "lead/lag interval", current, choices)
+}
+
 impl BundleEdits {
     fn apply(&self, app: &mut App) {
         for s in &self.signals {
@@ -839,6 +1007,7 @@ impl BundleEdits {
                     signal.stages.push(Stage::new());
                 }
                 signal.stages[idx].stage_type = canonical_stage.stage_type.clone();
+                signal.stages[idx].lead_lag = canonical_stage.lead_lag;
             }
             signals.push(signal);
         }
@@ -898,7 +1067,7 @@ fn draw_multiple_signals(
         );
         traffic_signal::draw_signal_stage(
             ctx.prerender,
-            &app.primary.map.get_traffic_signal(*i).stages[idx],
+            app.primary.map.get_traffic_signal(*i),
             idx,
             *i,
             None,
@@ -0,0 +1,59 @@
+use anyhow::Result;
+
+use abstutil::Timer;
+use map_model::{ControlTrafficSignal, EditCmd, EditIntersection};
+use widgetry::tools::PopupMsg;
+use widgetry::{EventCtx, State};
+
+use crate::edit::apply_map_edits;
+use crate::App;
+
+/// Writes the timing of every traffic signal in the map as a JSON list, in the format described
+/// by the `traffic_signal_data` crate. Returns the path written.
+pub fn export_all(app: &App) -> Result<String> {
+    let map = &app.primary.map;
+    let path = abstio::path_player(format!(
+        "traffic_signal_timing/{}.json",
+        map.get_name().as_filename()
+    ));
+    abstio::write_file(
+        path,
+        abstutil::to_json(&ControlTrafficSignal::export_all(map)),
+    )
+}
+
+/// Reads a JSON list of traffic signals, like one produced by `export_all`, and overwrites the
+/// timing of every matching intersection.
+pub fn import_all(ctx: &mut EventCtx, app: &mut App, path: &str) -> Box<dyn State<App>> {
+    let raw = match abstio::maybe_read_json(path.to_string(), &mut Timer::throwaway()) {
+        Ok(raw) => raw,
+        Err(err) => {
+            return PopupMsg::new_state(ctx, "Error", vec![err.to_string()]);
+        }
+    };
+    let (signals, errors) = ControlTrafficSignal::import_all(raw, &app.primary.map);
+
+    let mut edits = app.primary.map.get_edits().clone();
+    for signal in &signals {
+        edits.commands.push(EditCmd::ChangeIntersection {
+            i: signal.id,
+            old: app.primary.map.get_i_edit(signal.id),
+            new: EditIntersection::TrafficSignal(signal.export(&app.primary.map)),
+        });
+    }
+    apply_map_edits(ctx, app, edits);
+
+    let mut lines = vec![format!(
+        "{} traffic signals successfully imported",
+        signals.len()
+    )];
+    if !errors.is_empty() {
+        lines.push(format!("{} failures, such as:", errors.len()));
+        for err in &errors {
+            error!("{}", err);
+        }
+        // The full list is in the console logs
+        lines.extend(errors.into_iter().take(5));
+    }
+    PopupMsg::new_state(ctx, &format!("Import from {}", path), lines)
+}
@@ -0,0 +1,197 @@
+//! A capacity analysis for one traffic signal, following the Highway Capacity Manual's method for
+//! signalized intersections. Volumes and delays come from the current simulation; saturation flow
+//! and lost time are standard HCM assumptions, since the simulation doesn't model them directly.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use geom::{Duration, Time};
+use map_model::{CompressedMovementID, IntersectionID, TurnType};
+use sim::AgentType;
+
+use crate::app::App;
+use crate::common::cardinal_direction;
+
+/// The HCM base saturation flow rate, in passenger cars per hour of green per lane
+const BASE_SATURATION_FLOW: f64 = 1900.0;
+/// Start-up and clearance lost time, each time a movement gets a green light
+const LOST_TIME: Duration = Duration::const_seconds(4.0);
+
+/// Only these agents count towards vehicle volume and delay. Bikes are reported separately.
+const VEHICLES: [AgentType; 2] = [AgentType::Car, AgentType::Bus];
+
+/// Writes a CSV file with one row per vehicle movement through the signal, describing the busiest
+/// hour of the simulation so far. Returns the path written.
+pub fn export(app: &App, i: IntersectionID) -> Result<String> {
+    let map = &app.primary.map;
+    let analytics = app.primary.sim.get_analytics();
+    let signal = map.get_traffic_signal(i);
+    let cycle = signal.simple_cycle_duration();
+
+    // Like HCM, analyze the peak hour
+    let mut volume_per_hour: BTreeMap<usize, usize> = BTreeMap::new();
+    for ((mvmnt, agent_type, hour), count) in &analytics.traffic_signal_thruput.counts {
+        if mvmnt.i == i && VEHICLES.contains(agent_type) {
+            *volume_per_hour.entry(*hour).or_insert(0) += *count;
+        }
+    }
+    let hour = match volume_per_hour.into_iter().max_by_key(|(_, count)| *count) {
+        Some((hour, _)) => hour,
+        None => bail!("No vehicles have passed through {} yet", i),
+    };
+    let start = Time::START_OF_DAY + Duration::hours(hour);
+
+    let mut delays_per_movement: BTreeMap<u8, Vec<Duration>> = BTreeMap::new();
+    for (idx, time, delay, agent_type) in analytics
+        .intersection_delays
+        .get(&i)
+        .cloned()
+        .unwrap_or_default()
+    {
+        if time.get_hours() == hour && VEHICLES.contains(&agent_type) {
+            delays_per_movement.entry(idx).or_default().push(delay);
+        }
+    }
+
+    let mut out = app.primary.sim.run_metadata().to_csv_comment();
+    writeln!(
+        out,
+        "movement,lanes,volume_vph,bike_volume,ideal_sat_flow_pcphgpl,adjusted_sat_flow_vph,\
+         effective_green_s,cycle_s,g_c,capacity_vph,v_c,hcm_uniform_delay_s,sim_delay_s,los"
+    )?;
+    for (idx, (id, mvmnt)) in map.get_i(i).movements.iter().enumerate() {
+        if id.crosswalk {
+            continue;
+        }
+        let compressed = CompressedMovementID {
+            i,
+            idx: u8::try_from(idx)?,
+        };
+        let count = |agent_type| {
+            analytics
+                .traffic_signal_thruput
+                .counts
+                .get(&(compressed, agent_type, hour))
+                .cloned()
+                .unwrap_or(0)
+        };
+        let volume: usize = VEHICLES.into_iter().map(count).sum();
+        let bike_volume = count(AgentType::Bike);
+
+        let mut src_lanes: Vec<_> = mvmnt.members.iter().map(|t| t.src).collect();
+        src_lanes.sort();
+        src_lanes.dedup();
+        let lanes = src_lanes.len();
+
+        // HCM adjustment factors for turning movements
+        let turn_factor = match mvmnt.turn_type {
+            TurnType::Left | TurnType::UTurn => 0.95,
+            TurnType::Right => 0.85,
+            _ => 1.0,
+        };
+        let sat_flow = BASE_SATURATION_FLOW * (lanes as f64) * turn_factor;
+
+        // A green light is effective for the whole stage, minus lost time whenever the movement
+        // wasn't allowed in the previous stage. Permitted turns have to yield, so only count half
+        // of that time.
+        let mut green = Duration::ZERO;
+        for (stage_idx, stage) in signal.stages.iter().enumerate() {
+            let allowed = |s: &map_model::Stage| {
+                s.protected_movements.contains(id) || s.yield_movements.contains(id)
+            };
+            if !allowed(stage) {
+                continue;
+            }
+            let mut time = stage.stage_type.simple_duration();
+            if stage.yield_movements.contains(id) {
+                time = time / 2.0;
+            }
+            let prev = &signal.stages[(stage_idx + signal.stages.len() - 1) % signal.stages.len()];
+            if !allowed(prev) || signal.stages.len() == 1 {
+                time = (time - LOST_TIME).max(Duration::ZERO);
+            }
+            green += time;
+        }
+        let g_c = if cycle > Duration::ZERO {
+            green / cycle
+        } else {
+            0.0
+        };
+        let capacity = sat_flow * g_c;
+        let v_c = if capacity > 0.0 {
+            (volume as f64) / capacity
+        } else {
+            f64::INFINITY
+        };
+        let uniform_delay = if g_c < 1.0 {
+            0.5 * cycle.inner_seconds() * (1.0 - g_c).powi(2) / (1.0 - v_c.min(1.0) * g_c)
+        } else {
+            0.0
+        };
+
+        let (sim_delay, los) = match delays_per_movement.get(&compressed.idx) {
+            Some(delays) => {
+                let avg = delays.iter().cloned().sum::<Duration>() / (delays.len() as f64);
+                (
+                    format!("{:.1}", avg.inner_seconds()),
+                    level_of_service(avg).to_string(),
+                )
+            }
+            None => (String::new(), String::new()),
+        };
+
+        writeln!(
+            out,
+            "{}{},{},{},{},{},{:.0},{:.1},{:.1},{:.2},{:.0},{:.2},{:.1},{},{}",
+            cardinal_direction(map.get_l(src_lanes[0]).lane_center_pts.overall_angle()),
+            match mvmnt.turn_type {
+                TurnType::Left => "L",
+                TurnType::Right => "R",
+                TurnType::UTurn => "U",
+                _ => "T",
+            },
+            lanes,
+            volume,
+            bike_volume,
+            BASE_SATURATION_FLOW,
+            sat_flow,
+            green.inner_seconds(),
+            cycle.inner_seconds(),
+            g_c,
+            capacity,
+            v_c,
+            uniform_delay,
+            sim_delay,
+            los
+        )?;
+    }
+
+    let path = abstio::path_player(format!(
+        "capacity_reports/{}_{}_{}.csv",
+        map.get_name().as_filename(),
+        i.0,
+        start.as_filename()
+    ));
+    abstio::write_file(path, out)
+}
+
+/// The HCM level of service thresholds for signalized intersections, based on control delay per
+/// vehicle
+fn level_of_service(delay: Duration) -> &'static str {
+    let secs = delay.inner_seconds();
+    if secs <= 10.0 {
+        "A"
+    } else if secs <= 20.0 {
+        "B"
+    } else if secs <= 35.0 {
+        "C"
+    } else if secs <= 55.0 {
+        "D"
+    } else if secs <= 80.0 {
+        "E"
+    } else {
+        "F"
+    }
+}
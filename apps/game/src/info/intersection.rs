@@ -89,6 +89,33 @@ fn traffic_body(ctx: &mut EventCtx, app: &App, id: IntersectionID, opts: &DataOp
                 .total_for(id)
         )
     ));
+    if app.primary.map.get_i(id).is_border() {
+        let (delayed, avg_wait) = app
+            .primary
+            .sim
+            .get_analytics()
+            .border_entry_summary(id, app.primary.sim.time());
+        if delayed > 0 {
+            txt.add_line(format!(
+                "{} vehicles waited off-map to enter here, {} on average",
+                prettyprint_usize(delayed),
+                avg_wait
+            ));
+        }
+        let waiting = app
+            .primary
+            .sim
+            .border_queues(&app.primary.map)
+            .remove(&id)
+            .unwrap_or_else(Vec::new);
+        if let Some(longest) = waiting.iter().map(|(_, waited)| *waited).max() {
+            txt.add_line(format!(
+                "{} vehicles are waiting to enter right now, the longest for {}",
+                prettyprint_usize(waiting.len()),
+                longest
+            ));
+        }
+    }
     rows.push(txt.into_widget(ctx));
 
     rows.push(opts.to_controls(ctx, app));
@@ -327,6 +354,13 @@ fn traffic_signal_body(ctx: &mut EventCtx, app: &App, id: IntersectionID) -> Wid
         }
         rows.push(txt.into_widget(ctx));
     }
+    rows.push(
+        ctx.style()
+            .btn_outline
+            .text("Export capacity report")
+            .tooltip("Volume, capacity, and delay per movement during the busiest hour so far")
+            .build_widget(ctx, format!("capacity report for {}", id)),
+    );
 
     for (idx, stage) in signal.stages.iter().enumerate() {
         rows.push(
@@ -347,7 +381,7 @@ fn traffic_signal_body(ctx: &mut EventCtx, app: &App, id: IntersectionID) -> Wid
             let mut orig_batch = GeomBatch::new();
             draw_signal_stage(
                 ctx.prerender,
-                stage,
+                signal,
                 idx,
                 id,
                 None,
@@ -1,5 +1,5 @@
 use abstutil::prettyprint_usize;
-use map_model::{LaneID, PathConstraints};
+use map_model::{osm, Direction, LaneID, PathConstraints, Road, RoadID};
 use widgetry::{EventCtx, Line, LinePlot, PlotOptions, Series, Text, TextExt, Widget};
 
 use crate::app::App;
@@ -49,10 +49,67 @@ fn info_body(ctx: &EventCtx, app: &App, id: LaneID) -> Widget {
             ),
         ));
     } else {
-        kv.push(("Speed limit", r.speed_limit.to_string(&app.opts.units)));
+        let mut limit = r.speed_limit.to_string(&app.opts.units);
+        if r.is_speed_limit_inferred() {
+            limit.push_str(" (guessed)");
+        }
+        kv.push(("Speed limit", limit));
+        if let Some(ref control) = r.variable_speed_limit {
+            kv.push((
+                "Posted limit",
+                app.primary
+                    .sim
+                    .posted_speed_limit(r.id)
+                    .unwrap_or(r.speed_limit)
+                    .to_string(&app.opts.units),
+            ));
+            kv.push(("Managed", control.describe(&app.opts.units).join(", ")));
+        }
+        if !r.traffic_calming.is_empty() {
+            kv.push((
+                "Traffic calming",
+                format!(
+                    "{} devices, averaging {}",
+                    r.traffic_calming.len(),
+                    r.speed_limit_with_traffic_calming(&app.primary.map)
+                        .to_string(&app.opts.units)
+                ),
+            ));
+        }
     }
 
     kv.push(("Length", l.length().to_string(&app.opts.units)));
+    if let Some(i) = r.slip_lane {
+        kv.push(("Slip lane", format!("bypasses {}", i)));
+    }
+    if let Some(restriction) = l.get_restriction(&app.primary.map) {
+        kv.push(("Restriction", restriction.describe()));
+    }
+    if let Some(schedule) = l.get_reversal_schedule(&app.primary.map) {
+        kv.push(("Reversible", schedule.describe()));
+        if app.primary.sim.is_lane_closed(l.id) {
+            kv.push(("Closed", "pointing the other way right now".to_string()));
+        }
+    }
+    if let Some(ref meter) = r.ramp_meter {
+        kv.push(("Ramp meter", meter.describe()));
+        let (count, avg_wait) = app
+            .primary
+            .sim
+            .get_analytics()
+            .ramp_meter_summary(r.id, app.primary.sim.time());
+        kv.push((
+            "Metered vehicles",
+            format!(
+                "{}, waiting {} on average",
+                prettyprint_usize(count),
+                avg_wait
+            ),
+        ));
+        if let Some(mainline) = mainline_after_ramp(app, r) {
+            kv.push(("Motorway throughput", compare_throughput(app, mainline)));
+        }
+    }
 
     rows.extend(make_table(ctx, kv));
 
@@ -102,6 +159,39 @@ fn info_body(ctx: &EventCtx, app: &App, id: LaneID) -> Widget {
     Widget::col(rows)
 }
 
+/// The part of the motorway just past an on-ramp
+fn mainline_after_ramp(app: &App, ramp: &Road) -> Option<RoadID> {
+    let map = &app.primary.map;
+    let end = if ramp.oneway_for_driving()? == Direction::Fwd {
+        ramp.dst_i
+    } else {
+        ramp.src_i
+    };
+    map.get_i(end).roads.iter().cloned().find(|r| {
+        let road = map.get_r(*r);
+        *r != ramp.id
+            && road.osm_tags.is(osm::HIGHWAY, "motorway")
+            && road.lanes.iter().any(|l| l.is_driving() && l.src_i == end)
+    })
+}
+
+/// Traffic along a road so far, compared to the baseline before edits, if there is one
+fn compare_throughput(app: &App, r: RoadID) -> String {
+    let now = app.primary.sim.time();
+    let count = app
+        .primary
+        .sim
+        .get_analytics()
+        .road_thruput
+        .total_for_by_time(r, now);
+    let mut result = format!("{} so far", prettyprint_usize(count));
+    if app.has_prebaked().is_some() {
+        let baseline = app.prebaked().road_thruput.total_for_by_time(r, now);
+        result.push_str(&format!(", {} before edits", prettyprint_usize(baseline)));
+    }
+    result
+}
+
 pub fn debug(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Widget {
     Widget::custom_col(vec![
         header(ctx, app, details, id, Tab::LaneDebug(id)),
@@ -153,7 +243,7 @@ fn debug_body(ctx: &EventCtx, app: &App, id: LaneID) -> Widget {
     ));
     kv.push((
         "Incline / grade".to_string(),
-        format!("{:.1}%", r.percent_incline * 100.0),
+        format!("{:.1}%", l.percent_incline(map) * 100.0),
     ));
     kv.push((
         "Elevation details".to_string(),
@@ -12,10 +12,10 @@ use sim::{
     ProblemType, TripID, VehicleType,
 };
 use widgetry::mapspace::{ToggleZoomed, ToggleZoomedBuilder};
-use widgetry::tools::open_browser;
+use widgetry::tools::{open_browser, PopupMsg};
 use widgetry::{
-    Color, EventCtx, GfxCtx, Key, Line, LinePlot, Outcome, Panel, PlotOptions, Series, Text,
-    TextExt, Toggle, Widget,
+    Color, EventCtx, GfxCtx, InteractivePlot, Key, Line, LinePlot, Outcome, Panel, PlotOptions,
+    Series, Text, TextExt, Toggle, Widget,
 };
 
 use crate::app::{App, Transition};
@@ -26,6 +26,7 @@ use crate::layer::PANEL_PLACEMENT;
 use crate::sandbox::{dashboards, GameplayMode, SandboxMode, TimeWarpScreen};
 
 mod building;
+mod capacity_report;
 mod debug;
 mod intersection;
 mod lane;
@@ -405,6 +406,15 @@ impl InfoPanel {
         let maybe_id = tab.to_id(app);
         let mut cached_actions = Vec::new();
         if main_tab {
+            if let Some(ref id) = maybe_id {
+                let extra_rows: Vec<(String, String)> = app
+                    .session
+                    .info_panel_rows
+                    .iter()
+                    .flat_map(|x| (x.rows)(app, id))
+                    .collect();
+                col.extend(make_table(ctx, extra_rows));
+            }
             if let Some(id) = maybe_id.clone() {
                 for (key, label) in ctx_actions.actions(app, id) {
                     cached_actions.push(key);
@@ -614,6 +624,21 @@ impl InfoPanel {
                             dashboards::TrafficSignalDemand::new_state(ctx, app),
                         )),
                     )
+                } else if let Some(x) = action.strip_prefix("capacity report for Intersection #") {
+                    let i = IntersectionID(x.parse::<usize>().unwrap());
+                    (
+                        false,
+                        Some(Transition::Push(match capacity_report::export(app, i) {
+                            Ok(path) => PopupMsg::new_state(
+                                ctx,
+                                "Report exported",
+                                vec![format!("Capacity report exported to {}", path)],
+                            ),
+                            Err(err) => {
+                                PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                            }
+                        })),
+                    )
                 } else if let Some(x) = action.strip_prefix("routes across Intersection #") {
                     (
                         false,
@@ -754,7 +779,7 @@ fn throughput<F: Fn(&Analytics) -> Vec<(AgentType, Vec<(Time, usize)>)>>(
     plot_opts.disabled = opts.disabled_series();
     Widget::col(vec![
         Line(title).small_heading().into_widget(ctx),
-        LinePlot::new_widget(ctx, title, series, plot_opts, app.opts.units),
+        InteractivePlot::new_widget(ctx, "throughput", series, plot_opts, app.opts.units),
     ])
     .padding(10)
     .bg(app.cs.inner_panel_bg)
@@ -898,10 +923,15 @@ impl DataOptions {
 
     pub fn from_controls(c: &Panel) -> DataOptions {
         let show_before = c.maybe_is_checked("Show before changes").unwrap_or(false);
+        // Series can be hidden from the throughput plot's legend
+        let hidden = c
+            .maybe_find::<InteractivePlot<Time, usize>>("throughput")
+            .map(|plot| plot.hidden_series().clone())
+            .unwrap_or_default();
         let mut disabled_types = BTreeSet::new();
         for a in AgentType::all() {
             let label = a.noun();
-            if !c.maybe_is_checked(label).unwrap_or(true) {
+            if hidden.contains(label) || !c.maybe_is_checked(label).unwrap_or(true) {
                 disabled_types.insert(a);
             }
         }
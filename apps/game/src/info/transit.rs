@@ -2,7 +2,7 @@ use crate::ID;
 use abstutil::{prettyprint_usize, Counter};
 use geom::{Circle, Distance, Time};
 use map_gui::tools::ColorNetwork;
-use map_model::{PathStep, TransitRoute, TransitRouteID, TransitStopID};
+use map_model::{PathStep, StopLayout, TransitRoute, TransitRouteID, TransitStopID};
 use sim::{AgentID, CarID};
 use widgetry::{Color, ControlState, EventCtx, Key, Line, RewriteColor, Text, TextExt, Widget};
 
@@ -25,6 +25,15 @@ fn stop_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: TransitSt
     let sim = &app.primary.sim;
 
     rows.push(Line(&ts.name).into_widget(ctx));
+    if !ts.is_train_stop {
+        rows.push(
+            match ts.layout {
+                StopLayout::InLane => "Buses stop in the travel lane",
+                StopLayout::PullOut => "Buses pull out of the travel lane",
+            }
+            .text_widget(ctx),
+        );
+    }
 
     let all_arrivals = &sim.get_analytics().bus_arrivals;
     for r in app.primary.map.get_routes_serving_stop(id) {
@@ -3,9 +3,13 @@ use std::collections::BTreeMap;
 use maplit::btreemap;
 
 use crate::ID;
-use geom::{Distance, Duration, Percent, Polygon, Pt2D, UnitFmt};
-use map_model::{Map, Path, PathStep, Traversable};
-use sim::{AgentID, Analytics, PersonID, Problem, TripID, TripInfo, TripPhase, TripPhaseType};
+use geom::{ArrowCap, Distance, Duration, Percent, Polygon, Pt2D, UnitFmt};
+use map_gui::tools::color_for_mode;
+use map_model::{Map, Path, PathRequest, PathStep, Traversable, TurnID, TurnType};
+use sim::{
+    AgentID, AgentState, Analytics, DelayCause, PersonID, PlannedLeg, Problem, TripID, TripInfo,
+    TripPhase, TripPhaseType,
+};
 use synthpop::{TripEndpoint, TripMode};
 use widgetry::{
     Color, ControlState, DrawWithTooltips, EventCtx, GeomBatch, Line, LinePlot, PlotOptions,
@@ -21,9 +25,11 @@ pub struct OpenTrip {
     pub show_after: bool,
     // (unzoomed, zoomed). Indexed by order of TripPhase.
     cached_routes: Vec<Option<(Polygon, Vec<Polygon>)>>,
+    // (unzoomed, zoomed, estimated duration) for legs of an ongoing trip that haven't started yet.
+    cached_planned: Vec<(PathRequest, Option<(Polygon, Vec<Polygon>, Duration)>)>,
 }
 
-// Ignore cached_routes
+// Ignore cached_routes and cached_planned
 impl std::cmp::PartialEq for OpenTrip {
     fn eq(&self, other: &OpenTrip) -> bool {
         self.show_after == other.show_after
@@ -39,6 +45,7 @@ impl OpenTrip {
         OpenTrip {
             show_after: true,
             cached_routes: Vec::new(),
+            cached_planned: Vec::new(),
         }
     }
 }
@@ -116,6 +123,47 @@ pub fn ongoing(
             ]),
         ]));
     }
+    if let Some(state) = app.primary.sim.agent_state(agent) {
+        col.push(Widget::custom_row(vec![
+            Widget::custom_row(vec![Line("Doing").secondary().into_widget(ctx)])
+                .force_width_window_pct(ctx, col_width),
+            describe_state(app, state).text_widget(ctx),
+        ]));
+    }
+    if let Some(why) = explain_delay(ctx, app, agent, details) {
+        col.push(Widget::custom_row(vec![
+            Widget::custom_row(vec![Line("Why waiting?").secondary().into_widget(ctx)])
+                .force_width_window_pct(ctx, col_width),
+            why,
+        ]));
+    }
+    if let Some(path) = app.primary.sim.get_path(agent) {
+        let maneuvers = upcoming_maneuvers(path, &app.primary.map);
+        if let Some(next) = maneuvers.first() {
+            col.push(Widget::custom_row(vec![
+                Widget::custom_row(vec![Line("Next turn").secondary().into_widget(ctx)])
+                    .force_width_window_pct(ctx, col_width),
+                describe_maneuver(app, *next).text_widget(ctx),
+            ]));
+        }
+        for t in maneuvers {
+            let arrow = |thickness| {
+                app.primary
+                    .map
+                    .get_t(t)
+                    .geom
+                    .make_arrow(thickness, ArrowCap::Triangle)
+            };
+            details
+                .draw_extra
+                .unzoomed
+                .push(app.cs.selected, arrow(Distance::meters(3.0)));
+            details
+                .draw_extra
+                .zoomed
+                .push(app.cs.selected, arrow(Distance::meters(0.5)));
+        }
+    }
     col.push(describe_problems(
         ctx,
         app.primary.sim.get_analytics(),
@@ -130,6 +178,9 @@ pub fn ongoing(
             Line(trip.purpose.to_string()).secondary().into_widget(ctx),
         ]));
     }
+    col.push(make_legs(
+        ctx, app, id, open_trip, details, &phases, col_width,
+    ));
 
     col.push(make_trip_details(
         ctx,
@@ -253,6 +304,7 @@ pub fn finished(
             OpenTrip {
                 show_after: false,
                 cached_routes: Vec::new(),
+                cached_planned: Vec::new(),
             },
         );
         details.hyperlinks.insert(
@@ -619,8 +671,127 @@ fn draw_problems(
                     (id, *time),
                 ));
             }
+            Problem::StopSignViolation(t) | Problem::NearMiss(t, _) | Problem::HardBraking(t) => {
+                let t = map.get_t(*t);
+
+                let geom = t.geom.make_polygons(Distance::meters(10.0));
+                details.draw_extra.unzoomed.append(
+                    GeomBatch::load_svg(ctx, "system/assets/tools/alert.svg")
+                        .centered_on(geom.center())
+                        .color(RewriteColor::ChangeAlpha(0.8)),
+                );
+                details.draw_extra.zoomed.append(
+                    GeomBatch::load_svg(ctx, "system/assets/tools/alert.svg")
+                        .scale(0.5)
+                        .color(RewriteColor::ChangeAlpha(0.5))
+                        .centered_on(geom.center()),
+                );
+                details.tooltips.push((
+                    geom,
+                    Text::from(match problem {
+                        Problem::StopSignViolation(_) => {
+                            "This driver ran the stop sign, cutting off somebody already here."
+                                .to_string()
+                        }
+                        Problem::NearMiss(_, pet) => format!(
+                            "A near-miss between a vehicle and pedestrian, only {} apart",
+                            pet
+                        ),
+                        _ => "A vehicle had to brake hard for a pedestrian here.".to_string(),
+                    }),
+                    // Rewind to just before the violation
+                    (id, *time - Duration::seconds(5.0)),
+                ));
+            }
+        }
+    }
+}
+
+/// Lists how long each leg of an ongoing trip took so far, and estimates the remaining legs. The
+/// remaining legs are also drawn on the map, colored by mode, so the whole multi-modal route is
+/// visible, not just the current leg.
+fn make_legs(
+    ctx: &mut EventCtx,
+    app: &App,
+    id: TripID,
+    open_trip: &mut OpenTrip,
+    details: &mut Details,
+    phases: &[TripPhase],
+    col_width: Percent,
+) -> Widget {
+    let map = &app.primary.map;
+    let sim = &app.primary.sim;
+
+    let mut txt = Text::new();
+    for p in phases {
+        if matches!(
+            p.phase_type,
+            TripPhaseType::Cancelled | TripPhaseType::Finished
+        ) {
+            continue;
         }
+        txt.add_line(Line(p.phase_type.describe(map)).fg(color_for_trip_phase(app, p.phase_type)));
+        if let Some(t2) = p.end_time {
+            txt.append(Line(format!(
+                ": {}",
+                (t2 - p.start_time).to_string(&app.opts.units)
+            )));
+        } else {
+            txt.append(Line(format!(
+                ": {} so far",
+                (sim.time() - p.start_time).to_string(&app.opts.units)
+            )));
+        }
+    }
+
+    for PlannedLeg { mode, req } in sim.get_planned_legs(id, map) {
+        let color = color_for_mode(app, mode);
+        let estimate = req.and_then(|req| {
+            // Pathfinding for every leg each time the panel refreshes is expensive, so cache
+            let idx = match open_trip.cached_planned.iter().position(|(r, _)| r == &req) {
+                Some(idx) => idx,
+                None => {
+                    let cached = map.pathfind(req.clone()).ok().and_then(|path| {
+                        let trace = path.trace(map)?;
+                        Some((
+                            trace.make_polygons(Distance::meters(10.0)),
+                            trace.dashed_lines(
+                                Distance::meters(0.75),
+                                Distance::meters(1.0),
+                                Distance::meters(0.4),
+                            ),
+                            path.estimate_duration(map, None),
+                        ))
+                    });
+                    open_trip.cached_planned.push((req, cached));
+                    open_trip.cached_planned.len() - 1
+                }
+            };
+            let (unzoomed, zoomed, duration) = open_trip.cached_planned[idx].1.as_ref()?;
+            // Lighter than the legs already taken, since this is only the plan
+            details
+                .draw_extra
+                .unzoomed
+                .push(color.alpha(0.4), unzoomed.clone());
+            details
+                .draw_extra
+                .zoomed
+                .extend(color.alpha(0.4), zoomed.clone());
+            Some(*duration)
+        });
+
+        txt.add_line(Line(format!("Then {}", mode.ongoing_verb())).fg(color));
+        txt.append(Line(match estimate {
+            Some(d) => format!(": about {}", d.to_string(&app.opts.units)),
+            None => ": unknown".to_string(),
+        }));
     }
+
+    Widget::custom_row(vec![
+        Widget::custom_row(vec![Line("Legs").secondary().into_widget(ctx)])
+            .force_width_window_pct(ctx, col_width),
+        txt.into_widget(ctx),
+    ])
 }
 
 /// Draws the timeline for a single trip, with tooltips
@@ -993,3 +1164,140 @@ fn endpoint(endpt: &TripEndpoint, app: &App) -> (ID, Pt2D, String) {
         ),
     }
 }
+
+fn describe_state(app: &App, state: AgentState) -> String {
+    let map = &app.primary.map;
+    let road_name = |l| map.get_parent(l).get_name(app.opts.language.as_ref());
+    match state {
+        AgentState::WaitingToSpawn => "Waiting for room to appear on the map".to_string(),
+        AgentState::Moving(Traversable::Lane(l)) => format!("Moving along {}", road_name(l)),
+        AgentState::Moving(Traversable::Turn(_)) => "Moving through an intersection".to_string(),
+        AgentState::ChangingLanes { .. } => "Changing lanes".to_string(),
+        AgentState::Queued(Traversable::Lane(l)) => {
+            format!("Stuck behind somebody on {}", road_name(l))
+        }
+        AgentState::Queued(Traversable::Turn(_)) => {
+            "Stuck behind somebody in an intersection".to_string()
+        }
+        AgentState::WaitingForTurn(t) => format!("Waiting to {}", describe_maneuver(app, t)),
+        AgentState::LookingForParking => "Looking for a free parking spot".to_string(),
+        AgentState::Parking => "Parking".to_string(),
+        AgentState::Unparking => "Leaving a parking spot".to_string(),
+        AgentState::AtTransitStop => "Picking up and dropping off passengers".to_string(),
+        AgentState::EnteringBuilding => "Entering a building".to_string(),
+        AgentState::LeavingBuilding => "Leaving a building".to_string(),
+        AgentState::WaitingForTransit(route) => {
+            format!("Waiting for {}", map.get_tr(route).long_name)
+        }
+        AgentState::RidingTransit(_) => "Riding transit".to_string(),
+        AgentState::Jaywalking => "Crossing the road away from a crosswalk".to_string(),
+        AgentState::SwitchingModes => "Getting on or off a bike".to_string(),
+    }
+}
+
+/// Follow the chain of who's blocking this agent. Each blocker can be clicked to warp to them.
+fn explain_delay(
+    ctx: &mut EventCtx,
+    app: &App,
+    agent: AgentID,
+    details: &mut Details,
+) -> Option<Widget> {
+    let map = &app.primary.map;
+    let chain = app.primary.sim.explain_delay(map, agent);
+    // Vehicles following somebody are in the chain, even if they're not stuck
+    if chain[0].cause.is_none() || chain[0].waiting == Duration::ZERO {
+        return None;
+    }
+
+    let mut rows = Vec::new();
+    for (blocked, step) in chain.iter().zip(chain.iter().skip(1)) {
+        let label = format!("Blocked by {}", agent_name(step.agent));
+        let mut txt = Text::new();
+        if let Some(t) = blocked.conflicting_turn {
+            // What they're doing in the intersection matters more than where they came from
+            txt.append(Line(format!("About to {}", describe_maneuver(app, t))).secondary());
+        } else if let Some(state) = step.state.clone() {
+            txt.append(Line(describe_state(app, state)).secondary());
+        }
+        if step.waiting > Duration::ZERO {
+            txt.append(
+                Line(format!(
+                    ", stuck for {}",
+                    step.waiting.to_string(&app.opts.units)
+                ))
+                .secondary(),
+            );
+        }
+        rows.push(Widget::row(vec![
+            ctx.style().btn_plain.text(&label).build_def(ctx),
+            txt.into_widget(ctx).centered_vert(),
+        ]));
+        details.warpers.insert(label, ID::from_agent(step.agent));
+    }
+    match chain.last().unwrap().cause {
+        Some(DelayCause::Intersection(i)) => {
+            let i = map.get_i(i);
+            let reason = if i.is_traffic_signal() {
+                "a traffic signal"
+            } else if i.is_stop_sign() {
+                "a stop sign"
+            } else {
+                "an intersection"
+            };
+            rows.push(format!("Waiting at {}", reason).text_widget(ctx));
+        }
+        Some(DelayCause::Agent(a)) => {
+            // The chain only stops at an agent if they're already in it
+            rows.push(
+                Line(format!(
+                    "...who is blocked by {}. This is gridlock!",
+                    agent_name(a)
+                ))
+                .fg(app.cs.slowest_intersection)
+                .into_widget(ctx),
+            );
+        }
+        None => {}
+    }
+    Some(Widget::col(rows))
+}
+
+fn agent_name(agent: AgentID) -> String {
+    match agent {
+        AgentID::Car(id) => id.to_string(),
+        AgentID::Pedestrian(id) => id.to_string(),
+        AgentID::BusPassenger(person, bus) => format!("{} on {}", person, bus),
+    }
+}
+
+/// The turns and crossings left on the current path, skipping anything going straight
+fn upcoming_maneuvers(path: &Path, map: &Map) -> Vec<TurnID> {
+    path.get_steps()
+        .iter()
+        .skip(1)
+        .filter_map(|step| match step {
+            PathStep::Turn(t) | PathStep::ContraflowTurn(t) => Some(*t),
+            PathStep::Lane(_) | PathStep::ContraflowLane(_) => None,
+        })
+        .filter(|t| {
+            !matches!(
+                map.get_t(*t).turn_type,
+                TurnType::Straight | TurnType::SharedSidewalkCorner
+            )
+        })
+        .collect()
+}
+
+fn describe_maneuver(app: &App, t: TurnID) -> String {
+    let map = &app.primary.map;
+    let road_name = |l| map.get_parent(l).get_name(app.opts.language.as_ref());
+    match map.get_t(t).turn_type {
+        TurnType::Left => format!("turn left onto {}", road_name(t.dst)),
+        TurnType::Right => format!("turn right onto {}", road_name(t.dst)),
+        TurnType::UTurn => format!("make a U-turn onto {}", road_name(t.dst)),
+        TurnType::Crosswalk | TurnType::UnmarkedCrossing => format!("cross {}", road_name(t.src)),
+        TurnType::Straight | TurnType::SharedSidewalkCorner => {
+            format!("continue onto {}", road_name(t.dst))
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Circle, Distance, Pt2D, Time};
+use map_gui::tools::{make_heatmap, HeatmapOptions};
+use sim::{BikeShareNetwork, BikeShareStation, MAX_WALK_TO_BIKE_SHARE, MIN_BIKE_SHARE_TRIP_DIST};
+use synthpop::TripMode;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::tools::{ColorLegend, PopupMsg};
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    Spinner, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+
+const STATION_RADIUS: Distance = Distance::const_meters(15.0);
+
+/// Shows bike share stations and how many bikes are docked at each. Optionally also shows where
+/// walking trips could've used bike share, if only there was a station nearby, to help site new
+/// stations.
+pub struct BikeShare {
+    time: Time,
+    opts: Options,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Options {
+    // If None, don't show unserved demand
+    pub demand: Option<HeatmapOptions>,
+}
+
+impl Layer for BikeShare {
+    fn name(&self) -> Option<&'static str> {
+        Some("bike share")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            let mut new = BikeShare::new(ctx, app, self.opts.clone());
+            new.panel.restore(ctx, &self.panel);
+            *self = new;
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                "Edit stations" => {
+                    return Some(LayerOutcome::Transition(Transition::Push(
+                        EditStations::new_state(ctx, app),
+                    )));
+                }
+                _ => unreachable!(),
+            },
+            _ => {
+                let new_opts = self.options();
+                if self.opts != new_opts {
+                    *self = BikeShare::new(ctx, app, new_opts);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl BikeShare {
+    pub fn new(ctx: &mut EventCtx, app: &App, opts: Options) -> BikeShare {
+        let map = &app.primary.map;
+        let stations = app.primary.sim.get_bike_share_stations();
+
+        let mut draw = ToggleZoomed::builder();
+        let legend = if let Some(ref o) = opts.demand {
+            let station_pts: Vec<Pt2D> = stations
+                .iter()
+                .map(|s| map.get_b(s.bldg).sidewalk_pos.pt(map))
+                .collect();
+            let unserved = |pt: Pt2D| {
+                station_pts
+                    .iter()
+                    .all(|station| station.dist_to(pt) > MAX_WALK_TO_BIKE_SHARE)
+            };
+            // Count both ends of walking trips long enough to use bike share, if they're too far
+            // from an existing station
+            let mut pts = Vec::new();
+            for (_, trip) in app.primary.sim.all_trip_info() {
+                if trip.mode != TripMode::Walk {
+                    continue;
+                }
+                let pt1 = trip.start.pt(map);
+                let pt2 = trip.end.pt(map);
+                if pt1.dist_to(pt2) < MIN_BIKE_SHARE_TRIP_DIST {
+                    continue;
+                }
+                pts.extend([pt1, pt2].into_iter().filter(|pt| unserved(*pt)));
+            }
+            Some(make_heatmap(
+                ctx,
+                &mut draw.unzoomed,
+                map.get_bounds(),
+                pts,
+                o,
+            ))
+        } else {
+            None
+        };
+
+        let mut bikes = 0;
+        for station in stations {
+            bikes += station.bikes;
+            let circle = Circle::new(map.get_b(station.bldg).label_center, STATION_RADIUS);
+            let color = station_color(station.bikes, station.capacity);
+            draw.unzoomed.push(color, circle.to_polygon());
+            draw.zoomed.push(color.alpha(0.5), circle.to_polygon());
+        }
+
+        let mut col = vec![header(ctx, "Bike share")];
+        if stations.is_empty() {
+            col.push(
+                Text::from_multiline(vec![
+                    Line("This simulation has no bike share stations."),
+                    Line("Add some, then reset to midnight.").secondary(),
+                ])
+                .into_widget(ctx),
+            );
+        } else {
+            col.push(
+                format!(
+                    "{} bikes docked at {} stations",
+                    prettyprint_usize(bikes),
+                    prettyprint_usize(stations.len())
+                )
+                .text_widget(ctx),
+            );
+            col.push(ColorLegend::row(ctx, Color::RED, "empty"));
+            col.push(ColorLegend::row(
+                ctx,
+                Color::GREEN,
+                "bikes and docks available",
+            ));
+            col.push(ColorLegend::row(ctx, Color::BLUE, "full"));
+        }
+        col.push(ctx.style().btn_outline.text("Edit stations").build_def(ctx));
+        col.push(Toggle::switch(
+            ctx,
+            "Show walking trips far from a station",
+            None,
+            opts.demand.is_some(),
+        ));
+        if let Some(ref o) = opts.demand {
+            col.extend(o.to_controls(ctx, legend.unwrap()));
+        }
+
+        BikeShare {
+            time: app.primary.sim.time(),
+            opts,
+            draw: draw.build(ctx),
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned_pair(PANEL_PLACEMENT)
+                .build(ctx),
+        }
+    }
+
+    fn options(&self) -> Options {
+        let demand = if self
+            .panel
+            .is_checked("Show walking trips far from a station")
+        {
+            Some(HeatmapOptions::from_controls(&self.panel))
+        } else {
+            None
+        };
+        Options { demand }
+    }
+}
+
+fn station_color(bikes: usize, capacity: usize) -> Color {
+    if bikes == 0 {
+        Color::RED
+    } else if bikes >= capacity {
+        Color::BLUE
+    } else {
+        Color::GREEN
+    }
+}
+
+/// Place bike share stations and choose their size. They're saved as player data, and used the
+/// next time the simulation starts.
+struct EditStations {
+    panel: Panel,
+    network: BikeShareNetwork,
+    draw: Drawable,
+}
+
+impl EditStations {
+    fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let network = abstio::maybe_read_json::<BikeShareNetwork>(
+            BikeShareNetwork::path(app.primary.map.get_name()),
+            &mut Timer::throwaway(),
+        )
+        .unwrap_or_default();
+        let mut state = EditStations {
+            panel: Panel::empty(ctx),
+            network,
+            draw: Drawable::empty(ctx),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let gps_bounds = app.primary.map.get_gps_bounds();
+        let mut batch = GeomBatch::new();
+        for station in &self.network.stations {
+            batch.push(
+                station_color(station.initial_bikes, station.capacity),
+                Circle::new(station.pt.to_pt(gps_bounds), STATION_RADIUS).to_polygon(),
+            );
+        }
+        self.draw = ctx.upload(batch);
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Bike share stations").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "Click the map to add a station".text_widget(ctx),
+        ];
+        for (idx, station) in self.network.stations.iter().enumerate() {
+            col.push(Widget::row(vec![
+                station.name.as_str().text_widget(ctx).centered_vert(),
+                "Docks:".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    format!("capacity {}", idx),
+                    (1, 100),
+                    station.capacity,
+                    1,
+                ),
+                "Bikes at midnight:".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    format!("initial bikes {}", idx),
+                    (0, 100),
+                    station.initial_bikes,
+                    1,
+                ),
+                ctx.style()
+                    .btn_plain_destructive
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, format!("remove station {}", idx)),
+            ]));
+        }
+        col.push(ctx.style().btn_solid_primary.text("Save").build_def(ctx));
+
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .build(ctx);
+    }
+}
+
+impl State<App> for EditStations {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+            if ctx.normal_left_click() && app.primary.map.get_boundary_polygon().contains_pt(pt) {
+                self.network.stations.push(BikeShareStation {
+                    name: format!("Station {}", self.network.stations.len() + 1),
+                    pt: pt.to_gps(app.primary.map.get_gps_bounds()),
+                    capacity: 15,
+                    initial_bikes: 10,
+                });
+                self.recalculate(ctx, app);
+            }
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Save" => {
+                    abstio::write_json(
+                        BikeShareNetwork::path(app.primary.map.get_name()),
+                        &self.network,
+                    );
+                    app.primary.current_flags.sim_flags.opts.bike_share =
+                        if self.network.stations.is_empty() {
+                            None
+                        } else {
+                            Some(self.network.clone())
+                        };
+                    return Transition::Replace(PopupMsg::new_state(
+                        ctx,
+                        "Stations saved",
+                        vec!["Reset to midnight to simulate bike share with these stations."],
+                    ));
+                }
+                x => {
+                    let idx = x
+                        .strip_prefix("remove station ")
+                        .unwrap()
+                        .parse::<usize>()
+                        .unwrap();
+                    self.network.stations.remove(idx);
+                    self.recalculate(ctx, app);
+                }
+            },
+            Outcome::Changed(_) => {
+                for (idx, station) in self.network.stations.iter_mut().enumerate() {
+                    station.capacity = self.panel.spinner(&format!("capacity {}", idx));
+                    station.initial_bikes = self.panel.spinner(&format!("initial bikes {}", idx));
+                }
+                self.recalculate(ctx, app);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use maplit::btreeset;
 
 use crate::ID;
@@ -12,6 +14,7 @@ use widgetry::{Color, EventCtx, GfxCtx, Line, Panel, Text, Widget};
 
 use crate::app::App;
 use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+use crate::plugins::InfoPanelRows;
 
 pub struct BikeActivity {
     panel: Panel,
@@ -165,8 +168,10 @@ impl BikeActivity {
 
 pub struct Static {
     panel: Panel,
-    pub draw: ToggleZoomed,
+    pub draw: Rc<ToggleZoomed>,
     name: &'static str,
+    /// Show a proposal's notes when hovering on their pins
+    hover_notes: bool,
 }
 
 impl Layer for Static {
@@ -176,9 +181,12 @@ impl Layer for Static {
     fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Option<LayerOutcome> {
         <dyn Layer>::simple_event(ctx, &mut self.panel)
     }
-    fn draw(&self, g: &mut GfxCtx, _: &App) {
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
         self.panel.draw(g);
         self.draw.draw(g);
+        if self.hover_notes {
+            crate::edit::draw_hovered_note(g, app);
+        }
     }
     fn draw_minimap(&self, g: &mut GfxCtx) {
         g.redraw(&self.draw.unzoomed);
@@ -188,17 +196,23 @@ impl Layer for Static {
 impl Static {
     fn new(
         ctx: &mut EventCtx,
+        app: &App,
         colorer: ColorDiscrete,
         name: &'static str,
         title: String,
         extra: Widget,
     ) -> Static {
-        let (draw, legend) = colorer.build(ctx);
+        let (draw, legend) = colorer.build_cached(ctx, &mut app.primary.static_layers.borrow_mut());
         let panel = Panel::new_builder(Widget::col(vec![header(ctx, &title), extra, legend]))
             .aligned_pair(PANEL_PLACEMENT)
             .build(ctx);
 
-        Static { panel, draw, name }
+        Static {
+            panel,
+            draw,
+            name,
+            hover_notes: false,
+        }
     }
 
     pub fn edits(ctx: &mut EventCtx, app: &App) -> Static {
@@ -218,9 +232,11 @@ impl Static {
         for i in edits.original_intersections.keys() {
             colorer.add_i(*i, "modified road/intersection");
         }
+        crate::edit::draw_pins(ctx, app, &mut colorer.draw);
 
-        Static::new(
+        let mut layer = Static::new(
             ctx,
+            app,
             colorer,
             "map edits",
             format!("Map edits ({})", edits.edits_name),
@@ -230,9 +246,12 @@ impl Static {
                     "{} intersections changed",
                     edits.original_intersections.len()
                 )),
+                Line(format!("{} notes", edits.annotations.len())),
             ])
             .into_widget(ctx),
-        )
+        );
+        layer.hover_notes = true;
+        layer
     }
 
     pub fn amenities(ctx: &mut EventCtx, app: &App) -> Static {
@@ -275,8 +294,9 @@ impl Static {
 
         Static {
             panel,
-            draw: draw.build(ctx),
+            draw: Rc::new(draw.build(ctx)),
             name: "amenities",
+            hover_notes: false,
         }
     }
 
@@ -289,6 +309,7 @@ impl Static {
         }
         Static::new(
             ctx,
+            app,
             colorer,
             "no sidewalks",
             "No sidewalks".to_string(),
@@ -316,6 +337,7 @@ impl Static {
         }
         Static::new(
             ctx,
+            app,
             colorer,
             "blackholes",
             "blackholes".to_string(),
@@ -336,6 +358,7 @@ impl Static {
 
         Static::new(
             ctx,
+            app,
             colorer,
             "high stress",
             "High stress roads for biking".to_string(),
@@ -347,4 +370,129 @@ impl Static {
             .into_widget(ctx),
         )
     }
+
+    pub fn traffic_stress(ctx: &mut EventCtx, app: &App) -> Static {
+        let categories: Vec<(&str, Color)> = (1..=4)
+            .map(|lts| (TRAFFIC_STRESS_LABELS[lts - 1], traffic_stress_color(lts)))
+            .collect();
+        let mut colorer = ColorDiscrete::new(app, categories);
+
+        let map = &app.primary.map;
+        for r in map.all_roads() {
+            // Show the worse direction
+            if let Some(lts) = [Direction::Fwd, Direction::Back]
+                .into_iter()
+                .filter_map(|dir| r.bike_traffic_stress(map, dir))
+                .max()
+            {
+                colorer.add_r(r.id, TRAFFIC_STRESS_LABELS[lts - 1]);
+            }
+        }
+
+        Static::new(
+            ctx,
+            app,
+            colorer,
+            "traffic stress",
+            "Level of traffic stress for biking".to_string(),
+            Text::from_multiline(vec![
+                Line("Based on speed limit, lanes of traffic, and bike lanes"),
+                Line("Roads where bikes can't go aren't shown").secondary(),
+            ])
+            .into_widget(ctx),
+        )
+    }
+
+    pub fn curb_ramps(ctx: &mut EventCtx, app: &App) -> Static {
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("every crossing has ramps", Color::GREEN),
+                ("some crossings lack ramps", Color::RED),
+                ("unknown", Color::grey(0.5)),
+            ],
+        );
+
+        let map = &app.primary.map;
+        let mut num_missing = 0;
+        for i in map.all_intersections() {
+            let mut any_unknown = false;
+            let mut any_missing = false;
+            for t in &i.turns {
+                if !t.turn_type.pedestrian_crossing() {
+                    continue;
+                }
+                match t.curb_ramps(map) {
+                    Some(true) => {}
+                    Some(false) => {
+                        any_missing = true;
+                    }
+                    None => {
+                        any_unknown = true;
+                    }
+                }
+            }
+            if any_missing {
+                num_missing += 1;
+                colorer.add_i(i.id, "some crossings lack ramps");
+            } else if any_unknown {
+                colorer.add_i(i.id, "unknown");
+            } else if i.turns.iter().any(|t| t.turn_type.pedestrian_crossing()) {
+                colorer.add_i(i.id, "every crossing has ramps");
+            }
+        }
+
+        Static::new(
+            ctx,
+            app,
+            colorer,
+            "curb ramps",
+            "Curb ramps at crossings".to_string(),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} intersections have crossings without curb ramps",
+                    prettyprint_usize(num_missing)
+                )),
+                Line("From OSM kerb and wheelchair tags on crossings").secondary(),
+            ])
+            .into_widget(ctx),
+        )
+    }
+}
+
+pub const TRAFFIC_STRESS_LABELS: [&str; 4] = [
+    "LTS 1: all ages",
+    "LTS 2: most adults",
+    "LTS 3: confident cyclists",
+    "LTS 4: only the fearless",
+];
+
+/// Shows the level of traffic stress from the `traffic_stress` layer in lane info panels
+pub const TRAFFIC_STRESS_ROWS: InfoPanelRows = InfoPanelRows {
+    name: "traffic stress",
+    rows: traffic_stress_rows,
+};
+
+fn traffic_stress_rows(app: &App, id: &ID) -> Vec<(String, String)> {
+    let map = &app.primary.map;
+    if let ID::Lane(l) = id {
+        let lane = map.get_l(*l);
+        if let Some(lts) = map.get_parent(*l).bike_traffic_stress(map, lane.dir) {
+            return vec![(
+                "Traffic stress for biking".to_string(),
+                TRAFFIC_STRESS_LABELS[lts - 1].to_string(),
+            )];
+        }
+    }
+    Vec::new()
+}
+
+/// Colors for the levels of traffic stress from `Road::bike_traffic_stress`, from 1 to 4.
+pub fn traffic_stress_color(lts: usize) -> Color {
+    match lts {
+        1 => Color::hex("#1A9641"),
+        2 => Color::hex("#A6D96A"),
+        3 => Color::hex("#FDAE61"),
+        _ => Color::hex("#D7191C"),
+    }
 }
@@ -1,13 +1,14 @@
 use map_gui::tools::{grey_out_map, HeatmapOptions};
 use sim::AgentType;
 use widgetry::{
-    DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Image, Key, Line, Outcome, Panel, State,
-    TextExt, VerticalAlignment, Widget,
+    DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Image, Key, Line, Outcome, Panel, Slider,
+    State, TextExt, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, Transition};
 use crate::sandbox::dashboards;
 
+mod bike_share;
 pub mod elevation;
 pub mod favorites;
 pub mod map;
@@ -110,14 +111,17 @@ impl PickLayer {
                     btn("traffic jams", Key::J),
                     btn("cycling activity", Key::B),
                     btn("pedestrian crowding", Key::C),
+                    btn("safety", Key::Num1),
                 ]),
                 Widget::col(vec![
                     "Map".text_widget(ctx),
                     btn("map edits", Key::E),
                     btn("parking occupancy", Key::P),
                     btn("transit network", Key::U),
+                    btn("bike share", Key::I),
                     btn("population map", Key::X),
                     btn("no sidewalks", Key::S),
+                    btn("curb ramps", Key::Q),
                     btn("favorite buildings", Key::F),
                 ]),
             ])
@@ -136,6 +140,7 @@ impl PickLayer {
                     btn("blackholes", Key::L),
                     btn("problem map", Key::K),
                     btn("high stress", Key::H),
+                    btn("traffic stress", Key::W),
                     if app.primary.sim.get_pandemic_model().is_some() {
                         btn("pandemic model", Key::Y)
                     } else {
@@ -151,6 +156,8 @@ impl PickLayer {
             .evenly_spaced(),
         );
 
+        col.push(blending_controls(ctx, app));
+
         Box::new(PickLayer {
             panel: Panel::new_builder(Widget::col(col))
                 .exact_size_percent(35, 70)
@@ -170,6 +177,13 @@ impl State<App> for PickLayer {
                 "amenities" => {
                     app.primary.layer = Some(Box::new(map::Static::amenities(ctx, app)));
                 }
+                "bike share" => {
+                    app.primary.layer = Some(Box::new(bike_share::BikeShare::new(
+                        ctx,
+                        app,
+                        bike_share::Options { demand: None },
+                    )));
+                }
                 "backpressure" => {
                     app.primary.layer = Some(Box::new(traffic::Backpressure::new(ctx, app)));
                 }
@@ -194,9 +208,15 @@ impl State<App> for PickLayer {
                 "no sidewalks" => {
                     app.primary.layer = Some(Box::new(map::Static::no_sidewalks(ctx, app)));
                 }
+                "curb ramps" => {
+                    app.primary.layer = Some(Box::new(map::Static::curb_ramps(ctx, app)));
+                }
                 "high stress" => {
                     app.primary.layer = Some(Box::new(map::Static::high_stress(ctx, app)));
                 }
+                "traffic stress" => {
+                    app.primary.layer = Some(Box::new(map::Static::traffic_stress(ctx, app)));
+                }
                 "favorite buildings" => {
                     app.primary.layer = Some(Box::new(favorites::ShowFavorites::new(ctx, app)));
                 }
@@ -230,6 +250,18 @@ impl State<App> for PickLayer {
                         },
                     )));
                 }
+                "safety" => {
+                    // Compare with the baseline when possible, to judge proposals by safety
+                    let types = problems_diff::ProblemTypes::safety();
+                    let layer: Box<dyn Layer> = if app.has_prebaked().is_some() {
+                        Box::new(problems_diff::RelativeProblemMap::new(ctx, app, types))
+                    } else {
+                        let mut opts = problems::Options::new(app);
+                        opts.types = types;
+                        Box::new(problems::ProblemMap::new(ctx, app, opts))
+                    };
+                    app.primary.layer = Some(layer);
+                }
                 "problem map" => {
                     app.primary.layer = Some(Box::new(problems::ProblemMap::new(
                         ctx,
@@ -252,6 +284,14 @@ impl State<App> for PickLayer {
                         ctx, app, false, true, true,
                     )));
                 }
+                "keep this layer underneath" => {
+                    app.primary.background_layer = app.primary.layer.take();
+                    app.primary.layer_opacity = 1.0;
+                    return Transition::Replace(PickLayer::pick(ctx, app));
+                }
+                "remove the layer underneath" => {
+                    app.primary.background_layer = None;
+                }
                 "traffic signal demand" => {
                     return Transition::Replace(dashboards::TrafficSignalDemand::new_state(
                         ctx, app,
@@ -262,6 +302,19 @@ impl State<App> for PickLayer {
                 }
                 _ => unreachable!(),
             },
+            Outcome::Changed(x) => {
+                let pct = self.panel.slider(&x).get_percent();
+                match x.as_ref() {
+                    "layer opacity" => {
+                        app.primary.layer_opacity = pct;
+                    }
+                    "background layer opacity" => {
+                        app.primary.background_layer_opacity = pct;
+                    }
+                    _ => unreachable!(),
+                }
+                return Transition::Keep;
+            }
             _ => {
                 if self.panel.clicked_outside(ctx) {
                     return Transition::Pop;
@@ -282,6 +335,75 @@ impl State<App> for PickLayer {
     }
 }
 
+fn blending_controls(ctx: &mut EventCtx, app: &App) -> Widget {
+    let slider_width = 0.1 * ctx.canvas.window_width;
+    let mut col = Vec::new();
+    if app.primary.layer.is_some() {
+        col.push(Widget::row(vec![
+            "Opacity:".text_widget(ctx).centered_vert(),
+            Slider::area(
+                ctx,
+                slider_width,
+                app.primary.layer_opacity,
+                "layer opacity",
+            ),
+            ctx.style()
+                .btn_outline
+                .text("keep this layer underneath")
+                .build_def(ctx),
+        ]));
+    }
+    if let Some(ref l) = app.primary.background_layer {
+        col.push(Widget::row(vec![
+            format!("Underneath: {}", l.name().unwrap_or("layer"))
+                .text_widget(ctx)
+                .centered_vert(),
+            Slider::area(
+                ctx,
+                slider_width,
+                app.primary.background_layer_opacity,
+                "background layer opacity",
+            ),
+            ctx.style()
+                .btn_outline
+                .text("remove the layer underneath")
+                .build_def(ctx),
+        ]));
+    }
+    if col.is_empty() {
+        return Widget::nothing();
+    }
+    col.insert(0, "Blending".text_widget(ctx));
+    Widget::col(col).section(ctx)
+}
+
+/// Draws the layer underneath (if any), then the current layer, each faded by its opacity. The
+/// current layer's panel is never faded.
+pub fn draw_layers(g: &mut GfxCtx, app: &App) {
+    if let Some(ref l) = app.primary.background_layer {
+        g.set_opacity(app.primary.background_layer_opacity);
+        l.draw_minimap(g);
+    }
+    if let Some(ref l) = app.primary.layer {
+        g.set_opacity(app.primary.layer_opacity);
+        l.draw(g, app);
+    }
+    g.set_opacity(1.0);
+}
+
+/// Like `draw_layers`, but just the contents, for the minimap.
+pub fn draw_layers_minimap(g: &mut GfxCtx, app: &App) {
+    if let Some(ref l) = app.primary.background_layer {
+        g.set_opacity(app.primary.background_layer_opacity);
+        l.draw_minimap(g);
+    }
+    if let Some(ref l) = app.primary.layer {
+        g.set_opacity(app.primary.layer_opacity);
+        l.draw_minimap(g);
+    }
+    g.set_opacity(1.0);
+}
+
 /// Creates the top row for any layer panel.
 pub fn header(ctx: &mut EventCtx, name: &str) -> Widget {
     Widget::row(vec![
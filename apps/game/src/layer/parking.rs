@@ -227,7 +227,9 @@ impl Occupancy {
                 app.cs.parking_trip,
                 looking_for_parking,
             ),
-            ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0%", "100%"]),
+            app.cs
+                .good_to_bad_red
+                .make_legend(ctx, 0.0, 100.0, |x| format!("{}%", x)),
         ]))
         .aligned_pair(PANEL_PLACEMENT)
         .build(ctx);
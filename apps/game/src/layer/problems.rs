@@ -251,7 +251,7 @@ fn export_raw_problems(app: &App) -> Result<String> {
         map.get_name().as_filename(),
         app.primary.sim.time().as_filename()
     );
-    let mut out = String::new();
+    let mut out = app.primary.sim.run_metadata().to_csv_comment();
     writeln!(out, "trip_id,time,problem_type,longitude,latitude,osm_url")?;
     for (trip, problems) in &app.primary.sim.get_analytics().problems_per_trip {
         for (time, problem) in problems {
@@ -264,7 +264,10 @@ fn export_raw_problems(app: &App) -> Result<String> {
                     Traversable::Lane(l) => map.get_r(l.road).orig_id.to_string(),
                     Traversable::Turn(t) => map.get_i(t.parent).orig_id.to_string(),
                 },
-                Problem::ArterialIntersectionCrossing(t) => map.get_i(t.parent).orig_id.to_string(),
+                Problem::ArterialIntersectionCrossing(t)
+                | Problem::StopSignViolation(t)
+                | Problem::NearMiss(t, _)
+                | Problem::HardBraking(t) => map.get_i(t.parent).orig_id.to_string(),
             };
             writeln!(
                 out,
@@ -7,8 +7,7 @@ use map_gui::tools::ColorNetwork;
 use map_model::{IntersectionID, RoadID, Traversable};
 use sim::{Problem, ProblemType};
 use widgetry::mapspace::ToggleZoomed;
-use widgetry::tools::DivergingScale;
-use widgetry::{Color, EventCtx, GfxCtx, Outcome, Panel, Text, Toggle, Widget};
+use widgetry::{EventCtx, GfxCtx, Outcome, Panel, Text, Toggle, Widget};
 
 use crate::app::App;
 use crate::layer::{header, problems, Layer, LayerOutcome, PANEL_PLACEMENT};
@@ -132,7 +131,10 @@ impl RelativeProblemMap {
                             }
                         }
                     }
-                    Problem::ArterialIntersectionCrossing(t) => {
+                    Problem::ArterialIntersectionCrossing(t)
+                    | Problem::StopSignViolation(t)
+                    | Problem::NearMiss(t, _)
+                    | Problem::HardBraking(t) => {
                         intersections.inc(t.parent);
                     }
                 }
@@ -160,9 +162,7 @@ impl RelativeProblemMap {
 
         let mut colorer = ColorNetwork::new(app);
 
-        let scale = DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
-            .range(0.0, 2.0)
-            .ignore(0.7, 1.3);
+        let scale = app.cs.better_to_worse().range(0.0, 2.0).ignore(0.7, 1.3);
 
         for (r, before, after) in before_road.clone().compare(after_road.clone()) {
             if let Some(c) = scale.eval((after as f64) / (before as f64)) {
@@ -219,6 +219,16 @@ impl ProblemTypes {
         }
     }
 
+    /// Only show the surrogate safety measures
+    pub fn safety() -> Self {
+        Self {
+            disabled_types: ProblemType::all()
+                .into_iter()
+                .filter(|pt| !pt.is_safety())
+                .collect(),
+        }
+    }
+
     pub fn show(&self, problem: &Problem) -> bool {
         !self.disabled_types.contains(&ProblemType::from(problem))
     }
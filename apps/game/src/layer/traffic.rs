@@ -11,7 +11,7 @@ use map_model::{IntersectionID, Map, Traversable};
 use sim::{AgentType, VehicleType};
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::mapspace::{DummyID, World};
-use widgetry::tools::{ColorLegend, DivergingScale, PopupMsg};
+use widgetry::tools::{ColorLegend, PopupMsg};
 use widgetry::{Color, EventCtx, GfxCtx, Line, Outcome, Panel, Text, TextExt, Toggle, Widget};
 
 use crate::app::{App, Transition};
@@ -69,11 +69,11 @@ impl Backpressure {
             )
             .wrap_to_pct(ctx, 15)
             .into_widget(ctx),
-            ColorLegend::gradient(
-                ctx,
-                &app.cs.good_to_bad_red,
-                vec!["lowest count", "highest"],
-            ),
+            app.cs
+                .good_to_bad_red
+                .make_legend(ctx, 0.0, cnt_per_r.max() as f64, |x| {
+                    prettyprint_usize(x as usize)
+                }),
         ]))
         .aligned_pair(PANEL_PLACEMENT)
         .build(ctx);
@@ -381,9 +381,7 @@ impl CompareThroughput {
 
         let mut colorer = ColorNetwork::new(app);
 
-        let scale = DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
-            .range(0.0, 2.0)
-            .ignore(0.7, 1.3);
+        let scale = app.cs.better_to_worse().range(0.0, 2.0).ignore(0.7, 1.3);
 
         for (r, before, after) in before_road.compare(after_road) {
             if let Some(c) = scale.eval((after as f64) / (before as f64)) {
@@ -611,7 +609,13 @@ impl Delay {
             draw: draw.build(ctx),
             panel: Panel::new_builder(Widget::col(vec![
                 header(ctx, "Delay per agent (minutes)"),
-                ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0", "5", "10", "15+"]),
+                app.cs.good_to_bad_red.make_legend(ctx, 0.0, 15.0, |x| {
+                    if x >= 15.0 {
+                        "15+".to_string()
+                    } else {
+                        x.to_string()
+                    }
+                }),
             ]))
             .aligned_pair(PANEL_PLACEMENT)
             .build(ctx),
@@ -749,6 +753,7 @@ impl PedestrianCrowding {
 }
 
 fn export_throughput(app: &App) -> Result<(String, String)> {
+    let metadata = app.primary.sim.run_metadata().to_csv_comment();
     let path1 = format!(
         "road_throughput_{}_{}.csv",
         app.primary.map.get_name().as_filename(),
@@ -756,11 +761,15 @@ fn export_throughput(app: &App) -> Result<(String, String)> {
     );
     let path1 = abstio::write_file(
         path1,
-        app.primary
-            .sim
-            .get_analytics()
-            .road_thruput
-            .export_csv(|id| id.0),
+        format!(
+            "{}{}",
+            metadata,
+            app.primary
+                .sim
+                .get_analytics()
+                .road_thruput
+                .export_csv(|id| id.0)
+        ),
     )?;
 
     let path2 = format!(
@@ -770,11 +779,15 @@ fn export_throughput(app: &App) -> Result<(String, String)> {
     );
     let path2 = abstio::write_file(
         path2,
-        app.primary
-            .sim
-            .get_analytics()
-            .intersection_thruput
-            .export_csv(|id| id.0),
+        format!(
+            "{}{}",
+            metadata,
+            app.primary
+                .sim
+                .get_analytics()
+                .intersection_thruput
+                .export_csv(|id| id.0)
+        ),
     )?;
 
     Ok((path1, path2))
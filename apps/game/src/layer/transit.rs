@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use map_gui::tools::ColorDiscrete;
 use map_model::{PathConstraints, PathStep};
 use widgetry::mapspace::ToggleZoomed;
@@ -8,7 +10,7 @@ use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
 
 pub struct TransitNetwork {
     panel: Panel,
-    draw: ToggleZoomed,
+    draw: Rc<ToggleZoomed>,
 }
 
 impl Layer for TransitNetwork {
@@ -96,7 +98,8 @@ impl TransitNetwork {
                 }
             }
         }
-        let (draw, legend) = colorer.build(ctx);
+        // Flipping the toggles back and forth reuses earlier uploads
+        let (draw, legend) = colorer.build_cached(ctx, &mut app.primary.static_layers.borrow_mut());
 
         let panel = Panel::new_builder(Widget::col(vec![
             header(ctx, "Transit network"),
@@ -22,6 +22,7 @@ use widgetry::{EventCtx, Settings, State, Transition};
 use crate::app::{App, Flags, PerMap};
 use crate::common::jump_to_time_upon_startup;
 use crate::id::ID;
+use crate::plugins::CustomMode;
 use crate::pregame::TitleScreen;
 use crate::sandbox::{GameplayMode, SandboxMode};
 
@@ -34,14 +35,21 @@ mod edit;
 mod id;
 mod info;
 mod layer;
+pub mod plugins;
 mod pregame;
 mod render;
 mod sandbox;
 mod ungap;
 
 pub fn main() {
+    main_with_modes(Vec::new());
+}
+
+/// Runs the game with some extra top-level modes, selected with `--mode`. See `plugins` for how to
+/// write one.
+pub fn main_with_modes(custom_modes: Vec<CustomMode>) {
     let settings = Settings::new("A/B Street");
-    run(settings);
+    run(settings, custom_modes);
 }
 
 #[derive(StructOpt)]
@@ -120,6 +128,16 @@ struct Args {
     /// Start in a tool for comparing traffic counts
     #[structopt(long)]
     compare_counts: Option<Vec<String>>,
+    /// Start in a custom mode registered by `main_with_modes`
+    #[structopt(long = "mode")]
+    custom_mode: Option<String>,
+    /// Screenshot every place these map edits change, before and after, then quit. This should be
+    /// the name of edits, not a full path. The results go in `screenshots/edits/`.
+    #[structopt(long)]
+    screenshot_edits: Option<String>,
+    /// With `--screenshot-edits`, also write an HTML page showing the pairs side-by-side
+    #[structopt(long)]
+    screenshot_gallery: bool,
 }
 
 struct Setup {
@@ -131,6 +149,7 @@ struct Setup {
     start_time: Option<Duration>,
     diff_map: Option<String>,
     mode: Mode,
+    custom_modes: Vec<CustomMode>,
 }
 
 // TODO Switch to explicit enum subcommands, each of which includes precisely the set of common
@@ -147,9 +166,12 @@ enum Mode {
     LoadKML(String),
     CompareCounts(String, String),
     Gameplay(GameplayMode),
+    Custom(String),
+    /// Also write an HTML gallery?
+    ScreenshotEdits(bool),
 }
 
-fn run(mut settings: Settings) {
+fn run(mut settings: Settings, custom_modes: Vec<CustomMode>) {
     abstutil::logger::setup();
 
     settings = settings
@@ -194,9 +216,25 @@ fn run(mut settings: Settings) {
                 panic!("--compare-counts takes exactly two paths");
             }
             Mode::CompareCounts(paths.remove(0), paths.remove(0))
+        } else if let Some(name) = args.custom_mode {
+            if !custom_modes.iter().any(|m| m.name == name) {
+                panic!(
+                    "Invalid --mode={}. Choices: {}",
+                    name,
+                    custom_modes
+                        .iter()
+                        .map(|m| m.name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            Mode::Custom(name)
+        } else if args.screenshot_edits.is_some() {
+            Mode::ScreenshotEdits(args.screenshot_gallery)
         } else {
             Mode::SomethingElse
         },
+        custom_modes,
     };
 
     setup.opts.toggle_day_night_colors = true;
@@ -236,6 +274,14 @@ fn run(mut settings: Settings) {
             panic!("Invalid --challenge={}. Choices: {}", x, aliases.join(", "));
         }
     }
+    if let Some(edits) = args.screenshot_edits {
+        if cfg!(target_arch = "wasm32") {
+            panic!("--screenshot-edits only works natively");
+        }
+        // Load the edits like usual, then screenshot without and with them
+        assert!(setup.start_with_edits.is_none());
+        setup.start_with_edits = Some(edits);
+    }
     if let Some(n) = args.tutorial {
         setup.initialize_tutorial = true;
         setup.mode = Mode::Gameplay(sandbox::GameplayMode::Tutorial(
@@ -292,6 +338,7 @@ fn setup_app(ctx: &mut EventCtx, mut setup: Setup) -> (App, Vec<Box<dyn State<Ap
             setup.flags.sim_flags.load = default.last_map.path();
         }
     }
+    sandbox::load_saved_sim_options(&mut setup.flags.sim_flags);
 
     // If we're starting directly in a challenge mode, the tutorial, or by playing a scenario,
     // usually time is midnight, so save some effort and start with the correct color scheme. If
@@ -309,7 +356,8 @@ fn setup_app(ctx: &mut EventCtx, mut setup: Setup) -> (App, Vec<Box<dyn State<Ap
     if setup.mode != Mode::SomethingElse {
         setup.opts.color_scheme = map_gui::colors::ColorSchemeChoice::DayMode;
     }
-    let cs = map_gui::colors::ColorScheme::new(ctx, setup.opts.color_scheme);
+    let mut cs = map_gui::colors::ColorScheme::new(ctx, setup.opts.color_scheme);
+    cs.set_layer_palette(setup.opts.layer_palette, setup.opts.layer_patterns);
 
     // No web support; this uses blocking IO
     let secondary = setup.diff_map.as_ref().map(|path| {
@@ -352,6 +400,7 @@ fn setup_app(ctx: &mut EventCtx, mut setup: Setup) -> (App, Vec<Box<dyn State<Ap
             opts: setup.opts.clone(),
             per_obj: crate::app::PerObjectActions::new(),
             session: crate::app::SessionState::empty(),
+            audio: crate::common::Audio::new(),
         };
         let map_name = MapName::from_path(&app.primary.current_flags.sim_flags.load).unwrap();
         let states = vec![map_gui::load::MapLoader::new_state(
@@ -380,6 +429,7 @@ fn setup_app(ctx: &mut EventCtx, mut setup: Setup) -> (App, Vec<Box<dyn State<Ap
             opts: setup.opts.clone(),
             per_obj: crate::app::PerObjectActions::new(),
             session: crate::app::SessionState::empty(),
+            audio: crate::common::Audio::new(),
         };
 
         let states = continue_app_setup(ctx, &mut app, title, setup, None);
@@ -509,6 +559,14 @@ fn finish_app_setup(
         crate::sandbox::gameplay::Tutorial::initialize(ctx, app);
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Mode::ScreenshotEdits(gallery) = setup.mode {
+        let edits = app.primary.map.get_edits().clone();
+        return vec![debug::EditScreenshots::new_state(
+            ctx, app, edits, gallery, true,
+        )];
+    }
+
     if title {
         return vec![TitleScreen::new_state(ctx, app)];
     }
@@ -576,6 +634,16 @@ fn finish_app_setup(
                     ctx, app, path1, path2,
                 )
             }
+            Mode::Custom(name) => {
+                let mode = setup
+                    .custom_modes
+                    .into_iter()
+                    .find(|m| m.name == name)
+                    .unwrap();
+                (mode.start)(ctx, app)
+            }
+            // Handled above
+            Mode::ScreenshotEdits(_) => unreachable!(),
         }
     };
     vec![TitleScreen::new_state(ctx, app), state]
@@ -592,5 +660,5 @@ pub fn run_wasm(root_dom_id: String, assets_base_url: String, assets_are_gzipped
         .assets_base_url(assets_base_url)
         .assets_are_gzipped(assets_are_gzipped);
 
-    run(settings);
+    run(settings, Vec::new());
 }
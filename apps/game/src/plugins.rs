@@ -0,0 +1,69 @@
+//! Other crates can add their own top-level modes to A/B Street, like a dashboard for one study or
+//! an editor for some domain-specific data, without forking this crate. Write a binary that calls
+//! `game::main_with_modes`, then start the mode with `--mode=name` and a map:
+//!
+//! ```no_run
+//! use game::plugins::{App, CustomMode, EventCtx, GfxCtx, State, Transition};
+//!
+//! struct Hello;
+//!
+//! impl State<App> for Hello {
+//!     fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+//!         ctx.canvas_movement();
+//!         Transition::Keep
+//!     }
+//!
+//!     fn draw(&self, _: &mut GfxCtx, _: &App) {}
+//! }
+//!
+//! fn main() {
+//!     game::main_with_modes(vec![CustomMode {
+//!         name: "hello",
+//!         start: |_, _| Box::new(Hello),
+//!     }]);
+//! }
+//! ```
+//!
+//! Modes should reach the map, simulation, and drawing through the `AppLike` methods on `App` and
+//! the widgetry `State` trait. Those are stable; other fields of `App` may change.
+//!
+//! Layers and analyses can also add rows to the info panels of map objects, without touching the
+//! code for each panel. Register them with `App::add_info_panel_rows`.
+
+use std::fmt;
+
+pub use map_gui::AppLike;
+pub use widgetry::{EventCtx, GfxCtx, State};
+
+pub use crate::app::{App, Transition};
+pub use crate::id::ID;
+
+/// A top-level mode added by another crate
+#[derive(Clone)]
+pub struct CustomMode {
+    /// Selects this mode on the command line, like `--mode=hello`
+    pub name: &'static str,
+    /// Creates the first state of the mode, once the map is loaded
+    pub start: fn(&mut EventCtx, &mut App) -> Box<dyn State<App>>,
+}
+
+impl fmt::Debug for CustomMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CustomMode({})", self.name)
+    }
+}
+
+/// Extra rows for the main tab of some objects' info panels
+#[derive(Clone)]
+pub struct InfoPanelRows {
+    /// Describes who contributes the rows, for debugging
+    pub name: &'static str,
+    /// Returns (label, value) rows about an object, or nothing if this doesn't apply to it
+    pub rows: fn(&App, &ID) -> Vec<(String, String)>,
+}
+
+impl fmt::Debug for InfoPanelRows {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "InfoPanelRows({})", self.name)
+    }
+}
@@ -3,12 +3,12 @@ use std::collections::HashMap;
 
 use aabb_quadtree::QuadTree;
 
-use geom::{Circle, Pt2D, Time};
+use geom::{Angle, Circle, Distance, Pt2D, Time};
 use map_gui::colors::ColorScheme;
 use map_gui::options::Options;
 use map_model::{Map, Traversable};
 use sim::{AgentID, Sim, UnzoomedAgent, VehicleType};
-use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Panel, Prerender};
+use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Instance, InstancedDrawable, Panel, Prerender};
 
 use crate::render::{
     draw_vehicle, unzoomed_agent_radius, DrawPedCrowd, DrawPedestrian, GameRenderable,
@@ -22,8 +22,18 @@ pub struct AgentCache {
     time: Option<Time>,
     agents_per_on: HashMap<Traversable, Vec<Box<dyn GameRenderable>>>,
     // when either of (time, unzoomed agent filters) change, recalculate (a quadtree of all agents,
-    // draw all agents)
-    unzoomed: Option<(Time, UnzoomedAgents, QuadTree<AgentID>, Drawable)>,
+    // where to draw the circles for vehicles and pedestrians)
+    unzoomed: Option<(
+        Time,
+        UnzoomedAgents,
+        QuadTree<AgentID>,
+        (Vec<Instance>, Vec<Instance>),
+    )>,
+    // The circles for vehicles and pedestrians never change, so upload them once and just move
+    // copies around
+    circles: Option<(InstancedDrawable, InstancedDrawable)>,
+    // Like unzoomed, but also recalculated when the level of detail changes
+    density: Option<(Time, UnzoomedAgents, Distance, Drawable)>,
 }
 
 impl AgentCache {
@@ -33,6 +43,8 @@ impl AgentCache {
             time: None,
             agents_per_on: HashMap::new(),
             unzoomed: None,
+            circles: None,
+            density: None,
         }
     }
 
@@ -80,7 +92,7 @@ impl AgentCache {
     }
 
     /// If the sim time has changed or the unzoomed agent filters have been modified, recalculate
-    /// the quadtree and drawable for all unzoomed agents.
+    /// the quadtree and instances for all unzoomed agents.
     pub fn calculate_unzoomed_agents<P: AsRef<Prerender>>(
         &mut self,
         prerender: &mut P,
@@ -99,16 +111,11 @@ impl AgentCache {
         if recalc {
             let highlighted = sim.get_highlighted_people();
 
-            let mut batch = GeomBatch::new();
             let mut quadtree = QuadTree::default(map.get_bounds().as_bbox());
-            // It's quite silly to produce triangles for the same circle over and over again. ;)
-            let car_circle = Circle::new(
-                Pt2D::new(0.0, 0.0),
-                unzoomed_agent_radius(Some(VehicleType::Car)),
-            )
-            .to_polygon();
-            let ped_circle =
-                Circle::new(Pt2D::new(0.0, 0.0), unzoomed_agent_radius(None)).to_polygon();
+            let mut cars = Vec::new();
+            let mut peds = Vec::new();
+            let car_radius = unzoomed_agent_radius(Some(VehicleType::Car));
+            let ped_radius = unzoomed_agent_radius(None);
 
             for agent in sim.get_unzoomed_agents(map) {
                 if let Some(mut color) = self.unzoomed_agents.color(&agent, cs) {
@@ -122,19 +129,39 @@ impl AgentCache {
                         color = color.tint(0.5);
                     }
 
-                    let circle = if agent.id.to_vehicle_type().is_some() {
-                        car_circle.translate(agent.pos.x(), agent.pos.y())
+                    let (radius, list) = if agent.id.to_vehicle_type().is_some() {
+                        (car_radius, &mut cars)
                     } else {
-                        ped_circle.translate(agent.pos.x(), agent.pos.y())
+                        (ped_radius, &mut peds)
                     };
-                    quadtree.insert_with_box(agent.id, circle.get_bounds().as_bbox());
-                    batch.push(color, circle);
+                    quadtree.insert_with_box(
+                        agent.id,
+                        Circle::new(agent.pos, radius).get_bounds().as_bbox(),
+                    );
+                    list.push(Instance {
+                        pt: agent.pos,
+                        angle: Angle::ZERO,
+                        scale: 1.0,
+                        color,
+                    });
                 }
             }
 
-            let draw = prerender.as_ref().upload(batch);
+            self.unzoomed = Some((now, self.unzoomed_agents.clone(), quadtree, (cars, peds)));
+        }
 
-            self.unzoomed = Some((now, self.unzoomed_agents.clone(), quadtree, draw));
+        if self.circles.is_none() {
+            // It's quite silly to produce triangles for the same circle over and over again. ;)
+            let circle = |radius| {
+                prerender.as_ref().upload_instanced(GeomBatch::from(vec![(
+                    Color::WHITE,
+                    Circle::new(Pt2D::new(0.0, 0.0), radius).to_polygon(),
+                )]))
+            };
+            self.circles = Some((
+                circle(unzoomed_agent_radius(Some(VehicleType::Car))),
+                circle(unzoomed_agent_radius(None)),
+            ));
         }
 
         &self.unzoomed.as_ref().unwrap().2
@@ -149,7 +176,10 @@ impl AgentCache {
         opts: &Options,
     ) {
         self.calculate_unzoomed_agents(g, map, sim, cs);
-        g.redraw(&self.unzoomed.as_ref().unwrap().3);
+        let (cars, peds) = &self.unzoomed.as_ref().unwrap().3;
+        let (car_circle, ped_circle) = self.circles.as_ref().unwrap();
+        g.draw_instances(car_circle, cars);
+        g.draw_instances(ped_circle, peds);
 
         if opts.debug_all_agents {
             let mut cnt = 0;
@@ -165,6 +195,73 @@ impl AgentCache {
             // Pedestrians aren't the ones crashing
         }
     }
+
+    /// When the camera is zoomed out far, individual agents are smaller than a pixel. Instead,
+    /// group them into a grid and draw one dot per cell, sized by how many agents are there.
+    /// `resolution` is roughly how much distance one pixel covers.
+    pub fn draw_agent_density(
+        &mut self,
+        g: &mut GfxCtx,
+        map: &Map,
+        sim: &Sim,
+        cs: &ColorScheme,
+        resolution: Distance,
+    ) {
+        let now = sim.time();
+        let mut recalc = true;
+        if let Some((time, ref orig_agents, orig_resolution, _)) = self.density {
+            if now == time && self.unzoomed_agents == *orig_agents && resolution == orig_resolution
+            {
+                recalc = false;
+            }
+        }
+
+        if recalc {
+            // Each cell is a few pixels wide
+            let cell_size = 8.0 * resolution.inner_meters();
+            let mut cells: HashMap<(i64, i64), Vec<Color>> = HashMap::new();
+            for agent in sim.get_unzoomed_agents(map) {
+                if let Some(color) = self.unzoomed_agents.color(&agent, cs) {
+                    let key = (
+                        (agent.pos.x() / cell_size).floor() as i64,
+                        (agent.pos.y() / cell_size).floor() as i64,
+                    );
+                    cells.entry(key).or_default().push(color);
+                }
+            }
+
+            let mut batch = GeomBatch::new();
+            for ((x, y), colors) in cells {
+                // Use the most common type of agent in the cell
+                let mut counts: Vec<(Color, usize)> = Vec::new();
+                for color in &colors {
+                    if let Some(pair) = counts.iter_mut().find(|(c, _)| c == color) {
+                        pair.1 += 1;
+                    } else {
+                        counts.push((*color, 1));
+                    }
+                }
+                let color = counts.into_iter().max_by_key(|(_, cnt)| *cnt).unwrap().0;
+
+                // 10 or more agents fill the whole cell, but always keep the dot visible
+                let radius = (0.5 * cell_size * (colors.len() as f64 / 10.0).sqrt().min(1.0))
+                    .max(1.5 * resolution.inner_meters());
+                let center = Pt2D::new((x as f64 + 0.5) * cell_size, (y as f64 + 0.5) * cell_size);
+                batch.push(
+                    color,
+                    Circle::new(center, Distance::meters(radius)).to_polygon(),
+                );
+            }
+            self.density = Some((
+                now,
+                self.unzoomed_agents.clone(),
+                resolution,
+                g.upload(batch),
+            ));
+        }
+
+        g.redraw(&self.density.as_ref().unwrap().3);
+    }
 }
 
 #[derive(PartialEq, Clone)]
@@ -6,6 +6,7 @@ use map_model::Map;
 use sim::{CarID, DrawCarInput, Intent, Sim};
 use widgetry::{Drawable, GeomBatch, GfxCtx, Prerender};
 
+use crate::render::vehicle_models::bike_frame_color;
 use crate::render::{grey_out_unhighlighted_people, GameRenderable};
 use crate::ID;
 
@@ -34,10 +35,8 @@ impl DrawBike {
             &input.person,
             sim,
         );
-        draw_default.push(
-            cs.bike_frame,
-            input.body.make_polygons(Distance::meters(0.4)),
-        );
+        let frame_color = bike_frame_color(input.id, cs);
+        draw_default.push(frame_color, input.body.make_polygons(Distance::meters(0.4)));
 
         let err = format!("{} on {} has weird body", input.id, input.on);
         let (body_pos, facing) = input
@@ -58,7 +57,7 @@ impl DrawBike {
                 .dist_along(0.9 * input.body.length())
                 .expect(&err);
             draw_default.push(
-                cs.bike_frame,
+                frame_color,
                 Line::must_new(
                     hand_pos.project_away(body_radius, hand_angle.rotate_degs(90.0)),
                     hand_pos.project_away(body_radius, hand_angle.rotate_degs(-90.0)),
@@ -6,6 +6,7 @@ use map_model::{Map, TurnType};
 use sim::{CarID, CarStatus, DrawCarInput, Intent, Sim, VehicleType};
 use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Line, Prerender, Text};
 
+use crate::render::vehicle_models::CarModel;
 use crate::render::{grey_out_unhighlighted_people, GameRenderable};
 use crate::ID;
 
@@ -58,12 +59,13 @@ impl DrawCar {
         }
 
         let body_polygon = input.body.make_polygons(CAR_WIDTH);
+        let model = CarModel::for_car(input.id);
 
-        let draw_body = if input.body.length() < Distance::meters(1.1) {
+        let draw_body = if input.body.length() < model.front_taper() + Distance::meters(0.1) {
             // Simpler shape while appearing from a border
             Tessellation::from(body_polygon.clone())
         } else {
-            let front_corner = input.body.length() - Distance::meters(1.0);
+            let front_corner = input.body.length() - model.front_taper();
             let thick_line = Tessellation::from(
                 input
                     .body
@@ -87,6 +89,7 @@ impl DrawCar {
             }
         };
         draw_default.push(zoomed_color_car(&input, sim, cs), draw_body);
+        model.draw_details(&mut draw_default, &input.body, CAR_WIDTH, cs);
 
         if input.status == CarStatus::Parked {
             draw_default.append(
@@ -16,6 +16,7 @@ mod agents;
 mod bike;
 mod car;
 mod pedestrian;
+mod vehicle_models;
 
 // Like map_gui's Renderable, but uses our ID type
 pub trait GameRenderable {
@@ -0,0 +1,115 @@
+use geom::{Distance, PolyLine};
+use map_gui::colors::ColorScheme;
+use sim::{CarID, VehicleType};
+use widgetry::{Color, GeomBatch};
+
+/// A few body styles for cars, so mixed traffic is easier to read. The same car always looks the
+/// same, because the model is picked by hashing its ID.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CarModel {
+    Sedan,
+    Hatchback,
+    Van,
+    Pickup,
+    Bus,
+    Train,
+}
+
+impl CarModel {
+    pub fn for_car(id: CarID) -> CarModel {
+        match id.vehicle_type {
+            VehicleType::Bus => CarModel::Bus,
+            VehicleType::Train => CarModel::Train,
+            // Bikes are drawn separately, but don't crash if one winds up here
+            VehicleType::Car | VehicleType::Bike => match hash(id.id) % 20 {
+                0..=7 => CarModel::Sedan,
+                8..=12 => CarModel::Hatchback,
+                13..=16 => CarModel::Van,
+                _ => CarModel::Pickup,
+            },
+        }
+    }
+
+    /// How far back from the tip the body starts tapering
+    pub fn front_taper(self) -> Distance {
+        Distance::meters(match self {
+            CarModel::Sedan => 1.0,
+            CarModel::Hatchback => 0.8,
+            CarModel::Van | CarModel::Bus | CarModel::Train => 0.3,
+            CarModel::Pickup => 0.6,
+        })
+    }
+
+    /// Draws windows, roofs, and cargo beds over the body. `body` starts at the back of the
+    /// vehicle. Nothing is drawn while the vehicle is too short to fit the details, like when it's
+    /// appearing from a border.
+    pub fn draw_details(
+        self,
+        batch: &mut GeomBatch,
+        body: &PolyLine,
+        width: Distance,
+        cs: &ColorScheme,
+    ) {
+        let len = body.length();
+        if len < Distance::meters(3.5) {
+            return;
+        }
+        let glass = cs.vehicle_glass;
+        let m = Distance::meters;
+        // Both ends are measured from the back of the vehicle
+        let mut slice = |color: Color, start: Distance, end: Distance, width_pct: f64| {
+            if let Ok(pl) = body.maybe_exact_slice(start, end) {
+                batch.push(color, pl.make_polygons(width * width_pct));
+            }
+        };
+
+        match self {
+            CarModel::Sedan => {
+                slice(glass, m(1.0), m(1.5), 0.8);
+                slice(glass, len - m(2.3), len - m(1.6), 0.8);
+            }
+            CarModel::Hatchback => {
+                slice(glass, m(0.3), m(0.8), 0.8);
+                slice(glass, len - m(2.0), len - m(1.3), 0.8);
+            }
+            CarModel::Van => {
+                slice(cs.van_roof, m(0.5), len - m(1.5), 0.6);
+                slice(glass, len - m(1.2), len - m(0.6), 0.85);
+            }
+            CarModel::Pickup => {
+                // The cargo bed, then the back and front of the cab
+                slice(cs.pickup_bed, m(0.3), len - m(3.3), 0.8);
+                slice(glass, len - m(3.0), len - m(2.7), 0.8);
+                slice(glass, len - m(2.0), len - m(1.4), 0.8);
+            }
+            CarModel::Bus | CarModel::Train => {
+                slice(glass, len - m(0.9), len - m(0.3), 0.85);
+                // A row of windows along each side
+                for side in [
+                    body.shift_left(width * 0.35),
+                    body.shift_right(width * 0.35),
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    if let Ok(pl) = side.maybe_exact_slice(m(0.5), side.length() - m(1.5)) {
+                        batch.extend(glass, pl.dashed_lines(width * 0.2, m(1.2), m(0.4)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Bikes get a frame color that stays the same every time they appear
+pub fn bike_frame_color(id: CarID, cs: &ColorScheme) -> Color {
+    cs.bike_frames[hash(id.id) % cs.bike_frames.len()]
+}
+
+/// Scramble consecutive IDs, so vehicles spawned together don't all look alike
+fn hash(x: usize) -> usize {
+    let mut z = (x as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)) as usize
+}
@@ -0,0 +1,193 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use map_gui::tools::grey_out_map;
+use sim::{AlertCategory, AlertLocation, AlertSeverity};
+use widgetry::{
+    Color, EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Toggle, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::info::Tab;
+use crate::sandbox::SandboxMode;
+use crate::ID;
+
+/// Only list this many of the most recent alerts
+const MAX_SHOWN: usize = 50;
+
+/// Review everything unusual that's happened in the simulation so far, and mute kinds of alerts
+/// that are expected.
+pub struct AlertsPanel {
+    panel: Panel,
+    /// Where each listed alert happened, indexed by the buttons
+    locations: Vec<AlertLocation>,
+}
+
+impl AlertsPanel {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let analytics = app.primary.sim.get_analytics();
+        let muted = app.primary.sim.get_muted_alerts();
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Alerts").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from(
+                "Unusual things that've happened so far. Muted alerts are still recorded, but \
+                 aren't listed here, printed, or used to pause the simulation.",
+            )
+            .wrap_to_pct(ctx, 50)
+            .into_widget(ctx),
+        ];
+        for category in AlertCategory::all() {
+            col.push(Widget::row(vec![
+                Toggle::checkbox(ctx, category.describe(), None, !muted.contains(&category)),
+                Line(format!(
+                    "({})",
+                    analytics.alert_counts.get(&category).cloned().unwrap_or(0)
+                ))
+                .secondary()
+                .into_widget(ctx)
+                .centered_vert(),
+            ]));
+        }
+
+        let shown: Vec<_> = analytics
+            .alert_log
+            .iter()
+            .rev()
+            .filter(|alert| !muted.contains(&alert.category))
+            .collect();
+        let mut locations = Vec::new();
+        let mut rows = Vec::new();
+        for alert in shown.iter().take(MAX_SHOWN) {
+            let mut txt = Text::from(
+                Line(format!(
+                    "{} at {}",
+                    alert.category.describe(),
+                    alert.time.ampm_tostring()
+                ))
+                .fg(severity_color(ctx, app, alert.category.severity())),
+            );
+            txt.add_line(Line(&alert.msg).secondary());
+            let mut row = vec![txt.wrap_to_pct(ctx, 40).into_widget(ctx)];
+            if alert.location != AlertLocation::Nil {
+                row.push(
+                    ctx.style()
+                        .btn_plain
+                        .icon("system/assets/tools/location.svg")
+                        .build_widget(ctx, format!("jump to alert {}", locations.len() + 1))
+                        .align_right(),
+                );
+                locations.push(alert.location.clone());
+            }
+            rows.push(
+                Widget::row(row)
+                    .padding(10)
+                    .outline(ctx.style().section_outline),
+            );
+        }
+        if shown.is_empty() {
+            col.push("Nothing to report yet".text_widget(ctx));
+        } else {
+            col.push(Widget::col(rows));
+            if shown.len() > MAX_SHOWN {
+                col.push(
+                    Line(format!("... and {} older alerts", shown.len() - MAX_SHOWN))
+                        .secondary()
+                        .into_widget(ctx),
+                );
+            }
+        }
+
+        Box::new(AlertsPanel {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(60, 80)
+                .build(ctx),
+            locations,
+        })
+    }
+}
+
+impl State<App> for AlertsPanel {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if x == "close" {
+                    return Transition::Pop;
+                }
+                if let Some(idx) = x.strip_prefix("jump to alert ") {
+                    let idx = idx.parse::<usize>().unwrap() - 1;
+                    return jump_to(ctx, app, self.locations[idx].clone());
+                }
+                unreachable!()
+            }
+            Outcome::Changed(_) => {
+                let muted: BTreeSet<AlertCategory> = AlertCategory::all()
+                    .into_iter()
+                    .filter(|category| !self.panel.is_checked(category.describe()))
+                    .collect();
+                // Remember across resets, too
+                app.primary.current_flags.sim_flags.opts.muted_alerts = muted.clone();
+                app.primary.sim.set_muted_alerts(muted);
+                return Transition::Replace(AlertsPanel::new_state(ctx, app));
+            }
+            _ => {}
+        }
+
+        if self.panel.clicked_outside(ctx) {
+            return Transition::Pop;
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
+
+fn severity_color(ctx: &EventCtx, app: &App, severity: AlertSeverity) -> Color {
+    match severity {
+        AlertSeverity::Info => ctx.style().text_primary_color,
+        AlertSeverity::Warning => app.cs.alert_warning,
+        AlertSeverity::Error => ctx.style().text_destructive_color,
+    }
+}
+
+/// Warp to where an alert happened, or open the person's info panel
+fn jump_to(ctx: &mut EventCtx, app: &mut App, location: AlertLocation) -> Transition {
+    let id = match location {
+        AlertLocation::Nil => unreachable!(),
+        AlertLocation::Intersection(i) => ID::Intersection(i),
+        AlertLocation::Building(b) => ID::Building(b),
+        AlertLocation::Person(p) => {
+            return Transition::Multi(vec![
+                Transition::Pop,
+                Transition::ModifyState(Box::new(move |state, ctx, app| {
+                    if let Some(ref mut s) = state.downcast_mut::<SandboxMode>() {
+                        let mut actions = s.contextual_actions();
+                        s.controls.common.as_mut().unwrap().launch_info_panel(
+                            ctx,
+                            app,
+                            Tab::PersonTrips(p, BTreeMap::new()),
+                            &mut actions,
+                        );
+                    }
+                })),
+            ]);
+        }
+    };
+    match app.primary.canonical_point(id.clone()) {
+        Some(pt) => Transition::Replace(Warping::new_state(
+            ctx,
+            pt,
+            Some(10.0),
+            Some(id),
+            &mut app.primary,
+        )),
+        None => Transition::Keep,
+    }
+}
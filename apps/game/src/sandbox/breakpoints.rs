@@ -0,0 +1,162 @@
+use geom::Duration;
+use map_gui::tools::grey_out_map;
+use map_model::{IntersectionID, LaneID};
+use sim::{Breakpoint, TripID, TripResult};
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    EventCtx, GfxCtx, Line, Outcome, Panel, Spinner, State, Text, TextBox, TextExt, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// Manage conditions that pause the simulation, like somebody waiting too long at an intersection.
+/// When one is hit, the simulation pauses and an alert explains why.
+pub struct EditBreakpoints {
+    panel: Panel,
+}
+
+impl EditBreakpoints {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut rows = vec![
+            Widget::row(vec![
+                Line("Breakpoints").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from("Breakpoints are forgotten when the simulation is reset.")
+                .wrap_to_pct(ctx, 50)
+                .into_widget(ctx),
+        ];
+        for (idx, bp) in app.primary.sim.get_breakpoints().iter().enumerate() {
+            rows.push(
+                Widget::row(vec![
+                    format!("Pause when {}", bp.describe())
+                        .text_widget(ctx)
+                        .centered_vert(),
+                    ctx.style()
+                        .btn_solid_destructive
+                        .icon("system/assets/tools/trash.svg")
+                        .build_widget(ctx, format!("delete breakpoint {}", idx + 1))
+                        .align_right(),
+                ])
+                .padding(10)
+                .outline(ctx.style().section_outline),
+            );
+        }
+
+        rows.push(Line("Add a breakpoint").small_heading().into_widget(ctx));
+        rows.push(Widget::row(vec![
+            "Pause when any agent waits longer than"
+                .text_widget(ctx)
+                .centered_vert(),
+            Spinner::widget(
+                ctx,
+                "wait",
+                (Duration::minutes(1), Duration::hours(2)),
+                Duration::minutes(5),
+                Duration::minutes(1),
+            ),
+            ctx.style()
+                .btn_outline
+                .text("Add")
+                .build_widget(ctx, "add wait breakpoint"),
+        ]));
+        rows.push(Widget::row(vec![
+            "Pause when Lane #".text_widget(ctx).centered_vert(),
+            TextBox::default_widget(ctx, "lane", String::new()),
+            "has more than".text_widget(ctx).centered_vert(),
+            Spinner::<usize>::widget(ctx, "queue", (1, 100), 10, 1),
+            "vehicles".text_widget(ctx).centered_vert(),
+            ctx.style()
+                .btn_outline
+                .text("Add")
+                .build_widget(ctx, "add queue breakpoint"),
+        ]));
+        rows.push(Widget::row(vec![
+            "Pause when Trip #".text_widget(ctx).centered_vert(),
+            TextBox::default_widget(ctx, "trip", String::new()),
+            "reaches Intersection #".text_widget(ctx).centered_vert(),
+            TextBox::default_widget(ctx, "intersection", String::new()),
+            ctx.style()
+                .btn_outline
+                .text("Add")
+                .build_widget(ctx, "add trip breakpoint"),
+        ]));
+
+        Box::new(EditBreakpoints {
+            panel: Panel::new_builder(Widget::col(rows)).build(ctx),
+        })
+    }
+
+    /// Parses the ID fields for a new breakpoint, explaining any problem
+    fn parse_new_breakpoint(&self, app: &App, action: &str) -> Result<Breakpoint, String> {
+        let number = |name: &str| -> Result<usize, String> {
+            let value = self.panel.text_box(name);
+            value
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("\"{}\" isn't a valid {} number", value, name))
+        };
+        let map = &app.primary.map;
+        match action {
+            "add wait breakpoint" => {
+                Ok(Breakpoint::AgentWaitsLongerThan(self.panel.spinner("wait")))
+            }
+            "add queue breakpoint" => {
+                let l = LaneID::decode_u32(number("lane")? as u32);
+                if map.maybe_get_l(l).is_none() {
+                    return Err(format!("{} doesn't exist", l));
+                }
+                Ok(Breakpoint::LaneQueueExceeds(l, self.panel.spinner("queue")))
+            }
+            "add trip breakpoint" => {
+                let trip = TripID(number("trip")?);
+                if let TripResult::TripDoesntExist = app.primary.sim.trip_to_agent(trip) {
+                    return Err(format!("{} doesn't exist", trip));
+                }
+                let i = IntersectionID(number("intersection")?);
+                if map.maybe_get_i(i).is_none() {
+                    return Err(format!("{} doesn't exist", i));
+                }
+                Ok(Breakpoint::TripReachesIntersection(trip, i))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl State<App> for EditBreakpoints {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("delete breakpoint ") {
+                        let idx = idx.parse::<usize>().unwrap() - 1;
+                        app.primary.sim.remove_breakpoint(idx);
+                        return Transition::Replace(EditBreakpoints::new_state(ctx, app));
+                    }
+                    return match self.parse_new_breakpoint(app, x) {
+                        Ok(bp) => {
+                            app.primary.sim.add_breakpoint(bp);
+                            Transition::Replace(EditBreakpoints::new_state(ctx, app))
+                        }
+                        Err(err) => Transition::Push(PopupMsg::new_state(ctx, "Error", vec![err])),
+                    };
+                }
+            }
+        }
+
+        if self.panel.clicked_outside(ctx) {
+            return Transition::Pop;
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,279 @@
+use abstutil::prettyprint_usize;
+use geom::Distance;
+use map_gui::tools::FilePicker;
+use widgetry::tools::{Lasso, PopupMsg, PromptInput};
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
+    State, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::{NamedArea, NamedAreas};
+use crate::sandbox::dashboards::through_traffic::ThroughTraffic;
+use crate::sandbox::dashboards::DashTab;
+
+/// Manage the named areas defined for this map, and summarize what's inside of them.
+pub struct AreasDashboard {
+    panel: Panel,
+    areas: NamedAreas,
+    selected: Option<String>,
+    lasso: Option<Lasso>,
+    draw: Drawable,
+}
+
+impl AreasDashboard {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut state = AreasDashboard {
+            panel: Panel::empty(ctx),
+            areas: NamedAreas::load(app),
+            selected: None,
+            lasso: None,
+            draw: Drawable::empty(ctx),
+        };
+        state.update(ctx, app);
+        Box::new(state)
+    }
+
+    fn update(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+
+        let mut batch = GeomBatch::new();
+        for area in &self.areas.areas {
+            if let Some(polygon) = area.polygon(map) {
+                let color = if self.selected.as_ref() == Some(&area.name) {
+                    Color::RED
+                } else {
+                    Color::BLUE
+                };
+                batch.push(color.alpha(0.3), polygon.clone());
+                batch.push(color, polygon.to_outline(Distance::meters(3.0)));
+            }
+        }
+        self.draw = ctx.upload(batch);
+
+        let mut col = vec![
+            DashTab::Areas.picker(ctx, app),
+            Line(format!("{} named areas", self.areas.areas.len()))
+                .small_heading()
+                .into_widget(ctx),
+        ];
+        for area in &self.areas.areas {
+            col.push(Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text(&area.name)
+                    .disabled(self.selected.as_ref() == Some(&area.name))
+                    .build_widget(ctx, format!("select {}", area.name)),
+                ctx.style()
+                    .btn_solid_destructive
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, format!("delete {}", area.name))
+                    .align_right(),
+            ]));
+        }
+        if let Some(area) = self.selected.as_ref().and_then(|name| self.areas.get(name)) {
+            col.push(area_summary(ctx, app, area).section(ctx));
+        }
+        if self.lasso.is_some() {
+            col.push("Click and drag to draw the area".text_widget(ctx));
+        } else {
+            col.push(Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text("Draw new area")
+                    .hotkey(Key::A)
+                    .build_def(ctx),
+                if cfg!(not(target_arch = "wasm32")) {
+                    ctx.style()
+                        .btn_outline
+                        .text("Import GeoJSON")
+                        .build_def(ctx)
+                } else {
+                    Widget::nothing()
+                },
+            ]));
+        }
+
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .build(ctx);
+    }
+
+    fn add_areas(&mut self, ctx: &mut EventCtx, app: &App, areas: Vec<NamedArea>) {
+        for area in areas {
+            self.selected = Some(area.name.clone());
+            self.areas.add(area);
+        }
+        self.areas.save(app);
+        self.update(ctx, app);
+    }
+}
+
+impl State<App> for AreasDashboard {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(ref mut lasso) = self.lasso {
+            if let Some(polygon) = lasso.event(ctx) {
+                self.lasso = None;
+                self.update(ctx, app);
+                return Transition::Push(PromptInput::new_state(
+                    ctx,
+                    "Name this area",
+                    String::new(),
+                    Box::new(move |name, _, _| {
+                        Transition::Multi(vec![
+                            Transition::Pop,
+                            Transition::ModifyState(Box::new(move |state, ctx, app| {
+                                let area = NamedArea::new(name, &polygon, &app.primary.map);
+                                let state = state.downcast_mut::<AreasDashboard>().unwrap();
+                                state.add_areas(ctx, app, vec![area]);
+                            })),
+                        ])
+                    }),
+                ));
+            }
+            return Transition::Keep;
+        }
+
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Draw new area" => {
+                    self.lasso = Some(Lasso::new(Distance::meters(1.0)));
+                    self.update(ctx, app);
+                }
+                "Import GeoJSON" => {
+                    return Transition::Push(FilePicker::new_state(
+                        ctx,
+                        None,
+                        Box::new(|ctx, app, maybe_path| {
+                            if let Ok(Some(path)) = maybe_path {
+                                match import_geojson(app, &path) {
+                                    Ok(areas) => Transition::Multi(vec![
+                                        Transition::Pop,
+                                        Transition::ModifyState(Box::new(
+                                            move |state, ctx, app| {
+                                                let state =
+                                                    state.downcast_mut::<AreasDashboard>().unwrap();
+                                                state.add_areas(ctx, app, areas);
+                                            },
+                                        )),
+                                    ]),
+                                    Err(err) => Transition::Replace(PopupMsg::new_state(
+                                        ctx,
+                                        "Import failed",
+                                        vec![err.to_string()],
+                                    )),
+                                }
+                            } else {
+                                Transition::Pop
+                            }
+                        }),
+                    ));
+                }
+                "Analyze through-traffic" => {
+                    let area = self
+                        .areas
+                        .get(self.selected.as_ref().unwrap())
+                        .unwrap()
+                        .clone();
+                    return Transition::Push(ThroughTraffic::new_state(ctx, app, area));
+                }
+                x => {
+                    if let Some(name) = x.strip_prefix("select ") {
+                        self.selected = Some(name.to_string());
+                    } else if let Some(name) = x.strip_prefix("delete ") {
+                        self.areas.remove(name);
+                        self.areas.save(app);
+                        if self.selected.as_deref() == Some(name) {
+                            self.selected = None;
+                        }
+                    } else {
+                        unreachable!()
+                    }
+                    self.update(ctx, app);
+                }
+            },
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::Areas.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        if let Some(ref lasso) = self.lasso {
+            lasso.draw(g);
+        }
+    }
+}
+
+fn area_summary(ctx: &mut EventCtx, app: &App, area: &NamedArea) -> Widget {
+    let map = &app.primary.map;
+    let polygon = match area.polygon(map) {
+        Some(polygon) => polygon,
+        None => {
+            return "This area's boundary is invalid".text_widget(ctx);
+        }
+    };
+
+    let mut starting = 0;
+    let mut ending = 0;
+    for (_, trip) in app.primary.sim.all_trip_info() {
+        if polygon.contains_pt(trip.start.pt(map)) {
+            starting += 1;
+        }
+        if polygon.contains_pt(trip.end.pt(map)) {
+            ending += 1;
+        }
+    }
+
+    Widget::col(vec![
+        Line(&area.name).small_heading().into_widget(ctx),
+        format!(
+            "{} roads, {} intersections, {} buildings",
+            prettyprint_usize(area.roads(map).len()),
+            prettyprint_usize(area.intersections(map).len()),
+            prettyprint_usize(area.buildings(map).len())
+        )
+        .text_widget(ctx),
+        format!(
+            "{} trips start here, {} trips end here",
+            prettyprint_usize(starting),
+            prettyprint_usize(ending)
+        )
+        .text_widget(ctx),
+        ctx.style()
+            .btn_outline
+            .text("Analyze through-traffic")
+            .build_def(ctx),
+    ])
+}
+
+fn import_geojson(app: &App, path: &str) -> anyhow::Result<Vec<NamedArea>> {
+    let raw = fs_err::read_to_string(path)?;
+    let mut areas = Vec::new();
+    for (idx, (boundary, name)) in geom::LonLat::parse_geojson_polygons(raw)?
+        .into_iter()
+        .enumerate()
+    {
+        let area = NamedArea {
+            name: name.unwrap_or_else(|| format!("imported area {}", idx + 1)),
+            boundary,
+        };
+        if area.polygon(&app.primary.map).is_none() {
+            anyhow::bail!("{} has an invalid boundary", area.name);
+        }
+        areas.push(area);
+    }
+    Ok(areas)
+}
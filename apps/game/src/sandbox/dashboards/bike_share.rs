@@ -0,0 +1,147 @@
+use abstutil::{prettyprint_usize, Counter};
+use geom::{Duration, Time};
+use sim::BikeShareActivity;
+use widgetry::{
+    Color, EventCtx, GfxCtx, Line, LinePlot, Outcome, Panel, PlotOptions, Series, State, Text,
+    TextExt, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::dashboards::DashTab;
+
+/// Summarizes bike share activity per station, to see where bikes pile up or run out and how many
+/// need to be moved to restore the start-of-day distribution.
+pub struct BikeShareDashboard {
+    panel: Panel,
+}
+
+impl BikeShareDashboard {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut col = vec![
+            DashTab::BikeShare.picker(ctx, app),
+            Line("Bike share rebalancing")
+                .small_heading()
+                .into_widget(ctx),
+        ];
+        if app.primary.sim.get_bike_share_stations().is_empty() {
+            col.push(
+                "This simulation has no bike share stations. Add some from the bike share layer."
+                    .text_widget(ctx),
+            );
+        } else {
+            col.push(results(ctx, app));
+        }
+
+        Box::new(BikeShareDashboard {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for BikeShareDashboard {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => DashTab::BikeShare
+                .transition(ctx, app, &self.panel)
+                .unwrap_or(Transition::Keep),
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
+
+fn results(ctx: &mut EventCtx, app: &App) -> Widget {
+    let mut names = Text::from(Line("Station").secondary());
+    let mut docks = Text::from(Line("Docks").secondary());
+    let mut bikes = Text::from(Line("Bikes now").secondary());
+    let mut pickups = Text::from(Line("Pickups").secondary());
+    let mut returns = Text::from(Line("Returns").secondary());
+    let mut empty = Text::from(Line("Found empty").secondary());
+    let mut full = Text::from(Line("Found full").secondary());
+    let mut change = Text::from(Line("Change since midnight").secondary());
+
+    let mut per_hour: Vec<Counter<usize>> = vec![Counter::new(); 4];
+    let mut surplus = 0;
+    for station in app.primary.sim.get_bike_share_stations() {
+        let mut cnt = Counter::new();
+        for (t, activity) in &station.log {
+            let idx = match activity {
+                BikeShareActivity::Pickup => 0,
+                BikeShareActivity::Return => 1,
+                BikeShareActivity::EmptyStation => 2,
+                BikeShareActivity::FullStation => 3,
+            };
+            cnt.inc(idx);
+            per_hour[idx].inc(t.get_hours());
+        }
+        // Bikes returned to a full station are still docked there
+        let num_returns = cnt.get(1) + cnt.get(3);
+
+        names.add_line(Line(&station.name));
+        docks.add_line(prettyprint_usize(station.capacity));
+        bikes.add_line(prettyprint_usize(station.bikes));
+        pickups.add_line(prettyprint_usize(cnt.get(0)));
+        returns.add_line(prettyprint_usize(num_returns));
+        empty.add_line(prettyprint_usize(cnt.get(2)));
+        full.add_line(prettyprint_usize(cnt.get(3)));
+        if station.bikes >= station.initial_bikes {
+            surplus += station.bikes - station.initial_bikes;
+            change.add_line(format!("+{}", station.bikes - station.initial_bikes));
+        } else {
+            change.add_line(format!("-{}", station.initial_bikes - station.bikes));
+        }
+    }
+
+    let series = vec![
+        ("Pickups", Color::GREEN),
+        ("Returns", Color::BLUE),
+        ("Found empty", Color::RED),
+        ("Found full", Color::PURPLE),
+    ]
+    .into_iter()
+    .zip(per_hour)
+    .map(|((label, color), counts)| Series {
+        label: label.to_string(),
+        color,
+        pts: hourly(counts),
+    })
+    .collect();
+
+    Widget::col(vec![
+        format!(
+            "{} bikes need to be moved to restore the midnight distribution",
+            prettyprint_usize(surplus)
+        )
+        .text_widget(ctx),
+        LinePlot::new_widget(
+            ctx,
+            "bike share activity",
+            series,
+            PlotOptions::filterable(),
+            app.opts.units,
+        )
+        .section(ctx),
+        Widget::custom_row(
+            vec![names, docks, bikes, pickups, returns, empty, full, change]
+                .into_iter()
+                .map(|txt| txt.into_widget(ctx).margin_right(16))
+                .collect(),
+        ),
+    ])
+}
+
+fn hourly(counts: Counter<usize>) -> Vec<(Time, usize)> {
+    let last_hour = counts.borrow().keys().max().cloned().unwrap_or(0).max(23);
+    (0..=last_hour)
+        .map(|hour| (Time::START_OF_DAY + Duration::hours(hour), counts.get(hour)))
+        .collect()
+}
@@ -0,0 +1,464 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use abstutil::prettyprint_usize;
+use geom::{Distance, Duration, Speed, Time};
+use map_model::{DirectedRoadID, IntersectionID, Map, PathConstraints, RoadID, TransitRouteID};
+use sim::{AgentType, Analytics};
+use widgetry::tools::{Severity, Toast};
+use widgetry::{
+    EventCtx, GfxCtx, HorizontalAlignment, Key, Outcome, Panel, State, TextExt, VerticalAlignment,
+    Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::RouteSketcher;
+use crate::sandbox::dashboards::DashTab;
+
+/// Sketch a corridor along some roads, then generate a report summarizing how it performs.
+pub struct CorridorReport {
+    panel: Panel,
+    route_sketcher: RouteSketcher,
+}
+
+impl CorridorReport {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut state = CorridorReport {
+            panel: Panel::empty(ctx),
+            route_sketcher: RouteSketcher::new(app),
+        };
+        state.update_panel(ctx, app);
+        Box::new(state)
+    }
+
+    fn update_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.panel = Panel::new_builder(Widget::col(vec![
+            DashTab::CorridorReport.picker(ctx, app),
+            "Sketch a corridor along some roads to generate a report about it".text_widget(ctx),
+            self.route_sketcher.get_widget_to_describe(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Generate report")
+                .hotkey(Key::Enter)
+                .disabled(!self.route_sketcher.is_route_valid())
+                .build_def(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+    }
+}
+
+impl State<App> for CorridorReport {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if self.route_sketcher.event(ctx, app) {
+            self.update_panel(ctx, app);
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Generate report" => {
+                    let corridor = Corridor::new(self.route_sketcher.all_intersections(), app);
+                    ctx.show_toast(match corridor.export(app) {
+                        Ok(path) => Toast::new(
+                            Severity::Success,
+                            "Report generated",
+                            vec![format!("Report written to {}", path)],
+                        ),
+                        Err(err) => {
+                            Toast::new(Severity::Error, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                    return Transition::Keep;
+                }
+                x => {
+                    if self.route_sketcher.on_click(x) {
+                        self.update_panel(ctx, app);
+                        return Transition::Keep;
+                    }
+                    unreachable!()
+                }
+            },
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::CorridorReport.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+        self.route_sketcher.draw(g);
+    }
+}
+
+/// A sequence of roads, in the direction the player sketched them.
+struct Corridor {
+    intersections: Vec<IntersectionID>,
+    roads: Vec<RoadID>,
+}
+
+impl Corridor {
+    fn new(intersections: Vec<IntersectionID>, app: &App) -> Corridor {
+        let map = &app.primary.map;
+        let roads = intersections
+            .windows(2)
+            .map(|pair| map.find_road_between(pair[0], pair[1]).unwrap())
+            .collect();
+        Corridor {
+            intersections,
+            roads,
+        }
+    }
+
+    /// The directed roads traveling along the corridor, either in the sketched direction or
+    /// against it. None if some road can't be driven in that direction.
+    fn directed_roads(&self, map: &Map, forwards: bool) -> Option<Vec<DirectedRoadID>> {
+        let mut result = Vec::new();
+        for (idx, r) in self.roads.iter().enumerate() {
+            let dr = if forwards {
+                map.get_r(*r).directed_id_from(self.intersections[idx])
+            } else {
+                map.get_r(*r).directed_id_to(self.intersections[idx])
+            };
+            if dr.lanes(PathConstraints::Car, map).is_empty() {
+                return None;
+            }
+            result.push(dr);
+        }
+        if !forwards {
+            result.reverse();
+        }
+        Some(result)
+    }
+
+    fn free_flow_time(&self, map: &Map) -> Duration {
+        self.roads
+            .iter()
+            .map(|r| {
+                let road = map.get_r(*r);
+                road.length() / road.speed_limit
+            })
+            .sum()
+    }
+
+    /// Estimates the time for a car to drive along the corridor during some hour, by adding the
+    /// average delay measured at each traffic signal to the free-flow travel time.
+    fn travel_time(
+        &self,
+        map: &Map,
+        analytics: &Analytics,
+        now: Time,
+        forwards: bool,
+        hour: usize,
+    ) -> Option<Duration> {
+        let directed_roads = self.directed_roads(map, forwards)?;
+        let mut total = self.free_flow_time(map);
+        for pair in directed_roads.windows(2) {
+            let i = map.get_i(pair[0].dst_i(map));
+            if !i.is_traffic_signal() {
+                continue;
+            }
+            let idx = match i
+                .movements
+                .keys()
+                .position(|m| m.from == pair[0] && m.to == pair[1] && !m.crosswalk)
+            {
+                Some(idx) => idx,
+                None => {
+                    continue;
+                }
+            };
+            let delays = analytics
+                .intersection_delays
+                .get(&i.id)
+                .map(|list| {
+                    list.iter()
+                        .filter(|(movement, t, _, agent_type)| {
+                            *movement as usize == idx
+                                && *t <= now
+                                && t.get_hours() == hour
+                                && *agent_type == AgentType::Car
+                        })
+                        .map(|(_, _, dt, _)| *dt)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            total += mean(&delays);
+        }
+        Some(total)
+    }
+
+    /// Returns the number of measurements and the mean delay at one intersection.
+    fn signal_delay(analytics: &Analytics, now: Time, i: IntersectionID) -> (usize, Duration) {
+        let delays = analytics
+            .intersection_delays
+            .get(&i)
+            .map(|list| {
+                list.iter()
+                    .filter(|(_, t, _, _)| *t <= now)
+                    .map(|(_, _, dt, _)| *dt)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        (delays.len(), mean(&delays))
+    }
+
+    fn volume(&self, analytics: &Analytics, now: Time, r: RoadID, agent_type: AgentType) -> usize {
+        (0..=now.get_hours())
+            .map(|hour| {
+                analytics
+                    .road_thruput
+                    .counts
+                    .get(&(r, agent_type, hour))
+                    .cloned()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    /// For each transit route, the average speed of vehicles traveling between consecutive stops
+    /// along the corridor.
+    fn bus_speeds(
+        &self,
+        map: &Map,
+        analytics: &Analytics,
+        now: Time,
+    ) -> BTreeMap<TransitRouteID, (Distance, Duration)> {
+        let mut last_stop = BTreeMap::new();
+        let mut totals: BTreeMap<TransitRouteID, (Distance, Duration)> = BTreeMap::new();
+        for (t, bus, route, stop) in &analytics.bus_arrivals {
+            if *t > now {
+                break;
+            }
+            let pos = map.get_ts(*stop).driving_pos;
+            if !self.roads.contains(&pos.lane().road) {
+                last_stop.remove(bus);
+                continue;
+            }
+            if let Some((t1, pos1)) = last_stop.insert(*bus, (*t, pos)) {
+                let entry = totals
+                    .entry(*route)
+                    .or_insert((Distance::ZERO, Duration::ZERO));
+                entry.0 += pos1.pt(map).dist_to(pos.pt(map));
+                entry.1 += *t - t1;
+            }
+        }
+        totals
+    }
+
+    fn export(&self, app: &App) -> Result<String> {
+        let map = &app.primary.map;
+        let now = app.primary.sim.time();
+        let after = app.primary.sim.get_analytics();
+        let before = if app.has_prebaked().is_some() {
+            Some(app.prebaked())
+        } else {
+            None
+        };
+        let units = &app.opts.units;
+
+        let mut out = String::new();
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html><head><meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>Corridor report</title>")?;
+        writeln!(
+            out,
+            "<style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+             td, th {{ border: 1px solid #999; padding: 4px 8px; }}</style>"
+        )?;
+        writeln!(out, "</head><body>")?;
+        writeln!(out, "<h1>Corridor report</h1>")?;
+        writeln!(
+            out,
+            "<p>Map: {}<br>Proposal: {}<br>Simulated until: {}</p>",
+            escape(&map.get_name().describe()),
+            escape(&map.get_edits().edits_name),
+            now.ampm_tostring()
+        )?;
+        if before.is_none() {
+            writeln!(
+                out,
+                "<p>No baseline results are available, so no before/after comparison is \
+                 included.</p>"
+            )?;
+        }
+
+        let names: Vec<String> = self
+            .roads
+            .iter()
+            .map(|r| escape(&map.get_r(*r).get_name(app.opts.language.as_ref())))
+            .collect();
+        writeln!(
+            out,
+            "<p>{} road segments ({}), from {} to {}. Free-flow travel time is {}.</p>",
+            self.roads.len(),
+            self.roads
+                .iter()
+                .map(|r| map.get_r(*r).length())
+                .sum::<Distance>()
+                .to_string(units),
+            self.intersections[0],
+            self.intersections.last().unwrap(),
+            self.free_flow_time(map)
+        )?;
+
+        writeln!(out, "<h2>Travel time by direction and hour</h2>")?;
+        writeln!(
+            out,
+            "<p>Estimated for cars from the free-flow time plus average delay at each traffic \
+             signal along the corridor.</p>"
+        )?;
+        let mut headers = vec!["Hour", "Forwards", "Backwards"];
+        if before.is_some() {
+            headers.extend(vec!["Forwards (before)", "Backwards (before)"]);
+        }
+        let mut rows = Vec::new();
+        for hour in 0..=now.get_hours() {
+            let mut row = vec![(Time::START_OF_DAY + Duration::hours(hour)).ampm_tostring()];
+            let mut analytics = vec![after];
+            analytics.extend(before);
+            for a in analytics {
+                for forwards in [true, false] {
+                    row.push(match self.travel_time(map, a, now, forwards, hour) {
+                        Some(dt) => dt.to_string(units),
+                        None => "not drivable".to_string(),
+                    });
+                }
+            }
+            rows.push(row);
+        }
+        table(&mut out, headers, rows)?;
+
+        writeln!(out, "<h2>Signal delay per intersection</h2>")?;
+        let mut headers = vec!["Intersection", "Measurements", "Mean delay"];
+        if before.is_some() {
+            headers.extend(vec!["Measurements (before)", "Mean delay (before)"]);
+        }
+        let mut rows = Vec::new();
+        for i in &self.intersections {
+            if !map.get_i(*i).is_traffic_signal() {
+                continue;
+            }
+            let mut row = vec![escape(&map.get_i(*i).name(app.opts.language.as_ref(), map))];
+            let mut analytics = vec![after];
+            analytics.extend(before);
+            for a in analytics {
+                let (cnt, delay) = Corridor::signal_delay(a, now, *i);
+                row.push(prettyprint_usize(cnt));
+                row.push(delay.to_string(units));
+            }
+            rows.push(row);
+        }
+        table(&mut out, headers, rows)?;
+
+        writeln!(out, "<h2>Volumes</h2>")?;
+        let agent_types = vec![
+            AgentType::Car,
+            AgentType::Bike,
+            AgentType::Bus,
+            AgentType::Pedestrian,
+        ];
+        let mut headers = vec!["Road"];
+        headers.extend(agent_types.iter().map(|a| a.noun()));
+        if before.is_some() {
+            headers.extend(vec!["Total (before)", "Total (after)"]);
+        }
+        let mut rows = Vec::new();
+        for (r, name) in self.roads.iter().zip(names.iter()) {
+            let mut row = vec![format!("{} ({})", name, r)];
+            for agent_type in &agent_types {
+                row.push(prettyprint_usize(self.volume(after, now, *r, *agent_type)));
+            }
+            if let Some(before) = before {
+                for a in [before, after] {
+                    row.push(prettyprint_usize(
+                        agent_types
+                            .iter()
+                            .map(|agent_type| self.volume(a, now, *r, *agent_type))
+                            .sum(),
+                    ));
+                }
+            }
+            rows.push(row);
+        }
+        table(&mut out, headers, rows)?;
+
+        writeln!(out, "<h2>Bus speeds</h2>")?;
+        let mut headers = vec!["Route", "Average speed"];
+        if before.is_some() {
+            headers.push("Average speed (before)");
+        }
+        let speeds_after = self.bus_speeds(map, after, now);
+        let speeds_before = before
+            .map(|a| self.bus_speeds(map, a, now))
+            .unwrap_or_default();
+        let mut rows = Vec::new();
+        for (route, (dist, dt)) in &speeds_after {
+            let mut row = vec![
+                escape(&map.get_tr(*route).long_name),
+                Speed::from_dist_time(*dist, *dt).to_string(units),
+            ];
+            if before.is_some() {
+                row.push(match speeds_before.get(route) {
+                    Some((dist, dt)) => Speed::from_dist_time(*dist, *dt).to_string(units),
+                    None => "no data".to_string(),
+                });
+            }
+            rows.push(row);
+        }
+        table(&mut out, headers, rows)?;
+
+        writeln!(out, "</body></html>")?;
+
+        let path = format!(
+            "corridor_report_{}_{}.html",
+            map.get_name().as_filename(),
+            now.as_filename()
+        );
+        abstio::write_file(path, out)
+    }
+}
+
+fn mean(list: &[Duration]) -> Duration {
+    if list.is_empty() {
+        return Duration::ZERO;
+    }
+    list.iter().cloned().sum::<Duration>() / (list.len() as f64)
+}
+
+fn table(out: &mut String, headers: Vec<&str>, rows: Vec<Vec<String>>) -> Result<()> {
+    if rows.is_empty() {
+        writeln!(out, "<p>No data</p>")?;
+        return Ok(());
+    }
+    writeln!(out, "<table>")?;
+    write!(out, "<tr>")?;
+    for header in headers {
+        write!(out, "<th>{}</th>", header)?;
+    }
+    writeln!(out, "</tr>")?;
+    for row in rows {
+        write!(out, "<tr>")?;
+        for cell in row {
+            write!(out, "<td>{}</td>", cell)?;
+        }
+        writeln!(out, "</tr>")?;
+    }
+    writeln!(out, "</table>")?;
+    Ok(())
+}
+
+fn escape(x: &str) -> String {
+    x.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
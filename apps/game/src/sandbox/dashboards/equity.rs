@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Polygon};
+use map_gui::tools::FilePicker;
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text, TextExt,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::NamedAreas;
+use crate::sandbox::dashboards::DashTab;
+
+/// Breaks down the change in trip times caused by the current edits by demographic group. People
+/// are attributed to the named area where their first trip starts, and each area's population is
+/// split between groups using imported demographic data.
+pub struct EquityDashboard {
+    panel: Panel,
+}
+
+impl EquityDashboard {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut col = vec![
+            DashTab::Equity.picker(ctx, app),
+            Line("Equity analysis").small_heading().into_widget(ctx),
+        ];
+
+        let demographics = Demographics::load(app);
+        if demographics.groups.is_empty() {
+            col.push(
+                "Import a CSV file with a row per named area. The first column must match the area's name, and every other column is the population of one demographic group."
+                    .text_widget(ctx),
+            );
+        } else if app.has_prebaked().is_none() {
+            col.push("Trip times can't be compared without baseline results".text_widget(ctx));
+        } else {
+            col.push(results(ctx, app, &demographics));
+        }
+        if cfg!(not(target_arch = "wasm32")) {
+            col.push(
+                ctx.style()
+                    .btn_outline
+                    .text("Import demographics CSV")
+                    .build_def(ctx),
+            );
+        }
+
+        Box::new(EquityDashboard {
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for EquityDashboard {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                "Import demographics CSV" => Transition::Push(FilePicker::new_state(
+                    ctx,
+                    None,
+                    Box::new(|ctx, app, maybe_path| {
+                        if let Ok(Some(path)) = maybe_path {
+                            match Demographics::import(&path) {
+                                Ok(demographics) => {
+                                    demographics.save(app);
+                                    Transition::Multi(vec![
+                                        Transition::Pop,
+                                        Transition::Replace(EquityDashboard::new_state(ctx, app)),
+                                    ])
+                                }
+                                Err(err) => Transition::Replace(PopupMsg::new_state(
+                                    ctx,
+                                    "Import failed",
+                                    vec![err.to_string()],
+                                )),
+                            }
+                        } else {
+                            Transition::Pop
+                        }
+                    }),
+                )),
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => DashTab::Equity
+                .transition(ctx, app, &self.panel)
+                .unwrap_or(Transition::Keep),
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
+
+/// The share of each named area's population belonging to each demographic group, persisted as
+/// player data.
+#[derive(Default, Serialize, Deserialize)]
+struct Demographics {
+    groups: Vec<String>,
+    /// Keyed by the name of the area. The shares are in the same order as the groups, and sum to 1.
+    shares: BTreeMap<String, Vec<f64>>,
+}
+
+impl Demographics {
+    fn load(app: &App) -> Demographics {
+        abstio::maybe_read_json::<Demographics>(Demographics::path(app), &mut Timer::throwaway())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, app: &App) {
+        abstio::write_json(Demographics::path(app), self);
+    }
+
+    fn path(app: &App) -> String {
+        let name = app.primary.map.get_name();
+        abstio::path_player(format!(
+            "demographics/{}/{}/{}.json",
+            name.city.country, name.city.city, name.map
+        ))
+    }
+
+    fn import(path: &str) -> Result<Demographics> {
+        let mut reader = csv::Reader::from_reader(fs_err::File::open(path)?);
+        let groups: Vec<String> = reader
+            .headers()?
+            .iter()
+            .skip(1)
+            .map(|x| x.to_string())
+            .collect();
+        if groups.is_empty() {
+            bail!("{} needs at least one column besides the area name", path);
+        }
+
+        let mut shares = BTreeMap::new();
+        for rec in reader.records() {
+            let rec = rec?;
+            let zone = rec.get(0).unwrap_or("").to_string();
+            let mut population = Vec::new();
+            for value in rec.iter().skip(1) {
+                population.push(value.trim().parse::<f64>()?);
+            }
+            if population.len() != groups.len() {
+                bail!("{} doesn't have a value for every group", zone);
+            }
+            let total: f64 = population.iter().sum();
+            if total > 0.0 {
+                shares.insert(zone, population.into_iter().map(|x| x / total).collect());
+            }
+        }
+        Ok(Demographics { groups, shares })
+    }
+}
+
+struct GroupTotals {
+    // Weighted by each person's share in the group, so fractional
+    trips: f64,
+    before: Duration,
+    after: Duration,
+}
+
+fn results(ctx: &mut EventCtx, app: &App, demographics: &Demographics) -> Widget {
+    let map = &app.primary.map;
+    let sim = &app.primary.sim;
+    let zones: Vec<(String, Polygon)> = NamedAreas::load(app)
+        .areas
+        .into_iter()
+        .filter(|area| demographics.shares.contains_key(&area.name))
+        .filter_map(|area| {
+            let polygon = area.polygon(map)?;
+            Some((area.name, polygon))
+        })
+        .collect();
+
+    let mut totals: Vec<GroupTotals> = demographics
+        .groups
+        .iter()
+        .map(|_| GroupTotals {
+            trips: 0.0,
+            before: Duration::ZERO,
+            after: Duration::ZERO,
+        })
+        .collect();
+    let mut unattributed = 0;
+    for (id, before, after, _) in sim
+        .get_analytics()
+        .both_finished_trips(sim.time(), app.prebaked())
+    {
+        let home = sim
+            .trip_to_person(id)
+            .map(|p| sim.trip_info(sim.get_person(p).trips[0]).start.pt(map));
+        let zone = home.and_then(|pt| {
+            zones
+                .iter()
+                .find(|(_, polygon)| polygon.contains_pt(pt))
+                .map(|(name, _)| name)
+        });
+        if let Some(name) = zone {
+            for (group, share) in totals.iter_mut().zip(demographics.shares[name].iter()) {
+                group.trips += share;
+                group.before += *share * before;
+                group.after += *share * after;
+            }
+        } else {
+            unattributed += 1;
+        }
+    }
+
+    let mut names = Text::from(Line("Group").secondary());
+    let mut trips = Text::from(Line("Trips").secondary());
+    let mut avg_before = Text::from(Line("Average before").secondary());
+    let mut avg_after = Text::from(Line("Average after").secondary());
+    let mut change = Text::from(Line("Change").secondary());
+    for (name, group) in demographics.groups.iter().zip(totals) {
+        names.add_line(Line(name));
+        trips.add_line(prettyprint_usize(group.trips.round() as usize));
+        if group.trips == 0.0 {
+            avg_before.add_line("-");
+            avg_after.add_line("-");
+            change.add_line("-");
+            continue;
+        }
+        let before = group.before / group.trips;
+        let after = group.after / group.trips;
+        avg_before.add_line(before.to_string(&app.opts.units));
+        avg_after.add_line(after.to_string(&app.opts.units));
+        // Groups with trips that all took no time have no meaningful percentage
+        if before == Duration::ZERO {
+            change.add_line((after - before).to_string(&app.opts.units));
+        } else {
+            change.add_line(format!(
+                "{} ({:.1}%)",
+                (after - before).to_string(&app.opts.units),
+                100.0 * (after / before - 1.0)
+            ));
+        }
+    }
+
+    Widget::col(vec![
+        format!(
+            "{} finished trips couldn't be attributed to an area with demographic data",
+            prettyprint_usize(unattributed)
+        )
+        .text_widget(ctx),
+        Widget::custom_row(
+            vec![names, trips, avg_before, avg_after, change]
+                .into_iter()
+                .map(|txt| txt.into_widget(ctx).margin_right(16))
+                .collect(),
+        ),
+    ])
+}
@@ -6,13 +6,20 @@ use widgetry::{Choice, EventCtx, Image, Line, Panel, State, TextExt, Widget};
 use crate::app::App;
 use crate::app::Transition;
 
+mod areas;
+mod bike_share;
 mod commuter;
+mod corridor;
+mod equity;
 mod generic_trip_table;
 mod misc;
 mod mode_shift;
+mod od;
 mod parking_overhead;
+mod peak_spreading;
 mod risks;
 mod selector;
+mod through_traffic;
 mod traffic_signals;
 mod travel_times;
 mod trip_problems;
@@ -30,6 +37,12 @@ pub enum DashTab {
     CommuterPatterns,
     TrafficSignals,
     ModeShift,
+    CorridorReport,
+    Areas,
+    OriginDestination,
+    Equity,
+    PeakSpreading,
+    BikeShare,
 }
 
 impl DashTab {
@@ -44,6 +57,12 @@ impl DashTab {
             Choice::new("Commuter Patterns", DashTab::CommuterPatterns),
             Choice::new("Traffic Signal Demand", DashTab::TrafficSignals),
             Choice::new("Mode shift (experimental)", DashTab::ModeShift),
+            Choice::new("Corridor report", DashTab::CorridorReport),
+            Choice::new("Named areas", DashTab::Areas),
+            Choice::new("Origin-destination", DashTab::OriginDestination),
+            Choice::new("Equity", DashTab::Equity),
+            Choice::new("Peak spreading", DashTab::PeakSpreading),
+            Choice::new("Bike share", DashTab::BikeShare),
         ];
         if app.has_prebaked().is_none() {
             choices.remove(1);
@@ -73,6 +92,12 @@ impl DashTab {
             DashTab::CommuterPatterns => CommuterPatterns::new_state(ctx, app),
             DashTab::TrafficSignals => TrafficSignalDemand::new_state(ctx, app),
             DashTab::ModeShift => mode_shift::ModeShift::new_state(ctx, app),
+            DashTab::CorridorReport => corridor::CorridorReport::new_state(ctx, app),
+            DashTab::Areas => areas::AreasDashboard::new_state(ctx, app),
+            DashTab::OriginDestination => od::OriginDestination::new_state(ctx, app),
+            DashTab::Equity => equity::EquityDashboard::new_state(ctx, app),
+            DashTab::PeakSpreading => peak_spreading::PeakSpreading::new_state(ctx, app),
+            DashTab::BikeShare => bike_share::BikeShareDashboard::new_state(ctx, app),
         }
     }
 
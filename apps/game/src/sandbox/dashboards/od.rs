@@ -0,0 +1,246 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use abstutil::{prettyprint_usize, Counter};
+use geom::{ArrowCap, Circle, Distance, Duration, Polygon, Pt2D, Time};
+use map_gui::tools::checkbox_per_mode;
+use synthpop::TripMode;
+use widgetry::tools::{Severity, Toast};
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    Spinner, State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::{NamedArea, NamedAreas};
+use crate::sandbox::dashboards::DashTab;
+
+/// Aggregates trips between named areas, showing desire lines between them and a matrix of trip
+/// counts.
+pub struct OriginDestination {
+    panel: Panel,
+    draw: Drawable,
+    zones: Vec<(NamedArea, Polygon)>,
+    matrix: Counter<(usize, usize)>,
+    filter: Filter,
+}
+
+struct Filter {
+    modes: BTreeSet<TripMode>,
+    departs_after: Duration,
+    departs_before: Duration,
+}
+
+impl OriginDestination {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let zones: Vec<(NamedArea, Polygon)> = NamedAreas::load(app)
+            .areas
+            .into_iter()
+            .filter_map(|area| {
+                let polygon = area.polygon(&app.primary.map)?;
+                Some((area, polygon))
+            })
+            .collect();
+        let mut state = OriginDestination {
+            panel: Panel::empty(ctx),
+            draw: Drawable::empty(ctx),
+            zones,
+            matrix: Counter::new(),
+            filter: Filter {
+                modes: TripMode::all().into_iter().collect(),
+                departs_after: Duration::ZERO,
+                departs_before: Duration::hours(24),
+            },
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn zone(&self, pt: Pt2D) -> Option<usize> {
+        self.zones
+            .iter()
+            .position(|(_, polygon)| polygon.contains_pt(pt))
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+
+        self.matrix = Counter::new();
+        let mut outside = 0;
+        for (_, trip) in app.primary.sim.all_trip_info() {
+            if !self.filter.modes.contains(&trip.mode)
+                || trip.departure < Time::START_OF_DAY + self.filter.departs_after
+                || trip.departure > Time::START_OF_DAY + self.filter.departs_before
+            {
+                continue;
+            }
+            match (self.zone(trip.start.pt(map)), self.zone(trip.end.pt(map))) {
+                (Some(from), Some(to)) => {
+                    self.matrix.inc((from, to));
+                }
+                _ => {
+                    outside += 1;
+                }
+            }
+        }
+
+        // Width by volume, relative to the busiest pair of zones
+        let max = self.matrix.max().max(1) as f64;
+        let mut batch = GeomBatch::new();
+        for (_, polygon) in &self.zones {
+            batch.push(Color::BLUE.alpha(0.2), polygon.clone());
+            batch.push(Color::BLUE, polygon.to_outline(Distance::meters(3.0)));
+        }
+        for ((from, to), cnt) in self.matrix.borrow() {
+            let width = Distance::meters(2.0) + (*cnt as f64) / max * Distance::meters(30.0);
+            let pt1 = self.zones[*from].1.center();
+            let pt2 = self.zones[*to].1.center();
+            if from == to {
+                batch.push(
+                    Color::PURPLE.alpha(0.5),
+                    Circle::new(pt1, width).to_polygon(),
+                );
+            } else if let Ok(line) = geom::Line::new(pt1, pt2) {
+                batch.push(
+                    Color::PURPLE.alpha(0.5),
+                    line.to_polyline().make_arrow(width, ArrowCap::Triangle),
+                );
+            }
+        }
+        self.draw = ctx.upload(batch);
+
+        let mut col = vec![
+            DashTab::OriginDestination.picker(ctx, app),
+            checkbox_per_mode(ctx, app, &self.filter.modes),
+            Widget::row(vec![
+                "Departing between hour".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "departs after",
+                    (Duration::ZERO, Duration::hours(24)),
+                    self.filter.departs_after,
+                    Duration::hours(1),
+                ),
+                "and".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "departs before",
+                    (Duration::ZERO, Duration::hours(24)),
+                    self.filter.departs_before,
+                    Duration::hours(1),
+                ),
+            ]),
+        ];
+        if self.zones.len() < 2 {
+            col.push("Draw or import at least two named areas to use as zones".text_widget(ctx));
+        } else {
+            col.push(
+                format!(
+                    "{} trips between zones, {} trips starting or ending elsewhere",
+                    prettyprint_usize(self.matrix.sum()),
+                    prettyprint_usize(outside)
+                )
+                .text_widget(ctx),
+            );
+            col.push(self.matrix_widget(ctx));
+            col.push(ctx.style().btn_outline.text("Export to CSV").build_def(ctx));
+        }
+
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .build(ctx);
+    }
+
+    /// Origins are rows, destinations are columns
+    fn matrix_widget(&self, ctx: &EventCtx) -> Widget {
+        let mut columns = Vec::new();
+        let mut names = Text::from(Line("From \\ To").secondary());
+        for (area, _) in &self.zones {
+            names.add_line(Line(&area.name));
+        }
+        columns.push(names.into_widget(ctx).margin_right(16));
+        for (to, (area, _)) in self.zones.iter().enumerate() {
+            let mut txt = Text::from(Line(&area.name).secondary());
+            for from in 0..self.zones.len() {
+                txt.add_line(prettyprint_usize(self.matrix.get((from, to))));
+            }
+            columns.push(txt.into_widget(ctx).margin_right(16));
+        }
+        Widget::custom_row(columns)
+    }
+
+    fn export(&self, app: &App) -> Result<String> {
+        let path = format!(
+            "od_matrix_{}_{}.csv",
+            app.primary.map.get_name().as_filename(),
+            app.primary.sim.time().as_filename()
+        );
+        let mut out = app.primary.sim.run_metadata().to_csv_comment();
+        writeln!(out, "origin,destination,trips")?;
+        for (from, (origin, _)) in self.zones.iter().enumerate() {
+            for (to, (destination, _)) in self.zones.iter().enumerate() {
+                writeln!(
+                    out,
+                    "\"{}\",\"{}\",{}",
+                    origin.name.replace('"', "'"),
+                    destination.name.replace('"', "'"),
+                    self.matrix.get((from, to))
+                )?;
+            }
+        }
+        abstio::write_file(path, out)
+    }
+}
+
+impl State<App> for OriginDestination {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Export to CSV" => {
+                    ctx.show_toast(match self.export(app) {
+                        Ok(path) => Toast::new(
+                            Severity::Success,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            Toast::new(Severity::Error, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                    return Transition::Keep;
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::OriginDestination.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+
+                self.filter.modes.clear();
+                for m in TripMode::all() {
+                    if self.panel.is_checked(m.ongoing_verb()) {
+                        self.filter.modes.insert(m);
+                    }
+                }
+                self.filter.departs_after = self.panel.spinner("departs after");
+                self.filter.departs_before = self.panel.spinner("departs before");
+                self.recalculate(ctx, app);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use abstutil::Counter;
+use geom::{Duration, Polygon, Time};
+use map_gui::tools::{checkbox_per_mode, color_for_mode};
+use sim::{Analytics, TripID, TripInfo, TripPhaseType};
+use synthpop::TripMode;
+use widgetry::{
+    Choice, EventCtx, GfxCtx, InteractivePlot, Line, Outcome, Panel, PlotOptions, Series, State,
+    Text, TextExt, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::NamedAreas;
+use crate::sandbox::dashboards::DashTab;
+
+/// Histograms of when trips depart and arrive over the day, to see how proposed changes shift
+/// travel between peaks. When baseline results are available, they're shown alongside the current
+/// simulation.
+pub struct PeakSpreading {
+    panel: Panel,
+}
+
+struct Filter {
+    modes: BTreeSet<TripMode>,
+    area: Option<String>,
+    purpose: Option<String>,
+}
+
+impl PeakSpreading {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        PeakSpreading::make(
+            ctx,
+            app,
+            Filter {
+                modes: TripMode::all().into_iter().collect(),
+                area: None,
+                purpose: None,
+            },
+        )
+    }
+
+    fn make(ctx: &mut EventCtx, app: &App, filter: Filter) -> Box<dyn State<App>> {
+        let areas = NamedAreas::load(app);
+        let polygon = filter
+            .area
+            .as_ref()
+            .and_then(|name| areas.get(name))
+            .and_then(|area| area.polygon(&app.primary.map));
+        let trips: BTreeMap<TripID, TripInfo> =
+            app.primary.sim.all_trip_info().into_iter().collect();
+        let purposes: BTreeSet<String> = trips
+            .values()
+            .map(|trip| trip.purpose.to_string())
+            .collect();
+
+        let current = Histograms::new(
+            app,
+            app.primary.sim.get_analytics(),
+            &trips,
+            &filter,
+            polygon.as_ref(),
+        );
+        let mut series = vec![
+            Series {
+                label: "Departures".to_string(),
+                color: color_for_mode(app, TripMode::Walk),
+                pts: current.departures,
+            },
+            Series {
+                label: "Arrivals".to_string(),
+                color: color_for_mode(app, TripMode::Drive),
+                pts: current.arrivals,
+            },
+        ];
+        if app.has_prebaked().is_some() {
+            let baseline = Histograms::new(app, app.prebaked(), &trips, &filter, polygon.as_ref());
+            series.push(Series {
+                label: "Departures (baseline)".to_string(),
+                color: color_for_mode(app, TripMode::Walk).alpha(0.5),
+                pts: baseline.departures,
+            });
+            series.push(Series {
+                label: "Arrivals (baseline)".to_string(),
+                color: color_for_mode(app, TripMode::Drive).alpha(0.5),
+                pts: baseline.arrivals,
+            });
+        }
+
+        let mut area_choices = vec![Choice::new("anywhere", None)];
+        for area in areas.areas {
+            area_choices.push(Choice::new(area.name.clone(), Some(area.name)));
+        }
+        let mut purpose_choices = vec![Choice::new("any purpose", None)];
+        for purpose in purposes {
+            purpose_choices.push(Choice::new(purpose.clone(), Some(purpose)));
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            DashTab::PeakSpreading.picker(ctx, app),
+            Text::from(Line(
+                "Trips departing and arriving each hour. Departures are counted where trips start, and arrivals where they end.",
+            ).secondary())
+            .wrap_to_pct(ctx, 50)
+            .into_widget(ctx),
+            checkbox_per_mode(ctx, app, &filter.modes),
+            Widget::row(vec![
+                "In:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(ctx, "area", filter.area.clone(), area_choices),
+                "For:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(ctx, "purpose", filter.purpose.clone(), purpose_choices),
+            ]),
+            InteractivePlot::new_widget(
+                ctx,
+                "departures and arrivals",
+                series,
+                PlotOptions::filterable(),
+                app.opts.units,
+            )
+            .section(ctx),
+        ]))
+        .exact_size_percent(90, 90)
+        .build(ctx);
+
+        Box::new(PeakSpreading { panel })
+    }
+}
+
+impl State<App> for PeakSpreading {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                _ => unreachable!(),
+            },
+            // The plot already hid or showed the series
+            Outcome::Changed(x) if x == "departures and arrivals" => Transition::Keep,
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::PeakSpreading.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+
+                let mut filter = Filter {
+                    modes: BTreeSet::new(),
+                    area: self.panel.dropdown_value("area"),
+                    purpose: self.panel.dropdown_value("purpose"),
+                };
+                for m in TripMode::all() {
+                    if self.panel.is_checked(m.ongoing_verb()) {
+                        filter.modes.insert(m);
+                    }
+                }
+                Transition::Replace(PeakSpreading::make(ctx, app, filter))
+            }
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
+
+struct Histograms {
+    departures: Vec<(Time, usize)>,
+    arrivals: Vec<(Time, usize)>,
+}
+
+impl Histograms {
+    fn new(
+        app: &App,
+        analytics: &Analytics,
+        trips: &BTreeMap<TripID, TripInfo>,
+        filter: &Filter,
+        area: Option<&Polygon>,
+    ) -> Histograms {
+        let map = &app.primary.map;
+        let matches = |id: TripID| {
+            let trip = trips.get(&id)?;
+            if !filter.modes.contains(&trip.mode) {
+                return None;
+            }
+            if let Some(ref purpose) = filter.purpose {
+                if &trip.purpose.to_string() != purpose {
+                    return None;
+                }
+            }
+            Some(trip)
+        };
+
+        let mut departures = Counter::new();
+        let mut arrivals = Counter::new();
+        let mut started = BTreeSet::new();
+        for (t, id, _, phase_type) in &analytics.trip_log {
+            let trip = match matches(*id) {
+                Some(trip) => trip,
+                None => continue,
+            };
+            // The first phase of every trip marks its departure
+            if started.insert(*id)
+                && area
+                    .map(|polygon| polygon.contains_pt(trip.start.pt(map)))
+                    .unwrap_or(true)
+            {
+                departures.inc(t.get_hours());
+            }
+            if *phase_type == TripPhaseType::Finished
+                && area
+                    .map(|polygon| polygon.contains_pt(trip.end.pt(map)))
+                    .unwrap_or(true)
+            {
+                arrivals.inc(t.get_hours());
+            }
+        }
+
+        Histograms {
+            departures: per_hour(departures),
+            arrivals: per_hour(arrivals),
+        }
+    }
+}
+
+fn per_hour(counts: Counter<usize>) -> Vec<(Time, usize)> {
+    let last_hour = counts.borrow().keys().max().cloned().unwrap_or(0).max(23);
+    (0..=last_hour)
+        .map(|hour| (Time::START_OF_DAY + Duration::hours(hour), counts.get(hour)))
+        .collect()
+}
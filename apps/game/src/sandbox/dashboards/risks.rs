@@ -6,7 +6,7 @@ use anyhow::Result;
 use abstutil::prettyprint_usize;
 use sim::{ProblemType, TripID};
 use synthpop::TripMode;
-use widgetry::tools::PopupMsg;
+use widgetry::tools::{Severity, Toast};
 use widgetry::{EventCtx, GfxCtx, Image, Line, Outcome, Panel, State, TextExt, Toggle, Widget};
 
 use super::trip_problems::{problem_matrix, TripProblemFilter};
@@ -151,16 +151,17 @@ impl State<App> for RiskSummaries {
             Outcome::Clicked(x) => match x.as_ref() {
                 "close" => Transition::Pop,
                 "Export to CSV" => {
-                    return Transition::Push(match export_problems(app) {
-                        Ok(path) => PopupMsg::new_state(
-                            ctx,
+                    ctx.show_toast(match export_problems(app) {
+                        Ok(path) => Toast::new(
+                            Severity::Success,
                             "Data exported",
                             vec![format!("Data exported to {}", path)],
                         ),
                         Err(err) => {
-                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                            Toast::new(Severity::Error, "Export failed", vec![err.to_string()])
                         }
                     });
+                    return Transition::Keep;
                 }
                 _ => unreachable!(),
             },
@@ -206,7 +207,7 @@ fn export_problems(app: &App) -> Result<String> {
         app.primary.map.get_name().as_filename(),
         app.primary.sim.time().as_filename()
     );
-    let mut out = String::new();
+    let mut out = app.primary.sim.run_metadata().to_csv_comment();
     writeln!(
         out,
         "id,mode,seconds_after,problem_type,problems_before,problems_after"
@@ -1,13 +1,14 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use geom::{Polygon, Pt2D};
+use geom::{Percent, Polygon, Pt2D};
 use widgetry::{
     Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text,
     VerticalAlignment, Widget,
 };
 
 use crate::app::{App, Transition};
+use crate::common::NamedAreas;
 
 // TODO Lift to widgetry
 pub struct RectangularSelector {
@@ -19,8 +20,10 @@ pub struct RectangularSelector {
 impl RectangularSelector {
     pub fn new_state(
         ctx: &mut EventCtx,
+        app: &App,
         region: Rc<RefCell<Option<Polygon>>>,
     ) -> Box<dyn State<App>> {
+        let named_areas = NamedAreas::load(app);
         Box::new(RectangularSelector {
             panel: Panel::new_builder(Widget::col(vec![
                 Widget::row(vec![
@@ -46,6 +49,26 @@ impl RectangularSelector {
                         .text("Clear")
                         .build_def(ctx),
                 ]),
+                if named_areas.areas.is_empty() {
+                    Widget::nothing()
+                } else {
+                    Widget::col(vec![
+                        Line("Or use a named area").into_widget(ctx),
+                        Widget::row(
+                            named_areas
+                                .areas
+                                .iter()
+                                .map(|area| {
+                                    ctx.style()
+                                        .btn_outline
+                                        .text(&area.name)
+                                        .build_widget(ctx, format!("use area {}", area.name))
+                                })
+                                .collect(),
+                        )
+                        .flex_wrap(ctx, Percent::int(20)),
+                    ])
+                },
             ]))
             .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
             .build(ctx),
@@ -56,7 +79,7 @@ impl RectangularSelector {
 }
 
 impl State<App> for RectangularSelector {
-    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         if ctx.is_key_down(Key::LeftControl) {
             if ctx.input.left_mouse_button_released() {
                 if let Some((_, _, ref mut dragging)) = self.corners {
@@ -95,7 +118,16 @@ impl State<App> for RectangularSelector {
                     }
                     return Transition::Pop;
                 }
-                _ => unreachable!(),
+                x => {
+                    let name = x.strip_prefix("use area ").unwrap();
+                    if let Some(polygon) = NamedAreas::load(app)
+                        .get(name)
+                        .and_then(|area| area.polygon(&app.primary.map))
+                    {
+                        self.region.replace(Some(polygon));
+                    }
+                    return Transition::Pop;
+                }
             }
         }
 
@@ -0,0 +1,225 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use abstutil::{prettyprint_usize, Counter, Timer};
+use map_gui::tools::ColorNetwork;
+use map_model::{PathStepV2, RoadID};
+use synthpop::{TripEndpoint, TripMode};
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::tools::{PopupMsg, Severity, Toast};
+use widgetry::{
+    EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text, TextExt,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::NamedArea;
+
+/// Classifies every driving trip crossing a named area as local (starting or ending inside) or
+/// through traffic, and shows where the through traffic goes.
+pub struct ThroughTraffic {
+    panel: Panel,
+    draw: ToggleZoomed,
+    area: NamedArea,
+    stats: ThroughTrafficStats,
+}
+
+struct ThroughTrafficStats {
+    local_trips: usize,
+    through_trips: usize,
+    local_per_road: Counter<RoadID>,
+    through_per_road: Counter<RoadID>,
+}
+
+impl ThroughTrafficStats {
+    fn new(app: &App, area: &NamedArea, timer: &mut Timer) -> ThroughTrafficStats {
+        let map = &app.primary.map;
+        let polygon = area.polygon(map).unwrap();
+        let roads = area.roads(map);
+
+        let mut stats = ThroughTrafficStats {
+            local_trips: 0,
+            through_trips: 0,
+            local_per_road: Counter::new(),
+            through_per_road: Counter::new(),
+        };
+
+        let trips: Vec<_> = app
+            .primary
+            .sim
+            .all_trip_info()
+            .into_iter()
+            .filter(|(_, trip)| trip.mode == TripMode::Drive)
+            .collect();
+        for (local, path) in timer
+            .parallelize("calculate routes", trips, |(_, trip)| {
+                let local = polygon.contains_pt(trip.start.pt(map))
+                    || polygon.contains_pt(trip.end.pt(map));
+                TripEndpoint::path_req(trip.start, trip.end, trip.mode, map)
+                    .and_then(|req| map.pathfind_v2(req).ok())
+                    .map(|path| (local, path))
+            })
+            .into_iter()
+            .flatten()
+        {
+            let crossed: BTreeSet<RoadID> = path
+                .get_steps()
+                .iter()
+                .filter_map(|step| match step {
+                    PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) => Some(dr.road),
+                    _ => None,
+                })
+                .filter(|r| roads.contains(r))
+                .collect();
+            if crossed.is_empty() {
+                continue;
+            }
+            if local {
+                stats.local_trips += 1;
+                for r in crossed {
+                    stats.local_per_road.inc(r);
+                }
+            } else {
+                stats.through_trips += 1;
+                for r in crossed {
+                    stats.through_per_road.inc(r);
+                }
+            }
+        }
+
+        stats
+    }
+
+    fn through_pct(&self) -> f64 {
+        let total = self.local_trips + self.through_trips;
+        if total == 0 {
+            0.0
+        } else {
+            100.0 * (self.through_trips as f64) / (total as f64)
+        }
+    }
+}
+
+impl ThroughTraffic {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, area: NamedArea) -> Box<dyn State<App>> {
+        if area.polygon(&app.primary.map).is_none() {
+            return PopupMsg::new_state(ctx, "Error", vec!["This area's boundary is invalid"]);
+        }
+
+        let stats = ctx.loading_screen("find through-traffic", |_, timer| {
+            ThroughTrafficStats::new(app, &area, timer)
+        });
+
+        let mut colorer = ColorNetwork::new(app);
+        colorer.pct_roads(stats.through_per_road.clone(), &app.cs.good_to_bad_red);
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line(format!("Through-traffic in {}", area.name))
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            format!(
+                "{} driving trips cross this area. {} are local, {} ({:.1}%) are passing through.",
+                prettyprint_usize(stats.local_trips + stats.through_trips),
+                prettyprint_usize(stats.local_trips),
+                prettyprint_usize(stats.through_trips),
+                stats.through_pct()
+            )
+            .text_widget(ctx),
+            app.cs.good_to_bad_red.make_legend(
+                ctx,
+                0.0,
+                stats.through_per_road.max() as f64,
+                |x| prettyprint_usize(x as usize),
+            ),
+            Line("Streets with the most through-traffic")
+                .small_heading()
+                .into_widget(ctx),
+        ];
+        let mut txt = Text::new();
+        for (r, through) in stats.through_per_road.highest_n(10) {
+            txt.add_line(format!(
+                "{}: {} through, {} local",
+                app.primary
+                    .map
+                    .get_r(r)
+                    .get_name(app.opts.language.as_ref()),
+                prettyprint_usize(through),
+                prettyprint_usize(stats.local_per_road.get(r))
+            ));
+        }
+        col.push(txt.into_widget(ctx));
+        col.push(ctx.style().btn_outline.text("Export to CSV").build_def(ctx));
+
+        Box::new(ThroughTraffic {
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+                .build(ctx),
+            draw: colorer.build(ctx),
+            area,
+            stats,
+        })
+    }
+
+    fn export(&self, app: &App) -> Result<String> {
+        let map = &app.primary.map;
+        let path = format!(
+            "through_traffic_{}_{}_{}.csv",
+            map.get_name().as_filename(),
+            self.area.name.replace(' ', "_"),
+            app.primary.sim.time().as_filename()
+        );
+        let mut out = app.primary.sim.run_metadata().to_csv_comment();
+        writeln!(out, "road,name,local_trips,through_trips")?;
+        for r in self.area.roads(map) {
+            writeln!(
+                out,
+                "{},\"{}\",{},{}",
+                r.0,
+                map.get_r(r).get_name(None).replace('"', "'"),
+                self.stats.local_per_road.get(r),
+                self.stats.through_per_road.get(r)
+            )?;
+        }
+        abstio::write_file(path, out)
+    }
+}
+
+impl State<App> for ThroughTraffic {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Export to CSV" => {
+                    ctx.show_toast(match self.export(app) {
+                        Ok(path) => Toast::new(
+                            Severity::Success,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            Toast::new(Severity::Error, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                    return Transition::Keep;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.draw.draw(g);
+        self.panel.draw(g);
+    }
+}
@@ -8,10 +8,11 @@ use geom::{Distance, Duration, Polygon, Pt2D};
 use map_gui::tools::color_for_mode;
 use sim::{ProblemType, TripID};
 use synthpop::TripMode;
-use widgetry::tools::PopupMsg;
+use widgetry::tools::{Severity, Toast};
 use widgetry::{
-    Choice, Color, CompareTimes, DrawWithTooltips, EventCtx, GeomBatch, GfxCtx, Line, Outcome,
-    Panel, State, Text, TextExt, Toggle, Widget,
+    Binning, BoxPlot, Choice, Color, CompareTimes, Distribution, DrawWithTooltips, EventCtx,
+    GeomBatch, GfxCtx, HistogramOptions, HistogramPlot, Line, Outcome, PairedDisplay, Panel,
+    ScrollArea, State, Text, TextExt, Toggle, Widget,
 };
 
 use super::trip_problems::{problem_matrix, TripProblemFilter};
@@ -53,55 +54,61 @@ impl TravelTimes {
             DashTab::TravelTimes.picker(ctx, app),
             Widget::row(vec![
                 Widget::col(filters).section(ctx),
-                Widget::col(vec![
-                    summary_boxes(ctx, app, &filter),
+                ScrollArea::new_widget(
+                    ctx,
+                    "travel times",
+                    Some(summary_boxes(ctx, app, &filter)),
                     Widget::col(vec![
-                        Text::from(Line("Travel Times").small_heading()).into_widget(ctx),
-                        Widget::row(vec![
-                            "filter:".text_widget(ctx).centered_vert(),
-                            Widget::dropdown(
-                                ctx,
-                                "filter",
-                                filter.changes_pct,
-                                vec![
-                                    Choice::new("any change", None),
-                                    Choice::new("at least 1% change", Some(0.01)),
-                                    Choice::new("at least 10% change", Some(0.1)),
-                                    Choice::new("at least 50% change", Some(0.5)),
-                                ],
-                            ),
+                        Widget::col(vec![
+                            Text::from(Line("Travel Times").small_heading()).into_widget(ctx),
+                            Widget::row(vec![
+                                "filter:".text_widget(ctx).centered_vert(),
+                                Widget::dropdown(
+                                    ctx,
+                                    "filter",
+                                    filter.changes_pct,
+                                    vec![
+                                        Choice::new("any change", None),
+                                        Choice::new("at least 1% change", Some(0.01)),
+                                        Choice::new("at least 10% change", Some(0.1)),
+                                        Choice::new("at least 50% change", Some(0.5)),
+                                    ],
+                                ),
+                            ])
+                            .margin_above(8),
+                            Widget::horiz_separator(ctx, 1.0),
+                            Widget::row(vec![
+                                contingency_table(ctx, app, &filter).bg(ctx.style().section_bg),
+                                scatter_plot(ctx, app, &filter)
+                                    .bg(ctx.style().section_bg)
+                                    .margin_left(32),
+                            ]),
                         ])
-                        .margin_above(8),
-                        Widget::horiz_separator(ctx, 1.0),
+                        .section(ctx)
+                        .evenly_spaced(),
+                        distributions(ctx, app, &filter).section(ctx),
                         Widget::row(vec![
-                            contingency_table(ctx, app, &filter).bg(ctx.style().section_bg),
-                            scatter_plot(ctx, app, &filter)
-                                .bg(ctx.style().section_bg)
-                                .margin_left(32),
-                        ]),
-                    ])
-                    .section(ctx)
-                    .evenly_spaced(),
-                    Widget::row(vec![
-                        Widget::col(vec![
-                            Text::from(Line("Intersection Delays").small_heading())
-                                .into_widget(ctx),
-                            Toggle::checkbox(
+                            Widget::col(vec![
+                                Text::from(Line("Intersection Delays").small_heading())
+                                    .into_widget(ctx),
+                                Toggle::checkbox(
+                                    ctx,
+                                    "include trips without any changes",
+                                    None,
+                                    filter.include_no_changes(),
+                                ),
+                            ]),
+                            problem_matrix(
                                 ctx,
-                                "include trips without any changes",
-                                None,
-                                filter.include_no_changes(),
-                            ),
-                        ]),
-                        problem_matrix(
-                            ctx,
-                            app,
-                            filter.trip_problems(app, ProblemType::IntersectionDelay),
-                        )
-                        .margin_left(32),
-                    ])
-                    .section(ctx),
-                ]),
+                                app,
+                                filter.trip_problems(app, ProblemType::IntersectionDelay),
+                            )
+                            .margin_left(32),
+                        ])
+                        .section(ctx),
+                    ]),
+                    0.8 * ctx.canvas.window_height,
+                ),
             ]),
         ]))
         .exact_size_percent(90, 90)
@@ -114,16 +121,17 @@ impl State<App> for TravelTimes {
         match self.panel.event(ctx) {
             Outcome::Clicked(x) => match x.as_ref() {
                 "Export to CSV" => {
-                    return Transition::Push(match export_times(app) {
-                        Ok(path) => PopupMsg::new_state(
-                            ctx,
+                    ctx.show_toast(match export_times(app) {
+                        Ok(path) => Toast::new(
+                            Severity::Success,
                             "Data exported",
                             vec![format!("Data exported to {}", path)],
                         ),
                         Err(err) => {
-                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                            Toast::new(Severity::Error, "Export failed", vec![err.to_string()])
                         }
                     });
+                    return Transition::Keep;
                 }
                 "close" => Transition::Pop,
                 _ => unreachable!(),
@@ -140,6 +148,11 @@ impl State<App> for TravelTimes {
 
                 let mut filter = Filter {
                     changes_pct: self.panel.dropdown_value("filter"),
+                    // Not shown until some trips finish
+                    paired: self
+                        .panel
+                        .maybe_dropdown_value("paired display")
+                        .unwrap_or(PairedDisplay::SideBySide),
                     modes: BTreeSet::new(),
                     include_no_changes: self.panel.is_checked("include trips without any changes"),
                 };
@@ -288,6 +301,57 @@ fn scatter_plot(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     ])
 }
 
+/// Unlike the contingency table, this doesn't bucket trips coarsely, and shows how the whole
+/// distribution of trip times shifted.
+fn distributions(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
+    let points = filter.get_trips(app);
+    if points.is_empty() {
+        return Widget::nothing();
+    }
+    let edits_name = &app.primary.map.get_edits().edits_name;
+    let make = || {
+        vec![
+            Distribution {
+                label: format!("Before \"{}\"", edits_name),
+                color: app.cs.before_changes,
+                values: points.iter().map(|(b, _)| *b).collect(),
+            },
+            Distribution {
+                label: format!("After \"{}\"", edits_name),
+                color: app.cs.after_changes,
+                values: points.iter().map(|(_, a)| *a).collect(),
+            },
+        ]
+    };
+
+    let mut opts = HistogramOptions::new(Binning::Count(20));
+    opts.paired = filter.paired;
+    Widget::col(vec![
+        Widget::row(vec![
+            Line("Trip time distribution")
+                .small_heading()
+                .into_widget(ctx),
+            Widget::dropdown(
+                ctx,
+                "paired display",
+                filter.paired,
+                vec![
+                    Choice::new("side by side", PairedDisplay::SideBySide),
+                    Choice::new("overlaid", PairedDisplay::Overlay),
+                    Choice::new("difference", PairedDisplay::Difference),
+                ],
+            )
+            .align_right(),
+        ]),
+        Widget::row(vec![
+            HistogramPlot::new_widget(ctx, make(), opts, app.opts.units),
+            BoxPlot::new_widget(ctx, make(), None, app.opts.units)
+                .centered_vert()
+                .margin_left(32),
+        ]),
+    ])
+}
+
 fn contingency_table(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     let total_width = 500.0;
     let total_height = 300.0;
@@ -579,6 +643,7 @@ fn contingency_table(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
 
 pub struct Filter {
     changes_pct: Option<f64>,
+    paired: PairedDisplay,
     modes: BTreeSet<TripMode>,
     include_no_changes: bool,
 }
@@ -597,6 +662,7 @@ impl Filter {
     pub fn new() -> Filter {
         Filter {
             changes_pct: None,
+            paired: PairedDisplay::SideBySide,
             modes: TripMode::all().into_iter().collect(),
             include_no_changes: false,
         }
@@ -637,7 +703,7 @@ fn export_times(app: &App) -> Result<String> {
         app.primary.map.get_name().as_filename(),
         app.primary.sim.time().as_filename()
     );
-    let mut out = String::new();
+    let mut out = app.primary.sim.run_metadata().to_csv_comment();
     writeln!(out, "id,mode,seconds_before,seconds_after")?;
     for (id, b, a, mode) in app
         .primary
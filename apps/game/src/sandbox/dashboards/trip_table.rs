@@ -148,12 +148,14 @@ impl State<App> for TripTable {
                     self.recompute_filters = true;
                     return Transition::Push(RectangularSelector::new_state(
                         ctx,
+                        app,
                         self.panel.stash("starts_in"),
                     ));
                 } else if x == "filter ends" {
                     self.recompute_filters = true;
                     return Transition::Push(RectangularSelector::new_state(
                         ctx,
+                        app,
                         self.panel.stash("ends_in"),
                     ));
                 } else {
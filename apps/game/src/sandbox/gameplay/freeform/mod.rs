@@ -2,6 +2,7 @@ mod area_spawner;
 #[cfg(not(target_arch = "wasm32"))]
 mod importers;
 mod spawner;
+mod validate;
 
 use rand::seq::SliceRandom;
 use rand::Rng;
@@ -21,6 +22,7 @@ use widgetry::{
 
 use crate::app::{App, Transition};
 use crate::common::jump_to_time_upon_startup;
+use crate::debug::ImportWarnings;
 use crate::edit::EditMode;
 use crate::sandbox::gameplay::{GameplayMode, GameplayState};
 use crate::sandbox::{Actions, SandboxControls, SandboxMode};
@@ -76,6 +78,7 @@ impl GameplayState for Freeform {
                     app,
                     GameplayMode::Freeform(app.primary.map.get_name().clone()),
                 ))),
+                "import warnings" => Some(Transition::Push(ImportWarnings::new_state(ctx, app))),
                 "Start a new trip" => Some(Transition::Push(spawner::AgentSpawner::new_state(
                     ctx, app, None,
                 ))),
@@ -156,6 +159,19 @@ impl GameplayState for Freeform {
                     .build_def(ctx),
             ])
             .centered(),
+            // Point out problems with the OSM data, for people able to fix them
+            if app.primary.map.get_import_warnings().is_empty() {
+                Widget::nothing()
+            } else {
+                ctx.style()
+                    .btn_plain
+                    .text(format!(
+                        "{} problems found importing this map",
+                        app.primary.map.get_import_warnings().len()
+                    ))
+                    .build_widget(ctx, "import warnings")
+                    .centered_horiz()
+            },
             Text::from_all(vec![
                 Line("Select an intersection and press "),
                 Key::Z.txt(ctx),
@@ -170,7 +186,9 @@ impl GameplayState for Freeform {
     }
 }
 
-pub struct ChangeScenario;
+pub struct ChangeScenario {
+    current_scenario: String,
+}
 
 impl ChangeScenario {
     pub fn new_state(ctx: &mut EventCtx, app: &App, current_scenario: &str) -> Box<dyn State<App>> {
@@ -252,6 +270,18 @@ impl ChangeScenario {
                 .into_widget(ctx),
         ];
         for (name, label, description) in choices {
+            let mut details = vec![Text::from(Line(description).secondary())
+                .wrap_to_pct(ctx, 40)
+                .into_widget(ctx)];
+            // Only scenarios saved to a file can be checked
+            if abstio::file_exists(abstio::path_scenario(app.primary.map.get_name(), &name)) {
+                details.push(
+                    ctx.style()
+                        .btn_plain
+                        .text("Check for problems")
+                        .build_widget(ctx, format!("validate {}", name)),
+                );
+            }
             let btn = if name == current_scenario {
                 ctx.style().btn_tab.text(label).disabled(true)
             } else {
@@ -260,10 +290,7 @@ impl ChangeScenario {
             col.push(
                 Widget::row(vec![
                     btn.build_widget(ctx, name),
-                    Text::from(Line(description).secondary())
-                        .wrap_to_pct(ctx, 40)
-                        .into_widget(ctx)
-                        .align_right(),
+                    Widget::col(details).align_right(),
                 ])
                 .margin_above(30),
             );
@@ -278,7 +305,9 @@ impl ChangeScenario {
 
         <dyn SimpleState<_>>::new_state(
             Panel::new_builder(Widget::col(col)).build(ctx),
-            Box::new(ChangeScenario),
+            Box::new(ChangeScenario {
+                current_scenario: current_scenario.to_string(),
+            }),
         )
     }
 }
@@ -309,6 +338,13 @@ impl SimpleState<App> for ChangeScenario {
                 let _ = ctx;
                 unreachable!()
             }
+        } else if let Some(name) = x.strip_prefix("validate ") {
+            Transition::Push(validate::ValidateScenario::new_state(
+                ctx,
+                app,
+                name,
+                self.current_scenario.clone(),
+            ))
         } else if x == "import json" {
             #[cfg(not(target_arch = "wasm32"))]
             {
@@ -0,0 +1,155 @@
+use abstutil::Timer;
+use map_gui::tools::grey_out_map;
+use synthpop::Scenario;
+use widgetry::tools::{FileLoader, PopupMsg};
+use widgetry::{EventCtx, GfxCtx, Line, Panel, SimpleState, State, Text, TextExt, Widget};
+
+use super::ChangeScenario;
+use crate::app::{App, Transition};
+
+/// Only list this many individual problems
+const MAX_SHOWN: usize = 20;
+
+/// Check a saved scenario against the current map before playing it, and offer to repair it.
+pub struct ValidateScenario {
+    name: String,
+    /// Taken when repairing
+    scenario: Option<Scenario>,
+    /// To refresh the scenario picker after saving a repaired copy
+    current_scenario: String,
+}
+
+impl ValidateScenario {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        name: &str,
+        current_scenario: String,
+    ) -> Box<dyn State<App>> {
+        let name = name.to_string();
+        FileLoader::<App, Scenario>::new_state(
+            ctx,
+            abstio::path_scenario(app.primary.map.get_name(), &name),
+            Box::new(move |ctx, app, timer, scenario| {
+                Transition::Replace(match scenario {
+                    Ok(scenario) => {
+                        ValidateScenario::show(ctx, app, timer, name, scenario, current_scenario)
+                    }
+                    Err(err) => PopupMsg::new_state(
+                        ctx,
+                        "Error",
+                        vec![format!("Couldn't load {}: {}", name, err)],
+                    ),
+                })
+            }),
+        )
+    }
+
+    fn show(
+        ctx: &mut EventCtx,
+        app: &App,
+        timer: &mut Timer,
+        name: String,
+        scenario: Scenario,
+        current_scenario: String,
+    ) -> Box<dyn State<App>> {
+        let report = scenario.validate(&app.primary.map, timer);
+
+        let mut col = vec![Widget::row(vec![
+            Line(format!("Checking {}", name))
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        for line in report.summary() {
+            col.push(line.text_widget(ctx));
+        }
+        if !report.is_ok() {
+            let mut txt = Text::new();
+            for issue in report.issues.iter().take(MAX_SHOWN) {
+                txt.add_line(Line(issue.to_string()).secondary());
+            }
+            if report.issues.len() > MAX_SHOWN {
+                txt.add_line(
+                    Line(format!("... and {} more", report.issues.len() - MAX_SHOWN)).secondary(),
+                );
+            }
+            col.push(txt.wrap_to_pct(ctx, 50).into_widget(ctx));
+            col.push(
+                Text::from(
+                    "Repairing drops trips departing after midnight and moves impossible trips and \
+                     missing buildings to a nearby building. Anybody else with problems is \
+                     removed.",
+                )
+                .wrap_to_pct(ctx, 50)
+                .into_widget(ctx),
+            );
+            if cfg!(target_arch = "wasm32") {
+                col.push(
+                    "Repairing saves a new scenario file, so it needs the desktop version"
+                        .text_widget(ctx),
+                );
+            } else {
+                col.push(
+                    ctx.style()
+                        .btn_solid_primary
+                        .text(format!("Repair and save as {}", repaired_name(&name)))
+                        .build_widget(ctx, "repair"),
+                );
+            }
+        }
+
+        <dyn SimpleState<_>>::new_state(
+            Panel::new_builder(Widget::col(col)).build(ctx),
+            Box::new(ValidateScenario {
+                name,
+                scenario: Some(scenario),
+                current_scenario,
+            }),
+        )
+    }
+}
+
+impl SimpleState<App> for ValidateScenario {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        x: &str,
+        _: &mut Panel,
+    ) -> Transition {
+        match x {
+            "close" => Transition::Pop,
+            "repair" => {
+                let name = repaired_name(&self.name);
+                let scenario = self.scenario.take().unwrap();
+                let report = ctx.loading_screen("repair scenario", |_, timer| {
+                    let (mut repaired, report) = scenario.repair(&app.primary.map, timer);
+                    repaired.scenario_name = name.clone();
+                    repaired.save();
+                    report
+                });
+                let mut lines = report.summary();
+                lines.push(format!("Saved as {}", name));
+                Transition::Multi(vec![
+                    Transition::Pop,
+                    Transition::Replace(ChangeScenario::new_state(
+                        ctx,
+                        app,
+                        &self.current_scenario,
+                    )),
+                    Transition::Push(PopupMsg::new_state(ctx, "Scenario repaired", lines)),
+                ])
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+    }
+}
+
+fn repaired_name(name: &str) -> String {
+    format!("{}_repaired", name)
+}
@@ -191,13 +191,16 @@ impl GameplayMode {
                     }
                     _ => {}
                 },
-                EditCmd::ChangeCrosswalks { .. } => {
+                EditCmd::ChangeCrosswalks { .. } | EditCmd::ChangeUTurns { .. } => {
                     // TODO Another hack to see if we can only edit signal timing
                     if !self.can_edit_stop_signs() {
                         return false;
                     }
                 }
-                EditCmd::ChangeRouteSchedule { .. } => {}
+                EditCmd::ChangeRouteSchedule { .. }
+                | EditCmd::ChangeRouteStops { .. }
+                | EditCmd::ChangeTransitStop { .. }
+                | EditCmd::ChangeRouteBoarding { .. } => {}
             }
         }
         true
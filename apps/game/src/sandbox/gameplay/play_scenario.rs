@@ -7,7 +7,7 @@ use geom::{Duration, Time};
 use map_gui::tools::{checkbox_per_mode, grey_out_map, CityPicker};
 use sim::SlidingWindow;
 use synthpop::{ScenarioModifier, TripMode};
-use widgetry::tools::{ChooseSomething, PopupMsg, URLManager};
+use widgetry::tools::{ChooseSomething, PopupMsg, Severity, Toast, URLManager};
 use widgetry::{
     lctrl, Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, LinePlot, Outcome,
     Panel, PlotOptions, Series, SimpleState, Slider, Spinner, State, Text, TextExt,
@@ -15,6 +15,7 @@ use widgetry::{
 };
 
 use crate::app::{App, Transition};
+use crate::common::NamedAreas;
 use crate::edit::EditMode;
 use crate::sandbox::gameplay::freeform::ChangeScenario;
 use crate::sandbox::gameplay::{GameplayMode, GameplayState};
@@ -119,11 +120,12 @@ impl GameplayState for PlayScenario {
                     // it.
                     s.scenario_name = format!("saved_{}", s.scenario_name);
                     s.save();
-                    Some(Transition::Push(PopupMsg::new_state(
-                        ctx,
+                    ctx.show_toast(Toast::new(
+                        Severity::Success,
                         "Saved",
                         vec![format!("Scenario '{}' saved", s.scenario_name)],
-                    )))
+                    ));
+                    None
                 }
                 "When do trips start?" => {
                     Some(Transition::Push(DepartureSummary::new_state(ctx, app)))
@@ -272,6 +274,30 @@ impl EditScenarioModifiers {
                 .text("Repeat schedule multiple days with +/- 10 minutes of noise")
                 .build_def(ctx),
         ]));
+        rows.push(Widget::row(vec![
+            Spinner::widget(ctx, "sample_pct", (1, 100), 10_usize, 1),
+            "%".text_widget(ctx).centered_vert(),
+            ctx.style()
+                .btn_outline
+                .text("Only simulate some people")
+                .build_def(ctx),
+        ]));
+        rows.push(Widget::row(vec![
+            "From".text_widget(ctx).centered_vert(),
+            Spinner::widget(ctx, "window_start", (0, 23), 7_usize, 1),
+            "to".text_widget(ctx).centered_vert(),
+            Spinner::widget(ctx, "window_end", (1, 24), 10_usize, 1),
+            ctx.style()
+                .btn_outline
+                .text("Only keep trips departing in this window")
+                .build_def(ctx),
+        ]));
+        rows.push(
+            ctx.style()
+                .btn_outline
+                .text("Only keep trips touching an area")
+                .build_def(ctx),
+        );
         rows.push(Widget::horiz_separator(ctx, 1.0));
         rows.push(
             Widget::row(vec![
@@ -379,6 +405,72 @@ impl State<App> for EditScenarioModifiers {
                         self.modifiers.clone(),
                     ));
                 }
+                "Only simulate some people" => {
+                    self.modifiers.push(ScenarioModifier::SamplePeople(
+                        self.panel.spinner("sample_pct"),
+                    ));
+                    return Transition::Replace(EditScenarioModifiers::new_state(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Only keep trips departing in this window" => {
+                    let start: usize = self.panel.spinner("window_start");
+                    let end: usize = self.panel.spinner("window_end");
+                    if start >= end {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Invalid time window",
+                            vec!["The window has to end after it starts"],
+                        ));
+                    }
+                    self.modifiers.push(ScenarioModifier::TimeWindow(
+                        Time::START_OF_DAY + Duration::hours(start),
+                        Time::START_OF_DAY + Duration::hours(end),
+                    ));
+                    return Transition::Replace(EditScenarioModifiers::new_state(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Only keep trips touching an area" => {
+                    let areas = NamedAreas::load(app).areas;
+                    if areas.is_empty() {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "No areas",
+                            vec!["Draw some areas first, using the \"Named areas\" dashboard"],
+                        ));
+                    }
+                    return Transition::Push(ChooseSomething::new_state(
+                        ctx,
+                        "Which area should trips start or end in?",
+                        areas
+                            .into_iter()
+                            .map(|area| Choice::new(area.name.clone(), area))
+                            .collect(),
+                        Box::new(|area, _, _| {
+                            Transition::Multi(vec![
+                                Transition::Pop,
+                                Transition::ConsumeState(Box::new(|state, ctx, _| {
+                                    let mut state =
+                                        state.downcast::<EditScenarioModifiers>().ok().unwrap();
+                                    state.modifiers.push(ScenarioModifier::TouchingArea {
+                                        name: area.name,
+                                        boundary: area.boundary,
+                                    });
+                                    vec![EditScenarioModifiers::new_state(
+                                        ctx,
+                                        state.scenario_name,
+                                        state.modifiers,
+                                    )]
+                                })),
+                            ])
+                        }),
+                    ));
+                }
                 x => {
                     if let Some(x) = x.strip_prefix("delete modifier ") {
                         self.modifiers.remove(x.parse::<usize>().unwrap() - 1);
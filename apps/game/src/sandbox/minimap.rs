@@ -8,7 +8,7 @@ use widgetry::{
 use crate::app::App;
 use crate::app::Transition;
 use crate::common::Warping;
-use crate::layer::PickLayer;
+use crate::layer::{draw_layers_minimap, PickLayer};
 
 pub struct MinimapController;
 
@@ -21,9 +21,7 @@ impl MinimapControls<App> for MinimapController {
     }
 
     fn draw_extra(&self, g: &mut GfxCtx, app: &App) {
-        if let Some(ref l) = app.primary.layer {
-            l.draw_minimap(g);
-        }
+        draw_layers_minimap(g, app);
 
         let mut cache = app.primary.agents.borrow_mut();
         cache.draw_unzoomed_agents(g, &app.primary.map, &app.primary.sim, &app.cs, &app.opts);
@@ -15,6 +15,7 @@ use widgetry::{lctrl, Choice, EventCtx, GfxCtx, Key, Outcome, Panel, State, Upda
 pub use self::gameplay::{spawn_agents_around, GameplayMode, TutorialPointer, TutorialState};
 pub use self::minimap::MinimapController;
 use self::misc_tools::{RoutePreview, TrafficRecorder};
+pub use self::sim_settings::load_saved_sim_options;
 pub use self::speed::{SpeedSetting, TimePanel};
 pub use self::time_warp::TimeWarpScreen;
 use crate::app::{App, Transition};
@@ -25,15 +26,19 @@ use crate::edit::{
 };
 use crate::info::ContextualActions;
 use crate::layer::favorites::{Favorites, ShowFavorites};
-use crate::layer::PickLayer;
+use crate::layer::{draw_layers, PickLayer};
 use crate::pregame::TitleScreen;
 use crate::render::{unzoomed_agent_radius, UnzoomedAgents};
 use crate::ID;
 
+mod alerts;
+mod breakpoints;
 pub mod dashboards;
 pub mod gameplay;
 mod minimap;
 mod misc_tools;
+mod select_link;
+mod sim_settings;
 mod speed;
 mod time_warp;
 mod turn_explorer;
@@ -221,9 +226,7 @@ impl State<App> for SandboxMode {
     }
 
     fn draw(&self, g: &mut GfxCtx, app: &App) {
-        if let Some(ref l) = app.primary.layer {
-            l.draw(g, app);
-        }
+        draw_layers(g, app);
 
         if !app.opts.minimal_controls {
             if let Some(ref c) = self.controls.common {
@@ -252,6 +255,7 @@ impl State<App> for SandboxMode {
 
     fn on_destroy(&mut self, _: &mut EventCtx, app: &mut App) {
         app.primary.layer = None;
+        app.primary.background_layer = None;
         app.primary.agents.borrow_mut().unzoomed_agents = UnzoomedAgents::new();
         self.gameplay.on_destroy(app);
     }
@@ -328,6 +332,9 @@ impl ContextualActions for Actions {
                     if !app.primary.map.get_turns_from_lane(l).is_empty() {
                         actions.push((Key::Z, "explore turns from this lane".to_string()));
                     }
+                    if app.primary.map.get_parent(l).is_driveable() {
+                        actions.push((Key::K, "select-link analysis".to_string()));
+                    }
                     if self.gameplay.can_edit_roads() && can_edit_lane(app, l) {
                         actions.push((Key::E, "edit lane".to_string()));
                     }
@@ -382,6 +389,9 @@ impl ContextualActions for Actions {
             (ID::Lane(l), "explore turns from this lane") => {
                 Transition::Push(turn_explorer::TurnExplorer::new_state(ctx, app, l))
             }
+            (ID::Lane(l), "select-link analysis") => {
+                Transition::Push(select_link::SelectLink::new_state(ctx, app, l.road))
+            }
             (ID::Lane(l), "edit lane") => Transition::Multi(vec![
                 Transition::Push(EditMode::new_state(ctx, app, self.gameplay.clone())),
                 Transition::Push(RoadEditor::new_state(ctx, app, l)),
@@ -564,6 +574,8 @@ impl State<App> for SandboxLoader {
                             }
                         }
 
+                        let seed = app.primary.current_flags.sim_flags.rng_seed;
+                        app.primary.sim.record_rng_seed(seed);
                         app.primary
                             .sim
                             .instantiate(&scenario, &app.primary.map, &mut rng, timer);
@@ -575,6 +587,8 @@ impl State<App> for SandboxLoader {
                             // TODO Modifiers already applied
                             secondary.scenario = Some(scenario.clone());
 
+                            let seed = secondary.current_flags.sim_flags.rng_seed;
+                            secondary.sim.record_rng_seed(seed);
                             secondary.sim.instantiate(
                                 &scenario,
                                 &secondary.map,
@@ -0,0 +1,179 @@
+use abstutil::{prettyprint_usize, Counter};
+use geom::{ArrowCap, Distance, Duration, Time};
+use map_gui::tools::ColorNetwork;
+use map_model::{PathRequest, PathStepV2, RoadID};
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::tools::ColorLegend;
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    Spinner, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// Shows where all of the vehicles crossing one road during a time window come from and go to.
+pub struct SelectLink {
+    r: RoadID,
+    panel: Panel,
+    desire_lines: Drawable,
+    flow_map: ToggleZoomed,
+}
+
+impl SelectLink {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, r: RoadID) -> Box<dyn State<App>> {
+        let mut state = SelectLink {
+            r,
+            panel: Panel::empty(ctx),
+            desire_lines: Drawable::empty(ctx),
+            flow_map: ToggleZoomed::empty(ctx),
+        };
+        state.recalculate(ctx, app, Duration::hours(7), Duration::hours(9), false);
+        Box::new(state)
+    }
+
+    fn recalculate(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &App,
+        from: Duration,
+        to: Duration,
+        show_flow_map: bool,
+    ) {
+        let map = &app.primary.map;
+        let window = (Time::START_OF_DAY + from, Time::START_OF_DAY + to);
+        let results = ctx.loading_screen("select-link analysis", |_, timer| {
+            app.primary
+                .sim
+                .get_analytics()
+                .select_link(map, self.r, window, timer)
+        });
+
+        let mut origins: Counter<RoadID> = Counter::new();
+        let mut destinations: Counter<RoadID> = Counter::new();
+        let mut flow: Counter<RoadID> = Counter::new();
+        let mut batch = GeomBatch::new();
+        for (_, req, path) in &results {
+            origins.inc(req.start.lane().road);
+            destinations.inc(req.end.lane().road);
+            for step in path.get_steps() {
+                if let PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) = step {
+                    flow.inc(dr.road);
+                }
+            }
+            if let Some(arrow) = desire_line(app, req) {
+                batch.push(Color::PURPLE.alpha(0.5), arrow);
+            }
+        }
+        batch.push(Color::CYAN, map.get_r(self.r).get_thick_polygon());
+        self.desire_lines = ctx.upload(batch);
+
+        let mut colorer = ColorNetwork::new(app);
+        colorer.pct_roads(flow.clone(), &app.cs.good_to_bad_red);
+        colorer.add_r(self.r, Color::CYAN);
+        self.flow_map = colorer.build(ctx);
+
+        let name = |r: RoadID| map.get_r(r).get_name(app.opts.language.as_ref());
+        let mut txt = Text::new();
+        txt.add_line(Line("Top origins").small_heading());
+        for (r, cnt) in origins.highest_n(5) {
+            txt.add_line(format!("{}: {}", name(r), prettyprint_usize(cnt)));
+        }
+        txt.add_line(Line("Top destinations").small_heading());
+        for (r, cnt) in destinations.highest_n(5) {
+            txt.add_line(format!("{}: {}", name(r), prettyprint_usize(cnt)));
+        }
+
+        self.panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line(format!("Select-link analysis for {}", name(self.r)))
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::row(vec![
+                "From hour:".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "from",
+                    (Duration::ZERO, Duration::hours(24)),
+                    from,
+                    Duration::hours(1),
+                ),
+                "to:".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "to",
+                    (Duration::ZERO, Duration::hours(24)),
+                    to,
+                    Duration::hours(1),
+                ),
+            ]),
+            format!(
+                "{} vehicles crossed this road between {} and {}",
+                prettyprint_usize(results.len()),
+                window.0.ampm_tostring(),
+                window.1.ampm_tostring()
+            )
+            .text_widget(ctx),
+            Toggle::switch(ctx, "show flow map", None, show_flow_map),
+            if show_flow_map {
+                app.cs
+                    .good_to_bad_red
+                    .make_legend(ctx, 0.0, flow.max() as f64, |x| {
+                        prettyprint_usize(x as usize)
+                    })
+            } else {
+                ColorLegend::row(ctx, Color::PURPLE, "origin to destination")
+            },
+            txt.into_widget(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx);
+    }
+}
+
+impl State<App> for SelectLink {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                let mut from: Duration = self.panel.spinner("from");
+                let mut to: Duration = self.panel.spinner("to");
+                if from > to {
+                    std::mem::swap(&mut from, &mut to);
+                }
+                let show_flow_map = self.panel.is_checked("show flow map");
+                self.recalculate(ctx, app, from, to, show_flow_map);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        if self.panel.is_checked("show flow map") {
+            self.flow_map.draw(g);
+        } else {
+            g.redraw(&self.desire_lines);
+        }
+        self.panel.draw(g);
+    }
+}
+
+fn desire_line(app: &App, req: &PathRequest) -> Option<geom::Polygon> {
+    let map = &app.primary.map;
+    geom::Line::new(req.start.pt(map), req.end.pt(map))
+        .ok()
+        .map(|line| {
+            line.to_polyline()
+                .make_arrow(Distance::meters(3.0), ArrowCap::Triangle)
+        })
+}
@@ -0,0 +1,321 @@
+use anyhow::Result;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
+use map_gui::tools::grey_out_map;
+use sim::{AlertHandler, SimFlags, SimOptions};
+use widgetry::tools::{Confirm, PopupMsg};
+use widgetry::{
+    Choice, EventCtx, GfxCtx, Key, Line, Outcome, Panel, Spinner, State, Text, TextBox, Toggle,
+    Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::{GameplayMode, SandboxMode};
+
+const SAVED_OPTIONS: &str = "sim_options.json";
+
+/// Edit the options controlling the traffic simulation, which are otherwise only set by command
+/// line flags. Changes apply by restarting the simulation, and are remembered for next time.
+pub struct SimSettings {
+    panel: Panel,
+    mode: GameplayMode,
+}
+
+/// Everything chosen in the panel. Most of it lives in `SimOptions`, but the random seeds don't.
+#[derive(Clone, Serialize, Deserialize)]
+struct SavedSettings {
+    opts: SimOptions,
+    rng_seed: u64,
+    /// `SimOptions::enable_pandemic_model` holds an RNG, which can't be saved
+    pandemic_seed: Option<u64>,
+}
+
+impl SavedSettings {
+    fn load() -> Option<SavedSettings> {
+        abstio::maybe_read_json::<SavedSettings>(
+            abstio::path_player(SAVED_OPTIONS),
+            &mut Timer::throwaway(),
+        )
+        .ok()
+    }
+}
+
+impl SimSettings {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, mode: GameplayMode) -> Box<dyn State<App>> {
+        let flags = &app.primary.current_flags.sim_flags;
+        let mut opts = flags.opts.clone();
+        // The seed for the pandemic model can't be recovered from its RNG, so show the last one
+        // chosen here
+        let pandemic_seed = SavedSettings::load()
+            .and_then(|saved| saved.pandemic_seed)
+            .unwrap_or(flags.rng_seed);
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Simulation settings").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from(
+                "Changing these restarts the simulation from midnight. Kinds of alerts can be \
+                 muted from the alerts panel, and bike share stations placed from their layer.",
+            )
+            .wrap_to_pct(ctx, 50)
+            .into_widget(ctx),
+            Widget::row(vec![
+                Line("Run name:").into_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "run name", opts.run_name.clone()),
+            ]),
+            Widget::col(vec![
+                Widget::row(vec![
+                    Line("Random seed:").into_widget(ctx).centered_vert(),
+                    TextBox::default_widget(ctx, "random seed", flags.rng_seed.to_string()),
+                ]),
+                Text::from(
+                    Line("Different seeds pick different cars, parking spots, and so on")
+                        .secondary(),
+                )
+                .wrap_to_pct(ctx, 50)
+                .into_widget(ctx),
+            ]),
+            Widget::col(vec![
+                Widget::row(vec![
+                    Toggle::checkbox(
+                        ctx,
+                        "Pandemic model",
+                        None,
+                        opts.enable_pandemic_model.is_some(),
+                    ),
+                    Line("seed:").into_widget(ctx).centered_vert(),
+                    TextBox::default_widget(ctx, "pandemic seed", pandemic_seed.to_string()),
+                ]),
+                Text::from(Line("Experimental: model the spread of a disease").secondary())
+                    .wrap_to_pct(ctx, 50)
+                    .into_widget(ctx),
+            ]),
+        ];
+        for (label, explanation) in TOGGLES {
+            col.push(Widget::col(vec![
+                Toggle::checkbox(ctx, label, None, *toggle_field(&mut opts, label)),
+                Text::from(Line(explanation).secondary())
+                    .wrap_to_pct(ctx, 50)
+                    .into_widget(ctx),
+            ]));
+        }
+        col.push(Widget::col(vec![
+            Widget::row(vec![
+                Line("Drivers rolling through stop signs (%):")
+                    .into_widget(ctx)
+                    .centered_vert(),
+                Spinner::widget(ctx, "rolling stops", (0, 100), opts.rolling_stop_pct, 1),
+            ]),
+            Widget::row(vec![
+                Line("Drivers running stop signs (%):")
+                    .into_widget(ctx)
+                    .centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "stop sign violations",
+                    (0, 100),
+                    opts.stop_sign_violation_pct,
+                    1,
+                ),
+            ]),
+            Text::from_multiline(vec![
+                Line("Rolling stops only happen when nobody else is waiting.").secondary(),
+                Line("Running a stop sign cuts off whoever got there first.").secondary(),
+            ])
+            .wrap_to_pct(ctx, 50)
+            .into_widget(ctx),
+        ]));
+        col.push(Widget::row(vec![
+            Line("When something unusual happens:")
+                .into_widget(ctx)
+                .centered_vert(),
+            Widget::dropdown(
+                ctx,
+                "alerts",
+                opts.alerts.clone(),
+                vec![
+                    Choice::new("print a message", AlertHandler::Print),
+                    Choice::new("pause the simulation", AlertHandler::Block),
+                    Choice::new("ignore it", AlertHandler::Silence),
+                ],
+            ),
+        ]));
+        col.push(
+            ctx.style()
+                .btn_solid_primary
+                .text("Apply and restart")
+                .hotkey(Key::Enter)
+                .build_def(ctx)
+                .centered_horiz(),
+        );
+
+        Box::new(SimSettings {
+            panel: Panel::new_builder(Widget::col(col)).build(ctx),
+            mode,
+        })
+    }
+
+    fn chosen_settings(&self, app: &App) -> Result<SavedSettings> {
+        let mut opts = app.primary.current_flags.sim_flags.opts.clone();
+        opts.run_name = self.panel.text_box("run name");
+        if opts.run_name.is_empty() {
+            bail!("The run name can't be blank");
+        }
+        for (label, _) in TOGGLES {
+            *toggle_field(&mut opts, label) = self.panel.is_checked(label);
+        }
+        opts.rolling_stop_pct = self.panel.spinner("rolling stops");
+        opts.stop_sign_violation_pct = self.panel.spinner("stop sign violations");
+        opts.alerts = self.panel.dropdown_value("alerts");
+
+        let rng_seed = parse_seed(&self.panel.text_box("random seed"))?;
+        let pandemic_seed = if self.panel.is_checked("Pandemic model") {
+            Some(parse_seed(&self.panel.text_box("pandemic seed"))?)
+        } else {
+            None
+        };
+        Ok(SavedSettings {
+            opts,
+            rng_seed,
+            pandemic_seed,
+        })
+    }
+}
+
+impl State<App> for SimSettings {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Apply and restart" => {
+                    let mode = self.mode.clone();
+                    let settings = match self.chosen_settings(app) {
+                        Ok(settings) => settings,
+                        Err(err) => {
+                            return Transition::Push(PopupMsg::new_state(
+                                ctx,
+                                "Error",
+                                vec![err.to_string()],
+                            ));
+                        }
+                    };
+                    return Transition::Replace(Confirm::new_state(
+                        ctx,
+                        "Restart the simulation?",
+                        vec![format!(
+                            "The simulation will restart, losing progress up to {}.",
+                            app.primary.sim.time().ampm_tostring()
+                        )],
+                        "Restart",
+                        Box::new(move |_, app| {
+                            abstio::write_json(abstio::path_player(SAVED_OPTIONS), &settings);
+                            let flags = &mut app.primary.current_flags.sim_flags;
+                            flags.opts = settings.opts;
+                            flags.opts.enable_pandemic_model =
+                                settings.pandemic_seed.map(XorShiftRng::seed_from_u64);
+                            flags.rng_seed = settings.rng_seed;
+                            Transition::Replace(SandboxMode::simple_new(app, mode))
+                        }),
+                    ));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        if self.panel.clicked_outside(ctx) {
+            return Transition::Pop;
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
+
+/// Restore options chosen in this panel previously. Command line flags still take precedence.
+pub fn load_saved_sim_options(flags: &mut SimFlags) {
+    if let Some(saved) = SavedSettings::load() {
+        flags.opts.fill_in_from(&saved.opts);
+        if flags.rng_seed == SimFlags::RNG_SEED {
+            flags.rng_seed = saved.rng_seed;
+        }
+        if flags.opts.enable_pandemic_model.is_none() {
+            flags.opts.enable_pandemic_model = saved.pandemic_seed.map(XorShiftRng::seed_from_u64);
+        }
+    }
+}
+
+fn parse_seed(x: &str) -> Result<u64> {
+    x.trim()
+        .parse::<u64>()
+        .map_err(|_| anyhow!("{} isn't a valid seed; use a whole number", x))
+}
+
+/// Each on/off option, with an explanation
+const TOGGLES: [(&str, &str); 10] = [
+    (
+        "Ignore stop signs and traffic signals",
+        "Any turn not conflicting with one already happening can go, first come first served",
+    ),
+    (
+        "Allow blocking the box",
+        "Vehicles may start a turn even if there's no room on the other side",
+    ),
+    (
+        "Don't change lanes along the way",
+        "Vehicles stick to the lanes chosen at the start of the trip",
+    ),
+    (
+        "Don't break turn conflict cycles",
+        "Normally vehicles stuck waiting on each other may block the box to break gridlock",
+    ),
+    (
+        "Don't handle uber-turns",
+        "Uber-turns lock a whole sequence of turns through complex intersections at once",
+    ),
+    (
+        "Infinite parking",
+        "Ignore parking data and let every building hold any number of vehicles",
+    ),
+    (
+        "Disable turn conflicts",
+        "Everyone can enter intersections immediately, even if they'd hit somebody",
+    ),
+    (
+        "Skip analytics",
+        "Run faster, but without any data for dashboards",
+    ),
+    (
+        "Allow jaywalking",
+        "Pedestrians cross slow, narrow roads midblock when there's a gap in traffic",
+    ),
+    (
+        "Limit inflow at borders",
+        "Vehicles entering the map take turns on each lane, instead of all appearing at once",
+    ),
+];
+
+fn toggle_field<'a>(opts: &'a mut SimOptions, label: &str) -> &'a mut bool {
+    match label {
+        "Ignore stop signs and traffic signals" => &mut opts.use_freeform_policy_everywhere,
+        "Allow blocking the box" => &mut opts.allow_block_the_box,
+        "Don't change lanes along the way" => &mut opts.dont_recalc_lanechanging,
+        "Don't break turn conflict cycles" => &mut opts.dont_break_turn_conflict_cycles,
+        "Don't handle uber-turns" => &mut opts.dont_handle_uber_turns,
+        "Infinite parking" => &mut opts.infinite_parking,
+        "Disable turn conflicts" => &mut opts.disable_turn_conflicts,
+        "Skip analytics" => &mut opts.skip_analytics,
+        "Allow jaywalking" => &mut opts.allow_jaywalking,
+        "Limit inflow at borders" => &mut opts.limit_border_inflow,
+        _ => unreachable!(),
+    }
+}
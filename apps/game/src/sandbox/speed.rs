@@ -1,8 +1,8 @@
 use crate::ID;
 use abstutil::prettyprint_usize;
 use geom::{Circle, Distance, Duration, Polygon, Pt2D, Ring, Time};
-use sim::AlertLocation;
-use widgetry::tools::PopupMsg;
+use sim::{Alert, AlertLocation, AlertSeverity};
+use widgetry::tools::{Confirm, PopupMsg, Severity, Toast};
 use widgetry::{
     Choice, Color, ControlState, DrawWithTooltips, EdgeInsets, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Panel, PanelDims, PersistentSplit, ScreenDims, Text,
@@ -11,6 +11,9 @@ use widgetry::{
 
 use crate::app::{App, Transition};
 use crate::common::Warping;
+use crate::sandbox::alerts::AlertsPanel;
+use crate::sandbox::breakpoints::EditBreakpoints;
+use crate::sandbox::sim_settings::SimSettings;
 use crate::sandbox::time_warp::JumpToTime;
 use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
 
@@ -141,6 +144,27 @@ impl TimePanel {
                 .build_widget(ctx, "jump to specific time"),
         );
 
+        if app.opts.dev {
+            row.push(
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/pin.svg")
+                    .build_widget(ctx, "breakpoints"),
+            );
+        }
+
+        row.push(
+            ctx.style()
+                .btn_plain
+                .icon("system/assets/tools/alert.svg")
+                .build_widget(ctx, "alerts"),
+        );
+        row.push(
+            ctx.style()
+                .btn_plain
+                .icon("system/assets/tools/settings.svg")
+                .build_widget(ctx, "simulation settings"),
+        );
         row.push(
             ctx.style()
                 .btn_plain
@@ -376,9 +400,21 @@ impl TimePanel {
                 }
                 "reset to midnight" => {
                     if let Some(mode) = maybe_mode {
-                        return Some(Transition::Replace(SandboxMode::simple_new(
-                            app,
-                            mode.clone(),
+                        let mode = mode.clone();
+                        if app.primary.sim.time() == Time::START_OF_DAY {
+                            return Some(Transition::Replace(SandboxMode::simple_new(app, mode)));
+                        }
+                        return Some(Transition::Push(Confirm::new_state(
+                            ctx,
+                            "Reset to midnight?",
+                            vec![format!(
+                                "The simulation will restart, losing progress up to {}.",
+                                app.primary.sim.time().ampm_tostring()
+                            )],
+                            "Reset",
+                            Box::new(move |_, app| {
+                                Transition::Replace(SandboxMode::simple_new(app, mode))
+                            }),
                         )));
                     } else {
                         return Some(Transition::Push(PopupMsg::new_state(
@@ -395,6 +431,26 @@ impl TimePanel {
                         maybe_mode.cloned(),
                     )));
                 }
+                "simulation settings" => {
+                    if let Some(mode) = maybe_mode {
+                        return Some(Transition::Push(SimSettings::new_state(
+                            ctx,
+                            app,
+                            mode.clone(),
+                        )));
+                    }
+                    return Some(Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Error",
+                        vec!["Sorry, you can't change simulation settings in this mode."],
+                    )));
+                }
+                "breakpoints" => {
+                    return Some(Transition::Push(EditBreakpoints::new_state(ctx, app)));
+                }
+                "alerts" => {
+                    return Some(Transition::Push(AlertsPanel::new_state(ctx, app)));
+                }
                 "step forwards" => {
                     let dt = self.panel.persistent_split_value("step forwards");
                     if dt == Duration::seconds(0.1) {
@@ -486,6 +542,7 @@ impl TimePanel {
                     SpeedSetting::Fastest => 3600.0,
                 };
                 let dt = multiplier * real_dt;
+                let was_done = app.primary.sim.is_done();
                 // TODO This should match the update frequency in widgetry. Plumb along the deadline
                 // or frequency to here.
                 app.primary.sim.time_limited_step(
@@ -495,18 +552,31 @@ impl TimePanel {
                     &mut app.primary.sim_cb,
                 );
                 app.recalculate_current_selection(ctx);
+                if !was_done && app.primary.sim.is_done() {
+                    ctx.show_toast(Toast::new(
+                        Severity::Success,
+                        "Simulation finished",
+                        vec![format!(
+                            "Every trip finished by {}",
+                            app.primary.sim.time().ampm_tostring()
+                        )],
+                    ));
+                }
             }
         }
 
+        // Alerts that don't pause the simulation, like stuck agents, are just noted
+        for alert in app.primary.sim.take_printed_alerts() {
+            ctx.show_toast(alert_toast(&alert));
+        }
+
         // TODO Need to do this anywhere that steps the sim, like TimeWarpScreen.
         let alerts = app.primary.sim.clear_alerts();
         if !alerts.is_empty() {
-            let popup = PopupMsg::new_state(
-                ctx,
-                "Alerts",
-                alerts.iter().map(|(_, _, msg)| msg).collect(),
-            );
-            let maybe_id = match alerts[0].1 {
+            for alert in &alerts {
+                ctx.show_toast(alert_toast(alert));
+            }
+            let maybe_id = match alerts[0].location {
                 AlertLocation::Nil => None,
                 AlertLocation::Intersection(i) => Some(ID::Intersection(i)),
                 // TODO Open info panel and warp to them
@@ -514,7 +584,7 @@ impl TimePanel {
                 AlertLocation::Building(b) => Some(ID::Building(b)),
             };
             // TODO Can filter for particular alerts places like this:
-            /*if !alerts[0].2.contains("Turn conflict cycle") {
+            /*if !alerts[0].msg.contains("Turn conflict cycle") {
                 return None;
             }*/
             /*if maybe_id != Some(ID::Building(map_model::BuildingID(91))) {
@@ -522,19 +592,14 @@ impl TimePanel {
             }*/
             self.pause(ctx, app);
             if let Some(id) = maybe_id {
-                // Just go to the first one, but print all messages
-                return Some(Transition::Multi(vec![
-                    Transition::Push(popup),
-                    Transition::Push(Warping::new_state(
-                        ctx,
-                        app.primary.canonical_point(id).unwrap(),
-                        Some(10.0),
-                        None,
-                        &mut app.primary,
-                    )),
-                ]));
-            } else {
-                return Some(Transition::Push(popup));
+                // Just go to the first one, but show all messages
+                return Some(Transition::Push(Warping::new_state(
+                    ctx,
+                    app.primary.canonical_point(id).unwrap(),
+                    Some(10.0),
+                    None,
+                    &mut app.primary,
+                )));
             }
         }
 
@@ -564,3 +629,16 @@ impl TimePanel {
         self.paused
     }
 }
+
+fn alert_toast(alert: &Alert) -> Toast {
+    let severity = match alert.category.severity() {
+        AlertSeverity::Info => Severity::Info,
+        AlertSeverity::Warning => Severity::Warning,
+        AlertSeverity::Error => Severity::Error,
+    };
+    Toast::new(
+        severity,
+        &format!("Alert at {}", alert.time.ampm_tostring()),
+        vec![&alert.msg],
+    )
+}
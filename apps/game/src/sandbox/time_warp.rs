@@ -5,10 +5,12 @@ use abstutil::prettyprint_usize;
 use geom::{Duration, Polygon, Pt2D, Ring, Time};
 use map_gui::render::DrawOptions;
 use map_gui::tools::grey_out_map;
+use map_model::{IntersectionID, Map, TurnID};
+use sim::{AgentID, Sim, SimCallback, TripID, TripResult};
 use widgetry::tools::PopupMsg;
 use widgetry::{
     Choice, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, PanelDims,
-    Slider, State, TabController, Text, Toggle, UpdateType, Widget,
+    Slider, Spinner, State, TabController, Text, TextExt, Toggle, UpdateType, Widget,
 };
 
 use crate::app::{App, FindDelayedIntersections, ShowEverything, Transition};
@@ -21,6 +23,7 @@ pub struct JumpToTime {
     target: Time,
     maybe_mode: Option<GameplayMode>,
     tabs: TabController,
+    run_until: RunUntil,
 }
 
 impl JumpToTime {
@@ -92,13 +95,75 @@ impl JumpToTime {
             build_jump_to_delay_button(ctx, app.opts.jump_to_delay),
         ]);
 
+        let step_btn = ctx
+            .style()
+            .btn_tab
+            .text("Step exactly")
+            .hotkey(Key::S)
+            .tooltip("Step exactly");
+        let step_content = Widget::col(vec![
+            Line("Advance the simulation by exactly")
+                .small_heading()
+                .into_widget(ctx),
+            Widget::row(vec![
+                Spinner::<usize>::widget(ctx, "step minutes", (0, 180), 0, 1),
+                "minutes".text_widget(ctx).centered_vert(),
+                Spinner::<usize>::widget(ctx, "step seconds", (0, 59), 1, 1),
+                "seconds".text_widget(ctx).centered_vert(),
+            ]),
+            ctx.style()
+                .btn_solid_primary
+                .text("Step")
+                .hotkey(Key::Enter)
+                .build_widget(ctx, "step exactly")
+                .centered_horiz()
+                .margin_above(16),
+        ]);
+
+        let run_until = RunUntil::TripStarts;
+        let run_until_btn = ctx
+            .style()
+            .btn_tab
+            .text("Run until")
+            .hotkey(Key::U)
+            .tooltip("Run until");
+        let run_until_content = Widget::col(vec![
+            Widget::row(vec![
+                Line("Run until").small_heading().into_widget(ctx),
+                Widget::dropdown(
+                    ctx,
+                    "run until",
+                    run_until,
+                    vec![
+                        Choice::new("trip starts", RunUntil::TripStarts),
+                        Choice::new("trip ends", RunUntil::TripEnds),
+                        Choice::new(
+                            "something happens at intersection",
+                            RunUntil::IntersectionEvent,
+                        ),
+                    ],
+                ),
+            ]),
+            build_run_until_id(ctx, app, run_until),
+            ctx.style()
+                .btn_solid_primary
+                .text("Run")
+                .hotkey(Key::Enter)
+                .build_widget(ctx, "run until event")
+                .centered_horiz()
+                .margin_above(16),
+        ]);
+
         let mut tabs = TabController::new("jump_to_time_tabs");
         tabs.push_tab(jump_to_time_btn, jump_to_time_content);
         tabs.push_tab(jump_to_delay_btn, jump_to_delay_content);
+        tabs.push_tab(step_btn, step_content);
+        tabs.push_tab(run_until_btn, run_until_content);
 
         Box::new(JumpToTime {
             target,
             maybe_mode,
+            run_until,
             panel: Panel::new_builder(Widget::col(vec![
                 ctx.style().btn_close_widget(ctx),
                 tabs.build_widget(ctx),
@@ -159,6 +224,28 @@ impl State<App> for JumpToTime {
                         Some(delay),
                     ));
                 }
+                "step exactly" => {
+                    let dt = Duration::minutes(self.panel.spinner("step minutes"))
+                        + Duration::seconds(self.panel.spinner::<usize>("step seconds") as f64);
+                    if dt == Duration::ZERO {
+                        return Transition::Pop;
+                    }
+                    return Transition::Replace(TimeWarpScreen::new_state(
+                        ctx,
+                        app,
+                        app.primary.sim.time() + dt,
+                        None,
+                    ));
+                }
+                "run until event" => {
+                    let id = self.panel.spinner("run until id");
+                    return Transition::Replace(TimeWarpScreen::run_until(
+                        ctx,
+                        app,
+                        self.run_until,
+                        id,
+                    ));
+                }
                 action => {
                     if self.tabs.handle_action(ctx, action, &mut self.panel) {
                         // if true, tabs has handled the action
@@ -176,6 +263,17 @@ impl State<App> for JumpToTime {
                         build_jump_to_delay_button(ctx, self.panel.dropdown_value("delay")),
                     );
                 }
+                if self.tabs.active_tab_idx() == 3 {
+                    let run_until = self.panel.dropdown_value("run until");
+                    if run_until != self.run_until {
+                        self.run_until = run_until;
+                        self.panel.replace(
+                            ctx,
+                            "run until id row",
+                            build_run_until_id(ctx, app, run_until),
+                        );
+                    }
+                }
             }
             _ => {}
         }
@@ -212,7 +310,8 @@ pub struct TimeWarpScreen {
     target: Time,
     wall_time_started: Instant,
     sim_time_started: geom::Time,
-    halt_upon_delay: Option<Duration>,
+    /// If true, this state installed the sim callback and has to clean it up
+    owns_callback: bool,
     panel: Panel,
 }
 
@@ -221,8 +320,9 @@ impl TimeWarpScreen {
         ctx: &mut EventCtx,
         app: &mut App,
         target: Time,
-        mut halt_upon_delay: Option<Duration>,
+        halt_upon_delay: Option<Duration>,
     ) -> Box<dyn State<App>> {
+        let mut owns_callback = false;
         if let Some(halt_limit) = halt_upon_delay {
             if app.primary.sim_cb.is_none() {
                 app.primary.sim_cb = Some(Box::new(FindDelayedIntersections {
@@ -232,16 +332,60 @@ impl TimeWarpScreen {
                 }));
                 // TODO Can we get away with less frequently? Not sure about all the edge cases
                 app.primary.sim.set_periodic_callback(Duration::minutes(1));
-            } else {
-                halt_upon_delay = None;
+                owns_callback = true;
             }
         }
+        TimeWarpScreen::new_with_callback(ctx, app, target, owns_callback)
+    }
 
+    /// Run until the end of the day, halting as soon as some trip starts or ends, or something
+    /// changes at an intersection.
+    pub fn run_until(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        until: RunUntil,
+        id: usize,
+    ) -> Box<dyn State<App>> {
+        if app.primary.sim_cb.is_some() {
+            return PopupMsg::new_state(
+                ctx,
+                "Error",
+                vec!["Something else is already watching the simulation in this mode."],
+            );
+        }
+        let mut watch = WatchForEvent {
+            until,
+            id,
+            last_intersection_state: None,
+            happened: false,
+        };
+        // Learn what the intersection is doing right now, or notice the trip already happened
+        if watch.run(&app.primary.sim, &app.primary.map) {
+            return PopupMsg::new_state(
+                ctx,
+                "Already happened",
+                vec![format!("Trip #{} already {}", id, until.past_tense())],
+            );
+        }
+        app.primary.sim_cb = Some(Box::new(watch));
+        app.primary
+            .sim
+            .set_periodic_callback(until.check_frequency());
+        let target = app.primary.sim.get_end_of_day();
+        TimeWarpScreen::new_with_callback(ctx, app, target, true)
+    }
+
+    fn new_with_callback(
+        ctx: &mut EventCtx,
+        app: &App,
+        target: Time,
+        owns_callback: bool,
+    ) -> Box<dyn State<App>> {
         Box::new(TimeWarpScreen {
             target,
             wall_time_started: Instant::now(),
             sim_time_started: app.primary.sim.time(),
-            halt_upon_delay,
+            owns_callback,
             panel: Panel::new_builder(
                 Widget::col(vec![
                     Widget::placeholder(ctx, "text"),
@@ -278,27 +422,55 @@ impl State<App> for TimeWarpScreen {
                 &mut app.primary.sim_cb,
             );
             #[allow(clippy::never_loop)]
-            for (t, maybe_i, alert) in app.primary.sim.clear_alerts() {
+            for alert in app.primary.sim.clear_alerts() {
                 // TODO Just the first :(
                 return Transition::Replace(PopupMsg::new_state(
                     ctx,
                     "Alert",
-                    vec![format!("At {}, near {:?}, {}", t, maybe_i, alert)],
+                    vec![format!(
+                        "At {}, near {:?}, {}",
+                        alert.time, alert.location, alert.msg
+                    )],
                 ));
             }
             if let Some(ref mut cb) = app.primary.sim_cb {
-                let di = cb.downcast_mut::<FindDelayedIntersections>().unwrap();
-                if let Some((i, t)) = di.currently_delayed.get(0) {
-                    if app.primary.sim.time() - *t > di.halt_limit {
-                        let id = ID::Intersection(*i);
-                        app.primary.layer =
-                            Some(Box::new(crate::layer::traffic::TrafficJams::new(ctx, app)));
-                        return Transition::Replace(Warping::new_state(
+                if let Some(di) = cb.downcast_mut::<FindDelayedIntersections>() {
+                    if let Some((i, t)) = di.currently_delayed.get(0) {
+                        if app.primary.sim.time() - *t > di.halt_limit {
+                            let id = ID::Intersection(*i);
+                            app.primary.layer =
+                                Some(Box::new(crate::layer::traffic::TrafficJams::new(ctx, app)));
+                            return Transition::Replace(Warping::new_state(
+                                ctx,
+                                app.primary.canonical_point(id.clone()).unwrap(),
+                                Some(10.0),
+                                Some(id),
+                                &mut app.primary,
+                            ));
+                        }
+                    }
+                } else if let Some(watch) = cb.downcast_ref::<WatchForEvent>() {
+                    if watch.happened {
+                        let id = watch.location(&app.primary.sim);
+                        if let Some(pt) = id.clone().and_then(|id| app.primary.canonical_point(id))
+                        {
+                            return Transition::Replace(Warping::new_state(
+                                ctx,
+                                pt,
+                                Some(10.0),
+                                id,
+                                &mut app.primary,
+                            ));
+                        }
+                        return Transition::Replace(PopupMsg::new_state(
                             ctx,
-                            app.primary.canonical_point(id.clone()).unwrap(),
-                            Some(10.0),
-                            Some(id),
-                            &mut app.primary,
+                            "Stopped",
+                            vec![format!(
+                                "Trip #{} {} at {}",
+                                watch.id,
+                                watch.until.past_tense(),
+                                app.primary.sim.time().ampm_tostring()
+                            )],
                         ));
                     }
                 }
@@ -393,7 +565,7 @@ impl State<App> for TimeWarpScreen {
     }
 
     fn on_destroy(&mut self, _: &mut EventCtx, app: &mut App) {
-        if self.halt_upon_delay.is_some() {
+        if self.owns_callback {
             assert!(app.primary.sim_cb.is_some());
             app.primary.sim_cb = None;
             app.primary.sim.unset_periodic_callback();
@@ -401,6 +573,94 @@ impl State<App> for TimeWarpScreen {
     }
 }
 
+/// Something specific to run the simulation until
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RunUntil {
+    TripStarts,
+    TripEnds,
+    /// An agent enters, leaves, or starts waiting at an intersection, or a traffic signal changes
+    /// stage
+    IntersectionEvent,
+}
+
+impl RunUntil {
+    fn past_tense(self) -> &'static str {
+        match self {
+            RunUntil::TripStarts => "started",
+            RunUntil::TripEnds => "finished",
+            RunUntil::IntersectionEvent => "changed",
+        }
+    }
+
+    /// Intersections are watched closely, for stepping through them one change at a time. A trip
+    /// starting or ending a second late doesn't matter.
+    fn check_frequency(self) -> Duration {
+        match self {
+            RunUntil::TripStarts | RunUntil::TripEnds => Duration::seconds(1.0),
+            RunUntil::IntersectionEvent => Duration::seconds(0.1),
+        }
+    }
+}
+
+struct WatchForEvent {
+    until: RunUntil,
+    /// A trip or intersection ID
+    id: usize,
+    /// Accepted agents, waiting agents, and the current stage of a traffic signal
+    last_intersection_state: Option<(Vec<(AgentID, TurnID)>, Vec<AgentID>, Option<usize>)>,
+    happened: bool,
+}
+
+impl WatchForEvent {
+    /// Where to look once the event happens
+    fn location(&self, sim: &Sim) -> Option<ID> {
+        match self.until {
+            RunUntil::TripStarts | RunUntil::TripEnds => {
+                sim.trip_to_agent(TripID(self.id)).ok().map(ID::from_agent)
+            }
+            RunUntil::IntersectionEvent => Some(ID::Intersection(IntersectionID(self.id))),
+        }
+    }
+}
+
+impl SimCallback for WatchForEvent {
+    fn run(&mut self, sim: &Sim, map: &Map) -> bool {
+        self.happened = match self.until {
+            RunUntil::TripStarts => !matches!(
+                sim.trip_to_agent(TripID(self.id)),
+                TripResult::TripNotStarted
+            ),
+            RunUntil::TripEnds => matches!(
+                sim.trip_to_agent(TripID(self.id)),
+                TripResult::TripDone | TripResult::TripCancelled
+            ),
+            RunUntil::IntersectionEvent => {
+                let i = IntersectionID(self.id);
+                let state = (
+                    sim.get_accepted_agents(i),
+                    sim.get_waiting_agents(i)
+                        .into_iter()
+                        .map(|(a, _, _)| a)
+                        .collect(),
+                    if map.get_i(i).is_traffic_signal() {
+                        Some(sim.current_stage_and_remaining_time(i).0)
+                    } else {
+                        None
+                    },
+                );
+                let changed = self
+                    .last_intersection_state
+                    .as_ref()
+                    .map(|prev| *prev != state)
+                    .unwrap_or(false);
+                self.last_intersection_state = Some(state);
+                changed
+            }
+        };
+        self.happened
+    }
+}
+
 fn area_under_curve(raw: Vec<(Time, usize)>, width: f64, height: f64) -> Polygon {
     assert!(!raw.is_empty());
     let min_x = Time::START_OF_DAY;
@@ -456,3 +716,40 @@ fn build_jump_to_delay_button(ctx: &EventCtx, delay: Duration) -> Widget {
         .centered_horiz()
         .margin_above(16)
 }
+
+fn build_run_until_id(ctx: &EventCtx, app: &App, until: RunUntil) -> Widget {
+    let (label, max, current) = match until {
+        RunUntil::TripStarts | RunUntil::TripEnds => (
+            "Trip #",
+            {
+                let (finished, unfinished) = app.primary.sim.num_trips();
+                finished + unfinished
+            },
+            app.primary
+                .current_selection
+                .as_ref()
+                .and_then(|id| id.agent_id())
+                .and_then(|a| app.primary.sim.agent_to_trip(a))
+                .map(|t| t.0),
+        ),
+        RunUntil::IntersectionEvent => (
+            "Intersection #",
+            app.primary.map.all_intersections().len(),
+            match app.primary.current_selection {
+                Some(ID::Intersection(i)) => Some(i.0),
+                _ => None,
+            },
+        ),
+    };
+    Widget::row(vec![
+        label.text_widget(ctx).centered_vert(),
+        Spinner::widget(
+            ctx,
+            "run until id",
+            (0, max.max(1) - 1),
+            current.unwrap_or(0),
+            1,
+        ),
+    ])
+    .named("run until id row")
+}
@@ -174,7 +174,7 @@ impl TurnExplorer {
                 ));
             }
         } else {
-            let (lt, lc, slow_lane) = turns[idx - 1].penalty(PathConstraints::Car, app.map());
+            let (lt, lc, slow_lane) = turns[idx - 1].penalty(PathConstraints::Car, None, app.map());
             let (vehicles, bike) = app
                 .primary
                 .sim
@@ -97,7 +97,7 @@ impl Layers {
                     "painted bike lane" => PopupMsg::new_state(ctx, "Painted bike lanes", vec!["Bike lanes without any separation from vehicle traffic. Often uncomfortably close to the \"door zone\" of parked cars."]),
                     "greenway" => PopupMsg::new_state(ctx, "Stay Healthy Streets and neighborhood greenways", vec!["Residential streets with additional signage and light barriers. These are intended to be low traffic, dedicated for people walking and biking."]),
                     // TODO Add URLs
-                    "about the elevation data" => PopupMsg::new_state(ctx, "About the elevation data", vec!["Biking uphill next to traffic without any dedicated space isn't fun.", "Biking downhill next to traffic, especially in the door-zone of parked cars, and especially on Seattle's bumpy roads... is downright terrifying.", "", "Note the elevation data is incorrect near bridges.", "Thanks to King County LIDAR and Ordnance Survey for the data, and Eldan Goldenberg for processing it."]),
+                    "about the elevation data" => PopupMsg::new_state(ctx, "About the elevation data", vec!["Biking uphill next to traffic without any dedicated space isn't fun.", "Biking downhill next to traffic, especially in the door-zone of parked cars, and especially on Seattle's bumpy roads... is downright terrifying.", "", "Note the elevation data is incorrect near bridges.", "Thanks to King County LIDAR and Ordnance Survey for the data, and Eldan Goldenberg for processing it.", "Elsewhere, the data comes from digital elevation models like SRTM, if any were used when importing the map."]),
                    "zoom map out" => {
                         ctx.canvas.center_zoom(-8.0);
                         self.update_panel(ctx, app);
@@ -164,11 +164,9 @@ impl Layers {
                     if self.panel.is_checked("steep streets") {
                         let (mut colorer, _, _) =
                             crate::layer::elevation::SteepStreets::make_colorer(ctx, app);
-                        // The Colorer fades the map as the very first thing in the batch, but we
-                        // don't want to do that twice.
-                        // TODO Can't use no_fading without complicating make_colorer...
-                        colorer.draw.unzoomed.shift();
-                        self.steep_streets = Some(colorer.draw.unzoomed.upload(ctx));
+                        // The Colorer fades the map, but we don't want to do that twice.
+                        colorer.disable_fading();
+                        self.steep_streets = Some(colorer.into_builder().unzoomed.upload(ctx));
                     } else {
                         self.steep_streets = None;
                     }
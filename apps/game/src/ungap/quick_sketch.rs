@@ -225,6 +225,7 @@ fn make_quick_changes(
             buffer_type,
             app.primary.map.get_config().driving_side,
         );
+        new.lanes_rearranged(&old.lanes_ltr);
         if old == new {
             unchanged += 1;
         } else {
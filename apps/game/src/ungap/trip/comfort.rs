@@ -0,0 +1,315 @@
+use std::collections::BTreeSet;
+
+use abstutil::Timer;
+use geom::{Distance, Duration};
+use map_model::{
+    Direction, Map, Path, PathStep, PathfinderCaching, RoadID, RoutingParams, NORMAL_LANE_THICKNESS,
+};
+use synthpop::{TripEndpoint, TripMode};
+use widgetry::tools::ColorLegend;
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State,
+    Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::layer::map::{traffic_stress_color, TRAFFIC_STRESS_LABELS};
+
+/// Checking how much fixing a barrier helps means pathfinding again, so only do it for the
+/// longest few.
+const MAX_BARRIERS: usize = 5;
+
+/// For one trip, compares the best route for people only willing to ride on roads up to some level
+/// of traffic stress. Stressful roads along the fastest route are barriers forcing a detour. For
+/// each of those, estimate how much shorter the low-stress route would be if that road was made
+/// comfortable, like with a protected bike lane.
+pub struct ComfortReport {
+    panel: Panel,
+    draw: Drawable,
+}
+
+struct Barrier {
+    road: RoadID,
+    stress: usize,
+    length: Distance,
+    /// How long the low-stress route is if this road is allowed. `None` if there's still no route.
+    dist_if_fixed: Option<Distance>,
+}
+
+impl ComfortReport {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        waypoints: Vec<TripEndpoint>,
+    ) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Route comfort report")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "Stressful roads on the fastest route, and the detours needed to avoid them"
+                .text_widget(ctx),
+        ];
+        let mut batch = GeomBatch::new();
+
+        ctx.loading_screen("calculate routes by stress tolerance", |ctx, timer| {
+            let fastest = match pathfind(map, &waypoints, &RoutingParams::default()) {
+                Some(paths) => paths,
+                None => {
+                    col.push("There's no route by bike at all".text_widget(ctx));
+                    return;
+                }
+            };
+            let (fastest_dist, fastest_time) = stats(map, &fastest);
+            col.push(
+                Text::from_all(vec![
+                    Line("Fastest route: ").secondary(),
+                    Line(format!(
+                        "{}, {}",
+                        fastest_dist.to_string(&app.opts.units),
+                        fastest_time.to_string(&app.opts.units)
+                    )),
+                ])
+                .into_widget(ctx),
+            );
+            for path in &fastest {
+                if let Some(pl) = path.trace(map) {
+                    batch.push(
+                        Color::grey(0.3).alpha(0.8),
+                        pl.make_polygons(5.0 * NORMAL_LANE_THICKNESS),
+                    );
+                }
+            }
+
+            // Nobody needs to avoid roads if they tolerate the worst one on the fastest route
+            let max_stress = find_barriers(map, &fastest, 0)
+                .into_iter()
+                .map(|b| b.stress)
+                .max()
+                .unwrap_or(1);
+            timer.start_iter("check stress tolerances", max_stress - 1);
+            for tolerance in 1..max_stress {
+                timer.next();
+                col.push(
+                    tolerance_report(
+                        ctx,
+                        app,
+                        &waypoints,
+                        &fastest,
+                        fastest_dist,
+                        tolerance,
+                        &mut batch,
+                        timer,
+                    )
+                    .section(ctx),
+                );
+            }
+            if max_stress == 1 {
+                col.push("The fastest route is already comfortable for all ages".text_widget(ctx));
+            }
+        });
+
+        for lts in 1..=4 {
+            col.push(ColorLegend::row(
+                ctx,
+                traffic_stress_color(lts),
+                TRAFFIC_STRESS_LABELS[lts - 1],
+            ));
+        }
+
+        Box::new(ComfortReport {
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .exact_size_percent(30, 90)
+                .build(ctx),
+            draw: ctx.upload(batch),
+        })
+    }
+}
+
+impl State<App> for ComfortReport {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
+
+fn tolerance_report(
+    ctx: &mut EventCtx,
+    app: &App,
+    waypoints: &[TripEndpoint],
+    fastest: &[Path],
+    fastest_dist: Distance,
+    tolerance: usize,
+    batch: &mut GeomBatch,
+    timer: &mut Timer,
+) -> Widget {
+    let map = &app.primary.map;
+    let units = &app.opts.units;
+    let avoid = too_stressful(map, tolerance);
+    let low_stress = pathfind(map, waypoints, &avoid_params(avoid.clone()));
+
+    let mut txt = Text::from(
+        Line(format!(
+            "Tolerating up to {}",
+            TRAFFIC_STRESS_LABELS[tolerance - 1]
+        ))
+        .small_heading(),
+    );
+    let low_stress_dist = if let Some(ref paths) = low_stress {
+        let (dist, time) = stats(map, paths);
+        txt.add_line(Line(format!(
+            "Route: {}, {}",
+            dist.to_string(units),
+            time.to_string(units)
+        )));
+        if fastest_dist > Distance::ZERO {
+            txt.add_line(Line(format!(
+                "Detour: {:.2}x the fastest route",
+                dist / fastest_dist
+            )));
+        }
+        Some(dist)
+    } else {
+        txt.add_line(Line("No route avoids the barriers").fg(Color::RED));
+        None
+    };
+
+    let mut barriers = find_barriers(map, fastest, tolerance);
+    barriers.sort_by_key(|b| std::cmp::Reverse(b.length));
+    timer.start_iter("check barriers", barriers.len().min(MAX_BARRIERS));
+    for barrier in barriers.iter_mut().take(MAX_BARRIERS) {
+        timer.next();
+        let mut avoid = avoid.clone();
+        avoid.remove(&barrier.road);
+        barrier.dist_if_fixed =
+            pathfind(map, waypoints, &avoid_params(avoid)).map(|paths| stats(map, &paths).0);
+    }
+
+    txt.add_line(Line(format!(
+        "{} barriers on the fastest route",
+        barriers.len()
+    )));
+    for barrier in &barriers {
+        let road = map.get_r(barrier.road);
+        batch.push(
+            traffic_stress_color(barrier.stress),
+            road.get_thick_polygon(),
+        );
+
+        txt.add_line(Line(format!(
+            "- {} (LTS {}, {})",
+            road.get_name(app.opts.language.as_ref()),
+            barrier.stress,
+            barrier.length.to_string(units)
+        )));
+        let benefit = match (low_stress_dist, barrier.dist_if_fixed) {
+            (Some(before), Some(after)) if after < before => {
+                format!(
+                    "fixing this shortens the route by {}",
+                    (before - after).to_string(units)
+                )
+            }
+            (Some(_), Some(_)) => "fixing only this doesn't help".to_string(),
+            (None, Some(after)) => format!(
+                "fixing this makes a {} route possible",
+                after.to_string(units)
+            ),
+            // Not checked, or still impossible
+            (_, None) => continue,
+        };
+        txt.add_line(Line(format!("    {}", benefit)).secondary());
+    }
+    txt.into_widget(ctx)
+}
+
+/// Pathfinds between each pair of waypoints. Returns `None` if any part of the trip isn't possible.
+fn pathfind(map: &Map, waypoints: &[TripEndpoint], params: &RoutingParams) -> Option<Vec<Path>> {
+    waypoints
+        .windows(2)
+        .map(|pair| {
+            TripEndpoint::path_req(pair[0], pair[1], TripMode::Bike, map).and_then(|req| {
+                map.pathfind_with_params(req, params, PathfinderCaching::NoCache)
+                    .ok()
+            })
+        })
+        .collect()
+}
+
+fn stats(map: &Map, paths: &[Path]) -> (Distance, Duration) {
+    let mut dist = Distance::ZERO;
+    let mut time = Duration::ZERO;
+    for path in paths {
+        dist += path.total_length();
+        time += path.estimate_duration(map, Some(map_model::MAX_BIKE_SPEED));
+    }
+    (dist, time)
+}
+
+fn avoid_params(avoid_roads: BTreeSet<RoadID>) -> RoutingParams {
+    RoutingParams {
+        avoid_roads,
+        ..Default::default()
+    }
+}
+
+/// Roads that're too stressful in either direction. Being stricter than needed for two-way roads
+/// is simpler than routing by direction.
+fn too_stressful(map: &Map, tolerance: usize) -> BTreeSet<RoadID> {
+    map.all_roads()
+        .iter()
+        .filter(|r| {
+            [Direction::Fwd, Direction::Back]
+                .into_iter()
+                .filter_map(|dir| r.bike_traffic_stress(map, dir))
+                .any(|lts| lts > tolerance)
+        })
+        .map(|r| r.id)
+        .collect()
+}
+
+/// Stressful roads along the paths, in the direction travelled
+fn find_barriers(map: &Map, paths: &[Path], tolerance: usize) -> Vec<Barrier> {
+    let mut barriers: Vec<Barrier> = Vec::new();
+    for path in paths {
+        for step in path.get_steps() {
+            if let PathStep::Lane(l) | PathStep::ContraflowLane(l) = step {
+                let road = map.get_parent(*l);
+                let stress = match road.bike_traffic_stress(map, road.lanes[l.offset].dir) {
+                    Some(lts) if lts > tolerance => lts,
+                    _ => continue,
+                };
+                let length = road.length();
+                if let Some(barrier) = barriers.iter_mut().find(|b| b.road == road.id) {
+                    barrier.length += length;
+                } else {
+                    barriers.push(Barrier {
+                        road: road.id,
+                        stress,
+                        length,
+                        dist_if_fixed: None,
+                    });
+                }
+            }
+        }
+    }
+    barriers
+}
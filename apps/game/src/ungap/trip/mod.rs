@@ -2,13 +2,16 @@ use map_gui::tools::{InputWaypoints, TripManagement, TripManagementState, Waypoi
 use map_model::{PathConstraints, RoutingParams};
 use widgetry::mapspace::{ObjectID, World, WorldOutcome};
 use widgetry::{
-    ControlState, EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, Text, Toggle, Widget,
+    ControlState, EventCtx, GfxCtx, Key, Line, Outcome, Panel, Slider, State, Text, TextExt,
+    Toggle, Widget,
 };
 
+use self::comfort::ComfortReport;
 use self::results::RouteDetails;
 use crate::app::{App, Transition};
 use crate::ungap::{Layers, Tab, TakeLayers};
 
+mod comfort;
 mod results;
 
 pub struct TripPlanner {
@@ -102,11 +105,11 @@ impl TripPlanner {
         for preferences in [
             RoutingPreferences {
                 avoid_hills: false,
-                avoid_stressful_roads: false,
+                avoid_stress: 0,
             },
             RoutingPreferences {
                 avoid_hills: true,
-                avoid_stressful_roads: true,
+                avoid_stress: 100,
             },
         ] {
             if app.session.routing_preferences == preferences
@@ -155,19 +158,23 @@ impl TripPlanner {
         .section(ctx)];
         if self.waypoints.len() >= 2 {
             sections.push(
-                Widget::row(vec![
+                Widget::col(vec![
                     Toggle::checkbox(
                         ctx,
                         "Avoid steep hills",
                         None,
                         app.session.routing_preferences.avoid_hills,
                     ),
-                    Toggle::checkbox(
-                        ctx,
-                        "Avoid stressful roads",
-                        None,
-                        app.session.routing_preferences.avoid_stressful_roads,
-                    ),
+                    Widget::row(vec![
+                        "Fastest".text_widget(ctx).centered_vert(),
+                        Slider::area(
+                            ctx,
+                            0.1 * ctx.canvas.window_width,
+                            (app.session.routing_preferences.avoid_stress as f64) / 100.0,
+                            "Avoid stressful roads",
+                        ),
+                        "Low-stress".text_widget(ctx).centered_vert(),
+                    ]),
                 ])
                 .section(ctx),
             );
@@ -214,6 +221,13 @@ impl State<App> for TripPlanner {
                 }
                 return t;
             }
+            if x == "comfort report" {
+                return Transition::Push(ComfortReport::new_state(
+                    ctx,
+                    app,
+                    self.waypoints.get_waypoints(),
+                ));
+            }
             if x == "show original map" || x == "show edited map" {
                 app.swap_map();
                 // We're assuming building and intersection IDs haven't changed
@@ -224,10 +238,20 @@ impl State<App> for TripPlanner {
             }
         }
         if let Outcome::Changed(ref x) = panel_outcome {
-            if x == "Avoid steep hills" || x == "Avoid stressful roads" {
+            // Wait until the slider is released, since recalculating rebuilds the panel
+            if x == "Avoid steep hills"
+                || (x == "Avoid stressful roads" && ctx.input.left_mouse_button_released())
+            {
                 app.session.routing_preferences = RoutingPreferences {
                     avoid_hills: self.input_panel.is_checked("Avoid steep hills"),
-                    avoid_stressful_roads: self.input_panel.is_checked("Avoid stressful roads"),
+                    // Round to 10% steps
+                    avoid_stress: (self
+                        .input_panel
+                        .slider("Avoid stressful roads")
+                        .get_percent()
+                        * 10.0)
+                        .round() as usize
+                        * 10,
                 };
                 self.recalculate_routes(ctx, app);
                 return Transition::Keep;
@@ -277,7 +301,8 @@ impl State<App> for TripPlanner {
 #[derive(Clone, Copy, PartialEq)]
 pub struct RoutingPreferences {
     avoid_hills: bool,
-    avoid_stressful_roads: bool,
+    /// From 0 (just go the fastest way) to 100 (strongly prefer low-stress roads)
+    avoid_stress: usize,
 }
 
 impl RoutingPreferences {
@@ -285,12 +310,12 @@ impl RoutingPreferences {
     pub fn default() -> Self {
         Self {
             avoid_hills: false,
-            avoid_stressful_roads: false,
+            avoid_stress: 0,
         }
     }
 
     fn name(self) -> &'static str {
-        match (self.avoid_hills, self.avoid_stressful_roads) {
+        match (self.avoid_hills, self.avoid_stress > 0) {
             (false, false) => "fastest",
             (true, false) => "flat",
             (false, true) => "low-stress",
@@ -301,7 +326,8 @@ impl RoutingPreferences {
     fn routing_params(self) -> RoutingParams {
         RoutingParams {
             avoid_steep_incline_penalty: if self.avoid_hills { 2.0 } else { 1.0 },
-            avoid_high_stress: if self.avoid_stressful_roads { 2.0 } else { 1.0 },
+            // At most, each level of traffic stress doubles the cost
+            traffic_stress_penalty: 1.0 + (self.avoid_stress as f64) / 100.0,
             ..Default::default()
         }
     }
@@ -405,6 +405,10 @@ fn make_detail_widget(
                 .label_underlined_text("high-stress roads")
                 .build_def(ctx),
         ]),
+        ctx.style()
+            .btn_outline
+            .text("Compare routes by stress tolerance")
+            .build_widget(ctx, "comfort report"),
         Text::from_all(vec![
             Line("Estimated time: ").secondary(),
             Line(stats.total_time.to_string(&app.opts.units)),
@@ -230,7 +230,8 @@ impl App {
             manage_proposals: false,
         };
 
-        let cs = ColorScheme::new(ctx, opts.color_scheme);
+        let mut cs = ColorScheme::new(ctx, opts.color_scheme);
+        cs.set_layer_palette(opts.layer_palette, opts.layer_patterns);
         let app = App {
             // Start with a blank map
             per_map: PerMap::new(
@@ -257,21 +258,18 @@ impl App {
         (app, states)
     }
 
-    /// Draw unzoomed, but after the water/park areas layer, draw something custom.
+    /// Draw unzoomed, but after the water/park areas layer, draw something custom. The map is
+    /// simplified depending on the current zoom.
     pub fn draw_with_layering<F: Fn(&mut GfxCtx)>(&self, g: &mut GfxCtx, custom: F) {
         g.clear(self.cs.void_background);
         g.redraw(&self.per_map.draw_map.boundary_polygon);
-        g.redraw(&self.per_map.draw_map.draw_all_areas);
+        self.per_map.draw_map.draw_unzoomed_areas(g);
         custom(g);
-        g.redraw(&self.per_map.draw_map.draw_all_unzoomed_parking_lots);
-        g.redraw(
-            &self
-                .per_map
-                .draw_map
-                .draw_all_unzoomed_roads_and_intersections,
-        );
-        g.redraw(&self.per_map.draw_map.draw_all_buildings);
-        g.redraw(&self.per_map.draw_map.draw_all_building_outlines);
+        self.per_map.draw_map.draw_unzoomed_parking_lots(g);
+        self.per_map
+            .draw_map
+            .draw_unzoomed_roads_and_intersections(g);
+        self.per_map.draw_map.draw_unzoomed_buildings(g);
     }
 
     pub fn edits(&self) -> &Edits {
@@ -362,7 +362,7 @@ impl Mode {
                 entry(ctx, *colors::POROUS, "porous (≥2 crossings)"),
             ],
             Mode::Impact => vec![
-                map_gui::tools::compare_counts::CompareCounts::relative_scale()
+                map_gui::tools::compare_counts::CompareCounts::relative_scale(cs)
                     .make_legend(ctx, vec!["less", "same", "more"]),
             ],
         })
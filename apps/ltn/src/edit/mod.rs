@@ -239,12 +239,14 @@ fn fix_oneway_and_add_filter(ctx: &mut EventCtx, app: &mut App, roads: &[(RoadID
         edits
             .commands
             .push(app.per_map.map.edit_road_cmd(*r, |new| {
+                let old_lanes = new.lanes_ltr.clone();
                 LaneSpec::toggle_road_direction(&mut new.lanes_ltr, driving_side);
                 // Maybe we just flipped a one-way forwards to a one-way backwards. So one more
                 // time to make it two-way
                 if LaneSpec::oneway_for_driving(&new.lanes_ltr) == Some(Direction::Back) {
                     LaneSpec::toggle_road_direction(&mut new.lanes_ltr, driving_side);
                 }
+                new.lanes_rearranged(&old_lanes);
             }));
     }
     ctx.loading_screen("apply edits", |_, timer| {
@@ -51,7 +51,9 @@ pub fn handle_world_outcome(
             let driving_side = app.per_map.map.get_config().driving_side;
             let mut edits = app.per_map.map.get_edits().clone();
             edits.commands.push(app.per_map.map.edit_road_cmd(r, |new| {
+                let old_lanes = new.lanes_ltr.clone();
                 LaneSpec::toggle_road_direction(&mut new.lanes_ltr, driving_side);
+                new.lanes_rearranged(&old_lanes);
             }));
 
             ctx.loading_screen("apply edits", |_, timer| {
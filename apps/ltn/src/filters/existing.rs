@@ -1,6 +1,5 @@
 use abstutil::{Tags, Timer};
 use geom::Distance;
-use map_gui::render::DrawMap;
 use map_model::{osm, Map, Road};
 use widgetry::EventCtx;
 
@@ -32,11 +31,17 @@ pub fn transform_existing_filters(ctx: &EventCtx, app: &mut App, timer: &mut Tim
     }
 
     if !edits.commands.is_empty() {
-        app.per_map.map.must_apply_edits(edits, timer);
-        app.per_map
-            .draw_map
-            .draw_all_unzoomed_roads_and_intersections =
-            DrawMap::regenerate_unzoomed_layer(ctx, &app.per_map.map, &app.cs, &app.opts, timer);
+        let effects = app.per_map.map.must_apply_edits(edits, timer);
+        // Every level of detail has to be updated
+        app.per_map.draw_map.update_unzoomed_layer(
+            ctx,
+            &app.per_map.map,
+            &app.cs,
+            &app.opts,
+            &effects.changed_roads,
+            &effects.changed_intersections,
+            timer,
+        );
         // No need to recreate_road or recreate_intersection. They mostly have zoomed-in effects we
         // ignore. The thickened polygon may change slightly due to width, but it's negligible.
 
@@ -213,7 +213,7 @@ impl State<App> for ShowResults {
         // buildings are a distraction.
         g.clear(app.cs.void_background);
         g.redraw(&app.per_map.draw_map.boundary_polygon);
-        g.redraw(&app.per_map.draw_map.draw_all_areas);
+        app.per_map.draw_map.draw_unzoomed_areas(g);
         app.per_map.impact.compare_counts.draw(g, app);
         app.per_map.draw_all_filters.draw(g);
 
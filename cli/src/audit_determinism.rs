@@ -0,0 +1,127 @@
+//! Simulating the same scenario with the same RNG seed should always produce exactly the same
+//! results. When it doesn't, this tool finds the first event where two runs disagree, along with
+//! the part of the simulation that produced it and the command being handled at the time. Usually
+//! the culprit is iterating over a `HashMap` or reading the wall clock somewhere near there.
+//!
+//! By default, two copies of the simulation run in lockstep. To catch differences between
+//! platforms or builds, instead `--record` a trace with one binary, then `--compare` against it
+//! with another.
+
+use anyhow::{bail, Result};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Time};
+use map_model::Map;
+use sim::{AlertHandler, Divergence, EventTrace, Sim, SimOptions, TraceEntry};
+use synthpop::Scenario;
+
+/// How far to advance each simulation before comparing traces
+const STEP: Duration = Duration::const_seconds(60.0);
+
+pub fn run(
+    scenario_path: String,
+    end_time: Option<String>,
+    rng_seed: u64,
+    record: Option<String>,
+    compare: Option<String>,
+) -> Result<()> {
+    let mut timer = Timer::new("audit determinism");
+    let scenario: Scenario = abstio::maybe_read_binary(scenario_path, &mut timer)?;
+    let map = Map::load_synchronously(scenario.map_name.path(), &mut timer);
+    let end_time = end_time.as_deref().map(Time::parse).transpose()?;
+
+    let mut sim = new_sim(&scenario, &map, rng_seed, &mut timer);
+    let end_time = end_time.unwrap_or_else(|| sim.get_end_of_day());
+
+    if let Some(path) = record {
+        sim.timed_step(&map, end_time - sim.time(), &mut None, &mut timer);
+        let trace = sim.get_event_trace().unwrap();
+        abstio::write_json(path.clone(), trace);
+        println!(
+            "Recorded {} events to {}",
+            prettyprint_usize(trace.entries.len()),
+            path
+        );
+        return Ok(());
+    }
+
+    let expected: Option<EventTrace> = compare
+        .map(|path| abstio::maybe_read_json(path, &mut timer))
+        .transpose()?;
+    let mut other_sim = if expected.is_none() {
+        Some(new_sim(&scenario, &map, rng_seed, &mut timer))
+    } else {
+        None
+    };
+
+    let mut checked = 0;
+    loop {
+        let done = sim.time() >= end_time;
+        if !done {
+            let dt = STEP.min(end_time - sim.time());
+            sim.timed_step(&map, dt, &mut None, &mut Timer::throwaway());
+            if let Some(ref mut other) = other_sim {
+                other.timed_step(&map, dt, &mut None, &mut Timer::throwaway());
+            }
+        }
+
+        let actual = sim.get_event_trace().unwrap();
+        let expected = match (&expected, &other_sim) {
+            (Some(trace), _) => trace,
+            (None, Some(other)) => other.get_event_trace().unwrap(),
+            (None, None) => unreachable!(),
+        };
+        // A recorded trace covers the whole run, so it'll only line up at the end
+        if let Some(divergence) = actual.first_divergence(expected, checked, done) {
+            print_divergence(&divergence);
+            bail!("The simulation isn't deterministic");
+        }
+        checked = actual.entries.len().min(expected.entries.len());
+
+        if done {
+            break;
+        }
+        println!(
+            "{}: {} events match so far",
+            sim.time(),
+            prettyprint_usize(checked)
+        );
+    }
+
+    println!(
+        "All {} events match through {}",
+        prettyprint_usize(checked),
+        end_time
+    );
+    Ok(())
+}
+
+fn new_sim(scenario: &Scenario, map: &Map, rng_seed: u64, timer: &mut Timer) -> Sim {
+    let mut opts = SimOptions::new("audit");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(map, opts);
+    sim.enable_event_trace();
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+    sim.instantiate(scenario, map, &mut rng, timer);
+    sim
+}
+
+fn print_divergence(divergence: &Divergence) {
+    println!("First divergence at event #{}", divergence.index);
+    print_entry("Expected", &divergence.expected);
+    print_entry("Actual", &divergence.actual);
+}
+
+fn print_entry(label: &str, entry: &Option<TraceEntry>) {
+    if let Some(entry) = entry {
+        println!(
+            "  {}: at {} (step {}), {} produced while handling {}:",
+            label, entry.time, entry.step, entry.source, entry.cause
+        );
+        println!("    {}", entry.event);
+    } else {
+        println!("  {}: the trace ended", label);
+    }
+}
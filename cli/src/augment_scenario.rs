@@ -1,20 +1,28 @@
+use anyhow::{bail, Result};
 use rand::prelude::SliceRandom;
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
+use serde::Deserialize;
 
 use abstutil::{prettyprint_usize, Timer};
 use geom::{Distance, Duration, FindClosest};
 use map_model::{AmenityType, BuildingID, Map};
+use synthpop::make::BorderDemand;
 use synthpop::{IndividTrip, Scenario, ScenarioModifier, TripEndpoint, TripMode, TripPurpose};
 
+/// Of the vehicles entering the map, how many leave by another border
+const PCT_BORDER_DEMAND_THROUGH: f64 = 0.3;
+
 pub fn run(
     input_scenario: String,
     should_add_return_trips: bool,
     should_add_lunch_trips: bool,
     modifiers: Vec<ScenarioModifier>,
     should_delete_cancelled_trips: bool,
+    // (Optional CSV overrides, scale)
+    border_demand: Option<(Option<String>, f64)>,
     rng_seed: u64,
-) {
+) -> Result<()> {
     let mut rng = XorShiftRng::seed_from_u64(rng_seed);
     let mut timer = Timer::new("augment scenario");
 
@@ -27,6 +35,9 @@ pub fn run(
     if should_add_lunch_trips {
         add_lunch_trips(&mut scenario, &map, &mut rng, &mut timer);
     }
+    if let Some((csv_path, scale)) = border_demand {
+        add_border_demand(&mut scenario, &map, csv_path, scale, &mut rng)?;
+    }
 
     for m in modifiers {
         scenario = m.apply(&map, scenario, &mut rng);
@@ -37,6 +48,7 @@ pub fn run(
     }
 
     scenario.save();
+    Ok(())
 }
 
 fn add_return_trips(scenario: &mut Scenario, rng: &mut XorShiftRng) {
@@ -157,3 +169,52 @@ fn delete_cancelled_trips(mut scenario: Scenario) -> Scenario {
     }
     scenario.remove_weird_schedules(false)
 }
+
+fn add_border_demand(
+    scenario: &mut Scenario,
+    map: &Map,
+    csv_path: Option<String>,
+    scale: f64,
+    rng: &mut XorShiftRng,
+) -> Result<()> {
+    let mut demand = BorderDemand::all_from_road_class(map, scale);
+    if let Some(path) = csv_path {
+        for rec in csv::Reader::from_reader(fs_err::File::open(path)?).deserialize() {
+            let rec: BorderDemandRecord = rec?;
+            if rec.hour >= 24 {
+                bail!("Hour {} is out of range", rec.hour);
+            }
+            let border = if let Some(b) = demand
+                .iter_mut()
+                .find(|d| map.get_i(d.i).orig_id.0 == rec.osm_node_id)
+            {
+                b
+            } else {
+                bail!("Node {} isn't a border of this map", rec.osm_node_id);
+            };
+            border.inflow_per_hour[rec.hour] = rec.inflow;
+            border.outflow_per_hour[rec.hour] = rec.outflow;
+        }
+    }
+
+    let people = synthpop::make::ScenarioGenerator::external_demand(
+        map,
+        &demand,
+        PCT_BORDER_DEMAND_THROUGH,
+        rng,
+    );
+    info!(
+        "Added {} people entering or leaving through borders",
+        prettyprint_usize(people.len())
+    );
+    scenario.people.extend(people);
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct BorderDemandRecord {
+    osm_node_id: i64,
+    hour: usize,
+    inflow: usize,
+    outflow: usize,
+}
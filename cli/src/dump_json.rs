@@ -0,0 +1,118 @@
+//! Prints any binary file A/B Street saves as JSON, for debugging and scripting. The kind of file
+//! is guessed from its path, so maps, raw maps, scenarios, savestates, and prebaked analytics all
+//! work. JSON files like map edits pass through unchanged, so they can be filtered the same way.
+//!
+//! A filter like `intersections/42/movements` prints just one piece of the file. Each part of the
+//! filter is either the name of a field or a position in a list, starting from 0. Note maps keyed
+//! by IDs are usually saved as a list of pairs, so `traffic_signals/0/1` is the first signal.
+
+use anyhow::{anyhow, bail, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use abstutil::Timer;
+
+type Loader = fn(&str) -> Result<Value>;
+
+/// Which kind of file to expect, based on a directory appearing in the path
+fn formats() -> Vec<(&'static str, &'static str, Loader)> {
+    vec![
+        ("/maps/", "map", read_as::<map_model::Map> as Loader),
+        (
+            "/raw_maps/",
+            "raw map",
+            read_as::<raw_map::RawMap> as Loader,
+        ),
+        (
+            "/scenarios/",
+            "scenario",
+            read_as::<synthpop::Scenario> as Loader,
+        ),
+        ("/saves/", "savestate", read_as::<sim::Sim> as Loader),
+        (
+            "/prebaked_results/",
+            "prebaked analytics",
+            read_as::<sim::Analytics> as Loader,
+        ),
+    ]
+}
+
+pub fn run(path: String, filter: Option<String>) -> Result<()> {
+    let value = load(&path)?;
+    let value = if let Some(filter) = filter {
+        select(value, &filter)?
+    } else {
+        value
+    };
+    println!("{}", abstutil::to_json(&value));
+    Ok(())
+}
+
+fn load(path: &str) -> Result<Value> {
+    if path.ends_with(".json") {
+        return abstio::maybe_read_json(path.to_string(), &mut Timer::throwaway());
+    }
+
+    let formats = formats();
+    if let Some((_, name, loader)) = formats.iter().find(|(dir, _, _)| path.contains(*dir)) {
+        return loader(path)
+            .map_err(|err| anyhow!("{} doesn't look like a {}: {}", path, name, err));
+    }
+    // Files copied somewhere else lose the hint. Try every format; bincode will usually fail
+    // quickly on the wrong one.
+    for (_, _, loader) in &formats {
+        if let Ok(value) = loader(path) {
+            return Ok(value);
+        }
+    }
+    bail!(
+        "Don't know how to dump JSON for {}. Only maps, raw maps, scenarios, savestates, prebaked \
+         analytics, and JSON files are supported.",
+        path
+    )
+}
+
+fn read_as<T: DeserializeOwned + Serialize>(path: &str) -> Result<Value> {
+    let obj: T = abstio::maybe_read_binary(path.to_string(), &mut Timer::throwaway())?;
+    Ok(serde_json::to_value(&obj)?)
+}
+
+fn select(mut value: Value, filter: &str) -> Result<Value> {
+    let mut so_far = Vec::new();
+    for key in filter.split('/').filter(|key| !key.is_empty()) {
+        value = match value {
+            Value::Object(mut fields) => match fields.remove(key) {
+                Some(x) => x,
+                None => bail!(
+                    "No field {} in /{}. The fields are: {}",
+                    key,
+                    so_far.join("/"),
+                    fields.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+            },
+            Value::Array(mut list) => {
+                let idx: usize = key.parse().map_err(|_| {
+                    anyhow!("/{} is a list; {} isn't a position", so_far.join("/"), key)
+                })?;
+                if idx >= list.len() {
+                    bail!(
+                        "/{} only has {} items; can't get {}",
+                        so_far.join("/"),
+                        list.len(),
+                        idx
+                    );
+                }
+                list.swap_remove(idx)
+            }
+            other => bail!(
+                "/{} is just {}; can't look inside it for {}",
+                so_far.join("/"),
+                other,
+                key
+            ),
+        };
+        so_far.push(key);
+    }
+    Ok(value)
+}
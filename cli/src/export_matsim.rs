@@ -0,0 +1,211 @@
+//! Exports a scenario to MATSim's network and plans XML formats, so results can be compared
+//! against or continued in MATSim.
+//!
+//! Coordinates are in meters, in the same local coordinate system as the A/B Street map, but with
+//! the Y axis flipped. MATSim will need a coordinate system configured that treats these as
+//! Cartesian.
+
+use std::io::Write;
+
+use anyhow::Result;
+use fs_err::File;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Pt2D, Time};
+use map_model::{Direction, Map, Road};
+use synthpop::{Scenario, TripEndpoint, TripMode};
+
+/// MATSim needs a capacity per link. Guess a typical flow per lane, in vehicles per hour.
+const CAPACITY_PER_LANE: usize = 1800;
+
+pub fn run(scenario_path: String, output_dir: String) -> Result<()> {
+    let mut timer = Timer::new("export to MATSim");
+    let scenario: Scenario = abstio::must_read_object(scenario_path, &mut timer);
+    let map = Map::load_synchronously(scenario.map_name.path(), &mut timer);
+    fs_err::create_dir_all(&output_dir)?;
+
+    let network_path = format!("{}/network.xml", output_dir);
+    write_network(&map, &network_path)?;
+    println!("Wrote {}", network_path);
+
+    let plans_path = format!("{}/plans.xml", output_dir);
+    let num_people = write_plans(&map, &scenario, &plans_path)?;
+    println!(
+        "Wrote {} with {} people",
+        plans_path,
+        prettyprint_usize(num_people)
+    );
+    Ok(())
+}
+
+fn write_network(map: &Map, path: &str) -> Result<()> {
+    let mut f = File::create(path)?;
+    writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        f,
+        r#"<!DOCTYPE network SYSTEM "http://www.matsim.org/files/dtd/network_v2.dtd">"#
+    )?;
+    writeln!(f, r#"<network name="{}">"#, map.get_name().as_filename())?;
+
+    writeln!(f, "  <nodes>")?;
+    for i in map.all_intersections() {
+        writeln!(
+            f,
+            r#"    <node id="{}" x="{:.2}" y="{:.2}"/>"#,
+            i.id.0,
+            i.polygon.center().x(),
+            flip_y(i.polygon.center())
+        )?;
+    }
+    writeln!(f, "  </nodes>")?;
+
+    writeln!(
+        f,
+        r#"  <links capperiod="01:00:00" effectivecellsize="7.5" effectivelanewidth="3.75">"#
+    )?;
+    for r in map.all_roads() {
+        for dir in [Direction::Fwd, Direction::Back] {
+            let (lanes, modes) = match link_lanes(r, dir) {
+                Some(x) => x,
+                None => continue,
+            };
+            let (from, to) = match dir {
+                Direction::Fwd => (r.src_i, r.dst_i),
+                Direction::Back => (r.dst_i, r.src_i),
+            };
+            writeln!(
+                f,
+                r#"    <link id="{}_{}" from="{}" to="{}" length="{:.2}" freespeed="{:.2}" capacity="{}" permlanes="{}" oneway="1" modes="{}"/>"#,
+                r.id.0,
+                match dir {
+                    Direction::Fwd => "fwd",
+                    Direction::Back => "back",
+                },
+                from.0,
+                to.0,
+                r.length().inner_meters(),
+                r.speed_limit.inner_meters_per_second(),
+                lanes * CAPACITY_PER_LANE,
+                lanes,
+                modes
+            )?;
+        }
+    }
+    writeln!(f, "  </links>")?;
+    writeln!(f, "</network>")?;
+    Ok(())
+}
+
+/// How many lanes and which MATSim modes can travel along a road in one direction. Walking isn't
+/// included, since MATSim usually teleports pedestrians.
+fn link_lanes(r: &Road, dir: Direction) -> Option<(usize, &'static str)> {
+    let mut driving = 0;
+    let mut biking = 0;
+    for lane in &r.lanes {
+        if lane.dir != dir {
+            continue;
+        }
+        if lane.is_driving() || lane.is_bus() {
+            driving += 1;
+        } else if lane.is_biking() {
+            biking += 1;
+        }
+    }
+    if driving > 0 {
+        Some((driving, "car,bike"))
+    } else if biking > 0 {
+        Some((biking, "bike"))
+    } else {
+        None
+    }
+}
+
+/// Returns the number of people written
+fn write_plans(map: &Map, scenario: &Scenario, path: &str) -> Result<usize> {
+    let mut f = File::create(path)?;
+    writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        f,
+        r#"<!DOCTYPE population SYSTEM "http://www.matsim.org/files/dtd/population_v6.dtd">"#
+    )?;
+    writeln!(f, "<population>")?;
+
+    let mut num_people = 0;
+    for (idx, person) in scenario.people.iter().enumerate() {
+        let trips: Vec<_> = person.trips.iter().filter(|t| !t.cancelled).collect();
+        if trips.is_empty() {
+            continue;
+        }
+        num_people += 1;
+
+        writeln!(f, r#"  <person id="{}">"#, idx)?;
+        writeln!(f, r#"    <plan selected="yes">"#)?;
+        let first_type = match trips[0].origin {
+            TripEndpoint::Border(_) => "outside",
+            _ => "home",
+        };
+        write_activity(
+            &mut f,
+            first_type,
+            trips[0].origin.pt(map),
+            Some(trips[0].depart),
+        )?;
+        for (trip_idx, trip) in trips.iter().enumerate() {
+            writeln!(
+                f,
+                r#"      <leg mode="{}" dep_time="{}"/>"#,
+                match trip.mode {
+                    TripMode::Walk => "walk",
+                    TripMode::Bike => "bike",
+                    TripMode::Transit => "pt",
+                    TripMode::Drive => "car",
+                },
+                matsim_time(trip.depart)
+            )?;
+            let act_type = match trip.destination {
+                TripEndpoint::Border(_) => "outside".to_string(),
+                _ => trip.purpose.to_string().replace(' ', "_"),
+            };
+            // The last activity lasts until the end of the simulation
+            let end_time = trips.get(trip_idx + 1).map(|next| next.depart);
+            write_activity(&mut f, &act_type, trip.destination.pt(map), end_time)?;
+        }
+        writeln!(f, "    </plan>")?;
+        writeln!(f, "  </person>")?;
+    }
+
+    writeln!(f, "</population>")?;
+    Ok(num_people)
+}
+
+fn write_activity(f: &mut File, act_type: &str, pt: Pt2D, end_time: Option<Time>) -> Result<()> {
+    write!(
+        f,
+        r#"      <act type="{}" x="{:.2}" y="{:.2}""#,
+        act_type,
+        pt.x(),
+        flip_y(pt)
+    )?;
+    if let Some(time) = end_time {
+        write!(f, r#" end_time="{}""#, matsim_time(time))?;
+    }
+    writeln!(f, "/>")?;
+    Ok(())
+}
+
+/// A/B Street's Y axis points down, like screen coordinates. MATSim's points up, like a normal
+/// projection.
+fn flip_y(pt: Pt2D) -> f64 {
+    -pt.y()
+}
+
+/// MATSim uses HH:MM:SS, with hours allowed past 24
+fn matsim_time(time: Time) -> String {
+    let secs = time.inner_seconds() as usize;
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,208 @@
+//! Imports travel demand from a CSV trip table, so the output of any travel demand model can be
+//! simulated. Each row is one trip, with these columns:
+//!
+//! - `person`: optional. Rows with the same value become one person's schedule, ordered by
+//!   departure. A blank value makes a person with just that one trip.
+//! - `departure`: the time the trip starts, like `07:30` or `07:30:05`
+//! - `mode`: `walk`, `bike`, `transit`, or `drive`
+//! - `purpose`: optional. One of `home`, `work`, `school`, `escort`, `personal_business`,
+//!   `shopping`, `meal`, `social`, `recreation`, `medical`, or `park_and_ride_transfer`. Defaults
+//!   to `personal_business`.
+//! - `origin_lon`, `origin_lat`: where the trip starts. These're snapped to the nearest
+//!   building, or the nearest border if the point is outside the map.
+//! - `origin_osm_building`: optional. Instead of longitude and latitude, the OSM ID of a building,
+//!   like `way/123` or `relation/456`.
+//! - `destination_lon`, `destination_lat`, `destination_osm_building`: where the trip ends, in the
+//!   same way as the origin
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{LonLat, Time};
+use map_model::{osm, Map};
+use synthpop::{
+    ExternalPerson, ExternalTrip, ExternalTripEndpoint, Scenario, TripEndpoint, TripMode,
+    TripPurpose,
+};
+
+pub fn run(input: String, map: String, scenario_name: String, skip_problems: bool) -> Result<()> {
+    let mut timer = Timer::new("import trip CSV");
+    let map = Map::load_synchronously(map, &mut timer);
+    timer.start("parse CSV");
+    let people = parse_people(&map, input)?;
+    timer.stop("parse CSV");
+
+    let mut s = Scenario::empty(&map, &scenario_name);
+    // Include all buses/trains
+    s.only_seed_buses = None;
+    let orig_num = people.len();
+    s.people = ExternalPerson::import(&map, people, skip_problems)?;
+    // Always clean up people with no-op trips (going between the same buildings)
+    s = s.remove_weird_schedules(true);
+    println!(
+        "Imported {}/{} people",
+        prettyprint_usize(s.people.len()),
+        prettyprint_usize(orig_num)
+    );
+    s.save();
+    Ok(())
+}
+
+fn parse_people(map: &Map, path: String) -> Result<Vec<ExternalPerson>> {
+    let mut people = Vec::new();
+    // Keyed by the person column, but remembering the order people first appear in
+    let mut person_idx: BTreeMap<String, usize> = BTreeMap::new();
+    for (row, rec) in csv::Reader::from_reader(fs_err::File::open(path)?)
+        .deserialize()
+        .enumerate()
+    {
+        let rec: Record = rec?;
+        // Row 1 is the header
+        let row = row + 2;
+        let trip = ExternalTrip {
+            departure: Time::parse(&rec.departure)
+                .map_err(|err| anyhow!("row {}: bad departure: {}", row, err))?,
+            origin: parse_endpoint(map, rec.origin_lon, rec.origin_lat, rec.origin_osm_building)
+                .map_err(|err| anyhow!("row {}: bad origin: {}", row, err))?,
+            destination: parse_endpoint(
+                map,
+                rec.destination_lon,
+                rec.destination_lat,
+                rec.destination_osm_building,
+            )
+            .map_err(|err| anyhow!("row {}: bad destination: {}", row, err))?,
+            mode: parse_mode(&rec.mode).map_err(|err| anyhow!("row {}: {}", row, err))?,
+            purpose: match rec.purpose {
+                Some(x) => parse_purpose(&x).map_err(|err| anyhow!("row {}: {}", row, err))?,
+                None => TripPurpose::PersonalBusiness,
+            },
+        };
+
+        match rec.person {
+            Some(id) => {
+                let idx = *person_idx.entry(id).or_insert_with(|| {
+                    people.push(ExternalPerson { trips: Vec::new() });
+                    people.len() - 1
+                });
+                people[idx].trips.push(trip);
+            }
+            None => {
+                people.push(ExternalPerson { trips: vec![trip] });
+            }
+        }
+    }
+    for person in &mut people {
+        person.trips.sort_by_key(|t| t.departure);
+    }
+    Ok(people)
+}
+
+fn parse_endpoint(
+    map: &Map,
+    lon: Option<f64>,
+    lat: Option<f64>,
+    osm_building: Option<String>,
+) -> Result<ExternalTripEndpoint> {
+    if let Some(id) = osm_building {
+        let osm_id = parse_osm_id(&id)?;
+        let b = map
+            .find_b_by_osm_id(osm_id)
+            .ok_or_else(|| anyhow!("no building {} in the map", id))?;
+        return Ok(ExternalTripEndpoint::TripEndpoint(TripEndpoint::Building(
+            b,
+        )));
+    }
+    match (lon, lat) {
+        (Some(lon), Some(lat)) => Ok(ExternalTripEndpoint::Position(LonLat::new(lon, lat))),
+        _ => bail!("needs either a longitude and latitude or an OSM building ID"),
+    }
+}
+
+fn parse_osm_id(input: &str) -> Result<osm::OsmID> {
+    let (kind, id) = input
+        .split_once('/')
+        .ok_or_else(|| anyhow!("OSM ID {} should look like way/123", input))?;
+    let id = id.parse::<i64>()?;
+    match kind {
+        "way" => Ok(osm::OsmID::Way(osm::WayID(id))),
+        "relation" => Ok(osm::OsmID::Relation(osm::RelationID(id))),
+        "node" => Ok(osm::OsmID::Node(osm::NodeID(id))),
+        _ => bail!("OSM ID {} should start with way, relation, or node", input),
+    }
+}
+
+fn parse_mode(input: &str) -> Result<TripMode> {
+    match input.to_lowercase().as_str() {
+        "walk" => Ok(TripMode::Walk),
+        "bike" => Ok(TripMode::Bike),
+        "transit" => Ok(TripMode::Transit),
+        "drive" => Ok(TripMode::Drive),
+        _ => bail!("unknown mode {}; use walk, bike, transit, or drive", input),
+    }
+}
+
+fn parse_purpose(input: &str) -> Result<TripPurpose> {
+    // Spaces and underscores are interchangeable
+    match input.to_lowercase().replace(' ', "_").as_str() {
+        "home" => Ok(TripPurpose::Home),
+        "work" => Ok(TripPurpose::Work),
+        "school" => Ok(TripPurpose::School),
+        "escort" => Ok(TripPurpose::Escort),
+        "personal_business" => Ok(TripPurpose::PersonalBusiness),
+        "shopping" => Ok(TripPurpose::Shopping),
+        "meal" => Ok(TripPurpose::Meal),
+        "social" => Ok(TripPurpose::Social),
+        "recreation" => Ok(TripPurpose::Recreation),
+        "medical" => Ok(TripPurpose::Medical),
+        "park_and_ride_transfer" => Ok(TripPurpose::ParkAndRideTransfer),
+        _ => bail!("unknown purpose {}", input),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    #[serde(default)]
+    person: Option<String>,
+    departure: String,
+    mode: String,
+    #[serde(default)]
+    purpose: Option<String>,
+    #[serde(default)]
+    origin_lon: Option<f64>,
+    #[serde(default)]
+    origin_lat: Option<f64>,
+    #[serde(default)]
+    origin_osm_building: Option<String>,
+    #[serde(default)]
+    destination_lon: Option<f64>,
+    #[serde(default)]
+    destination_lat: Option<f64>,
+    #[serde(default)]
+    destination_osm_building: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_purpose() {
+        assert!(matches!(parse_purpose("work"), Ok(TripPurpose::Work)));
+        assert!(matches!(
+            parse_purpose("Shopping"),
+            Ok(TripPurpose::Shopping)
+        ));
+        assert!(matches!(
+            parse_purpose("personal_business"),
+            Ok(TripPurpose::PersonalBusiness)
+        ));
+        assert!(matches!(
+            parse_purpose("Park and ride transfer"),
+            Ok(TripPurpose::ParkAndRideTransfer)
+        ));
+        assert!(parse_purpose("commute").is_err());
+    }
+}
@@ -4,17 +4,25 @@
 #[macro_use]
 extern crate log;
 
+mod audit_determinism;
 mod augment_scenario;
 mod clip_osm;
+mod dump_json;
+mod export_matsim;
 mod generate_houses;
 mod import_grid2demand;
 mod import_scenario;
+mod import_trip_csv;
+mod map_query;
 mod one_step_import;
+mod run_experiments;
+mod signal_timing;
+mod synthesize_population;
 
 use std::io::Write;
 
-use abstio::CityName;
-use anyhow::Result;
+use abstio::{CityName, MapName};
+use anyhow::{anyhow, bail, Result};
 use fs_err::File;
 use importer::Job;
 use structopt::StructOpt;
@@ -24,10 +32,21 @@ use abstutil::Timer;
 #[derive(StructOpt)]
 #[structopt(name = "abcli", about = "The A/B Street multi-tool")]
 enum Command {
-    /// Print a binary map or scenario file as JSON
+    /// Print a binary map, raw map, scenario, savestate, or prebaked analytics file as JSON. JSON
+    /// files like map edits can be filtered too.
     DumpJSON {
         #[structopt()]
         path: String,
+        /// Only print part of the file, like `intersections/42`. Each part is a field name or a
+        /// position in a list. See `cli/src/dump_json.rs` for details.
+        #[structopt(long)]
+        filter: Option<String>,
+    },
+    /// Print how a simulation run was set up -- the map, edits, scenario, options, RNG seed, and
+    /// code version. Works on savestates, prebaked results, and CSV files exported from the game.
+    RunMetadata {
+        #[structopt()]
+        path: String,
     },
     /// Generates a random scenario using the proletariat robot travel demand model
     RandomScenario {
@@ -41,6 +60,23 @@ enum Command {
         #[structopt(long)]
         scenario_name: String,
     },
+    /// Generates a scenario for a map without any travel survey, given only how many people live
+    /// and work in different zones
+    SynthesizePopulation {
+        /// The path to a map to generate a scenario for
+        #[structopt(long)]
+        map: String,
+        /// The path to a GeoJSON file with zone polygons. Each needs `population` and `employment`
+        /// properties, and optionally a `name`.
+        #[structopt(long)]
+        zones: String,
+        /// A seed for generating random numbers
+        #[structopt(long, default_value = "42")]
+        rng_seed: u64,
+        /// The name of the scenario to generate
+        #[structopt(long)]
+        scenario_name: String,
+    },
     /// Modifies the schedule of every person in an existing scenario.
     AugmentScenario {
         /// The path to a scenario to augment. This will be modified in-place.
@@ -62,6 +98,18 @@ enum Command {
         /// Delete cancelled trips, and delete people with no remaining trips.
         #[structopt(long)]
         delete_cancelled_trips: bool,
+        /// Add vehicles entering, leaving, and passing through the map's borders. Hourly volumes
+        /// are guessed from the class of each border's road.
+        #[structopt(long)]
+        add_border_demand: bool,
+        /// A CSV file with `osm_node_id,hour,inflow,outflow` columns, overriding the guessed
+        /// volumes for some borders. Only used with --add-border-demand.
+        #[structopt(long)]
+        border_demand_csv: Option<String>,
+        /// Scales the guessed border volumes, since small maps can't absorb a full day of arterial
+        /// and highway traffic.
+        #[structopt(long, default_value = "1.0")]
+        border_demand_scale: f64,
         /// A seed for generating random numbers
         #[structopt(long, default_value = "42")]
         rng_seed: u64,
@@ -79,6 +127,15 @@ enum Command {
         #[structopt(long)]
         out_path: String,
     },
+    /// Exports a scenario and its map to MATSim's network.xml and plans.xml formats.
+    ExportMATSim {
+        /// The path to a scenario file
+        #[structopt(long)]
+        scenario: String,
+        /// The directory to write network.xml and plans.xml
+        #[structopt(long)]
+        output_dir: String,
+    },
     /// Import a scenario from https://github.com/asu-trans-ai-lab/grid2demand.
     ImportGrid2Demand {
         /// The path to a grid2demand CSV file
@@ -102,6 +159,23 @@ enum Command {
         #[structopt(long)]
         skip_problems: bool,
     },
+    /// Import a scenario from a CSV trip table, with one row per trip. See
+    /// `cli/src/import_trip_csv.rs` for a description of the columns.
+    ImportTripCSV {
+        /// The path to a CSV file
+        #[structopt(long)]
+        input: String,
+        /// The path to a map matching the trips
+        #[structopt(long)]
+        map: String,
+        /// The name of the scenario to create
+        #[structopt(long)]
+        scenario_name: String,
+        /// Problems occur when a position is within the map boundary, but not close enough to
+        /// buildings. Skip people with problematic positions if true, abort otherwise.
+        #[structopt(long)]
+        skip_problems: bool,
+    },
     /// Transform a JSON map that's been manually edited into the binary format suitable for
     /// simulation.
     ImportJSONMap {
@@ -112,6 +186,38 @@ enum Command {
         #[structopt(long)]
         output: String,
     },
+    /// Writes the timing of every traffic signal in a map as a JSON list, in the format described
+    /// by the `traffic_signal_data` crate.
+    ExportSignalTiming {
+        /// The path to a map
+        #[structopt(long)]
+        map: String,
+        /// The path to the JSON file to write
+        #[structopt(long)]
+        output: String,
+    },
+    /// Imports traffic signal timing from a JSON list, like one written by export-signal-timing,
+    /// saving the result as map edits. Signals are matched to intersections by OSM node ID.
+    ImportSignalTiming {
+        /// The path to a map
+        #[structopt(long)]
+        map: String,
+        /// The path to a JSON file with a list of traffic signals
+        #[structopt(long)]
+        input: String,
+        /// The name of the edits to save
+        #[structopt(long, default_value = "imported signal timing")]
+        edits_name: String,
+    },
+    /// Answers questions about a map without the GUI: listing roads by tag, checking buildings are
+    /// reachable, finding disconnected sidewalks, and describing intersections.
+    MapQuery {
+        /// The path to a map
+        #[structopt(long)]
+        map: String,
+        #[structopt(subcommand)]
+        query: map_query::Query,
+    },
     /// Removes nonessential parts of a Map, for the bike network tool.
     MinifyMap {
         /// The path to a map to shrink. The map is modified in-place.
@@ -147,7 +253,11 @@ enum Command {
     OneStepImport {
         /// The path to a GeoJSON file with a boundary
         #[structopt(long)]
-        geojson_path: String,
+        geojson_path: Option<String>,
+        /// Instead of a GeoJSON file, import a bounding box, given as
+        /// "min_lon,min_lat,max_lon,max_lat"
+        #[structopt(long)]
+        bbox: Option<String>,
         /// What to name the new imported map. The country will always be "zz" (a fake country
         /// code), with the city as "oneshot." This name shouldn't contain spaces or be empty.
         #[structopt(long)]
@@ -174,6 +284,20 @@ enum Command {
         /// Downgrade crosswalks not matching a `highway=crossing` OSM node into unmarked crossings.
         #[structopt(long)]
         filter_crosswalks: bool,
+        /// Import alleys and driveways as roads, only usable at the start or end of a trip.
+        #[structopt(long)]
+        include_driveways: bool,
+        /// Turn cycleways mapped separately next to a road into protected bike lanes on that road.
+        #[structopt(long)]
+        snap_cycleways: bool,
+        /// A GeoTIFF or SRTM .hgt file with elevation data, using longitude/latitude coordinates.
+        /// Repeat to use several tiles.
+        #[structopt(long)]
+        dem: Vec<String>,
+        /// A JSON file with regional defaults for lanes, speed limits, driving side, and
+        /// sidewalks, used where OSM doesn't tag them. See `importer/profiles` for examples.
+        #[structopt(long)]
+        profile: Option<String>,
         /// Generate a simple travel demand model based on 2011 UK commuting data. This will only
         /// work if the boundary is in the UK.
         #[structopt(long)]
@@ -181,6 +305,36 @@ enum Command {
         #[structopt(flatten)]
         opts: map_model::RawToMapOptions,
     },
+    /// Combines two adjacent maps into one, reconnecting roads cut by the shared edge. Takes the
+    /// RawMaps produced while importing, like `data/input/zz/oneshot/raw_maps/foo.bin`.
+    StitchMaps {
+        #[structopt()]
+        raw_map1: String,
+        #[structopt()]
+        raw_map2: String,
+        /// What to name the combined map. The country will always be "zz" (a fake country code),
+        /// with the city as "oneshot." This name shouldn't contain spaces or be empty.
+        #[structopt(long)]
+        map_name: String,
+        #[structopt(flatten)]
+        opts: map_model::RawToMapOptions,
+    },
+    /// Cuts a smaller map out of a bigger one, keeping only what's inside a boundary. This is much
+    /// faster than importing the smaller area from scratch.
+    ExtractRegion {
+        /// The path to a raw map of the bigger area
+        #[structopt()]
+        raw_map: String,
+        /// The path to a GeoJSON file with one polygon, the boundary of the smaller map
+        #[structopt(long)]
+        clip_path: String,
+        /// What to name the new map. The country will always be "zz" (a fake country code), with
+        /// the city as "oneshot." This name shouldn't contain spaces or be empty.
+        #[structopt(long)]
+        map_name: String,
+        #[structopt(flatten)]
+        opts: map_model::RawToMapOptions,
+    },
     /// Regenerate all maps and scenarios from scratch.
     RegenerateEverything {
         /// If this command is being run in the cloud, parallelize the jobs by specifying which
@@ -199,6 +353,35 @@ enum Command {
         #[structopt(flatten)]
         job: Job,
     },
+    /// Simulates every combination of some map edits and scenarios in parallel, writing a CSV
+    /// with trip metrics for each. See `cli/src/run_experiments.rs` for the config format.
+    RunExperiments {
+        /// The path to a JSON config listing edits and scenarios
+        #[structopt(long)]
+        config: String,
+        /// The path to the CSV file to write
+        #[structopt(long)]
+        output: String,
+    },
+    /// Simulates a scenario twice with the same RNG seed and reports the first event where the
+    /// runs disagree, to track down sources of nondeterminism.
+    AuditDeterminism {
+        /// The path to a scenario file
+        #[structopt(long)]
+        scenario: String,
+        /// When to stop simulating, like "12:00:00". Defaults to the scenario's end of day.
+        #[structopt(long)]
+        end_time: Option<String>,
+        /// A seed for generating random numbers
+        #[structopt(long, default_value = "42")]
+        rng_seed: u64,
+        /// Instead of comparing two runs, write every event from one run to this JSON file
+        #[structopt(long)]
+        record: Option<String>,
+        /// Instead of comparing two runs, compare one run against a trace written by --record
+        #[structopt(long)]
+        compare: Option<String>,
+    },
     /// Simulate a full day of a scenario, and write the "prebaked results," so the UI can later be
     /// used for A/B testing.
     #[structopt(name = "prebake-scenario")]
@@ -223,7 +406,7 @@ async fn main() -> Result<()> {
     // All but a few commands want logging
     if !matches!(
         cmd,
-        Command::DumpJSON { .. } | Command::PickGeofabrik { .. },
+        Command::DumpJSON { .. } | Command::RunMetadata { .. } | Command::PickGeofabrik { .. },
     ) {
         abstutil::logger::setup();
     }
@@ -231,18 +414,28 @@ async fn main() -> Result<()> {
     // Short implementations can stay in this file, but please split larger subcommands to their
     // own module.
     match Command::from_args() {
-        Command::DumpJSON { path } => dump_json(path),
+        Command::DumpJSON { path, filter } => dump_json::run(path, filter)?,
+        Command::RunMetadata { path } => print_run_metadata(path)?,
         Command::RandomScenario {
             rng_seed,
             map,
             scenario_name,
         } => random_scenario(rng_seed, map, scenario_name),
+        Command::SynthesizePopulation {
+            map,
+            zones,
+            rng_seed,
+            scenario_name,
+        } => synthesize_population::run(map, zones, rng_seed, scenario_name)?,
         Command::AugmentScenario {
             input_scenario,
             add_return_trips,
             add_lunch_trips,
             scenario_modifiers,
             delete_cancelled_trips,
+            add_border_demand,
+            border_demand_csv,
+            border_demand_scale,
             rng_seed,
         } => augment_scenario::run(
             input_scenario,
@@ -250,20 +443,38 @@ async fn main() -> Result<()> {
             add_lunch_trips,
             scenario_modifiers,
             delete_cancelled_trips,
+            add_border_demand.then_some((border_demand_csv, border_demand_scale)),
             rng_seed,
-        ),
+        )?,
         Command::ClipOSM {
             pbf_path,
             clip_path,
             out_path,
         } => clip_osm::run(pbf_path, clip_path, out_path)?,
+        Command::ExportMATSim {
+            scenario,
+            output_dir,
+        } => export_matsim::run(scenario, output_dir)?,
         Command::ImportGrid2Demand { input, map } => import_grid2demand::run(input, map)?,
         Command::ImportScenario {
             input,
             map,
             skip_problems,
         } => import_scenario::run(input, map, skip_problems),
+        Command::ImportTripCSV {
+            input,
+            map,
+            scenario_name,
+            skip_problems,
+        } => import_trip_csv::run(input, map, scenario_name, skip_problems)?,
         Command::ImportJSONMap { input, output } => import_json_map(input, output),
+        Command::ExportSignalTiming { map, output } => signal_timing::export(map, output)?,
+        Command::ImportSignalTiming {
+            map,
+            input,
+            edits_name,
+        } => signal_timing::import(map, input, edits_name)?,
+        Command::MapQuery { map, query } => map_query::run(map, query)?,
         Command::MinifyMap { map } => minify_map(map),
         Command::GenerateHouses {
             map,
@@ -276,6 +487,7 @@ async fn main() -> Result<()> {
         }
         Command::OneStepImport {
             geojson_path,
+            bbox,
             map_name,
             use_geofabrik,
             filter_crosswalks,
@@ -283,6 +495,7 @@ async fn main() -> Result<()> {
         } => {
             one_step_import::run(
                 geojson_path,
+                bbox,
                 map_name,
                 use_geofabrik,
                 filter_crosswalks,
@@ -294,59 +507,99 @@ async fn main() -> Result<()> {
             osm_input,
             clip_path,
             filter_crosswalks,
+            include_driveways,
+            snap_cycleways,
+            dem,
+            profile,
             create_uk_travel_demand_model,
             opts,
         } => {
+            let mut options = convert_osm::Options::default();
+            options.filter_crosswalks = filter_crosswalks;
+            options.include_driveways = include_driveways;
+            options.snap_cycleways = snap_cycleways;
+            if !dem.is_empty() {
+                options.elevation = convert_osm::Elevation::Dem(dem);
+            }
+            if let Some(path) = profile {
+                options.profile = abstio::maybe_read_json(path, &mut Timer::throwaway())?;
+            }
             importer::oneshot(
                 osm_input,
                 clip_path,
-                filter_crosswalks,
+                options,
                 create_uk_travel_demand_model,
                 opts,
             )
             .await
         }
+        Command::StitchMaps {
+            raw_map1,
+            raw_map2,
+            map_name,
+            opts,
+        } => importer::stitch_maps(
+            raw_map1,
+            raw_map2,
+            MapName::new("zz", "oneshot", &map_name),
+            opts,
+        )?,
+        Command::ExtractRegion {
+            raw_map,
+            clip_path,
+            map_name,
+            opts,
+        } => importer::extract_region(
+            raw_map,
+            clip_path,
+            MapName::new("zz", "oneshot", &map_name),
+            opts,
+        )?,
         Command::RegenerateEverything {
             shard_num,
             num_shards,
         } => importer::regenerate_everything(shard_num, num_shards).await,
         Command::RegenerateEverythingExternally => regenerate_everything_externally()?,
         Command::Import { job } => job.run(&mut Timer::new("import one city")).await,
+        Command::RunExperiments { config, output } => run_experiments::run(config, output)?,
+        Command::AuditDeterminism {
+            scenario,
+            end_time,
+            rng_seed,
+            record,
+            compare,
+        } => audit_determinism::run(scenario, end_time, rng_seed, record, compare)?,
         Command::PrebakeScenario { scenario_path } => prebake_scenario(scenario_path),
     }
     Ok(())
 }
 
-fn dump_json(path: String) {
-    // Just try to deserialize as different formats
-    if path.contains("/maps/") {
-        if let Ok(map) =
-            abstio::maybe_read_binary::<map_model::Map>(path.clone(), &mut Timer::throwaway())
-        {
-            println!("{}", abstutil::to_json(&map));
-            return;
+fn print_run_metadata(path: String) -> Result<()> {
+    let mut timer = Timer::throwaway();
+    let metadata = if path.ends_with(".csv") {
+        // Exported CSV files start with the description as comments
+        let lines: Vec<String> = fs_err::read_to_string(&path)?
+            .lines()
+            .map_while(|line| line.strip_prefix("# ").map(|x| x.to_string()))
+            .collect();
+        if lines.is_empty() {
+            bail!("{} doesn't start with any run metadata", path);
         }
-    }
-    if path.contains("/raw_maps/") {
-        if let Ok(map) =
-            abstio::maybe_read_binary::<raw_map::RawMap>(path.clone(), &mut Timer::throwaway())
-        {
-            println!("{}", abstutil::to_json(&map));
-            return;
-        }
-    }
-    if path.contains("/scenarios/") {
-        if let Ok(scenario) =
-            abstio::maybe_read_binary::<synthpop::Scenario>(path.clone(), &mut Timer::throwaway())
-        {
-            println!("{}", abstutil::to_json(&scenario));
-            return;
+        for line in lines {
+            println!("{}", line);
         }
+        return Ok(());
+    } else if path.contains("/prebaked_results/") {
+        abstio::maybe_read_binary::<sim::Analytics>(path.clone(), &mut timer)?
+            .run_metadata
+            .ok_or_else(|| anyhow!("{} was saved without run metadata", path))?
+    } else {
+        abstio::maybe_read_binary::<sim::Sim>(path, &mut timer)?.run_metadata()
+    };
+    for line in metadata.describe() {
+        println!("{}", line);
     }
-    panic!(
-        "Don't know how to dump JSON for {}. Only maps, raw maps, and scenarios are supported.",
-        path
-    );
+    Ok(())
 }
 
 fn random_scenario(rng_seed: u64, map: String, scenario_name: String) {
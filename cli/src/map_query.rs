@@ -0,0 +1,227 @@
+//! Answers questions about a map without opening the GUI, mostly for validating imports in
+//! scripts. Output is plain text, one result per line where possible.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use anyhow::{bail, Result};
+use structopt::StructOpt;
+
+use abstutil::{prettyprint_usize, Timer};
+use map_model::{connectivity, IntersectionID, LaneID, Map, PathConstraints, Road};
+
+#[derive(StructOpt)]
+pub enum Query {
+    /// Lists roads matching all of the given OSM tag filters, one per line as `road ID, OSM way
+    /// ID, name, highway type, number of lanes`.
+    ListRoads {
+        /// Like `highway=residential`, or just `maxspeed` to match roads with any value. Repeat to
+        /// require several.
+        #[structopt(long)]
+        tag: Vec<String>,
+    },
+    /// Checks that every building can be reached by walking, biking, and driving. Fails if any
+    /// can't.
+    CheckReachability {
+        /// List every unreachable building, not just the counts
+        #[structopt(long)]
+        verbose: bool,
+    },
+    /// Finds groups of sidewalks and footways that aren't connected to the rest of the walking
+    /// network.
+    SidewalkIslands,
+    /// Describes every intersection, or just one.
+    Intersections {
+        /// Only describe the intersection with this ID
+        #[structopt(long)]
+        id: Option<usize>,
+    },
+}
+
+pub fn run(map: String, query: Query) -> Result<()> {
+    let map = Map::load_synchronously(map, &mut Timer::throwaway());
+    match query {
+        Query::ListRoads { tag } => list_roads(&map, tag),
+        Query::CheckReachability { verbose } => check_reachability(&map, verbose),
+        Query::SidewalkIslands => {
+            sidewalk_islands(&map);
+            Ok(())
+        }
+        Query::Intersections { id } => intersections(&map, id),
+    }
+}
+
+fn list_roads(map: &Map, filters: Vec<String>) -> Result<()> {
+    let filters: Vec<(String, Option<String>)> = filters
+        .into_iter()
+        .map(|x| match x.split_once('=') {
+            Some((k, v)) => (k.to_string(), Some(v.to_string())),
+            None => (x, None),
+        })
+        .collect();
+    let matches = |r: &Road| {
+        filters.iter().all(|(k, v)| match v {
+            Some(v) => r.osm_tags.is(k, v),
+            None => r.osm_tags.contains_key(k),
+        })
+    };
+
+    let mut count = 0;
+    for r in map.all_roads() {
+        if !matches(r) {
+            continue;
+        }
+        count += 1;
+        println!(
+            "{}, {}, {}, {}, {}",
+            r.id.0,
+            r.orig_id.osm_way_id.0,
+            r.get_name(None),
+            r.osm_tags
+                .get("highway")
+                .map(|x| x.as_str())
+                .unwrap_or("none"),
+            r.lanes.len()
+        );
+    }
+    if count == 0 {
+        bail!("No roads match");
+    }
+    Ok(())
+}
+
+fn check_reachability(map: &Map, verbose: bool) -> Result<()> {
+    let mut failures = 0;
+    for (name, constraints) in [
+        ("walking", PathConstraints::Pedestrian),
+        ("biking", PathConstraints::Bike),
+        ("driving", PathConstraints::Car),
+    ] {
+        // Buildings have to connect to the largest connected part of each network
+        let (main_graph, _) = connectivity::find_scc(map, constraints);
+        let mut unreachable = Vec::new();
+        for b in map.all_buildings() {
+            let lane = match constraints {
+                PathConstraints::Pedestrian => Some(b.sidewalk()),
+                PathConstraints::Bike => b.biking_connection(map).map(|(pos, _)| pos.lane()),
+                _ => b.driving_connection(map).map(|(pos, _)| pos.lane()),
+            };
+            if !lane.map(|l| main_graph.contains(&l)).unwrap_or(false) {
+                unreachable.push(b.id);
+            }
+        }
+
+        println!(
+            "{}: {} / {} buildings unreachable",
+            name,
+            prettyprint_usize(unreachable.len()),
+            prettyprint_usize(map.all_buildings().len())
+        );
+        if verbose {
+            for b in &unreachable {
+                println!("  {}: {}", b, map.get_b(*b).address);
+            }
+        }
+        if !unreachable.is_empty() {
+            failures += 1;
+        }
+    }
+    if failures > 0 {
+        bail!("Some buildings can't be reached by {} modes", failures);
+    }
+    Ok(())
+}
+
+fn sidewalk_islands(map: &Map) {
+    // People can walk both ways along a sidewalk, so treat the network as undirected
+    let mut neighbors: HashMap<LaneID, Vec<LaneID>> = HashMap::new();
+    for turn in map.all_turns() {
+        if PathConstraints::Pedestrian.can_use(map.get_l(turn.id.src), map)
+            && PathConstraints::Pedestrian.can_use(map.get_l(turn.id.dst), map)
+        {
+            neighbors.entry(turn.id.src).or_default().push(turn.id.dst);
+            neighbors.entry(turn.id.dst).or_default().push(turn.id.src);
+        }
+    }
+
+    let mut seen: HashSet<LaneID> = HashSet::new();
+    let mut islands: Vec<Vec<LaneID>> = Vec::new();
+    for lane in map.all_lanes() {
+        if !PathConstraints::Pedestrian.can_use(lane, map) || seen.contains(&lane.id) {
+            continue;
+        }
+        let mut island = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(lane.id);
+        seen.insert(lane.id);
+        while let Some(l) = queue.pop_front() {
+            island.push(l);
+            for next in neighbors.get(&l).into_iter().flatten() {
+                if seen.insert(*next) {
+                    queue.push_back(*next);
+                }
+            }
+        }
+        islands.push(island);
+    }
+    // The largest group is the main network
+    islands.sort_by_key(|island| island.len());
+    islands.pop();
+
+    println!("{} sidewalk islands", prettyprint_usize(islands.len()));
+    for island in islands.into_iter().rev() {
+        let roads: BTreeSet<String> = island
+            .iter()
+            .map(|l| map.get_parent(*l).get_name(None))
+            .collect();
+        let pt = map
+            .get_l(island[0])
+            .lane_center_pts
+            .middle()
+            .to_gps(map.get_gps_bounds());
+        println!(
+            "{} lanes near ({:.6}, {:.6}) along {}",
+            island.len(),
+            pt.y(),
+            pt.x(),
+            roads.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+}
+
+fn intersections(map: &Map, id: Option<usize>) -> Result<()> {
+    let list = if let Some(id) = id {
+        if id >= map.all_intersections().len() {
+            bail!(
+                "The map only has {} intersections",
+                map.all_intersections().len()
+            );
+        }
+        vec![map.get_i(IntersectionID(id))]
+    } else {
+        map.all_intersections().iter().collect()
+    };
+    for i in list {
+        println!(
+            "{} (OSM node {}): {:?}, {:?}",
+            i.id, i.orig_id.0, i.kind, i.control
+        );
+        for r in &i.roads {
+            let road = map.get_r(*r);
+            println!(
+                "  {} ({}): {}",
+                r,
+                road.orig_id.osm_way_id,
+                road.get_name(None)
+            );
+        }
+        println!("  {} turns, {} movements", i.turns.len(), i.movements.len());
+        if let Some(signal) = map.maybe_get_traffic_signal(i.id) {
+            println!(
+                "  {} stages, cycle length {}",
+                signal.stages.len(),
+                signal.simple_cycle_duration()
+            );
+        }
+    }
+    Ok(())
+}
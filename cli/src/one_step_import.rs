@@ -1,10 +1,11 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use abstio::CityName;
 use geom::LonLat;
 
 pub async fn run(
-    geojson_path: String,
+    geojson_path: Option<String>,
+    bbox: Option<String>,
     name: String,
     use_geofabrik: bool,
     filter_crosswalks: bool,
@@ -18,26 +19,23 @@ pub async fn run(
     }
 
     let city = CityName::new("zz", "oneshot");
+    // Everything downstream needs a boundary file, so write one for a bounding box
+    let geojson_path = match (geojson_path, bbox) {
+        (Some(path), None) => path,
+        (None, Some(bbox)) => {
+            let path = city.input_path(format!("boundaries/{}.geojson", name));
+            LonLat::write_geojson_polygon(&path, &convert_osm::overpass::parse_bbox(&bbox)?)?;
+            path
+        }
+        _ => bail!("Pass exactly one of --geojson-path or --bbox"),
+    };
+
     let osm;
     if !use_geofabrik {
         println!("Downloading OSM data from Overpass...");
         osm = city.input_path(format!("osm/{}.osm", name));
-
-        let geojson = abstio::slurp_file(geojson_path.clone())?;
-        let mut polygons = LonLat::parse_geojson_polygons(String::from_utf8(geojson)?)?;
-        let mut filter = "poly:\"".to_string();
-        for pt in polygons.pop().unwrap().0 {
-            filter.push_str(&format!("{} {} ", pt.y(), pt.x()));
-        }
-        filter.pop();
-        filter.push('"');
-        // See https://wiki.openstreetmap.org/wiki/Overpass_API/Overpass_QL
-        let query = format!(
-            "(\n   nwr({});\n     node(w)->.x;\n   <;\n);\nout meta;\n",
-            filter
-        );
-        abstio::download_to_file("https://overpass-api.de/api/interpreter", Some(query), &osm)
-            .await?;
+        let boundary = LonLat::read_geojson_polygon(&geojson_path)?;
+        convert_osm::overpass::download(&boundary, &osm).await?;
     } else {
         println!("Figuring out what Geofabrik file contains your boundary");
         let url = importer::pick_geofabrik(geojson_path.clone()).await?;
@@ -61,10 +59,12 @@ pub async fn run(
 
     // Import!
     println!("Running importer");
+    let mut options = convert_osm::Options::default();
+    options.filter_crosswalks = filter_crosswalks;
     importer::oneshot(
         osm,
         Some(geojson_path),
-        filter_crosswalks,
+        options,
         create_uk_travel_demand_model,
         map_model::RawToMapOptions::default(),
     )
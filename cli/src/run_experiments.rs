@@ -0,0 +1,177 @@
+//! Simulates every combination of some map edits and scenarios, for comparing many design
+//! alternatives at once. The config is a JSON file like:
+//!
+//! ```json
+//! {
+//!   "edits": [null, "data/player/edits/us/seattle/montlake/bike_lanes.json"],
+//!   "scenarios": ["data/system/us/seattle/scenarios/montlake/weekday.bin"],
+//!   "end_time": "24:00:00",
+//!   "rng_seed": 42
+//! }
+//! ```
+//!
+//! A `null` entry in `edits` simulates without any edits, as a baseline. `end_time` and `rng_seed`
+//! are optional. The results are a CSV file with one row per combination and trip mode, plus an
+//! `all` row summing over modes.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use fs_err::File;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::Deserialize;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Time};
+use map_model::{Map, MapEdits};
+use sim::{AlertHandler, Sim, SimFlags, SimOptions};
+use synthpop::{Scenario, TripMode};
+
+#[derive(Deserialize)]
+struct Config {
+    edits: Vec<Option<String>>,
+    scenarios: Vec<String>,
+    /// Defaults to the scenario's end of day
+    #[serde(default)]
+    end_time: Option<String>,
+    #[serde(default = "default_rng_seed")]
+    rng_seed: u64,
+}
+
+fn default_rng_seed() -> u64 {
+    SimFlags::RNG_SEED
+}
+
+struct Row {
+    mode: String,
+    finished_trips: usize,
+    cancelled_trips: usize,
+    total_duration: Duration,
+    median_duration: Duration,
+}
+
+pub fn run(config_path: String, output: String) -> Result<()> {
+    let mut timer = Timer::new("run experiments");
+    let config: Config = abstio::maybe_read_json(config_path, &mut timer)?;
+    let end_time = config.end_time.as_deref().map(Time::parse).transpose()?;
+    let rng_seed = config.rng_seed;
+
+    let mut cells = Vec::new();
+    for edits in &config.edits {
+        for scenario in &config.scenarios {
+            cells.push((edits.clone(), scenario.clone()));
+        }
+    }
+    println!("Running {} experiments", prettyprint_usize(cells.len()));
+
+    let results = timer.parallelize(
+        "simulate each combination",
+        cells.clone(),
+        |(edits, scenario)| run_one(edits, scenario, end_time, rng_seed),
+    );
+
+    let mut f = File::create(&output)?;
+    writeln!(
+        f,
+        "edits,scenario,mode,finished_trips,cancelled_trips,total_duration_s,mean_duration_s,median_duration_s"
+    )?;
+    let mut failures = 0;
+    for ((edits, scenario), result) in cells.into_iter().zip(results) {
+        let edits = edits.unwrap_or_else(|| "none".to_string());
+        match result {
+            Ok(rows) => {
+                for row in rows {
+                    let mean = if row.finished_trips == 0 {
+                        Duration::ZERO
+                    } else {
+                        row.total_duration / (row.finished_trips as f64)
+                    };
+                    writeln!(
+                        f,
+                        "{},{},{},{},{},{:.1},{:.1},{:.1}",
+                        edits,
+                        scenario,
+                        row.mode,
+                        row.finished_trips,
+                        row.cancelled_trips,
+                        row.total_duration.inner_seconds(),
+                        mean.inner_seconds(),
+                        row.median_duration.inner_seconds()
+                    )?;
+                }
+            }
+            Err(err) => {
+                error!("{} with edits {} failed: {}", scenario, edits, err);
+                failures += 1;
+            }
+        }
+    }
+    println!("Wrote {}", output);
+    if failures > 0 {
+        bail!("{} experiments failed; see the logs", failures);
+    }
+    Ok(())
+}
+
+fn run_one(
+    edits: Option<String>,
+    scenario_path: String,
+    end_time: Option<Time>,
+    rng_seed: u64,
+) -> Result<Vec<Row>> {
+    let mut timer = Timer::throwaway();
+    let scenario: Scenario = abstio::maybe_read_binary(scenario_path, &mut timer)?;
+    let mut map = Map::load_synchronously(scenario.map_name.path(), &mut timer);
+    if let Some(path) = edits {
+        let edits = MapEdits::load_from_file(&map, path, &mut timer)?;
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+
+    let mut opts = SimOptions::new("experiment");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(&map, opts);
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+    sim.instantiate(&scenario, &map, &mut rng, &mut timer);
+    let end_time = end_time.unwrap_or_else(|| sim.get_end_of_day());
+    sim.timed_step(&map, end_time - Time::START_OF_DAY, &mut None, &mut timer);
+
+    let mut durations: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+    let mut cancelled: BTreeMap<String, usize> = BTreeMap::new();
+    for mode in TripMode::all() {
+        durations.insert(mode_name(mode), Vec::new());
+        cancelled.insert(mode_name(mode), 0);
+    }
+    durations.insert("all".to_string(), Vec::new());
+    cancelled.insert("all".to_string(), 0);
+    for (_, _, mode, maybe_duration) in &sim.get_analytics().finished_trips {
+        for key in [mode_name(*mode), "all".to_string()] {
+            if let Some(dt) = maybe_duration {
+                durations.get_mut(&key).unwrap().push(*dt);
+            } else {
+                *cancelled.get_mut(&key).unwrap() += 1;
+            }
+        }
+    }
+
+    Ok(durations
+        .into_iter()
+        .map(|(mode, mut list)| {
+            list.sort();
+            Row {
+                cancelled_trips: cancelled[&mode],
+                mode,
+                finished_trips: list.len(),
+                total_duration: list.iter().cloned().sum(),
+                median_duration: list.get(list.len() / 2).cloned().unwrap_or(Duration::ZERO),
+            }
+        })
+        .collect())
+}
+
+/// Like "walk" or "drive"
+fn mode_name(mode: TripMode) -> String {
+    format!("{:?}", mode).to_lowercase()
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+
+use abstutil::Timer;
+use map_model::{ControlTrafficSignal, EditCmd, EditIntersection, Map};
+
+pub fn export(map: String, output: String) -> Result<()> {
+    let map = Map::load_synchronously(map, &mut Timer::throwaway());
+    let signals = ControlTrafficSignal::export_all(&map);
+    println!("Exporting timing for {} traffic signals", signals.len());
+    abstio::write_file(output, abstutil::to_json(&signals))?;
+    Ok(())
+}
+
+pub fn import(map: String, input: String, edits_name: String) -> Result<()> {
+    let mut timer = Timer::new("import traffic signal timing");
+    let mut map = Map::load_synchronously(map, &mut timer);
+    let raw = abstio::maybe_read_json(input, &mut timer)?;
+    let (signals, errors) = ControlTrafficSignal::import_all(raw, &map);
+    for err in &errors {
+        error!("{}", err);
+    }
+    println!(
+        "Imported {} traffic signals, {} failures",
+        signals.len(),
+        errors.len()
+    );
+
+    let mut edits = map.new_edits();
+    edits.edits_name = edits_name;
+    for signal in signals {
+        edits.commands.push(EditCmd::ChangeIntersection {
+            i: signal.id,
+            old: map.get_i_edit(signal.id),
+            new: EditIntersection::TrafficSignal(signal.export(&map)),
+        });
+    }
+    map.must_apply_edits(edits, &mut timer);
+    map.save_edits();
+    println!(
+        "Wrote {}",
+        abstio::path_edits(map.get_name(), &map.get_edits().edits_name)
+    );
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anyhow::{bail, Result};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::Polygon;
+use map_model::Map;
+use synthpop::make::{PopulationOptions, ZoneTarget};
+
+pub fn run(map: String, zones_path: String, rng_seed: u64, scenario_name: String) -> Result<()> {
+    let mut timer = Timer::new("synthesize population");
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+    let map = Map::load_synchronously(map, &mut timer);
+
+    let mut zones = Vec::new();
+    let require_in_bounds = false;
+    for (polygon, props) in Polygon::from_geojson_bytes(
+        &abstio::slurp_file(zones_path)?,
+        map.get_gps_bounds(),
+        require_in_bounds,
+    )? {
+        let count = |key: &str| -> Result<usize> {
+            match props.get(key) {
+                Some(x) => Ok(x.parse::<usize>()?),
+                None => bail!("Zone is missing {}: {:?}", key, props),
+            }
+        };
+        zones.push(ZoneTarget {
+            name: props.get("name").cloned().unwrap_or_default(),
+            polygon,
+            population: count("population")?,
+            employment: count("employment")?,
+        });
+    }
+
+    let mut scenario = sim::ScenarioGenerator::from_zone_targets(
+        &map,
+        zones,
+        PopulationOptions::default(),
+        &mut rng,
+        &mut timer,
+    );
+    scenario.scenario_name = scenario_name;
+    scenario = scenario.remove_weird_schedules(true);
+    println!(
+        "Synthesized {} people",
+        prettyprint_usize(scenario.people.len())
+    );
+    scenario.save();
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! Read elevation from digital elevation models (DEMs), so any map can have elevation data without
+//! a region-specific source. GeoTIFF files and SRTM `.hgt` tiles both work, as long as they use
+//! WGS84 longitude/latitude coordinates and store meters.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufReader;
+
+use anyhow::Result;
+use fs_err::File;
+use osm2streets::IntersectionID;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+
+use geom::{Distance, LonLat};
+use raw_map::RawMap;
+
+/// SRTM tiles mark voids with this value
+const HGT_VOID: i16 = -32768;
+
+/// Sample elevation for every intersection from the first DEM covering it, then calculate the
+/// incline of every road.
+pub fn add_data(map: &mut RawMap, paths: &[String]) -> Result<()> {
+    let rasters = paths
+        .iter()
+        .map(|path| Raster::read(path))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut missing = BTreeSet::new();
+    for (id, i) in &map.streets.intersections {
+        let gps = i.point.to_gps(&map.streets.gps_bounds);
+        if let Some(z) = rasters.iter().find_map(|raster| raster.sample(gps)) {
+            map.elevation_per_intersection
+                .insert(*id, Distance::meters(z));
+        } else {
+            missing.insert(*id);
+        }
+    }
+    if missing.len() == map.streets.intersections.len() {
+        bail!("None of the DEMs cover this map");
+    }
+    if !missing.is_empty() {
+        warn!(
+            "The DEMs have no data for {} intersections; interpolating along roads",
+            missing.len()
+        );
+        interpolate_missing(map, missing);
+    }
+
+    crate::elevation::calculate_inclines(map);
+    Ok(())
+}
+
+/// Give intersections without data the average elevation of their neighbors, repeating to spread
+/// values along chains of roads. Anything disconnected from known data stays flat.
+fn interpolate_missing(map: &mut RawMap, mut missing: BTreeSet<IntersectionID>) {
+    loop {
+        let mut neighbors: BTreeMap<IntersectionID, Vec<Distance>> = BTreeMap::new();
+        for road in map.streets.roads.values() {
+            for (i, other) in [(road.src_i, road.dst_i), (road.dst_i, road.src_i)] {
+                if missing.contains(&i) && !missing.contains(&other) {
+                    neighbors
+                        .entry(i)
+                        .or_insert_with(Vec::new)
+                        .push(map.elevation_per_intersection[&other]);
+                }
+            }
+        }
+        if neighbors.is_empty() {
+            return;
+        }
+        for (i, values) in neighbors {
+            let avg = values.iter().cloned().sum::<Distance>() / (values.len() as f64);
+            map.elevation_per_intersection.insert(i, avg);
+            missing.remove(&i);
+        }
+    }
+}
+
+/// A grid of elevation values
+struct Raster {
+    /// Where the first value is, in the northwest corner of the grid
+    top_left: LonLat,
+    /// Degrees between adjacent columns
+    step_lon: f64,
+    /// Degrees between adjacent rows
+    step_lat: f64,
+    width: usize,
+    height: usize,
+    /// In meters, row by row from the north. NaN means there's no data.
+    values: Vec<f64>,
+}
+
+impl Raster {
+    fn read(path: &str) -> Result<Raster> {
+        let raster = if path.to_lowercase().ends_with(".hgt") {
+            Raster::read_hgt(path)?
+        } else {
+            Raster::read_geotiff(path)?
+        };
+        if raster.width < 2 || raster.height < 2 {
+            bail!("{} is too small to interpolate", path);
+        }
+        if raster.values.len() != raster.width * raster.height {
+            bail!(
+                "{} has {} values, but is {}x{}",
+                path,
+                raster.values.len(),
+                raster.width,
+                raster.height
+            );
+        }
+        Ok(raster)
+    }
+
+    fn read_geotiff(path: &str) -> Result<Raster> {
+        let mut decoder = Decoder::new(BufReader::new(File::open(path)?))?;
+        let (width, height) = decoder.dimensions()?;
+        // See http://geotiff.maptools.org/spec/geotiff2.6.html. A tiepoint matches one raster
+        // position (i, j) to model coordinates (x, y).
+        let scale = decoder.get_tag_f64_vec(Tag::ModelPixelScaleTag)?;
+        let tiepoint = decoder.get_tag_f64_vec(Tag::ModelTiepointTag)?;
+        if scale.len() < 2 || tiepoint.len() < 6 {
+            bail!(
+                "{} isn't georeferenced with a tiepoint and pixel scale",
+                path
+            );
+        }
+        let left = tiepoint[3] - tiepoint[0] * scale[0];
+        let top = tiepoint[4] + tiepoint[1] * scale[1];
+        if left.abs() > 180.0 || top.abs() > 90.0 || scale[0] > 1.0 || scale[1] > 1.0 {
+            bail!(
+                "{} doesn't seem to use longitude/latitude coordinates",
+                path
+            );
+        }
+        let nodata = decoder
+            .get_tag_ascii_string(Tag::GdalNodata)
+            .ok()
+            .and_then(|x| x.trim_matches(char::from(0)).trim().parse::<f64>().ok());
+
+        let values: Vec<f64> = match decoder.read_image()? {
+            DecodingResult::U8(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::U16(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::U32(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::U64(v) => v.into_iter().map(|x| x as f64).collect(),
+            DecodingResult::I8(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::I16(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::I32(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::I64(v) => v.into_iter().map(|x| x as f64).collect(),
+            DecodingResult::F32(v) => v.into_iter().map(f64::from).collect(),
+            DecodingResult::F64(v) => v,
+        };
+
+        Ok(Raster {
+            // Each pixel usually covers an area, so treat the value as belonging to its center
+            top_left: LonLat::new(left + scale[0] / 2.0, top - scale[1] / 2.0),
+            step_lon: scale[0],
+            step_lat: scale[1],
+            width: width as usize,
+            height: height as usize,
+            values: values
+                .into_iter()
+                .map(|z| if Some(z) == nodata { f64::NAN } else { z })
+                .collect(),
+        })
+    }
+
+    /// Read an SRTM tile. Tiles cover one degree square and are named for the southwest corner,
+    /// like N47W122.hgt.
+    fn read_hgt(path: &str) -> Result<Raster> {
+        let (lon, lat) = parse_hgt_name(&abstutil::basename(path)).ok_or_else(|| {
+            anyhow!(
+                "{} isn't named like an SRTM tile, such as N47W122.hgt",
+                path
+            )
+        })?;
+        let bytes = fs_err::read(path)?;
+        // Tiles are square, with samples 1 or 3 arc-seconds apart. The edge rows and columns
+        // overlap with the neighboring tiles.
+        let size = ((bytes.len() / 2) as f64).sqrt() as usize;
+        if size < 2 || size * size * 2 != bytes.len() {
+            bail!("{} isn't a square grid of 16-bit values", path);
+        }
+        let step = 1.0 / ((size - 1) as f64);
+        Ok(Raster {
+            top_left: LonLat::new(lon, lat + 1.0),
+            step_lon: step,
+            step_lat: step,
+            width: size,
+            height: size,
+            values: bytes
+                .chunks_exact(2)
+                .map(|pair| {
+                    let z = i16::from_be_bytes([pair[0], pair[1]]);
+                    if z == HGT_VOID {
+                        f64::NAN
+                    } else {
+                        f64::from(z)
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    /// Bilinearly interpolate between the 4 values surrounding a point. None if the point is
+    /// outside the grid or near missing data.
+    fn sample(&self, pt: LonLat) -> Option<f64> {
+        let x = (pt.x() - self.top_left.x()) / self.step_lon;
+        let y = (self.top_left.y() - pt.y()) / self.step_lat;
+        if x < 0.0 || y < 0.0 || x > (self.width - 1) as f64 || y > (self.height - 1) as f64 {
+            return None;
+        }
+        // Points exactly on the last row or column still use the cell before it
+        let col = (x.floor() as usize).min(self.width - 2);
+        let row = (y.floor() as usize).min(self.height - 2);
+        let (dx, dy) = (x - col as f64, y - row as f64);
+        let get = |col: usize, row: usize| self.values[row * self.width + col];
+        let z = get(col, row) * (1.0 - dx) * (1.0 - dy)
+            + get(col + 1, row) * dx * (1.0 - dy)
+            + get(col, row + 1) * (1.0 - dx) * dy
+            + get(col + 1, row + 1) * dx * dy;
+        if z.is_finite() {
+            Some(z)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the (longitude, latitude) of the southwest corner
+fn parse_hgt_name(name: &str) -> Option<(f64, f64)> {
+    let name = name.to_uppercase();
+    let lat_sign = match name.get(0..1)? {
+        "N" => 1.0,
+        "S" => -1.0,
+        _ => return None,
+    };
+    let lat = name.get(1..3)?.parse::<f64>().ok()?;
+    let lon_sign = match name.get(3..4)? {
+        "E" => 1.0,
+        "W" => -1.0,
+        _ => return None,
+    };
+    let lon = name.get(4..7)?.parse::<f64>().ok()?;
+    Some((lon_sign * lon, lat_sign * lat))
+}
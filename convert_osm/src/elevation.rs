@@ -145,6 +145,12 @@ fn scrape_output(
         bail!("Output had {} lines, but we made {} queries", cnt, num_ids);
     }
 
+    calculate_inclines(map);
+    Ok(())
+}
+
+/// After `elevation_per_intersection` is filled out, set the incline of every road.
+pub(crate) fn calculate_inclines(map: &mut RawMap) {
     // Calculate the incline for each road here, before the road gets trimmed for intersection
     // geometry. If we did this after trimming, we'd miss some of the horizontal distance.
     for road in map.streets.roads.values() {
@@ -168,6 +174,4 @@ fn scrape_output(
             );
         }
     }
-
-    Ok(())
 }
@@ -1,10 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use abstutil::{MultiMap, Tags, Timer};
 use geom::{Distance, FindClosest, HashablePt2D, Polygon, Pt2D, Ring};
 use osm2streets::osm::{OsmID, RelationID, WayID};
 use osm2streets::{osm, DrivingSide, NamePerLanguage};
-use raw_map::{Amenity, AreaType, CrossingType, RawArea, RawBuilding, RawMap, RawParkingLot};
+use raw_map::{
+    Amenity, AreaType, CrossingType, RawArea, RawBuilding, RawMap, RawParkingLot, TrafficCalming,
+};
 
 use crate::Options;
 use streets_reader::osm_reader::{get_multipolygon_members, glue_multipolygon, multipoly_geometry};
@@ -16,9 +18,13 @@ pub struct Extract {
     pub bus_routes_on_roads: MultiMap<WayID, String>,
     /// Crossings located at these points, which should be on a Road's center line
     pub crossing_nodes: HashSet<(HashablePt2D, CrossingType)>,
+    /// Crossings tagged with whether there are curb ramps or not
+    pub curb_ramp_nodes: HashMap<HashablePt2D, bool>,
     /// Some kind of barrier nodes at these points. Only the ones on a Road center line are
     /// relevant.
     pub barrier_nodes: HashSet<HashablePt2D>,
+    /// Traffic calming devices on roads
+    pub traffic_calming_nodes: HashSet<(HashablePt2D, TrafficCalming)>,
 }
 
 pub fn extract_osm(
@@ -28,7 +34,18 @@ pub fn extract_osm(
     opts: &Options,
     timer: &mut Timer,
 ) -> Extract {
-    let osm_xml = fs_err::read_to_string(osm_input_path).unwrap();
+    // Regional extracts from Geofabrik are .osm.pbf
+    let osm_xml = if crate::pbf::is_pbf(osm_input_path).unwrap() {
+        let clip_path = clip_path.as_deref().unwrap_or_else(|| {
+            panic!(
+                "{} is .osm.pbf, so it needs a clip boundary",
+                osm_input_path
+            )
+        });
+        crate::pbf::read_as_xml(osm_input_path, clip_path, timer).unwrap()
+    } else {
+        fs_err::read_to_string(osm_input_path).unwrap()
+    };
     let mut doc =
         streets_reader::osm_reader::read(&osm_xml, &map.streets.gps_bounds, timer).unwrap();
 
@@ -44,12 +61,17 @@ pub fn extract_osm(
         } else {
             DrivingSide::Right
         };
+    if let Some(driving_side) = opts.profile.driving_side {
+        map.streets.config.driving_side = driving_side;
+    }
 
     let mut out = OsmExtract::new();
     let mut amenity_points = Vec::new();
     let mut bus_routes_on_roads: MultiMap<WayID, String> = MultiMap::new();
     let mut crossing_nodes = HashSet::new();
+    let mut curb_ramp_nodes = HashMap::new();
     let mut barrier_nodes = HashSet::new();
+    let mut traffic_calming_nodes = HashSet::new();
 
     timer.start_iter("processing OSM nodes", doc.nodes.len());
     for (id, node) in &doc.nodes {
@@ -67,16 +89,36 @@ pub fn extract_osm(
                 CrossingType::Unsignalized
             };
             crossing_nodes.insert((node.pt.to_hashable(), kind));
+            if let Some(ramps) = get_curb_ramps(&node.tags) {
+                curb_ramp_nodes.insert(node.pt.to_hashable(), ramps);
+            }
         }
         // TODO Any kind of barrier?
         if node.tags.is("barrier", "bollard") {
             barrier_nodes.insert(node.pt.to_hashable());
         }
+        if let Some(kind) = node
+            .tags
+            .get("traffic_calming")
+            .and_then(|x| TrafficCalming::from_osm(x))
+        {
+            traffic_calming_nodes.insert((node.pt.to_hashable(), kind));
+        }
+        if node.tags.is(osm::HIGHWAY, "street_lamp") {
+            map.street_lamps.push(node.pt);
+        }
+        if node
+            .tags
+            .is_any(osm::HIGHWAY, vec!["turning_circle", "turning_loop"])
+        {
+            map.turning_circles.insert(*id);
+        }
     }
 
     let mut coastline_groups: Vec<(WayID, Vec<Pt2D>)> = Vec::new();
     let mut memorial_areas: Vec<Polygon> = Vec::new();
     let mut amenity_areas: Vec<(Polygon, Amenity)> = Vec::new();
+    let mut landuse_areas: Vec<(Polygon, String)> = Vec::new();
     timer.start_iter("processing OSM ways", doc.ways.len());
     for (id, way) in &mut doc.ways {
         timer.next();
@@ -84,6 +126,13 @@ pub fn extract_osm(
 
         if out.handle_way(id, &way, &opts.map_config) {
             continue;
+        } else if opts.include_driveways
+            && way.tags.is(osm::HIGHWAY, "service")
+            && way.tags.is_any("service", vec!["driveway", "alley"])
+        {
+            // is_road skips these, but we want them as roads anyway
+            out.roads.push((id, way.pts.clone(), way.tags.clone()));
+            continue;
         } else if way.tags.is(osm::HIGHWAY, "service") {
             // If we got here, is_road didn't interpret it as a normal road
             map.parking_aisles.push((id, way.pts.clone()));
@@ -134,6 +183,8 @@ pub fn extract_osm(
                 osm_tags: way.tags.clone(),
             };
             amenity_areas.push((polygon, amenity));
+        } else if let Some(landuse) = get_landuse(&way.tags) {
+            landuse_areas.push((polygon, landuse));
         }
     }
 
@@ -274,6 +325,19 @@ pub fn extract_osm(
         }
     }
 
+    // Buildings usually aren't tagged with what they're used for, but the land around them often
+    // is. Copy that onto the building, so it can be classified later.
+    timer.start_iter("match buildings to landuse areas", landuse_areas.len());
+    for (poly, landuse) in landuse_areas {
+        timer.next();
+        for b in closest_bldg.all_points_inside(&poly) {
+            let tags = &mut map.buildings.get_mut(&b).unwrap().osm_tags;
+            if !tags.contains_key("landuse") {
+                tags.insert("landuse", landuse.clone());
+            }
+        }
+    }
+
     // Hack to fix z-ordering for Green Lake (and probably other places). Put water and islands
     // last. I think the more proper fix is interpreting "inner" roles in relations.
     map.areas.sort_by_key(|a| match a.area_type {
@@ -291,7 +355,29 @@ pub fn extract_osm(
         doc,
         bus_routes_on_roads,
         crossing_nodes,
+        curb_ramp_nodes,
         barrier_nodes,
+        traffic_calming_nodes,
+    }
+}
+
+/// Can a wheelchair get on and off the sidewalk at this crossing? `None` if it's not tagged.
+fn get_curb_ramps(tags: &Tags) -> Option<bool> {
+    // https://wiki.openstreetmap.org/wiki/Key:kerb
+    match tags.get("kerb").map(|x| x.as_str()) {
+        // Rolled curbs are sloped enough for wheelchairs
+        Some("lowered" | "flush" | "no" | "rolled") => {
+            return Some(true);
+        }
+        Some("raised") => {
+            return Some(false);
+        }
+        _ => {}
+    }
+    match tags.get("wheelchair").map(|x| x.as_str()) {
+        Some("yes" | "designated") => Some(true),
+        Some("no") => Some(false),
+        _ => None,
     }
 }
 
@@ -314,6 +400,24 @@ fn get_bldg_amenities(tags: &Tags) -> Vec<Amenity> {
     amenities
 }
 
+/// Only the kinds of landuse that say something about the buildings inside
+fn get_landuse(tags: &Tags) -> Option<String> {
+    let landuse = tags.get("landuse")?;
+    if [
+        "residential",
+        "commercial",
+        "retail",
+        "industrial",
+        "education",
+    ]
+    .contains(&landuse.as_str())
+    {
+        Some(landuse.clone())
+    } else {
+        None
+    }
+}
+
 fn get_area_type(tags: &Tags) -> Option<AreaType> {
     if tags.is_any("leisure", vec!["garden", "park", "golf_course"]) {
         return Some(AreaType::Park);
@@ -401,3 +505,39 @@ fn service_road_crosses_parking_lot(map: &RawMap, pts: &[Pt2D], candidates: Vec<
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_curb_ramps() {
+        let tags = |pairs: &[(&str, &str)]| {
+            let mut tags = Tags::empty();
+            for (k, v) in pairs {
+                tags.insert(*k, *v);
+            }
+            tags
+        };
+        for kerb in ["lowered", "flush", "no", "rolled"] {
+            assert_eq!(
+                get_curb_ramps(&tags(&[("kerb", kerb)])),
+                Some(true),
+                "{}",
+                kerb
+            );
+        }
+        assert_eq!(get_curb_ramps(&tags(&[("kerb", "raised")])), Some(false));
+        // The kerb tag wins over the more general wheelchair tag
+        assert_eq!(
+            get_curb_ramps(&tags(&[("kerb", "raised"), ("wheelchair", "yes")])),
+            Some(false)
+        );
+        assert_eq!(get_curb_ramps(&tags(&[("wheelchair", "no")])), Some(false));
+        assert_eq!(
+            get_curb_ramps(&tags(&[("wheelchair", "designated")])),
+            Some(true)
+        );
+        assert_eq!(get_curb_ramps(&tags(&[("highway", "crossing")])), None);
+    }
+}
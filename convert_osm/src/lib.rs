@@ -10,13 +10,22 @@ use anyhow::Result;
 use abstio::MapName;
 use abstutil::{Tags, Timer};
 use geom::{Distance, GPSBounds, HashablePt2D, LonLat, PolyLine, Polygon, Ring};
-use osm2streets::{osm, MapConfig, Road, RoadID};
-use raw_map::{CrossingType, ExtraRoadData, RawMap};
+use osm2streets::{osm, Direction, LaneType, MapConfig, Road, RoadID};
+use raw_map::{CrossingType, ExtraRoadData, RawMap, RegionProfile, TrafficCalming};
 
+mod dem;
 mod elevation;
 mod extract;
 mod gtfs;
+pub mod overpass;
 mod parking;
+pub mod pbf;
+mod region;
+mod snap_cycleways;
+mod stitch;
+
+pub use region::extract_region;
+pub use stitch::stitch;
 
 /// Configures the creation of a `RawMap` from OSM and other input data.
 pub struct Options {
@@ -29,9 +38,18 @@ pub struct Options {
     pub extra_buildings: Option<String>,
     /// Configure public transit using this URL to a static GTFS feed in .zip format.
     pub gtfs_url: Option<String>,
-    pub elevation: bool,
+    pub elevation: Elevation,
     /// Only include crosswalks that match a `highway=crossing` OSM node.
     pub filter_crosswalks: bool,
+    /// Import `service=driveway` and `service=alley` ways as roads, even where osm2streets skips
+    /// them. Vehicles only use them to start or end a trip.
+    pub include_driveways: bool,
+    /// Turn `highway=cycleway` ways running alongside a road into protected bike lanes on that
+    /// road.
+    pub snap_cycleways: bool,
+    /// Regional defaults for lanes, speed limits, driving side, and sidewalks, where OSM doesn't
+    /// tag them.
+    pub profile: RegionProfile,
 }
 
 impl Options {
@@ -43,8 +61,11 @@ impl Options {
             private_offstreet_parking: PrivateOffstreetParking::FixedPerBldg(1),
             extra_buildings: None,
             gtfs_url: None,
-            elevation: false,
+            elevation: Elevation::None,
             filter_crosswalks: false,
+            include_driveways: false,
+            snap_cycleways: false,
+            profile: RegionProfile::default(),
         }
     }
 }
@@ -75,7 +96,21 @@ pub enum PrivateOffstreetParking {
     // TODO Based on the number of residents?
 }
 
-/// Create a RawMap from OSM and other input data.
+/// Where does elevation data come from? `incline` tags on short paths are always used.
+pub enum Elevation {
+    /// Leave the map flat.
+    None,
+    /// Run <https://github.com/eldang/elevation_lookups> through Docker. This only has sources
+    /// for a few places, like Seattle and Great Britain.
+    ElevationLookups,
+    /// Sample these digital elevation models, each a GeoTIFF or an SRTM `.hgt` tile. They must
+    /// use WGS84 longitude/latitude coordinates and store meters. When they overlap, the first
+    /// file listed wins.
+    Dem(Vec<String>),
+}
+
+/// Create a RawMap from OSM and other input data. The OSM input can be XML (.osm) or a .osm.pbf
+/// file, detected from the contents. A .osm.pbf file must be clipped with `clip_path`.
 pub fn convert(
     osm_input_path: String,
     name: MapName,
@@ -89,6 +124,10 @@ pub fn convert(
     // Note that DrivingSide is still incorrect. It'll be set in extract_osm, before Road::new
     // happens in split_ways.
     map.streets.config = opts.map_config.clone();
+    if let Some(inferred_sidewalks) = opts.profile.inferred_sidewalks {
+        map.streets.config.inferred_sidewalks = inferred_sidewalks;
+    }
+    map.profile = opts.profile.clone();
 
     if let Some(ref path) = clip_path {
         let pts = LonLat::read_geojson_polygon(path).unwrap();
@@ -127,22 +166,34 @@ pub fn convert(
         }
     }
 
+    use_default_lanes(&mut map);
     parking::apply_parking(&mut map, &opts, timer);
+    use_turn_lanes_tags(&mut map);
 
     use_barrier_nodes(&mut map, extract.barrier_nodes, &pt_to_road);
     use_crossing_nodes(&mut map, &extract.crossing_nodes, &pt_to_road);
+    use_traffic_calming_nodes(&mut map, extract.traffic_calming_nodes, &pt_to_road);
+    use_curb_ramp_nodes(&mut map, extract.curb_ramp_nodes, &pt_to_road);
 
     if opts.filter_crosswalks {
         filter_crosswalks(&mut map, extract.crossing_nodes, pt_to_road, timer);
     }
+    if opts.snap_cycleways {
+        timer.start("snap separate cycleways");
+        snap_cycleways::snap_cycleways(&mut map);
+        timer.stop("snap separate cycleways");
+    }
 
-    if opts.elevation {
-        timer.start("add elevation data");
-        if let Err(err) = elevation::add_data(&mut map) {
-            error!("No elevation data: {}", err);
-        }
-        timer.stop("add elevation data");
+    timer.start("add elevation data");
+    if let Err(err) = match opts.elevation {
+        Elevation::None => Ok(()),
+        Elevation::ElevationLookups => elevation::add_data(&mut map),
+        Elevation::Dem(ref paths) => dem::add_data(&mut map, paths),
+    } {
+        error!("No elevation data: {}", err);
     }
+    timer.stop("add elevation data");
+    use_incline_tags(&mut map);
     if let Some(ref path) = opts.extra_buildings {
         add_extra_buildings(&mut map, path).unwrap();
     }
@@ -262,10 +313,161 @@ fn clip_map(map: &mut RawMap, timer: &mut Timer) {
     }
     map.areas = result_areas;
 
+    map.street_lamps
+        .retain(|pt| boundary_polygon.contains_pt(*pt));
+
     // TODO Don't touch parking lots. It'll be visually obvious if a clip intersects one of these.
     // The boundary should be manually adjusted.
 }
 
+/// Short paths and stairs often have an `incline` tag, which is more precise than elevation data
+/// sampled at intersections. Use it when the road doesn't already have an incline.
+fn use_incline_tags(map: &mut RawMap) {
+    for road in map.streets.roads.values() {
+        let data = map.extra_road_data.get_mut(&road.id).unwrap();
+        if data.percent_incline != 0.0 {
+            continue;
+        }
+        let incline = road
+            .osm_ids
+            .get(0)
+            .and_then(|id| map.osm_tags.get(&id.osm_way_id))
+            .and_then(|tags| tags.get("incline"))
+            .and_then(|value| parse_incline(value));
+        if let Some(incline) = incline {
+            data.percent_incline = incline;
+        }
+    }
+}
+
+/// Where a way doesn't say how many lanes it has, use the number the regional profile expects for
+/// that kind of road. The `lanes` tag is filled in, so anything reading tags later agrees.
+fn use_default_lanes(map: &mut RawMap) {
+    if map.profile.default_lanes.is_empty() {
+        return;
+    }
+    let mut changed_ways = HashSet::new();
+    for (id, tags) in &mut map.osm_tags {
+        if let Some(lanes) = default_lanes(tags, &map.profile) {
+            tags.insert("lanes", lanes.to_string());
+            // Remember that this isn't OSM data
+            tags.insert("abst:lanes_source", "profile");
+            changed_ways.insert(*id);
+        }
+    }
+
+    for road in map.streets.roads.values_mut() {
+        // Roads merged from several ways keep all of their IDs; any of them may have changed
+        let way = match road
+            .osm_ids
+            .iter()
+            .find(|id| changed_ways.contains(&id.osm_way_id))
+        {
+            Some(id) => id.osm_way_id,
+            None => continue,
+        };
+        road.lane_specs_ltr =
+            osm2streets::get_lane_specs_ltr(&map.osm_tags[&way], &map.streets.config);
+    }
+}
+
+/// How many lanes the profile expects for a way that doesn't say, or None if the way is tagged
+/// with any lane count or the profile has nothing for its kind of road.
+fn default_lanes(tags: &Tags, profile: &RegionProfile) -> Option<usize> {
+    if [
+        "lanes",
+        "lanes:forward",
+        "lanes:backward",
+        "lanes:both_ways",
+    ]
+    .into_iter()
+    .any(|key| tags.contains_key(key))
+    {
+        return None;
+    }
+    let total = *profile.default_lanes.get(tags.get(osm::HIGHWAY)?)?;
+    // Motorways are almost always mapped as one-way carriageways
+    let oneway = tags.is_any("oneway", vec!["yes", "-1"])
+        || tags.is("junction", "roundabout")
+        || tags.is(osm::HIGHWAY, "motorway");
+    let lanes = if oneway { (total + 1) / 2 } else { total };
+    Some(lanes.max(1))
+}
+
+/// Copy `turn:lanes`, `turn:lanes:forward`, and `turn:lanes:backward` onto each driving and bus
+/// lane, so turns can be restricted per lane. See <https://wiki.openstreetmap.org/wiki/Key:turn>.
+/// Each lane keeps the raw values, like `left` or `through`; interpreting them happens when turns
+/// are generated.
+// TODO This copies the tags onto every segment of an OSM way. They maybe only make sense for the
+// last segment.
+fn use_turn_lanes_tags(map: &mut RawMap) {
+    for road in map.streets.roads.values_mut() {
+        let tags = match road
+            .osm_ids
+            .get(0)
+            .and_then(|id| map.osm_tags.get(&id.osm_way_id))
+        {
+            Some(tags) => tags,
+            None => continue,
+        };
+        for dir in [Direction::Fwd, Direction::Back] {
+            let value = if dir == Direction::Fwd {
+                tags.get("turn:lanes:forward")
+                    .or_else(|| tags.get("turn:lanes"))
+            } else {
+                tags.get("turn:lanes:backward")
+            };
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            // The tag lists lanes from left to right, from the perspective of somebody travelling
+            // in that direction
+            let mut lanes: Vec<usize> = road
+                .lane_specs_ltr
+                .iter()
+                .enumerate()
+                .filter(|(_, spec)| {
+                    spec.dir == dir && matches!(spec.lt, LaneType::Driving | LaneType::Bus)
+                })
+                .map(|(idx, _)| idx)
+                .collect();
+            if dir == Direction::Back {
+                lanes.reverse();
+            }
+            let parts: Vec<&str> = value.split('|').collect();
+            if parts.len() != lanes.len() {
+                warn!("{}'s turn:lanes don't match the lanes", road.id);
+                continue;
+            }
+            for (idx, part) in lanes.into_iter().zip(parts) {
+                // An empty value means the same as "none": no markings on that lane
+                road.lane_specs_ltr[idx].turn_restrictions = if part.trim().is_empty() {
+                    vec!["none".to_string()]
+                } else {
+                    part.split(';').map(|x| x.trim().to_string()).collect()
+                };
+            }
+        }
+    }
+}
+
+/// Parses values like "10%" or "-5°". Just "up" or "down" don't say how steep, so they're
+/// ignored.
+fn parse_incline(value: &str) -> Option<f64> {
+    if let Some(pct) = value.trim().strip_suffix('%') {
+        return pct.trim().parse::<f64>().ok().map(|x| x / 100.0);
+    }
+    if let Some(degrees) = value.trim().strip_suffix('°') {
+        return degrees
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|x| x.to_radians().tan());
+    }
+    None
+}
+
 fn use_barrier_nodes(
     map: &mut RawMap,
     barrier_nodes: HashSet<HashablePt2D>,
@@ -325,6 +527,43 @@ fn use_crossing_nodes(
     }
 }
 
+fn use_traffic_calming_nodes(
+    map: &mut RawMap,
+    traffic_calming_nodes: HashSet<(HashablePt2D, TrafficCalming)>,
+    pt_to_road: &HashMap<HashablePt2D, RoadID>,
+) {
+    for (pt, kind) in traffic_calming_nodes {
+        if let Some(road) = pt_to_road
+            .get(&pt)
+            .and_then(|r| map.extra_road_data.get_mut(r))
+        {
+            road.traffic_calming_nodes.push((pt.to_pt2d(), kind));
+        }
+    }
+}
+
+fn use_curb_ramp_nodes(
+    map: &mut RawMap,
+    curb_ramp_nodes: HashMap<HashablePt2D, bool>,
+    pt_to_road: &HashMap<HashablePt2D, RoadID>,
+) {
+    for (pt, ramps) in curb_ramp_nodes {
+        if let Some(road) = pt_to_road.get(&pt).and_then(|r| map.streets.roads.get(r)) {
+            // Snap to the nearest end of the road, like filter_crosswalks
+            if let Some((dist, _)) = road.reference_line.dist_along_of_point(pt.to_pt2d()) {
+                let data = map.extra_road_data.get_mut(&road.id).unwrap();
+                let end = if dist / road.reference_line.length() <= 0.5 {
+                    &mut data.curb_ramps_backward
+                } else {
+                    &mut data.curb_ramps_forward
+                };
+                // If any crossing at this end lacks ramps, the end does
+                *end = Some(end.unwrap_or(true) && ramps);
+            }
+        }
+    }
+}
+
 fn filter_crosswalks(
     map: &mut RawMap,
     crosswalks: HashSet<(HashablePt2D, CrossingType)>,
@@ -365,3 +604,63 @@ fn filter_crosswalks(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_lanes() {
+        let tags = |pairs: &[(&str, &str)]| {
+            let mut tags = Tags::empty();
+            for (k, v) in pairs {
+                tags.insert(*k, *v);
+            }
+            tags
+        };
+        let mut profile = RegionProfile::default();
+        profile.default_lanes.insert("primary".to_string(), 4);
+        profile.default_lanes.insert("motorway".to_string(), 6);
+        profile.default_lanes.insert("service".to_string(), 1);
+
+        assert_eq!(
+            default_lanes(&tags(&[("highway", "primary")]), &profile),
+            Some(4)
+        );
+        // One-way roads get half, rounding up
+        assert_eq!(
+            default_lanes(
+                &tags(&[("highway", "primary"), ("oneway", "yes")]),
+                &profile
+            ),
+            Some(2)
+        );
+        assert_eq!(
+            default_lanes(&tags(&[("highway", "motorway")]), &profile),
+            Some(3)
+        );
+        assert_eq!(
+            default_lanes(&tags(&[("highway", "service"), ("oneway", "-1")]), &profile),
+            Some(1)
+        );
+        // Nothing in the profile for this kind of road
+        assert_eq!(
+            default_lanes(&tags(&[("highway", "residential")]), &profile),
+            None
+        );
+        // Anything already tagged is left alone
+        for key in [
+            "lanes",
+            "lanes:forward",
+            "lanes:backward",
+            "lanes:both_ways",
+        ] {
+            assert_eq!(
+                default_lanes(&tags(&[("highway", "primary"), (key, "1")]), &profile),
+                None,
+                "{}",
+                key
+            );
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Fetch OSM data for an area straight from the Overpass API, instead of producing an extract
+//! first.
+
+use anyhow::Result;
+
+use geom::LonLat;
+
+const OVERPASS_URL: &str = "https://overpass-api.de/api/interpreter";
+
+/// Parses a bounding box formatted as "min_lon,min_lat,max_lon,max_lat" -- the order used by
+/// GeoJSON and most websites -- into a closed boundary polygon.
+pub fn parse_bbox(bbox: &str) -> Result<Vec<LonLat>> {
+    let nums = bbox
+        .split(',')
+        .map(|x| x.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if nums.len() != 4 {
+        bail!("A bounding box needs 4 numbers: min_lon,min_lat,max_lon,max_lat");
+    }
+    let (min_lon, min_lat, max_lon, max_lat) = (nums[0], nums[1], nums[2], nums[3]);
+    if min_lon >= max_lon || min_lat >= max_lat {
+        bail!("Bounding box {} has its corners out of order", bbox);
+    }
+    Ok(vec![
+        LonLat::new(min_lon, min_lat),
+        LonLat::new(max_lon, min_lat),
+        LonLat::new(max_lon, max_lat),
+        LonLat::new(min_lon, max_lat),
+        LonLat::new(min_lon, min_lat),
+    ])
+}
+
+/// An Overpass QL query for everything inside a boundary, plus all nodes of ways partly inside
+/// and the relations using anything returned. See
+/// https://wiki.openstreetmap.org/wiki/Overpass_API/Overpass_QL
+pub fn query(boundary: &[LonLat]) -> String {
+    let mut filter = "poly:\"".to_string();
+    for pt in boundary {
+        filter.push_str(&format!("{} {} ", pt.y(), pt.x()));
+    }
+    filter.pop();
+    filter.push('"');
+    format!(
+        "(\n   nwr({});\n     node(w)->.x;\n   <;\n);\nout meta;\n",
+        filter
+    )
+}
+
+/// Download OSM XML for everything inside a boundary to a file. The response is cached; if the
+/// file already exists and was downloaded for the same boundary, nothing is downloaded.
+pub async fn download(boundary: &[LonLat], output: &str) -> Result<()> {
+    let query = query(boundary);
+    // Remember the query next to the output, so changing the boundary invalidates the cache
+    let query_path = format!("{}.overpass_query", output);
+    if abstio::file_exists(output)
+        && fs_err::read_to_string(&query_path).ok().as_ref() == Some(&query)
+    {
+        println!("- {} already exists", output);
+        return Ok(());
+    }
+    println!("- Downloading {} from Overpass", output);
+    abstio::download_to_file(OVERPASS_URL, Some(query.clone()), output).await?;
+    fs_err::write(query_path, query)?;
+    Ok(())
+}
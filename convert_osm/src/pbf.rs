@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+use std::io::{BufReader, Read, Write};
+
+use anyhow::Result;
+use fs_err::File;
+use geo::prelude::Contains;
+use geo::{LineString, Point, Polygon};
+use osmio::obj_types::ArcOSMObj;
+use osmio::{Node, OSMObj, OSMObjBase, OSMObjectType, OSMReader, OSMWriter, Relation, Way};
+
+use abstutil::Timer;
+use geom::LonLat;
+
+/// Read a .osm.pbf file as OSM XML, keeping only what's inside a GeoJSON boundary. These files are
+/// usually regional extracts, much too big to convert whole, so the boundary is required.
+pub fn read_as_xml(pbf_path: &str, clip_path: &str, timer: &mut Timer) -> Result<String> {
+    timer.start(format!("convert {} to XML", pbf_path));
+    let boundary = read_boundary(clip_path)?;
+    let mut xml = Vec::new();
+    clip_pbf(pbf_path, &boundary, &mut xml)?;
+    timer.stop(format!("convert {} to XML", pbf_path));
+    Ok(String::from_utf8(xml)?)
+}
+
+/// Is this a .osm.pbf file, rather than OSM XML? This checks the contents, not the extension.
+pub fn is_pbf(path: &str) -> Result<bool> {
+    let mut start = Vec::new();
+    File::open(path)?.take(16).read_to_end(&mut start)?;
+    Ok(looks_like_pbf(&start))
+}
+
+/// A .osm.pbf file starts with the length of the first blob header, then that header. Its first
+/// field is the blob type, which is always "OSMHeader" for the first blob.
+fn looks_like_pbf(start: &[u8]) -> bool {
+    start.get(4..15) == Some(&b"\x0a\x09OSMHeader"[..])
+}
+
+/// Read a GeoJSON file with one polygon, for `clip_pbf`.
+pub fn read_boundary(clip_path: &str) -> Result<Polygon> {
+    let boundary_pts = LonLat::read_geojson_polygon(clip_path)?;
+    let raw_pts: Vec<(f64, f64)> = boundary_pts
+        .into_iter()
+        .map(|pt| (pt.x(), pt.y()))
+        .collect();
+    Ok(Polygon::new(LineString::from(raw_pts), Vec::new()))
+}
+
+/// Write a .osm.pbf file as OSM XML. Only keep nodes inside the boundary, ways at least partly
+/// inside it (with all of their nodes), and relations involving anything kept. This is a simple
+/// Rust port of `osmium extract large_map.osm -p clipping.poly -o smaller_map.osm`.
+pub fn clip_pbf<W: Write>(pbf_path: &str, boundary: &Polygon, output: W) -> Result<()> {
+    // TODO Maybe just have a single map with RcOSMObj. But then the order we write will be wrong.
+    let mut way_node_ids: HashSet<i64> = HashSet::new();
+    let mut way_ids: HashSet<i64> = HashSet::new();
+    let mut relation_ids: HashSet<i64> = HashSet::new();
+    let mut node_ids_within_boundary: HashSet<i64> = HashSet::new();
+    {
+        // First Pass: accumulate the IDs we want to include in the output
+        let mut reader = osmio::pbf::PBFReader::new(BufReader::new(File::open(pbf_path)?));
+        for obj in reader.objects() {
+            match obj.object_type() {
+                OSMObjectType::Node => {
+                    let node = obj.into_node().unwrap();
+                    if let Some(lat_lon) = node.lat_lon() {
+                        if boundary.contains(&to_pt(lat_lon)) {
+                            node_ids_within_boundary.insert(node.id());
+                        }
+                    }
+                }
+                OSMObjectType::Way => {
+                    // Assume all nodes appear before any way.
+                    let way = obj.into_way().unwrap();
+                    if way
+                        .nodes()
+                        .iter()
+                        .any(|id| node_ids_within_boundary.contains(id))
+                    {
+                        way_ids.insert(way.id());
+
+                        // To properly compute border nodes, we include all nodes of ways that are
+                        // at least partially in the boundary.
+                        way_node_ids.extend(way.nodes().iter().cloned());
+                    }
+                }
+                OSMObjectType::Relation => {
+                    let relation = obj.into_relation().unwrap();
+                    if relation.members().any(|(obj_type, id, _)| {
+                        (obj_type == OSMObjectType::Node && node_ids_within_boundary.contains(&id))
+                            || (obj_type == OSMObjectType::Way && way_ids.contains(&id))
+                            || (obj_type == OSMObjectType::Relation && relation_ids.contains(&id))
+                    }) {
+                        relation_ids.insert(relation.id());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut writer = osmio::xml::XMLWriter::new(output);
+    // Second Pass: write the feature for each ID accumulated in the first pass
+    let mut reader = osmio::pbf::PBFReader::new(BufReader::new(File::open(pbf_path)?));
+    for obj in reader.objects() {
+        match &obj {
+            ArcOSMObj::Node(node) => {
+                // Standalone nodes like bus stops and crossings aren't part of any way
+                if way_node_ids.contains(&node.id())
+                    || node_ids_within_boundary.contains(&node.id())
+                {
+                    writer.write_obj(&obj)?;
+                }
+            }
+            ArcOSMObj::Way(way) => {
+                if way_ids.contains(&way.id()) {
+                    writer.write_obj(&obj)?;
+                }
+            }
+            ArcOSMObj::Relation(relation) => {
+                if relation_ids.contains(&relation.id()) {
+                    writer.write_obj(&obj)?;
+                }
+            }
+        }
+    }
+
+    // Don't call write.close() -- it happens when writer gets dropped, and the implementation
+    // isn't idempotent.
+
+    Ok(())
+}
+
+fn to_pt(pair: (osmio::Lat, osmio::Lon)) -> Point {
+    // Note our polygon uses (lon, lat)
+    (pair.1.into(), pair.0.into()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_like_pbf() {
+        // The start of a real extract: the header length, then the blob type
+        let pbf = b"\x00\x00\x00\x0d\x0a\x09OSMHeader\x18\x91\x01";
+        assert!(looks_like_pbf(pbf));
+        assert!(!looks_like_pbf(b"<?xml version='1.0' encoding='UTF-8'?>"));
+        assert!(!looks_like_pbf(b"<osm>"));
+        // Truncated files aren't .osm.pbf, even with the right extension
+        assert!(!looks_like_pbf(b"\x00\x00\x00\x0d\x0a\x09OSM"));
+        assert!(!looks_like_pbf(b""));
+    }
+}
@@ -0,0 +1,98 @@
+//! Cuts a smaller RawMap out of a bigger one, like a single neighborhood from a citywide import.
+//! This is much faster than importing the smaller area from OSM again, so it's handy for iterating
+//! on one place.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::{Distance, GPSBounds, LonLat, Ring};
+use raw_map::{ExtraRoadData, RawMap};
+
+use crate::stitch::copy_map;
+
+/// Clips `map` to a new boundary, then shrinks the coordinate system to fit the smaller area.
+/// Roads crossing the boundary end at new border intersections.
+pub fn extract_region(
+    name: MapName,
+    mut map: RawMap,
+    boundary: Vec<LonLat>,
+    timer: &mut Timer,
+) -> Result<RawMap> {
+    timer.start("clip to the region");
+    map.streets.boundary_polygon =
+        Ring::new(map.streets.gps_bounds.convert(&boundary))?.into_polygon();
+    crate::clip_map(&mut map, timer);
+    if map.streets.roads.is_empty() {
+        bail!("There aren't any roads inside the boundary");
+    }
+
+    let boundary_polygon = map.streets.boundary_polygon.clone();
+    map.parking_lots
+        .retain(|lot| boundary_polygon.contains_pt(lot.polygon.center()));
+    map.parking_aisles
+        .retain(|(_, pts)| pts.iter().any(|pt| boundary_polygon.contains_pt(*pt)));
+
+    // Only keep transit stops inside, and routes that still use one
+    map.transit_stops
+        .retain(|_, stop| boundary_polygon.contains_pt(stop.position));
+    for route in &mut map.transit_routes {
+        route
+            .stops
+            .retain(|stop_id| map.transit_stops.contains_key(stop_id));
+    }
+    map.transit_routes.retain(|route| !route.stops.is_empty());
+
+    // Clipping removes some roads and intersections and creates new borders. Clean up the extra
+    // data to match.
+    let streets = &map.streets;
+    map.elevation_per_intersection
+        .retain(|i, _| streets.intersections.contains_key(i));
+    map.extra_road_data
+        .retain(|r, _| streets.roads.contains_key(r));
+    for i in streets.intersections.values() {
+        if !map.elevation_per_intersection.contains_key(&i.id) {
+            // New borders sit along a road that used to continue; use the elevation on the other
+            // end
+            let elevation = i
+                .roads
+                .iter()
+                .map(|r| {
+                    let road = &streets.roads[r];
+                    if road.src_i == i.id {
+                        road.dst_i
+                    } else {
+                        road.src_i
+                    }
+                })
+                .find_map(|other| map.elevation_per_intersection.get(&other).cloned())
+                .unwrap_or(Distance::ZERO);
+            map.elevation_per_intersection.insert(i.id, elevation);
+        }
+    }
+    for r in streets.roads.keys() {
+        map.extra_road_data
+            .entry(*r)
+            .or_insert_with(ExtraRoadData::default);
+    }
+    let way_ids: HashSet<_> = streets
+        .roads
+        .values()
+        .flat_map(|r| r.osm_ids.iter().map(|id| id.osm_way_id))
+        .collect();
+    map.osm_tags.retain(|way, _| way_ids.contains(way));
+    timer.stop("clip to the region");
+
+    timer.start("change coordinate system");
+    let mut result = RawMap::blank(name);
+    let gps_bounds = GPSBounds::from(boundary.clone());
+    result.streets.boundary_polygon = Ring::new(gps_bounds.convert(&boundary))?.into_polygon();
+    result.streets.gps_bounds = gps_bounds;
+    result.streets.config = map.streets.config.clone();
+    copy_map(&mut result, map, None)?;
+    timer.stop("change coordinate system");
+
+    Ok(result)
+}
@@ -0,0 +1,355 @@
+//! OSM often maps cycle tracks next to a road as separate `highway=cycleway` ways. As their own
+//! roads, they get their own tiny intersections where they cross side streets, and bikes on them
+//! can't interact with the road they follow. Where a cycleway clearly runs alongside one road,
+//! remove it and add a bike lane separated by a curb to that road instead. Bikes then cross side
+//! streets as part of the road's intersections.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use geom::{Distance, FindClosest, PolyLine, Pt2D, EPSILON_DIST};
+use osm2streets::{
+    osm, BufferType, Direction, DrivingSide, IntersectionControl, IntersectionID, IntersectionKind,
+    LaneSpec, LaneType, Road, RoadID,
+};
+use raw_map::RawMap;
+
+/// The cycleway must stay this close to the road
+const MAX_DIST_FROM_ROAD: Distance = Distance::const_meters(20.0);
+/// Each end of the cycleway must be this close to the matching end of the road. Both are usually
+/// split at the same side streets.
+const MAX_ENDPOINT_DIST: Distance = Distance::const_meters(30.0);
+/// The cycleway and road must point in the same or opposite direction, within this many degrees
+const MAX_ANGLE_DIFF: f64 = 30.0;
+
+pub fn snap_cycleways(map: &mut RawMap) {
+    let driving_side = map.streets.config.driving_side;
+
+    // Only roads with driving lanes can take a cycleway
+    let mut closest: FindClosest<RoadID> = FindClosest::new(&map.streets.gps_bounds.to_bounds());
+    for road in map.streets.roads.values() {
+        if has_driving_lane(road) {
+            closest.add(road.id, road.reference_line.points());
+        }
+    }
+
+    let mut matches = Vec::new();
+    for cycleway in map.streets.roads.values() {
+        if !is_cycleway(map, cycleway) {
+            continue;
+        }
+        if let Some(m) = find_road(map, &closest, cycleway) {
+            matches.push(m);
+        }
+    }
+
+    // Two cycleways can snap to the same side of a road, if OSM splits them more often. Only add
+    // one lane.
+    let mut widened = BTreeSet::new();
+    // Where a removed cycleway ended, and the nearby intersection of the road it snapped to
+    let mut old_endpoints: BTreeMap<IntersectionID, IntersectionID> = BTreeMap::new();
+    for m in &matches {
+        let cycleway = map.streets.remove_road(m.cycleway);
+        map.extra_road_data.remove(&m.cycleway);
+        let (src, dst) = if m.same_dir {
+            (m.road_src_i, m.road_dst_i)
+        } else {
+            (m.road_dst_i, m.road_src_i)
+        };
+        old_endpoints.entry(cycleway.src_i).or_insert(src);
+        old_endpoints.entry(cycleway.dst_i).or_insert(dst);
+
+        if !widened.insert((m.road, m.right_side)) {
+            continue;
+        }
+        let road = map.streets.roads.get_mut(&m.road).unwrap();
+        // Don't duplicate a lane mapped on the road itself
+        if has_bike_lane_on_side(road, m.right_side) {
+            continue;
+        }
+        let new_lanes = protected_lanes(&cycleway, m.same_dir, m.right_side, driving_side);
+        // Keep sidewalks on the outside
+        let idx = if m.right_side {
+            let mut idx = road.lane_specs_ltr.len();
+            while idx > 0 && road.lane_specs_ltr[idx - 1].lt.is_walkable() {
+                idx -= 1;
+            }
+            idx
+        } else {
+            road.lane_specs_ltr
+                .iter()
+                .take_while(|spec| spec.lt.is_walkable())
+                .count()
+        };
+        road.lane_specs_ltr.splice(idx..idx, new_lanes);
+        road.update_center_line(driving_side);
+    }
+
+    for (i, snapped_to) in old_endpoints {
+        fix_old_endpoint(map, i, snapped_to);
+    }
+
+    info!("Snapped {} separate cycleways to roads", matches.len());
+}
+
+/// A cycleway running alongside a road
+struct Match {
+    cycleway: RoadID,
+    road: RoadID,
+    road_src_i: IntersectionID,
+    road_dst_i: IntersectionID,
+    /// Does the cycleway point the same way as the road?
+    same_dir: bool,
+    /// Relative to the road's direction
+    right_side: bool,
+}
+
+fn find_road(map: &RawMap, closest: &FindClosest<RoadID>, cycleway: &Road) -> Option<Match> {
+    let pl = &cycleway.reference_line;
+    let cycleway_src = map.streets.intersections[&cycleway.src_i].point;
+    let cycleway_dst = map.streets.intersections[&cycleway.dst_i].point;
+    let samples: Vec<Pt2D> = [0.25, 0.5, 0.75]
+        .into_iter()
+        .map(|pct| pl.must_dist_along(pct * pl.length()).0)
+        .collect();
+
+    // Every sample must be close to the road, so only roads near the middle one can match
+    let candidates: BTreeSet<RoadID> = closest
+        .all_close_pts(samples[1], MAX_DIST_FROM_ROAD)
+        .into_iter()
+        .map(|(r, _, _)| r)
+        .collect();
+
+    let mut best: Option<(Distance, Match)> = None;
+    for r in candidates {
+        let road = &map.streets.roads[&r];
+        if road.id == cycleway.id {
+            continue;
+        }
+        // The cycleway shouldn't share an intersection with the road it follows. If it does, it's
+        // probably crossing or branching off.
+        if [road.src_i, road.dst_i].contains(&cycleway.src_i)
+            || [road.src_i, road.dst_i].contains(&cycleway.dst_i)
+        {
+            continue;
+        }
+
+        let road_src = map.streets.intersections[&road.src_i].point;
+        let road_dst = map.streets.intersections[&road.dst_i].point;
+        let same_dir = if cycleway_src.dist_to(road_src) <= MAX_ENDPOINT_DIST
+            && cycleway_dst.dist_to(road_dst) <= MAX_ENDPOINT_DIST
+        {
+            true
+        } else if cycleway_src.dist_to(road_dst) <= MAX_ENDPOINT_DIST
+            && cycleway_dst.dist_to(road_src) <= MAX_ENDPOINT_DIST
+        {
+            false
+        } else {
+            continue;
+        };
+
+        let road_pl = &road.reference_line;
+        if !road_pl
+            .overall_angle()
+            .approx_parallel(pl.overall_angle(), MAX_ANGLE_DIFF)
+        {
+            continue;
+        }
+        let mut total_dist = Distance::ZERO;
+        // Each sample votes for which side of the road it's on. Samples right on top of the road
+        // or past its ends don't vote.
+        let mut right_votes = 0;
+        let mut left_votes = 0;
+        let mut close = true;
+        for pt in &samples {
+            let projected = road_pl.project_pt(*pt);
+            let dist = projected.dist_to(*pt);
+            if dist > MAX_DIST_FROM_ROAD {
+                close = false;
+                break;
+            }
+            total_dist += dist;
+            if dist < EPSILON_DIST {
+                continue;
+            }
+            if let Some((_, angle)) = road_pl.dist_along_of_point(projected) {
+                if angle
+                    .rotate_degs(90.0)
+                    .approx_eq(projected.angle_to(*pt), 90.0)
+                {
+                    right_votes += 1;
+                } else {
+                    left_votes += 1;
+                }
+            }
+        }
+        // If the side is unclear, don't guess
+        if !close || right_votes == left_votes {
+            continue;
+        }
+
+        if best
+            .as_ref()
+            .map(|(dist, _)| total_dist < *dist)
+            .unwrap_or(true)
+        {
+            best = Some((
+                total_dist,
+                Match {
+                    cycleway: cycleway.id,
+                    road: road.id,
+                    road_src_i: road.src_i,
+                    road_dst_i: road.dst_i,
+                    same_dir,
+                    right_side: right_votes > left_votes,
+                },
+            ));
+        }
+    }
+    best.map(|(_, m)| m)
+}
+
+/// The bike lanes of the cycleway, oriented to the road, plus a curb separating them from the
+/// road. In left-to-right order.
+fn protected_lanes(
+    cycleway: &Road,
+    same_dir: bool,
+    right_side: bool,
+    driving_side: DrivingSide,
+) -> Vec<LaneSpec> {
+    let mut bike_lanes: Vec<LaneSpec> = cycleway
+        .lane_specs_ltr
+        .iter()
+        .filter(|spec| spec.lt == LaneType::Biking)
+        .cloned()
+        .map(|mut spec| {
+            if !same_dir {
+                spec.dir = spec.dir.opposite();
+            }
+            spec
+        })
+        .collect();
+    // Within a two-way cycleway, bikes keep to the same side as other traffic
+    bike_lanes.sort_by_key(|spec| match (spec.dir, driving_side) {
+        (Direction::Back, DrivingSide::Right) | (Direction::Fwd, DrivingSide::Left) => 0,
+        _ => 1,
+    });
+
+    let lt = LaneType::Buffer(BufferType::Curb);
+    let buffer = LaneSpec {
+        lt,
+        dir: bike_lanes
+            .get(0)
+            .map(|spec| spec.dir)
+            .unwrap_or(Direction::Fwd),
+        width: LaneSpec::typical_lane_widths(lt, "cycleway")[0].0,
+        turn_restrictions: Vec::new(),
+    };
+    if right_side {
+        bike_lanes.insert(0, buffer);
+    } else {
+        bike_lanes.push(buffer);
+    }
+    bike_lanes
+}
+
+/// Clean up an intersection where a removed cycleway ended.
+fn fix_old_endpoint(map: &mut RawMap, i: IntersectionID, snapped_to: IntersectionID) {
+    // The intersection the road ends at may itself have been removed, if the road was also a
+    // cycleway that snapped somewhere. That's unlikely, since only roads with driving lanes are
+    // snapped to.
+    if !map.streets.intersections.contains_key(&i) {
+        return;
+    }
+    let roads = map.streets.intersections[&i].roads.clone();
+    if roads.is_empty() {
+        map.streets.remove_intersection(i);
+        map.elevation_per_intersection.remove(&i);
+        return;
+    }
+
+    if roads
+        .iter()
+        .any(|r| has_driving_lane(&map.streets.roads[r]))
+    {
+        // The cycleway crossed a side street here. Now the side street just continues through.
+        let intersection = map.streets.intersections.get_mut(&i).unwrap();
+        if roads.len() == 2 {
+            intersection.kind = IntersectionKind::Connection;
+            intersection.control = IntersectionControl::Uncontrolled;
+        }
+        return;
+    }
+
+    // Only paths are left, like the cycleway continuing away from the road or a path leading to
+    // a crossing. Reconnect them to the road's intersection.
+    if !map.streets.intersections.contains_key(&snapped_to) {
+        return;
+    }
+    let new_pt = map.streets.intersections[&snapped_to].point;
+    for r in roads {
+        let mut road = map.streets.remove_road(r);
+        let mut pts = road.reference_line.clone().into_points();
+        if road.src_i == i {
+            road.src_i = snapped_to;
+            pts[0] = new_pt;
+        }
+        if road.dst_i == i {
+            road.dst_i = snapped_to;
+            *pts.last_mut().unwrap() = new_pt;
+        }
+        if road.src_i == road.dst_i {
+            // A tiny path between the cycleway and road would collapse into a loop
+            map.extra_road_data.remove(&r);
+            continue;
+        }
+        if let Ok(pl) = PolyLine::deduping_new(pts) {
+            road.reference_line = pl;
+        }
+        road.update_center_line(map.streets.config.driving_side);
+        map.streets.insert_road(road);
+    }
+    map.streets.remove_intersection(i);
+    map.elevation_per_intersection.remove(&i);
+
+    let intersection = map.streets.intersections.get_mut(&snapped_to).unwrap();
+    if intersection.kind == IntersectionKind::Connection && intersection.roads.len() > 2 {
+        intersection.kind = IntersectionKind::Intersection;
+    }
+}
+
+fn is_cycleway(map: &RawMap, road: &Road) -> bool {
+    let tagged = road
+        .osm_ids
+        .get(0)
+        .and_then(|id| map.osm_tags.get(&id.osm_way_id))
+        .map(|tags| tags.is(osm::HIGHWAY, "cycleway"))
+        .unwrap_or(false);
+    tagged
+        && !road.internal_junction_road
+        && road
+            .lane_specs_ltr
+            .iter()
+            .any(|spec| spec.lt == LaneType::Biking)
+        && road
+            .lane_specs_ltr
+            .iter()
+            .all(|spec| spec.lt == LaneType::Biking || spec.lt.is_walkable())
+}
+
+fn has_driving_lane(road: &Road) -> bool {
+    road.lane_specs_ltr
+        .iter()
+        .any(|spec| spec.lt == LaneType::Driving)
+}
+
+fn has_bike_lane_on_side(road: &Road, right_side: bool) -> bool {
+    let lanes: Vec<&LaneSpec> = if right_side {
+        road.lane_specs_ltr.iter().rev().collect()
+    } else {
+        road.lane_specs_ltr.iter().collect()
+    };
+    lanes
+        .into_iter()
+        .take_while(|spec| spec.lt != LaneType::Driving && spec.lt != LaneType::Bus)
+        .any(|spec| spec.lt == LaneType::Biking)
+}
@@ -0,0 +1,249 @@
+//! Combines two adjacent RawMaps, like neighboring extracts of the same city, into one. Roads
+//! crossing the shared edge are clipped in both maps, ending at border intersections on either
+//! side. Those pairs of borders get merged back together, reconnecting the roads.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::{Distance, GPSBounds, PolyLine, Polygon, Pt2D};
+use osm2streets::{osm, IntersectionControl, IntersectionID, IntersectionKind, RoadID};
+use raw_map::RawMap;
+
+/// Border intersections in the two maps closer than this are assumed to be the same place.
+const MAX_BORDER_GAP: Distance = Distance::const_meters(5.0);
+
+/// The two maps should touch, but not overlap much. Anything from the second map located inside
+/// the first map's boundary is skipped, assuming it's a duplicate.
+pub fn stitch(name: MapName, map1: RawMap, map2: RawMap, timer: &mut Timer) -> Result<RawMap> {
+    if map1.streets.config.driving_side != map2.streets.config.driving_side {
+        bail!("The two maps drive on different sides of the road");
+    }
+
+    let mut gps_bounds = GPSBounds::new();
+    for pt in map1
+        .streets
+        .gps_bounds
+        .get_rectangle()
+        .into_iter()
+        .chain(map2.streets.gps_bounds.get_rectangle())
+    {
+        gps_bounds.update(pt);
+    }
+
+    let mut result = RawMap::blank(name);
+    result.streets.gps_bounds = gps_bounds.clone();
+    result.streets.config = map1.streets.config.clone();
+
+    let boundary1 = map1
+        .streets
+        .boundary_polygon
+        .change_gps_bounds(&map1.streets.gps_bounds, &gps_bounds)?;
+    let boundary2 = map2
+        .streets
+        .boundary_polygon
+        .change_gps_bounds(&map2.streets.gps_bounds, &gps_bounds)?;
+    let mut union = Polygon::union_all_into_multipolygon(vec![boundary1.clone(), boundary2]);
+    result.streets.boundary_polygon = if union.0.len() == 1 {
+        Polygon::try_from(union.0.pop().unwrap())?
+    } else {
+        bail!("The two maps don't touch");
+    };
+
+    timer.start("copy first map");
+    let borders1 = copy_map(&mut result, map1, None)?;
+    timer.stop("copy first map");
+    timer.start("copy second map");
+    let borders2 = copy_map(&mut result, map2, Some(&boundary1))?;
+    timer.stop("copy second map");
+
+    timer.start("merge borders");
+    let mut num_merged = 0;
+    for i2 in borders2 {
+        let pt2 = result.streets.intersections[&i2].point;
+        let way2 = way_ids(&result, i2);
+        // Only merge borders where the same OSM way was clipped
+        if let Some(i1) = borders1
+            .iter()
+            .filter(|i1| result.streets.intersections.contains_key(i1))
+            .filter(|i1| result.streets.intersections[i1].point.dist_to(pt2) <= MAX_BORDER_GAP)
+            .filter(|i1| way_ids(&result, **i1).iter().any(|w| way2.contains(w)))
+            .min_by_key(|i1| result.streets.intersections[i1].point.dist_to(pt2))
+            .cloned()
+        {
+            merge_borders(&mut result, i1, i2);
+            num_merged += 1;
+        }
+    }
+    timer.stop("merge borders");
+    info!("Merged {} pairs of border intersections", num_merged);
+
+    Ok(result)
+}
+
+/// Copies everything from `map` into `result`, changing to the new coordinate system. Returns the
+/// new IDs of border intersections.
+pub(crate) fn copy_map(
+    result: &mut RawMap,
+    map: RawMap,
+    skip_inside: Option<&Polygon>,
+) -> Result<Vec<IntersectionID>> {
+    let from = map.streets.gps_bounds.clone();
+    let to = result.streets.gps_bounds.clone();
+    let reproject = |pt: Pt2D| pt.to_gps(&from).to_pt(&to);
+    let reproject_pl =
+        |pl: &PolyLine| PolyLine::new(pl.points().iter().map(|pt| reproject(*pt)).collect());
+    let skip = |pt: Pt2D| skip_inside.map(|p| p.contains_pt(pt)).unwrap_or(false);
+
+    let mut intersections: BTreeMap<IntersectionID, IntersectionID> = BTreeMap::new();
+    let mut borders = Vec::new();
+    for i in map.streets.intersections.values() {
+        let pt = reproject(i.point);
+        // Borders sit right on the shared edge, so they might land slightly inside the other map
+        if i.kind != IntersectionKind::MapEdge && skip(pt) {
+            continue;
+        }
+        let id = result
+            .streets
+            .insert_intersection(i.osm_ids.clone(), pt, i.kind, i.control);
+        intersections.insert(i.id, id);
+        if i.kind == IntersectionKind::MapEdge {
+            borders.push(id);
+        }
+        if let Some(elevation) = map.elevation_per_intersection.get(&i.id) {
+            result.elevation_per_intersection.insert(id, *elevation);
+        }
+    }
+
+    let mut roads: BTreeMap<RoadID, RoadID> = BTreeMap::new();
+    for r in map.streets.roads.values() {
+        // Roads are only skipped when an endpoint was
+        let (src_i, dst_i) = match (intersections.get(&r.src_i), intersections.get(&r.dst_i)) {
+            (Some(i1), Some(i2)) => (*i1, *i2),
+            _ => continue,
+        };
+        let mut road = r.clone();
+        road.id = result.streets.next_road_id();
+        road.src_i = src_i;
+        road.dst_i = dst_i;
+        road.reference_line = reproject_pl(&r.reference_line)?;
+        road.update_center_line(result.streets.config.driving_side);
+        roads.insert(r.id, road.id);
+        result.streets.insert_road(road);
+    }
+    for (id, mut extra) in map.extra_road_data {
+        if let Some(new_id) = roads.get(&id) {
+            for pt in &mut extra.barrier_nodes {
+                *pt = reproject(*pt);
+            }
+            for (pt, _) in &mut extra.crossing_nodes {
+                *pt = reproject(*pt);
+            }
+            for (pt, _) in &mut extra.traffic_calming_nodes {
+                *pt = reproject(*pt);
+            }
+            result.extra_road_data.insert(*new_id, extra);
+        }
+    }
+    for (way, tags) in map.osm_tags {
+        result.osm_tags.entry(way).or_insert(tags);
+    }
+    for (way, routes) in map.bus_routes_on_roads.consume() {
+        for route in routes {
+            result.bus_routes_on_roads.insert(way, route);
+        }
+    }
+
+    for (id, mut b) in map.buildings {
+        b.polygon = b.polygon.change_gps_bounds(&from, &to)?;
+        if !skip(b.polygon.center()) {
+            result.buildings.entry(id).or_insert(b);
+        }
+    }
+    for mut area in map.areas {
+        area.polygon = area.polygon.change_gps_bounds(&from, &to)?;
+        if !skip(area.polygon.center()) {
+            result.areas.push(area);
+        }
+    }
+    for mut lot in map.parking_lots {
+        lot.polygon = lot.polygon.change_gps_bounds(&from, &to)?;
+        if !skip(lot.polygon.center()) {
+            result.parking_lots.push(lot);
+        }
+    }
+    for (id, pts) in map.parking_aisles {
+        let pts: Vec<Pt2D> = pts.into_iter().map(reproject).collect();
+        if !pts.iter().all(|pt| skip(*pt)) {
+            result.parking_aisles.push((id, pts));
+        }
+    }
+    for (id, mut stop) in map.transit_stops {
+        stop.position = reproject(stop.position);
+        result.transit_stops.entry(id).or_insert(stop);
+    }
+    for mut route in map.transit_routes {
+        // The same GTFS route might appear in both maps
+        if result
+            .transit_routes
+            .iter()
+            .any(|r| r.gtfs_id == route.gtfs_id)
+        {
+            continue;
+        }
+        route.shape = reproject_pl(&route.shape)?;
+        result.transit_routes.push(route);
+    }
+    for pt in map.street_lamps {
+        let pt = reproject(pt);
+        if !skip(pt) {
+            result.street_lamps.push(pt);
+        }
+    }
+
+    Ok(borders)
+}
+
+fn way_ids(map: &RawMap, i: IntersectionID) -> Vec<osm::WayID> {
+    map.streets.intersections[&i]
+        .roads
+        .iter()
+        .flat_map(|r| map.streets.roads[r].osm_ids.iter().map(|id| id.osm_way_id))
+        .collect()
+}
+
+/// Moves all roads from the second border to the first, and turns that into a normal
+/// intersection.
+fn merge_borders(map: &mut RawMap, keep: IntersectionID, remove: IntersectionID) {
+    let keep_pt = map.streets.intersections[&keep].point;
+    for r in map.streets.intersections[&remove].roads.clone() {
+        let mut road = map.streets.remove_road(r);
+        let mut pts = road.reference_line.clone().into_points();
+        if road.src_i == remove {
+            road.src_i = keep;
+            pts[0] = keep_pt;
+        }
+        if road.dst_i == remove {
+            road.dst_i = keep;
+            *pts.last_mut().unwrap() = keep_pt;
+        }
+        // If the two points were very close, keep the old line
+        if let Ok(pl) = PolyLine::deduping_new(pts) {
+            road.reference_line = pl;
+        }
+        road.update_center_line(map.streets.config.driving_side);
+        map.streets.insert_road(road);
+    }
+    map.streets.remove_intersection(remove);
+    map.elevation_per_intersection.remove(&remove);
+
+    let i = map.streets.intersections.get_mut(&keep).unwrap();
+    i.kind = if i.roads.len() == 2 {
+        IntersectionKind::Connection
+    } else {
+        IntersectionKind::Intersection
+    };
+    i.control = IntersectionControl::Uncontrolled;
+}
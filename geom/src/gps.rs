@@ -153,6 +153,22 @@ impl LonLat {
         Ok(list.pop().unwrap().0)
     }
 
+    /// Writes a GeoJSON file with one polygon, which `read_geojson_polygon` can read back.
+    pub fn write_geojson_polygon(path: &str, pts: &[LonLat]) -> Result<()> {
+        let polygon = geojson::Geometry::new(Value::Polygon(vec![pts
+            .iter()
+            .map(|pt| vec![pt.x(), pt.y()])
+            .collect()]));
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            fs_err::create_dir_all(parent)?;
+        }
+        fs_err::write(
+            path,
+            crate::geometries_to_geojson(vec![polygon]).to_string(),
+        )?;
+        Ok(())
+    }
+
     pub fn to_geojson(self) -> geojson::Geometry {
         geojson::Geometry::new(geojson::Value::Point(vec![self.x(), self.y()]))
     }
@@ -105,6 +105,12 @@ impl Polygon {
             .expect("must_scale collapsed a Ring")
     }
 
+    /// Moves every point from one map's coordinate system to another's, like when combining two
+    /// maps with different GPS boundaries.
+    pub fn change_gps_bounds(&self, from: &GPSBounds, to: &GPSBounds) -> Result<Self> {
+        self.transform(|pt| pt.to_gps(from).to_pt(to))
+    }
+
     pub fn rotate(&self, angle: Angle) -> Self {
         self.rotate_around(angle, self.center())
     }
@@ -102,22 +102,22 @@ impl Time {
             bail!("Time {}: no :'s", string);
         }
 
-        let mut seconds = parts.last().unwrap().parse::<f64>()?;
-        match parts.len() {
-            1 => Ok(Time::seconds_since_midnight(seconds)),
-            2 => {
-                // They're really minutes
-                seconds *= 60.0;
-                seconds += 3600.0 * parts[0].parse::<f64>()?;
-                Ok(Time::seconds_since_midnight(seconds))
+        let mut values = Vec::new();
+        for part in &parts {
+            let value = part.parse::<f64>()?;
+            if !value.is_finite() || value < 0.0 {
+                bail!("Time {}: {} can't be negative", string, part);
             }
-            3 => {
-                seconds += 60.0 * parts[1].parse::<f64>()?;
-                seconds += 3600.0 * parts[0].parse::<f64>()?;
-                Ok(Time::seconds_since_midnight(seconds))
-            }
-            _ => bail!("Time {}: weird number of parts", string),
+            values.push(value);
         }
+        let seconds = match values[..] {
+            [seconds] => seconds,
+            // They're really minutes
+            [hours, minutes] => 3600.0 * hours + 60.0 * minutes,
+            [hours, minutes, seconds] => 3600.0 * hours + 60.0 * minutes + seconds,
+            _ => bail!("Time {}: weird number of parts", string),
+        };
+        Ok(Time::seconds_since_midnight(seconds))
     }
 
     // TODO These are a little weird, so don't operator overload yet
@@ -209,6 +209,10 @@ mod tests {
                 + Duration::seconds(5.0),
             Time::parse("07:30:05").unwrap()
         );
+        assert!(Time::parse("-07:30").is_err());
+        assert!(Time::parse("07:-30").is_err());
+        assert!(Time::parse("-5").is_err());
+        assert!(Time::parse("07:30:05:01").is_err());
     }
 
     #[test]
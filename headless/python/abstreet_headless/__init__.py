@@ -0,0 +1,3 @@
+from .client import ApiError, Client
+
+__all__ = ['ApiError', 'Client']
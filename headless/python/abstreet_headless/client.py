@@ -0,0 +1,224 @@
+import requests
+
+
+class ApiError(Exception):
+    """The server rejected a request. The message explains why."""
+    pass
+
+
+class Client:
+    """Wraps the HTTP API served by the headless binary.
+
+    Times are strings like '07:30:00'. Durations and distances in responses are
+    numbers, in seconds and meters.
+    """
+
+    def __init__(self, api='http://localhost:1234'):
+        self.api = api.rstrip('/')
+        # Remembered so edits can be applied without the caller repeating
+        # these. None means the server is using whatever it started with.
+        self.scenario = None
+        self.modifiers = []
+        self.repair = False
+
+    def _get(self, cmd, **params):
+        resp = requests.get(self.api + cmd, params=params)
+        if resp.status_code != requests.codes.ok:
+            raise ApiError(resp.text)
+        return resp
+
+    def _post(self, cmd, body, **params):
+        resp = requests.post(self.api + cmd, json=body, params=params)
+        if resp.status_code != requests.codes.ok:
+            raise ApiError(resp.text)
+        return resp
+
+    # Controlling the simulation
+
+    def reset(self):
+        """Restart the current scenario from midnight, keeping any edits."""
+        return self._get('/sim/reset').text
+
+    def load_scenario(self, scenario, modifiers=[], edits=None, repair=False):
+        """Load a scenario file, like
+        'data/system/us/seattle/scenarios/montlake/weekday.bin'. Optionally
+        transform it with a list of ScenarioModifiers and apply map edits, in
+        the JSON format returned by get_edits. With repair, people whose trips
+        don't work on the map are fixed or removed first.
+
+        Modifiers can also shrink a scenario for quicker runs, like
+        {'SamplePeople': 10} to keep a deterministic 10% of people,
+        {'TimeWindow': [252000000, 360000000]} to keep trips departing between
+        7 and 10am (in 1/10,000ths of a second after midnight), or
+        {'TouchingArea': {'name': 'downtown', 'boundary': [{'longitude': ...,
+        'latitude': ...}, ...]}} to keep trips starting or ending in an area."""
+        self.scenario = scenario
+        self.modifiers = modifiers
+        self.repair = repair
+        return self._post('/sim/load', {
+            'scenario': scenario,
+            'modifiers': modifiers,
+            'edits': edits,
+            'repair': repair,
+        }).text
+
+    def apply_edits(self, edits):
+        """Restart the last loaded scenario with different map edits."""
+        if self.scenario is None:
+            raise ApiError('call load_scenario before apply_edits')
+        return self.load_scenario(self.scenario, self.modifiers, edits, self.repair)
+
+    def validate_scenario(self):
+        """Check the current scenario against the map, without changing
+        anything. Each issue names a person and trip (indices into the
+        scenario) and the problem, like a missing building or a trip that's
+        impossible with its mode."""
+        return self._get('/sim/validate-scenario').json()
+
+    def merge_scenarios(self, scenarios, name):
+        """Overlay scenario files for the same map, like a special event on
+        top of a weekday baseline, and save the result as a new scenario with
+        this name. Returns the people and trips per mode in each input and in
+        the result. Pass the new scenario's path to load_scenario to simulate
+        it."""
+        return self._post('/sim/merge-scenarios', {
+            'scenarios': scenarios,
+            'name': name,
+        }).json()
+
+    def load_blank_map(self, map_path):
+        """Switch to a different map, with nobody in the simulation."""
+        self.scenario = None
+        self.modifiers = []
+        self.repair = False
+        return self._get('/sim/load-blank', map=map_path).text
+
+    def get_time(self):
+        return self._get('/sim/get-time').text
+
+    def goto_time(self, t):
+        """Run the simulation until some time later in the day."""
+        return self._get('/sim/goto-time', t=t).text
+
+    def new_person(self, person):
+        """Start a person with some trips. See ExternalPerson in synthpop for
+        the format."""
+        return self._post('/sim/new-person', person).text
+
+    # Traffic signals
+
+    def get_traffic_signal(self, i):
+        return self._get('/traffic-signals/get', id=i).json()
+
+    def set_traffic_signal(self, ts):
+        """Change a traffic signal, in the format returned by
+        get_traffic_signal. This is recorded as a map edit."""
+        return self._post('/traffic-signals/set', ts).text
+
+    def signal_delays(self, i, t1, t2):
+        """One row per agent that waited at the signal between two times."""
+        resp = self._get('/traffic-signals/get-delays', id=i, t1=t1, t2=t2)
+        rows = []
+        for movement, delays in resp.json()['per_direction']:
+            for delay in delays:
+                row = _flatten_movement(movement)
+                row['delay'] = delay
+                rows.append(row)
+        return rows
+
+    def signal_thruput(self, i):
+        """One row per movement through the signal, with the number of agents
+        that've done it so far."""
+        resp = self._get('/traffic-signals/get-cumulative-thruput', id=i)
+        rows = []
+        for movement, count in resp.json()['per_direction']:
+            row = _flatten_movement(movement)
+            row['count'] = count
+            rows.append(row)
+        return rows
+
+    def all_signal_states(self):
+        """Maps each traffic signal to its current stage and waiting agents."""
+        return self._get('/traffic-signals/get-all-current-state').json()
+
+    # Querying data
+
+    def finished_trips(self):
+        """One row per finished or cancelled trip. Cancelled trips have no
+        duration."""
+        return self._get('/data/get-finished-trips').json()
+
+    def agent_positions(self):
+        """One row per agent currently in the simulation."""
+        rows = []
+        for agent in self._get('/data/get-agent-positions').json()['agents']:
+            row = dict(agent)
+            pos = row.pop('pos')
+            row['longitude'] = pos['longitude']
+            row['latitude'] = pos['latitude']
+            rows.append(row)
+        return rows
+
+    def road_thruput(self):
+        """One row per road, agent type, and hour, counting how many agents
+        crossed the road."""
+        return [{'road': road, 'agent_type': agent_type, 'hour': hour, 'count': count}
+                for road, agent_type, hour, count in self._get('/data/get-road-thruput').json()['counts']]
+
+    def blocked_by_graph(self):
+        return self._get('/data/get-blocked-by-graph').json()
+
+    def explain_delay(self, trip):
+        """The chain of agents blocking an ongoing trip, starting with the
+        trip's own agent. Each step has the agent, what it's doing, how long
+        it's waited, the cause it's waiting for, and the conflicting turn of
+        whoever it's yielding to."""
+        return self._get('/data/explain-delay', id=trip).json()
+
+    def trip_time_lower_bound(self, trip):
+        """The fastest a trip could possibly be, in seconds, ignoring other
+        agents."""
+        return float(self._get('/data/trip-time-lower-bound', id=trip).text)
+
+    def all_trip_time_lower_bounds(self):
+        """Maps each trip ID to trip_time_lower_bound."""
+        resp = self._get('/data/all-trip-time-lower-bounds').json()
+        return {int(trip): dt for trip, dt in resp.items()}
+
+    # The map
+
+    def get_edits(self):
+        """The current map edits, in a format that can be passed to
+        load_scenario or apply_edits."""
+        return self._get('/map/get-edits').json()
+
+    def get_edit_road_command(self, road):
+        """An edit command describing a road as it is now. Change it and add
+        it to the commands in get_edits to modify the road."""
+        return self._get('/map/get-edit-road-command', id=road).json()
+
+    def intersection_geometry(self, i):
+        """GeoJSON for an intersection and its roads, in meters centered
+        around the intersection."""
+        return self._get('/map/get-intersection-geometry', id=i).json()
+
+    def all_geometry(self):
+        """GeoJSON for the entire map."""
+        return self._get('/map/get-all-geometry').json()
+
+    def nearest_road(self, lon, lat, threshold_meters):
+        """The ID of the closest road to a point, raising ApiError if nothing
+        is within the threshold."""
+        return int(self._get('/map/get-nearest-road', lon=lon, lat=lat,
+                             threshold_meters=threshold_meters).text)
+
+
+def _flatten_movement(movement):
+    return {
+        'intersection': movement['parent'],
+        'from_road': movement['from']['road'],
+        'from_dir': movement['from']['dir'],
+        'to_road': movement['to']['road'],
+        'to_dir': movement['to']['dir'],
+        'crosswalk': movement['crosswalk'],
+    }
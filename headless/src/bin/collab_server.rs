@@ -0,0 +1,201 @@
+//! A tiny server letting a few people edit the same proposal at once, like in a workshop. To run:
+//!
+//! > cd headless; cargo run --bin collab_server -- --port=8080
+//!
+//! Then everybody opens the same map in A/B Street, enters edit mode, and picks "join a
+//! collaborative editing session" from the proposal menu, using the same session name.
+//!
+//! The server doesn't understand map edits. Each session is just an ordered log of changes, each
+//! a list of edit commands. A change is rejected if it touches an object that somebody else
+//! changed since that client last pulled. The server also relays where everybody's camera is
+//! looking. Nothing is saved; sessions vanish when the server stops.
+//!
+//! The game's client is in apps/game/src/edit/collab.rs. Keep the JSON types in sync.
+
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate log;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+/// Forget about collaborators who haven't pulled recently
+const PRESENCE_TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static::lazy_static! {
+    static ref SESSIONS: Mutex<BTreeMap<String, Session>> = Mutex::new(BTreeMap::new());
+}
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "collab_server",
+    about = "Sync map edits between several people editing the same proposal"
+)]
+struct Args {
+    /// What port to listen on. Other machines on the network can connect to it.
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(Default)]
+struct Session {
+    changes: Vec<Change>,
+    /// Keyed by client
+    presence: BTreeMap<u64, (Presence, Instant)>,
+}
+
+#[derive(Serialize)]
+struct Change {
+    version: usize,
+    #[serde(skip_serializing)]
+    client: u64,
+    author: String,
+    #[serde(skip_serializing)]
+    touched: Vec<String>,
+    cmds: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct Push {
+    client: u64,
+    author: String,
+    /// The latest version this client has pulled
+    base_version: usize,
+    /// Names of the objects these commands change, used to detect conflicts
+    touched: Vec<String>,
+    cmds: serde_json::Value,
+}
+
+#[derive(Serialize)]
+enum PushResponse {
+    Accepted { version: usize },
+    Conflict { author: String, object: String },
+}
+
+#[derive(Deserialize)]
+struct Pull {
+    client: u64,
+    since: usize,
+    presence: Presence,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Presence {
+    author: String,
+    view: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct PullResponse<'a> {
+    changes: &'a [Change],
+    collaborators: Vec<Presence>,
+}
+
+#[tokio::main]
+async fn main() {
+    abstutil::logger::setup();
+    let args = Args::from_args();
+
+    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], args.port));
+    info!("Listening on http://{}", addr);
+    let serve_future = Server::bind(&addr).serve(hyper::service::make_service_fn(|_| async {
+        Ok::<_, hyper::Error>(hyper::service::service_fn(serve_req))
+    }));
+    if let Err(err) = serve_future.await {
+        panic!("Server error: {}", err);
+    }
+}
+
+async fn serve_req(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+    let path = req.uri().path().to_string();
+    // Url::parse needs an absolute URL
+    let params: HashMap<String, String> =
+        url::Url::parse(&format!("http://localhost{}", req.uri()))
+            .unwrap()
+            .query_pairs()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+    let body = hyper::body::to_bytes(req).await?.to_vec();
+    Ok(
+        match handle_command(&path, &params, &body, &mut SESSIONS.lock().unwrap()) {
+            Ok(resp) => Response::new(Body::from(resp)),
+            Err(err) => {
+                error!("{}: {}", path, err);
+                Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(Body::from(format!("Bad command {}: {}", path, err)))
+                    .unwrap()
+            }
+        },
+    )
+}
+
+fn handle_command(
+    path: &str,
+    params: &HashMap<String, String>,
+    body: &[u8],
+    sessions: &mut BTreeMap<String, Session>,
+) -> Result<String> {
+    let name = params
+        .get("session")
+        .ok_or_else(|| anyhow!("missing GET parameter session"))?;
+    let session = sessions.entry(name.clone()).or_default();
+
+    match path {
+        "/push" => {
+            let push: Push = abstutil::from_json(body)?;
+            for change in &session.changes[push.base_version.min(session.changes.len())..] {
+                if change.client == push.client {
+                    continue;
+                }
+                if let Some(object) = push.touched.iter().find(|x| change.touched.contains(x)) {
+                    info!(
+                        "Rejecting change from {} in {}; {} changed {} first",
+                        push.author, name, change.author, object
+                    );
+                    return Ok(abstutil::to_json(&PushResponse::Conflict {
+                        author: change.author.clone(),
+                        object: object.clone(),
+                    }));
+                }
+            }
+
+            let version = session.changes.len() + 1;
+            info!("{} pushed version {} of {}", push.author, version, name);
+            session.changes.push(Change {
+                version,
+                client: push.client,
+                author: push.author,
+                touched: push.touched,
+                cmds: push.cmds,
+            });
+            Ok(abstutil::to_json(&PushResponse::Accepted { version }))
+        }
+        "/pull" => {
+            let pull: Pull = abstutil::from_json(body)?;
+            let now = Instant::now();
+            session.presence.insert(pull.client, (pull.presence, now));
+            session
+                .presence
+                .retain(|_, (_, last_seen)| now - *last_seen < PRESENCE_TIMEOUT);
+
+            Ok(abstutil::to_json(&PullResponse {
+                changes: &session.changes[pull.since.min(session.changes.len())..],
+                collaborators: session
+                    .presence
+                    .iter()
+                    .filter(|(client, _)| **client != pull.client)
+                    .map(|(_, (presence, _))| presence.clone())
+                    .collect(),
+            }))
+        }
+        _ => bail!("unknown command"),
+    }
+}
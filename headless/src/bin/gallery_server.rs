@@ -0,0 +1,182 @@
+//! A simple backend for the community gallery of proposals. People publish proposals from the
+//! game's edit mode, then anybody on the same map can browse and load them. To run:
+//!
+//! > cd headless; cargo run --bin gallery_server -- --port=8081 --dir=gallery
+//!
+//! Each published proposal is stored as one JSON file in the directory, and kept in memory while
+//! the server runs. The API:
+//!
+//! - POST /v1/publish with a `Submission` as JSON. Returns the new ID.
+//! - GET /v1/list?map=us_seattle_montlake returns a summary of every proposal for a map, newest
+//!   first.
+//! - GET /v1/get?id=3 returns the proposal's `PermanentMapEdits` as JSON.
+//!
+//! The game's client is in apps/game/src/edit/gallery.rs. Keep the JSON types in sync.
+
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate log;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+lazy_static::lazy_static! {
+    static ref DIR: Mutex<String> = Mutex::new(String::new());
+    // Every published proposal, keyed by ID. Publishing holds the lock, so two proposals aren't
+    // given the same ID.
+    static ref GALLERY: Mutex<BTreeMap<usize, Submission>> = Mutex::new(BTreeMap::new());
+}
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "gallery_server",
+    about = "Share proposals in a community gallery"
+)]
+struct Args {
+    /// What port to listen on
+    #[structopt(long)]
+    port: u16,
+    /// Where to store published proposals
+    #[structopt(long)]
+    dir: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Submission {
+    /// From `MapName::as_filename`
+    map: String,
+    title: String,
+    author: String,
+    description: String,
+    /// A small picture of the map with the changes highlighted
+    thumbnail_svg: String,
+    /// The version of A/B Street used to make the proposal
+    version: String,
+    num_commands: usize,
+    /// PermanentMapEdits. Stored without understanding it.
+    edits: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct Summary<'a> {
+    id: usize,
+    title: &'a str,
+    author: &'a str,
+    description: &'a str,
+    thumbnail_svg: &'a str,
+    version: &'a str,
+    num_commands: usize,
+}
+
+#[tokio::main]
+async fn main() {
+    abstutil::logger::setup();
+    let args = Args::from_args();
+    *GALLERY.lock().unwrap() = load_all(&args.dir);
+    *DIR.lock().unwrap() = args.dir;
+
+    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], args.port));
+    info!("Listening on http://{}", addr);
+    let serve_future = Server::bind(&addr).serve(hyper::service::make_service_fn(|_| async {
+        Ok::<_, hyper::Error>(hyper::service::service_fn(serve_req))
+    }));
+    if let Err(err) = serve_future.await {
+        panic!("Server error: {}", err);
+    }
+}
+
+async fn serve_req(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+    let path = req.uri().path().to_string();
+    // Url::parse needs an absolute URL
+    let params: HashMap<String, String> =
+        url::Url::parse(&format!("http://localhost{}", req.uri()))
+            .unwrap()
+            .query_pairs()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+    let body = hyper::body::to_bytes(req).await?.to_vec();
+    info!("Handling {}", path);
+    let dir = DIR.lock().unwrap().clone();
+    Ok(match handle_command(&path, &params, &body, &dir) {
+        Ok(resp) => Response::new(Body::from(resp)),
+        Err(err) => {
+            error!("{}: {}", path, err);
+            Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(format!("Bad command {}: {}", path, err)))
+                .unwrap()
+        }
+    })
+}
+
+fn handle_command(
+    path: &str,
+    params: &HashMap<String, String>,
+    body: &[u8],
+    dir: &str,
+) -> Result<String> {
+    let get = |key: &str| {
+        params
+            .get(key)
+            .ok_or_else(|| anyhow!("missing GET parameter {}", key))
+    };
+
+    match path {
+        "/v1/publish" => {
+            let submission: Submission = abstutil::from_json(body)?;
+            if submission.title.is_empty() {
+                bail!("a proposal needs a title");
+            }
+            let mut gallery = GALLERY.lock().unwrap();
+            let id = gallery.keys().next_back().cloned().unwrap_or(0) + 1;
+            abstio::write_raw(
+                format!("{}/{}.json", dir, id),
+                abstutil::to_json(&submission).as_bytes(),
+            )?;
+            info!("Published #{}: {}", id, submission.title);
+            gallery.insert(id, submission);
+            Ok(id.to_string())
+        }
+        "/v1/list" => {
+            let map = get("map")?;
+            let gallery = GALLERY.lock().unwrap();
+            Ok(abstutil::to_json(
+                &gallery
+                    .iter()
+                    .rev()
+                    .filter(|(_, s)| &s.map == map)
+                    .map(|(id, s)| Summary {
+                        id: *id,
+                        title: &s.title,
+                        author: &s.author,
+                        description: &s.description,
+                        thumbnail_svg: &s.thumbnail_svg,
+                        version: &s.version,
+                        num_commands: s.num_commands,
+                    })
+                    .collect::<Vec<_>>(),
+            ))
+        }
+        "/v1/get" => {
+            let id = get("id")?.parse::<usize>()?;
+            match GALLERY.lock().unwrap().get(&id) {
+                Some(submission) => Ok(submission.edits.to_string()),
+                None => bail!("no proposal #{}", id),
+            }
+        }
+        _ => bail!("unknown command"),
+    }
+}
+
+fn load_all(dir: &str) -> BTreeMap<usize, Submission> {
+    abstio::load_all_objects(dir.to_string())
+        .into_iter()
+        .filter_map(|(name, s)| name.parse::<usize>().ok().map(|id| (id, s)))
+        .collect()
+}
@@ -8,6 +8,9 @@
 //! it's now 01:01:00.0
 //! > curl http://localhost:1234/data/get-road-thruput
 //! ... huge JSON blob
+//!
+//! headless/python has a Python package wrapping this API. Keep it in sync when changing commands;
+//! a test checks every command has a method there.
 
 #[macro_use]
 extern crate anyhow;
@@ -44,6 +47,7 @@ lazy_static::lazy_static! {
             scenario: abstio::path_scenario(&MapName::seattle("montlake"), "weekday"),
             modifiers: Vec::new(),
             edits: None,
+            repair: false,
             rng_seed: SimFlags::RNG_SEED,
             opts: SimOptions::default(),
         }
@@ -153,6 +157,7 @@ fn handle_command(
             load.scenario = args.scenario;
             load.modifiers = args.modifiers;
             load.edits = args.edits;
+            load.repair = args.repair;
 
             // Also reset
             let (new_map, new_sim) = load.setup(&mut Timer::new("reset sim"));
@@ -167,6 +172,37 @@ fn handle_command(
             *sim = Sim::new(&map, SimOptions::default());
             Ok("map changed, blank simulation".to_string())
         }
+        "/sim/validate-scenario" => {
+            let mut timer = Timer::new("validate scenario");
+            // Check what /sim/reset would simulate: the same modifiers with the same RNG seed,
+            // against the current map and edits
+            let scenario: Scenario = abstio::read_object(load.scenario.clone(), &mut timer)?;
+            if &scenario.map_name != map.get_name() {
+                bail!(
+                    "{} is for {}, but {} is loaded",
+                    load.scenario,
+                    scenario.map_name.describe(),
+                    map.get_name().describe()
+                );
+            }
+            let mut rng = XorShiftRng::seed_from_u64(load.rng_seed);
+            let scenario = load.apply_modifiers(scenario, map, &mut rng);
+            Ok(abstutil::to_json(&scenario.validate(map, &mut timer)))
+        }
+        "/sim/merge-scenarios" => {
+            let args: MergeScenarios = abstutil::from_json(body)?;
+            let mut timer = Timer::new("merge scenarios");
+            let mut scenarios = Vec::new();
+            for path in args.scenarios {
+                scenarios.push(abstio::read_object::<Scenario>(path, &mut timer)?);
+            }
+            let (scenario, report) = Scenario::merge(&args.name, scenarios)?;
+            scenario.save();
+            for line in report.summary() {
+                info!("{}", line);
+            }
+            Ok(abstutil::to_json(&report))
+        }
         "/sim/get-time" => Ok(sim.time().to_string()),
         "/sim/goto-time" => {
             let t = Time::parse(get("t")?)?;
@@ -354,6 +390,13 @@ fn handle_command(
                 })
                 .collect(),
         })),
+        "/data/explain-delay" => {
+            let id = TripID(get("id")?.parse::<usize>()?);
+            match sim.trip_to_agent(id).ok() {
+                Some(agent) => Ok(abstutil::to_json(&sim.explain_delay(map, agent))),
+                None => bail!("{} isn't currently happening", id),
+            }
+        }
         "/data/trip-time-lower-bound" => {
             let id = TripID(get("id")?.parse::<usize>()?);
             let duration = sim.get_trip_time_lower_bound(map, id)?;
@@ -492,6 +535,9 @@ struct LoadSim {
     scenario: String,
     modifiers: Vec<ScenarioModifier>,
     edits: Option<PermanentMapEdits>,
+    /// Fix or remove people with problems before simulating. See `Scenario::repair`.
+    #[serde(default)]
+    repair: bool,
     // These are fixed from the initial command line flags
     #[serde(skip_deserializing)]
     rng_seed: u64,
@@ -499,7 +545,27 @@ struct LoadSim {
     opts: SimOptions,
 }
 
+#[derive(Deserialize)]
+struct MergeScenarios {
+    /// Paths to scenario files for the same map. Later ones are overlaid on the first.
+    scenarios: Vec<String>,
+    /// Save the result as a new scenario for that map with this name
+    name: String,
+}
+
 impl LoadSim {
+    fn apply_modifiers(
+        &self,
+        mut scenario: Scenario,
+        map: &Map,
+        rng: &mut XorShiftRng,
+    ) -> Scenario {
+        for m in &self.modifiers {
+            scenario = m.apply(map, scenario, rng);
+        }
+        scenario
+    }
+
     fn setup(&self, timer: &mut Timer) -> (Map, Sim) {
         let mut scenario: Scenario = abstio::must_read_object(self.scenario.clone(), timer);
 
@@ -511,11 +577,17 @@ impl LoadSim {
         }
 
         let mut rng = XorShiftRng::seed_from_u64(self.rng_seed);
-        for m in &self.modifiers {
-            scenario = m.apply(&map, scenario, &mut rng);
+        scenario = self.apply_modifiers(scenario, &map, &mut rng);
+        if self.repair {
+            let (repaired, report) = scenario.repair(&map, timer);
+            for line in report.summary() {
+                info!("{}", line);
+            }
+            scenario = repaired;
         }
 
         let mut sim = Sim::new(&map, self.opts.clone());
+        sim.record_rng_seed(self.rng_seed);
         sim.instantiate(&scenario, &map, &mut rng, timer);
 
         (map, sim)
@@ -558,3 +630,31 @@ fn export_geometry(map: &Map, i: IntersectionID) -> geojson::GeoJson {
 
     geom::geometries_with_properties_to_geojson(pairs)
 }
+
+#[cfg(test)]
+mod tests {
+    /// Every command handled above should have a method in the Python client.
+    #[test]
+    fn test_python_client_covers_all_commands() {
+        let client = include_str!("../python/abstreet_headless/client.py");
+        let mut commands = Vec::new();
+        for line in include_str!("main.rs").lines() {
+            // Match arms in handle_command look like `        "/sim/reset" => ...`
+            let line = line.trim_start();
+            if let Some(rest) = line.strip_prefix("\"/") {
+                if let Some((cmd, arm)) = rest.split_once('"') {
+                    if arm.trim_start().starts_with("=>") {
+                        commands.push(format!("/{}", cmd));
+                    }
+                }
+            }
+        }
+        assert!(commands.len() > 20, "only found {:?}", commands);
+
+        let missing: Vec<&String> = commands
+            .iter()
+            .filter(|cmd| !client.contains(&format!("'{}'", cmd)))
+            .collect();
+        assert!(missing.is_empty(), "client.py doesn't call {:?}", missing);
+    }
+}
@@ -7,6 +7,9 @@ pub struct ImporterConfiguration {
     pub unzip: String,
     pub gunzip: String,
     pub gunzip_args: String,
+    /// Download OSM data for each map from the Overpass API, instead of clipping a Geofabrik
+    /// extract with osmium
+    pub use_overpass: bool,
 }
 
 impl Default for ImporterConfiguration {
@@ -16,6 +19,7 @@ impl Default for ImporterConfiguration {
             unzip: String::from("unzip"),
             gunzip: String::from("gunzip"),
             gunzip_args: String::from(""),
+            use_overpass: false,
         }
     }
 }
@@ -9,11 +9,14 @@ extern crate anyhow;
 #[macro_use]
 extern crate log;
 
+use anyhow::Result;
 use structopt::StructOpt;
 
 use abstio::{CityName, MapName};
 use abstutil::Timer;
+use geom::LonLat;
 use map_model::RawToMapOptions;
+use raw_map::RawMap;
 
 use self::configuration::{load_configuration, ImporterConfiguration};
 pub use self::pick_geofabrik::pick_geofabrik;
@@ -49,15 +52,13 @@ pub async fn regenerate_everything(shard_num: usize, num_shards: usize) {
 pub async fn oneshot(
     osm_path: String,
     clip: Option<String>,
-    filter_crosswalks: bool,
+    options: convert_osm::Options,
     create_uk_travel_demand_model: bool,
     opts: RawToMapOptions,
 ) {
     let mut timer = abstutil::Timer::new("oneshot");
     println!("- Running convert_osm on {}", osm_path);
     let name = abstutil::basename(&osm_path);
-    let mut options = convert_osm::Options::default();
-    options.filter_crosswalks = filter_crosswalks;
     let raw = convert_osm::convert(
         osm_path,
         MapName::new("zz", "oneshot", &name),
@@ -83,6 +84,44 @@ pub async fn oneshot(
     println!("{} has been created", map.get_name().path());
 }
 
+/// Combines two adjacent maps into one, merging the border intersections along their shared edge.
+/// Both inputs are paths to RawMaps.
+pub fn stitch_maps(raw1: String, raw2: String, name: MapName, opts: RawToMapOptions) -> Result<()> {
+    let mut timer = abstutil::Timer::new("stitch maps");
+    let map1 = abstio::read_binary::<RawMap>(raw1, &mut timer);
+    let map2 = abstio::read_binary::<RawMap>(raw2, &mut timer);
+    let raw = convert_osm::stitch(name, map1, map2, &mut timer)?;
+    raw.save();
+    let map = map_model::Map::create_from_raw(raw, opts, &mut timer);
+    timer.start("save map");
+    map.save();
+    timer.stop("save map");
+
+    println!("{} has been created", map.get_name().path());
+    Ok(())
+}
+
+/// Cuts a smaller map out of a RawMap, using a GeoJSON file with one boundary polygon.
+pub fn extract_region(
+    raw_map: String,
+    clip_path: String,
+    name: MapName,
+    opts: RawToMapOptions,
+) -> Result<()> {
+    let mut timer = abstutil::Timer::new("extract region");
+    let map = abstio::read_binary::<RawMap>(raw_map, &mut timer);
+    let boundary = LonLat::read_geojson_polygon(&clip_path)?;
+    let raw = convert_osm::extract_region(name, map, boundary, &mut timer)?;
+    raw.save();
+    let map = map_model::Map::create_from_raw(raw, opts, &mut timer);
+    timer.start("save map");
+    map.save();
+    timer.stop("save map");
+
+    println!("{} has been created", map.get_name().path());
+    Ok(())
+}
+
 /// A specification for importing all maps in a single city.
 #[derive(StructOpt)]
 pub struct Job {
@@ -100,6 +139,10 @@ pub struct Job {
     /// Produce a city overview from all of the individual maps in a city.
     #[structopt(long)]
     pub city_overview: bool,
+    /// Download OSM data for each map from the Overpass API, instead of clipping a Geofabrik
+    /// extract with osmium. Responses are cached in data/input.
+    #[structopt(long)]
+    pub use_overpass: bool,
 
     /// Only process one map. If not specified, process all maps defined by clipping polygons in
     /// importer/config/$city/.
@@ -118,6 +161,7 @@ impl Job {
             raw_to_map: true,
             scenario: false,
             city_overview: false,
+            use_overpass: false,
             only_map: None,
             opts: RawToMapOptions::default(),
         };
@@ -159,6 +203,9 @@ impl Job {
         if self.city_overview {
             flags.push("--city-overview".to_string());
         }
+        if self.use_overpass {
+            flags.push("--use-overpass".to_string());
+        }
         if let Some(ref name) = self.only_map {
             flags.push(name.clone());
         }
@@ -173,7 +220,8 @@ impl Job {
             std::process::exit(1);
         }
 
-        let config: ImporterConfiguration = load_configuration();
+        let mut config: ImporterConfiguration = load_configuration();
+        config.use_overpass |= self.use_overpass;
 
         timer.start(format!("import {}", self.city.describe()));
         let names = if let Some(n) = self.only_map {
@@ -213,7 +261,13 @@ impl Job {
             if self.osm_to_raw
                 && (!built_raw_huge_seattle || name != MapName::seattle("huge_seattle"))
             {
-                let raw = utils::osm_to_raw(name.clone(), timer, &config).await;
+                let raw = match utils::osm_to_raw(name.clone(), timer, &config).await {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        println!("Can't import {}: {:#}", name.describe(), err);
+                        std::process::exit(1);
+                    }
+                };
 
                 // The collision data will only cover one part of London, since we don't have a
                 // region-wide map there yet
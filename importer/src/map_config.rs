@@ -1,9 +1,12 @@
 use std::collections::BTreeSet;
 
+use anyhow::{Context, Result};
+
 use abstio::{CityName, MapName};
 use abstutil::Timer;
 use geom::Distance;
 use map_model::DrivingSide;
+use raw_map::RegionProfile;
 
 /// Given the name of a map, configure its import.
 ///
@@ -11,7 +14,7 @@ use map_model::DrivingSide;
 /// places used default values that were copied around.
 // Slightly more verbose logic feels easier to read
 #[allow(clippy::match_like_matches_macro)]
-pub fn config_for_map(name: &MapName) -> convert_osm::Options {
+pub fn config_for_map(name: &MapName) -> Result<convert_osm::Options> {
     // Some maps have extra procedurally generated houses. Just see if a file in a canonical
     // location exists.
     let procgen_houses = name.city.input_path("procgen_houses.json");
@@ -20,13 +23,22 @@ pub fn config_for_map(name: &MapName) -> convert_osm::Options {
     } else {
         None
     };
+    // Likewise, any GeoTIFF or SRTM files in this directory provide elevation
+    let dems: Vec<String> = abstio::list_dir(name.city.input_path("elevation"))
+        .into_iter()
+        .filter(|path| {
+            let path = path.to_lowercase();
+            path.ends_with(".tif") || path.ends_with(".tiff") || path.ends_with(".hgt")
+        })
+        .collect();
 
-    convert_osm::Options {
+    Ok(convert_osm::Options {
         map_config: osm2streets::MapConfig {
             // osm2streets will set this anyway, it doesn't matter here
             driving_side: DrivingSide::Right,
             bikes_can_use_bus_lanes: name.city.country != "pl",
-            inferred_sidewalks: name.city.country != "pl",
+            // The regional profile may override this
+            inferred_sidewalks: true,
             street_parking_spot_length: if name.city == CityName::new("ca", "montreal") {
                 Distance::meters(6.5)
             } else {
@@ -67,6 +79,8 @@ pub fn config_for_map(name: &MapName) -> convert_osm::Options {
             osm2lanes: false,
         },
         filter_crosswalks: false,
+        include_driveways: false,
+        snap_cycleways: false,
         onstreet_parking: match name.city.city.as_ref() {
             "seattle" => {
                 convert_osm::OnstreetParking::Blockface(name.city.input_path("blockface.bin"))
@@ -104,7 +118,31 @@ pub fn config_for_map(name: &MapName) -> convert_osm::Options {
         } else {
             None
         },
-        // We only have a few elevation sources working
-        elevation: name.city == CityName::new("us", "seattle") || name.city.country == "gb",
+        elevation: if !dems.is_empty() {
+            convert_osm::Elevation::Dem(dems)
+        } else if name.city == CityName::new("us", "seattle") || name.city.country == "gb" {
+            convert_osm::Elevation::ElevationLookups
+        } else {
+            convert_osm::Elevation::None
+        },
+        profile: load_profile(&name.city)?,
+    })
+}
+
+/// Regional defaults come from `importer/profiles/{country}.json`, with
+/// `importer/profiles/{country}/{city}.json` overriding parts of that.
+fn load_profile(city: &CityName) -> Result<RegionProfile> {
+    let mut profile = RegionProfile::default();
+    for path in [
+        format!("importer/profiles/{}.json", city.country),
+        format!("importer/profiles/{}/{}.json", city.country, city.city),
+    ] {
+        if abstio::file_exists(&path) {
+            let specific =
+                abstio::maybe_read_json::<RegionProfile>(path.clone(), &mut Timer::throwaway())
+                    .with_context(|| format!("Couldn't load the regional profile {}", path))?;
+            profile = profile.overlay(specific);
+        }
     }
+    Ok(profile)
 }
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use abstutil::{prettyprint_usize, MultiMap, Timer};
-use geom::PolyLine;
+use geom::{Duration, PolyLine};
 use map_model::{osm, BuildingID, Map, Path, PathConstraints, PathRequest, PathStep};
 use synthpop::{
     IndividTrip, MapBorder, MapBorders, OrigPersonID, PersonSpec, Scenario, TripEndpoint, TripMode,
@@ -14,6 +14,8 @@ struct Trip {
     from: TripEndpoint,
     to: TripEndpoint,
     orig: OrigTrip,
+    /// When the trip starts off-map, how long it takes to reach the border
+    time_to_enter: Duration,
 }
 
 /// Transform the Seattle-wide `Endpoints` into specific `TripEndpoints` for this map. When the
@@ -75,9 +77,9 @@ fn endpoints(
         }
     }
 
-    // TODO When the trip begins or ends at a border, it'd be nice to fix depart_at, trip_time, and
-    // trip_dist. Assume constant speed through the trip. But when I last tried this, the distance
-    // was way off. :\
+    // TODO When the trip begins or ends at a border, it'd be nice to fix trip_time and trip_dist.
+    // Assume constant speed through the trip. But when I last tried this, the distance was way
+    // off. :\ (clip_trips does delay depart_at by an estimate of the time to reach the border.)
 
     let snapper = BorderSnapper::new(from, to, constraints, maybe_huge_map)
         .unwrap_or(BorderSnapper { path: None });
@@ -208,10 +210,27 @@ fn clip_trips(
                 maybe_huge_map.as_ref(),
                 only_passthrough_trips,
             )?;
+            // The route outside the map isn't known, so estimate the time to reach the border
+            let time_to_enter = match from {
+                TripEndpoint::Border(i) => borders
+                    .for_mode(orig.mode)
+                    .0
+                    .iter()
+                    .find(|border| border.i == i)
+                    .map(|border| {
+                        border.off_map_travel_time(
+                            orig.from.pos.to_pt(map.get_gps_bounds()),
+                            orig.mode,
+                        )
+                    })
+                    .unwrap_or(Duration::ZERO),
+                _ => Duration::ZERO,
+            };
             Some(Trip {
                 from,
                 to,
                 orig: orig.clone(),
+                time_to_enter,
             })
         });
     let trips: Vec<Trip> = maybe_results.into_iter().flatten().collect();
@@ -234,18 +253,22 @@ pub fn make_scenario(
 ) -> Scenario {
     let only_passthrough_trips = scenario_name == "passthrough";
 
-    let mut individ_trips: Vec<Option<IndividTrip>> = Vec::new();
+    // Trips starting off-map also have the time needed to reach the border
+    let mut individ_trips: Vec<Option<(IndividTrip, Duration)>> = Vec::new();
     // person -> (trip seq, index into individ_trips)
     let mut trips_per_person: MultiMap<OrigPersonID, ((usize, bool, usize), usize)> =
         MultiMap::new();
     for trip in clip_trips(map, popdat, huge_map, only_passthrough_trips, timer) {
         let idx = individ_trips.len();
-        individ_trips.push(Some(IndividTrip::new(
-            trip.orig.depart_at,
-            trip.orig.purpose,
-            trip.from,
-            trip.to,
-            trip.orig.mode,
+        individ_trips.push(Some((
+            IndividTrip::new(
+                trip.orig.depart_at,
+                trip.orig.purpose,
+                trip.from,
+                trip.to,
+                trip.orig.mode,
+            ),
+            trip.time_to_enter,
         )));
         trips_per_person.insert(trip.orig.person, (trip.orig.seq, idx));
     }
@@ -263,7 +286,16 @@ pub fn make_scenario(
         }
         // Actually, the sequence in the Soundcast dataset crosses midnight. Don't do that; sort by
         // departure time starting with midnight.
-        trips.sort_by_key(|t| t.depart);
+        trips.sort_by_key(|(t, _)| t.depart);
+        // Only then delay trips by the time needed to reach the map. If that puts a trip after
+        // the person's next one, remove_weird_schedules drops them.
+        let trips: Vec<IndividTrip> = trips
+            .into_iter()
+            .map(|(mut t, time_to_enter)| {
+                t.depart += time_to_enter;
+                t
+            })
+            .collect();
         // Sanity check that endpoints match up
         for pair in trips.windows(2) {
             let destination = &pair[0].destination;
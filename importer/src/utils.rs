@@ -1,8 +1,11 @@
 use std::path::Path;
 use std::process::Command;
 
+use anyhow::Result;
+
 use abstio::{CityName, MapName};
 use abstutil::{must_run_cmd, Timer};
+use geom::LonLat;
 use map_model::RawToMapOptions;
 use raw_map::RawMap;
 
@@ -116,11 +119,12 @@ pub async fn osm_to_raw(
     name: MapName,
     timer: &mut abstutil::Timer<'_>,
     config: &ImporterConfiguration,
-) -> RawMap {
+) -> Result<RawMap> {
+    // Check the configuration before downloading anything
+    let opts = crate::map_config::config_for_map(&name)?;
     if name.city == CityName::seattle() {
         crate::seattle::input(config, timer).await;
     }
-    let opts = crate::map_config::config_for_map(&name);
     if let Some(ref url) = opts.gtfs_url {
         download(config, name.city.input_path("gtfs/"), url).await;
     }
@@ -129,27 +133,36 @@ pub async fn osm_to_raw(
         "importer/config/{}/{}/{}.geojson",
         name.city.country, name.city.city, name.map
     );
-    let osm_url = crate::pick_geofabrik(boundary_polygon.clone())
+    if config.use_overpass {
+        convert_osm::overpass::download(
+            &LonLat::read_geojson_polygon(&boundary_polygon).unwrap(),
+            &name.city.input_path(format!("osm/{}.osm", name.map)),
+        )
         .await
         .unwrap();
-
-    let local_osm_file = name.city.input_path(format!(
-        "osm/{}",
-        std::path::Path::new(&osm_url)
-            .file_name()
-            .unwrap()
-            .to_os_string()
-            .into_string()
-            .unwrap()
-    ));
-    download(config, local_osm_file.clone(), &osm_url).await;
-
-    osmium(
-        local_osm_file,
-        boundary_polygon.clone(),
-        name.city.input_path(format!("osm/{}.osm", name.map)),
-        config,
-    );
+    } else {
+        let osm_url = crate::pick_geofabrik(boundary_polygon.clone())
+            .await
+            .unwrap();
+
+        let local_osm_file = name.city.input_path(format!(
+            "osm/{}",
+            std::path::Path::new(&osm_url)
+                .file_name()
+                .unwrap()
+                .to_os_string()
+                .into_string()
+                .unwrap()
+        ));
+        download(config, local_osm_file.clone(), &osm_url).await;
+
+        osmium(
+            local_osm_file,
+            boundary_polygon.clone(),
+            name.city.input_path(format!("osm/{}.osm", name.map)),
+            config,
+        );
+    }
 
     let map = convert_osm::convert(
         name.city.input_path(format!("osm/{}.osm", name.map)),
@@ -159,7 +172,7 @@ pub async fn osm_to_raw(
         timer,
     );
     map.save();
-    map
+    Ok(map)
 }
 
 /// Converts a RawMap to a Map.
@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 
 use map_model::osm::RoadRank;
 use map_model::{LaneType, Map};
-use widgetry::tools::ColorScale;
+use widgetry::tools::{ColorScale, DivergingScale};
 use widgetry::{Choice, Color, EventCtx, Fill, Style, Texture};
 
 use crate::tools::loading_tips;
@@ -61,6 +61,28 @@ impl ColorSchemeChoice {
     }
 }
 
+/// Colors for layers that measure something or compare before and after. Independent of the
+/// `ColorSchemeChoice`.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum LayerPalette {
+    /// Red means worse, green means better
+    Default,
+    /// Safe for red-green colorblindness
+    BlueOrange,
+    /// Safe for all common types of colorblindness
+    BrownTeal,
+}
+
+impl LayerPalette {
+    pub fn choices() -> Vec<Choice<LayerPalette>> {
+        vec![
+            Choice::new("red / green", LayerPalette::Default),
+            Choice::new("blue / orange (colorblind-safe)", LayerPalette::BlueOrange),
+            Choice::new("brown / teal (colorblind-safe)", LayerPalette::BrownTeal),
+        ]
+    }
+}
+
 pub struct ColorScheme {
     scheme: ColorSchemeChoice,
 
@@ -77,6 +99,7 @@ pub struct ColorScheme {
     pub current_object: Color,
     pub perma_selected_object: Color,
     pub fade_map_dark: Color,
+    pub alert_warning: Color,
     gui_style: Style,
     pub minimap_cursor_border: Color,
     pub minimap_cursor_bg: Option<Color>,
@@ -91,6 +114,9 @@ pub struct ColorScheme {
     pub general_road_marking: Color,
     road_center_line: Color,
     pub light_rail_track: Color,
+    /// In the 3D camera angles
+    pub bridge_side: Color,
+    pub bridge_pillar: Color,
     pub private_road: Option<Color>,
     pub unzoomed_highway: Color,
     pub unzoomed_arterial: Color,
@@ -110,6 +136,12 @@ pub struct ColorScheme {
     pub signal_box: Color,
     pub signal_spinner: Color,
     pub signal_turn_block_bg: Color,
+    pub signal_head_housing: Color,
+    pub signal_light_green: Color,
+    pub signal_light_yellow: Color,
+    pub signal_light_red: Color,
+    pub signal_light_off: Color,
+    pub signal_ped_countdown: Color,
 
     // Problems encountered on a trip
     pub slowest_intersection: Color,
@@ -147,11 +179,20 @@ pub struct ColorScheme {
     pub ped_preparing_bike_body: Color,
     pub ped_crowd: Color,
     pub bike_frame: Color,
+    /// Each bike gets one of these frame colors
+    pub bike_frames: Vec<Color>,
+    pub vehicle_glass: Color,
+    pub van_roof: Color,
+    pub pickup_bed: Color,
     pub parked_car: Color,
 
-    // Layers
+    // Layers. The names of these describe the default palette; see `set_layer_palette`.
     pub good_to_bad_red: ColorScale,
     pub good_to_bad_green: ColorScale,
+    pub better_than_before: Color,
+    pub worse_than_before: Color,
+    /// Draw categories in layers with different patterns, not just different colors
+    pub layer_patterns: bool,
     pub bus_layer: Color,
     pub edits_layer: Color,
 
@@ -201,6 +242,7 @@ impl ColorScheme {
             current_object: Color::WHITE,
             perma_selected_object: Color::BLUE,
             fade_map_dark: Color::BLACK.alpha(0.6),
+            alert_warning: Color::ORANGE,
             minimap_cursor_border: Color::BLACK,
             minimap_cursor_bg: None,
             gui_style,
@@ -215,6 +257,8 @@ impl ColorScheme {
             general_road_marking: Color::WHITE,
             road_center_line: Color::YELLOW,
             light_rail_track: hex("#844204"),
+            bridge_side: hex("#BBBEC3"),
+            bridge_pillar: hex("#7E8187"),
             private_road: Some(hex("#F0B0C0")),
             unzoomed_highway: hex("#E892A2"),
             unzoomed_arterial: hex("#FFC73E"),
@@ -235,6 +279,12 @@ impl ColorScheme {
             signal_box: Color::grey(0.5),
             signal_spinner: hex("#F2994A"),
             signal_turn_block_bg: Color::grey(0.6),
+            signal_head_housing: Color::BLACK,
+            signal_light_green: hex("#3CCB5A"),
+            signal_light_yellow: hex("#FFC83D"),
+            signal_light_red: hex("#E8402F"),
+            signal_light_off: hex("#4D4D4D"),
+            signal_ped_countdown: Color::ORANGE,
 
             // Problems encountered on a trip
             slowest_intersection: Color::RED,
@@ -278,11 +328,25 @@ impl ColorScheme {
             ped_preparing_bike_body: Color::rgb(255, 0, 144),
             ped_crowd: Color::rgb_f(0.2, 0.7, 0.7),
             bike_frame: hex("#AAA9AD"),
+            bike_frames: vec![
+                hex("#313131"),
+                hex("#C0392B"),
+                hex("#2471A3"),
+                hex("#1E8449"),
+                hex("#D4AC0D"),
+                hex("#E5E7E9"),
+            ],
+            vehicle_glass: hex("#2B3A4A").alpha(0.8),
+            van_roof: Color::WHITE.alpha(0.25),
+            pickup_bed: Color::BLACK.alpha(0.35),
             parked_car: hex("#938E85"),
 
             // Layers
             good_to_bad_red: ColorScale(vec![hex("#F19A93"), hex("#A32015")]),
             good_to_bad_green: ColorScale(vec![hex("#BEDB92"), hex("#397A4C")]),
+            better_than_before: hex("#5D9630"),
+            worse_than_before: hex("#A32015"),
+            layer_patterns: false,
             bus_layer: hex("#4CA7E9"),
             edits_layer: hex("#12409D"),
 
@@ -395,6 +459,42 @@ impl ColorScheme {
 }
 
 impl ColorScheme {
+    /// Switch the colors used by layers, without affecting anything else. The color scheme's
+    /// constructor always starts with the default palette.
+    pub fn set_layer_palette(&mut self, palette: LayerPalette, patterns: bool) {
+        self.layer_patterns = patterns;
+        match palette {
+            LayerPalette::Default => {
+                self.good_to_bad_red = ColorScale(vec![hex("#F19A93"), hex("#A32015")]);
+                self.good_to_bad_green = ColorScale(vec![hex("#BEDB92"), hex("#397A4C")]);
+                self.better_than_before = hex("#5D9630");
+                self.worse_than_before = hex("#A32015");
+            }
+            // From ColorBrewer's PuOr and BrBG, which avoid relying on red versus green
+            LayerPalette::BlueOrange => {
+                self.good_to_bad_red = ColorScale(vec![hex("#FDD0A2"), hex("#B35806")]);
+                self.good_to_bad_green = ColorScale(vec![hex("#C6DBEF"), hex("#2166AC")]);
+                self.better_than_before = hex("#2166AC");
+                self.worse_than_before = hex("#B35806");
+            }
+            LayerPalette::BrownTeal => {
+                self.good_to_bad_red = ColorScale(vec![hex("#F6E8C3"), hex("#8C510A")]);
+                self.good_to_bad_green = ColorScale(vec![hex("#C7EAE5"), hex("#01665E")]);
+                self.better_than_before = hex("#01665E");
+                self.worse_than_before = hex("#8C510A");
+            }
+        }
+    }
+
+    /// For comparing before and after, with improvements on the low end
+    pub fn better_to_worse(&self) -> DivergingScale {
+        DivergingScale::new(
+            self.better_than_before,
+            Color::WHITE,
+            self.worse_than_before,
+        )
+    }
+
     pub fn rotating_color_plot(&self, idx: usize) -> Color {
         modulo_color(
             &[
@@ -65,6 +65,8 @@ pub trait AppLike {
         }
         self.mut_opts().color_scheme = cs;
         *self.mut_cs() = ColorScheme::new(ctx, self.opts().color_scheme);
+        let (palette, patterns) = (self.opts().layer_palette, self.opts().layer_patterns);
+        self.mut_cs().set_layer_palette(palette, patterns);
 
         ctx.loading_screen("rerendering map colors", |ctx, timer| {
             *self.mut_draw_map() = DrawMap::new(ctx, self.map(), self.opts(), self.cs(), timer);
@@ -1,13 +1,13 @@
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Duration, UnitFmt};
+use geom::{Angle, Distance, Duration, UnitFmt};
 use widgetry::{
     CanvasSettings, Choice, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, Spinner, State,
     TextExt, Toggle, Widget,
 };
 
-use crate::colors::ColorSchemeChoice;
+use crate::colors::{ColorSchemeChoice, LayerPalette};
 use crate::render::DrawBuilding;
 use crate::tools::grey_out_map;
 use crate::AppLike;
@@ -30,6 +30,17 @@ pub struct Options {
     pub color_scheme: ColorSchemeChoice,
     /// Automatically change color_scheme based on simulation time to reflect day/night
     pub toggle_day_night_colors: bool,
+    /// Darken the map at night, with lit windows and street lights, based on simulation time
+    pub day_night_lighting: bool,
+    /// Colors for layers, which may need to avoid relying on red versus green
+    pub layer_palette: LayerPalette,
+    /// Draw categories in layers with different patterns, not just different colors
+    pub layer_patterns: bool,
+    /// Draw a scale bar and north arrow over the map
+    pub show_scale_bar: bool,
+    /// After hovering on something in the map this long, show a tooltip summarizing it. None
+    /// disables tooltips.
+    pub hover_tooltip_delay: Option<Duration>,
     /// Draw buildings in different perspectives
     pub camera_angle: CameraAngle,
     /// Draw building driveways.
@@ -61,6 +72,8 @@ pub struct Options {
     pub language: Option<String>,
     /// How to render geometric units
     pub units: UnitFmt,
+    /// How loud sound effects and ambient audio are
+    pub volume: AudioMixer,
 }
 
 impl Options {
@@ -86,6 +99,11 @@ impl Options {
             traffic_signal_style: TrafficSignalStyle::Brian,
             color_scheme: ColorSchemeChoice::DayMode,
             toggle_day_night_colors: false,
+            day_night_lighting: false,
+            layer_palette: LayerPalette::Default,
+            layer_patterns: false,
+            show_scale_bar: false,
+            hover_tooltip_delay: Some(Duration::seconds(0.8)),
             camera_angle: CameraAngle::TopDown,
             show_building_driveways: true,
             show_stop_signs: true,
@@ -105,6 +123,12 @@ impl Options {
                 // TODO Should default be based on the map?
                 metric: false,
             },
+            volume: AudioMixer {
+                // Browsers frown on pages that suddenly make noise
+                master: if cfg!(target_arch = "wasm32") { 0 } else { 50 },
+                ambient: 100,
+                effects: 100,
+            },
         }
     }
 }
@@ -115,6 +139,8 @@ pub enum TrafficSignalStyle {
     Brian,
     Yuwen,
     IndividualTurnArrows,
+    /// A signal head for each approach and crosswalk, showing the light for every movement
+    SignalHeads,
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -124,9 +150,71 @@ pub enum CameraAngle {
     IsometricNW,
     IsometricSE,
     IsometricSW,
+    /// Looking north at an angle, so the map is foreshortened and buildings and bridges rise up
+    /// the screen
+    Tilted,
     Abstract,
 }
 
+/// How far the tilted view leans away from looking straight down
+const TILT_DEGREES: f64 = 45.0;
+
+impl CameraAngle {
+    /// How much the map is squashed vertically on the screen. See `Canvas::vertical_scale`.
+    pub fn vertical_scale(&self) -> f64 {
+        match self {
+            CameraAngle::Tilted => TILT_DEGREES.to_radians().cos(),
+            _ => 1.0,
+        }
+    }
+
+    /// In the pseudo-3D views, how far along `extrusion_angle` to shift something this high
+    /// above the ground. The tilted view squashes the map vertically, so heights have to be
+    /// stretched to look right.
+    pub fn extrusion_length(&self, height: Distance) -> Distance {
+        match self {
+            CameraAngle::Tilted => height * TILT_DEGREES.to_radians().tan(),
+            _ => height,
+        }
+    }
+
+    /// In the pseudo-3D views, things above the ground are drawn shifted this way on the screen.
+    pub fn extrusion_angle(&self) -> Option<Angle> {
+        match self {
+            CameraAngle::TopDown | CameraAngle::Abstract => None,
+            CameraAngle::IsometricNE => Some(Angle::degrees(-45.0)),
+            CameraAngle::IsometricNW => Some(Angle::degrees(-135.0)),
+            CameraAngle::IsometricSE => Some(Angle::degrees(45.0)),
+            CameraAngle::IsometricSW => Some(Angle::degrees(135.0)),
+            CameraAngle::Tilted => Some(Angle::degrees(-90.0)),
+        }
+    }
+}
+
+/// Volume levels, each a percent. Nothing plays when `master` is 0.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct AudioMixer {
+    pub master: usize,
+    /// The hum of nearby traffic
+    pub ambient: usize,
+    /// Horns, UI clicks, and other short sounds
+    pub effects: usize,
+}
+
+impl AudioMixer {
+    pub fn is_muted(&self) -> bool {
+        self.master == 0
+    }
+
+    pub fn ambient_volume(&self) -> f32 {
+        (self.master * self.ambient) as f32 / 10_000.0
+    }
+
+    pub fn effects_volume(&self) -> f32 {
+        (self.master * self.effects) as f32 / 10_000.0
+    }
+}
+
 pub struct OptionsPanel {
     panel: Panel,
 }
@@ -200,6 +288,7 @@ impl OptionsPanel {
                             vec![
                                 Choice::new("Default (Brian's style)", TrafficSignalStyle::Brian),
                                 Choice::new("Yuwen's style", TrafficSignalStyle::Yuwen),
+                                Choice::new("Signal heads", TrafficSignalStyle::SignalHeads),
                                 Choice::new(
                                     "arrows showing individual turns (to debug)",
                                     TrafficSignalStyle::IndividualTurnArrows,
@@ -219,6 +308,7 @@ impl OptionsPanel {
                                 Choice::new("Isometric (northwest)", CameraAngle::IsometricNW),
                                 Choice::new("Isometric (southeast)", CameraAngle::IsometricSE),
                                 Choice::new("Isometric (southwest)", CameraAngle::IsometricSW),
+                                Choice::new("Tilted 3D", CameraAngle::Tilted),
                                 Choice::new("Abstract (just symbols)", CameraAngle::Abstract),
                             ],
                         ),
@@ -232,6 +322,47 @@ impl OptionsPanel {
                             ColorSchemeChoice::choices(),
                         ),
                     ]),
+                    Widget::row(vec![
+                        "Colors for layers:".text_widget(ctx),
+                        Widget::dropdown(
+                            ctx,
+                            "layer palette",
+                            app.opts().layer_palette,
+                            LayerPalette::choices(),
+                        ),
+                    ]),
+                    Toggle::checkbox(
+                        ctx,
+                        "Light the map based on the time of day",
+                        None,
+                        app.opts().day_night_lighting,
+                    ),
+                    Toggle::checkbox(
+                        ctx,
+                        "Use patterns to distinguish categories in layers",
+                        None,
+                        app.opts().layer_patterns,
+                    ),
+                    Toggle::checkbox(
+                        ctx,
+                        "Show a scale bar and north arrow",
+                        None,
+                        app.opts().show_scale_bar,
+                    ),
+                    Widget::row(vec![
+                        "Tooltips when hovering on the map:".text_widget(ctx),
+                        Widget::dropdown(
+                            ctx,
+                            "hover tooltips",
+                            app.opts().hover_tooltip_delay,
+                            vec![
+                                Choice::new("never", None),
+                                Choice::new("quickly", Some(Duration::seconds(0.3))),
+                                Choice::new("after a moment", Some(Duration::seconds(0.8))),
+                                Choice::new("after a long pause", Some(Duration::seconds(2.0))),
+                            ],
+                        ),
+                    ]),
                     Widget::row(vec![
                         "Camera zoom to switch to unzoomed view".text_widget(ctx),
                         Widget::dropdown(
@@ -276,6 +407,14 @@ impl OptionsPanel {
                 ])
                 .bg(app.cs().inner_panel_bg)
                 .padding(8),
+                "Audio".text_widget(ctx),
+                Widget::col(vec![
+                    volume_spinner(ctx, "Master volume", app.opts().volume.master),
+                    volume_spinner(ctx, "Traffic noise", app.opts().volume.ambient),
+                    volume_spinner(ctx, "Sound effects", app.opts().volume.effects),
+                ])
+                .bg(app.cs().inner_panel_bg)
+                .padding(8),
                 "Debug".text_widget(ctx),
                 Widget::col(vec![
                     Toggle::checkbox(ctx, "Enable developer mode", None, app.opts().dev),
@@ -343,6 +482,10 @@ impl<A: AppLike> State<A> for OptionsPanel {
                     let camera_angle = self.panel.dropdown_value("Camera angle");
                     if opts.camera_angle != camera_angle {
                         opts.camera_angle = camera_angle;
+                        // Keep looking at the same place
+                        let center = ctx.canvas.center_to_map_pt();
+                        ctx.canvas.vertical_scale = opts.camera_angle.vertical_scale();
+                        ctx.canvas.center_on_map_pt(center);
                         ctx.loading_screen("rerendering buildings", |ctx, timer| {
                             let mut all_buildings = GeomBatch::new();
                             let mut all_building_outlines = GeomBatch::new();
@@ -379,7 +522,25 @@ impl<A: AppLike> State<A> for OptionsPanel {
                         opts.toggle_day_night_colors = false;
                     }
 
+                    // Changing the color scheme resets the layer palette, so do this after
+                    opts.layer_palette = self.panel.dropdown_value("layer palette");
+                    opts.layer_patterns = self
+                        .panel
+                        .is_checked("Use patterns to distinguish categories in layers");
+                    app.mut_cs()
+                        .set_layer_palette(opts.layer_palette, opts.layer_patterns);
+                    opts.show_scale_bar = self.panel.is_checked("Show a scale bar and north arrow");
+                    opts.day_night_lighting = self
+                        .panel
+                        .is_checked("Light the map based on the time of day");
+                    opts.hover_tooltip_delay = self.panel.dropdown_value("hover tooltips");
+
                     opts.units.metric = self.panel.is_checked("metric / imperial units");
+                    opts.volume = AudioMixer {
+                        master: self.panel.spinner("Master volume"),
+                        ambient: self.panel.spinner("Traffic noise"),
+                        effects: self.panel.spinner("Sound effects"),
+                    };
 
                     let language = self.panel.dropdown_value("language");
                     if language != opts.language {
@@ -410,3 +571,17 @@ impl<A: AppLike> State<A> for OptionsPanel {
         self.panel.draw(g);
     }
 }
+
+fn volume_spinner(ctx: &EventCtx, label: &str, current: usize) -> Widget {
+    Widget::row(vec![
+        label.text_widget(ctx).centered_vert(),
+        Spinner::widget_with_custom_rendering(
+            ctx,
+            label,
+            (0, 100),
+            current,
+            10,
+            Box::new(|x| format!("{}%", x)),
+        ),
+    ])
+}
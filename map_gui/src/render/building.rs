@@ -1,6 +1,6 @@
 use std::cell::RefCell;
 
-use geom::{Angle, Distance, Line, Polygon, Pt2D, Ring, Tessellation};
+use geom::{Distance, Line, Polygon, Pt2D, Ring, Tessellation};
 use map_model::{Building, BuildingID, Map, OffstreetParking};
 use widgetry::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, Text};
 
@@ -64,25 +64,24 @@ impl DrawBuilding {
                 );
             }
             x => {
-                let angle = match x {
-                    CameraAngle::IsometricNE => Angle::degrees(-45.0),
-                    CameraAngle::IsometricNW => Angle::degrees(-135.0),
-                    CameraAngle::IsometricSE => Angle::degrees(45.0),
-                    CameraAngle::IsometricSW => Angle::degrees(135.0),
-                    CameraAngle::TopDown | CameraAngle::Abstract => unreachable!(),
-                };
+                let angle = x.extrusion_angle().unwrap();
 
                 let bldg_height_per_level = 3.5;
                 // In downtown areas, really tall buildings look kind of ridculous next to
                 // everything else. So we artificially compress the number of levels a bit.
                 let bldg_rendered_meters = bldg_height_per_level * bldg.levels.powf(0.8);
-                let height = Distance::meters(bldg_rendered_meters);
+                let height = x.extrusion_length(Distance::meters(bldg_rendered_meters));
 
                 let map_bounds = map.get_gps_bounds().to_bounds();
                 let (map_width, map_height) = (map_bounds.width(), map_bounds.height());
                 let map_length = map_width.hypot(map_height);
 
                 let distance = |pt: &Pt2D| {
+                    // Looking north, things farther south are closer
+                    if *x == CameraAngle::Tilted {
+                        return Distance::meters(map_height - pt.y());
+                    }
+
                     // some normalization so we can compute the distance to the corner of the
                     // screen from which the orthographic projection is based.
                     let projection_origin = match x {
@@ -90,7 +89,9 @@ impl DrawBuilding {
                         CameraAngle::IsometricNW => Pt2D::new(map_width, map_height),
                         CameraAngle::IsometricSE => Pt2D::new(0.0, 0.0),
                         CameraAngle::IsometricSW => Pt2D::new(map_width, 0.0),
-                        CameraAngle::TopDown | CameraAngle::Abstract => unreachable!(),
+                        CameraAngle::TopDown | CameraAngle::Tilted | CameraAngle::Abstract => {
+                            unreachable!()
+                        }
                     };
 
                     let abs_pt = Pt2D::new(
@@ -6,19 +6,21 @@ use geom::{
 };
 use map_model::{
     ControlTrafficSignal, Direction, DrivingSide, Intersection, IntersectionControl,
-    IntersectionID, LaneType, Map, Road, RoadWithStopSign, Turn, TurnType, SIDEWALK_THICKNESS,
+    IntersectionID, LaneType, Map, Road, RoadWithStopSign, Turn, TurnType,
+};
+use widgetry::{
+    Color, Drawable, GeomBatch, GfxCtx, Instance, PreparedInstances, Prerender, RewriteColor, Text,
 };
-use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Prerender, RewriteColor, Text};
 
 use crate::colors::ColorScheme;
-use crate::render::{traffic_signal, DrawOptions, Renderable, OUTLINE_THICKNESS};
+use crate::render::{markings, traffic_signal, DrawOptions, Renderable, OUTLINE_THICKNESS};
 use crate::{AppLike, ID};
 
 pub struct DrawIntersection {
     pub id: IntersectionID,
     zorder: isize,
 
-    draw_default: RefCell<Option<Drawable>>,
+    draw_default: RefCell<Option<(Drawable, PreparedInstances)>>,
     pub draw_traffic_signal: RefCell<Option<(Time, Drawable)>>,
 }
 
@@ -33,6 +35,17 @@ impl DrawIntersection {
     }
 
     pub fn render<P: AsRef<Prerender>>(&self, prerender: &P, app: &dyn AppLike) -> GeomBatch {
+        let (mut batch, stripes) = self.render_instanced(prerender, app);
+        batch.append(markings::crosswalk_stripe().copy_for_instances(&stripes));
+        batch
+    }
+
+    /// Like `render`, but returns the crosswalk stripes separately, to draw with instancing.
+    fn render_instanced<P: AsRef<Prerender>>(
+        &self,
+        prerender: &P,
+        app: &dyn AppLike,
+    ) -> (GeomBatch, Vec<Instance>) {
         let map = app.map();
         let i = map.get_i(self.id);
 
@@ -70,12 +83,18 @@ impl DrawIntersection {
             }
         }
 
+        let mut stripes = Vec::new();
         for turn in &i.turns {
             if !app.opts().show_crosswalks {
                 break;
             }
             if turn.turn_type.pedestrian_crossing() {
-                make_crosswalk(&mut default_geom, turn, map, app.cs());
+                stripes.extend(make_crosswalk_except_stripes(
+                    &mut default_geom,
+                    turn,
+                    map,
+                    app.cs(),
+                ));
             }
         }
 
@@ -137,9 +156,12 @@ impl DrawIntersection {
         let zorder = i.get_zorder(map);
         if zorder < 0 {
             default_geom = default_geom.color(RewriteColor::ChangeAlpha(0.5));
+            for stripe in &mut stripes {
+                stripe.color = stripe.color.alpha(0.5);
+            }
         }
 
-        default_geom
+        (default_geom, stripes)
     }
 
     // Returns the (octagon, pole, angle of the angle) if there's room to draw it.
@@ -222,9 +244,16 @@ impl DrawIntersection {
         // exhaustively see every intersection during a single session
         let mut draw = self.draw_default.borrow_mut();
         if draw.is_none() {
-            *draw = Some(g.upload(self.render(g, app)));
+            let (batch, stripes) = self.render_instanced(g, app);
+            let stripes = app
+                .draw_map()
+                .markings
+                .prepare_crosswalk_stripes(g.as_ref(), stripes);
+            *draw = Some((g.upload(batch), stripes));
         }
-        g.redraw(draw.as_ref().unwrap());
+        let (draw, stripes) = draw.as_ref().unwrap();
+        g.redraw(draw);
+        app.draw_map().markings.draw_crosswalk_stripes(g, stripes);
     }
 
     fn draw_traffic_signal(
@@ -258,7 +287,7 @@ impl DrawIntersection {
                 let mut batch = GeomBatch::new();
                 traffic_signal::draw_signal_stage(
                     g.prerender,
-                    &signal.stages[idx],
+                    signal,
                     idx,
                     self.id,
                     Some(remaining),
@@ -544,58 +573,28 @@ fn make_octagon(center: Pt2D, radius: Distance, facing: Angle) -> Polygon {
 
 /// Draws both zebra crosswalks and unmarked crossings
 pub fn make_crosswalk(batch: &mut GeomBatch, turn: &Turn, map: &Map, cs: &ColorScheme) {
+    let stripes = make_crosswalk_except_stripes(batch, turn, map, cs);
+    batch.append(markings::crosswalk_stripe().copy_for_instances(&stripes));
+}
+
+/// Like `make_crosswalk`, but returns the stripes of zebra crosswalks to draw with instancing,
+/// instead of adding them to the batch.
+fn make_crosswalk_except_stripes(
+    batch: &mut GeomBatch,
+    turn: &Turn,
+    map: &Map,
+    cs: &ColorScheme,
+) -> Vec<Instance> {
     if turn.turn_type == TurnType::UnmarkedCrossing {
         make_unmarked_crossing(batch, turn, map, cs);
-        return;
+        return Vec::new();
     }
 
     if make_rainbow_crosswalk(batch, turn, map) {
-        return;
+        return Vec::new();
     }
 
-    // This size also looks better for shoulders
-    let width = SIDEWALK_THICKNESS;
-    // Start at least width out to not hit sidewalk corners. Also account for the thickness of the
-    // crosswalk line itself. Center the lines inside these two boundaries.
-    let boundary = width;
-    let tile_every = width * 0.6;
-    let line = if let Some(l) = turn.crosswalk_line() {
-        l
-    } else {
-        return;
-    };
-
-    const CROSSWALK_LINE_THICKNESS: Distance = Distance::const_meters(0.15);
-
-    let available_length = line.length() - (boundary * 2.0);
-    if available_length > Distance::ZERO {
-        let num_markings = (available_length / tile_every).floor() as usize;
-        let mut dist_along =
-            boundary + (available_length - tile_every * (num_markings as f64)) / 2.0;
-        // TODO Seems to be an off-by-one sometimes. Not enough of these.
-        let err = format!("make_crosswalk for {} broke", turn.id);
-        for _ in 0..=num_markings {
-            let pt1 = line.dist_along(dist_along).expect(&err);
-            // Reuse perp_line. Project away an arbitrary amount
-            let pt2 = pt1.project_away(Distance::meters(1.0), line.angle());
-            batch.push(
-                cs.general_road_marking,
-                perp_line(Line::must_new(pt1, pt2), width).make_polygons(CROSSWALK_LINE_THICKNESS),
-            );
-
-            // Actually every line is a double
-            let pt3 = line
-                .dist_along(dist_along + 2.0 * CROSSWALK_LINE_THICKNESS)
-                .expect(&err);
-            let pt4 = pt3.project_away(Distance::meters(1.0), line.angle());
-            batch.push(
-                cs.general_road_marking,
-                perp_line(Line::must_new(pt3, pt4), width).make_polygons(CROSSWALK_LINE_THICKNESS),
-            );
-
-            dist_along += tile_every;
-        }
-    }
+    markings::crosswalk_stripes(turn, cs.general_road_marking)
 }
 
 fn make_rainbow_crosswalk(batch: &mut GeomBatch, turn: &Turn, map: &Map) -> bool {
@@ -670,10 +669,3 @@ fn make_unmarked_crossing(batch: &mut GeomBatch, turn: &Turn, map: &Map, cs: &Co
         }
     }
 }
-
-// TODO copied from DrawLane
-fn perp_line(l: Line, length: Distance) -> Line {
-    let pt1 = l.shift_right(length / 2.0).pt1();
-    let pt2 = l.shift_left(length / 2.0).pt1();
-    Line::must_new(pt1, pt2)
-}
@@ -3,12 +3,18 @@ use std::cell::RefCell;
 use lyon::geom::{CubicBezierSegment, Point, QuadraticBezierSegment};
 
 use geom::{
-    Angle, ArrowCap, Circle, Distance, InfiniteLine, Line, PolyLine, Polygon, Pt2D, Tessellation,
+    Angle, ArrowCap, Circle, Distance, InfiniteLine, Line, PolyLine, Polygon, Pt2D, Ring,
+    Tessellation,
+};
+use map_model::{
+    BufferType, Direction, DrivingSide, Lane, LaneID, LaneRestriction, LaneRestrictionKind,
+    LaneType, Map, Road, TurnID,
+};
+use widgetry::{
+    Color, Drawable, GeomBatch, GfxCtx, Instance, PreparedInstances, Prerender, RewriteColor,
 };
-use map_model::{BufferType, Direction, DrivingSide, Lane, LaneID, LaneType, Map, Road, TurnID};
-use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Prerender, RewriteColor};
 
-use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
+use crate::render::{markings, DrawOptions, Renderable, OUTLINE_THICKNESS};
 use crate::{AppLike, ID};
 
 pub struct DrawLane {
@@ -16,7 +22,10 @@ pub struct DrawLane {
     pub polygon: Polygon,
     zorder: isize,
 
-    draw_default: RefCell<Option<Drawable>>,
+    /// Also the ticks between parking spots, drawn with instancing
+    draw_default: RefCell<Option<(Drawable, PreparedInstances)>>,
+    /// Markings for a lane restriction that only applies some of the time
+    draw_restriction: RefCell<Option<Drawable>>,
 }
 
 impl DrawLane {
@@ -26,10 +35,35 @@ impl DrawLane {
             polygon: lane.get_thick_polygon(),
             zorder: road.zorder,
             draw_default: RefCell::new(None),
+            draw_restriction: RefCell::new(None),
         }
     }
 
     pub fn render<P: AsRef<Prerender>>(&self, prerender: &P, app: &dyn AppLike) -> GeomBatch {
+        let mut batch = self.render_without_ticks(prerender, app);
+        batch.append(markings::parking_tick().copy_for_instances(&self.parking_ticks(app)));
+        batch
+    }
+
+    /// Where to draw the ticks between parking spots, if this is a parking lane
+    fn parking_ticks(&self, app: &dyn AppLike) -> Vec<Instance> {
+        let map = app.map();
+        let lane = map.get_l(self.id);
+        if lane.lane_type != LaneType::Parking {
+            return Vec::new();
+        }
+        let mut color = app.cs().general_road_marking;
+        if self.zorder < 0 {
+            color = color.alpha(0.5);
+        }
+        markings::parking_ticks(lane, map, color)
+    }
+
+    fn render_without_ticks<P: AsRef<Prerender>>(
+        &self,
+        prerender: &P,
+        app: &dyn AppLike,
+    ) -> GeomBatch {
         let map = app.map();
         let lane = map.get_l(self.id);
         let road = map.get_r(lane.id.road);
@@ -62,9 +96,8 @@ impl DrawLane {
                     }
                 }
             }
-            LaneType::Parking => {
-                batch.extend(general_road_marking, calculate_parking_lines(lane, map));
-            }
+            // The ticks between spots are drawn with instancing
+            LaneType::Parking => {}
             LaneType::Driving => {
                 batch.extend(general_road_marking, calculate_driving_lines(lane, road));
                 batch.extend(general_road_marking, calculate_turn_markings(map, lane));
@@ -74,17 +107,7 @@ impl DrawLane {
                 batch.extend(general_road_marking, calculate_driving_lines(lane, road));
                 batch.extend(general_road_marking, calculate_turn_markings(map, lane));
                 batch.extend(general_road_marking, calculate_one_way_markings(lane, road));
-                for (pt, angle) in lane
-                    .lane_center_pts
-                    .step_along(Distance::meters(30.0), Distance::meters(5.0))
-                {
-                    batch.append(
-                        GeomBatch::load_svg(prerender, "system/assets/map/bus_only.svg")
-                            .scale(0.06)
-                            .centered_on(pt)
-                            .rotate(angle.shortest_rotation_towards(Angle::degrees(-90.0))),
-                    );
-                }
+                batch.append(calculate_bus_only_markings(prerender, lane));
             }
             LaneType::Biking => {
                 for (pt, angle) in lane
@@ -193,6 +216,17 @@ impl DrawLane {
             }
         }
 
+        if let Some(restriction) = lane.get_restriction(map) {
+            if restriction.always_active() {
+                batch.append(calculate_restriction_markings(
+                    prerender,
+                    app,
+                    lane,
+                    restriction,
+                ));
+            }
+        }
+
         if road.is_private() {
             if let Some(color) = app.cs().private_road {
                 batch.push(color.alpha(0.5), self.polygon.clone());
@@ -208,6 +242,7 @@ impl DrawLane {
 
     pub fn clear_rendering(&mut self) {
         *self.draw_default.borrow_mut() = None;
+        *self.draw_restriction.borrow_mut() = None;
     }
 }
 
@@ -221,9 +256,29 @@ impl Renderable for DrawLane {
         // exhaustively see every lane during a single session
         let mut draw = self.draw_default.borrow_mut();
         if draw.is_none() {
-            *draw = Some(g.upload(self.render(g, app)));
+            let ticks = app
+                .draw_map()
+                .markings
+                .prepare_parking_ticks(g.as_ref(), self.parking_ticks(app));
+            *draw = Some((g.upload(self.render_without_ticks(g, app)), ticks));
+        }
+        let (draw, ticks) = draw.as_ref().unwrap();
+        g.redraw(draw);
+        app.draw_map().markings.draw_parking_ticks(g, ticks);
+
+        // Restrictions only in effect some of the time are drawn separately, so they can come and
+        // go with the simulation's clock
+        if let Some(restriction) = app.map().get_l(self.id).get_restriction(app.map()) {
+            if !restriction.always_active() && restriction.active_at(app.sim_time()) {
+                let mut draw = self.draw_restriction.borrow_mut();
+                if draw.is_none() {
+                    let lane = app.map().get_l(self.id);
+                    *draw =
+                        Some(g.upload(calculate_restriction_markings(g, app, lane, restriction)));
+                }
+                g.redraw(draw.as_ref().unwrap());
+            }
         }
-        g.redraw(draw.as_ref().unwrap());
     }
 
     fn get_outline(&self, map: &Map) -> Tessellation {
@@ -242,6 +297,58 @@ impl Renderable for DrawLane {
     }
 }
 
+fn calculate_bus_only_markings<P: AsRef<Prerender>>(prerender: &P, lane: &Lane) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    for (pt, angle) in lane
+        .lane_center_pts
+        .step_along(Distance::meters(30.0), Distance::meters(5.0))
+    {
+        batch.append(
+            GeomBatch::load_svg(prerender, "system/assets/map/bus_only.svg")
+                .scale(0.06)
+                .centered_on(pt)
+                .rotate(angle.shortest_rotation_towards(Angle::degrees(-90.0))),
+        );
+    }
+    batch
+}
+
+fn calculate_restriction_markings<P: AsRef<Prerender>>(
+    prerender: &P,
+    app: &dyn AppLike,
+    lane: &Lane,
+    restriction: &LaneRestriction,
+) -> GeomBatch {
+    match restriction.kind {
+        LaneRestrictionKind::BusOnly => calculate_bus_only_markings(prerender, lane),
+        LaneRestrictionKind::HighOccupancy(_) => {
+            // The diamond painted on HOV lanes
+            let mut batch = GeomBatch::new();
+            let half_length = Distance::meters(2.0);
+            let half_width = lane.width / 4.0;
+            for (pt, angle) in lane
+                .lane_center_pts
+                .step_along(Distance::meters(30.0), Distance::meters(5.0))
+            {
+                let ring = Ring::must_new(vec![
+                    pt.project_away(half_length, angle),
+                    pt.project_away(half_width, angle.rotate_degs(90.0)),
+                    pt.project_away(half_length, angle.opposite()),
+                    pt.project_away(half_width, angle.rotate_degs(-90.0)),
+                    pt.project_away(half_length, angle),
+                ]);
+                batch.push(
+                    app.cs().general_road_marking,
+                    ring.to_outline(Distance::meters(0.25)),
+                );
+            }
+            batch
+        }
+        // The turn arrows already say enough
+        LaneRestrictionKind::TurnOnly => GeomBatch::new(),
+    }
+}
+
 // TODO this always does it at pt1
 fn perp_line(l: Line, length: Distance) -> Line {
     let pt1 = l.shift_right(length / 2.0).pt1();
@@ -261,40 +368,6 @@ fn calculate_sidewalk_lines(lane: &Lane) -> Vec<Polygon> {
         .collect()
 }
 
-fn calculate_parking_lines(lane: &Lane, map: &Map) -> Vec<Polygon> {
-    let leg_length = Distance::meters(1.0);
-
-    let mut result = Vec::new();
-    let num_spots = lane.number_parking_spots(map.get_config());
-    if num_spots > 0 {
-        for idx in 0..=num_spots {
-            let (pt, lane_angle) = lane
-                .lane_center_pts
-                .must_dist_along(map.get_config().street_parking_spot_length * (1.0 + idx as f64));
-            let perp_angle = if map.get_config().driving_side == DrivingSide::Right {
-                lane_angle.rotate_degs(270.0)
-            } else {
-                lane_angle.rotate_degs(90.0)
-            };
-            // Find the outside of the lane. Actually, shift inside a little bit, since the line
-            // will have thickness, but shouldn't really intersect the adjacent line
-            // when drawn.
-            let t_pt = pt.project_away(lane.width * 0.4, perp_angle);
-            // The perp leg
-            let p1 = t_pt.project_away(leg_length, perp_angle.opposite());
-            result.push(Line::must_new(t_pt, p1).make_polygons(Distance::meters(0.25)));
-            // Upper leg
-            let p2 = t_pt.project_away(leg_length, lane_angle);
-            result.push(Line::must_new(t_pt, p2).make_polygons(Distance::meters(0.25)));
-            // Lower leg
-            let p3 = t_pt.project_away(leg_length, lane_angle.opposite());
-            result.push(Line::must_new(t_pt, p3).make_polygons(Distance::meters(0.25)));
-        }
-    }
-
-    result
-}
-
 // Because the stripe straddles two lanes, it'll be partly hidden on one side. There are a bunch of
 // ways to work around this z-order issue. The current approach is to rely on the fact that
 // quadtrees return LaneIDs in order, and lanes are always created from left->right.
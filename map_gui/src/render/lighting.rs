@@ -0,0 +1,87 @@
+use geom::{Circle, Distance, Pt2D, Time};
+use map_model::Map;
+use widgetry::{Color, Drawable, GeomBatch, GfxCtx};
+
+/// Without any street lamps mapped in OSM, guess there's one this often along each road
+const GUESSED_LAMP_SPACING: Distance = Distance::const_meters(40.0);
+
+/// Darkens the map at night, with glowing windows and street lights. This is purely visual.
+pub struct DrawLighting {
+    window_glow: Drawable,
+    street_lights: Drawable,
+}
+
+impl DrawLighting {
+    pub fn new(g: &mut GfxCtx, map: &Map) -> DrawLighting {
+        let mut windows = GeomBatch::new();
+        for b in map.all_buildings() {
+            // Not everybody is home with the lights on. Vary this deterministically, so the same
+            // buildings stay lit.
+            if b.id.0 % 3 != 0 {
+                windows.push(Color::hex("#FFD479").alpha(0.5), b.polygon.clone());
+            }
+        }
+
+        let mut lights = GeomBatch::new();
+        let lamps: Vec<Pt2D> = if map.all_street_lamps().is_empty() {
+            map.all_roads()
+                .iter()
+                .filter(|r| !r.is_light_rail() && !r.is_footway())
+                .flat_map(|r| {
+                    r.center_pts
+                        .shift_right(r.get_half_width())
+                        .map(|pl| pl.step_along(GUESSED_LAMP_SPACING, GUESSED_LAMP_SPACING / 2.0))
+                        .unwrap_or_else(|_| Vec::new())
+                })
+                .map(|(pt, _)| pt)
+                .collect()
+        } else {
+            map.all_street_lamps().clone()
+        };
+        for pt in lamps {
+            lights.push(
+                Color::hex("#FFE8A3").alpha(0.2),
+                Circle::new(pt, Distance::meters(12.0)).to_polygon(),
+            );
+            lights.push(
+                Color::hex("#FFE8A3").alpha(0.3),
+                Circle::new(pt, Distance::meters(5.0)).to_polygon(),
+            );
+            lights.push(
+                Color::hex("#FFF6D9"),
+                Circle::new(pt, Distance::meters(0.8)).to_polygon(),
+            );
+        }
+
+        DrawLighting {
+            window_glow: g.upload(windows),
+            street_lights: g.upload(lights),
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, time: Time) {
+        let darkness = darkness(time);
+        if darkness == 0.0 {
+            return;
+        }
+        g.draw_polygon(
+            Color::hex("#0B1A3A").alpha((0.6 * darkness) as f32),
+            g.get_screen_bounds().get_rectangle(),
+        );
+        g.set_opacity(darkness);
+        g.redraw(&self.window_glow);
+        g.redraw(&self.street_lights);
+        g.set_opacity(1.0);
+    }
+}
+
+/// 0 during the day, 1 at night, fading over a few hours around sunrise at 6am and sunset at 6pm
+fn darkness(time: Time) -> f64 {
+    let hours = (time.inner_seconds() / 3600.0) % 24.0;
+    let fade = |hours_past_transition: f64| (hours_past_transition / 2.0 + 0.5).clamp(0.0, 1.0);
+    if hours < 12.0 {
+        1.0 - fade(hours - 6.0)
+    } else {
+        fade(hours - 18.0)
+    }
+}
@@ -1,19 +1,23 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
 
 use aabb_quadtree::QuadTree;
 
 use abstutil::Timer;
-use geom::{Bounds, Distance, Tessellation};
+use geom::{Bounds, Distance, PolyLine, Pt2D, Tessellation, Time};
 use map_model::{
-    AreaID, BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Road, RoadID, TransitStopID,
+    AreaID, BuildingID, Intersection, IntersectionID, LaneID, Map, ParkingLotID, Road, RoadID,
+    TransitStopID,
 };
-use widgetry::{Color, Drawable, EventCtx, Fill, GeomBatch};
+use widgetry::{Color, Drawable, EventCtx, Fill, GeomBatch, GfxCtx};
 
 use crate::colors::ColorScheme;
 use crate::options::Options;
 use crate::render::building::DrawBuilding;
 use crate::render::intersection::DrawIntersection;
 use crate::render::lane::DrawLane;
+use crate::render::lighting::DrawLighting;
+use crate::render::markings::DrawMarkings;
 use crate::render::parking_lot::DrawParkingLot;
 use crate::render::road::DrawRoad;
 use crate::render::transit_stop::DrawTransitStop;
@@ -34,6 +38,7 @@ pub struct DrawMap {
     pub draw_all_building_outlines: Drawable,
     pub draw_all_unzoomed_parking_lots: Drawable,
     pub draw_all_areas: Drawable,
+    pub markings: DrawMarkings,
 
     pub zorder_range: (isize, isize),
     pub show_zorder: isize,
@@ -41,6 +46,10 @@ pub struct DrawMap {
     quadtree: QuadTree<ID>,
     // Remember these so we can modify the bounding box of some objects.
     quadtree_ids: HashMap<ID, aabb_quadtree::ItemId>,
+    unzoomed_pieces: UnzoomedPieces,
+    levels_of_detail: Vec<LevelOfDetail>,
+    // Only built the first time it's needed
+    lighting: RefCell<Option<DrawLighting>>,
 }
 
 impl DrawMap {
@@ -51,6 +60,9 @@ impl DrawMap {
         cs: &ColorScheme,
         timer: &mut Timer,
     ) -> DrawMap {
+        // The tilted camera angle draws everything foreshortened
+        ctx.canvas.vertical_scale = opts.camera_angle.vertical_scale();
+
         let mut roads: Vec<DrawRoad> = Vec::new();
         let mut low_z = 0;
         let mut high_z = 0;
@@ -69,8 +81,18 @@ impl DrawMap {
             intersections.push(DrawIntersection::new(i, map));
         }
 
-        let draw_all_unzoomed_roads_and_intersections =
-            DrawMap::regenerate_unzoomed_layer(ctx, map, cs, opts, timer);
+        timer.start("generate unzoomed roads and intersections");
+        let unzoomed_pieces = UnzoomedPieces::new(ctx, map, cs, opts, None);
+        let draw_all_unzoomed_roads_and_intersections = unzoomed_pieces.upload(ctx);
+        timer.stop("generate unzoomed roads and intersections");
+
+        timer.start("generate levels of detail");
+        let levels_of_detail = LEVELS_OF_DETAIL
+            .iter()
+            .enumerate()
+            .map(|(idx, max_zoom)| LevelOfDetail::new(ctx, map, cs, opts, *max_zoom, idx > 0))
+            .collect();
+        timer.stop("generate levels of detail");
 
         let (buildings, draw_all_buildings, draw_all_building_outlines) =
             DrawMap::regenerate_buildings(ctx, map, cs, opts, timer);
@@ -94,6 +116,12 @@ impl DrawMap {
             timer.next();
             areas.push(DrawArea::new(ctx, a, cs, &mut all_areas));
         }
+        // Roads and intersections are drawn on top, leaving just the islands
+        for r in map.all_roads() {
+            if let Some(island) = r.slip_lane_island(map) {
+                all_areas.push(cs.grass.clone(), island);
+            }
+        }
         timer.start("upload all areas");
         let draw_all_areas = all_areas.upload(ctx);
         timer.stop("upload all areas");
@@ -150,9 +178,13 @@ impl DrawMap {
             draw_all_building_outlines,
             draw_all_unzoomed_parking_lots,
             draw_all_areas,
+            markings: DrawMarkings::new(ctx),
 
             quadtree,
             quadtree_ids,
+            unzoomed_pieces,
+            levels_of_detail,
+            lighting: RefCell::new(None),
 
             zorder_range: (low_z, high_z),
             show_zorder: high_z,
@@ -217,132 +249,100 @@ impl DrawMap {
         timer: &mut Timer,
     ) -> Drawable {
         timer.start("generate unzoomed roads and intersections");
+        let draw = UnzoomedPieces::new(ctx, map, cs, opts, None).upload(ctx);
+        timer.stop("generate unzoomed roads and intersections");
+        draw
+    }
 
-        // TODO Different in night mode
-        let outline_color = Color::BLACK;
-        let outline_thickness = Distance::meters(1.0);
-        // We want the outlines slightly above the equivalent layer. z-order is an isize, and f64
-        // makes sort_by_key annoying, so just multiply the existing z-orders by 10.
-        let outline_z_offset = 5;
-        let mut unzoomed_pieces: Vec<(isize, Fill, Tessellation)> = Vec::new();
+    /// Like `regenerate_unzoomed_layer`, but only recalculates the geometry of some roads and
+    /// intersections, reusing everything else. Use this after map edits.
+    pub fn update_unzoomed_layer(
+        &mut self,
+        ctx: &EventCtx,
+        map: &Map,
+        cs: &ColorScheme,
+        opts: &Options,
+        changed_roads: &BTreeSet<RoadID>,
+        changed_intersections: &BTreeSet<IntersectionID>,
+        timer: &mut Timer,
+    ) {
+        timer.start("update unzoomed roads and intersections");
+        self.unzoomed_pieces
+            .update(ctx, map, cs, opts, changed_roads, changed_intersections);
+        self.draw_all_unzoomed_roads_and_intersections = self.unzoomed_pieces.upload(ctx);
+        for lod in &mut self.levels_of_detail {
+            lod.pieces
+                .update(ctx, map, cs, opts, changed_roads, changed_intersections);
+            lod.draw_roads_and_intersections = lod.pieces.upload(ctx);
+        }
+        timer.stop("update unzoomed roads and intersections");
+    }
 
-        for r in map.all_roads() {
-            let width = r.get_width();
-
-            unzoomed_pieces.push((
-                10 * r.zorder,
-                Fill::Color(if r.is_light_rail() {
-                    cs.light_rail_track
-                } else if r.is_cycleway() {
-                    cs.unzoomed_cycleway
-                } else if r.is_footway() {
-                    cs.unzoomed_footway
-                } else if r.is_private() && cs.private_road.is_some() {
-                    cs.private_road.unwrap()
-                } else {
-                    cs.unzoomed_road_surface(r.get_rank())
-                }),
-                r.center_pts.make_polygons(width).into(),
-            ));
+    /// When the camera is zoomed out far, use a simpler version of the map.
+    fn level_of_detail(&self, zoom: f64) -> Option<&LevelOfDetail> {
+        self.levels_of_detail
+            .iter()
+            .rev()
+            .find(|lod| zoom < lod.max_zoom)
+    }
 
-            if cs.road_outlines {
-                // Draw a thick outline on the left and right
-                for pl in [
-                    r.center_pts.shift_left(width / 2.0),
-                    r.center_pts.shift_right(width / 2.0),
-                ]
-                .into_iter()
-                .flatten()
-                {
-                    if (opts.simplify_basemap && r.is_cycleway()) || r.is_footway() {
-                        for p in pl.exact_dashed_polygons(
-                            0.5 * outline_thickness,
-                            Distance::meters(5.0),
-                            Distance::meters(2.0),
-                        ) {
-                            unzoomed_pieces.push((
-                                10 * r.zorder + outline_z_offset,
-                                outline_color.into(),
-                                p.into(),
-                            ));
-                        }
-                    } else {
-                        unzoomed_pieces.push((
-                            10 * r.zorder + outline_z_offset,
-                            outline_color.into(),
-                            pl.make_polygons(outline_thickness).into(),
-                        ));
-                    }
-                }
-            }
+    /// If the camera is zoomed out far enough to simplify the map, returns roughly how much
+    /// distance one pixel covers at that level of detail. Details smaller than this can be
+    /// skipped.
+    pub fn lod_resolution(&self, zoom: f64) -> Option<Distance> {
+        self.level_of_detail(zoom)
+            .map(|lod| Distance::meters(lod.simplify.epsilon))
+    }
+
+    /// Draws all areas, simplified if the camera is zoomed out far.
+    pub fn draw_unzoomed_areas(&self, g: &mut GfxCtx) {
+        if let Some(lod) = self.level_of_detail(g.canvas.cam_zoom) {
+            g.redraw(&lod.draw_areas);
+        } else {
+            g.redraw(&self.draw_all_areas);
         }
+    }
 
-        let traffic_signal_icon = if opts.show_traffic_signal_icon {
-            GeomBatch::load_svg(ctx, "system/assets/map/traffic_signal.svg").scale(0.8)
+    /// Parking lots are skipped when the camera is zoomed out far.
+    pub fn draw_unzoomed_parking_lots(&self, g: &mut GfxCtx) {
+        if self.level_of_detail(g.canvas.cam_zoom).is_none() {
+            g.redraw(&self.draw_all_unzoomed_parking_lots);
+        }
+    }
+
+    /// Draws all roads and intersections, simplified if the camera is zoomed out far.
+    pub fn draw_unzoomed_roads_and_intersections(&self, g: &mut GfxCtx) {
+        if let Some(lod) = self.level_of_detail(g.canvas.cam_zoom) {
+            g.redraw(&lod.draw_roads_and_intersections);
         } else {
-            GeomBatch::new()
-        };
+            g.redraw(&self.draw_all_unzoomed_roads_and_intersections);
+        }
+    }
 
-        for i in map.all_intersections() {
-            let zorder = 10 * i.get_zorder(map);
-            let intersection_color = if opts.simplify_basemap
-                || i.is_stop_sign()
-                || (i.is_traffic_signal() && opts.show_traffic_signal_icon)
-            {
-                // Use the color of the road, so the intersection doesn't stand out
-                // TODO When cycleways meet footways, we fallback to unzoomed_road_surface. Maybe
-                // we need a ranking for types here too
-                if i.is_light_rail(map) {
-                    cs.light_rail_track
-                } else if i.is_cycleway(map) {
-                    cs.unzoomed_cycleway
-                } else if i.is_footway(map) {
-                    cs.unzoomed_footway
-                } else if i.is_private(map) && cs.private_road.is_some() {
-                    cs.private_road.unwrap()
-                } else {
-                    cs.unzoomed_road_surface(i.get_rank(map))
-                }
-            } else {
-                cs.unzoomed_interesting_intersection
-            };
-            unzoomed_pieces.push((zorder, intersection_color.into(), i.polygon.clone().into()));
-
-            if cs.road_outlines {
-                // It'd be nice to dash the outline for footways, but usually the pieces of the
-                // outline in between the roads are too small to dash, and using the entire thing
-                // would look like the intersection is blocked off
-                for pl in DrawIntersection::get_unzoomed_outline(i, map) {
-                    unzoomed_pieces.push((
-                        zorder + outline_z_offset,
-                        outline_color.into(),
-                        pl.make_polygons(0.5 * outline_thickness).into(),
-                    ));
-                }
+    /// Draws all buildings. Outlines are skipped when the camera is zoomed out far, and
+    /// buildings entirely when it's even further away.
+    pub fn draw_unzoomed_buildings(&self, g: &mut GfxCtx) {
+        match self.level_of_detail(g.canvas.cam_zoom) {
+            None => {
+                g.redraw(&self.draw_all_buildings);
+                g.redraw(&self.draw_all_building_outlines);
             }
-
-            if opts.show_traffic_signal_icon && i.is_traffic_signal() {
-                // When the intersection has several z-orders meeting, we want to take the highest,
-                // so the icon is drawn over any connecting roads.
-                let icon_zorder = 10 * i.roads.iter().map(|r| map.get_r(*r).zorder).max().unwrap();
-                for (fill, polygon, _) in traffic_signal_icon
-                    .clone()
-                    .centered_on(i.polygon.polylabel())
-                    .consume()
-                {
-                    unzoomed_pieces.push((icon_zorder + outline_z_offset, fill, polygon));
+            Some(lod) => {
+                if !lod.simplify.coarse {
+                    g.redraw(&self.draw_all_buildings);
                 }
             }
         }
-        unzoomed_pieces.sort_by_key(|(z, _, _)| *z);
-        let mut unzoomed_batch = GeomBatch::new();
-        for (_, fill, poly) in unzoomed_pieces {
-            unzoomed_batch.push(fill, poly);
-        }
+    }
 
-        let draw_all_unzoomed_roads_and_intersections = unzoomed_batch.upload(ctx);
-        timer.stop("generate unzoomed roads and intersections");
-        draw_all_unzoomed_roads_and_intersections
+    /// Darkens everything drawn so far based on the time of day, with lit windows and street
+    /// lights at night. Call after drawing the map and agents, before anything in screen-space.
+    pub fn draw_lighting(&self, g: &mut GfxCtx, map: &Map, time: Time) {
+        let mut lighting = self.lighting.borrow_mut();
+        if lighting.is_none() {
+            *lighting = Some(DrawLighting::new(g, map));
+        }
+        lighting.as_ref().unwrap().draw(g, time);
     }
 
     // The alt to these is implementing std::ops::Index, but that's way more verbose!
@@ -530,6 +530,22 @@ impl DrawMap {
         self.roads[road.id.0] = draw;
     }
 
+    /// Transit stops may be created, moved, or deleted.
+    pub fn recreate_transit_stop(
+        &mut self,
+        ctx: &EventCtx,
+        id: TransitStopID,
+        map: &Map,
+        cs: &ColorScheme,
+    ) {
+        if let Some(stop) = map.maybe_get_ts(id) {
+            self.bus_stops
+                .insert(id, DrawTransitStop::new(ctx, stop, map, cs));
+        } else {
+            self.bus_stops.remove(&id);
+        }
+    }
+
     pub fn free_memory(&mut self) {
         // Clear the lazily evaluated zoomed-in details
         for r in &mut self.roads {
@@ -546,3 +562,307 @@ impl DrawMap {
         }
     }
 }
+
+// TODO Different in night mode
+const OUTLINE_COLOR: Color = Color::BLACK;
+const OUTLINE_THICKNESS: Distance = Distance::const_meters(1.0);
+// We want the outlines slightly above the equivalent layer. z-order is an isize, and f64 makes
+// sort_by_key annoying, so just multiply the existing z-orders by 10.
+const OUTLINE_Z_OFFSET: isize = 5;
+
+type UnzoomedPiece = (isize, Fill, Tessellation);
+
+/// Zoomed out further than each of these, draw a simpler version of the map. Each level is
+/// coarser than the previous.
+const LEVELS_OF_DETAIL: [f64; 2] = [1.0, 0.25];
+
+/// How to simplify geometry for one level of detail
+#[derive(Clone, Copy)]
+struct Simplification {
+    /// Roughly how many meters one pixel covers. Details smaller than this are removed.
+    epsilon: f64,
+    /// Also skip service roads and buildings
+    coarse: bool,
+}
+
+impl Simplification {
+    fn skip_road(&self, r: &Road) -> bool {
+        r.is_footway() || (self.coarse && r.is_service())
+    }
+}
+
+/// A simpler version of the unzoomed map, used when the camera is zoomed out further than
+/// `max_zoom`
+struct LevelOfDetail {
+    max_zoom: f64,
+    simplify: Simplification,
+    pieces: UnzoomedPieces,
+    draw_roads_and_intersections: Drawable,
+    draw_areas: Drawable,
+}
+
+impl LevelOfDetail {
+    fn new(
+        ctx: &EventCtx,
+        map: &Map,
+        cs: &ColorScheme,
+        opts: &Options,
+        max_zoom: f64,
+        coarse: bool,
+    ) -> LevelOfDetail {
+        let simplify = Simplification {
+            epsilon: 1.0 / max_zoom,
+            coarse,
+        };
+        let pieces = UnzoomedPieces::new(ctx, map, cs, opts, Some(simplify));
+        let draw_roads_and_intersections = pieces.upload(ctx);
+
+        // Tiny areas wouldn't be visible anyway
+        let min_area = (4.0 * simplify.epsilon).powi(2);
+        let mut areas = GeomBatch::new();
+        for a in map.all_areas() {
+            if a.polygon.area() >= min_area {
+                areas.push(
+                    DrawArea::fill(a.area_type, cs),
+                    a.polygon.simplify(simplify.epsilon.powi(2)),
+                );
+            }
+        }
+
+        LevelOfDetail {
+            max_zoom,
+            simplify,
+            pieces,
+            draw_roads_and_intersections,
+            draw_areas: areas.upload(ctx),
+        }
+    }
+}
+
+/// The unzoomed layer is one big Drawable, but the pieces for each road and intersection are kept
+/// separately, so that after map edits, only the changed objects need to be recalculated.
+struct UnzoomedPieces {
+    simplify: Option<Simplification>,
+    roads: Vec<Vec<UnzoomedPiece>>,
+    intersections: Vec<Vec<UnzoomedPiece>>,
+}
+
+impl UnzoomedPieces {
+    fn new(
+        ctx: &EventCtx,
+        map: &Map,
+        cs: &ColorScheme,
+        opts: &Options,
+        simplify: Option<Simplification>,
+    ) -> UnzoomedPieces {
+        let traffic_signal_icon = traffic_signal_icon(ctx, opts);
+        UnzoomedPieces {
+            simplify,
+            roads: map
+                .all_roads()
+                .iter()
+                .map(|r| unzoomed_road(r, cs, opts, simplify))
+                .collect(),
+            intersections: map
+                .all_intersections()
+                .iter()
+                .map(|i| unzoomed_intersection(i, map, cs, opts, &traffic_signal_icon, simplify))
+                .collect(),
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &EventCtx,
+        map: &Map,
+        cs: &ColorScheme,
+        opts: &Options,
+        changed_roads: &BTreeSet<RoadID>,
+        changed_intersections: &BTreeSet<IntersectionID>,
+    ) {
+        let traffic_signal_icon = traffic_signal_icon(ctx, opts);
+        for r in changed_roads {
+            self.roads[r.0] = unzoomed_road(map.get_r(*r), cs, opts, self.simplify);
+        }
+        for i in changed_intersections {
+            self.intersections[i.0] = unzoomed_intersection(
+                map.get_i(*i),
+                map,
+                cs,
+                opts,
+                &traffic_signal_icon,
+                self.simplify,
+            );
+        }
+    }
+
+    fn upload(&self, ctx: &EventCtx) -> Drawable {
+        let mut pieces: Vec<&UnzoomedPiece> = self
+            .roads
+            .iter()
+            .chain(self.intersections.iter())
+            .flatten()
+            .collect();
+        pieces.sort_by_key(|(z, _, _)| *z);
+        let mut batch = GeomBatch::new();
+        for (_, fill, poly) in pieces {
+            batch.push(fill.clone(), poly.clone());
+        }
+        batch.upload(ctx)
+    }
+}
+
+fn traffic_signal_icon(ctx: &EventCtx, opts: &Options) -> GeomBatch {
+    if opts.show_traffic_signal_icon {
+        GeomBatch::load_svg(ctx, "system/assets/map/traffic_signal.svg").scale(0.8)
+    } else {
+        GeomBatch::new()
+    }
+}
+
+fn unzoomed_road(
+    r: &Road,
+    cs: &ColorScheme,
+    opts: &Options,
+    simplify: Option<Simplification>,
+) -> Vec<UnzoomedPiece> {
+    let mut pieces = Vec::new();
+    let width = r.get_width();
+    let center_pts = match simplify {
+        Some(simplify) => {
+            if simplify.skip_road(r) {
+                return pieces;
+            }
+            PolyLine::new(Pt2D::simplify_rdp(
+                r.center_pts.points().clone(),
+                simplify.epsilon,
+            ))
+            .unwrap_or_else(|_| r.center_pts.clone())
+        }
+        None => r.center_pts.clone(),
+    };
+
+    pieces.push((
+        10 * r.zorder,
+        Fill::Color(if r.is_light_rail() {
+            cs.light_rail_track
+        } else if r.is_cycleway() {
+            cs.unzoomed_cycleway
+        } else if r.is_footway() {
+            cs.unzoomed_footway
+        } else if r.is_private() && cs.private_road.is_some() {
+            cs.private_road.unwrap()
+        } else {
+            cs.unzoomed_road_surface(r.get_rank())
+        }),
+        center_pts.make_polygons(width).into(),
+    ));
+
+    // Outlines are too thin to see when simplified
+    if cs.road_outlines && simplify.is_none() {
+        // Draw a thick outline on the left and right
+        for pl in [
+            r.center_pts.shift_left(width / 2.0),
+            r.center_pts.shift_right(width / 2.0),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if (opts.simplify_basemap && r.is_cycleway()) || r.is_footway() {
+                for p in pl.exact_dashed_polygons(
+                    0.5 * OUTLINE_THICKNESS,
+                    Distance::meters(5.0),
+                    Distance::meters(2.0),
+                ) {
+                    pieces.push((
+                        10 * r.zorder + OUTLINE_Z_OFFSET,
+                        OUTLINE_COLOR.into(),
+                        p.into(),
+                    ));
+                }
+            } else {
+                pieces.push((
+                    10 * r.zorder + OUTLINE_Z_OFFSET,
+                    OUTLINE_COLOR.into(),
+                    pl.make_polygons(OUTLINE_THICKNESS).into(),
+                ));
+            }
+        }
+    }
+    pieces
+}
+
+fn unzoomed_intersection(
+    i: &Intersection,
+    map: &Map,
+    cs: &ColorScheme,
+    opts: &Options,
+    traffic_signal_icon: &GeomBatch,
+    simplify: Option<Simplification>,
+) -> Vec<UnzoomedPiece> {
+    let mut pieces = Vec::new();
+    if let Some(simplify) = simplify {
+        if i.roads.iter().all(|r| simplify.skip_road(map.get_r(*r))) {
+            return pieces;
+        }
+    }
+    let zorder = 10 * i.get_zorder(map);
+    let intersection_color = if opts.simplify_basemap
+        || i.is_stop_sign()
+        || (i.is_traffic_signal() && opts.show_traffic_signal_icon)
+    {
+        // Use the color of the road, so the intersection doesn't stand out
+        // TODO When cycleways meet footways, we fallback to unzoomed_road_surface. Maybe we need
+        // a ranking for types here too
+        if i.is_light_rail(map) {
+            cs.light_rail_track
+        } else if i.is_cycleway(map) {
+            cs.unzoomed_cycleway
+        } else if i.is_footway(map) {
+            cs.unzoomed_footway
+        } else if i.is_private(map) && cs.private_road.is_some() {
+            cs.private_road.unwrap()
+        } else {
+            cs.unzoomed_road_surface(i.get_rank(map))
+        }
+    } else {
+        cs.unzoomed_interesting_intersection
+    };
+    if let Some(simplify) = simplify {
+        // Outlines and icons are too small to see
+        pieces.push((
+            zorder,
+            intersection_color.into(),
+            i.polygon.simplify(simplify.epsilon.powi(2)).into(),
+        ));
+        return pieces;
+    }
+    pieces.push((zorder, intersection_color.into(), i.polygon.clone().into()));
+
+    if cs.road_outlines {
+        // It'd be nice to dash the outline for footways, but usually the pieces of the outline in
+        // between the roads are too small to dash, and using the entire thing would look like the
+        // intersection is blocked off
+        for pl in DrawIntersection::get_unzoomed_outline(i, map) {
+            pieces.push((
+                zorder + OUTLINE_Z_OFFSET,
+                OUTLINE_COLOR.into(),
+                pl.make_polygons(0.5 * OUTLINE_THICKNESS).into(),
+            ));
+        }
+    }
+
+    if opts.show_traffic_signal_icon && i.is_traffic_signal() {
+        // When the intersection has several z-orders meeting, we want to take the highest, so the
+        // icon is drawn over any connecting roads.
+        let icon_zorder = 10 * i.roads.iter().map(|r| map.get_r(*r).zorder).max().unwrap();
+        for (fill, polygon, _) in traffic_signal_icon
+            .clone()
+            .centered_on(i.polygon.polylabel())
+            .consume()
+        {
+            pieces.push((icon_zorder + OUTLINE_Z_OFFSET, fill, polygon));
+        }
+    }
+    pieces
+}
@@ -0,0 +1,154 @@
+//! Crosswalk stripes and parking spot ticks repeat the same small shape thousands of times across
+//! a map. Upload each shape once, then just draw copies of it with instancing.
+
+use geom::{Angle, Distance, Line, Pt2D};
+use map_model::{DrivingSide, Lane, Map, Turn, SIDEWALK_THICKNESS};
+use widgetry::{
+    Color, EventCtx, GeomBatch, GfxCtx, Instance, InstancedDrawable, PreparedInstances, Prerender,
+};
+
+const CROSSWALK_LINE_THICKNESS: Distance = Distance::const_meters(0.15);
+const PARKING_LINE_THICKNESS: Distance = Distance::const_meters(0.25);
+
+pub struct DrawMarkings {
+    crosswalk_stripe: InstancedDrawable,
+    parking_tick: InstancedDrawable,
+}
+
+impl DrawMarkings {
+    pub fn new(ctx: &EventCtx) -> DrawMarkings {
+        DrawMarkings {
+            crosswalk_stripe: ctx.upload_instanced(crosswalk_stripe()),
+            parking_tick: ctx.upload_instanced(parking_tick()),
+        }
+    }
+
+    /// Lanes and intersections cache these, so nothing's uploaded per frame without instancing
+    pub fn prepare_crosswalk_stripes(
+        &self,
+        prerender: &Prerender,
+        instances: Vec<Instance>,
+    ) -> PreparedInstances {
+        self.crosswalk_stripe.prepare(prerender, instances)
+    }
+
+    pub fn prepare_parking_ticks(
+        &self,
+        prerender: &Prerender,
+        instances: Vec<Instance>,
+    ) -> PreparedInstances {
+        self.parking_tick.prepare(prerender, instances)
+    }
+
+    pub fn draw_crosswalk_stripes(&self, g: &mut GfxCtx, prepared: &PreparedInstances) {
+        g.draw_prepared_instances(&self.crosswalk_stripe, prepared);
+    }
+
+    pub fn draw_parking_ticks(&self, g: &mut GfxCtx, prepared: &PreparedInstances) {
+        g.draw_prepared_instances(&self.parking_tick, prepared);
+    }
+}
+
+/// One white stripe of a zebra crosswalk, crossing the X axis at the origin
+pub fn crosswalk_stripe() -> GeomBatch {
+    let origin = Pt2D::new(0.0, 0.0);
+    let line = Line::must_new(
+        origin,
+        origin.project_away(Distance::meters(1.0), Angle::ZERO),
+    );
+    GeomBatch::from(vec![(
+        Color::WHITE,
+        perp_line(line, SIDEWALK_THICKNESS).make_polygons(CROSSWALK_LINE_THICKNESS),
+    )])
+}
+
+/// The white "T" marking the boundary between two parking spots, with the origin where the legs
+/// meet. The parking lane points along the X axis, and the lane's outer edge is towards -Y.
+pub fn parking_tick() -> GeomBatch {
+    let origin = Pt2D::new(0.0, 0.0);
+    let leg_length = Distance::meters(1.0);
+    let mut batch = GeomBatch::new();
+    for angle in [Angle::degrees(90.0), Angle::ZERO, Angle::degrees(180.0)] {
+        batch.push(
+            Color::WHITE,
+            Line::must_new(origin, origin.project_away(leg_length, angle))
+                .make_polygons(PARKING_LINE_THICKNESS),
+        );
+    }
+    batch
+}
+
+/// Where to draw the stripes of a zebra crosswalk
+pub fn crosswalk_stripes(turn: &Turn, color: Color) -> Vec<Instance> {
+    let mut instances = Vec::new();
+    // This size also looks better for shoulders
+    let width = SIDEWALK_THICKNESS;
+    // Start at least width out to not hit sidewalk corners. Also account for the thickness of the
+    // crosswalk line itself. Center the lines inside these two boundaries.
+    let boundary = width;
+    let tile_every = width * 0.6;
+    let line = if let Some(l) = turn.crosswalk_line() {
+        l
+    } else {
+        return instances;
+    };
+
+    let available_length = line.length() - (boundary * 2.0);
+    if available_length > Distance::ZERO {
+        let num_markings = (available_length / tile_every).floor() as usize;
+        let mut dist_along =
+            boundary + (available_length - tile_every * (num_markings as f64)) / 2.0;
+        // TODO Seems to be an off-by-one sometimes. Not enough of these.
+        let err = format!("crosswalk_stripes for {} broke", turn.id);
+        for _ in 0..=num_markings {
+            // Actually every line is a double
+            for dist in [dist_along, dist_along + 2.0 * CROSSWALK_LINE_THICKNESS] {
+                instances.push(Instance {
+                    pt: line.dist_along(dist).expect(&err),
+                    angle: line.angle(),
+                    scale: 1.0,
+                    color,
+                });
+            }
+            dist_along += tile_every;
+        }
+    }
+    instances
+}
+
+/// Where to draw the ticks between parking spots
+pub fn parking_ticks(lane: &Lane, map: &Map, color: Color) -> Vec<Instance> {
+    let mut instances = Vec::new();
+    let num_spots = lane.number_parking_spots(map.get_config());
+    if num_spots > 0 {
+        for idx in 0..=num_spots {
+            let (pt, lane_angle) = lane
+                .lane_center_pts
+                .must_dist_along(map.get_config().street_parking_spot_length * (1.0 + idx as f64));
+            let (perp_angle, angle) = if map.get_config().driving_side == DrivingSide::Right {
+                (lane_angle.rotate_degs(270.0), lane_angle)
+            } else {
+                // The tick is symmetric along the lane, so flipping it points the perp leg the
+                // other way
+                (lane_angle.rotate_degs(90.0), lane_angle.opposite())
+            };
+            // Find the outside of the lane. Actually, shift inside a little bit, since the line
+            // will have thickness, but shouldn't really intersect the adjacent line
+            // when drawn.
+            instances.push(Instance {
+                pt: pt.project_away(lane.width * 0.4, perp_angle),
+                angle,
+                scale: 1.0,
+                color,
+            });
+        }
+    }
+    instances
+}
+
+// TODO copied from DrawLane
+fn perp_line(l: Line, length: Distance) -> Line {
+    let pt1 = l.shift_right(length / 2.0).pt1();
+    let pt2 = l.shift_left(length / 2.0).pt1();
+    Line::must_new(pt1, pt2)
+}
@@ -15,7 +15,9 @@ mod area;
 mod building;
 mod intersection;
 mod lane;
+mod lighting;
 mod map;
+mod markings;
 mod parking_lot;
 mod road;
 pub mod traffic_signal;
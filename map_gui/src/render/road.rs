@@ -1,6 +1,6 @@
 use std::cell::RefCell;
 
-use geom::{Distance, Pt2D, Tessellation};
+use geom::{Distance, Pt2D, Ring, Tessellation};
 use map_model::{Building, LaneType, Map, Road, RoadID, NORMAL_LANE_THICKNESS};
 use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Line, Prerender, Text};
 
@@ -15,6 +15,8 @@ const LABEL_SCALE_FACTOR: f64 = 0.1;
 // Making the label follow the road's curvature usually looks better, but sometimes the letters
 // squish together, so keep this experiment disabled for now.
 const DRAW_CURVEY_LABEL: bool = false;
+// In the 3D camera angles, how high each layer of bridges appears
+const BRIDGE_HEIGHT_PER_LAYER: Distance = Distance::const_meters(5.0);
 
 pub struct DrawRoad {
     pub id: RoadID,
@@ -89,6 +91,7 @@ impl DrawRoad {
         for b in app.map().road_to_buildings(self.id) {
             draw_building_driveway(app, app.map().get_b(*b), &mut batch);
         }
+        draw_bridge_supports(app, r, &mut batch);
 
         batch
     }
@@ -213,3 +216,58 @@ fn draw_building_driveway(app: &dyn AppLike, bldg: &Building, batch: &mut GeomBa
         );
     }
 }
+
+/// In the 3D camera angles, bridges float above the ground. Draw the side of the deck facing the
+/// viewer and some pillars down to the ground, so it's clear which roads pass over others.
+fn draw_bridge_supports(app: &dyn AppLike, r: &Road, batch: &mut GeomBatch) {
+    let up = match app.opts().camera_angle.extrusion_angle() {
+        Some(angle) => angle,
+        None => return,
+    };
+    if r.zorder <= 0 {
+        return;
+    }
+    let height = app
+        .opts()
+        .camera_angle
+        .extrusion_length(BRIDGE_HEIGHT_PER_LAYER * (r.zorder as f64));
+    let down = up.opposite();
+
+    // Hang everything from whichever edge of the road is closer to the viewer
+    let toward_viewer = |pt: Pt2D| {
+        pt.x() * down.normalized_radians().cos() + pt.y() * down.normalized_radians().sin()
+    };
+    let edge = match (
+        r.center_pts.shift_left(r.get_half_width()),
+        r.center_pts.shift_right(r.get_half_width()),
+    ) {
+        (Ok(left), Ok(right)) => {
+            if toward_viewer(left.middle()) > toward_viewer(right.middle()) {
+                left
+            } else {
+                right
+            }
+        }
+        _ => return,
+    };
+
+    let mut pts = edge.points().clone();
+    pts.extend(
+        edge.points()
+            .iter()
+            .rev()
+            .map(|pt| pt.project_away(height, down)),
+    );
+    pts.push(pts[0]);
+    if let Ok(ring) = Ring::new(pts) {
+        batch.push(app.cs().bridge_side, ring.into_polygon());
+    }
+    for (pt, _) in edge.step_along(Distance::meters(30.0), Distance::meters(5.0)) {
+        if let Ok(line) = geom::Line::new(pt, pt.project_away(height, down)) {
+            batch.push(
+                app.cs().bridge_pillar,
+                line.make_polygons(Distance::meters(1.0)),
+            );
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use geom::{Angle, ArrowCap, Circle, Distance, Duration, Line, PolyLine, Pt2D};
 use map_model::{
-    Intersection, IntersectionID, Movement, Stage, StageType, TurnPriority, SIDEWALK_THICKNESS,
+    ControlTrafficSignal, DirectedRoadID, DrivingSide, Intersection, IntersectionID, Movement,
+    Stage, StageType, TurnPriority, TurnType, SIDEWALK_THICKNESS,
 };
 use widgetry::{Color, GeomBatch, Line, Prerender, RewriteColor, Text};
 
@@ -13,7 +14,7 @@ use crate::AppLike;
 
 pub fn draw_signal_stage(
     prerender: &Prerender,
-    stage: &Stage,
+    signal: &ControlTrafficSignal,
     idx: usize,
     i: IntersectionID,
     time_left: Option<Duration>,
@@ -22,6 +23,7 @@ pub fn draw_signal_stage(
     signal_style: TrafficSignalStyle,
 ) {
     let i = app.map().get_i(i);
+    let stage = &signal.stages[idx];
 
     match signal_style {
         TrafficSignalStyle::Brian => {
@@ -125,6 +127,8 @@ pub fn draw_signal_stage(
                 }
             }
 
+            draw_bike_movements(stage, i, batch, app);
+
             for m in dont_walk {
                 batch.append(dont_walk_icon(&i.movements[m], prerender));
             }
@@ -162,6 +166,7 @@ pub fn draw_signal_stage(
                     );
                 }
             }
+            draw_bike_movements(stage, i, batch, app);
             if let Some(t) = time_left {
                 draw_time_left(app, prerender, stage, i, idx, t, batch);
             }
@@ -194,6 +199,208 @@ pub fn draw_signal_stage(
                 draw_time_left(app, prerender, stage, i, idx, t, batch);
             }
         }
+        TrafficSignalStyle::SignalHeads => {
+            draw_signal_heads(prerender, signal, idx, i, time_left, batch, app);
+        }
+    }
+}
+
+/// Movements with a green bike signal get a thin arrow on top, since bikes might go when general
+/// traffic can't.
+pub fn draw_bike_movements(
+    stage: &Stage,
+    i: &Intersection,
+    batch: &mut GeomBatch,
+    app: &dyn AppLike,
+) {
+    for m in &stage.bike_movements {
+        let arrow = i.movements[m]
+            .geom
+            .make_arrow(BIG_ARROW_THICKNESS / 2.0, ArrowCap::Triangle);
+        batch.push(app.cs().bike_trip, arrow.clone());
+        batch.push(Color::BLACK, arrow.to_outline(Distance::meters(0.1)));
+    }
+}
+
+/// Pedestrian countdowns assume people walk this fast
+const PED_COUNTDOWN_SPEED_MPS: f64 = 1.2;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Light {
+    Green,
+    /// A permitted movement that must yield
+    FlashingYellow,
+    Yellow,
+    Red,
+}
+
+/// Draws a signal head with one light per movement from each incoming road, and walk signals with
+/// countdowns on the crosswalks.
+fn draw_signal_heads(
+    prerender: &Prerender,
+    signal: &ControlTrafficSignal,
+    idx: usize,
+    i: &Intersection,
+    time_left: Option<Duration>,
+    batch: &mut GeomBatch,
+    app: &dyn AppLike,
+) {
+    let cs = app.cs();
+    let stage = &signal.stages[idx];
+    let next_stage = &signal.stages[(idx + 1) % signal.stages.len()];
+    // Match the yellow and all-red intervals the simulation uses. Without a time, just show the
+    // stage.
+    let yellow = |m: &Movement| {
+        time_left
+            .map(|t| signal.is_yellow(idx, m.id, false, t))
+            .unwrap_or(false)
+    };
+    let all_red = |m: &Movement| {
+        time_left
+            .map(|t| {
+                let duration = stage.stage_type.simple_duration();
+                let elapsed = duration - t.min(duration);
+                signal.all_red_wait(idx, m.id, false, elapsed).is_some()
+            })
+            .unwrap_or(false)
+    };
+    // Blink once a second
+    let blink_on = (app.sim_time().inner_seconds() as usize) % 2 == 0;
+
+    let mut per_approach: BTreeMap<DirectedRoadID, Vec<&Movement>> = BTreeMap::new();
+    for (id, movement) in &i.movements {
+        if id.crosswalk {
+            if all_red(movement) {
+                batch.append(dont_walk_icon(movement, prerender));
+            } else {
+                draw_ped_signal(
+                    prerender, stage, next_stage, movement, time_left, batch, app,
+                );
+            }
+        } else {
+            per_approach.entry(id.from).or_default().push(movement);
+        }
+    }
+
+    for (_, mut movements) in per_approach {
+        // Match the order of lights from left to right, as a driver would see them
+        movements.sort_by_key(|m| match m.turn_type {
+            TurnType::UTurn => 0,
+            TurnType::Left => 1,
+            TurnType::Straight => 2,
+            _ => 3,
+        });
+        if app.map().get_config().driving_side == DrivingSide::Left {
+            movements.reverse();
+        }
+
+        let heading = movements[0].geom.first_line().angle();
+        let base = Pt2D::center(
+            &movements
+                .iter()
+                .map(|m| m.geom.first_pt())
+                .collect::<Vec<_>>(),
+        )
+        .project_away(Distance::meters(2.0), heading.opposite());
+        let spacing = Distance::meters(1.6);
+        let offset = (movements.len() as f64 - 1.0) / 2.0;
+        let lamps: Vec<Pt2D> = (0..movements.len())
+            .map(|idx| {
+                // Lay the lights out left to right from the driver's point of view
+                let dist = spacing * (idx as f64 - offset);
+                if dist < Distance::ZERO {
+                    base.project_away(-dist, heading.rotate_degs(-90.0))
+                } else {
+                    base.project_away(dist, heading.rotate_degs(90.0))
+                }
+            })
+            .collect();
+
+        // The housing
+        for pair in lamps.windows(2) {
+            if let Ok(line) = Line::new(pair[0], pair[1]) {
+                batch.push(
+                    cs.signal_head_housing,
+                    line.make_polygons(Distance::meters(1.6)),
+                );
+            }
+        }
+        for (movement, pt) in movements.into_iter().zip(lamps) {
+            batch.push(
+                cs.signal_head_housing,
+                Circle::new(pt, Distance::meters(0.8)).to_polygon(),
+            );
+
+            let light = if all_red(movement) {
+                Light::Red
+            } else if yellow(movement) {
+                Light::Yellow
+            } else if stage.protected_movements.contains(&movement.id) {
+                Light::Green
+            } else if stage.yield_movements.contains(&movement.id) {
+                Light::FlashingYellow
+            } else {
+                Light::Red
+            };
+            let color = match light {
+                Light::Green => cs.signal_light_green,
+                Light::FlashingYellow if !blink_on => cs.signal_light_off,
+                Light::FlashingYellow | Light::Yellow => cs.signal_light_yellow,
+                Light::Red => cs.signal_light_red,
+            };
+            batch.push(color, Circle::new(pt, Distance::meters(0.6)).to_polygon());
+
+            // An arrow pointing the way the movement leaves the intersection
+            let exit = movement.geom.last_line().angle();
+            if let Ok(pl) = PolyLine::new(vec![
+                pt.project_away(Distance::meters(0.4), exit.opposite()),
+                pt.project_away(Distance::meters(0.4), exit),
+            ]) {
+                batch.push(
+                    cs.signal_head_housing,
+                    pl.make_arrow(Distance::meters(0.15), ArrowCap::Triangle),
+                );
+            }
+        }
+    }
+
+    draw_stage_number(prerender, i, idx, batch);
+}
+
+/// Walk while there's enough time to cross, then don't walk, counting down the seconds left.
+fn draw_ped_signal(
+    prerender: &Prerender,
+    stage: &Stage,
+    next_stage: &Stage,
+    movement: &Movement,
+    time_left: Option<Duration>,
+    batch: &mut GeomBatch,
+    app: &dyn AppLike,
+) {
+    if !stage.protected_movements.contains(&movement.id) {
+        batch.append(dont_walk_icon(movement, prerender));
+        return;
+    }
+    let crossing_time =
+        Duration::seconds(movement.geom.length().inner_meters() / PED_COUNTDOWN_SPEED_MPS);
+    match time_left {
+        Some(t) if t <= crossing_time && !next_stage.protected_movements.contains(&movement.id) => {
+            batch.append(dont_walk_icon(movement, prerender));
+            let (center, angle) = crosswalk_icon(&movement.geom);
+            batch.append(
+                Text::from(
+                    Line(format!("{}", t.inner_seconds().ceil() as usize))
+                        .fg(app.cs().signal_ped_countdown),
+                )
+                .render_autocropped(prerender)
+                .scale(0.05)
+                .centered_on(center.project_away(Distance::meters(1.5), angle))
+                .rotate_around_batch_center(angle),
+            );
+        }
+        _ => {
+            batch.append(walk_icon(movement, prerender));
+        }
     }
 }
 
@@ -109,7 +109,8 @@ impl<T: 'static> SimpleApp<T> {
         abstutil::logger::setup();
         ctx.canvas.settings = opts.canvas_settings.clone();
 
-        let cs = ColorScheme::new(ctx, opts.color_scheme);
+        let mut cs = ColorScheme::new(ctx, opts.color_scheme);
+        cs.set_layer_palette(opts.layer_palette, opts.layer_patterns);
         // Start with a minimal map
         let map = Map::almost_blank();
         let draw_map = DrawMap::new(ctx, &map, &opts, &cs, &mut Timer::throwaway());
@@ -142,11 +143,10 @@ impl<T: 'static> SimpleApp<T> {
     pub fn draw_unzoomed(&self, g: &mut GfxCtx) {
         g.clear(self.cs.void_background);
         g.redraw(&self.draw_map.boundary_polygon);
-        g.redraw(&self.draw_map.draw_all_areas);
-        g.redraw(&self.draw_map.draw_all_unzoomed_parking_lots);
-        g.redraw(&self.draw_map.draw_all_unzoomed_roads_and_intersections);
-        g.redraw(&self.draw_map.draw_all_buildings);
-        g.redraw(&self.draw_map.draw_all_building_outlines);
+        self.draw_map.draw_unzoomed_areas(g);
+        self.draw_map.draw_unzoomed_parking_lots(g);
+        self.draw_map.draw_unzoomed_roads_and_intersections(g);
+        self.draw_map.draw_unzoomed_buildings(g);
         // Not the building paths
 
         // Still show some shape selection when zoomed out.
@@ -322,6 +322,7 @@ impl<T: 'static> AppLike for SimpleApp<T> {
         } else {
             self.draw_zoomed(g, opts);
         }
+        draw_scale_bar(g, self);
     }
 
     fn make_warper(
@@ -1,22 +1,30 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use abstutil::Counter;
 use geom::{Circle, Distance};
 use map_model::{BuildingID, IntersectionID, LaneID, Map, ParkingLotID, RoadID, TransitStopID};
-use widgetry::mapspace::{ToggleZoomed, ToggleZoomedBuilder};
+use widgetry::mapspace::{DrawCache, ToggleZoomed, ToggleZoomedBuilder};
 use widgetry::tools::{ColorLegend, ColorScale};
-use widgetry::{Color, EventCtx, GeomBatch, Widget};
+use widgetry::{Color, EventCtx, GeomBatch, Pattern, Widget};
 
-use crate::AppLike;
+use crate::{AppLike, ID};
 
 // TODO Tooltips would almost be nice, for cases like pedestrian crowding
 pub struct ColorDiscrete<'a> {
     map: &'a Map,
-    // pub so callers can add stuff in before building
+    /// Darkens the rest of the map when unzoomed
+    fade: Option<Color>,
+    // pub so callers can add stuff in before building. This is drawn on top of the colored
+    // objects.
     pub draw: ToggleZoomedBuilder,
     // Store both, so we can build the legend in the original order later
     pub categories: Vec<(String, Color)>,
-    colors: HashMap<String, Color>,
+    colors: HashMap<String, (Color, Pattern)>,
+    /// If true, each category also gets a different pattern
+    patterns: bool,
+    /// Objects aren't tessellated until building, so that a cached layer can skip that entirely
+    objects: Vec<(ID, Color, Pattern)>,
 }
 
 impl<'a> ColorDiscrete<'a> {
@@ -24,18 +32,21 @@ impl<'a> ColorDiscrete<'a> {
         app: &'a dyn AppLike,
         categories: Vec<(I, Color)>,
     ) -> ColorDiscrete<'a> {
-        let mut draw = ToggleZoomed::builder();
-        draw.unzoomed.push(
-            app.cs().fade_map_dark,
-            app.map().get_boundary_polygon().clone(),
-        );
         let categories: Vec<(String, Color)> =
             categories.into_iter().map(|(k, v)| (k.into(), v)).collect();
+        let patterns = app.cs().layer_patterns;
         ColorDiscrete {
             map: app.map(),
-            draw,
-            colors: categories.iter().cloned().collect(),
+            fade: Some(app.cs().fade_map_dark),
+            draw: ToggleZoomed::builder(),
+            colors: categories
+                .iter()
+                .enumerate()
+                .map(|(idx, (k, color))| (k.clone(), (*color, category_pattern(patterns, idx))))
+                .collect(),
             categories,
+            patterns,
+            objects: Vec::new(),
         }
     }
 
@@ -44,70 +55,143 @@ impl<'a> ColorDiscrete<'a> {
         categories: Vec<(I, Color)>,
     ) -> ColorDiscrete<'a> {
         let mut c = ColorDiscrete::new(app, categories);
-        c.draw.unzoomed = GeomBatch::new();
+        c.disable_fading();
         c
     }
 
+    pub fn disable_fading(&mut self) {
+        self.fade = None;
+    }
+
     pub fn add_l<I: AsRef<str>>(&mut self, l: LaneID, category: I) {
-        let color = self.colors[category.as_ref()];
-        self.draw
-            .unzoomed
-            .push(color, self.map.get_parent(l).get_thick_polygon());
-        let lane = self.map.get_l(l);
-        self.draw
-            .zoomed
-            .push(color.alpha(0.4), lane.get_thick_polygon());
+        self.add(ID::Lane(l), category.as_ref());
     }
 
     pub fn add_r<I: AsRef<str>>(&mut self, r: RoadID, category: I) {
-        let color = self.colors[category.as_ref()];
-        self.draw
-            .unzoomed
-            .push(color, self.map.get_r(r).get_thick_polygon());
-        self.draw
-            .zoomed
-            .push(color.alpha(0.4), self.map.get_r(r).get_thick_polygon());
+        self.add(ID::Road(r), category.as_ref());
     }
 
     pub fn add_i<I: AsRef<str>>(&mut self, i: IntersectionID, category: I) {
-        let color = self.colors[category.as_ref()];
-        self.draw
-            .unzoomed
-            .push(color, self.map.get_i(i).polygon.clone());
-        self.draw
-            .zoomed
-            .push(color.alpha(0.4), self.map.get_i(i).polygon.clone());
+        self.add(ID::Intersection(i), category.as_ref());
     }
 
     pub fn add_b<I: AsRef<str>>(&mut self, b: BuildingID, category: I) {
-        let color = self.colors[category.as_ref()];
-        self.draw
-            .unzoomed
-            .push(color, self.map.get_b(b).polygon.clone());
-        self.draw
-            .zoomed
-            .push(color.alpha(0.4), self.map.get_b(b).polygon.clone());
+        self.add(ID::Building(b), category.as_ref());
     }
 
     pub fn add_ts<I: AsRef<str>>(&mut self, ts: TransitStopID, category: I) {
-        let color = self.colors[category.as_ref()];
-        let pt = self.map.get_ts(ts).sidewalk_pos.pt(self.map);
-        self.draw.zoomed.push(
-            color.alpha(0.4),
-            Circle::new(pt, Distance::meters(5.0)).to_polygon(),
-        );
-        self.draw
-            .unzoomed
-            .push(color, Circle::new(pt, Distance::meters(15.0)).to_polygon());
+        self.add(ID::TransitStop(ts), category.as_ref());
+    }
+
+    fn add(&mut self, id: ID, category: &str) {
+        let (color, pattern) = self.colors[category];
+        self.objects.push((id, color, pattern));
+    }
+
+    /// Tessellates everything, without uploading.
+    pub fn into_builder(self) -> ToggleZoomedBuilder {
+        let map = self.map;
+        let mut draw = ToggleZoomed::builder();
+        if let Some(color) = self.fade {
+            draw.unzoomed
+                .push(color, map.get_boundary_polygon().clone());
+        }
+        for (id, color, pattern) in self.objects {
+            // Stripes need to be closer together on the smaller zoomed-in shapes
+            let unzoomed = |batch: &mut GeomBatch, polygon| {
+                batch.push_pattern(color, polygon, pattern, 10.0);
+            };
+            let zoomed = |batch: &mut GeomBatch, polygon| {
+                batch.push_pattern(color.alpha(0.4), polygon, pattern, 2.0);
+            };
+            match id {
+                ID::Lane(l) => {
+                    unzoomed(&mut draw.unzoomed, map.get_parent(l).get_thick_polygon());
+                    zoomed(&mut draw.zoomed, map.get_l(l).get_thick_polygon());
+                }
+                ID::Road(r) => {
+                    let polygon = map.get_r(r).get_thick_polygon();
+                    unzoomed(&mut draw.unzoomed, polygon.clone());
+                    zoomed(&mut draw.zoomed, polygon);
+                }
+                ID::Intersection(i) => {
+                    unzoomed(&mut draw.unzoomed, map.get_i(i).polygon.clone());
+                    zoomed(&mut draw.zoomed, map.get_i(i).polygon.clone());
+                }
+                ID::Building(b) => {
+                    unzoomed(&mut draw.unzoomed, map.get_b(b).polygon.clone());
+                    zoomed(&mut draw.zoomed, map.get_b(b).polygon.clone());
+                }
+                ID::TransitStop(ts) => {
+                    let pt = map.get_ts(ts).sidewalk_pos.pt(map);
+                    zoomed(
+                        &mut draw.zoomed,
+                        Circle::new(pt, Distance::meters(5.0)).to_polygon(),
+                    );
+                    unzoomed(
+                        &mut draw.unzoomed,
+                        Circle::new(pt, Distance::meters(15.0)).to_polygon(),
+                    );
+                }
+                _ => unreachable!(),
+            }
+        }
+        draw.append(self.draw);
+        draw
     }
 
     pub fn build(self, ctx: &EventCtx) -> (ToggleZoomed, Widget) {
-        let legend = self
-            .categories
-            .into_iter()
-            .map(|(name, color)| ColorLegend::row(ctx, color, name))
-            .collect();
-        (self.draw.build(ctx), Widget::col(legend))
+        let legend = self.legend(ctx);
+        (self.into_builder().build(ctx), legend)
+    }
+
+    /// Like `build`, but if exactly the same layer was built before, reuses the upload. Anything
+    /// added to `draw` directly can't be compared, so that disables caching.
+    pub fn build_cached(
+        self,
+        ctx: &EventCtx,
+        cache: &mut DrawCache<ToggleZoomed>,
+    ) -> (Rc<ToggleZoomed>, Widget) {
+        let legend = self.legend(ctx);
+        if !self.draw.unzoomed.is_empty() || !self.draw.zoomed.is_empty() {
+            return (Rc::new(self.build(ctx).0), legend);
+        }
+        // Colors aren't hashable, so use their exact bits
+        let color_bits = |c: Color| [c.r, c.g, c.b, c.a].map(f32::to_bits);
+        let key = DrawCache::<ToggleZoomed>::key(&(
+            self.fade.map(color_bits),
+            self.objects
+                .iter()
+                .map(|(id, color, pattern)| (id, color_bits(*color), pattern))
+                .collect::<Vec<_>>(),
+        ));
+        (
+            cache.get_or_build(key, || self.into_builder().build(ctx)),
+            legend,
+        )
+    }
+
+    fn legend(&self, ctx: &EventCtx) -> Widget {
+        Widget::col(
+            self.categories
+                .iter()
+                .map(|(name, color)| {
+                    if self.patterns {
+                        ColorLegend::row_with_pattern(ctx, *color, self.colors[name].1, name)
+                    } else {
+                        ColorLegend::row(ctx, *color, name)
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+fn category_pattern(patterns: bool, idx: usize) -> Pattern {
+    if patterns {
+        Pattern::nth(idx)
+    } else {
+        Pattern::Solid
     }
 }
 
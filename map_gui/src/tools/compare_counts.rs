@@ -6,6 +6,7 @@ use widgetry::mapspace::{ObjectID, ToggleZoomed, ToggleZoomedBuilder, World, Wor
 use widgetry::tools::DivergingScale;
 use widgetry::{Color, EventCtx, GeomBatch, GfxCtx, Key, Line, Text, TextExt, Widget};
 
+use crate::colors::ColorScheme;
 use crate::tools::{cmp_count, ColorNetwork};
 use crate::AppLike;
 
@@ -208,9 +209,14 @@ impl CompareCounts {
         Some(self.get_panel_widget(ctx))
     }
 
-    pub fn relative_scale() -> DivergingScale {
+    pub fn relative_scale(cs: &ColorScheme) -> DivergingScale {
         // TODO This is still a bit arbitrary
-        DivergingScale::new(Color::GREEN, Color::grey(0.2), Color::RED).range(0.0, 2.0)
+        DivergingScale::new(
+            cs.better_than_before,
+            Color::grey(0.2),
+            cs.worse_than_before,
+        )
+        .range(0.0, 2.0)
     }
 }
 
@@ -253,7 +259,7 @@ fn calculate_relative_heatmap(
     let min_count = hgram_before.select(Statistic::Min).unwrap();
     let max_count = hgram_before.select(Statistic::Max).unwrap();
 
-    let scale = CompareCounts::relative_scale();
+    let scale = CompareCounts::relative_scale(app.cs());
 
     let mut draw_roads = GeomBatch::new();
     for (r, before, after) in counts_a.per_road.clone().compare(counts_b.per_road.clone()) {
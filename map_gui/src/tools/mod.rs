@@ -17,6 +17,7 @@ pub use self::labels::{DrawRoadLabels, DrawSimpleRoadLabels};
 pub use self::minimap::{Minimap, MinimapControls};
 pub use self::navigate::Navigator;
 pub use self::polygon::EditPolygon;
+pub use self::scale_bar::draw_scale_bar;
 pub use self::title_screen::{Executable, TitleScreen};
 pub use self::trip_files::{TripManagement, TripManagementState};
 pub use self::ui::{
@@ -46,6 +47,7 @@ mod labels;
 mod minimap;
 mod navigate;
 mod polygon;
+mod scale_bar;
 mod title_screen;
 mod trip_files;
 mod ui;
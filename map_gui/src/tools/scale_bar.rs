@@ -0,0 +1,95 @@
+use geom::{Distance, Polygon, Pt2D, Ring, UnitFmt};
+use widgetry::{Color, GeomBatch, GfxCtx, Line, Text};
+
+use crate::AppLike;
+
+/// The scale bar is roughly this many pixels long, then rounded to a nice distance
+const TARGET_WIDTH: f64 = 120.0;
+/// Distance from the bottom-left corner of the screen
+const MARGIN: f64 = 20.0;
+
+/// Draws a scale bar and north arrow over the bottom-left corner of the map, if the player enabled
+/// them in the options.
+pub fn draw_scale_bar(g: &mut GfxCtx, app: &dyn AppLike) {
+    if !app.opts().show_scale_bar || app.opts().minimal_controls {
+        return;
+    }
+
+    let dist = round_distance(
+        Distance::meters(TARGET_WIDTH / g.canvas.cam_zoom),
+        &app.opts().units,
+    );
+    let width = dist.inner_meters() * g.canvas.cam_zoom;
+    let height = 6.0;
+    let (fg, bg) = (Color::BLACK, Color::WHITE.alpha(0.8));
+
+    let mut batch = GeomBatch::new();
+    // The bar, with ticks at both ends and the middle
+    batch.push(
+        bg,
+        Polygon::rectangle(width + 20.0, 60.0).translate(-10.0, -30.0),
+    );
+    batch.push(fg, Polygon::rectangle(width, height));
+    for x in [0.0, width / 2.0, width - 2.0] {
+        batch.push(fg, Polygon::rectangle(2.0, 14.0).translate(x, -8.0));
+    }
+    batch.append(
+        Text::from(Line(dist.to_string(&app.opts().units)).fg(fg))
+            .render_autocropped(g)
+            .scale(0.8)
+            .translate(0.0, -28.0),
+    );
+
+    // The north arrow. The map is never rotated, so north is always up.
+    let arrow_x = width + 30.0;
+    batch.push(
+        bg,
+        Polygon::rectangle(30.0, 60.0).translate(arrow_x - 5.0, -30.0),
+    );
+    batch.push(
+        fg,
+        Ring::must_new(vec![
+            Pt2D::new(arrow_x + 10.0, -8.0),
+            Pt2D::new(arrow_x + 18.0, 10.0),
+            Pt2D::new(arrow_x + 10.0, 6.0),
+            Pt2D::new(arrow_x + 2.0, 10.0),
+            Pt2D::new(arrow_x + 10.0, -8.0),
+        ])
+        .into_polygon(),
+    );
+    batch.append(
+        Text::from(Line("N").fg(fg))
+            .render_autocropped(g)
+            .scale(0.8)
+            .centered_on(Pt2D::new(arrow_x + 10.0, -19.0)),
+    );
+
+    let batch = batch.translate(MARGIN + 10.0, g.canvas.window_height - MARGIN - 30.0);
+    g.fork_screenspace();
+    batch.draw(g);
+    g.unfork();
+}
+
+/// Rounds down to 1, 2, or 5 times a power of ten, in the units the player prefers
+fn round_distance(dist: Distance, fmt: &UnitFmt) -> Distance {
+    let round = |x: f64| -> f64 {
+        let magnitude = 10.0_f64.powf(x.log10().floor());
+        let leading = x / magnitude;
+        let nice = if leading >= 5.0 {
+            5.0
+        } else if leading >= 2.0 {
+            2.0
+        } else {
+            1.0
+        };
+        nice * magnitude
+    };
+
+    if fmt.metric {
+        Distance::meters(round(dist.inner_meters()))
+    } else if dist.to_miles() >= 0.1 {
+        Distance::miles(round(dist.to_miles()))
+    } else {
+        Distance::feet(round(dist.to_feet()))
+    }
+}
@@ -5,13 +5,17 @@ use geom::{Duration, Speed};
 
 use crate::connectivity::Spot;
 use crate::pathfind::{zone_cost, WalkingNode};
-use crate::{BuildingID, Lane, LaneType, Map, PathConstraints, PathStep};
+use crate::{
+    BuildingID, Lane, LaneType, Map, PathConstraints, PathRequest, PathStep, TransitStopID,
+};
 
 #[derive(Clone)]
 pub struct WalkingOptions {
     /// If true, allow walking on shoulders.
     pub allow_shoulders: bool,
     pub walking_speed: Speed,
+    /// If true, ride public transit, waiting at each stop for half of the route's headway.
+    pub use_transit: bool,
 }
 
 impl WalkingOptions {
@@ -19,6 +23,7 @@ impl WalkingOptions {
         WalkingOptions {
             allow_shoulders: true,
             walking_speed: WalkingOptions::default_speed(),
+            use_transit: false,
         }
     }
 
@@ -110,6 +115,12 @@ pub fn all_walking_costs_from(
     for b in map.all_buildings() {
         sidewalk_to_bldgs.insert(b.sidewalk(), b.id);
     }
+    let mut sidewalk_to_stops = MultiMap::new();
+    if opts.use_transit {
+        for ts in map.all_transit_stops().values() {
+            sidewalk_to_stops.insert(ts.sidewalk_pos.lane(), ts.id);
+        }
+    }
 
     let mut results = HashMap::new();
 
@@ -125,7 +136,70 @@ pub fn all_walking_costs_from(
 
         let (r, is_dst_i) = match current.value {
             WalkingNode::SidewalkEndpoint(r, is_dst_i) => (r, is_dst_i),
-            _ => unreachable!(),
+            WalkingNode::RideTransit(ts) => {
+                let stop = map.get_ts(ts);
+                // Board any route serving this stop
+                for route in map.get_routes_serving_stop(ts) {
+                    queue.push(PriorityQueueItem {
+                        cost: current.cost + route.expected_wait(),
+                        value: WalkingNode::OnTransit(route.id, ts),
+                    });
+                }
+                // Or hop off and walk. Buildings along the same sidewalk are reached directly;
+                // the rest are found by walking from either end of the sidewalk.
+                let lane = map.get_l(stop.sidewalk_pos.lane());
+                let speed = PathStep::Lane(lane.id).max_speed_along(
+                    Some(opts.walking_speed),
+                    PathConstraints::Pedestrian,
+                    map,
+                );
+                for b in sidewalk_to_bldgs.get(lane.id) {
+                    let dist = (map.get_b(*b).sidewalk_pos.dist_along()
+                        - stop.sidewalk_pos.dist_along())
+                    .abs();
+                    let bldg_cost = current.cost + dist / speed;
+                    if bldg_cost <= time_limit {
+                        let cost = results.entry(*b).or_insert(bldg_cost);
+                        *cost = (*cost).min(bldg_cost);
+                    }
+                }
+                let dr = lane.get_directed_parent();
+                queue.push(PriorityQueueItem {
+                    cost: current.cost + stop.sidewalk_pos.dist_along() / speed,
+                    value: WalkingNode::SidewalkEndpoint(dr, false),
+                });
+                queue.push(PriorityQueueItem {
+                    cost: current.cost + (lane.length() - stop.sidewalk_pos.dist_along()) / speed,
+                    value: WalkingNode::SidewalkEndpoint(dr, true),
+                });
+                continue;
+            }
+            WalkingNode::OnTransit(route_id, ts) => {
+                // Hop off here
+                queue.push(PriorityQueueItem {
+                    cost: current.cost,
+                    value: WalkingNode::RideTransit(ts),
+                });
+                // Or keep riding to the next stop
+                let route = map.get_tr(route_id);
+                let idx = route.stops.iter().position(|s| *s == ts).unwrap();
+                if let Some(next) = route.stops.get(idx + 1) {
+                    let req = PathRequest::vehicle(
+                        map.get_ts(ts).driving_pos,
+                        map.get_ts(*next).driving_pos,
+                        route.route_type,
+                    );
+                    if let Ok(path) = map.pathfind_v2(req) {
+                        queue.push(PriorityQueueItem {
+                            cost: current.cost + path.get_cost(),
+                            value: WalkingNode::OnTransit(route_id, *next),
+                        });
+                    }
+                }
+                continue;
+            }
+            // Never added to the queue
+            WalkingNode::LeaveMap(_) => unreachable!(),
         };
         let lane = map.get_l(r.must_get_sidewalk(map));
         // Cross the lane
@@ -156,9 +230,22 @@ pub fn all_walking_costs_from(
                     };
                     let bldg_cost = current.cost + dist_to_bldg / speed;
                     if bldg_cost <= time_limit {
-                        results.insert(*b, bldg_cost);
+                        let cost = results.entry(*b).or_insert(bldg_cost);
+                        *cost = (*cost).min(bldg_cost);
                     }
                 }
+                for ts in sidewalk_to_stops.get(lane.id) {
+                    let stop_dist_along = map.get_ts(*ts).sidewalk_pos.dist_along();
+                    let dist_to_stop = if is_dst_i {
+                        sidewalk_len - stop_dist_along
+                    } else {
+                        stop_dist_along
+                    };
+                    queue.push(PriorityQueueItem {
+                        cost: current.cost + dist_to_stop / speed,
+                        value: WalkingNode::RideTransit(*ts),
+                    });
+                }
 
                 queue.push(PriorityQueueItem {
                     cost: current.cost + sidewalk_len / speed,
@@ -8,19 +8,25 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Distance, HashablePt2D, Line, Speed, Time};
+use geom::{Distance, HashablePt2D, Line, LonLat, Speed, Time};
 use osm2streets::{get_lane_specs_ltr, osm, InputRoad};
 
-pub use self::perma::PermanentMapEdits;
-use crate::make::{match_points_to_lanes, snap_driveway, trim_path};
+pub use self::perma::{PermanentEditCmd, PermanentMapEdits};
+pub use self::repair::RepairReport;
+use crate::make::{
+    match_points_to_lanes, slip_lanes, snap_driveway, trim_path, turning_circle_polygon,
+};
 use crate::{
-    connectivity, AccessRestrictions, BuildingID, ControlStopSign, ControlTrafficSignal,
-    IntersectionControl, IntersectionID, LaneID, LaneSpec, Map, MapConfig, Movement, ParkingLotID,
-    PathConstraints, Pathfinder, Road, RoadID, TransitRouteID, TurnID, TurnType, Zone,
+    connectivity, AccessRestrictions, BoardingConfig, BuildingID, ControlStopSign,
+    ControlTrafficSignal, Direction, IntersectionControl, IntersectionID, LaneID, LaneRestriction,
+    LaneReversalSchedule, LaneSpec, LaneType, Map, MapConfig, Movement, ParkingLotID,
+    PathConstraints, Pathfinder, Position, RampMeter, Road, RoadID, StopLayout, TrafficCalming,
+    TransitRouteID, TransitStop, TransitStopID, TurnID, TurnType, VariableSpeedLimit, Zone,
 };
 
 mod compat;
 mod perma;
+mod repair;
 
 /// Represents changes to a map. Note this isn't serializable -- that's what `PermanentMapEdits`
 /// does.
@@ -39,12 +45,34 @@ pub struct MapEdits {
     pub changed_roads: BTreeSet<RoadID>,
     pub original_intersections: BTreeMap<IntersectionID, EditIntersection>,
     pub original_crosswalks: BTreeMap<IntersectionID, EditCrosswalks>,
+    pub original_u_turns: BTreeMap<IntersectionID, bool>,
     pub changed_routes: BTreeSet<TransitRouteID>,
+    pub original_route_stops: BTreeMap<TransitRouteID, Vec<TransitStopID>>,
+    /// None means the stop didn't exist originally
+    pub original_transit_stops: BTreeMap<TransitStopID, Option<EditTransitStop>>,
+    pub original_route_boarding: BTreeMap<TransitRouteID, BoardingConfig>,
 
     /// Some edits are included in the game by default, in data/system/proposals, as "community
     /// proposals." They require a description and may have a link to a write-up.
     pub proposal_description: Vec<String>,
     pub proposal_link: Option<String>,
+    /// Notes explaining parts of the proposal, pinned to places on the map
+    pub annotations: Vec<Annotation>,
+}
+
+/// A note attached to some place in a proposal, like why a bike lane is protected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub pt: LonLat,
+    pub text: String,
+}
+
+/// The editable properties of a transit stop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EditTransitStop {
+    /// On a sidewalk of the stop's road
+    pub sidewalk_pos: Position,
+    pub layout: StopLayout,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -61,6 +89,20 @@ pub struct EditRoad {
     pub lanes_ltr: Vec<LaneSpec>,
     pub speed_limit: Speed,
     pub access_restrictions: AccessRestrictions,
+    /// Keyed by the index into `lanes_ltr`. Only driving lanes can be restricted. When changing
+    /// `lanes_ltr`, use the methods below to keep these attached to the right lanes.
+    #[serde(default)]
+    pub lane_restrictions: BTreeMap<usize, LaneRestriction>,
+    /// Keyed by the index into `lanes_ltr`. Only driving lanes can be reversible.
+    #[serde(default)]
+    pub reversible_lanes: BTreeMap<usize, LaneReversalSchedule>,
+    /// Distance along the road's center line
+    #[serde(default)]
+    pub traffic_calming: Vec<(Distance, TrafficCalming)>,
+    #[serde(default)]
+    pub variable_speed_limit: Option<VariableSpeedLimit>,
+    #[serde(default)]
+    pub ramp_meter: Option<RampMeter>,
 }
 
 /// This must contain all crossing turns at one intersection, each mapped either to Crosswalk or
@@ -74,6 +116,71 @@ impl EditRoad {
             lanes_ltr: get_lane_specs_ltr(&r.osm_tags, cfg),
             speed_limit: r.speed_limit_from_osm(),
             access_restrictions: r.access_restrictions_from_osm(),
+            lane_restrictions: BTreeMap::new(),
+            reversible_lanes: BTreeMap::new(),
+            traffic_calming: r.traffic_calming_from_osm(),
+            variable_speed_limit: None,
+            ramp_meter: None,
+        }
+    }
+
+    /// Remove a lane, keeping the restrictions and schedules on the other lanes attached to them.
+    pub fn remove_lane(&mut self, idx: usize) {
+        self.lanes_ltr.remove(idx);
+        self.lane_restrictions = reindex_after_removal(&mut self.lane_restrictions, idx);
+        self.reversible_lanes = reindex_after_removal(&mut self.reversible_lanes, idx);
+    }
+
+    /// Make room for a lane just inserted into `lanes_ltr` at `idx`.
+    pub fn lane_inserted(&mut self, idx: usize) {
+        self.lane_restrictions = reindex_after_insertion(&mut self.lane_restrictions, idx);
+        self.reversible_lanes = reindex_after_insertion(&mut self.reversible_lanes, idx);
+    }
+
+    /// Move a lane, along with its restriction and schedule.
+    pub fn move_lane(&mut self, from: usize, to: usize) {
+        let restriction = self.lane_restrictions.remove(&from);
+        let schedule = self.reversible_lanes.remove(&from);
+        let spec = self.lanes_ltr[from].clone();
+        self.remove_lane(from);
+        self.lanes_ltr.insert(to, spec);
+        self.lane_inserted(to);
+        if let Some(restriction) = restriction {
+            self.lane_restrictions.insert(to, restriction);
+        }
+        if let Some(schedule) = schedule {
+            self.reversible_lanes.insert(to, schedule);
+        }
+    }
+
+    /// Call after rearranging `lanes_ltr` any other way than `remove_lane`, `lane_inserted`, or
+    /// `move_lane`. Restrictions and schedules follow the lanes that're still there, matched up by
+    /// type and direction in order. Ones on lanes that disappeared are dropped.
+    pub fn lanes_rearranged(&mut self, old_lanes: &[LaneSpec]) {
+        let matches = match_lanes(old_lanes, &self.lanes_ltr);
+        self.lane_restrictions = reindex_matched(&mut self.lane_restrictions, &matches);
+        self.reversible_lanes = reindex_matched(&mut self.reversible_lanes, &matches);
+    }
+
+    /// `turn:lanes` values are copied onto each lane during import. Once the lanes in one
+    /// direction change at all, the values may belong to a different lane, so drop them for that
+    /// direction.
+    fn clear_stale_turn_restrictions(&mut self, old: &EditRoad) {
+        for dir in [Direction::Fwd, Direction::Back] {
+            let layout = |lanes: &[LaneSpec]| -> Vec<(LaneType, Distance)> {
+                lanes
+                    .iter()
+                    .filter(|spec| spec.dir == dir)
+                    .map(|spec| (spec.lt, spec.width))
+                    .collect()
+            };
+            if layout(&old.lanes_ltr) != layout(&self.lanes_ltr) {
+                for spec in &mut self.lanes_ltr {
+                    if spec.dir == dir {
+                        spec.turn_restrictions.clear();
+                    }
+                }
+            }
         }
     }
 
@@ -116,10 +223,82 @@ impl EditRoad {
         if self.access_restrictions != other.access_restrictions {
             changes.push("access restrictions".to_string());
         }
+        if self.lane_restrictions != other.lane_restrictions {
+            changes.push("lane restrictions".to_string());
+        }
+        if self.reversible_lanes != other.reversible_lanes {
+            changes.push("reversible lanes".to_string());
+        }
+        if self.traffic_calming != other.traffic_calming {
+            changes.push("traffic calming".to_string());
+        }
+        if self.variable_speed_limit != other.variable_speed_limit {
+            changes.push("variable speed limit".to_string());
+        }
+        if self.ramp_meter != other.ramp_meter {
+            changes.push("ramp meter".to_string());
+        }
         changes
     }
 }
 
+fn reindex_after_removal<T>(map: &mut BTreeMap<usize, T>, idx: usize) -> BTreeMap<usize, T> {
+    std::mem::take(map)
+        .into_iter()
+        .filter(|(i, _)| *i != idx)
+        .map(|(i, x)| if i > idx { (i - 1, x) } else { (i, x) })
+        .collect()
+}
+
+fn reindex_after_insertion<T>(map: &mut BTreeMap<usize, T>, idx: usize) -> BTreeMap<usize, T> {
+    std::mem::take(map)
+        .into_iter()
+        .map(|(i, x)| if i >= idx { (i + 1, x) } else { (i, x) })
+        .collect()
+}
+
+fn reindex_matched<T>(
+    map: &mut BTreeMap<usize, T>,
+    matches: &BTreeMap<usize, usize>,
+) -> BTreeMap<usize, T> {
+    std::mem::take(map)
+        .into_iter()
+        .filter_map(|(i, x)| matches.get(&i).map(|new_i| (*new_i, x)))
+        .collect()
+}
+
+/// Pairs up old and new lane indices, using the longest common subsequence of lane types and
+/// directions.
+fn match_lanes(old: &[LaneSpec], new: &[LaneSpec]) -> BTreeMap<usize, usize> {
+    let same = |a: &LaneSpec, b: &LaneSpec| a.lt == b.lt && a.dir == b.dir;
+    // best[i][j] is the length of the common subsequence of old[i..] and new[j..]
+    let mut best = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            best[i][j] = if same(&old[i], &new[j]) {
+                best[i + 1][j + 1] + 1
+            } else {
+                best[i + 1][j].max(best[i][j + 1])
+            };
+        }
+    }
+
+    let mut matches = BTreeMap::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if same(&old[i], &new[j]) {
+            matches.insert(i, j);
+            i += 1;
+            j += 1;
+        } else if best[i + 1][j] >= best[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum EditCmd {
     ChangeRoad {
@@ -142,6 +321,29 @@ pub enum EditCmd {
         old: EditCrosswalks,
         new: EditCrosswalks,
     },
+    /// Allows or bans vehicles U-turning at an intersection
+    ChangeUTurns {
+        i: IntersectionID,
+        old: bool,
+        new: bool,
+    },
+    ChangeRouteStops {
+        id: TransitRouteID,
+        old: Vec<TransitStopID>,
+        new: Vec<TransitStopID>,
+    },
+    /// Creates, moves, changes the layout of, or deletes a stop. None means the stop doesn't
+    /// exist.
+    ChangeTransitStop {
+        id: TransitStopID,
+        old: Option<EditTransitStop>,
+        new: Option<EditTransitStop>,
+    },
+    ChangeRouteBoarding {
+        id: TransitRouteID,
+        old: BoardingConfig,
+        new: BoardingConfig,
+    },
 }
 
 pub struct EditEffects {
@@ -152,6 +354,7 @@ pub struct EditEffects {
     pub added_turns: BTreeSet<TurnID>,
     pub deleted_turns: BTreeSet<TurnID>,
     pub changed_parking_lots: BTreeSet<ParkingLotID>,
+    pub changed_transit_stops: BTreeSet<TransitStopID>,
     modified_lanes: BTreeSet<LaneID>,
 }
 
@@ -161,20 +364,34 @@ impl MapEdits {
             edits_name: "TODO temporary".to_string(),
             proposal_description: Vec::new(),
             proposal_link: None,
+            annotations: Vec::new(),
             commands: Vec::new(),
             merge_zones: true,
 
             changed_roads: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             original_crosswalks: BTreeMap::new(),
+            original_u_turns: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
+            original_route_stops: BTreeMap::new(),
+            original_transit_stops: BTreeMap::new(),
+            original_route_boarding: BTreeMap::new(),
         }
     }
 
-    /// Load map edits from a JSON file. Strip out any commands that're broken because they don't
-    /// match the current map. If the resulting edits are totally empty, consider that a failure --
-    /// the edits likely don't cover this map at all.
+    /// Load map edits from a JSON file. Adapt or strip out any commands that're broken because
+    /// they don't match the current map. If the resulting edits are totally empty, consider that a
+    /// failure -- the edits likely don't cover this map at all.
     pub fn load_from_file(map: &Map, path: String, timer: &mut Timer) -> Result<MapEdits> {
+        Self::load_from_file_with_report(map, path, timer).map(|(edits, _)| edits)
+    }
+
+    /// Like `load_from_file`, but also describe what didn't match the current map.
+    pub fn load_from_file_with_report(
+        map: &Map,
+        path: String,
+        timer: &mut Timer,
+    ) -> Result<(MapEdits, RepairReport)> {
         let perma = match abstio::maybe_read_json::<PermanentMapEdits>(path.clone(), timer) {
             Ok(perma) => perma,
             Err(_) => {
@@ -196,17 +413,25 @@ impl MapEdits {
             );
         }
 
-        let edits = perma.into_edits_permissive(map);
+        let (edits, report) = perma.into_edits_repaired(map);
         if edits.commands.is_empty() {
             bail!("None of the edits apply to this map");
         }
-        Ok(edits)
+        Ok((edits, report))
     }
 
-    /// Load map edits from the given JSON bytes. Strip out any commands that're broken because
-    /// they don't match the current map. If the resulting edits are totally empty, consider that a
-    /// failure -- the edits likely don't cover this map at all.
+    /// Load map edits from the given JSON bytes. Adapt or strip out any commands that're broken
+    /// because they don't match the current map. If the resulting edits are totally empty,
+    /// consider that a failure -- the edits likely don't cover this map at all.
     pub fn load_from_bytes(map: &Map, bytes: Vec<u8>) -> Result<MapEdits> {
+        Self::load_from_bytes_with_report(map, bytes).map(|(edits, _)| edits)
+    }
+
+    /// Like `load_from_bytes`, but also describe what didn't match the current map.
+    pub fn load_from_bytes_with_report(
+        map: &Map,
+        bytes: Vec<u8>,
+    ) -> Result<(MapEdits, RepairReport)> {
         let perma = match abstutil::from_json::<PermanentMapEdits>(&bytes) {
             Ok(perma) => perma,
             Err(_) => {
@@ -216,11 +441,11 @@ impl MapEdits {
                 compat::upgrade(value, map)?
             }
         };
-        let edits = perma.into_edits_permissive(map);
+        let (edits, report) = perma.into_edits_repaired(map);
         if edits.commands.is_empty() {
             bail!("None of the edits apply to this map");
         }
-        Ok(edits)
+        Ok((edits, report))
     }
 
     fn save(&self, map: &Map) {
@@ -239,7 +464,11 @@ impl MapEdits {
         self.changed_roads.clear();
         self.original_intersections.clear();
         self.original_crosswalks.clear();
+        self.original_u_turns.clear();
         self.changed_routes.clear();
+        self.original_route_stops.clear();
+        self.original_transit_stops.clear();
+        self.original_route_boarding.clear();
 
         for cmd in &self.commands {
             match cmd {
@@ -256,9 +485,29 @@ impl MapEdits {
                         self.original_crosswalks.insert(*i, old.clone());
                     }
                 }
+                EditCmd::ChangeUTurns { i, old, .. } => {
+                    if !self.original_u_turns.contains_key(i) {
+                        self.original_u_turns.insert(*i, *old);
+                    }
+                }
                 EditCmd::ChangeRouteSchedule { id, .. } => {
                     self.changed_routes.insert(*id);
                 }
+                EditCmd::ChangeRouteStops { id, ref old, .. } => {
+                    if !self.original_route_stops.contains_key(id) {
+                        self.original_route_stops.insert(*id, old.clone());
+                    }
+                }
+                EditCmd::ChangeTransitStop { id, old, .. } => {
+                    if !self.original_transit_stops.contains_key(id) {
+                        self.original_transit_stops.insert(*id, *old);
+                    }
+                }
+                EditCmd::ChangeRouteBoarding { id, old, .. } => {
+                    if !self.original_route_boarding.contains_key(id) {
+                        self.original_route_boarding.insert(*id, *old);
+                    }
+                }
             }
         }
 
@@ -269,10 +518,18 @@ impl MapEdits {
             .retain(|i, orig| map.get_i_edit(*i) != orig.clone());
         self.original_crosswalks
             .retain(|i, orig| map.get_i_crosswalks_edit(*i) != orig.clone());
+        self.original_u_turns
+            .retain(|i, orig| map.get_i(*i).allow_u_turns != *orig);
         self.changed_routes.retain(|br| {
             let r = map.get_tr(*br);
             r.spawn_times != r.orig_spawn_times
         });
+        self.original_route_stops
+            .retain(|id, orig| &map.get_tr(*id).stops != orig);
+        self.original_transit_stops
+            .retain(|id, orig| map.get_ts_edit(*id) != *orig);
+        self.original_route_boarding
+            .retain(|id, orig| map.get_tr(*id).boarding != *orig);
     }
 
     /// Assumes update_derived has been called.
@@ -298,6 +555,13 @@ impl MapEdits {
                 new: map.get_i_crosswalks_edit(*i),
             });
         }
+        for (i, old) in &self.original_u_turns {
+            self.commands.push(EditCmd::ChangeUTurns {
+                i: *i,
+                old: *old,
+                new: map.get_i(*i).allow_u_turns,
+            });
+        }
         for r in &self.changed_routes {
             let r = map.get_tr(*r);
             self.commands.push(EditCmd::ChangeRouteSchedule {
@@ -306,6 +570,39 @@ impl MapEdits {
                 old: r.orig_spawn_times.clone(),
             });
         }
+        // Create and move stops before routes refer to them, and only delete stops afterwards
+        for (id, old) in &self.original_transit_stops {
+            if let Some(new) = map.get_ts_edit(*id) {
+                self.commands.push(EditCmd::ChangeTransitStop {
+                    id: *id,
+                    old: *old,
+                    new: Some(new),
+                });
+            }
+        }
+        for (id, old) in &self.original_route_stops {
+            self.commands.push(EditCmd::ChangeRouteStops {
+                id: *id,
+                old: old.clone(),
+                new: map.get_tr(*id).stops.clone(),
+            });
+        }
+        for (id, old) in &self.original_transit_stops {
+            if map.get_ts_edit(*id).is_none() {
+                self.commands.push(EditCmd::ChangeTransitStop {
+                    id: *id,
+                    old: *old,
+                    new: None,
+                });
+            }
+        }
+        for (id, old) in &self.original_route_boarding {
+            self.commands.push(EditCmd::ChangeRouteBoarding {
+                id: *id,
+                old: *old,
+                new: map.get_tr(*id).boarding,
+            });
+        }
     }
 
     /// Pick apart changed_roads and figure out if an entire road was edited, or just a few lanes.
@@ -319,6 +616,9 @@ impl MapEdits {
             // What exactly changed?
             if r.speed_limit != orig.speed_limit
                 || r.access_restrictions != orig.access_restrictions
+                || r.traffic_calming != orig.traffic_calming
+                || r.variable_speed_limit.is_some()
+                || r.ramp_meter.is_some()
                 // If a lane was added or deleted, figuring out if any were modified is kind of
                 // unclear -- just mark the entire road.
                 || r.lanes.len() != orig.lanes_ltr.len()
@@ -326,7 +626,12 @@ impl MapEdits {
                 roads.insert(r.id);
             } else {
                 for (l, spec) in r.lanes.iter().zip(orig.lanes_ltr.iter()) {
-                    if l.dir != spec.dir || l.lane_type != spec.lt || l.width != spec.width {
+                    if l.dir != spec.dir
+                        || l.lane_type != spec.lt
+                        || l.width != spec.width
+                        || r.lane_restrictions.contains_key(&l.id.offset)
+                        || r.reversible_lanes.contains_key(&l.id.offset)
+                    {
                         lanes.insert(l.id);
                     }
                 }
@@ -376,9 +681,37 @@ impl EditCmd {
                 EditIntersection::Closed => format!("close {}", i),
             },
             EditCmd::ChangeCrosswalks { i, .. } => format!("crosswalks at {}", i),
+            EditCmd::ChangeUTurns { i, new, .. } => {
+                if *new {
+                    format!("allow U-turns at {}", i)
+                } else {
+                    format!("ban U-turns at {}", i)
+                }
+            }
             EditCmd::ChangeRouteSchedule { id, .. } => {
                 format!("reschedule route {}", map.get_tr(*id).short_name)
             }
+            EditCmd::ChangeRouteStops { id, old, new } => {
+                details.push(format!("{} stops, previously {}", new.len(), old.len()));
+                format!("change stops of route {}", map.get_tr(*id).short_name)
+            }
+            EditCmd::ChangeTransitStop { id, old, new } => match (old, new) {
+                (None, _) => format!("add transit stop on road #{}", id.road.0),
+                (_, None) => format!("delete transit stop on road #{}", id.road.0),
+                (Some(old), Some(new)) => {
+                    if old.layout != new.layout {
+                        details.push(format!("{:?}, previously {:?}", new.layout, old.layout));
+                    }
+                    if old.sidewalk_pos == new.sidewalk_pos {
+                        format!("change layout of transit stop on road #{}", id.road.0)
+                    } else {
+                        format!("move transit stop on road #{}", id.road.0)
+                    }
+                }
+            },
+            EditCmd::ChangeRouteBoarding { id, .. } => {
+                format!("change vehicles of route {}", map.get_tr(*id).short_name)
+            }
         };
         (summary, details)
     }
@@ -392,9 +725,21 @@ impl EditCmd {
                 }
 
                 modify_lanes(map, *r, new.lanes_ltr.clone(), effects);
+                // Stop signs are recalculated below, so the slip lane needs to be current
+                slip_lanes::update_slip_lane(map, *r);
                 let road = &mut map.roads[r.0];
                 road.speed_limit = new.speed_limit;
                 road.access_restrictions = new.access_restrictions.clone();
+                road.lane_restrictions = new.lane_restrictions.clone();
+                road.reversible_lanes = new.reversible_lanes.clone();
+                road.traffic_calming = new.traffic_calming.clone();
+                road.variable_speed_limit = new.variable_speed_limit.clone();
+                road.ramp_meter = new.ramp_meter.clone();
+                let lanes = &road.lanes;
+                let is_driving =
+                    |idx: &usize| lanes.get(*idx).map(|l| l.lane_type) == Some(LaneType::Driving);
+                road.lane_restrictions.retain(|idx, _| is_driving(idx));
+                road.reversible_lanes.retain(|idx, _| is_driving(idx));
 
                 effects.changed_roads.insert(road.id);
                 for i in [road.src_i, road.dst_i] {
@@ -461,13 +806,80 @@ impl EditCmd {
                     map.mut_turn(*turn).turn_type = *turn_type;
                 }
             }
+            EditCmd::ChangeUTurns { i, new, .. } => {
+                if map.get_i(*i).allow_u_turns == *new {
+                    return;
+                }
+                map.intersections[i.0].allow_u_turns = *new;
+                effects.changed_intersections.insert(*i);
+                recalculate_turns(*i, map, effects);
+            }
             EditCmd::ChangeRouteSchedule { id, new, .. } => {
                 map.transit_routes[id.0].spawn_times = new.clone();
             }
+            EditCmd::ChangeRouteStops { id, new, .. } => {
+                map.transit_routes[id.0].stops = new.clone();
+            }
+            EditCmd::ChangeRouteBoarding { id, new, .. } => {
+                map.transit_routes[id.0].boarding = *new;
+            }
+            EditCmd::ChangeTransitStop { id, ref new, .. } => {
+                if map.get_ts_edit(*id) == *new {
+                    return;
+                }
+                let new = match new {
+                    Some(new) => *new,
+                    None => {
+                        // Routes have to stop using a stop before it can be deleted
+                        if let Some(route) =
+                            map.transit_routes.iter().find(|r| r.stops.contains(id))
+                        {
+                            warn!("Can't delete {}, because {} still uses it", id, route.id);
+                            return;
+                        }
+                        effects.changed_transit_stops.insert(*id);
+                        map.roads[id.road.0].transit_stops.remove(id);
+                        map.transit_stops.remove(id);
+                        return;
+                    }
+                };
+                effects.changed_transit_stops.insert(*id);
+                let driving_pos = match TransitStop::find_driving_pos(new.sidewalk_pos, map) {
+                    Some(pos) => pos,
+                    None => {
+                        warn!(
+                            "No lane for buses next to {}, so can't place {}",
+                            new.sidewalk_pos, id
+                        );
+                        return;
+                    }
+                };
+                if let Some(stop) = map.transit_stops.get_mut(id) {
+                    stop.sidewalk_pos = new.sidewalk_pos;
+                    stop.driving_pos = driving_pos;
+                    stop.layout = new.layout;
+                } else {
+                    let name = format!("New stop on {}", map.get_r(id.road).get_name(None));
+                    map.roads[id.road.0].transit_stops.insert(*id);
+                    map.transit_stops.insert(
+                        *id,
+                        TransitStop {
+                            id: *id,
+                            name,
+                            gtfs_id: String::new(),
+                            driving_pos,
+                            sidewalk_pos: new.sidewalk_pos,
+                            is_train_stop: false,
+                            layout: new.layout,
+                        },
+                    );
+                }
+            }
         }
     }
 
-    fn undo(self) -> EditCmd {
+    /// The command that reverses this one
+    pub fn undo(self) -> EditCmd {
         match self {
             EditCmd::ChangeRoad { r, old, new } => EditCmd::ChangeRoad {
                 r,
@@ -484,11 +896,31 @@ impl EditCmd {
                 old: new,
                 new: old,
             },
+            EditCmd::ChangeUTurns { i, old, new } => EditCmd::ChangeUTurns {
+                i,
+                old: new,
+                new: old,
+            },
             EditCmd::ChangeRouteSchedule { id, old, new } => EditCmd::ChangeRouteSchedule {
                 id,
                 old: new,
                 new: old,
             },
+            EditCmd::ChangeRouteStops { id, old, new } => EditCmd::ChangeRouteStops {
+                id,
+                old: new,
+                new: old,
+            },
+            EditCmd::ChangeTransitStop { id, old, new } => EditCmd::ChangeTransitStop {
+                id,
+                old: new,
+                new: old,
+            },
+            EditCmd::ChangeRouteBoarding { id, old, new } => EditCmd::ChangeRouteBoarding {
+                id,
+                old: new,
+                new: old,
+            },
         }
     }
 }
@@ -625,7 +1057,13 @@ fn recalculate_intersection_polygon(
     )
     .unwrap();
 
-    map.intersections[i.0].polygon = results.intersection_polygon;
+    // osm2streets doesn't know about turning circles, so redraw the bulb around the new polygon
+    map.intersections[i.0].polygon = if intersection.turning_circle {
+        turning_circle_polygon(results.intersection_polygon.center(), changed_road_width)
+    } else {
+        results.intersection_polygon
+    };
+    slip_lanes::cut_out_slip_lanes(map, i);
 
     // Recalculate trimmed centers
     let mut affected = Vec::new();
@@ -697,6 +1135,9 @@ fn fix_building_driveways(map: &mut Map, input: Vec<BuildingID>, effects: &mut E
                 .ok()
         }) {
             Some((sidewalk_pos, driveway_geom)) => {
+                let old_road = map.get_b(id).sidewalk_pos.lane().road;
+                map.road_to_buildings.remove(old_road, id);
+                map.road_to_buildings.insert(sidewalk_pos.lane().road, id);
                 let b = &mut map.buildings[id.0];
                 b.sidewalk_pos = sidewalk_pos;
                 b.driveway_geom = driveway_geom.to_polyline();
@@ -779,6 +1220,11 @@ impl Map {
             lanes_ltr: r.lane_specs(),
             speed_limit: r.speed_limit,
             access_restrictions: r.access_restrictions.clone(),
+            lane_restrictions: r.lane_restrictions.clone(),
+            reversible_lanes: r.reversible_lanes.clone(),
+            traffic_calming: r.traffic_calming.clone(),
+            variable_speed_limit: r.variable_speed_limit.clone(),
+            ramp_meter: r.ramp_meter.clone(),
         }
     }
 
@@ -786,6 +1232,7 @@ impl Map {
         let old = self.get_r_edit(r);
         let mut new = old.clone();
         f(&mut new);
+        new.clear_stale_turn_restrictions(&old);
         EditCmd::ChangeRoad { r, old, new }
     }
 
@@ -811,6 +1258,28 @@ impl Map {
         EditCrosswalks(turns)
     }
 
+    /// The editable properties of a transit stop, or None if it doesn't exist.
+    pub fn get_ts_edit(&self, id: TransitStopID) -> Option<EditTransitStop> {
+        self.maybe_get_ts(id).map(|ts| EditTransitStop {
+            sidewalk_pos: ts.sidewalk_pos,
+            layout: ts.layout,
+        })
+    }
+
+    /// Picks an unused ID for a new transit stop on some road.
+    pub fn new_transit_stop_id(&self, r: RoadID) -> TransitStopID {
+        let idx = self
+            .get_r(r)
+            .transit_stops
+            .iter()
+            .chain(self.edits.original_transit_stops.keys())
+            .filter(|ts| ts.road == r)
+            .map(|ts| ts.idx + 1)
+            .max()
+            .unwrap_or(0);
+        TransitStopID { road: r, idx }
+    }
+
     pub fn save_edits(&self) {
         // Don't overwrite the current edits with the compressed first. Otherwise, undo/redo order
         // in the UI gets messed up.
@@ -852,6 +1321,7 @@ impl Map {
             added_turns: BTreeSet::new(),
             deleted_turns: BTreeSet::new(),
             changed_parking_lots: BTreeSet::new(),
+            changed_transit_stops: BTreeSet::new(),
             modified_lanes: BTreeSet::new(),
         };
 
@@ -873,6 +1343,19 @@ impl Map {
             }
         }
 
+        // Zones only depend on access restrictions, so remember them for roads about to change
+        let mut old_access_restrictions = BTreeMap::new();
+        for cmd in self.edits.commands[start_at_idx..]
+            .iter()
+            .chain(new_edits.commands[start_at_idx..].iter())
+        {
+            if let EditCmd::ChangeRoad { r, .. } = cmd {
+                old_access_restrictions
+                    .entry(*r)
+                    .or_insert_with(|| self.get_r(*r).access_restrictions.clone());
+            }
+        }
+
         timer.start_iter("undo old edits", self.edits.commands.len() - start_at_idx);
         for _ in start_at_idx..self.edits.commands.len() {
             timer.next();
@@ -891,10 +1374,15 @@ impl Map {
         }
 
         timer.start("re-snap buildings");
+        // Only buildings along a modified road could be affected
         let mut recalc_buildings = Vec::new();
-        for b in self.all_buildings() {
-            if effects.modified_lanes.contains(&b.sidewalk()) {
-                recalc_buildings.push(b.id);
+        let modified_roads: BTreeSet<RoadID> =
+            effects.modified_lanes.iter().map(|l| l.road).collect();
+        for r in modified_roads {
+            for b in self.road_to_buildings(r) {
+                if effects.modified_lanes.contains(&self.get_b(*b).sidewalk()) {
+                    recalc_buildings.push(*b);
+                }
             }
         }
         fix_building_driveways(self, recalc_buildings, &mut effects);
@@ -939,7 +1427,10 @@ impl Map {
         self.pathfinder_dirty = true;
 
         // Update zones after setting the new edits, since it'll pull merge_zones from there
-        if !effects.changed_roads.is_empty() || merge_zones_changed {
+        let access_restrictions_changed = old_access_restrictions
+            .into_iter()
+            .any(|(r, old)| self.get_r(r).access_restrictions != old);
+        if access_restrictions_changed || merge_zones_changed {
             self.zones = Zone::make_all(self);
         }
 
@@ -960,8 +1451,6 @@ impl Map {
             .changed_intersections
             .extend(more_changed_intersections);
 
-        self.recalculate_road_to_buildings();
-
         effects
     }
 
@@ -1010,3 +1499,78 @@ impl Map {
         self.edits_generation
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LaneRestrictionKind;
+
+    #[test]
+    fn test_lanes_rearranged() {
+        let lane = |lt, dir| LaneSpec {
+            lt,
+            dir,
+            width: Distance::meters(3.0),
+            turn_restrictions: Vec::new(),
+        };
+        let bus_only = LaneRestriction {
+            kind: LaneRestrictionKind::BusOnly,
+            hours: Vec::new(),
+        };
+
+        let mut road = EditRoad {
+            lanes_ltr: vec![
+                lane(LaneType::Sidewalk, Direction::Back),
+                lane(LaneType::Parking, Direction::Back),
+                lane(LaneType::Driving, Direction::Back),
+                lane(LaneType::Driving, Direction::Fwd),
+                lane(LaneType::Driving, Direction::Fwd),
+                lane(LaneType::Sidewalk, Direction::Fwd),
+            ],
+            speed_limit: Speed::miles_per_hour(25.0),
+            access_restrictions: AccessRestrictions::new(),
+            lane_restrictions: BTreeMap::new(),
+            reversible_lanes: BTreeMap::new(),
+            traffic_calming: Vec::new(),
+            variable_speed_limit: None,
+            ramp_meter: None,
+        };
+        road.lane_restrictions.insert(4, bus_only.clone());
+
+        // Bike lanes get added next to both sidewalks, replacing the parking lane
+        let old_lanes = road.lanes_ltr.clone();
+        road.lanes_ltr = vec![
+            lane(LaneType::Sidewalk, Direction::Back),
+            lane(LaneType::Biking, Direction::Back),
+            lane(LaneType::Driving, Direction::Back),
+            lane(LaneType::Driving, Direction::Fwd),
+            lane(LaneType::Driving, Direction::Fwd),
+            lane(LaneType::Biking, Direction::Fwd),
+            lane(LaneType::Sidewalk, Direction::Fwd),
+        ];
+        road.lanes_rearranged(&old_lanes);
+        assert_eq!(
+            road.lane_restrictions,
+            vec![(4, bus_only.clone())].into_iter().collect()
+        );
+
+        // Now a bike lane gets inserted before the driving lanes. The restriction stays with the
+        // outer forwards driving lane.
+        let old_lanes = road.lanes_ltr.clone();
+        road.lanes_ltr
+            .insert(3, lane(LaneType::Biking, Direction::Fwd));
+        road.lanes_rearranged(&old_lanes);
+        assert_eq!(
+            road.lane_restrictions,
+            vec![(5, bus_only)].into_iter().collect()
+        );
+
+        // Flipping every lane's direction leaves nothing to match the restriction to
+        let old_lanes = road.lanes_ltr.clone();
+        for spec in &mut road.lanes_ltr {
+            spec.dir = spec.dir.opposite();
+        }
+        road.lanes_rearranged(&old_lanes);
+        assert!(road.lane_restrictions.is_empty());
+    }
+}
@@ -5,10 +5,16 @@ use serde::{Deserialize, Serialize};
 
 use abstio::MapName;
 use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::Time;
+use geom::{Distance, LonLat, Time};
 
-use crate::edits::{EditCmd, EditCrosswalks, EditIntersection, EditRoad, MapEdits};
-use crate::{osm, ControlStopSign, IntersectionID, Map, MovementID, OriginalRoad, TurnType};
+use crate::edits::repair::{repair_cmd, RepairReport};
+use crate::edits::{
+    Annotation, EditCmd, EditCrosswalks, EditIntersection, EditRoad, EditTransitStop, MapEdits,
+};
+use crate::{
+    osm, BoardingConfig, ControlStopSign, IntersectionID, LaneID, Map, MovementID, OriginalRoad,
+    Position, RoadID, StopLayout, TransitStopID, TurnType,
+};
 
 // Manually change this to attempt to preserve edits after major OSM updates.
 const IGNORE_OLD_LANES: bool = false;
@@ -31,6 +37,9 @@ pub struct PermanentMapEdits {
     pub proposal_description: Vec<String>,
     /// The link is optional even for proposals
     pub proposal_link: Option<String>,
+    /// Older edits don't have this
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -55,6 +64,21 @@ pub struct PermanentEditCrosswalks {
     turns: BTreeMap<traffic_signal_data::Turn, TurnType>,
 }
 
+/// Stops created by edits don't have a GTFS ID, so refer to every stop by its road.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PermanentTransitStop {
+    road: OriginalRoad,
+    idx: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PermanentEditTransitStop {
+    /// The offset of the sidewalk lane within the stop's road
+    sidewalk: usize,
+    dist_along: Distance,
+    layout: StopLayout,
+}
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Serialize, Deserialize, Clone)]
 pub enum PermanentEditCmd {
@@ -62,6 +86,10 @@ pub enum PermanentEditCmd {
         r: OriginalRoad,
         new: EditRoad,
         old: EditRoad,
+        /// Where the road's center line was when the edit was made, to recognize the road when its
+        /// OSM IDs change. Older edits don't have this.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        geometry: Vec<LonLat>,
     },
     ChangeIntersection {
         i: osm::NodeID,
@@ -73,11 +101,31 @@ pub enum PermanentEditCmd {
         new: PermanentEditCrosswalks,
         old: PermanentEditCrosswalks,
     },
+    ChangeUTurns {
+        i: osm::NodeID,
+        new: bool,
+        old: bool,
+    },
     ChangeRouteSchedule {
         gtfs_id: String,
         old: Vec<Time>,
         new: Vec<Time>,
     },
+    ChangeRouteStops {
+        gtfs_id: String,
+        old: Vec<PermanentTransitStop>,
+        new: Vec<PermanentTransitStop>,
+    },
+    ChangeTransitStop {
+        stop: PermanentTransitStop,
+        old: Option<PermanentEditTransitStop>,
+        new: Option<PermanentEditTransitStop>,
+    },
+    ChangeRouteBoarding {
+        gtfs_id: String,
+        old: BoardingConfig,
+        new: BoardingConfig,
+    },
 }
 
 impl EditCmd {
@@ -87,6 +135,9 @@ impl EditCmd {
                 r: map.get_r(*r).orig_id,
                 new: new.clone(),
                 old: old.clone(),
+                geometry: map
+                    .get_gps_bounds()
+                    .convert_back(map.get_r(*r).untrimmed_center_pts.points()),
             },
             EditCmd::ChangeIntersection { i, new, old } => PermanentEditCmd::ChangeIntersection {
                 i: map.get_i(*i).orig_id,
@@ -98,6 +149,11 @@ impl EditCmd {
                 new: new.to_permanent(map),
                 old: old.to_permanent(map),
             },
+            EditCmd::ChangeUTurns { i, new, old } => PermanentEditCmd::ChangeUTurns {
+                i: map.get_i(*i).orig_id,
+                new: *new,
+                old: *old,
+            },
             EditCmd::ChangeRouteSchedule { id, old, new } => {
                 PermanentEditCmd::ChangeRouteSchedule {
                     gtfs_id: map.get_tr(*id).gtfs_id.clone(),
@@ -105,6 +161,23 @@ impl EditCmd {
                     new: new.clone(),
                 }
             }
+            EditCmd::ChangeRouteStops { id, old, new } => PermanentEditCmd::ChangeRouteStops {
+                gtfs_id: map.get_tr(*id).gtfs_id.clone(),
+                old: old.iter().map(|ts| ts.to_permanent(map)).collect(),
+                new: new.iter().map(|ts| ts.to_permanent(map)).collect(),
+            },
+            EditCmd::ChangeTransitStop { id, old, new } => PermanentEditCmd::ChangeTransitStop {
+                stop: id.to_permanent(map),
+                old: old.map(|ts| ts.to_permanent()),
+                new: new.map(|ts| ts.to_permanent()),
+            },
+            EditCmd::ChangeRouteBoarding { id, old, new } => {
+                PermanentEditCmd::ChangeRouteBoarding {
+                    gtfs_id: map.get_tr(*id).gtfs_id.clone(),
+                    old: *old,
+                    new: *new,
+                }
+            }
         }
     }
 }
@@ -112,7 +185,7 @@ impl EditCmd {
 impl PermanentEditCmd {
     pub fn into_cmd(self, map: &Map) -> Result<EditCmd> {
         match self {
-            PermanentEditCmd::ChangeRoad { r, new, old } => {
+            PermanentEditCmd::ChangeRoad { r, new, old, .. } => {
                 let id = map.find_r_by_osm_id(r)?;
                 let num_current = map.get_r(id).lanes.len();
                 // The basemap changed -- it'd be pretty hard to understand the original
@@ -160,13 +233,103 @@ impl PermanentEditCmd {
                         .with_context(|| format!("old ChangeCrosswalks of {} invalid", i))?,
                 })
             }
+            PermanentEditCmd::ChangeUTurns { i, new, old } => {
+                let id = map.find_i_by_osm_id(i)?;
+                Ok(EditCmd::ChangeUTurns { i: id, new, old })
+            }
             PermanentEditCmd::ChangeRouteSchedule { gtfs_id, old, new } => {
                 let id = map
                     .find_tr_by_gtfs(&gtfs_id)
                     .ok_or_else(|| anyhow!("can't find {}", gtfs_id))?;
                 Ok(EditCmd::ChangeRouteSchedule { id, old, new })
             }
+            PermanentEditCmd::ChangeRouteStops { gtfs_id, old, new } => {
+                let id = map
+                    .find_tr_by_gtfs(&gtfs_id)
+                    .ok_or_else(|| anyhow!("can't find {}", gtfs_id))?;
+                Ok(EditCmd::ChangeRouteStops {
+                    id,
+                    old: old
+                        .into_iter()
+                        .map(|ts| ts.with_permanent(map))
+                        .collect::<Result<Vec<_>>>()?,
+                    new: new
+                        .into_iter()
+                        .map(|ts| ts.with_permanent(map))
+                        .collect::<Result<Vec<_>>>()?,
+                })
+            }
+            PermanentEditCmd::ChangeTransitStop { stop, old, new } => {
+                let id = stop.with_permanent(map)?;
+                Ok(EditCmd::ChangeTransitStop {
+                    id,
+                    old: old.map(|ts| ts.with_permanent(id.road, map)).transpose()?,
+                    new: new.map(|ts| ts.with_permanent(id.road, map)).transpose()?,
+                })
+            }
+            PermanentEditCmd::ChangeRouteBoarding { gtfs_id, old, new } => {
+                let id = map
+                    .find_tr_by_gtfs(&gtfs_id)
+                    .ok_or_else(|| anyhow!("can't find {}", gtfs_id))?;
+                Ok(EditCmd::ChangeRouteBoarding { id, old, new })
+            }
+        }
+    }
+}
+
+impl TransitStopID {
+    fn to_permanent(self, map: &Map) -> PermanentTransitStop {
+        PermanentTransitStop {
+            road: map.get_r(self.road).orig_id,
+            idx: self.idx,
+        }
+    }
+}
+
+impl PermanentTransitStop {
+    fn with_permanent(self, map: &Map) -> Result<TransitStopID> {
+        Ok(TransitStopID {
+            road: map.find_r_by_osm_id(self.road)?,
+            idx: self.idx,
+        })
+    }
+}
+
+impl EditTransitStop {
+    fn to_permanent(self) -> PermanentEditTransitStop {
+        PermanentEditTransitStop {
+            sidewalk: self.sidewalk_pos.lane().offset,
+            dist_along: self.sidewalk_pos.dist_along(),
+            layout: self.layout,
+        }
+    }
+}
+
+impl PermanentEditTransitStop {
+    fn with_permanent(self, r: RoadID, map: &Map) -> Result<EditTransitStop> {
+        let road = map.get_r(r);
+        if self.sidewalk >= road.lanes.len() || !road.lanes[self.sidewalk].is_walkable() {
+            bail!(
+                "{} doesn't have a sidewalk at lane {}",
+                road.orig_id,
+                self.sidewalk
+            );
+        }
+        let lane = LaneID {
+            road: r,
+            offset: self.sidewalk,
+        };
+        if self.dist_along > map.get_l(lane).length() {
+            bail!(
+                "transit stop at {} is past the end of {}",
+                self.dist_along,
+                lane
+            );
         }
+        Ok(EditTransitStop {
+            sidewalk_pos: Position::new(lane, self.dist_along),
+            layout: self.layout,
+        })
     }
 }
 
@@ -180,6 +343,7 @@ impl MapEdits {
             version: 12,
             proposal_description: self.proposal_description.clone(),
             proposal_link: self.proposal_link.clone(),
+            annotations: self.annotations.clone(),
             commands: self.commands.iter().map(|cmd| cmd.to_perma(map)).collect(),
             merge_zones: self.merge_zones,
         }
@@ -194,6 +358,7 @@ impl PermanentMapEdits {
             edits_name: self.edits_name,
             proposal_description: self.proposal_description,
             proposal_link: self.proposal_link,
+            annotations: self.annotations,
             commands: self
                 .commands
                 .into_iter()
@@ -204,39 +369,56 @@ impl PermanentMapEdits {
             changed_roads: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             original_crosswalks: BTreeMap::new(),
+            original_u_turns: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
+            original_route_stops: BTreeMap::new(),
+            original_transit_stops: BTreeMap::new(),
+            original_route_boarding: BTreeMap::new(),
         };
         edits.update_derived(map);
         Ok(edits)
     }
 
     /// Transform permanent edits to MapEdits, looking up the map IDs by the hopefully stabler OSM
-    /// IDs. Strip out commands that're broken, but log warnings.
+    /// IDs. Adapt commands that only partly match the current map, and strip out ones that're
+    /// totally broken, but log warnings.
     pub fn into_edits_permissive(self, map: &Map) -> MapEdits {
+        self.into_edits_repaired(map).0
+    }
+
+    /// Like `into_edits_permissive`, but also describe which commands were adapted or dropped.
+    pub fn into_edits_repaired(self, map: &Map) -> (MapEdits, RepairReport) {
+        let mut report = RepairReport::default();
+        let mut commands = Vec::new();
+        for cmd in self.commands {
+            commands.extend(repair_cmd(cmd, map, &mut report));
+        }
+        for x in &report.adapted {
+            warn!("Adapted command: {}", x);
+        }
+        for x in &report.dropped {
+            warn!("Skipping broken command: {}", x);
+        }
+
         let mut edits = MapEdits {
             edits_name: self.edits_name,
             proposal_description: self.proposal_description,
             proposal_link: self.proposal_link,
-            commands: self
-                .commands
-                .into_iter()
-                .filter_map(|cmd| match cmd.into_cmd(map) {
-                    Ok(cmd) => Some(cmd),
-                    Err(err) => {
-                        warn!("Skipping broken command: {}", err);
-                        None
-                    }
-                })
-                .collect(),
+            annotations: self.annotations,
+            commands,
             merge_zones: self.merge_zones,
 
             changed_roads: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             original_crosswalks: BTreeMap::new(),
+            original_u_turns: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
+            original_route_stops: BTreeMap::new(),
+            original_transit_stops: BTreeMap::new(),
+            original_route_boarding: BTreeMap::new(),
         };
         edits.update_derived(map);
-        edits
+        (edits, report)
     }
 
     /// Get the human-friendly of these edits. If they have a description, the first line is the
@@ -269,7 +451,7 @@ impl EditIntersection {
 }
 
 impl PermanentEditIntersection {
-    fn with_permanent(self, i: IntersectionID, map: &Map) -> Result<EditIntersection> {
+    pub(crate) fn with_permanent(self, i: IntersectionID, map: &Map) -> Result<EditIntersection> {
         match self {
             PermanentEditIntersection::StopSign { must_stop } => {
                 let mut translated_must_stop = BTreeMap::new();
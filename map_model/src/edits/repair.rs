@@ -0,0 +1,332 @@
+//! Edits refer to roads and intersections by OSM IDs, but those still change when the basemap is
+//! rebuilt from newer OSM data -- ways get split, lanes get retagged, and intersections get
+//! consolidated. Rather than dropping every command that doesn't exactly match the current map,
+//! try to adapt it. Roads are matched by their OSM way and where they are, so a way ID that now
+//! belongs to something elsewhere isn't mistaken for the edited road.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use anyhow::Result;
+
+use geom::{Distance, LonLat, PolyLine};
+
+use super::perma::{PermanentEditCmd, PermanentEditIntersection};
+use crate::edits::{EditCmd, EditIntersection, EditRoad};
+use crate::{osm, ControlStopSign, IntersectionID, Map, OriginalRoad, Road, RoadID};
+
+/// A road still matches the edited one if its center line stays this close to the recorded one
+const MAX_DRIFT: Distance = Distance::const_meters(15.0);
+
+/// Describes how edits made against an older version of a map were matched to the current one.
+#[derive(Clone, Debug, Default)]
+pub struct RepairReport {
+    /// How many commands applied exactly as they were
+    pub unchanged: usize,
+    /// Commands that only partly matched the current map, and how they were adapted
+    pub adapted: Vec<String>,
+    /// Commands that couldn't be matched at all, and why
+    pub dropped: Vec<String>,
+}
+
+impl RepairReport {
+    /// True if every command applied exactly as it was
+    pub fn is_clean(&self) -> bool {
+        self.adapted.is_empty() && self.dropped.is_empty()
+    }
+
+    /// A summary meant for players
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{} changes still match this map, {} were adapted, and {} were dropped.",
+            self.unchanged,
+            self.adapted.len(),
+            self.dropped.len()
+        )];
+        for x in &self.adapted {
+            lines.push(format!("Adapted: {}", x));
+        }
+        for x in &self.dropped {
+            lines.push(format!("Dropped: {}", x));
+        }
+        lines
+    }
+}
+
+/// Returns the commands matching the original one in the current map. If a road was split, there
+/// may be several; if nothing matches, there are none.
+pub(crate) fn repair_cmd(
+    cmd: PermanentEditCmd,
+    map: &Map,
+    report: &mut RepairReport,
+) -> Vec<EditCmd> {
+    let err = match cmd.clone().into_cmd(map) {
+        Ok(new_cmd) => {
+            let moved = match &cmd {
+                PermanentEditCmd::ChangeRoad { r, geometry, .. } => {
+                    !matches_geometry(map.get_r(map.find_r_by_osm_id(*r).unwrap()), geometry, map)
+                }
+                _ => false,
+            };
+            if !moved {
+                report.unchanged += 1;
+                return vec![new_cmd];
+            }
+            // repair_road describes this better
+            anyhow!("road moved")
+        }
+        Err(err) => err,
+    };
+
+    let result = match cmd {
+        PermanentEditCmd::ChangeRoad {
+            r,
+            new,
+            old,
+            geometry,
+        } => repair_road(r, new, old, &geometry, map),
+        PermanentEditCmd::ChangeIntersection { i, new, old } => {
+            repair_intersection(i, new, old, map)
+        }
+        // Crosswalks and transit edits are too detailed to guess about
+        _ => Err(err),
+    };
+    match result {
+        Ok((cmds, description)) => {
+            report.adapted.push(description);
+            cmds
+        }
+        Err(err) => {
+            report.dropped.push(err.to_string());
+            Vec::new()
+        }
+    }
+}
+
+fn repair_road(
+    r: OriginalRoad,
+    new: EditRoad,
+    old: EditRoad,
+    geometry: &[LonLat],
+    map: &Map,
+) -> Result<(Vec<EditCmd>, String)> {
+    if let Ok(id) = map.find_r_by_osm_id(r) {
+        if !matches_geometry(map.get_r(id), geometry, map) {
+            bail!("{} is somewhere else in this map now", r);
+        }
+        // The road is still there, but its lanes are different
+        return Ok((
+            vec![adapt_lanes(id, &new, &old, map)?],
+            format!(
+                "the lanes on {} changed, so only the speed limit and access restrictions were \
+                 kept",
+                r
+            ),
+        ));
+    }
+
+    // The way was probably split into several roads
+    let pieces = trace_way(r, geometry, map)?;
+    let mut cmds = Vec::new();
+    let mut lanes_changed = false;
+    for id in &pieces {
+        if map.get_r(*id).lanes.len() == old.lanes_ltr.len() {
+            cmds.push(EditCmd::ChangeRoad {
+                r: *id,
+                new: new.clone(),
+                old: old.clone(),
+            });
+        } else {
+            cmds.push(adapt_lanes(*id, &new, &old, map)?);
+            lanes_changed = true;
+        }
+    }
+    let mut description = if pieces.len() == 1 {
+        format!("{} is {} now", r, map.get_r(pieces[0]).orig_id)
+    } else {
+        format!(
+            "{} is {} roads now; all of them were changed",
+            r,
+            pieces.len()
+        )
+    };
+    if lanes_changed {
+        description.push_str(", but only the speed limit and access restrictions on some");
+    }
+    Ok((cmds, description))
+}
+
+/// If the edit didn't touch lanes, keep the rest of it.
+fn adapt_lanes(id: RoadID, new: &EditRoad, old: &EditRoad, map: &Map) -> Result<EditCmd> {
+    let road = map.get_r(id);
+    if new.lanes_ltr != old.lanes_ltr {
+        bail!(
+            "{} has {} lanes now, but {} in the edits, which changed the lanes",
+            road.orig_id,
+            road.lanes.len(),
+            old.lanes_ltr.len()
+        );
+    }
+    let orig = EditRoad::get_orig_from_osm(road, map.get_config());
+    let mut adapted = orig.clone();
+    adapted.speed_limit = new.speed_limit;
+    adapted.access_restrictions = new.access_restrictions.clone();
+    adapted.lane_restrictions = new.lane_restrictions.clone();
+    adapted.reversible_lanes = new.reversible_lanes.clone();
+    adapted.traffic_calming = new.traffic_calming.clone();
+    adapted.variable_speed_limit = new.variable_speed_limit.clone();
+    adapted.ramp_meter = new.ramp_meter.clone();
+    Ok(EditCmd::ChangeRoad {
+        r: id,
+        new: adapted,
+        old: orig,
+    })
+}
+
+/// Find the roads along the same OSM way and in the same place that connect the original
+/// endpoints. If one endpoint was consolidated into a different intersection, fall back to the
+/// only piece of the way touching the other endpoint. If both were, use every piece of the way
+/// along the recorded geometry.
+fn trace_way(r: OriginalRoad, geometry: &[LonLat], map: &Map) -> Result<Vec<RoadID>> {
+    let same_way: Vec<&Road> = map
+        .all_roads()
+        .iter()
+        .filter(|road| road.orig_id.osm_way_id == r.osm_way_id)
+        .collect();
+    if same_way.is_empty() {
+        bail!("{} isn't in this map anymore", r.osm_way_id);
+    }
+    let candidates: Vec<&Road> = same_way
+        .into_iter()
+        .filter(|road| matches_geometry(road, geometry, map))
+        .collect();
+    if candidates.is_empty() {
+        bail!("{} is somewhere else in this map now", r.osm_way_id);
+    }
+
+    let (start, end) = match (map.find_i_by_osm_id(r.i1), map.find_i_by_osm_id(r.i2)) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Ok(i), Err(_)) | (Err(_), Ok(i)) => {
+            let touching: Vec<RoadID> = candidates
+                .iter()
+                .filter(|road| road.src_i == i || road.dst_i == i)
+                .map(|road| road.id)
+                .collect();
+            if touching.len() == 1 {
+                return Ok(touching);
+            }
+            bail!("one end of {} was merged into another intersection", r);
+        }
+        (Err(_), Err(_)) => {
+            if geometry.is_empty() {
+                bail!("both ends of {} were merged into other intersections", r);
+            }
+            return Ok(candidates.into_iter().map(|road| road.id).collect());
+        }
+    };
+
+    // Breadth-first search, only using roads along the way
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    let mut visited = BTreeSet::new();
+    visited.insert(start);
+    let mut came_from: BTreeMap<IntersectionID, RoadID> = BTreeMap::new();
+    while let Some(current) = queue.pop_front() {
+        if current == end {
+            let mut pieces = Vec::new();
+            let mut i = end;
+            while i != start {
+                let piece = came_from[&i];
+                pieces.push(piece);
+                i = map.get_r(piece).other_endpt(i);
+            }
+            pieces.reverse();
+            return Ok(pieces);
+        }
+        for road in &candidates {
+            if road.src_i == current || road.dst_i == current {
+                let next = road.other_endpt(current);
+                if visited.insert(next) {
+                    came_from.insert(next, road.id);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    bail!("{} doesn't connect its original endpoints anymore", r)
+}
+
+/// Is the road still along the center line recorded in the edits? Pieces of a split road match
+/// too. Edits without a recorded geometry can only trust the OSM IDs.
+fn matches_geometry(road: &Road, geometry: &[LonLat], map: &Map) -> bool {
+    if geometry.is_empty() {
+        return true;
+    }
+    let recorded = match map.get_gps_bounds().try_convert(geometry) {
+        Some(pts) => match PolyLine::new(pts) {
+            Ok(pl) => pl,
+            Err(_) => return true,
+        },
+        // Off the edge of this map
+        None => return false,
+    };
+    road.untrimmed_center_pts
+        .points()
+        .iter()
+        .all(|pt| recorded.project_pt(*pt).dist_to(*pt) <= MAX_DRIFT)
+}
+
+fn repair_intersection(
+    i: osm::NodeID,
+    new: PermanentEditIntersection,
+    old: PermanentEditIntersection,
+    map: &Map,
+) -> Result<(Vec<EditCmd>, String)> {
+    let id = map
+        .find_i_by_osm_id(i)
+        .map_err(|_| anyhow!("{} was merged into another intersection", i))?;
+    Ok((
+        vec![EditCmd::ChangeIntersection {
+            i: id,
+            new: adapt_control(new, id, map)?,
+            old: adapt_control(old, id, map)?,
+        }],
+        format!(
+            "the roads at {} changed, so only some stop signs were kept",
+            i
+        ),
+    ))
+}
+
+/// Stop signs only match if the intersection has exactly the same roads. Instead, keep the signs
+/// on roads that still exist, and use the defaults for the rest.
+fn adapt_control(
+    control: PermanentEditIntersection,
+    id: IntersectionID,
+    map: &Map,
+) -> Result<EditIntersection> {
+    let err = match control.clone().with_permanent(id, map) {
+        Ok(control) => {
+            return Ok(control);
+        }
+        Err(err) => err,
+    };
+    if let PermanentEditIntersection::StopSign { must_stop } = control {
+        let mut ss = ControlStopSign::new(map, id);
+        let mut matched = 0;
+        for (r, stop) in must_stop {
+            if let Some(road) = map
+                .find_r_by_osm_id(r)
+                .ok()
+                .and_then(|r| ss.roads.get_mut(&r))
+            {
+                road.must_stop = stop;
+                matched += 1;
+            }
+        }
+        if matched == 0 {
+            bail!("none of the roads at the stop sign {} match anymore", id);
+        }
+        return Ok(EditIntersection::StopSign(ss));
+    }
+    Err(err)
+}
@@ -36,30 +36,42 @@ use abstio::MapName;
 use abstutil::{
     deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
 };
-use geom::{Bounds, GPSBounds, Polygon};
+use geom::{Bounds, GPSBounds, Polygon, Pt2D};
 pub use osm2streets::{
     osm, BufferType, Direction, DrivingSide, IntersectionControl, IntersectionKind, LaneSpec,
     LaneType, MapConfig, NamePerLanguage, OriginalRoad, RestrictionType, NORMAL_LANE_THICKNESS,
     SIDEWALK_THICKNESS,
 };
-pub use raw_map::{Amenity, AmenityType, AreaType, CrossingType};
+pub use raw_map::{Amenity, AmenityType, AreaType, CrossingType, TrafficCalming};
 
 pub use crate::city::City;
 pub use crate::edits::{
-    EditCmd, EditEffects, EditIntersection, EditRoad, MapEdits, PermanentMapEdits,
+    Annotation, EditCmd, EditEffects, EditIntersection, EditRoad, EditTransitStop, MapEdits,
+    PermanentEditCmd, PermanentMapEdits, RepairReport,
 };
-pub use crate::make::RawToMapOptions;
+pub use crate::make::{ImportWarning, RawToMapOptions};
 pub use crate::objects::area::{Area, AreaID};
 pub use crate::objects::block::{Block, Perimeter};
 pub use crate::objects::building::{Building, BuildingID, BuildingType, OffstreetParking};
 pub use crate::objects::intersection::{Intersection, IntersectionID};
-pub use crate::objects::lane::{CommonEndpoint, Lane, LaneID, PARKING_LOT_SPOT_LENGTH};
+pub use crate::objects::lane::{
+    CommonEndpoint, Lane, LaneID, LaneRestriction, LaneRestrictionKind, LaneReversalSchedule,
+    PARKING_LOT_SPOT_LENGTH,
+};
 pub use crate::objects::movement::{CompressedMovementID, Movement, MovementID};
 pub use crate::objects::parking_lot::{ParkingLot, ParkingLotID};
-pub use crate::objects::road::{DirectedRoadID, Road, RoadID, RoadSideID, SideOfRoad};
+pub use crate::objects::road::{
+    DirectedRoadID, RampMeter, Road, RoadID, RoadSideID, SideOfRoad, SpeedLimitSource,
+    VariableSpeedLimit,
+};
 pub use crate::objects::stop_signs::{ControlStopSign, RoadWithStopSign};
-pub use crate::objects::traffic_signals::{ControlTrafficSignal, Stage, StageType};
-pub use crate::objects::transit::{TransitRoute, TransitRouteID, TransitStop, TransitStopID};
+pub use crate::objects::traffic_signals::{
+    ControlTrafficSignal, LeadLagInterval, Stage, StageType, ALL_RED_INTERVAL, YELLOW_INTERVAL,
+};
+pub use crate::objects::transit::{
+    BoardingConfig, FarePayment, StopLayout, TransitRoute, TransitRouteID, TransitStop,
+    TransitStopID,
+};
 pub use crate::objects::turn::{Turn, TurnID, TurnPriority, TurnType};
 pub use crate::objects::zone::{AccessRestrictions, Zone};
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn};
@@ -116,6 +128,10 @@ pub struct Map {
     zones: Vec<Zone>,
 
     name: MapName,
+    /// Problems found while importing, for people fixing the OSM data
+    import_warnings: Vec<ImportWarning>,
+    /// Only used for drawing
+    street_lamps: Vec<Pt2D>,
 
     #[serde(skip_serializing, skip_deserializing)]
     edits: MapEdits,
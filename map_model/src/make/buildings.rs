@@ -7,7 +7,7 @@ use abstutil::{Tags, Timer};
 use geom::{Distance, HashablePt2D, Line};
 use raw_map::RawBuilding;
 
-use crate::make::{match_points_to_lanes, trim_path};
+use crate::make::{match_points_to_lanes, trim_path, ImportWarning};
 use crate::{
     osm, Amenity, Building, BuildingID, BuildingType, LaneID, Map, NamePerLanguage,
     OffstreetParking,
@@ -18,6 +18,7 @@ pub fn make_all_buildings(
     input: &BTreeMap<osm::OsmID, RawBuilding>,
     map: &Map,
     keep_bldg_tags: bool,
+    warnings: &mut Vec<ImportWarning>,
     timer: &mut Timer,
 ) -> Vec<Building> {
     timer.start("convert buildings");
@@ -56,6 +57,14 @@ pub fn make_all_buildings(
                         "Skipping building {} because front path has 0 length",
                         orig_id
                     );
+                    warnings.push(ImportWarning {
+                        category: "buildings".to_string(),
+                        osm_id: Some(orig_id),
+                        pt: bldg_center.to_pt2d(),
+                        message:
+                            "This building was skipped, because its center is right on a sidewalk"
+                                .to_string(),
+                    });
                     continue;
                 }
             };
@@ -63,12 +72,7 @@ pub fn make_all_buildings(
             let id = BuildingID(results.len());
 
             let mut rng = XorShiftRng::seed_from_u64(orig_id.inner() as u64);
-            // TODO is it worth using height or building:height as an alternative if not tagged?
-            let levels = b
-                .osm_tags
-                .get("building:levels")
-                .and_then(|x| x.parse::<f64>().ok())
-                .unwrap_or(1.0);
+            let levels = get_levels(&b.osm_tags);
 
             results.push(Building {
                 id,
@@ -114,6 +118,14 @@ pub fn make_all_buildings(
                 sidewalk_pos: *sidewalk_pos,
                 driveway_geom: sidewalk_line.to_polyline(),
             });
+        } else {
+            warnings.push(ImportWarning {
+                category: "buildings".to_string(),
+                osm_id: Some(orig_id),
+                pt: bldg_center.to_pt2d(),
+                message: "This building was skipped, because it's more than 1km from any sidewalk"
+                    .to_string(),
+            });
         }
     }
 
@@ -139,6 +151,43 @@ fn get_address(tags: &Tags, sidewalk: LaneID, map: &Map) -> String {
     }
 }
 
+/// Buildings are tagged with `building:levels`, a height, or more often nothing at all.
+fn get_levels(tags: &Tags) -> f64 {
+    if let Some(levels) = tags
+        .get("building:levels")
+        .and_then(|x| x.parse::<f64>().ok())
+    {
+        // Roof levels are counted separately
+        let roof = tags
+            .get("roof:levels")
+            .and_then(|x| x.parse::<f64>().ok())
+            .unwrap_or(0.0);
+        return levels + roof;
+    }
+    for key in ["height", "building:height"] {
+        if let Some(height) = tags.get(key).and_then(|x| parse_height(x)) {
+            return (height.inner_meters() / METERS_PER_LEVEL).round().max(1.0);
+        }
+    }
+    1.0
+}
+
+/// A rough guess used to turn heights into levels
+const METERS_PER_LEVEL: f64 = 3.5;
+
+/// Heights are meters by default, but may have units: `12`, `12 m`, `40 ft`, or `40'`.
+fn parse_height(x: &str) -> Option<Distance> {
+    let x = x.trim();
+    if let Some(feet) = x.strip_suffix("ft").or_else(|| x.strip_suffix('\'')) {
+        return feet.trim().parse::<f64>().ok().map(Distance::feet);
+    }
+    x.trim_end_matches('m')
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(Distance::meters)
+}
+
 fn classify_bldg(
     tags: &Tags,
     amenities: &[Amenity],
@@ -221,6 +270,13 @@ fn classify_bldg(
         //  - building type. e.g. apartment vs single family
         //  - regional/cultural norms
         residents = (area_sq_meters / 10.0) as usize;
+    } else if tags.is_any("landuse", vec!["commercial", "retail", "industrial"]) {
+        // A generic building, but the land around it is used for work. This comes from
+        // landuse areas in convert_osm.
+        commercial = true;
+        workers = (area_sq_meters / 10.0) as usize;
+    } else if tags.is("landuse", "education") {
+        return BuildingType::Empty;
     } else {
         residents = rng.gen_range(0..2);
     }
@@ -241,3 +297,38 @@ fn classify_bldg(
         num_housing_units: 1,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_height() {
+        assert_eq!(parse_height("12"), Some(Distance::meters(12.0)));
+        assert_eq!(parse_height("12.5 m"), Some(Distance::meters(12.5)));
+        assert_eq!(parse_height("12m"), Some(Distance::meters(12.0)));
+        assert_eq!(parse_height("40 ft"), Some(Distance::feet(40.0)));
+        assert_eq!(parse_height("40'"), Some(Distance::feet(40.0)));
+        assert_eq!(parse_height("tall"), None);
+        assert_eq!(parse_height(""), None);
+    }
+
+    #[test]
+    fn test_get_levels() {
+        let mut tags = Tags::empty();
+        assert_eq!(get_levels(&tags), 1.0);
+
+        tags.insert("height", "10.5");
+        assert_eq!(get_levels(&tags), 3.0);
+
+        // Explicit levels win over the height
+        tags.insert("building:levels", "5");
+        tags.insert("roof:levels", "1");
+        assert_eq!(get_levels(&tags), 6.0);
+
+        // Very short buildings still have one level
+        let mut tags = Tags::empty();
+        tags.insert("building:height", "1 m");
+        assert_eq!(get_levels(&tags), 1.0);
+    }
+}
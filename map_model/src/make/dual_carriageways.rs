@@ -0,0 +1,129 @@
+//! Where two divided roads cross, OSM has four small intersections connected by short one-way
+//! pieces of each carriageway. Where a divided road crosses a normal road, there are two small
+//! intersections. Both cases produce tiny intersection polygons and nonsense turns between them.
+//! Optionally mark the short roads in between as internal to one junction, so osm2streets merges
+//! the cluster into a single intersection.
+
+use std::collections::BTreeMap;
+
+use geom::Distance;
+use osm2streets::{IntersectionID, Road, RoadID};
+use raw_map::RawMap;
+
+use crate::{osm, LaneType};
+
+/// Medians wider than this probably really separate two intersections
+const MAX_MEDIAN_WIDTH: Distance = Distance::const_meters(40.0);
+/// The two carriageways of one divided road must be parallel, within this many degrees
+const MAX_ANGLE_DIFF: f64 = 30.0;
+
+pub fn consolidate_crossings(raw: &mut RawMap) {
+    let driving_side = raw.streets.config.driving_side;
+    let mut internal = Vec::new();
+    for road in raw.streets.roads.values() {
+        if road.internal_junction_road || road.src_i == road.dst_i {
+            continue;
+        }
+        if road.get_untrimmed_center_line(driving_side).length() > MAX_MEDIAN_WIDTH {
+            continue;
+        }
+        if tags(raw, road)
+            .and_then(|tags| tags.get(osm::HIGHWAY))
+            .map(|hwy| hwy.starts_with("motorway"))
+            .unwrap_or(false)
+        {
+            continue;
+        }
+
+        // The same divided road has to pass through both ends. Where two divided roads cross,
+        // this also holds for the short pieces of each carriageway, because the other divided
+        // road passes through both of their ends.
+        let src = divided_roads_through(raw, road.src_i, road.id);
+        let dst = divided_roads_through(raw, road.dst_i, road.id);
+        if src.iter().any(|(name, src_roads)| {
+            dst.get(name)
+                .map(|dst_roads| carriageways_match(raw, road, src_roads, dst_roads))
+                .unwrap_or(false)
+        }) {
+            internal.push(road.id);
+        }
+    }
+
+    info!(
+        "Merging {} short roads between dual carriageways",
+        internal.len()
+    );
+    for r in internal {
+        raw.streets
+            .roads
+            .get_mut(&r)
+            .unwrap()
+            .internal_junction_road = true;
+    }
+}
+
+/// The divided roads passing through an intersection, not counting one road, keyed by name. One
+/// carriageway of a divided road shows up as two one-way roads with the same name.
+fn divided_roads_through(
+    raw: &RawMap,
+    i: IntersectionID,
+    ignore: RoadID,
+) -> BTreeMap<String, Vec<RoadID>> {
+    let mut by_name: BTreeMap<String, Vec<RoadID>> = BTreeMap::new();
+    for r in &raw.streets.intersections[&i].roads {
+        if *r == ignore {
+            continue;
+        }
+        let road = &raw.streets.roads[r];
+        if !is_oneway(road) {
+            continue;
+        }
+        if let Some(name) = tags(raw, road).and_then(|tags| tags.get("name")) {
+            by_name
+                .entry(name.clone())
+                .or_insert_with(Vec::new)
+                .push(*r);
+        }
+    }
+    by_name.retain(|_, roads| roads.len() >= 2);
+    by_name
+}
+
+/// Unrelated one-way roads can share a name. Only treat the roads at both ends of the short road
+/// as the two carriageways of one divided road if they run parallel and close together.
+fn carriageways_match(
+    raw: &RawMap,
+    short: &Road,
+    src_roads: &[RoadID],
+    dst_roads: &[RoadID],
+) -> bool {
+    let src_pt = raw.streets.intersections[&short.src_i].point;
+    let dst_pt = raw.streets.intersections[&short.dst_i].point;
+    src_roads.iter().all(|src_r| {
+        let src_pl = &raw.streets.roads[src_r].reference_line;
+        dst_roads.iter().any(|dst_r| {
+            let dst_pl = &raw.streets.roads[dst_r].reference_line;
+            src_pl
+                .overall_angle()
+                .approx_parallel(dst_pl.overall_angle(), MAX_ANGLE_DIFF)
+                && src_pl.project_pt(dst_pt).dist_to(dst_pt) <= MAX_MEDIAN_WIDTH
+                && dst_pl.project_pt(src_pt).dist_to(src_pt) <= MAX_MEDIAN_WIDTH
+        })
+    })
+}
+
+fn is_oneway(road: &Road) -> bool {
+    let mut dirs = road
+        .lane_specs_ltr
+        .iter()
+        .filter(|spec| spec.lt == LaneType::Driving)
+        .map(|spec| spec.dir);
+    match dirs.next() {
+        Some(dir) => dirs.all(|x| x == dir),
+        None => false,
+    }
+}
+
+fn tags<'a>(raw: &'a RawMap, road: &Road) -> Option<&'a abstutil::Tags> {
+    raw.osm_tags.get(&road.osm_ids.get(0)?.osm_way_id)
+}
@@ -7,27 +7,44 @@ use structopt::StructOpt;
 
 use abstutil::{MultiMap, Tags, Timer};
 use geom::{
-    Distance, FindClosest, HashablePt2D, Line, PolyLine, Polygon, Pt2D, Speed, EPSILON_DIST,
+    Circle, Distance, FindClosest, HashablePt2D, Line, PolyLine, Polygon, Pt2D, Speed, EPSILON_DIST,
 };
 use osm2streets::Transformation;
-use raw_map::RawMap;
+use raw_map::{RawMap, SpeedLimitDefaults};
 
 pub use self::parking_lots::snap_driveway;
+pub use self::validate::ImportWarning;
 use crate::pathfind::{CreateEngine, Pathfinder};
 use crate::{
     connectivity, osm, AccessRestrictions, Area, AreaID, ControlStopSign, ControlTrafficSignal,
     Intersection, IntersectionControl, IntersectionID, IntersectionKind, Lane, LaneID, Map,
-    MapEdits, OriginalRoad, PathConstraints, Position, Road, RoadID, RoutingParams, Zone,
+    MapEdits, OriginalRoad, PathConstraints, Position, Road, RoadID, RoutingParams,
+    SpeedLimitSource, Zone,
 };
 
 mod bridges;
 mod buildings;
+mod dual_carriageways;
 mod parking_lots;
+pub(crate) mod slip_lanes;
+mod speed_limits;
 pub mod traffic_signals;
 pub mod transit;
 pub mod turns;
+mod validate;
 mod walking_turns;
 
+/// Turning circles in OSM are just a node, so guess how big they are
+const TURNING_CIRCLE_RADIUS: Distance = Distance::const_meters(10.0);
+/// A turning circle should be a bit wider than the road leading into it
+const TURNING_CIRCLE_RIM: Distance = Distance::const_meters(3.0);
+
+/// The bulb at the end of a dead-end with a turning circle, sized for the road leading into it
+pub(crate) fn turning_circle_polygon(center: Pt2D, road_width: Distance) -> Polygon {
+    let radius = TURNING_CIRCLE_RADIUS.max(road_width / 2.0 + TURNING_CIRCLE_RIM);
+    Circle::new(center, radius).to_polygon()
+}
+
 /// Options for converting RawMaps to Maps.
 #[derive(Clone, Default, StructOpt)]
 pub struct RawToMapOptions {
@@ -38,13 +55,48 @@ pub struct RawToMapOptions {
     /// Preserve all OSM tags for buildings, increasing the final file size substantially.
     #[structopt(long)]
     pub keep_bldg_tags: bool,
+    /// Where divided roads cross, merge the cluster of small intersections into one.
+    #[structopt(long)]
+    pub consolidate_dual_carriageways: bool,
 }
 
 impl Map {
     pub fn create_from_raw(mut raw: RawMap, opts: RawToMapOptions, timer: &mut Timer) -> Map {
+        // Remember where every OSM way was, to point out any that the transformations remove
+        let mut ways_before: BTreeMap<osm::WayID, Pt2D> = BTreeMap::new();
+        for r in raw.streets.roads.values() {
+            for id in &r.osm_ids {
+                ways_before
+                    .entry(id.osm_way_id)
+                    .or_insert_with(|| r.center_line.middle());
+            }
+        }
+
+        if opts.consolidate_dual_carriageways {
+            dual_carriageways::consolidate_crossings(&mut raw);
+        }
         raw.streets
             .apply_transformations(Transformation::abstreet(), timer);
 
+        let ways_after: BTreeSet<osm::WayID> = raw
+            .streets
+            .roads
+            .values()
+            .flat_map(|r| r.osm_ids.iter().map(|id| id.osm_way_id))
+            .collect();
+        let dropped_ways = ways_before
+            .into_iter()
+            .filter(|(id, _)| !ways_after.contains(id))
+            .map(|(id, pt)| ImportWarning {
+                category: "dropped ways".to_string(),
+                osm_id: Some(osm::OsmID::Way(id)),
+                pt,
+                message: "This way isn't part of the final map. It might be disconnected from \
+                          other roads, or so short that it became part of an intersection."
+                    .to_string(),
+            })
+            .collect();
+
         let mut map = Map {
             roads: Vec::new(),
             intersections: Vec::new(),
@@ -65,6 +117,8 @@ impl Map {
             pathfinder_dirty: false,
             routing_params: RoutingParams::default(),
             name: raw.name.clone(),
+            import_warnings: dropped_ways,
+            street_lamps: std::mem::take(&mut raw.street_lamps),
             edits: MapEdits::new(),
             edits_generation: 0,
             road_to_buildings: MultiMap::new(),
@@ -103,10 +157,19 @@ impl Map {
                 merged: !raw.streets.intersections[&i.id]
                     .trim_roads_for_merging
                     .is_empty(),
+                // Filled out later, once the roads exist
+                allow_u_turns: false,
+                turning_circle: i.roads.len() == 1
+                    && i.osm_ids.iter().any(|id| raw.turning_circles.contains(id)),
             });
             intersection_id_mapping.insert(i.id, id);
         }
 
+        let speed_limit_defaults = raw
+            .profile
+            .speed_limits
+            .clone()
+            .unwrap_or_else(|| SpeedLimitDefaults::for_country(&raw.name.city.country));
         timer.start_iter("expand roads to lanes", raw.streets.roads.len());
         for r in raw.streets.roads.values_mut() {
             timer.next();
@@ -119,6 +182,8 @@ impl Map {
             let barrier_nodes = snap_nodes_to_line(&extra.barrier_nodes, &r.center_line);
             let crossing_nodes =
                 snap_nodes_with_data_to_line(&extra.crossing_nodes, &r.center_line);
+            let traffic_calming =
+                snap_nodes_with_data_to_line(&extra.traffic_calming_nodes, &r.center_line);
             let mut road = Road {
                 id: road_id,
                 // Arbitrarily remember OSM tags from one of the ways
@@ -168,19 +233,32 @@ impl Map {
                 src_i: i1,
                 dst_i: i2,
                 speed_limit: Speed::ZERO,
+                orig_speed_limit: Speed::ZERO,
+                speed_limit_source: SpeedLimitSource::Inferred,
                 zorder: r.layer,
                 access_restrictions: AccessRestrictions::new(),
+                lane_restrictions: BTreeMap::new(),
+                reversible_lanes: BTreeMap::new(),
                 percent_incline: extra.percent_incline,
                 crosswalk_forward: extra.crosswalk_forward,
                 crosswalk_backward: extra.crosswalk_backward,
+                curb_ramps_forward: extra.curb_ramps_forward,
+                curb_ramps_backward: extra.curb_ramps_backward,
                 transit_stops: BTreeSet::new(),
                 barrier_nodes,
                 crossing_nodes,
+                traffic_calming: traffic_calming.clone(),
+                orig_traffic_calming: traffic_calming,
+                variable_speed_limit: None,
+                ramp_meter: None,
+                slip_lane: None,
             };
-            road.speed_limit = road.speed_limit_from_osm();
             road.access_restrictions = road.access_restrictions_from_osm();
 
             road.recreate_lanes(r.lane_specs_ltr.clone());
+            (road.speed_limit, road.speed_limit_source) =
+                speed_limits::speed_limit(&road, &speed_limit_defaults);
+            road.orig_speed_limit = road.speed_limit;
             for lane in &road.lanes {
                 map.intersections[lane.src_i.0].outgoing_lanes.push(lane.id);
                 map.intersections[lane.dst_i.0].incoming_lanes.push(lane.id);
@@ -218,23 +296,51 @@ impl Map {
             }
         }
 
+        for i in map.intersections.iter_mut() {
+            if i.turning_circle {
+                let road = &map.roads[i.roads[0].0];
+                i.polygon = turning_circle_polygon(i.polygon.center(), road.get_width());
+            }
+        }
+
+        slip_lanes::find_slip_lanes(&mut map);
+        for idx in 0..map.intersections.len() {
+            map.intersections[idx].allow_u_turns =
+                turns::default_u_turns(&map, &map.intersections[idx]);
+        }
+
         let mut all_turns = Vec::new();
         let mut connectivity_problems = 0;
+        let mut import_warnings = Vec::new();
         for i in &map.intersections {
             if i.is_border() || i.is_closed() {
                 continue;
             }
             if !i.is_footway(&map) && (i.incoming_lanes.is_empty() || i.outgoing_lanes.is_empty()) {
                 warn!("{} is orphaned!", i.orig_id);
+                import_warnings.push(ImportWarning {
+                    category: "intersections".to_string(),
+                    osm_id: Some(osm::OsmID::Node(i.orig_id)),
+                    pt: i.polygon.center(),
+                    message: "Roads only lead into or only lead out of this intersection"
+                        .to_string(),
+                });
                 continue;
             }
 
             let results = turns::make_all_turns(&map, i);
-            if turns::verify_vehicle_connectivity(&results, i, &map).is_err() {
+            if let Err(err) = turns::verify_vehicle_connectivity(&results, i, &map) {
                 connectivity_problems += 1;
+                import_warnings.push(ImportWarning {
+                    category: "intersections".to_string(),
+                    osm_id: Some(osm::OsmID::Node(i.orig_id)),
+                    pt: i.polygon.center(),
+                    message: err.to_string(),
+                });
             }
             all_turns.extend(results);
         }
+        map.import_warnings.extend(import_warnings);
         error!(
             "{} total intersections have some connectivity problem",
             connectivity_problems
@@ -256,8 +362,16 @@ impl Map {
         }
         timer.stop("find blackholes");
 
-        map.buildings =
-            buildings::make_all_buildings(&raw.buildings, &map, opts.keep_bldg_tags, timer);
+        let mut import_warnings = Vec::new();
+        map.buildings = buildings::make_all_buildings(
+            &raw.buildings,
+            &map,
+            opts.keep_bldg_tags,
+            &mut import_warnings,
+            timer,
+        );
+        import_warnings.extend(validate::validate_connectivity(&mut map, timer));
+        map.import_warnings.extend(import_warnings);
 
         map.parking_lots = parking_lots::make_all_parking_lots(
             &raw.parking_lots,
@@ -284,6 +398,7 @@ impl Map {
 
         let mut stop_signs: BTreeMap<IntersectionID, ControlStopSign> = BTreeMap::new();
         let mut traffic_signals: BTreeMap<IntersectionID, ControlTrafficSignal> = BTreeMap::new();
+        let mut import_warnings = Vec::new();
         for i in &map.intersections {
             if i.kind == IntersectionKind::MapEdge {
                 continue;
@@ -295,6 +410,14 @@ impl Map {
                 IntersectionControl::Signalled => {
                     if i.movements.is_empty() {
                         error!("Traffic signal at {} downgraded to stop sign, because it has no movements -- probably roads under construction", i.orig_id);
+                        import_warnings.push(ImportWarning {
+                            category: "intersections".to_string(),
+                            osm_id: Some(osm::OsmID::Node(i.orig_id)),
+                            pt: i.polygon.center(),
+                            message: "This traffic signal has no movements, so it became a stop \
+                                      sign. The roads might be under construction."
+                                .to_string(),
+                        });
                         stop_signs.insert(i.id, ControlStopSign::new(&map, i.id));
                     } else {
                         traffic_signals.insert(
@@ -310,6 +433,7 @@ impl Map {
         }
         map.stop_signs = stop_signs;
         map.traffic_signals = traffic_signals;
+        map.import_warnings.extend(import_warnings);
         // Fix up the type for any problematic traffic signals
         for i in map.stop_signs.keys() {
             map.intersections[i.0].control = IntersectionControl::Signed;
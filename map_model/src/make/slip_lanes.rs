@@ -0,0 +1,122 @@
+//! Slip lanes (or channelized turn lanes) are short one-way roads letting vehicles turn right (or
+//! left, when driving on the left) before reaching an intersection. An island usually separates
+//! them from the intersection. In OSM, they're mapped as separate ways, so they form a small
+//! triangle with the two roads they connect.
+
+use std::collections::BTreeSet;
+
+use geom::{Distance, PolyLine};
+
+use crate::make::turns::turn_type_from_angles;
+use crate::{osm, Direction, DrivingSide, IntersectionID, Map, Road, RoadID, TurnType};
+
+/// Anything longer than this is probably a real road, not a slip lane
+const MAX_LENGTH: Distance = Distance::const_meters(100.0);
+
+/// Mark every road that looks like a slip lane with the intersection it bypasses.
+pub fn find_slip_lanes(map: &mut Map) {
+    let found: Vec<_> = map
+        .roads
+        .iter()
+        .filter_map(|r| bypassed_intersection(map, r).map(|i| (r.id, i)))
+        .collect();
+    info!("Found {} slip lanes", found.len());
+    let mut bypassed = BTreeSet::new();
+    for (r, i) in found {
+        map.roads[r.0].slip_lane = Some(i);
+        bypassed.insert(i);
+    }
+    for i in bypassed {
+        cut_out_slip_lanes(map, i);
+    }
+}
+
+/// Lane edits can change a road's direction, so check again if it's a slip lane.
+pub fn update_slip_lane(map: &mut Map, r: RoadID) {
+    map.roads[r.0].slip_lane = bypassed_intersection(map, map.get_r(r));
+}
+
+/// The polygon of an intersection with slip lanes sometimes spreads over the start of them. Keep
+/// the slip lanes separate; they have their own intersections where they merge back.
+pub fn cut_out_slip_lanes(map: &mut Map, i: IntersectionID) {
+    // A slip lane connects two roads leading to the intersection it bypasses
+    let mut slip_lanes = BTreeSet::new();
+    for r in &map.get_i(i).roads {
+        let neighbor = map.get_r(*r).other_endpt(i);
+        for slip in &map.get_i(neighbor).roads {
+            if map.get_r(*slip).slip_lane == Some(i) {
+                slip_lanes.insert(*slip);
+            }
+        }
+    }
+
+    for slip in slip_lanes {
+        let slip = map.get_r(slip).get_thick_polygon();
+        let polygon = &map.get_i(i).polygon;
+        if !polygon.intersects(&slip) {
+            continue;
+        }
+        // If the cut splits the polygon, keep the main part
+        if let Some(main) = polygon.difference(&slip).ok().and_then(|pieces| {
+            pieces
+                .into_iter()
+                .max_by(|a, b| a.area().partial_cmp(&b.area()).unwrap())
+        }) {
+            map.intersections[i.0].polygon = main;
+        }
+    }
+}
+
+fn bypassed_intersection(map: &Map, slip: &Road) -> Option<IntersectionID> {
+    let dir = slip.oneway_for_driving()?;
+    if slip.get_rank() == osm::RoadRank::Highway || slip.center_pts.length() > MAX_LENGTH {
+        return None;
+    }
+    let (from, to) = if dir == Direction::Fwd {
+        (slip.src_i, slip.dst_i)
+    } else {
+        (slip.dst_i, slip.src_i)
+    };
+    let bypassed_turn = if map.get_config().driving_side == DrivingSide::Right {
+        TurnType::Right
+    } else {
+        TurnType::Left
+    };
+
+    for approach in &map.get_i(from).roads {
+        if *approach == slip.id {
+            continue;
+        }
+        let approach = map.get_r(*approach);
+        let main = approach.other_endpt(from);
+        if main == to || map.get_i(main).roads.len() < 3 {
+            continue;
+        }
+        for exit in &map.get_i(to).roads {
+            if *exit == slip.id || *exit == approach.id {
+                continue;
+            }
+            let exit = map.get_r(*exit);
+            if exit.other_endpt(to) != main {
+                continue;
+            }
+            // The slip lane should be a shortcut for exactly the turn at the main intersection
+            // that doesn't cross oncoming traffic
+            let into_main = pointing_to(approach, main).last_line().angle();
+            let out_of_main = pointing_to(exit, main).reversed().first_line().angle();
+            if turn_type_from_angles(into_main, out_of_main) == bypassed_turn {
+                return Some(main);
+            }
+        }
+    }
+    None
+}
+
+/// The road's center line, oriented to end at `i`
+fn pointing_to(road: &Road, i: IntersectionID) -> PolyLine {
+    if road.dst_i == i {
+        road.center_pts.clone()
+    } else {
+        road.center_pts.reversed()
+    }
+}
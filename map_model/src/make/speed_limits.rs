@@ -0,0 +1,178 @@
+//! Most roads in OSM don't have a `maxspeed` tag. Rather than giving all of them one default, guess
+//! from the kind of road, the country's default limits, the number of lanes, and whether the road
+//! looks urban or rural.
+
+use geom::Speed;
+use raw_map::SpeedLimitDefaults;
+
+use crate::{osm, Direction, LaneType, Road, SpeedLimitSource};
+
+/// Determine a road's speed limit, and whether it was tagged or guessed. `locale` has the map's
+/// defaults. The road's lanes must be filled out already.
+pub fn speed_limit(road: &Road, locale: &SpeedLimitDefaults) -> (Speed, SpeedLimitSource) {
+    if let Some(value) = road.osm_tags.get("maxspeed") {
+        if let Some(speed) = parse_maxspeed(value, locale) {
+            return (speed, SpeedLimitSource::Tagged);
+        }
+        warn!(
+            "{} has an unknown maxspeed={}, guessing instead",
+            road.orig_id.osm_way_id, value
+        );
+    }
+    (infer(road, locale), SpeedLimitSource::Inferred)
+}
+
+/// Handles plain numbers in km/h, "25 mph", and implicit limits like "DE:urban" or "PL:zone30"
+fn parse_maxspeed(value: &str, locale: &SpeedLimitDefaults) -> Option<Speed> {
+    if let Ok(kmph) = value.parse::<f64>() {
+        if kmph == 0.0 {
+            warn!("Speed limit of 0 found, using 1 mph instead");
+            return Some(Speed::miles_per_hour(1.0));
+        }
+        return Some(Speed::km_per_hour(kmph));
+    }
+    if let Some(mph) = value
+        .strip_suffix(" mph")
+        .and_then(|x| x.parse::<f64>().ok())
+    {
+        return Some(Speed::miles_per_hour(mph));
+    }
+    if let Some(kmph) = value
+        .strip_suffix(" km/h")
+        .and_then(|x| x.parse::<f64>().ok())
+    {
+        return Some(Speed::km_per_hour(kmph));
+    }
+
+    // Implicit limits are prefixed by a country, which might not match the map's
+    let prefixed;
+    let (locale, kind) = match value.split_once(':') {
+        Some((prefix, kind)) if prefix.len() == 2 => {
+            prefixed = SpeedLimitDefaults::for_country(&prefix.to_lowercase());
+            (&prefixed, kind)
+        }
+        _ => (locale, value),
+    };
+    match kind {
+        "urban" => Some(locale.speed(locale.urban)),
+        "rural" | "nsl_single" => Some(locale.speed(locale.rural)),
+        "motorway" | "nsl_dual" | "none" => Some(locale.speed(locale.motorway)),
+        "living_street" | "walk" => Some(locale.speed(locale.living_street)),
+        _ => {
+            // zone30, zone:30, or zone20
+            let digits = kind.strip_prefix("zone")?.trim_start_matches(':');
+            digits.parse::<f64>().ok().map(|x| locale.speed(x))
+        }
+    }
+}
+
+fn infer(road: &Road, locale: &SpeedLimitDefaults) -> Speed {
+    let hwy = match road.osm_tags.get(osm::HIGHWAY) {
+        Some(hwy) => hwy.as_str(),
+        None => {
+            return locale.speed(locale.urban);
+        }
+    };
+    let urban = is_urban(road);
+    let multi_lane = [Direction::Fwd, Direction::Back].into_iter().all(|dir| {
+        road.lanes
+            .iter()
+            .filter(|l| l.dir == dir && l.lane_type == LaneType::Driving)
+            .count()
+            >= 2
+    });
+
+    let limit = match hwy {
+        "motorway" => locale.motorway,
+        "motorway_link" | "trunk_link" => {
+            if urban {
+                locale.urban
+            } else {
+                locale.rural
+            }
+        }
+        "trunk" | "primary" | "secondary" => {
+            if urban && multi_lane {
+                // Multi-lane arterials are usually posted a bit above the urban default
+                locale.urban + 10.0
+            } else if urban {
+                locale.urban
+            } else if multi_lane && hwy == "trunk" {
+                locale.motorway
+            } else {
+                locale.rural
+            }
+        }
+        "living_street" => locale.living_street,
+        "service" if road.is_driveway_or_alley() => {
+            // About 5mph either way
+            if locale.mph {
+                5.0
+            } else {
+                10.0
+            }
+        }
+        "service" | "track" => {
+            // About 10mph either way
+            if locale.mph {
+                10.0
+            } else {
+                15.0
+            }
+        }
+        "residential" => locale.urban,
+        _ => {
+            if urban {
+                locale.urban
+            } else {
+                locale.rural
+            }
+        }
+    };
+    locale.speed(limit)
+}
+
+/// Without landuse data here, use hints from the road itself. Sidewalks and street lights are
+/// rare outside of built-up areas.
+fn is_urban(road: &Road) -> bool {
+    let tags = &road.osm_tags;
+    for key in ["maxspeed:type", "source:maxspeed", "zone:maxspeed"] {
+        if let Some(value) = tags.get(key) {
+            if value.ends_with(":urban") || value.ends_with(":zone30") {
+                return true;
+            }
+            if value.ends_with(":rural") {
+                return false;
+            }
+        }
+    }
+    tags.is("lit", "yes") || road.lanes.iter().any(|l| l.lane_type == LaneType::Sidewalk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_maxspeed() {
+        let check = |value, expected| {
+            let actual = parse_maxspeed(value, &SpeedLimitDefaults::for_country("us")).unwrap();
+            assert!(
+                (actual - expected).inner_meters_per_second().abs() < 0.01,
+                "{} parsed as {:?}, not {:?}",
+                value,
+                actual,
+                expected
+            );
+        };
+        check("50", Speed::km_per_hour(50.0));
+        check("25 mph", Speed::miles_per_hour(25.0));
+        check("DE:urban", Speed::km_per_hour(50.0));
+        check("DE:rural", Speed::km_per_hour(100.0));
+        check("GB:nsl_dual", Speed::miles_per_hour(70.0));
+        check("PL:zone30", Speed::km_per_hour(30.0));
+        check("DE:zone:20", Speed::km_per_hour(20.0));
+        check("urban", Speed::miles_per_hour(25.0));
+        assert!(parse_maxspeed("signals", &SpeedLimitDefaults::for_country("us")).is_none());
+    }
+}
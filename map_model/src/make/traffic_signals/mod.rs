@@ -74,6 +74,7 @@ fn new(id: IntersectionID) -> ControlTrafficSignal {
         id,
         stages: Vec::new(),
         offset: Duration::ZERO,
+        bike_signals: BTreeSet::new(),
     }
 }
 
@@ -331,6 +332,8 @@ fn make_stages(
         let mut stage = Stage::new();
         let mut explicit_crosswalks = false;
         for (roads, mut turn_type, protected) in specs.iter() {
+            // U-turns cross oncoming traffic, so let them go with the hardest turn
+            let with_u_turns = turn_type == TurnType::Left;
             // The heuristics are written assuming right turns are easy and lefts are hard, so
             // invert in the UK.
             if map_config.driving_side == DrivingSide::Left {
@@ -345,7 +348,10 @@ fn make_stages(
             }
 
             for movement in i.movements.values() {
-                if !roads.contains(&movement.id.from.road) || turn_type != movement.turn_type {
+                let u_turn = with_u_turns && movement.turn_type == TurnType::UTurn;
+                if !roads.contains(&movement.id.from.road)
+                    || (turn_type != movement.turn_type && !u_turn)
+                {
                     continue;
                 }
 
@@ -361,7 +367,7 @@ fn make_stages(
 
                 stage.edit_movement(
                     movement,
-                    if *protected {
+                    if *protected && !u_turn {
                         TurnPriority::Protected
                     } else {
                         TurnPriority::Yield
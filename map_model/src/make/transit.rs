@@ -6,10 +6,10 @@ use abstutil::Timer;
 use geom::{Distance, Duration, FindClosest, HashablePt2D, Time};
 use raw_map::{RawMap, RawTransitRoute, RawTransitStop, RawTransitType};
 
-use crate::make::match_points_to_lanes;
+use crate::make::{match_points_to_lanes, ImportWarning};
 use crate::{
-    LaneID, Map, PathConstraints, Position, TransitRoute, TransitRouteID, TransitStop,
-    TransitStopID,
+    BoardingConfig, LaneID, Map, PathConstraints, Position, StopLayout, TransitRoute,
+    TransitRouteID, TransitStop, TransitStopID,
 };
 
 pub fn finalize_transit(map: &mut Map, raw: &RawMap, timer: &mut Timer) {
@@ -34,6 +34,12 @@ pub fn finalize_transit(map: &mut Map, raw: &RawMap, timer: &mut Timer) {
     for stop in raw.transit_stops.values() {
         if let Err(err) = create_stop(stop, &sidewalk_pts, &mut gtfs_to_stop_id, map) {
             warn!("Couldn't create stop {}: {}", stop.gtfs_id, err);
+            map.import_warnings.push(ImportWarning {
+                category: "transit".to_string(),
+                osm_id: None,
+                pt: stop.position,
+                message: format!("Couldn't match GTFS stop {}: {}", stop.gtfs_id, err),
+            });
         }
     }
 
@@ -44,6 +50,18 @@ pub fn finalize_transit(map: &mut Map, raw: &RawMap, timer: &mut Timer) {
                 "Couldn't snap route {} ({}): {}",
                 route.gtfs_id, route.short_name, err
             );
+            // Point to the first stop, if it's known
+            if let Some(stop) = route.stops.get(0).and_then(|id| raw.transit_stops.get(id)) {
+                map.import_warnings.push(ImportWarning {
+                    category: "transit".to_string(),
+                    osm_id: None,
+                    pt: stop.position,
+                    message: format!(
+                        "Couldn't match GTFS route {} ({}), starting here: {}",
+                        route.gtfs_id, route.short_name, err
+                    ),
+                });
+            }
         }
     }
 
@@ -80,6 +98,7 @@ fn create_stop(
                     driving_pos,
                     sidewalk_pos: *sidewalk_pos,
                     is_train_stop: vehicle == PathConstraints::Train,
+                    layout: StopLayout::InLane,
                 },
             );
             gtfs_to_stop_id.insert(stop.gtfs_id.clone(), id);
@@ -224,6 +243,10 @@ fn create_route(
         .map(|i| Time::START_OF_DAY + (i as f64) * Duration::minutes(30))
         .collect();
 
+    let route_type = match route.route_type {
+        RawTransitType::Bus => PathConstraints::Bus,
+        RawTransitType::Train => PathConstraints::Train,
+    };
     let result = TransitRoute {
         id: TransitRouteID(map.transit_routes.len()),
         long_name: route.long_name.clone(),
@@ -232,12 +255,10 @@ fn create_route(
         stops,
         start,
         end_border,
-        route_type: match route.route_type {
-            RawTransitType::Bus => PathConstraints::Bus,
-            RawTransitType::Train => PathConstraints::Train,
-        },
+        route_type,
         spawn_times: spawn_times.clone(),
         orig_spawn_times: spawn_times,
+        boarding: BoardingConfig::default_for(route_type),
     };
 
     // Check that the paths are valid
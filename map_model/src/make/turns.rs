@@ -5,7 +5,10 @@ use lyon::geom::{CubicBezierSegment, Point, QuadraticBezierSegment};
 
 use geom::{Angle, PolyLine, Pt2D};
 
-use crate::{Intersection, Lane, LaneID, LaneType, Map, RoadID, Turn, TurnID, TurnType};
+use crate::{
+    osm, Direction, DrivingSide, Intersection, Lane, LaneID, LaneType, Map, RestrictionType, Road,
+    RoadID, Turn, TurnID, TurnType,
+};
 
 /// Generate all driving and walking turns at an intersection, accounting for OSM turn restrictions.
 pub fn make_all_turns(map: &Map, i: &Intersection) -> Vec<Turn> {
@@ -25,18 +28,23 @@ pub fn make_all_turns(map: &Map, i: &Intersection) -> Vec<Turn> {
         .collect();
 
     // Try to use turn lane tags...
-    let filtered_turns: Vec<Turn> = all_turns
-        .clone()
-        .into_iter()
+    let lane_permitted_turns: Vec<Turn> = all_turns
+        .iter()
         .filter(|t| t.permitted_by_lane(map))
+        .cloned()
         .collect();
     // And remove merging left or right turns. If we wanted to remove the "lane-changing at
     // intersections" behavior, we could do this for TurnType::Straight too.
-    let filtered_turns = remove_merging_turns(map, filtered_turns, TurnType::Right);
+    let filtered_turns = remove_merging_turns(map, lane_permitted_turns.clone(), TurnType::Right);
     let mut filtered_turns = remove_merging_turns(map, filtered_turns, TurnType::Left);
-    if i.merged {
+    if !i.allow_u_turns && !i.is_deadend_for_driving(map) {
         filtered_turns.retain(|turn| {
-            if turn.turn_type == TurnType::UTurn {
+            // The policy is about turning around on the same road. Sharp turns onto another road
+            // can be classified as U-turns by angle, but they're normal movements -- except at
+            // merged intersections, where they go between the two carriageways of one road.
+            if turn.turn_type == TurnType::UTurn
+                && (turn.id.src.road == turn.id.dst.road || i.merged)
+            {
                 let src_lane = map.get_l(turn.id.src);
                 // U-turns at divided highways are sometimes legal (and a common movement --
                 // https://www.openstreetmap.org/way/361443212), so let OSM turn:lanes override.
@@ -45,7 +53,7 @@ pub fn make_all_turns(map: &Map, i: &Intersection) -> Vec<Turn> {
                     .map(|set| !set.contains(&TurnType::UTurn))
                     .unwrap_or(true)
                 {
-                    warn!("Removing u-turn from merged intersection: {}", turn.id);
+                    warn!("Removing u-turn from {}", turn.id);
                     false
                 } else {
                     true
@@ -56,7 +64,12 @@ pub fn make_all_turns(map: &Map, i: &Intersection) -> Vec<Turn> {
         });
     }
 
-    // But then see how all of that filtering affects lane connectivity.
+    // But then see how all of that filtering affects lane connectivity. Turn lane tags are often
+    // copied along every segment of a way, so they don't always fit each intersection the way
+    // crosses. Rather than ignoring the tags for the whole intersection, just undo the filtering
+    // for lanes left without any turns.
+    let filtered_turns =
+        restore_orphaned_lanes(map, i, filtered_turns, &lane_permitted_turns, &all_turns);
     match verify_vehicle_connectivity(&filtered_turns, i, map) {
         Ok(()) => filtered_turns,
         Err(err) => {
@@ -94,6 +107,25 @@ fn ensure_unique(turns: Vec<Turn>) -> Vec<Turn> {
 /// turn restrictions _probably_ indicate the vehicle movements allowed further on, and _don't_
 /// describe the turns between the road and the trail.
 pub fn verify_vehicle_connectivity(turns: &[Turn], i: &Intersection, map: &Map) -> Result<()> {
+    let (incoming_missing, outgoing_missing) = orphaned_lanes(turns, i, map);
+    if !incoming_missing.is_empty() || !outgoing_missing.is_empty() {
+        bail!(
+            "Turns for {} orphan some lanes. Incoming: {:?}, outgoing: {:?}",
+            i.id,
+            incoming_missing,
+            outgoing_missing
+        );
+    }
+    Ok(())
+}
+
+/// Returns the (incoming, outgoing) lanes not connected well enough, according to
+/// `verify_vehicle_connectivity`.
+fn orphaned_lanes(
+    turns: &[Turn],
+    i: &Intersection,
+    map: &Map,
+) -> (HashSet<LaneID>, HashSet<LaneID>) {
     let mut incoming_missing: HashSet<LaneID> = HashSet::new();
     for l in &i.incoming_lanes {
         if map.get_l(*l).lane_type.is_for_moving_vehicles() {
@@ -124,15 +156,32 @@ pub fn verify_vehicle_connectivity(turns: &[Turn], i: &Intersection, map: &Map)
         }
     }
 
-    if !incoming_missing.is_empty() || !outgoing_missing.is_empty() {
-        bail!(
-            "Turns for {} orphan some lanes. Incoming: {:?}, outgoing: {:?}",
-            i.id,
-            incoming_missing,
-            outgoing_missing
-        );
+    (incoming_missing, outgoing_missing)
+}
+
+/// Add back turns for any lanes that filtering left disconnected. Turns allowed by the lane tags
+/// (and only removed for merging) are preferred; if those aren't enough, the tags on the lane
+/// don't fit this intersection, so allow anything the road-level restrictions do.
+fn restore_orphaned_lanes(
+    map: &Map,
+    i: &Intersection,
+    mut turns: Vec<Turn>,
+    lane_permitted_turns: &[Turn],
+    all_turns: &[Turn],
+) -> Vec<Turn> {
+    let mut ids: HashSet<TurnID> = turns.iter().map(|t| t.id).collect();
+    for candidates in [lane_permitted_turns, all_turns] {
+        let (incoming, outgoing) = orphaned_lanes(&turns, i, map);
+        if incoming.is_empty() && outgoing.is_empty() {
+            break;
+        }
+        for t in candidates {
+            if (incoming.contains(&t.id.src) || outgoing.contains(&t.id.dst)) && ids.insert(t.id) {
+                turns.push(t.clone());
+            }
+        }
     }
-    Ok(())
+    turns
 }
 
 fn make_vehicle_turns(i: &Intersection, map: &Map) -> Vec<Turn> {
@@ -152,8 +201,12 @@ fn make_vehicle_turns(i: &Intersection, map: &Map) -> Vec<Turn> {
             if !dst.lane_type.is_for_moving_vehicles() {
                 continue;
             }
-            // Only allow U-turns at deadends
-            if src.id.road == dst.id.road && !is_deadend {
+            // Anywhere besides a dead-end, only U-turn from the innermost lane into the innermost
+            // lane going the other way
+            if src.id.road == dst.id.road
+                && !is_deadend
+                && !(i.allow_u_turns && is_innermost(src, map) && is_innermost(dst, map))
+            {
                 continue;
             }
             // Can't go between light rail and normal roads
@@ -197,8 +250,18 @@ fn make_vehicle_turns(i: &Intersection, map: &Map) -> Vec<Turn> {
                 }
             }
 
-            let geom = curvey_turn(src, dst, i)
-                .unwrap_or_else(|_| PolyLine::must_new(vec![src.last_pt(), dst.first_pt()]));
+            if let Some(restriction) = src.get_restriction(map) {
+                if restriction.always_active() && !restriction.allows_turn(turn_type) {
+                    continue;
+                }
+            }
+
+            let geom = if is_deadend && src.id.road == dst.id.road {
+                dead_end_u_turn(src, dst, i)
+            } else {
+                curvey_turn(src, dst, i)
+            }
+            .unwrap_or_else(|_| PolyLine::must_new(vec![src.last_pt(), dst.first_pt()]));
 
             turns.push(Turn {
                 id: TurnID {
@@ -215,6 +278,91 @@ fn make_vehicle_turns(i: &Intersection, map: &Map) -> Vec<Turn> {
     turns
 }
 
+/// Decide if vehicles may U-turn at an intersection, before any edits. They're never allowed next
+/// to highways or where OSM has a `no_u_turn` restriction. Otherwise, they're expected where a
+/// road is divided by a median -- either tagged on one road, or mapped as two one-way carriageways
+/// with the same name.
+pub fn default_u_turns(map: &Map, i: &Intersection) -> bool {
+    if i.is_deadend_for_driving(map) {
+        return true;
+    }
+    // Merged intersections are usually the middle of a divided highway
+    if i.merged {
+        return false;
+    }
+
+    for r in &i.roads {
+        let road = map.get_r(*r);
+        if road.get_rank() == osm::RoadRank::Highway {
+            return false;
+        }
+        if road
+            .turn_restrictions
+            .iter()
+            .any(|(restriction, to)| matches!(restriction, RestrictionType::BanTurns) && to == r)
+        {
+            return false;
+        }
+    }
+
+    if i.roads.iter().any(|r| has_median(map.get_r(*r))) {
+        return true;
+    }
+    // Two carriageways of the same road meet here, so going between them looks like a U-turn by
+    // angle
+    i.incoming_lanes.iter().any(|src| {
+        let src = map.get_l(*src);
+        src.lane_type == LaneType::Driving
+            && i.outgoing_lanes.iter().any(|dst| {
+                let dst = map.get_l(*dst);
+                dst.lane_type == LaneType::Driving
+                    && src.id.road != dst.id.road
+                    && map.get_parent(src.id).get_name(None)
+                        == map.get_parent(dst.id).get_name(None)
+                    && turn_type_from_angles(src.last_line().angle(), dst.first_line().angle())
+                        == TurnType::UTurn
+            })
+    })
+}
+
+/// Is there a buffer or a shared center turn lane between the two directions of driving lanes?
+fn has_median(road: &Road) -> bool {
+    let driving = |lanes: &[Lane], dir: Direction| {
+        lanes
+            .iter()
+            .any(|l| l.lane_type == LaneType::Driving && l.dir == dir)
+    };
+    road.lanes.iter().enumerate().any(|(idx, lane)| {
+        matches!(
+            lane.lane_type,
+            LaneType::Buffer(_) | LaneType::SharedLeftTurn
+        ) && ((driving(&road.lanes[..idx], Direction::Back)
+            && driving(&road.lanes[idx + 1..], Direction::Fwd))
+            || (driving(&road.lanes[..idx], Direction::Fwd)
+                && driving(&road.lanes[idx + 1..], Direction::Back)))
+    })
+}
+
+/// Is this the lane closest to the center of the road, out of the lanes for moving vehicles going
+/// the same way?
+fn is_innermost(lane: &Lane, map: &Map) -> bool {
+    let road = map.get_parent(lane.id);
+    // Ordered from the left-most lane when driving on the right
+    let mut children = if lane.dir == Direction::Fwd {
+        road.children_forwards()
+    } else {
+        road.children_backwards()
+    };
+    if map.get_config().driving_side == DrivingSide::Left {
+        children.reverse();
+    }
+    children
+        .into_iter()
+        .find(|(_, lt)| lt.is_for_moving_vehicles())
+        .map(|(l, _)| l == lane.id)
+        .unwrap_or(false)
+}
+
 fn curvey_turn(src: &Lane, dst: &Lane, i: &Intersection) -> Result<PolyLine> {
     fn to_pt(pt: Pt2D) -> Point<f64> {
         Point::new(pt.x(), pt.y())
@@ -273,6 +421,55 @@ fn curvey_turn(src: &Lane, dst: &Lane, i: &Intersection) -> Result<PolyLine> {
     PolyLine::new(curve)
 }
 
+/// At a dead-end, sweep around past the end of the road, instead of pivoting in place. With a
+/// turning circle, go around the whole bulb.
+fn dead_end_u_turn(src: &Lane, dst: &Lane, i: &Intersection) -> Result<PolyLine> {
+    let start = src.last_pt();
+    let end = dst.first_pt();
+    let forwards = src.last_line().angle();
+    let center = if i.turning_circle {
+        i.polygon.center()
+    } else {
+        Pt2D::center(&[start, end])
+    };
+    let start_angle = center.angle_to(start);
+    let start_radius = center.dist_to(start);
+    let end_radius = center.dist_to(end);
+
+    // Going around either way reaches the destination. Pick the way leading further out of the
+    // road.
+    let short = -start_angle.simple_shortest_rotation_towards(center.angle_to(end));
+    let long = if short < 0.0 {
+        short + 360.0
+    } else {
+        short - 360.0
+    };
+    let sweep_at = |sweep: f64, pct: f64| {
+        center.project_away(
+            start_radius + (end_radius - start_radius) * pct,
+            start_angle.rotate_degs(sweep * pct),
+        )
+    };
+    let how_far_out = |sweep: f64| {
+        let pt = sweep_at(sweep, 0.5);
+        (pt.x() - center.x()) * forwards.normalized_radians().cos()
+            + (pt.y() - center.y()) * forwards.normalized_radians().sin()
+    };
+    let sweep = if how_far_out(short) >= how_far_out(long) {
+        short
+    } else {
+        long
+    };
+
+    let pieces = 12;
+    let mut pts = vec![start];
+    for step in 1..pieces {
+        pts.push(sweep_at(sweep, f64::from(step) / f64::from(pieces)));
+    }
+    pts.push(end);
+    PolyLine::deduping_new(pts)
+}
+
 fn remove_merging_turns(map: &Map, input: Vec<Turn>, turn_type: TurnType) -> Vec<Turn> {
     let mut turns = Vec::new();
 
@@ -381,7 +578,7 @@ fn remove_merging_turns(map: &Map, input: Vec<Turn>, turn_type: TurnType) -> Vec
     turns
 }
 
-fn turn_type_from_angles(from: Angle, to: Angle) -> TurnType {
+pub(crate) fn turn_type_from_angles(from: Angle, to: Angle) -> TurnType {
     let diff = from.simple_shortest_rotation_towards(to);
     // This is a pretty arbitrary parameter, but a difference of 30 degrees seems reasonable for
     // some observed cases.
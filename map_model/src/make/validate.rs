@@ -0,0 +1,145 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
+use geom::{Distance, HashablePt2D, Line, Pt2D};
+
+use crate::make::{match_points_to_lanes, trim_path};
+use crate::{connectivity, osm, BuildingID, LaneID, Map, PathConstraints, RoadID};
+
+/// Something suspicious found while importing a map, pointing at the OSM object to fix.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportWarning {
+    /// Groups similar warnings, like "connectivity" or "transit"
+    pub category: String,
+    /// Some input, like GTFS transit stops, doesn't come from OSM
+    pub osm_id: Option<osm::OsmID>,
+    /// Where on the map to look
+    pub pt: Pt2D,
+    pub message: String,
+}
+
+/// Buildings farther than this from the main sidewalk network won't be snapped to it
+/// automatically; something bigger is probably wrong with the OSM data there.
+const MAX_RESNAP_DISTANCE: Distance = Distance::const_meters(100.0);
+
+/// Checks that every mode can get around the map. Buildings snapped to an isolated piece of
+/// sidewalk are moved to the main sidewalk network when it's nearby; everything else becomes an
+/// `ImportWarning`. Call this after the blackhole lanes and buildings are set up.
+pub fn validate_connectivity(map: &mut Map, timer: &mut Timer) -> Vec<ImportWarning> {
+    timer.start("validate connectivity");
+    let mut warnings = Vec::new();
+
+    let (main_sidewalks, _) = connectivity::find_scc(map, PathConstraints::Pedestrian);
+    let stranded: Vec<BuildingID> = map
+        .buildings
+        .iter()
+        .filter(|b| !main_sidewalks.contains(&b.sidewalk()))
+        .map(|b| b.id)
+        .collect();
+    let fixed = resnap_buildings(map, &stranded, &main_sidewalks);
+    info!(
+        "{} buildings were snapped to disconnected sidewalks; {} fixed automatically",
+        stranded.len(),
+        fixed.len()
+    );
+    for b in stranded {
+        if fixed.contains(&b) {
+            continue;
+        }
+        let bldg = map.get_b(b);
+        warnings.push(ImportWarning {
+            category: "connectivity".to_string(),
+            osm_id: Some(bldg.orig_id),
+            pt: bldg.label_center,
+            message: format!(
+                "This building's nearest sidewalk isn't connected to the rest of the map, along \
+                 {}. Check for missing crossings or footpaths.",
+                map.get_parent(bldg.sidewalk()).get_name(None)
+            ),
+        });
+    }
+
+    for (mode, constraints) in [
+        ("Cars", PathConstraints::Car),
+        ("Bikes", PathConstraints::Bike),
+    ] {
+        let mut roads: BTreeSet<RoadID> = BTreeSet::new();
+        for l in map.all_lanes() {
+            let blackhole = if constraints == PathConstraints::Car {
+                l.driving_blackhole
+            } else {
+                l.biking_blackhole
+            };
+            if blackhole {
+                roads.insert(l.id.road);
+            }
+        }
+        for r in roads {
+            let road = map.get_r(r);
+            // Roads leading off the map can't loop back, so they always look disconnected
+            if map.get_i(road.src_i).is_border() || map.get_i(road.dst_i).is_border() {
+                continue;
+            }
+            warnings.push(ImportWarning {
+                category: "connectivity".to_string(),
+                osm_id: Some(osm::OsmID::Way(road.orig_id.osm_way_id)),
+                pt: road.center_pts.middle(),
+                message: format!(
+                    "{} can't get between {} and the rest of the map. Check oneway tags, turn \
+                     restrictions, and access tags nearby.",
+                    mode,
+                    road.get_name(None)
+                ),
+            });
+        }
+    }
+
+    timer.stop("validate connectivity");
+    warnings
+}
+
+/// Try to move buildings to the closest sidewalk in the main network. Returns the buildings that
+/// moved.
+fn resnap_buildings(
+    map: &mut Map,
+    input: &[BuildingID],
+    main_sidewalks: &HashSet<LaneID>,
+) -> HashSet<BuildingID> {
+    // TODO Copying from make/buildings.rs
+    let mut center_per_bldg: BTreeMap<BuildingID, HashablePt2D> = BTreeMap::new();
+    let mut query: HashSet<HashablePt2D> = HashSet::new();
+    for id in input {
+        let center = map.get_b(*id).polygon.center().to_hashable();
+        center_per_bldg.insert(*id, center);
+        query.insert(center);
+    }
+
+    let mut sidewalk_pts = match_points_to_lanes(
+        map,
+        query,
+        |l| l.is_walkable() && main_sidewalks.contains(&l.id),
+        // Don't put connections too close to intersections
+        Distance::meters(7.5),
+        MAX_RESNAP_DISTANCE,
+        &mut Timer::throwaway(),
+    );
+
+    let mut fixed = HashSet::new();
+    for (id, bldg_center) in center_per_bldg {
+        if let Some((sidewalk_pos, driveway_geom)) =
+            sidewalk_pts.remove(&bldg_center).and_then(|pos| {
+                Line::new(bldg_center.to_pt2d(), pos.pt(map))
+                    .map(|l| (pos, trim_path(&map.get_b(id).polygon, l)))
+                    .ok()
+            })
+        {
+            let b = &mut map.buildings[id.0];
+            b.sidewalk_pos = sidewalk_pos;
+            b.driveway_geom = driveway_geom.to_polyline();
+            fixed.insert(id);
+        }
+    }
+    fixed
+}
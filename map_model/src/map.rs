@@ -13,11 +13,11 @@ use raw_map::{RawBuilding, RawMap};
 use crate::{
     osm, Area, AreaID, AreaType, Building, BuildingID, BuildingType, CommonEndpoint,
     CompressedMovementID, ControlStopSign, ControlTrafficSignal, DirectedRoadID, Direction,
-    DrivingSide, Intersection, IntersectionControl, IntersectionID, IntersectionKind, Lane, LaneID,
-    LaneType, Map, MapConfig, MapEdits, Movement, MovementID, OffstreetParking, OriginalRoad,
-    ParkingLot, ParkingLotID, Path, PathConstraints, PathRequest, PathV2, Pathfinder,
-    PathfinderCaching, Position, Road, RoadID, RoutingParams, TransitRoute, TransitRouteID,
-    TransitStop, TransitStopID, Turn, TurnID, TurnType, Zone,
+    DrivingSide, ImportWarning, Intersection, IntersectionControl, IntersectionID,
+    IntersectionKind, Lane, LaneID, LaneType, Map, MapConfig, MapEdits, Movement, MovementID,
+    OffstreetParking, OriginalRoad, ParkingLot, ParkingLotID, Path, PathConstraints, PathRequest,
+    PathV2, Pathfinder, PathfinderCaching, Position, Road, RoadID, RoutingParams, TransitRoute,
+    TransitRouteID, TransitStop, TransitStopID, Turn, TurnID, TurnType, Zone,
 };
 
 impl Map {
@@ -152,6 +152,8 @@ impl Map {
             pathfinder_dirty: false,
             routing_params: RoutingParams::default(),
             name: MapName::blank(),
+            import_warnings: Vec::new(),
+            street_lamps: Vec::new(),
             edits: MapEdits::new(),
             edits_generation: 0,
             road_to_buildings: MultiMap::new(),
@@ -784,6 +786,14 @@ impl Map {
         &self.config
     }
 
+    pub fn get_import_warnings(&self) -> &Vec<ImportWarning> {
+        &self.import_warnings
+    }
+
+    pub fn all_street_lamps(&self) -> &Vec<Pt2D> {
+        &self.street_lamps
+    }
+
     /// Simple search along undirected roads. Expresses the result as a sequence of roads and a
     /// sequence of intersections.
     pub fn simple_path_btwn(
@@ -53,6 +53,12 @@ pub struct Intersection {
 
     /// Was a short road adjacent to this intersection merged?
     pub merged: bool,
+    /// Can vehicles U-turn here? Dead-ends always allow it. The default comes from
+    /// `default_u_turns`, but this can be edited.
+    pub allow_u_turns: bool,
+    /// Is this a dead-end with a turning circle mapped in OSM? If so, the polygon is a bulb
+    /// around the end of the road.
+    pub turning_circle: bool,
     // These increase the map file size, so instead, just use `recalculate_all_movements` after
     // deserializing.
     #[serde(skip_serializing, skip_deserializing)]
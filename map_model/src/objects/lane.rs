@@ -3,11 +3,11 @@ use std::fmt;
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use geom::{Distance, Line, PolyLine, Polygon, Pt2D};
+use geom::{Distance, Duration, Line, PolyLine, Polygon, Pt2D, Time};
 
 use crate::{
-    DirectedRoadID, Direction, DrivingSide, IntersectionID, LaneType, Map, MapConfig, Road, RoadID,
-    RoadSideID, SideOfRoad, TurnType,
+    DirectedRoadID, Direction, DrivingSide, IntersectionID, LaneType, Map, MapConfig,
+    PathConstraints, Road, RoadID, RoadSideID, SideOfRoad, TurnType,
 };
 
 /// From some manually audited cases in Seattle, the length of parallel street parking spots is a
@@ -72,6 +72,150 @@ impl<'de> Deserialize<'de> for LaneID {
     }
 }
 
+/// Car passengers aren't simulated, so every car just carries its driver.
+const CAR_OCCUPANCY: usize = 1;
+
+/// Limits who may drive in a general-purpose lane, either always or only during some hours.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LaneRestriction {
+    pub kind: LaneRestrictionKind,
+    /// The times of day when the restriction applies, repeated every day. If empty, it always
+    /// applies.
+    pub hours: Vec<(Time, Time)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LaneRestrictionKind {
+    /// Only buses, and bikes where they're allowed in bus lanes
+    BusOnly,
+    /// Only vehicles carrying at least this many people
+    HighOccupancy(usize),
+    /// Vehicles have to turn off the road, not continue straight
+    TurnOnly,
+}
+
+impl LaneRestriction {
+    pub fn always_active(&self) -> bool {
+        self.hours.is_empty()
+    }
+
+    /// Does the restriction apply at this time? Simulations can run past midnight, so the hours
+    /// repeat.
+    pub fn active_at(&self, time: Time) -> bool {
+        if self.hours.is_empty() {
+            return true;
+        }
+        let time_of_day = Time::START_OF_DAY
+            + Duration::seconds(time.inner_seconds() % Duration::hours(24).inner_seconds());
+        self.hours
+            .iter()
+            .any(|(start, end)| time_of_day >= *start && time_of_day < *end)
+    }
+
+    /// Can this kind of vehicle use the lane while the restriction applies?
+    pub fn allows_vehicle(&self, constraints: PathConstraints, map: &Map) -> bool {
+        let bikes_allowed =
+            constraints == PathConstraints::Bike && map.get_config().bikes_can_use_bus_lanes;
+        match self.kind {
+            LaneRestrictionKind::BusOnly => constraints == PathConstraints::Bus || bikes_allowed,
+            // Buses always count as high occupancy. Bikes get the same exemption as in bus lanes.
+            LaneRestrictionKind::HighOccupancy(min_people) => match constraints {
+                PathConstraints::Bus => true,
+                PathConstraints::Car => CAR_OCCUPANCY >= min_people,
+                _ => bikes_allowed,
+            },
+            LaneRestrictionKind::TurnOnly => true,
+        }
+    }
+
+    /// Can vehicles leaving the lane make this turn while the restriction applies?
+    pub fn allows_turn(&self, turn_type: TurnType) -> bool {
+        self.kind != LaneRestrictionKind::TurnOnly || turn_type != TurnType::Straight
+    }
+
+    pub fn describe(&self) -> String {
+        let kind = match self.kind {
+            LaneRestrictionKind::BusOnly => "bus only".to_string(),
+            LaneRestrictionKind::HighOccupancy(n) => format!("HOV {}+", n),
+            LaneRestrictionKind::TurnOnly => "turn only".to_string(),
+        };
+        if self.hours.is_empty() {
+            return kind;
+        }
+        let hours: Vec<String> = self
+            .hours
+            .iter()
+            .map(|(start, end)| format!("{} - {}", start.ampm_tostring(), end.ampm_tostring()))
+            .collect();
+        format!("{}, {}", kind, hours.join(" and "))
+    }
+}
+
+/// A reversible lane changes direction at some times of day, like a center lane on an arterial
+/// carrying inbound traffic in the morning and outbound traffic in the evening.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LaneReversalSchedule {
+    /// Starting at each time of day, the lane points this way. Sorted by time and repeated every
+    /// day. Before the first switch, the last switch of the previous day still applies.
+    pub switches: Vec<(Time, Direction)>,
+}
+
+impl LaneReversalSchedule {
+    /// Which way should the lane point at this time? Simulations can run past midnight, so the
+    /// schedule repeats.
+    pub fn direction_at(&self, time: Time) -> Option<Direction> {
+        let time_of_day = time_of_day(time);
+        self.switches
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= time_of_day)
+            .or_else(|| self.switches.last())
+            .map(|(_, dir)| *dir)
+    }
+
+    /// When does the lane next switch direction, strictly after this time?
+    pub fn next_switch_after(&self, time: Time) -> Option<Time> {
+        let start_of_day = time - (time_of_day(time) - Time::START_OF_DAY);
+        self.switches
+            .iter()
+            .map(|(at, _)| {
+                let switch = start_of_day + (*at - Time::START_OF_DAY);
+                if switch > time {
+                    switch
+                } else {
+                    switch + Duration::hours(24)
+                }
+            })
+            .min()
+    }
+
+    pub fn describe(&self) -> String {
+        let switches: Vec<String> = self
+            .switches
+            .iter()
+            .map(|(at, dir)| {
+                format!(
+                    "{} from {}",
+                    if *dir == Direction::Fwd {
+                        "forwards"
+                    } else {
+                        "backwards"
+                    },
+                    at.ampm_tostring()
+                )
+            })
+            .collect();
+        switches.join(", ")
+    }
+}
+
+/// Simulations can run past midnight, so daily schedules repeat. Returns the equivalent time
+/// during the first day.
+pub(crate) fn time_of_day(time: Time) -> Time {
+    Time::START_OF_DAY
+        + Duration::seconds(time.inner_seconds() % Duration::hours(24).inner_seconds())
+}
+
 /// A road segment is broken down into individual lanes, which have a LaneType.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Lane {
@@ -80,6 +224,9 @@ pub struct Lane {
     pub lane_center_pts: PolyLine,
     pub width: Distance,
     pub dir: Direction,
+    /// The values from OSM `turn:lanes` tags for this lane, like `left` or `through`. Empty when
+    /// the road isn't tagged. See `get_lane_level_turn_restrictions` for how these're interpreted.
+    pub turn_restrictions: Vec<String>,
 
     pub src_i: IntersectionID,
     pub dst_i: IntersectionID,
@@ -191,6 +338,20 @@ impl Lane {
         self.lane_type == LaneType::Footway || self.lane_type == LaneType::SharedUse
     }
 
+    /// A restriction on who may drive here, beyond the lane type
+    pub fn get_restriction<'a>(&self, map: &'a Map) -> Option<&'a LaneRestriction> {
+        map.get_r(self.id.road)
+            .lane_restrictions
+            .get(&self.id.offset)
+    }
+
+    /// When this lane changes direction, if it's reversible
+    pub fn get_reversal_schedule<'a>(&self, map: &'a Map) -> Option<&'a LaneReversalSchedule> {
+        map.get_r(self.id.road)
+            .reversible_lanes
+            .get(&self.id.offset)
+    }
+
     pub fn get_directed_parent(&self) -> DirectedRoadID {
         DirectedRoadID {
             road: self.id.road,
@@ -198,6 +359,11 @@ impl Lane {
         }
     }
 
+    /// The incline for somebody travelling along this lane. Positive is uphill.
+    pub fn percent_incline(&self, map: &Map) -> f64 {
+        map.get_r(self.id.road).percent_incline_in(self.dir)
+    }
+
     /// This does the reasonable thing for the leftmost and rightmost lane on a road -- except for
     /// roads with exactly one lane. For lanes in the middle of a road, it uses the direction of
     /// the lane -- so bidirectional/contraflow cycletracks will produce weird results.
@@ -243,37 +409,17 @@ impl Lane {
         if !self.is_driving() && (!force_bus || !self.is_bus()) {
             return None;
         }
-
-        // TODO This'll interpret turn restrictions along every segment of an OSM way. They maybe
-        // only make sense for the first or last segment.
-        // TODO We could plumb forward LaneSpec.turn_restrictions instead of redoing some work here
-
-        let all = if self.dir == Direction::Fwd {
-            road.osm_tags
-                .get("turn:lanes:forward")
-                .or_else(|| road.osm_tags.get("turn:lanes"))?
-        } else if self.dir == Direction::Back {
-            road.osm_tags.get("turn:lanes:backward")?
-        } else {
-            return None;
-        };
-        let parts: Vec<&str> = all.split('|').collect();
-        // Verify the number of parts matches the road's lanes
-        let lanes: Vec<LaneID> = road
-            .children(self.dir)
-            .into_iter()
-            .filter(|(_, lt)| *lt == LaneType::Driving || *lt == LaneType::Bus)
-            .map(|(id, _)| id)
-            .collect();
-        if parts.len() != lanes.len() {
-            warn!("{}'s turn restrictions don't match the lanes", road.orig_id);
+        // convert_osm copies the values from the road's turn:lanes tags onto each lane
+        if self.turn_restrictions.is_empty() {
             return None;
         }
-        // TODO More warnings if this fails
-        let part = parts[lanes.iter().position(|l| *l == self.id)?];
 
         // TODO Probably the target lane should get marked as LaneType::Bus
-        if part == "yes" || part == "psv" || part == "bus" {
+        if self
+            .turn_restrictions
+            .iter()
+            .any(|x| x == "yes" || x == "psv" || x == "bus")
+        {
             return None;
         }
 
@@ -284,10 +430,17 @@ impl Lane {
         // If a multi-lane road lacks markings, just listening to this function will mean that the
         // rightmos lanes could turn left, which probably isn't great for people in the middle
         // lanes going straight. Further filtering (in remove_merging_turns) will prune this out.
-        if part.is_empty() || part == "none" {
-            let all_explicit_types: BTreeSet<TurnType> = parts
+        if self
+            .turn_restrictions
+            .iter()
+            .all(|x| x.is_empty() || x == "none")
+        {
+            let all_explicit_types: BTreeSet<TurnType> = road
+                .lanes
                 .iter()
-                .flat_map(|part| part.split(';').flat_map(parse_turn_type_from_osm))
+                .filter(|l| l.dir == self.dir)
+                .flat_map(|l| l.turn_restrictions.iter())
+                .flat_map(|x| parse_turn_type_from_osm(x))
                 .collect();
             let mut implied = BTreeSet::new();
             implied.insert(TurnType::Straight);
@@ -299,7 +452,12 @@ impl Lane {
             return Some(implied);
         }
 
-        Some(part.split(';').flat_map(parse_turn_type_from_osm).collect())
+        Some(
+            self.turn_restrictions
+                .iter()
+                .flat_map(|x| parse_turn_type_from_osm(x))
+                .collect(),
+        )
     }
 
     pub fn common_endpoint(&self, other: &Lane) -> CommonEndpoint {
@@ -376,4 +534,33 @@ mod tests {
         };
         assert_eq!(l, LaneID::decode_u32(l.encode_u32()));
     }
+
+    #[test]
+    fn test_reversal_schedule() {
+        let hour = |h: f64| Time::START_OF_DAY + Duration::hours(1) * h;
+        // Reversed during the morning rush
+        let schedule = LaneReversalSchedule {
+            switches: vec![(hour(6.0), Direction::Back), (hour(10.0), Direction::Fwd)],
+        };
+
+        // Before the first switch, the last one from the previous day applies
+        assert_eq!(schedule.direction_at(hour(0.0)), Some(Direction::Fwd));
+        assert_eq!(schedule.direction_at(hour(6.0)), Some(Direction::Back));
+        assert_eq!(schedule.direction_at(hour(9.5)), Some(Direction::Back));
+        assert_eq!(schedule.direction_at(hour(10.0)), Some(Direction::Fwd));
+        // The schedule repeats after midnight
+        assert_eq!(schedule.direction_at(hour(31.0)), Some(Direction::Back));
+
+        assert_eq!(schedule.next_switch_after(hour(0.0)), Some(hour(6.0)));
+        // Strictly after
+        assert_eq!(schedule.next_switch_after(hour(6.0)), Some(hour(10.0)));
+        assert_eq!(schedule.next_switch_after(hour(12.0)), Some(hour(30.0)));
+        assert_eq!(schedule.next_switch_after(hour(26.0)), Some(hour(30.0)));
+
+        let empty = LaneReversalSchedule {
+            switches: Vec::new(),
+        };
+        assert_eq!(empty.direction_at(hour(7.0)), None);
+        assert_eq!(empty.next_switch_after(hour(7.0)), None);
+    }
 }
@@ -171,6 +171,12 @@ impl Movement {
         {
             return true;
         }
+        // U-turns sweep across the whole intersection, even if the geometry is tight
+        if (self.turn_type == TurnType::UTurn && !other.turn_type.pedestrian_crossing())
+            || (other.turn_type == TurnType::UTurn && !self.turn_type.pedestrian_crossing())
+        {
+            return true;
+        }
         // TODO If you hit a panic below, you've probably got two separate roads overlapping.
         // Fix it in OSM. Examples: https://www.openstreetmap.org/changeset/87465499,
         // https://www.openstreetmap.org/changeset/85952811
@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 use anyhow::Result;
@@ -6,12 +6,14 @@ use enumset::EnumSet;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize, Tags};
-use geom::{Distance, PolyLine, Polygon, Speed};
+use geom::{Distance, Duration, PolyLine, Polygon, Ring, Speed, Time, UnitFmt};
+use raw_map::SpeedLimitDefaults;
 
+use crate::objects::lane::time_of_day;
 use crate::{
     osm, AccessRestrictions, CommonEndpoint, CrossingType, Direction, DrivingSide, IntersectionID,
-    Lane, LaneID, LaneSpec, LaneType, Map, OriginalRoad, PathConstraints, RestrictionType,
-    TransitStopID, Zone,
+    Lane, LaneID, LaneRestriction, LaneReversalSchedule, LaneSpec, LaneType, Map, OriginalRoad,
+    PathConstraints, RestrictionType, TrafficCalming, TransitStopID, Zone,
 };
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -153,6 +155,121 @@ impl RoadSideID {
     }
 }
 
+/// Where a road's original speed limit came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpeedLimitSource {
+    /// From `maxspeed`, including implicit values like `DE:urban`
+    Tagged,
+    /// Guessed from the kind of road, country, lanes, and surroundings
+    Inferred,
+}
+
+/// A managed motorway changes its speed limit by time of day or in response to congestion, posting
+/// the current limit on overhead message signs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VariableSpeedLimit {
+    /// Starting at each time of day, this limit is posted. `None` goes back to the road's normal
+    /// limit. Sorted by time and repeated every day. Before the first change, the last change of
+    /// the previous day still applies.
+    pub schedule: Vec<(Time, Option<Speed>)>,
+    /// When there are at least this many vehicles per kilometer of each lane, post
+    /// `congested_limit` instead, if it's lower.
+    pub congested_density: Option<usize>,
+    pub congested_limit: Speed,
+}
+
+impl VariableSpeedLimit {
+    /// The limit to post at this time, given the road's normal limit
+    pub fn limit_at(&self, time: Time, normal: Speed, congested: bool) -> Speed {
+        let time_of_day = time_of_day(time);
+        let mut limit = self
+            .schedule
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= time_of_day)
+            .or_else(|| self.schedule.last())
+            .and_then(|(_, limit)| *limit)
+            .unwrap_or(normal);
+        if congested {
+            limit = limit.min(self.congested_limit);
+        }
+        limit
+    }
+
+    /// Is the road congested enough to lower the limit? Once it's lowered, wait for traffic to
+    /// thin out a bit more before raising it again, so the signs don't flicker.
+    pub fn is_congested(&self, vehicles_per_lane_km: f64, currently_lowered: bool) -> bool {
+        let threshold = match self.congested_density {
+            Some(x) => x as f64,
+            None => {
+                return false;
+            }
+        };
+        if currently_lowered {
+            vehicles_per_lane_km >= 0.75 * threshold
+        } else {
+            vehicles_per_lane_km >= threshold
+        }
+    }
+
+    pub fn describe(&self, units: &UnitFmt) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .schedule
+            .iter()
+            .map(|(at, limit)| match limit {
+                Some(limit) => format!("{} from {}", limit.to_string(units), at.ampm_tostring()),
+                None => format!("normal limit from {}", at.ampm_tostring()),
+            })
+            .collect();
+        if let Some(density) = self.congested_density {
+            lines.push(format!(
+                "{} when there are {} vehicles per lane-km",
+                self.congested_limit.to_string(units),
+                density
+            ));
+        }
+        lines
+    }
+}
+
+/// Responsive ramp meters never hold vehicles longer than this
+const MAX_RAMP_METER_CYCLE: Duration = Duration::const_seconds(15.0);
+
+/// A signal at the end of a motorway on-ramp, letting one vehicle onto the motorway per green
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RampMeter {
+    /// The shortest time between two vehicles
+    pub cycle: Duration,
+    /// If set, release vehicles more slowly when the motorway just past the ramp has more than
+    /// this many vehicles per lane-km
+    pub target_density: Option<usize>,
+}
+
+impl RampMeter {
+    /// How long to wait after releasing one vehicle before the next, given the density of the
+    /// motorway downstream
+    pub fn cycle_for(&self, vehicles_per_lane_km: f64) -> Duration {
+        match self.target_density {
+            Some(target) if target > 0 && vehicles_per_lane_km > target as f64 => {
+                // Slow down in proportion to how far over the target the motorway is
+                (self.cycle * (vehicles_per_lane_km / target as f64))
+                    .min(MAX_RAMP_METER_CYCLE.max(self.cycle))
+            }
+            _ => self.cycle,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self.target_density {
+            Some(target) => format!(
+                "one vehicle every {}, slower above {} vehicles per lane-km on the motorway",
+                self.cycle, target
+            ),
+            None => format!("one vehicle every {}", self.cycle),
+        }
+    }
+}
+
 /// A Road represents a segment between exactly two Intersections. It contains Lanes as children.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Road {
@@ -164,7 +281,21 @@ pub struct Road {
     pub complicated_turn_restrictions: Vec<(RoadID, RoadID)>,
     pub orig_id: OriginalRoad,
     pub speed_limit: Speed,
+    /// The speed limit before any edits
+    pub(crate) orig_speed_limit: Speed,
+    /// Whether the original speed limit was tagged in OSM or guessed. Edits don't change this.
+    ///
+    /// Maps are stored with bincode, which has no notion of missing fields. Adding this and
+    /// `orig_speed_limit` broke every existing map file, and they have to be regenerated by
+    /// re-importing.
+    pub speed_limit_source: SpeedLimitSource,
     pub access_restrictions: AccessRestrictions,
+    /// Restrictions on individual driving lanes, keyed by the lane's offset
+    pub lane_restrictions: BTreeMap<usize, LaneRestriction>,
+    /// Driving lanes that change direction by time of day, keyed by the lane's offset. The map
+    /// always has the lane pointing `Lane::dir`; while the schedule points it the other way, the
+    /// simulation closes it.
+    pub reversible_lanes: BTreeMap<usize, LaneReversalSchedule>,
     pub zorder: isize,
     /// [-1.0, 1.0] theoretically, but in practice, about [-0.25, 0.25]. 0 is flat,
     /// positive is uphill from src_i -> dst_i, negative is downhill.
@@ -188,6 +319,9 @@ pub struct Road {
     /// Is there a tagged crosswalk near each end of the road?
     pub crosswalk_forward: bool,
     pub crosswalk_backward: bool,
+    /// Are there curb ramps at the crossing near each end of the road? `None` means unknown.
+    pub curb_ramps_forward: Option<bool>,
+    pub curb_ramps_backward: Option<bool>,
 
     /// Meaningless order
     pub transit_stops: BTreeSet<TransitStopID>,
@@ -196,6 +330,17 @@ pub struct Road {
     pub barrier_nodes: Vec<Distance>,
     /// Some kind of crossing this distance along center_pts.
     pub crossing_nodes: Vec<(Distance, CrossingType)>,
+    /// Speed humps and other traffic calming this distance along center_pts
+    pub traffic_calming: Vec<(Distance, TrafficCalming)>,
+    /// The traffic calming before any edits
+    pub(crate) orig_traffic_calming: Vec<(Distance, TrafficCalming)>,
+    /// Managed motorways change their speed limit as the simulation runs. Adding this broke every
+    /// existing map file, since they're stored with bincode; they have to be regenerated.
+    pub variable_speed_limit: Option<VariableSpeedLimit>,
+    /// Only on motorway on-ramps
+    pub ramp_meter: Option<RampMeter>,
+    /// If this is a slip lane, the intersection that it lets vehicles turn before reaching
+    pub slip_lane: Option<IntersectionID>,
 }
 
 impl Road {
@@ -206,8 +351,7 @@ impl Road {
                 lt: l.lane_type,
                 dir: l.dir,
                 width: l.width,
-                // TODO These get lost from osm2streets
-                turn_restrictions: Vec::new(),
+                turn_restrictions: l.turn_restrictions.clone(),
             })
             .collect()
     }
@@ -233,40 +377,76 @@ impl Road {
     }
 
     pub(crate) fn speed_limit_from_osm(&self) -> Speed {
-        if let Some(limit) = self.osm_tags.get("maxspeed") {
-            if let Ok(kmph) = limit.parse::<f64>() {
-                if kmph == 0.0 {
-                    warn!("{} has a speed limit of 0", self.orig_id.osm_way_id);
-                    return Speed::miles_per_hour(1.0);
-                }
-                return Speed::km_per_hour(kmph);
-            }
+        self.orig_speed_limit
+    }
 
-            if let Some(mph) = limit
-                .strip_suffix(" mph")
-                .and_then(|x| x.parse::<f64>().ok())
-            {
-                return Speed::miles_per_hour(mph);
-            }
+    pub(crate) fn traffic_calming_from_osm(&self) -> Vec<(Distance, TrafficCalming)> {
+        self.orig_traffic_calming.clone()
+    }
 
-            // TODO Handle implicits, like PL:zone30
+    /// The average speed of a vehicle driving the whole road at the speed limit, slowing down
+    /// around each traffic calming device. Only meant for describing the road; vehicles apply
+    /// `traffic_calming_delay` to the part of the road they actually cross.
+    pub fn speed_limit_with_traffic_calming(&self, map: &Map) -> Speed {
+        let length = self.length();
+        if length == Distance::ZERO {
+            return self.speed_limit;
         }
+        let time = length / self.speed_limit
+            + self.traffic_calming_delay(Distance::ZERO, length, self.speed_limit, map);
+        Speed::meters_per_second(length.inner_meters() / time.inner_seconds())
+    }
 
-        // These're half reasonable guesses. Better to explicitly tag in OSM.
-        if self
-            .osm_tags
-            .is_any(osm::HIGHWAY, vec!["primary", "secondary", "motorway_link"])
-        {
-            return Speed::miles_per_hour(40.0);
-        }
-        if self.osm_tags.is(osm::HIGHWAY, "living_street") {
-            // about 12mph
-            return Speed::km_per_hour(20.0);
+    /// The extra time a vehicle otherwise moving at `speed` spends between `start` and `end`
+    /// (measured along the center line) slowing down for traffic calming. Each device only
+    /// slows vehicles down in a zone around it, and where zones overlap, only the slowest one
+    /// counts.
+    pub fn traffic_calming_delay(
+        &self,
+        start: Distance,
+        end: Distance,
+        speed: Speed,
+        map: &Map,
+    ) -> Duration {
+        if self.traffic_calming.is_empty() {
+            return Duration::ZERO;
         }
-        if self.is_service() {
-            return Speed::miles_per_hour(10.0);
+        calming_delay(
+            &self.traffic_calming,
+            start,
+            end,
+            speed,
+            &SpeedLimitDefaults::for_country(&map.get_name().city.country),
+        )
+    }
+
+    /// Is this a one-way link feeding into a motorway?
+    pub fn is_on_ramp(&self, map: &Map) -> bool {
+        self.on_ramp_joins(map).is_some()
+    }
+
+    /// If this is an on-ramp, the fastest motorway it joins.
+    pub fn on_ramp_joins(&self, map: &Map) -> Option<RoadID> {
+        if !self.osm_tags.is(osm::HIGHWAY, "motorway_link") {
+            return None;
         }
-        Speed::miles_per_hour(20.0)
+        let end = match self.oneway_for_driving()? {
+            Direction::Fwd => self.dst_i,
+            Direction::Back => self.src_i,
+        };
+        map.get_i(end)
+            .roads
+            .iter()
+            .filter(|r| **r != self.id && map.get_r(**r).osm_tags.is(osm::HIGHWAY, "motorway"))
+            .max_by_key(|r| map.get_r(**r).speed_limit)
+            .copied()
+    }
+
+    /// True if the current speed limit was guessed during import, so the editor can point out
+    /// limits worth checking.
+    pub fn is_speed_limit_inferred(&self) -> bool {
+        self.speed_limit_source == SpeedLimitSource::Inferred
+            && self.speed_limit == self.orig_speed_limit
     }
 
     /// Includes off-side
@@ -332,6 +512,15 @@ impl Road {
         self.center_pts.length()
     }
 
+    /// The incline for somebody travelling along this road in one direction. Positive is uphill.
+    pub fn percent_incline_in(&self, dir: Direction) -> f64 {
+        if dir == Direction::Fwd {
+            self.percent_incline
+        } else {
+            -self.percent_incline
+        }
+    }
+
     /// Creates the thick polygon representing one half of the road. For roads with multiple
     /// direction changes (like a two-way cycletrack adjacent to a regular two-way road), the
     /// results are probably weird.
@@ -437,10 +626,20 @@ impl Road {
             )
     }
 
+    /// Only footways with stairs would match this.
+    pub fn is_stairs(&self) -> bool {
+        self.osm_tags.is(osm::HIGHWAY, "steps")
+    }
+
     pub fn is_service(&self) -> bool {
         self.osm_tags.is(osm::HIGHWAY, "service")
     }
 
+    /// Alleys and driveways are service roads only meant for reaching the places along them.
+    pub fn is_driveway_or_alley(&self) -> bool {
+        self.is_service() && self.osm_tags.is_any("service", vec!["driveway", "alley"])
+    }
+
     pub fn is_cycleway(&self) -> bool {
         let mut bike = false;
         for lane in &self.lanes {
@@ -461,6 +660,25 @@ impl Road {
         CommonEndpoint::new((self.src_i, self.dst_i), (other.src_i, other.dst_i))
     }
 
+    /// For slip lanes, the channelizing island between this road and the intersection it bypasses.
+    /// Most of this polygon is covered by the adjacent roads and intersection, so it's only meant
+    /// to be drawn underneath them.
+    pub fn slip_lane_island(&self, map: &Map) -> Option<Polygon> {
+        let main = map.get_i(self.slip_lane?).polygon.center();
+        let left = self.center_pts.shift_left(self.get_half_width()).ok()?;
+        let right = self.center_pts.shift_right(self.get_half_width()).ok()?;
+        let edge = if left.middle().dist_to(main) < right.middle().dist_to(main) {
+            left
+        } else {
+            right
+        };
+        let mut pts = edge.into_points();
+        let first = pts[0];
+        pts.push(main);
+        pts.push(first);
+        Ring::new(pts).ok().map(|ring| ring.into_polygon())
+    }
+
     /// Returns the other intersection of this road, panicking if this road doesn't connect to the
     /// input
     /// TODO This should use CommonEndpoint
@@ -487,6 +705,9 @@ impl Road {
                 allow |= PathConstraints::Bus;
             }
             allow
+        } else if self.is_driveway_or_alley() {
+            // Vehicles can only use these to reach somewhere along them
+            PathConstraints::Pedestrian | PathConstraints::Bike
         } else {
             EnumSet::all()
         };
@@ -577,6 +798,7 @@ impl Road {
                 dst_i,
                 lane_type: lane.lt,
                 dir: lane.dir,
+                turn_restrictions: lane.turn_restrictions,
                 driving_blackhole: false,
                 biking_blackhole: false,
             });
@@ -624,6 +846,71 @@ impl Road {
         self.get_rank() != osm::RoadRank::Local
     }
 
+    /// Classifies cycling along the directed road into the usual 4 levels of traffic stress. LTS
+    /// 1 is comfortable for children, 2 for most adults, 3 only for confident cyclists, and 4 only
+    /// for the "strong and fearless." This is a simplification of the Mineta criteria, based on
+    /// the speed limit, the number of lanes with moving traffic, and bike infrastructure. Returns
+    /// `None` if bikes can't use the road at all.
+    pub fn bike_traffic_stress(&self, map: &Map, dir: Direction) -> Option<usize> {
+        let mut bike_lane = false;
+        let mut can_use = false;
+        let mut buffered = false;
+        let mut lanes_in_dir = 0;
+        let mut lanes_total = 0;
+        for l in &self.lanes {
+            if l.lane_type == LaneType::Biking && l.dir == dir {
+                bike_lane = true;
+            }
+            if PathConstraints::Bike.can_use(l, map) {
+                can_use = true;
+            }
+            if matches!(l.lane_type, LaneType::Buffer(_)) {
+                buffered = true;
+            }
+            if matches!(l.lane_type, LaneType::Driving | LaneType::Bus) {
+                lanes_total += 1;
+                if l.dir == dir {
+                    lanes_in_dir += 1;
+                }
+            }
+        }
+        if !can_use {
+            return None;
+        }
+        // A path separated from all motor vehicles
+        if lanes_total == 0 {
+            return Some(1);
+        }
+
+        let under = |mph: f64| self.speed_limit <= Speed::miles_per_hour(mph);
+        let level = if bike_lane {
+            let level = if under(25.0) && lanes_in_dir <= 1 {
+                1
+            } else if under(30.0) && lanes_in_dir <= 2 {
+                2
+            } else if under(35.0) {
+                3
+            } else {
+                4
+            };
+            // Physical separation from traffic makes up for a bit of speed
+            if buffered {
+                (level - 1).max(1)
+            } else {
+                level
+            }
+        } else if under(25.0) && lanes_total <= 2 && self.get_rank() == osm::RoadRank::Local {
+            1
+        } else if (under(25.0) && lanes_total <= 3) || (under(30.0) && lanes_total <= 2) {
+            2
+        } else if under(30.0) || (under(35.0) && lanes_total <= 2) {
+            3
+        } else {
+            4
+        };
+        Some(level)
+    }
+
     pub fn oneway_for_driving(&self) -> Option<Direction> {
         LaneSpec::oneway_for_driving(&self.lane_specs())
     }
@@ -682,3 +969,206 @@ impl Road {
         }
     }
 }
+
+/// How slow vehicles go around a traffic calming device, and for how long of a stretch. Speeds
+/// are rounded to what drivers would aim for in the local units.
+fn calming_slowdown(device: TrafficCalming, locale: &SpeedLimitDefaults) -> (Speed, Distance) {
+    let (mph, kmph, zone) = match device {
+        TrafficCalming::SpeedHump => (15.0, 20.0, 20.0),
+        TrafficCalming::RaisedCrossing => (20.0, 30.0, 15.0),
+        TrafficCalming::Chicane => (20.0, 30.0, 30.0),
+    };
+    (
+        locale.speed(if locale.mph { mph } else { kmph }),
+        Distance::meters(zone),
+    )
+}
+
+fn calming_delay(
+    devices: &[(Distance, TrafficCalming)],
+    start: Distance,
+    end: Distance,
+    speed: Speed,
+    locale: &SpeedLimitDefaults,
+) -> Duration {
+    if end <= start {
+        return Duration::ZERO;
+    }
+    let zones: Vec<(Distance, Distance, Speed)> = devices
+        .iter()
+        .map(|(dist, device)| {
+            let (slow_speed, zone) = calming_slowdown(*device, locale);
+            (*dist - zone / 2.0, *dist + zone / 2.0, slow_speed)
+        })
+        .collect();
+
+    // Split [start, end] wherever a zone begins or ends, so each piece is covered by a fixed set
+    // of zones
+    let mut breakpoints = vec![start, end];
+    for (zone_start, zone_end, _) in &zones {
+        for pt in [*zone_start, *zone_end] {
+            if pt > start && pt < end {
+                breakpoints.push(pt);
+            }
+        }
+    }
+    breakpoints.sort();
+
+    let mut delay = Duration::ZERO;
+    for pair in breakpoints.windows(2) {
+        let segment = pair[1] - pair[0];
+        if segment == Distance::ZERO {
+            continue;
+        }
+        let middle = pair[0] + segment / 2.0;
+        let slowest = zones
+            .iter()
+            .filter(|(zone_start, zone_end, _)| *zone_start <= middle && middle <= *zone_end)
+            .map(|(_, _, slow_speed)| *slow_speed)
+            .min();
+        if let Some(slow_speed) = slowest {
+            if slow_speed < speed {
+                delay += segment / slow_speed - segment / speed;
+            }
+        }
+    }
+    delay
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delay(devices: &[(f64, TrafficCalming)], start: f64, end: f64, country: &str) -> f64 {
+        let devices: Vec<(Distance, TrafficCalming)> = devices
+            .iter()
+            .map(|(dist, device)| (Distance::meters(*dist), *device))
+            .collect();
+        calming_delay(
+            &devices,
+            Distance::meters(start),
+            Distance::meters(end),
+            Speed::miles_per_hour(30.0),
+            &SpeedLimitDefaults::for_country(country),
+        )
+        .inner_seconds()
+    }
+
+    fn approx_eq(x: f64, y: f64) -> bool {
+        (x - y).abs() < 0.001
+    }
+
+    #[test]
+    fn test_calming_delay_is_local() {
+        let hump = [(100.0, TrafficCalming::SpeedHump)];
+        let full = delay(&hump, 0.0, 200.0, "us");
+        assert!(full > 0.0);
+        // Far from the hump, there's no delay
+        assert_eq!(delay(&hump, 0.0, 80.0, "us"), 0.0);
+        assert_eq!(delay(&hump, 120.0, 200.0, "us"), 0.0);
+        // Crossing half of the zone costs half the delay
+        assert!(approx_eq(delay(&hump, 0.0, 100.0, "us"), full / 2.0));
+    }
+
+    #[test]
+    fn test_overlapping_calming_zones() {
+        let one = delay(&[(100.0, TrafficCalming::SpeedHump)], 0.0, 200.0, "us");
+        // Two humps at the same spot don't slow anybody down more than one
+        let stacked = [
+            (100.0, TrafficCalming::SpeedHump),
+            (100.0, TrafficCalming::SpeedHump),
+        ];
+        assert!(approx_eq(delay(&stacked, 0.0, 200.0, "us"), one));
+        // A chicane around the hump only adds delay where it extends past the hump
+        let mixed = [
+            (100.0, TrafficCalming::SpeedHump),
+            (100.0, TrafficCalming::Chicane),
+        ];
+        let chicane = delay(&[(100.0, TrafficCalming::Chicane)], 0.0, 200.0, "us");
+        let mixed = delay(&mixed, 0.0, 200.0, "us");
+        assert!(mixed > one && mixed > chicane && mixed < one + chicane);
+    }
+
+    #[test]
+    fn test_ramp_meter_cycle() {
+        let fixed = RampMeter {
+            cycle: Duration::seconds(4.0),
+            target_density: None,
+        };
+        assert_eq!(fixed.cycle_for(100.0), Duration::seconds(4.0));
+
+        let responsive = RampMeter {
+            cycle: Duration::seconds(4.0),
+            target_density: Some(20),
+        };
+        // At or under the target, the meter runs at its usual rate
+        assert_eq!(responsive.cycle_for(10.0), Duration::seconds(4.0));
+        assert_eq!(responsive.cycle_for(20.0), Duration::seconds(4.0));
+        // Over it, the meter slows down in proportion
+        assert_eq!(responsive.cycle_for(30.0), Duration::seconds(6.0));
+        // But never holds vehicles too long
+        assert_eq!(responsive.cycle_for(1000.0), MAX_RAMP_METER_CYCLE);
+    }
+
+    #[test]
+    fn test_variable_speed_limit() {
+        let normal = Speed::miles_per_hour(60.0);
+        let hour = |h: usize| Time::START_OF_DAY + Duration::hours(h);
+        let control = VariableSpeedLimit {
+            schedule: vec![
+                (hour(7), Some(Speed::miles_per_hour(50.0))),
+                (hour(10), None),
+                (hour(22), Some(Speed::miles_per_hour(55.0))),
+            ],
+            congested_density: Some(40),
+            congested_limit: Speed::miles_per_hour(40.0),
+        };
+
+        assert_eq!(
+            control.limit_at(hour(8), normal, false),
+            Speed::miles_per_hour(50.0)
+        );
+        assert_eq!(control.limit_at(hour(12), normal, false), normal);
+        // Before the first change, the last one from the previous day applies
+        assert_eq!(
+            control.limit_at(hour(3), normal, false),
+            Speed::miles_per_hour(55.0)
+        );
+        // The schedule repeats every day
+        assert_eq!(
+            control.limit_at(hour(24 + 8), normal, false),
+            Speed::miles_per_hour(50.0)
+        );
+        // Congestion only lowers the limit
+        assert_eq!(
+            control.limit_at(hour(12), normal, true),
+            Speed::miles_per_hour(40.0)
+        );
+        let slow = VariableSpeedLimit {
+            schedule: vec![(hour(0), Some(Speed::miles_per_hour(30.0)))],
+            ..control.clone()
+        };
+        assert_eq!(
+            slow.limit_at(hour(12), normal, true),
+            Speed::miles_per_hour(30.0)
+        );
+
+        // Once lowered, the limit stays down until traffic thins out more
+        assert!(!control.is_congested(35.0, false));
+        assert!(control.is_congested(40.0, false));
+        assert!(control.is_congested(35.0, true));
+        assert!(!control.is_congested(25.0, true));
+        let no_sensors = VariableSpeedLimit {
+            congested_density: None,
+            ..control
+        };
+        assert!(!no_sensors.is_congested(1000.0, true));
+    }
+
+    #[test]
+    fn test_calming_speeds_use_local_units() {
+        let hump = [(100.0, TrafficCalming::SpeedHump)];
+        // 20km/h is a bit slower than 15mph
+        assert!(delay(&hump, 0.0, 200.0, "de") > delay(&hump, 0.0, 200.0, "us"));
+    }
+}
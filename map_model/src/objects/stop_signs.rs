@@ -93,20 +93,46 @@ impl ControlStopSign {
             }
         }
 
+        // Slip lanes merge into traffic from the side, so they yield to everything else. Decide
+        // everything else as if they weren't there.
+        let (mut slip_lanes, others): (BTreeMap<_, _>, BTreeMap<_, _>) =
+            std::mem::take(&mut ss.roads)
+                .into_iter()
+                .partition(|(r, _)| map.get_r(*r).slip_lane.is_some());
+        ss.roads = others;
+        ss.assign_stop_signs(map);
+        for cfg in slip_lanes.values_mut() {
+            cfg.must_stop = true;
+        }
+        ss.roads.extend(slip_lanes);
+        ss
+    }
+
+    /// Decide which of the incoming roads stop, starting from none of them.
+    fn assign_stop_signs(&mut self, map: &Map) {
+        // Vehicles leaving an alley or driveway yield to the street, which doesn't stop at all.
+        let minor = |r: &RoadID| map.get_r(*r).is_driveway_or_alley();
+        if self.roads.keys().any(minor) && !self.roads.keys().all(minor) {
+            for (r, cfg) in self.roads.iter_mut() {
+                cfg.must_stop = minor(r);
+            }
+            return;
+        }
+
         // Degenerate roads and deadends don't need any stop signs. But be careful with
         // roundabouts; we want it to be lower priority to enter a roundabout than continue through
         // it.
-        if ss.roads.len() <= 2
-            && ss
+        if self.roads.len() <= 2
+            && self
                 .roads
                 .keys()
                 .all(|r| !map.get_r(*r).osm_tags.is("junction", "roundabout"))
         {
-            return ss;
+            return;
         }
-        if map.get_i(id).is_cycleway(map) {
+        if map.get_i(self.id).is_cycleway(map) {
             // Two cyclepaths intersecting can just yield.
-            return ss;
+            return;
         }
 
         // Rank each road based on OSM highway type, and additionally:
@@ -115,7 +141,7 @@ impl ControlStopSign {
         // - Treat on/off ramps with less priority than the main part of the highway
         // - Lower the priority of service roads
         let mut rank: HashMap<RoadID, (osm::RoadRank, usize)> = HashMap::new();
-        for r in ss.roads.keys() {
+        for r in self.roads.keys() {
             let r = map.get_r(*r);
             // Lower number is lower priority
             let priority = if r.is_cycleway() || r.osm_tags.is(osm::HIGHWAY, "service") {
@@ -142,7 +168,7 @@ impl ControlStopSign {
 
         // If all roads have the same rank, all-way stop. Otherwise, everything stops except the
         // highest-priority roads.
-        for (r, cfg) in ss.roads.iter_mut() {
+        for (r, cfg) in self.roads.iter_mut() {
             if ranks.len() == 1 || rank[r] != ranks[0] {
                 // Don't stop in the middle of something that's likely actually an intersection.
                 if !map.get_r(*r).is_extremely_short() {
@@ -150,7 +176,6 @@ impl ControlStopSign {
                 }
             }
         }
-        ss
     }
 
     /// Get the priority of a turn according to the stop sign -- either protected or yield, never
@@ -160,6 +185,8 @@ impl ControlStopSign {
             TurnType::SharedSidewalkCorner => TurnPriority::Protected,
             TurnType::Crosswalk => TurnPriority::Protected,
             TurnType::UnmarkedCrossing => TurnPriority::Yield,
+            // Even on a road that doesn't stop, U-turns have to wait for a gap
+            TurnType::UTurn => TurnPriority::Yield,
             _ => {
                 if self.roads[&turn.src.road].must_stop {
                     TurnPriority::Yield
@@ -7,13 +7,20 @@ use geom::{Distance, Duration, Speed};
 
 use crate::make::traffic_signals::get_possible_policies;
 use crate::{
-    Intersection, IntersectionID, Map, Movement, MovementID, RoadID, TurnID, TurnPriority,
+    osm, Intersection, IntersectionID, Map, Movement, MovementID, RoadID, TurnID, TurnPriority,
 };
 
 // The pace to use for crosswalk pace in m/s
 // https://en.wikipedia.org/wiki/Preferred_walking_speed
 const CROSSWALK_PACE: Speed = Speed::const_meters_per_second(1.4);
 
+/// Before a stage ends, vehicle movements that don't continue into the next stage turn yellow for
+/// this long. Vehicles that haven't started the movement by then have to stop.
+pub const YELLOW_INTERVAL: Duration = Duration::const_seconds(3.0);
+/// When a stage starts, movements that weren't allowed during the previous stage stay red for this
+/// long, so vehicles that entered on yellow can clear the intersection.
+pub const ALL_RED_INTERVAL: Duration = Duration::const_seconds(2.0);
+
 /// A traffic signal consists of a sequence of Stages that repeat in a cycle. Most Stages last for a
 /// fixed duration. During a single Stage, some movements are protected (can proceed with the
 /// highest priority), while others are permitted (have to yield before proceeding).
@@ -22,6 +29,13 @@ pub struct ControlTrafficSignal {
     pub id: IntersectionID,
     pub stages: Vec<Stage>,
     pub offset: Duration,
+    /// Movements with a separate signal head for cyclists. Bikes making these movements follow
+    /// `Stage::bike_movements`, not the signal for general traffic.
+    ///
+    /// Signals are saved as part of the map with bincode, which has no notion of missing fields.
+    /// Adding this, `Stage::bike_movements`, and `Stage::lead_lag` broke every existing map file;
+    /// they have to be regenerated by re-importing.
+    pub bike_signals: BTreeSet<MovementID>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -31,6 +45,11 @@ pub struct Stage {
     // TODO Not renaming this, because this is going to change radically in
     // https://github.com/a-b-street/abstreet/pull/298 anyway
     pub stage_type: StageType,
+    /// Movements with a bike signal that's green during this stage. A stage with only these is a
+    /// bike-only phase.
+    pub bike_movements: BTreeSet<MovementID>,
+    /// Crosswalks and movements with a bike signal can start before or after general traffic.
+    pub lead_lag: Option<LeadLagInterval>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -42,6 +61,24 @@ pub enum StageType {
     Variable(Duration, Duration, Duration),
 }
 
+/// Part of a stage when only some movements can go, measured from the start of the stage. This
+/// applies to crosswalks, and to cyclists making a movement with its own bike signal.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum LeadLagInterval {
+    /// Pedestrians and cyclists get a head start on general traffic
+    Leading(Duration),
+    /// General traffic goes first, letting turning vehicles clear
+    Lagging(Duration),
+}
+
+impl LeadLagInterval {
+    pub fn duration(self) -> Duration {
+        match self {
+            LeadLagInterval::Leading(d) | LeadLagInterval::Lagging(d) => d,
+        }
+    }
+}
+
 impl StageType {
     // TODO Maybe don't have this; force callers to acknowledge different policies
     pub fn simple_duration(&self) -> Duration {
@@ -123,6 +160,43 @@ impl ControlTrafficSignal {
                 // TODO Maybe make UnmarkedCrossing yield
                 assert!(!m.turn_type.pedestrian_crossing())
             }
+            // Bikes need their own signal head to get a separate green
+            for m in &stage.bike_movements {
+                if !self.bike_signals.contains(m) {
+                    bail!(
+                        "Stage {} gives bikes a green for {:?}, which has no bike signal",
+                        stage_index,
+                        m
+                    );
+                }
+                let m1 = &i.movements[m];
+                for m2 in stage
+                    .bike_movements
+                    .iter()
+                    .chain(stage.protected_movements.iter().filter(|x| !x.crosswalk))
+                    .map(|x| &i.movements[x])
+                {
+                    if m1.conflicts_with(m2) {
+                        bail!(
+                            "Traffic signal has a bike movement conflicting with a protected \
+                             movement in one stage:\n{:?}\n\n{:?}",
+                            m1,
+                            m2
+                        );
+                    }
+                }
+            }
+            if let Some(interval) = stage.lead_lag {
+                if interval.duration() >= stage.stage_type.simple_duration() {
+                    bail!(
+                        "Stage {} has a leading or lagging interval of {}, but only lasts {}",
+                        stage_index,
+                        interval.duration(),
+                        stage.stage_type.simple_duration()
+                    );
+                }
+            }
+
             // Is there enough time in each stage to walk across the crosswalk
             let min_crossing_time = self.get_min_crossing_time(stage_index, i);
             if stage.stage_type.simple_duration() < min_crossing_time {
@@ -136,9 +210,95 @@ impl ControlTrafficSignal {
                 );
             }
         }
+        for m in &self.bike_signals {
+            if !i.movements.contains_key(m) || m.crosswalk {
+                bail!("Bike signal for {:?} isn't a vehicle movement", m);
+            }
+            if !self.stages.iter().any(|s| s.bike_movements.contains(m)) {
+                bail!("Bike signal for {:?} is never green", m);
+            }
+        }
         Ok(())
     }
 
+    /// The priority of a turn during one stage. Cyclists follow their own signal for movements
+    /// that have one, and the general signal otherwise.
+    pub fn get_priority_of_turn(
+        &self,
+        stage: usize,
+        t: TurnID,
+        bike: bool,
+        i: &Intersection,
+    ) -> TurnPriority {
+        self.movement_priority(stage, i.turn_to_movement(t).0, bike)
+    }
+
+    fn movement_priority(&self, stage: usize, m: MovementID, bike: bool) -> TurnPriority {
+        let stage = &self.stages[stage];
+        if bike && self.bike_signals.contains(&m) {
+            if stage.bike_movements.contains(&m) {
+                TurnPriority::Protected
+            } else {
+                TurnPriority::Banned
+            }
+        } else {
+            stage.get_priority_of_movement(m)
+        }
+    }
+
+    /// Is a vehicle movement allowed during this stage, but about to end? `time_left` is how much
+    /// longer the stage lasts. Variable stages might extend instead of ending, so they don't have
+    /// a yellow.
+    pub fn is_yellow(&self, stage: usize, m: MovementID, bike: bool, time_left: Duration) -> bool {
+        let next = (stage + 1) % self.stages.len();
+        !m.crosswalk
+            && matches!(self.stages[stage].stage_type, StageType::Fixed(_))
+            && time_left <= YELLOW_INTERVAL
+            && self.movement_priority(stage, m, bike) != TurnPriority::Banned
+            && self.movement_priority(next, m, bike) == TurnPriority::Banned
+    }
+
+    /// If a movement has to wait for the all-red interval at the start of a stage, returns how
+    /// much longer. `elapsed` is how long the stage has been active.
+    pub fn all_red_wait(
+        &self,
+        stage: usize,
+        m: MovementID,
+        bike: bool,
+        elapsed: Duration,
+    ) -> Option<Duration> {
+        let prev = (stage + self.stages.len() - 1) % self.stages.len();
+        if elapsed >= ALL_RED_INTERVAL
+            || self.movement_priority(prev, m, bike) != TurnPriority::Banned
+        {
+            None
+        } else {
+            Some(ALL_RED_INTERVAL - elapsed)
+        }
+    }
+
+    /// If a movement has to wait for a leading or lagging interval to end, returns how much
+    /// longer. `elapsed` is how long the stage has been active.
+    pub fn lead_lag_wait(
+        &self,
+        stage: usize,
+        m: MovementID,
+        bike: bool,
+        elapsed: Duration,
+    ) -> Option<Duration> {
+        let interval = self.stages[stage].lead_lag?;
+        let vulnerable = m.crosswalk || (bike && self.bike_signals.contains(&m));
+        let goes_first = match interval {
+            LeadLagInterval::Leading(_) => vulnerable,
+            LeadLagInterval::Lagging(_) => !vulnerable,
+        };
+        if goes_first || elapsed >= interval.duration() {
+            None
+        } else {
+            Some(interval.duration() - elapsed)
+        }
+    }
+
     /// Move crosswalks from stages, adding them to an all-walk as last stage. This may promote
     /// yields to protected. True is returned if any stages were added or modified.
     pub fn convert_to_ped_scramble(&mut self, i: &Intersection) -> bool {
@@ -287,6 +447,8 @@ impl Stage {
             yield_movements: BTreeSet::new(),
             // TODO Set a default
             stage_type: StageType::Fixed(Duration::seconds(30.0)),
+            bike_movements: BTreeSet::new(),
+            lead_lag: None,
         }
     }
 
@@ -396,10 +558,32 @@ impl ControlTrafficSignal {
                                 )
                             }
                         },
+                        bike_turns: s
+                            .bike_movements
+                            .iter()
+                            .map(|mvmnt| mvmnt.to_permanent(map))
+                            .collect(),
+                        lead_lag: s.lead_lag.map(|interval| match interval {
+                            LeadLagInterval::Leading(d) => {
+                                traffic_signal_data::LeadLagInterval::Leading(
+                                    d.inner_seconds() as usize
+                                )
+                            }
+                            LeadLagInterval::Lagging(d) => {
+                                traffic_signal_data::LeadLagInterval::Lagging(
+                                    d.inner_seconds() as usize
+                                )
+                            }
+                        }),
                     })
                     .collect(),
                 offset_seconds: self.offset.inner_seconds() as usize,
             }],
+            bike_signals: self
+                .bike_signals
+                .iter()
+                .map(|mvmnt| mvmnt.to_permanent(map))
+                .collect(),
         }
     }
 
@@ -408,6 +592,9 @@ impl ControlTrafficSignal {
         id: IntersectionID,
         map: &Map,
     ) -> Result<ControlTrafficSignal> {
+        if raw.plans.is_empty() {
+            bail!("{} has no timing plans", id);
+        }
         // TODO Only import the first plan. Will import all of them later.
         let plan = raw.plans.remove(0);
         let mut stages = Vec::new();
@@ -435,10 +622,30 @@ impl ControlTrafficSignal {
                     }
                 }
             }
+            let mut bike_movements = BTreeSet::new();
+            for t in s.bike_turns {
+                match MovementID::from_permanent(t, map) {
+                    Ok(mvmnt) => {
+                        bike_movements.insert(mvmnt);
+                    }
+                    Err(err) => {
+                        errors.push(err.to_string());
+                    }
+                }
+            }
             if errors.is_empty() {
                 stages.push(Stage {
                     protected_movements,
                     yield_movements: permitted_movements,
+                    bike_movements,
+                    lead_lag: s.lead_lag.map(|interval| match interval {
+                        traffic_signal_data::LeadLagInterval::Leading(d) => {
+                            LeadLagInterval::Leading(Duration::seconds(d as f64))
+                        }
+                        traffic_signal_data::LeadLagInterval::Lagging(d) => {
+                            LeadLagInterval::Lagging(Duration::seconds(d as f64))
+                        }
+                    }),
                     stage_type: match s.stage_type {
                         traffic_signal_data::StageType::Fixed(d) => {
                             StageType::Fixed(Duration::seconds(d as f64))
@@ -456,12 +663,211 @@ impl ControlTrafficSignal {
                 bail!("{}", errors.join("; "));
             }
         }
+        let mut bike_signals = BTreeSet::new();
+        for t in raw.bike_signals {
+            bike_signals.insert(MovementID::from_permanent(t, map)?);
+        }
         let ts = ControlTrafficSignal {
             id,
             stages,
             offset: Duration::seconds(plan.offset_seconds as f64),
+            bike_signals,
         };
         ts.validate(map.get_i(id))?;
         Ok(ts)
     }
+
+    /// Exports the timing of every traffic signal in the map, so it can be shared with other
+    /// tools or imported into another version of the map.
+    pub fn export_all(map: &Map) -> Vec<traffic_signal_data::TrafficSignal> {
+        map.all_intersections()
+            .iter()
+            .filter(|i| i.is_traffic_signal())
+            .map(|i| map.get_traffic_signal(i.id).export(map))
+            .collect()
+    }
+
+    /// Matches each signal to an intersection by OSM node ID and imports the timing. Signals that
+    /// can't be matched or don't fit the intersection's movements are skipped, with one error per
+    /// signal returned.
+    pub fn import_all(
+        raw: Vec<traffic_signal_data::TrafficSignal>,
+        map: &Map,
+    ) -> (Vec<ControlTrafficSignal>, Vec<String>) {
+        let mut signals = Vec::new();
+        let mut errors = Vec::new();
+        for ts in raw {
+            let osm_id = ts.intersection_osm_node_id;
+            match map
+                .find_i_by_osm_id(osm::NodeID(osm_id))
+                .and_then(|i| ControlTrafficSignal::import(ts, i, map))
+            {
+                Ok(signal) => {
+                    signals.push(signal);
+                }
+                Err(err) => {
+                    errors.push(format!("OSM node {}: {}", osm_id, err));
+                }
+            }
+        }
+        (signals, errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DirectedRoadID, Direction};
+
+    fn movement(from: usize, to: usize) -> MovementID {
+        MovementID {
+            from: DirectedRoadID {
+                road: RoadID(from),
+                dir: Direction::Fwd,
+            },
+            to: DirectedRoadID {
+                road: RoadID(to),
+                dir: Direction::Fwd,
+            },
+            parent: IntersectionID(0),
+            crosswalk: false,
+        }
+    }
+
+    #[test]
+    fn test_yellow_and_all_red() {
+        let north_south = movement(0, 1);
+        let east_west = movement(2, 3);
+        let mut stage1 = Stage::new();
+        stage1.protected_movements.insert(north_south);
+        let mut stage2 = Stage::new();
+        stage2.protected_movements.insert(east_west);
+        let mut signal = ControlTrafficSignal {
+            id: IntersectionID(0),
+            stages: vec![stage1, stage2],
+            offset: Duration::ZERO,
+            bike_signals: BTreeSet::new(),
+        };
+
+        // Only the movement that's ending turns yellow, and only near the end of the stage
+        assert!(signal.is_yellow(0, north_south, false, Duration::seconds(2.0)));
+        assert!(!signal.is_yellow(0, north_south, false, Duration::seconds(10.0)));
+        assert!(!signal.is_yellow(0, east_west, false, Duration::seconds(2.0)));
+
+        // The movement that's starting waits out the all-red
+        assert_eq!(
+            signal.all_red_wait(1, east_west, false, Duration::seconds(0.5)),
+            Some(ALL_RED_INTERVAL - Duration::seconds(0.5))
+        );
+        assert_eq!(
+            signal.all_red_wait(1, east_west, false, ALL_RED_INTERVAL),
+            None
+        );
+
+        // Movements that continue into the next stage don't stop
+        signal.stages[1].yield_movements.insert(north_south);
+        assert!(!signal.is_yellow(0, north_south, false, Duration::seconds(2.0)));
+        assert_eq!(
+            signal.all_red_wait(0, north_south, false, Duration::ZERO),
+            None
+        );
+
+        // Variable stages might extend, so they don't have a yellow
+        signal.stages[0].stage_type = StageType::Variable(
+            Duration::seconds(30.0),
+            Duration::seconds(5.0),
+            Duration::seconds(30.0),
+        );
+        signal.stages[1].yield_movements.clear();
+        assert!(!signal.is_yellow(0, north_south, false, Duration::seconds(2.0)));
+    }
+
+    #[test]
+    fn test_bike_signals() {
+        let north_south = movement(0, 1);
+        let east_west = movement(2, 3);
+        let mut stage1 = Stage::new();
+        stage1.protected_movements.insert(north_south);
+        let mut stage2 = Stage::new();
+        stage2.protected_movements.insert(east_west);
+        // A bike-only phase for the north-south movement
+        stage2.bike_movements.insert(north_south);
+        let signal = ControlTrafficSignal {
+            id: IntersectionID(0),
+            stages: vec![stage1, stage2],
+            offset: Duration::ZERO,
+            bike_signals: vec![north_south].into_iter().collect(),
+        };
+
+        // Cyclists follow their own signal, everybody else the general one
+        assert_eq!(
+            signal.movement_priority(0, north_south, false),
+            TurnPriority::Protected
+        );
+        assert_eq!(
+            signal.movement_priority(0, north_south, true),
+            TurnPriority::Banned
+        );
+        assert_eq!(
+            signal.movement_priority(1, north_south, false),
+            TurnPriority::Banned
+        );
+        assert_eq!(
+            signal.movement_priority(1, north_south, true),
+            TurnPriority::Protected
+        );
+        // Without a bike signal, cyclists follow the general signal
+        assert_eq!(
+            signal.movement_priority(1, east_west, true),
+            TurnPriority::Protected
+        );
+    }
+
+    #[test]
+    fn test_lead_lag_wait() {
+        let north_south = movement(0, 1);
+        let crosswalk = MovementID {
+            crosswalk: true,
+            ..movement(4, 4)
+        };
+        let mut stage = Stage::new();
+        stage.protected_movements.insert(north_south);
+        stage.protected_movements.insert(crosswalk);
+        stage.bike_movements.insert(north_south);
+        stage.lead_lag = Some(LeadLagInterval::Leading(Duration::seconds(5.0)));
+        let mut signal = ControlTrafficSignal {
+            id: IntersectionID(0),
+            stages: vec![stage],
+            offset: Duration::ZERO,
+            bike_signals: vec![north_south].into_iter().collect(),
+        };
+
+        // Pedestrians and cyclists with their own signal start right away; cars wait
+        let early = Duration::seconds(2.0);
+        assert_eq!(signal.lead_lag_wait(0, crosswalk, false, early), None);
+        assert_eq!(signal.lead_lag_wait(0, north_south, true, early), None);
+        assert_eq!(
+            signal.lead_lag_wait(0, north_south, false, early),
+            Some(Duration::seconds(3.0))
+        );
+        assert_eq!(
+            signal.lead_lag_wait(0, north_south, false, Duration::seconds(5.0)),
+            None
+        );
+
+        // A lagging interval is the other way around
+        signal.stages[0].lead_lag = Some(LeadLagInterval::Lagging(Duration::seconds(5.0)));
+        assert_eq!(signal.lead_lag_wait(0, north_south, false, early), None);
+        assert_eq!(
+            signal.lead_lag_wait(0, crosswalk, false, early),
+            Some(Duration::seconds(3.0))
+        );
+        assert_eq!(
+            signal.lead_lag_wait(0, north_south, true, early),
+            Some(Duration::seconds(3.0))
+        );
+
+        signal.stages[0].lead_lag = None;
+        assert_eq!(signal.lead_lag_wait(0, north_south, false, early), None);
+    }
 }
@@ -6,7 +6,7 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::Time;
+use geom::{Duration, Time};
 
 use crate::{LaneID, Map, Path, PathConstraints, PathRequest, Position, RoadID};
 
@@ -49,6 +49,18 @@ pub struct TransitStop {
     pub sidewalk_pos: Position,
     /// If false, only buses serve this stop
     pub is_train_stop: bool,
+    pub layout: StopLayout,
+}
+
+/// How vehicles stop to serve passengers.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopLayout {
+    /// Vehicles stop in the travel lane, blocking traffic behind them while dwelling. Bus bulbs
+    /// and train platforms work this way.
+    InLane,
+    /// Vehicles pull out of the travel lane while dwelling, letting traffic pass, but then have to
+    /// wait for a gap to merge back in.
+    PullOut,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -69,6 +81,91 @@ pub struct TransitRoute {
     /// Explicitly store whatever the original was, since this can't be reconstructed without side
     /// input.
     pub orig_spawn_times: Vec<Time>,
+    pub boarding: BoardingConfig,
+}
+
+/// How passengers pay their fare, which affects how quickly they board.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FarePayment {
+    /// Paid to the driver when boarding
+    Cash,
+    /// Tapped on a reader by the front door when boarding
+    SmartCard,
+    /// Paid before boarding, so passengers can use any door
+    OffBoard,
+}
+
+/// Properties of a route's vehicles that determine how long they dwell at stops.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BoardingConfig {
+    pub fare_payment: FarePayment,
+    /// Passengers have to climb steps to board vehicles without a low floor, which is slower.
+    pub low_floor: bool,
+    /// At least 1. Unless fares are paid off-board, everybody boards through the front door, and
+    /// the other doors are only used for alighting.
+    pub num_doors: usize,
+}
+
+impl BoardingConfig {
+    pub fn default_for(route_type: PathConstraints) -> BoardingConfig {
+        if route_type == PathConstraints::Train {
+            BoardingConfig {
+                fare_payment: FarePayment::OffBoard,
+                low_floor: true,
+                num_doors: 4,
+            }
+        } else {
+            BoardingConfig {
+                fare_payment: FarePayment::SmartCard,
+                low_floor: true,
+                num_doors: 2,
+            }
+        }
+    }
+
+    /// How long a vehicle dwells at a stop to let some number of passengers board and alight.
+    /// Based loosely on the Transit Capacity and Quality of Service Manual.
+    pub fn dwell_time(&self, boardings: usize, alightings: usize) -> Duration {
+        // Opening and closing the doors
+        let mut result = Duration::seconds(4.0);
+
+        let mut per_boarding = Duration::seconds(match self.fare_payment {
+            FarePayment::Cash => 4.0,
+            FarePayment::SmartCard => 3.0,
+            FarePayment::OffBoard => 2.0,
+        });
+        let mut per_alighting = Duration::seconds(1.5);
+        if !self.low_floor {
+            per_boarding += Duration::seconds(0.5);
+            per_alighting += Duration::seconds(0.5);
+        }
+        let boarding = (boardings as f64) * per_boarding;
+        let alighting = (alightings as f64) * per_alighting;
+
+        let num_doors = self.num_doors.max(1) as f64;
+        if self.fare_payment == FarePayment::OffBoard {
+            // Everybody shares all of the doors
+            result += (boarding + alighting) / num_doors;
+        } else if self.num_doors <= 1 {
+            // Passengers alight, then board
+            result += boarding + alighting;
+        } else {
+            // Boarding at the front happens while passengers alight at the other doors
+            result += boarding.max(alighting / (num_doors - 1.0));
+        }
+        result
+    }
+}
+
+impl TransitStop {
+    /// Where buses serving a stop at this sidewalk position wait: in the closest lane buses can
+    /// use. None if the road has no such lane.
+    pub fn find_driving_pos(sidewalk_pos: Position, map: &Map) -> Option<Position> {
+        let sidewalk = sidewalk_pos.lane();
+        map.get_parent(sidewalk)
+            .find_closest_lane(sidewalk, |l| PathConstraints::Bus.can_use(l, map))
+            .map(|l| sidewalk_pos.equiv_pos(l, map))
+    }
 }
 
 impl TransitRoute {
@@ -107,6 +204,16 @@ impl TransitRoute {
     /// Entry i is the path to drive to stop i. The very last entry is to drive from the last step
     /// to the place where the vehicle vanishes.
     pub fn all_paths(&self, map: &Map) -> Result<Vec<Path>> {
+        self.all_paths_using(map, |req| map.pathfind(req))
+    }
+
+    /// Like `all_paths`, but calculating each path some other way -- like with a separate
+    /// pathfinder, while the map is being edited.
+    pub fn all_paths_using<F: Fn(PathRequest) -> Result<Path>>(
+        &self,
+        map: &Map,
+        pathfind: F,
+    ) -> Result<Vec<Path>> {
         let mut paths = Vec::new();
         for req in self.all_path_requests(map) {
             if req.start.lane().road == req.end.lane().road
@@ -118,7 +225,7 @@ impl TransitRoute {
                 );
             }
 
-            let path = map.pathfind(req)?;
+            let path = pathfind(req)?;
             if path.is_empty() {
                 bail!("Empty path between stops: {}", path.get_req());
             }
@@ -138,6 +245,24 @@ impl TransitRoute {
         Ok(paths)
     }
 
+    /// The average time between vehicles starting this route. None if only one vehicle runs all
+    /// day.
+    pub fn average_headway(&self) -> Option<Duration> {
+        if self.spawn_times.len() < 2 {
+            return None;
+        }
+        let span = *self.spawn_times.last().unwrap() - self.spawn_times[0];
+        Some(span / ((self.spawn_times.len() - 1) as f64))
+    }
+
+    /// How long somebody arriving at a stop at a random time waits for the next vehicle, on
+    /// average. If the headway is unknown, optimistically assume no waiting.
+    pub fn expected_wait(&self) -> Duration {
+        self.average_headway()
+            .map(|headway| headway / 2.0)
+            .unwrap_or(Duration::ZERO)
+    }
+
     pub fn plural_noun(&self) -> &'static str {
         if self.route_type == PathConstraints::Bus {
             "buses"
@@ -2,10 +2,10 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
-use geom::{Angle, Line, PolyLine};
+use geom::{Angle, Line, PolyLine, Time};
 
 use crate::{
-    DirectedRoadID, Direction, Intersection, IntersectionID, LaneID, Map, MovementID,
+    DirectedRoadID, Direction, Intersection, IntersectionID, Lane, LaneID, Map, MovementID,
     PathConstraints, RestrictionType,
 };
 
@@ -99,6 +99,12 @@ impl Turn {
         if self.geom.last_pt() == other.geom.last_pt() {
             return true;
         }
+        // U-turns sweep across the whole intersection, even if the geometry is tight
+        if (self.turn_type == TurnType::UTurn && !other.between_sidewalks())
+            || (other.turn_type == TurnType::UTurn && !self.between_sidewalks())
+        {
+            return true;
+        }
         self.geom.intersection(&other.geom).is_some()
     }
 
@@ -124,8 +130,14 @@ impl Turn {
 
     // TODO Maybe precompute this.
     /// Penalties for (lane types, lane-changing, slow lane). The penalty may depend on the vehicle
-    /// performing the turn. Lower means preferable.
-    pub fn penalty(&self, constraints: PathConstraints, map: &Map) -> (usize, usize, usize) {
+    /// performing the turn. Lower means preferable. Lane restrictions count if they apply at
+    /// `now`, or at any time if `now` isn't specified.
+    pub fn penalty(
+        &self,
+        constraints: PathConstraints,
+        now: Option<Time>,
+        map: &Map,
+    ) -> (usize, usize, usize) {
         let from = map.get_l(self.id.src);
         let to = map.get_l(self.id.dst);
 
@@ -197,6 +209,26 @@ impl Turn {
         } else {
             0
         };
+        // Likewise stay out of restricted lanes, and don't go straight from a turn-only lane.
+        // Without a time, only count restrictions that always apply, matching
+        // `PathConstraints::can_use`.
+        let restricted = |lane: &Lane| {
+            lane.get_restriction(map).filter(|r| {
+                now.map(|t| r.active_at(t))
+                    .unwrap_or_else(|| r.always_active())
+            })
+        };
+        let lt_cost = if restricted(to)
+            .map(|r| !r.allows_vehicle(constraints, map))
+            .unwrap_or(false)
+            || restricted(from)
+                .map(|r| !r.allows_turn(self.turn_type))
+                .unwrap_or(false)
+        {
+            lt_cost.max(3)
+        } else {
+            lt_cost
+        };
 
         // Keep right (in the US)
         let slow_lane = if to_idx > 1 { 1 } else { 0 };
@@ -282,6 +314,38 @@ impl Turn {
         })
     }
 
+    /// For pedestrian crossings, are there curb ramps on both sides? `None` means unknown.
+    pub fn curb_ramps(&self, map: &Map) -> Option<bool> {
+        if let Some(dr) = self.crosswalk_over_road(map) {
+            let road = map.get_r(dr.road);
+            return if dr.dir == Direction::Fwd {
+                road.curb_ramps_forward
+            } else {
+                road.curb_ramps_backward
+            };
+        }
+        // When crossing multiple roads, the ends of all of them need ramps
+        let mut result = Some(true);
+        for l in [self.id.src, self.id.dst] {
+            let road = map.get_parent(l);
+            let ramps = if road.dst_i == self.id.parent {
+                road.curb_ramps_forward
+            } else {
+                road.curb_ramps_backward
+            };
+            match ramps {
+                Some(false) => {
+                    return Some(false);
+                }
+                None => {
+                    result = None;
+                }
+                Some(true) => {}
+            }
+        }
+        result
+    }
+
     /// Only appropriat for pedestrian crossings. The geometry of crosswalks will first cross part
     /// of a sidewalk corner, then actually enter the road. Extract the piece that's in the road.
     pub fn crosswalk_line(&self) -> Option<Line> {
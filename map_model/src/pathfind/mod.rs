@@ -60,8 +60,13 @@ impl PathConstraints {
     }
 
     /// Can an agent use a lane? There are some subtle exceptions with using bus-only lanes for
-    /// turns.
+    /// turns. Lane restrictions only limited to some hours are ignored here.
     pub fn can_use(self, lane: &Lane, map: &Map) -> bool {
+        if let Some(restriction) = lane.get_restriction(map) {
+            if restriction.always_active() && !restriction.allows_vehicle(self, map) {
+                return false;
+            }
+        }
         let result = match self {
             PathConstraints::Pedestrian => {
                 return lane.is_walkable();
@@ -183,6 +188,20 @@ pub struct RoutingParams {
     pub avoid_steep_incline_penalty: f64,
     // If the road is `high_stress_for_bikes`, multiply by the base cost.
     pub avoid_high_stress: f64,
+    /// For bike routing. Multiply the base cost by this once per level of traffic stress above
+    /// 1, so that values above 1 prefer calmer roads, like most real cyclists do. See
+    /// `Road::bike_traffic_stress`.
+    pub traffic_stress_penalty: f64,
+
+    /// Only affects pedestrian routing. Don't use stairs at all, like for wheelchairs or
+    /// strollers.
+    pub avoid_stairs: bool,
+    /// For pedestrian routing. When a path is steeper than 8% in either direction, multiply the
+    /// base cost by this.
+    pub steep_walking_penalty: f64,
+    /// Only affects pedestrian routing. Don't cross roads where curbs are known to be raised.
+    /// Most crossings aren't tagged in OSM, so those are assumed to be passable.
+    pub require_curb_ramps: bool,
 
     /// When crossing an arterial or highway road, multiply the base cost by this penalty. When
     /// greater than 1, this will encourage routes to use local roads more.
@@ -198,6 +217,19 @@ pub struct RoutingParams {
     pub avoid_movements_between: BTreeSet<(RoadID, RoadID)>,
 }
 
+impl RoutingParams {
+    /// Somebody using a wheelchair can't use stairs, struggles with hills, and needs curb ramps
+    /// to cross roads.
+    pub fn wheelchair() -> Self {
+        Self {
+            avoid_stairs: true,
+            steep_walking_penalty: 3.0,
+            require_curb_ramps: true,
+            ..Default::default()
+        }
+    }
+}
+
 impl Default for RoutingParams {
     fn default() -> Self {
         Self {
@@ -211,6 +243,11 @@ impl Default for RoutingParams {
 
             avoid_steep_incline_penalty: 1.0,
             avoid_high_stress: 1.0,
+            traffic_stress_penalty: 1.0,
+
+            avoid_stairs: false,
+            steep_walking_penalty: 1.0,
+            require_curb_ramps: false,
 
             main_road_penalty: 1.0,
 
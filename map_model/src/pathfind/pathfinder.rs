@@ -115,7 +115,7 @@ impl Pathfinder {
         timer.stop("prepare pathfinding for trains");
 
         timer.start("prepare pathfinding for pedestrians");
-        let walking_graph = SidewalkPathfinder::new(map, None, engine);
+        let walking_graph = SidewalkPathfinder::new(map, None, &params, engine);
         timer.stop("prepare pathfinding for pedestrians");
 
         // Transit routes haven't been created yet, so defer this step
@@ -169,7 +169,7 @@ impl Pathfinder {
             timer.start(format!("prepare pathfinding for just {:?}", constraints));
             match constraints {
                 PathConstraints::Pedestrian => {
-                    p.walking_graph = SidewalkPathfinder::new(map, None, &engine);
+                    p.walking_graph = SidewalkPathfinder::new(map, None, &params, &engine);
                 }
                 PathConstraints::Car => {
                     p.car_graph = VehiclePathfinder::new(map, constraints, &params, &engine);
@@ -191,8 +191,12 @@ impl Pathfinder {
     }
 
     pub(crate) fn finalize_transit(&mut self, map: &Map, engine: &CreateEngine) {
-        self.walking_with_transit_graph =
-            SidewalkPathfinder::new(map, Some((&self.bus_graph, &self.train_graph)), engine);
+        self.walking_with_transit_graph = SidewalkPathfinder::new(
+            map,
+            Some((&self.bus_graph, &self.train_graph)),
+            &self.params,
+            engine,
+        );
     }
 
     /// Finds a path from a start to an end for a certain type of agent.
@@ -8,8 +8,8 @@ use abstutil::prettyprint_usize;
 use geom::{Distance, Duration, PolyLine, Polygon, Ring, Speed, EPSILON_DIST};
 
 use crate::{
-    BuildingID, DirectedRoadID, LaneID, Map, PathConstraints, Position, RoadID, Traversable,
-    TurnID, UberTurn,
+    BuildingID, DirectedRoadID, Direction, LaneID, Map, PathConstraints, Position, RoadID,
+    Traversable, TurnID, UberTurn,
 };
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -114,6 +114,23 @@ impl PathStep {
         max_speed_on_flat_ground: Option<Speed>,
         constraints: PathConstraints,
         map: &Map,
+    ) -> (Speed, f64) {
+        self.max_speed_and_incline_with_posted_limits(
+            max_speed_on_flat_ground,
+            constraints,
+            map,
+            &BTreeMap::new(),
+        )
+    }
+
+    /// Like `max_speed_and_incline_along`, but some managed motorways currently post a different
+    /// speed limit than usual.
+    pub fn max_speed_and_incline_with_posted_limits(
+        &self,
+        max_speed_on_flat_ground: Option<Speed>,
+        constraints: PathConstraints,
+        map: &Map,
+        posted_limits: &BTreeMap<RoadID, Speed>,
     ) -> (Speed, f64) {
         match self {
             PathStep::Lane(l) => Traversable::max_speed_along_road(
@@ -121,6 +138,7 @@ impl PathStep {
                 max_speed_on_flat_ground,
                 constraints,
                 map,
+                posted_limits,
             ),
             PathStep::ContraflowLane(l) => Traversable::max_speed_along_road(
                 {
@@ -131,6 +149,7 @@ impl PathStep {
                 max_speed_on_flat_ground,
                 constraints,
                 map,
+                posted_limits,
             ),
             PathStep::Turn(t) | PathStep::ContraflowTurn(t) => (
                 Traversable::max_speed_along_movement(
@@ -138,11 +157,46 @@ impl PathStep {
                     max_speed_on_flat_ground,
                     constraints,
                     map,
+                    posted_limits,
                 ),
                 0.0,
             ),
         }
     }
+
+    /// The extra time a vehicle moving at `speed` spends slowing down for traffic calming between
+    /// `start` and `end` along this step. Only cars, buses, and trains on lanes are affected.
+    pub fn traffic_calming_delay(
+        &self,
+        start: Distance,
+        end: Distance,
+        speed: Speed,
+        constraints: PathConstraints,
+        map: &Map,
+    ) -> Duration {
+        if constraints == PathConstraints::Bike || constraints == PathConstraints::Pedestrian {
+            return Duration::ZERO;
+        }
+        let lane = match self {
+            PathStep::Lane(l) => map.get_l(*l),
+            _ => {
+                return Duration::ZERO;
+            }
+        };
+        let road = map.get_r(lane.id.road);
+        if road.traffic_calming.is_empty() || lane.length() == Distance::ZERO {
+            return Duration::ZERO;
+        }
+        // Device positions are measured along the road's center line, which may be a bit longer
+        // or shorter than this lane
+        let scale = road.length() / lane.length();
+        let (start, end) = if lane.dir == Direction::Fwd {
+            (start * scale, end * scale)
+        } else {
+            (road.length() - end * scale, road.length() - start * scale)
+        };
+        road.traffic_calming_delay(start, end, speed, map)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -502,6 +556,20 @@ impl Path {
             let dist = self.dist_crossed_from_step(map, step);
             let speed = step.max_speed_along(max_speed, self.orig_req.constraints, map);
             total += dist / speed;
+            if let PathStep::Lane(l) = step {
+                let start = if self.orig_req.start.lane() == *l {
+                    self.orig_req.start.dist_along()
+                } else {
+                    Distance::ZERO
+                };
+                let end = if self.orig_req.end.lane() == *l {
+                    self.orig_req.end.dist_along()
+                } else {
+                    map.get_l(*l).length()
+                };
+                total +=
+                    step.traffic_calming_delay(start, end, speed, self.orig_req.constraints, map);
+            }
         }
         total
     }
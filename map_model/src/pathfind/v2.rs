@@ -233,7 +233,7 @@ impl PathV2 {
 
                 // Normally opportunistic lane-changing adjusts the path live, but that doesn't work
                 // near uber-turns. So still use some of the penalties here.
-                let (lt, lc, slow_lane) = map.get_t(*t).penalty(self.req.constraints, map);
+                let (lt, lc, slow_lane) = map.get_t(*t).penalty(self.req.constraints, None, map);
                 let mut extra_penalty = lt + lc;
                 if self.req.constraints == PathConstraints::Bike {
                     extra_penalty += slow_lane;
@@ -1,12 +1,12 @@
 //! Pathfinding for cars, bikes, buses, and trains using contraction hierarchies
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use fast_paths::InputGraph;
 use serde::{Deserialize, Serialize};
 
 use abstutil::MultiMap;
-use geom::Duration;
+use geom::{Distance, Duration};
 
 use crate::pathfind::engine::{CreateEngine, PathfindEngine};
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
@@ -299,10 +299,22 @@ pub fn vehicle_cost(
         PathConstraints::Bike => Some(crate::MAX_BIKE_SPEED),
         PathConstraints::Pedestrian => unreachable!(),
     };
-    let t1 = road.length() / Traversable::max_speed_along_road(dr, max_speed, constraints, map).0;
+    // Routes are planned with the usual speed limits, not ones a managed motorway posts for a while
+    let speed =
+        Traversable::max_speed_along_road(dr, max_speed, constraints, map, &BTreeMap::new()).0;
+    let mut t1 = road.length() / speed;
+    if constraints != PathConstraints::Bike {
+        t1 += road.traffic_calming_delay(Distance::ZERO, road.length(), speed, map);
+    }
 
     let t2 = movement.geom.length()
-        / Traversable::max_speed_along_movement(mvmnt, max_speed, constraints, map);
+        / Traversable::max_speed_along_movement(
+            mvmnt,
+            max_speed,
+            constraints,
+            map,
+            &BTreeMap::new(),
+        );
 
     let base = match constraints {
         PathConstraints::Car | PathConstraints::Train => t1 + t2,
@@ -337,12 +349,7 @@ pub fn vehicle_cost(
     if constraints == PathConstraints::Bike
         && (params.avoid_steep_incline_penalty - 1.0).abs() > f64::EPSILON
     {
-        let percent_incline = if dr.dir == Direction::Fwd {
-            road.percent_incline
-        } else {
-            -road.percent_incline
-        };
-        if percent_incline >= 0.08 {
+        if road.percent_incline_in(dr.dir) >= 0.08 {
             multiplier *= params.avoid_steep_incline_penalty;
         }
     }
@@ -354,6 +361,14 @@ pub fn vehicle_cost(
         multiplier *= params.avoid_high_stress;
     }
 
+    if constraints == PathConstraints::Bike
+        && (params.traffic_stress_penalty - 1.0).abs() > f64::EPSILON
+    {
+        if let Some(lts) = road.bike_traffic_stress(map, dr.dir) {
+            multiplier *= params.traffic_stress_penalty.powi(lts as i32 - 1);
+        }
+    }
+
     if params.avoid_roads.contains(&dr.road)
         || params
             .avoid_movements_between
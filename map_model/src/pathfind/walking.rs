@@ -14,7 +14,7 @@ use crate::pathfind::zone_cost;
 use crate::pathfind::{round, unround};
 use crate::{
     DirectedRoadID, IntersectionID, Map, PathConstraints, PathRequest, PathStep, PathStepV2,
-    PathV2, Position, TransitRoute, TransitRouteID, TransitStopID, TurnType,
+    PathV2, Position, RoutingParams, TransitRouteID, TransitStopID, TurnType,
 };
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -22,6 +22,7 @@ pub struct SidewalkPathfinder {
     #[serde(deserialize_with = "deserialize_nodemap")]
     nodes: NodeMap<WalkingNode>,
     use_transit: bool,
+    params: RoutingParams,
     engine: PathfindEngine,
 }
 
@@ -29,10 +30,12 @@ pub struct SidewalkPathfinder {
 pub enum WalkingNode {
     /// false is src_i, true is dst_i
     SidewalkEndpoint(DirectedRoadID, bool),
-    // TODO Lots of complexity below could be avoided by explicitly sticking TransitRouteID here too.
-    // Worth it?
+    /// Waiting at a stop, for any route serving it
     RideTransit(TransitStopID),
     LeaveMap(IntersectionID),
+    /// On board a vehicle of one route, stopped at a stop. Boarding from `RideTransit` costs the
+    /// expected wait for this route, so transfers between routes aren't free.
+    OnTransit(TransitRouteID, TransitStopID),
 }
 
 impl WalkingNode {
@@ -59,6 +62,7 @@ impl SidewalkPathfinder {
         SidewalkPathfinder {
             nodes: NodeMap::new(),
             use_transit: false,
+            params: RoutingParams::default(),
             engine: PathfindEngine::Empty,
         }
     }
@@ -66,6 +70,7 @@ impl SidewalkPathfinder {
     pub fn new(
         map: &Map,
         use_transit: Option<(&VehiclePathfinder, &VehiclePathfinder)>,
+        params: &RoutingParams,
         engine: &CreateEngine,
     ) -> SidewalkPathfinder {
         let mut nodes = NodeMap::new();
@@ -79,22 +84,20 @@ impl SidewalkPathfinder {
             }
         }
         if use_transit.is_some() {
-            // Add a node for each stop.
-            for ts in map.all_transit_stops().keys() {
-                nodes.get_or_insert(WalkingNode::RideTransit(*ts));
-            }
+            add_transit_nodes(&mut nodes, map);
             for i in map.all_outgoing_borders() {
                 // We could filter for those with sidewalks, but eh
                 nodes.get_or_insert(WalkingNode::LeaveMap(i.id));
             }
         }
 
-        let input_graph = make_input_graph(&nodes, use_transit, map);
+        let input_graph = make_input_graph(&nodes, use_transit, params, map);
         let engine = engine.create(input_graph);
 
         SidewalkPathfinder {
             nodes,
             use_transit: use_transit.is_some(),
+            params: params.clone(),
             engine,
         }
     }
@@ -108,8 +111,15 @@ impl SidewalkPathfinder {
             return;
         }
 
-        let input_graph = make_input_graph(&self.nodes, use_transit, map);
-        let engine = self.engine.reuse_ordering().create(input_graph);
+        // Transit stops and routes may have been edited. Stale nodes just won't have any edges,
+        // but new ones have to be added, and then the old node ordering can't be reused.
+        let added_nodes = use_transit.is_some() && add_transit_nodes(&mut self.nodes, map);
+        let input_graph = make_input_graph(&self.nodes, use_transit, &self.params, map);
+        let engine = if added_nodes && !self.engine.is_dijkstra() {
+            CreateEngine::CH.create(input_graph)
+        } else {
+            self.engine.reuse_ordering().create(input_graph)
+        };
         self.engine = engine;
     }
 
@@ -164,68 +174,29 @@ impl SidewalkPathfinder {
             }
         }
 
-        let mut first_stop = None;
+        // Just look at the first route ridden. If the path transfers to another route, the person
+        // hops off where they would transfer and walks the rest of the way.
+        let mut first: Option<(TransitStopID, TransitRouteID)> = None;
         let mut last_stop = None;
-        let mut possible_routes: Vec<&TransitRoute> = Vec::new();
         for n in &nodes {
-            match n {
-                WalkingNode::RideTransit(stop2) => {
-                    if let Some(stop1) = first_stop {
-                        // Keep riding the same route?
-                        // We need to do this check, because some transfers might be instantaneous
-                        // at the same stop and involve no walking.
-                        // Also need to make sure the stops are in the proper order. We might have
-                        // a transfer, then try to hop on the first route again, but starting from
-                        // a different point.
-                        let mut filtered = possible_routes.clone();
-                        filtered.retain(|r| {
-                            let idx1 = r.stops.iter().position(|s| *s == stop1).unwrap();
-                            let idx2 = r.stops.iter().position(|s| s == stop2);
-                            idx2.map(|idx2| idx1 < idx2).unwrap_or(false)
-                        });
-                        if filtered.is_empty() {
-                            // Aha, a transfer!
-                            return Some((
-                                first_stop.unwrap(),
-                                // TODO I thought this should be impossible, but huge_seattle hits
-                                // it. Workaround for now by just walking.
-                                Some(last_stop?),
-                                possible_routes[0].id,
-                            ));
-                        }
-                        last_stop = Some(*stop2);
-                        possible_routes = filtered;
-                    } else {
-                        first_stop = Some(*stop2);
-                        possible_routes = map.get_routes_serving_stop(*stop2);
-                        assert!(!possible_routes.is_empty());
-                    }
+            match (n, first) {
+                (WalkingNode::OnTransit(route, stop), None) => {
+                    first = Some((*stop, *route));
                 }
-                WalkingNode::LeaveMap(i) => {
-                    // Make sure the route actually leaves via the correct border!
-                    if let Some(r) = possible_routes.iter().find(|r| {
-                        r.end_border
-                            .map(|l| map.get_l(l).dst_i == *i)
-                            .unwrap_or(false)
-                    }) {
-                        return Some((first_stop.unwrap(), None, r.id));
-                    }
-                    // We can get close to the border, but should hop off at some stop.
-                    return Some((
-                        first_stop.unwrap(),
-                        Some(last_stop.expect("impossible transit transfer")),
-                        possible_routes[0].id,
-                    ));
+                (WalkingNode::OnTransit(route, stop), Some((_, first_route)))
+                    if *route == first_route =>
+                {
+                    last_stop = Some(*stop);
                 }
-                WalkingNode::SidewalkEndpoint(_, _) => {
-                    if let Some(stop1) = first_stop {
-                        return Some((
-                            stop1,
-                            Some(last_stop.expect("impossible transit transfer")),
-                            possible_routes[0].id,
-                        ));
-                    }
+                (WalkingNode::LeaveMap(_), Some((stop1, route))) => {
+                    return Some((stop1, None, route));
                 }
+                (_, Some((stop1, route))) => {
+                    // TODO I thought this should be impossible, but huge_seattle hits it.
+                    // Workaround for now by just walking.
+                    return Some((stop1, Some(last_stop?), route));
+                }
+                (_, None) => {}
             }
         }
         None
@@ -241,7 +212,7 @@ impl SidewalkPathfinder {
             self.engine.all_costs_from(start)
         } else {
             // The CH engine doesn't support this!
-            let input_graph = make_input_graph(&self.nodes, None, map);
+            let input_graph = make_input_graph(&self.nodes, None, &self.params, map);
             CreateEngine::Dijkstra
                 .create(input_graph)
                 .all_costs_from(start)
@@ -264,12 +235,17 @@ impl SidewalkPathfinder {
 fn make_input_graph(
     nodes: &NodeMap<WalkingNode>,
     use_transit: Option<(&VehiclePathfinder, &VehiclePathfinder)>,
+    params: &RoutingParams,
     map: &Map,
 ) -> InputGraph {
     let max_speed = Some(crate::MAX_WALKING_SPEED);
     let mut input_graph = InputGraph::new();
 
     for l in map.all_lanes() {
+        let road = map.get_r(l.id.road);
+        if params.avoid_stairs && road.is_stairs() {
+            continue;
+        }
         if l.is_walkable() {
             // Sidewalks can be crossed in two directions. When there's a steep incline, of course
             // it flips.
@@ -289,12 +265,22 @@ fn make_input_graph(
                 if l.is_shoulder() {
                     cost = 2.0 * cost;
                 }
+                // Going up and down steep paths are both hard for some people
+                if road.percent_incline.abs() >= 0.08 {
+                    cost = params.steep_walking_penalty * cost;
+                }
                 input_graph.add_edge(pair.0, pair.1, round(cost));
             }
         }
     }
 
     for t in map.all_turns() {
+        if params.require_curb_ramps
+            && t.turn_type.pedestrian_crossing()
+            && t.curb_ramps(map) == Some(false)
+        {
+            continue;
+        }
         if t.between_sidewalks() {
             let src = map.get_l(t.id.src);
             let dst = map.get_l(t.id.dst);
@@ -330,6 +316,27 @@ fn make_input_graph(
     input_graph
 }
 
+/// Add a node for each stop, and for riding each route through its stops. Returns true if any nodes
+/// were missing.
+fn add_transit_nodes(nodes: &mut NodeMap<WalkingNode>, map: &Map) -> bool {
+    let mut added = false;
+    let mut add = |node| {
+        if !nodes.contains(node) {
+            nodes.get_or_insert(node);
+            added = true;
+        }
+    };
+    for ts in map.all_transit_stops().keys() {
+        add(WalkingNode::RideTransit(*ts));
+    }
+    for route in map.all_transit_routes() {
+        for ts in &route.stops {
+            add(WalkingNode::OnTransit(route.id, *ts));
+        }
+    }
+    added
+}
+
 fn transit_input_graph(
     input_graph: &mut InputGraph,
     nodes: &NodeMap<WalkingNode>,
@@ -365,8 +372,17 @@ fn transit_input_graph(
     }
 
     // Connect each adjacent stop along a route, with the cost based on how long it'll take a
-    // transit vehicle to drive between the stops. Optimistically assume no waiting time at a stop.
+    // transit vehicle to drive between the stops. Boarding a route costs the expected wait, based
+    // on its headway, and hopping off is free.
     for route in map.all_transit_routes() {
+        let wait = route.expected_wait();
+        for ts in &route.stops {
+            let waiting = nodes.get(WalkingNode::RideTransit(*ts));
+            let on_board = nodes.get(WalkingNode::OnTransit(route.id, *ts));
+            input_graph.add_edge(waiting, on_board, round(wait));
+            input_graph.add_edge(on_board, waiting, round(Duration::ZERO));
+        }
+
         // TODO Also plug in border starts
         for pair in route.stops.windows(2) {
             let (stop1, stop2) = (map.get_ts(pair[0]), map.get_ts(pair[1]));
@@ -378,8 +394,8 @@ fn transit_input_graph(
             };
             if let Some(driving_cost) = maybe_driving_cost {
                 input_graph.add_edge(
-                    nodes.get(WalkingNode::RideTransit(stop1.id)),
-                    nodes.get(WalkingNode::RideTransit(stop2.id)),
+                    nodes.get(WalkingNode::OnTransit(route.id, stop1.id)),
+                    nodes.get(WalkingNode::OnTransit(route.id, stop2.id)),
                     round(driving_cost),
                 );
             } else {
@@ -402,7 +418,7 @@ fn transit_input_graph(
             if let Some(driving_cost) = maybe_driving_cost {
                 let border = map.get_i(map.get_l(l).dst_i);
                 input_graph.add_edge(
-                    nodes.get(WalkingNode::RideTransit(stop1.id)),
+                    nodes.get(WalkingNode::OnTransit(route.id, stop1.id)),
                     nodes.get(WalkingNode::LeaveMap(border.id)),
                     round(driving_cost),
                 );
@@ -423,13 +439,15 @@ fn walking_path_to_steps(path: Vec<WalkingNode>, map: &Map) -> Vec<PathStepV2> {
     for pair in path.windows(2) {
         let (r1, r1_endpt) = match pair[0] {
             WalkingNode::SidewalkEndpoint(r, endpt) => (r, endpt),
-            WalkingNode::RideTransit(_) => unreachable!(),
-            WalkingNode::LeaveMap(_) => unreachable!(),
+            WalkingNode::RideTransit(_)
+            | WalkingNode::LeaveMap(_)
+            | WalkingNode::OnTransit(_, _) => unreachable!(),
         };
         let r2 = match pair[1] {
             WalkingNode::SidewalkEndpoint(r, _) => r,
-            WalkingNode::RideTransit(_) => unreachable!(),
-            WalkingNode::LeaveMap(_) => unreachable!(),
+            WalkingNode::RideTransit(_)
+            | WalkingNode::LeaveMap(_)
+            | WalkingNode::OnTransit(_, _) => unreachable!(),
         };
 
         if r1 == r2 {
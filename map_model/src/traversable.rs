@@ -1,10 +1,13 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
 use geom::{Angle, Distance, PolyLine, Pt2D, Speed};
 
-use crate::{DirectedRoadID, Direction, LaneID, Map, MovementID, PathConstraints, TurnID};
+use crate::{
+    DirectedRoadID, LaneID, Map, MovementID, PathConstraints, Road, RoadID, TurnID, TurnType,
+};
 
 /// Represents a specific point some distance along a lane.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -201,13 +204,10 @@ impl Traversable {
         max_speed_on_flat_ground: Option<Speed>,
         constraints: PathConstraints,
         map: &Map,
+        posted_limits: &BTreeMap<RoadID, Speed>,
     ) -> (Speed, f64) {
         let road = map.get_r(dr.road);
-        let percent_incline = if dr.dir == Direction::Fwd {
-            road.percent_incline
-        } else {
-            -road.percent_incline
-        };
+        let percent_incline = road.percent_incline_in(dr.dir);
 
         let base = if constraints == PathConstraints::Bike {
             // We assume every bike has a max_speed defined.
@@ -217,8 +217,9 @@ impl Traversable {
             walking_speed_on_incline(max_speed_on_flat_ground.unwrap(), percent_incline)
         } else {
             debug_assert!(max_speed_on_flat_ground.is_none());
-            // Incline doesn't affect cars, buses, or trains
-            road.speed_limit
+            // Incline doesn't affect cars, buses, or trains. Traffic calming only slows them down
+            // near each device; see `PathStep::traffic_calming_delay`.
+            limit_in_effect(road, posted_limits)
         };
 
         let speed = if let Some(s) = max_speed_on_flat_ground {
@@ -236,12 +237,16 @@ impl Traversable {
         max_speed_on_flat_ground: Option<Speed>,
         _: PathConstraints,
         map: &Map,
+        posted_limits: &BTreeMap<RoadID, Speed>,
     ) -> Speed {
         // TODO Ignore elevation on turns?
-        let base = map
-            .get_r(mvmnt.from.road)
-            .speed_limit
-            .min(map.get_r(mvmnt.to.road).speed_limit);
+        let base = limit_in_effect(map.get_r(mvmnt.from.road), posted_limits)
+            .min(limit_in_effect(map.get_r(mvmnt.to.road), posted_limits));
+        // Nobody turns around at full speed
+        let base = match map.get_i(mvmnt.parent).movements.get(&mvmnt) {
+            Some(m) if m.turn_type == TurnType::UTurn => base.min(MAX_U_TURN_SPEED),
+            _ => base,
+        };
         if let Some(s) = max_speed_on_flat_ground {
             base.min(s)
         } else {
@@ -250,10 +255,20 @@ impl Traversable {
     }
 }
 
+/// Managed motorways may currently post a different limit than usual
+fn limit_in_effect(road: &Road, posted_limits: &BTreeMap<RoadID, Speed>) -> Speed {
+    posted_limits
+        .get(&road.id)
+        .copied()
+        .unwrap_or(road.speed_limit)
+}
+
 // 10 mph
 pub const MAX_BIKE_SPEED: Speed = Speed::const_meters_per_second(4.4704);
 // 3 mph
 pub const MAX_WALKING_SPEED: Speed = Speed::const_meters_per_second(1.34112);
+// 10 km/h
+const MAX_U_TURN_SPEED: Speed = Speed::const_meters_per_second(2.7778);
 
 fn bike_speed_on_incline(max_speed: Speed, percent_incline: f64) -> Speed {
     // There doesn't seem to be a straightforward way of calculating how an "average" cyclist's
@@ -2,7 +2,7 @@
 //! structure is useful to iterate quickly on parts of the map importing pipeline without having to
 //! constantly read .osm files, and to visualize the intermediate state with map_editor.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use osm2streets::{osm, IntersectionID, RoadID, StreetNetwork};
 use serde::{Deserialize, Serialize};
@@ -14,8 +14,10 @@ use abstutil::{
 };
 use geom::{Distance, PolyLine, Polygon, Pt2D};
 
+pub use self::profile::{RegionProfile, SpeedLimitDefaults};
 pub use self::types::{Amenity, AmenityType, AreaType};
 
+mod profile;
 mod types;
 
 #[derive(Serialize, Deserialize)]
@@ -62,6 +64,15 @@ pub struct RawMap {
         deserialize_with = "deserialize_btreemap"
     )]
     pub elevation_per_intersection: BTreeMap<IntersectionID, Distance>,
+    /// Street lamps mapped in OSM, only used for drawing
+    pub street_lamps: Vec<Pt2D>,
+    /// Nodes tagged as a turning circle or loop at the end of a road
+    pub turning_circles: BTreeSet<osm::NodeID>,
+    /// The regional defaults used to import this map. Speed limits are guessed from these later.
+    ///
+    /// RawMaps are stored with bincode, which has no notion of missing fields, so adding this broke
+    /// every existing raw map file; they have to be regenerated by re-running the importer.
+    pub profile: RegionProfile,
 }
 
 impl RawMap {
@@ -79,6 +90,9 @@ impl RawMap {
             osm_tags: BTreeMap::new(),
             extra_road_data: BTreeMap::new(),
             elevation_per_intersection: BTreeMap::new(),
+            street_lamps: Vec::new(),
+            turning_circles: BTreeSet::new(),
+            profile: RegionProfile::default(),
         }
     }
 
@@ -157,6 +171,32 @@ pub enum CrossingType {
     Unsignalized,
 }
 
+/// Physical features meant to slow down vehicles at one point along a road
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TrafficCalming {
+    /// Speed humps, bumps, and cushions
+    SpeedHump,
+    /// A crossing raised to the level of the sidewalk
+    RaisedCrossing,
+    /// Anything narrowing or shifting the road, like chicanes and chokers
+    Chicane,
+}
+
+impl TrafficCalming {
+    /// Parse the value of OSM's `traffic_calming` tag.
+    pub fn from_osm(value: &str) -> Option<TrafficCalming> {
+        // https://wiki.openstreetmap.org/wiki/Key:traffic_calming
+        match value {
+            "yes" | "bump" | "hump" | "cushion" | "dip" | "rumble_strip" => {
+                Some(TrafficCalming::SpeedHump)
+            }
+            "table" => Some(TrafficCalming::RaisedCrossing),
+            "chicane" | "choker" | "island" | "painted_island" => Some(TrafficCalming::Chicane),
+            _ => None,
+        }
+    }
+}
+
 /// Extra data associated with one Road
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExtraRoadData {
@@ -164,6 +204,9 @@ pub struct ExtraRoadData {
     /// Is there a tagged crosswalk near each end of the road?
     pub crosswalk_forward: bool,
     pub crosswalk_backward: bool,
+    /// Are there curb ramps at the crossing near each end of the road? `None` means unknown.
+    pub curb_ramps_forward: Option<bool>,
+    pub curb_ramps_backward: Option<bool>,
     // TODO Preserving these two across transformations (especially merging dual carriageways!)
     // could be really hard. It might be better to split the road into two pieces to match the more
     // often used OSM style.
@@ -171,6 +214,9 @@ pub struct ExtraRoadData {
     pub barrier_nodes: Vec<Pt2D>,
     /// Crossing nodes along this road's original center line.
     pub crossing_nodes: Vec<(Pt2D, CrossingType)>,
+    /// Traffic calming nodes along this road's original center line.
+    #[serde(default)]
+    pub traffic_calming_nodes: Vec<(Pt2D, TrafficCalming)>,
 }
 
 impl ExtraRoadData {
@@ -180,8 +226,11 @@ impl ExtraRoadData {
             // Start assuming there's a crosswalk everywhere, and maybe filter it down later
             crosswalk_forward: true,
             crosswalk_backward: true,
+            curb_ramps_forward: None,
+            curb_ramps_backward: None,
             barrier_nodes: Vec::new(),
             crossing_nodes: Vec::new(),
+            traffic_calming_nodes: Vec::new(),
         }
     }
 }
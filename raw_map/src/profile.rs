@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use osm2streets::DrivingSide;
+use serde::{Deserialize, Serialize};
+
+use geom::Speed;
+
+/// Regional defaults for things OSM usually leaves untagged. A profile is written as JSON for a
+/// country or city; anything it leaves out falls back to the built-in guesses.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RegionProfile {
+    /// Overrides the driving side detected from the map's location
+    #[serde(default)]
+    pub driving_side: Option<DrivingSide>,
+    /// Should roads without sidewalk tags be assumed to have sidewalks?
+    #[serde(default)]
+    pub inferred_sidewalks: Option<bool>,
+    /// For roads without a `lanes` tag, how many driving lanes both directions have in total,
+    /// keyed by the `highway` value, like "primary". One-way roads get half, rounding up.
+    #[serde(default)]
+    pub default_lanes: BTreeMap<String, usize>,
+    /// Replaces the country's built-in default speed limits
+    #[serde(default)]
+    pub speed_limits: Option<SpeedLimitDefaults>,
+}
+
+impl RegionProfile {
+    /// Use anything the more specific profile specifies, like a city's, falling back to this one,
+    /// like the country's.
+    pub fn overlay(mut self, specific: RegionProfile) -> RegionProfile {
+        if specific.driving_side.is_some() {
+            self.driving_side = specific.driving_side;
+        }
+        if specific.inferred_sidewalks.is_some() {
+            self.inferred_sidewalks = specific.inferred_sidewalks;
+        }
+        self.default_lanes.extend(specific.default_lanes);
+        if specific.speed_limits.is_some() {
+            self.speed_limits = specific.speed_limits;
+        }
+        self
+    }
+}
+
+/// Default speed limits for one place. Units are mph if `mph` is set, otherwise km/h.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpeedLimitDefaults {
+    pub mph: bool,
+    pub urban: f64,
+    pub rural: f64,
+    /// Also used for rural dual carriageways
+    pub motorway: f64,
+    pub living_street: f64,
+}
+
+impl SpeedLimitDefaults {
+    /// The built-in defaults for a country, given a two-letter code like "us"
+    pub fn for_country(country: &str) -> SpeedLimitDefaults {
+        let (mph, urban, rural, motorway, living_street) = match country {
+            "us" => (true, 25.0, 55.0, 65.0, 15.0),
+            "gb" => (true, 30.0, 60.0, 70.0, 10.0),
+            "ca" => (false, 50.0, 80.0, 100.0, 20.0),
+            "au" => (false, 50.0, 100.0, 110.0, 10.0),
+            // There's no general limit on German motorways; 130 is the advisory speed
+            "de" => (false, 50.0, 100.0, 130.0, 7.0),
+            "at" => (false, 50.0, 100.0, 130.0, 7.0),
+            "ch" => (false, 50.0, 80.0, 120.0, 20.0),
+            "fr" => (false, 50.0, 80.0, 130.0, 20.0),
+            "nl" => (false, 50.0, 80.0, 100.0, 15.0),
+            "pl" => (false, 50.0, 90.0, 140.0, 20.0),
+            "br" => (false, 50.0, 80.0, 110.0, 20.0),
+            _ => (false, 50.0, 90.0, 120.0, 20.0),
+        };
+        SpeedLimitDefaults {
+            mph,
+            urban,
+            rural,
+            motorway,
+            living_street,
+        }
+    }
+
+    /// Interpret a number in this place's units
+    pub fn speed(&self, x: f64) -> Speed {
+        if self.mph {
+            Speed::miles_per_hour(x)
+        } else {
+            Speed::km_per_hour(x)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay() {
+        let mut country = RegionProfile {
+            driving_side: Some(DrivingSide::Left),
+            inferred_sidewalks: Some(true),
+            speed_limits: Some(SpeedLimitDefaults::for_country("gb")),
+            ..Default::default()
+        };
+        country.default_lanes.insert("primary".to_string(), 2);
+        country.default_lanes.insert("secondary".to_string(), 2);
+
+        let mut city = RegionProfile {
+            inferred_sidewalks: Some(false),
+            ..Default::default()
+        };
+        city.default_lanes.insert("primary".to_string(), 4);
+
+        let profile = country.overlay(city);
+        // Left out by the city, so the country's value stays
+        assert_eq!(profile.driving_side, Some(DrivingSide::Left));
+        assert!(profile.speed_limits.unwrap().mph);
+        // The city's value wins
+        assert_eq!(profile.inferred_sidewalks, Some(false));
+        // Lane defaults merge per kind of road
+        assert_eq!(profile.default_lanes["primary"], 4);
+        assert_eq!(profile.default_lanes["secondary"], 2);
+    }
+}
@@ -0,0 +1,32 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    // Record the commit, so simulation results can say what code produced them
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|x| x.trim().to_string())
+        .unwrap_or_else(|| format!("unknown (sim {})", env!("CARGO_PKG_VERSION")));
+    println!("cargo:rustc-env=ABST_GIT_COMMIT={}", commit);
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    // HEAD usually names a branch, which moves on every commit without HEAD changing
+    if let Some(branch) = fs::read_to_string("../.git/HEAD")
+        .ok()
+        .and_then(|head| head.trim().strip_prefix("ref: ").map(|x| x.to_string()))
+    {
+        for path in [
+            format!("../.git/{}", branch),
+            "../.git/packed-refs".to_string(),
+        ] {
+            // Watching a file that doesn't exist reruns this every build
+            if Path::new(&path).exists() {
+                println!("cargo:rerun-if-changed={}", path);
+            }
+        }
+    }
+}
@@ -3,15 +3,22 @@ use std::fmt::Write;
 
 use serde::{Deserialize, Serialize};
 
-use abstutil::Counter;
+use abstutil::{Counter, Timer};
 use geom::{Duration, Pt2D, Time};
 use map_model::{
     CompressedMovementID, IntersectionID, LaneID, Map, MovementID, ParkingLotID, Path, PathRequest,
-    RoadID, TransitRouteID, TransitStopID, Traversable, TurnID,
+    PathStepV2, PathV2, RoadID, TransitRouteID, TransitStopID, Traversable, TurnID,
 };
 use synthpop::TripMode;
 
-use crate::{AgentID, AgentType, AlertLocation, CarID, Event, ParkingSpot, TripID, TripPhaseType};
+use crate::{
+    AgentID, AgentType, Alert, AlertCategory, CarID, Event, ParkingSpot, RunMetadata, TripID,
+    TripPhaseType,
+};
+
+/// Only remember this many of the most recent alerts. A broken scenario can raise one for every
+/// trip.
+const MAX_ALERT_LOG: usize = 1000;
 
 /// As a simulation runs, different pieces emit Events. The Analytics object listens to these,
 /// organizing and storing some information from them. The UI queries Analytics to draw time-series
@@ -21,6 +28,11 @@ use crate::{AgentID, AgentType, AlertLocation, CarID, Event, ParkingSpot, TripID
 /// results." These are just serialized Analytics after running the simulation on a map without any
 /// edits for the full day. This is the basis of A/B testing -- the player can edit the map, start
 /// running the simulation, and compare the live Analytics to the prebaked baseline Analytics.
+///
+/// Prebaked results are stored with bincode, which has no notion of missing fields. Any change to
+/// the fields here breaks every existing file, and they have to be regenerated (see
+/// data/regen.sh). Adding `ramp_meter_releases` and `border_entry_delays` was one such
+/// change, and so was adding `alert_log` and `alert_counts`.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Analytics {
     pub road_thruput: TimeSeriesCount<RoadID>,
@@ -54,11 +66,28 @@ pub struct Analytics {
     /// Only for traffic signals. The u8 is the movement index from a CompressedMovementID.
     pub intersection_delays: BTreeMap<IntersectionID, Vec<(u8, Time, Duration, AgentType)>>,
 
+    /// For each vehicle released by a ramp meter, when and how long it waited. Keyed by the
+    /// on-ramp.
+    pub ramp_meter_releases: BTreeMap<RoadID, Vec<(Time, Duration)>>,
+    /// For each vehicle that had to wait off-map before entering at a border, when it entered and
+    /// how long it waited. Vehicles that still haven't entered aren't counted here; see
+    /// `Sim::border_queues`.
+    pub border_entry_delays: BTreeMap<IntersectionID, Vec<(Time, TripID, Duration)>>,
+
     /// Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
     pub parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
 
-    pub(crate) alerts: Vec<(Time, AlertLocation, String)>,
+    /// The most recent alerts raised, for reviewing later
+    pub alert_log: VecDeque<Alert>,
+    /// How many alerts of each kind have been raised so far, including ones too old to still be
+    /// in `alert_log`
+    pub alert_counts: BTreeMap<AlertCategory, usize>,
+    /// Alerts not handled yet. See `AlertHandler`.
+    pub(crate) alerts: Vec<Alert>,
+
+    /// How the run producing this was set up. Only filled out when the analytics are saved.
+    pub run_metadata: Option<RunMetadata>,
 
     /// For benchmarking, we may want to disable collecting data.
     record_anything: bool,
@@ -76,6 +105,13 @@ pub enum Problem {
     OvertakeDesired(Traversable),
     /// Too many people are crossing the same sidewalk or crosswalk at the same time.
     PedestrianOvercrowding(Traversable),
+    /// A driver went through a stop sign without yielding to somebody who was there first.
+    StopSignViolation(TurnID),
+    /// A vehicle and pedestrian used this crossing shortly after each other. The duration is the
+    /// post-encroachment time.
+    NearMiss(TurnID, Duration),
+    /// A vehicle had to stop suddenly, because a pedestrian just stepped into this crossing.
+    HardBraking(TurnID),
 }
 
 impl Problem {
@@ -89,7 +125,10 @@ impl Problem {
             Problem::OvertakeDesired(on) | Problem::PedestrianOvercrowding(on) => {
                 on.get_polyline(map).middle()
             }
-            Problem::ArterialIntersectionCrossing(t) => map.get_t(*t).geom.middle(),
+            Problem::ArterialIntersectionCrossing(t)
+            | Problem::StopSignViolation(t)
+            | Problem::NearMiss(t, _)
+            | Problem::HardBraking(t) => map.get_t(*t).geom.middle(),
         }
     }
 }
@@ -101,6 +140,9 @@ pub enum ProblemType {
     OvertakeDesired,
     ArterialIntersectionCrossing,
     PedestrianOvercrowding,
+    StopSignViolation,
+    NearMiss,
+    HardBraking,
 }
 
 impl From<&Problem> for ProblemType {
@@ -111,6 +153,9 @@ impl From<&Problem> for ProblemType {
             Problem::OvertakeDesired(_) => Self::OvertakeDesired,
             Problem::ArterialIntersectionCrossing(_) => Self::ArterialIntersectionCrossing,
             Problem::PedestrianOvercrowding(_) => Self::PedestrianOvercrowding,
+            Problem::StopSignViolation(_) => Self::StopSignViolation,
+            Problem::NearMiss(_, _) => Self::NearMiss,
+            Problem::HardBraking(_) => Self::HardBraking,
         }
     }
 }
@@ -133,9 +178,20 @@ impl ProblemType {
             ProblemType::OvertakeDesired,
             ProblemType::ArterialIntersectionCrossing,
             ProblemType::PedestrianOvercrowding,
+            ProblemType::StopSignViolation,
+            ProblemType::NearMiss,
+            ProblemType::HardBraking,
         ]
     }
 
+    /// Surrogate safety measures, standing in for crash data
+    pub fn is_safety(self) -> bool {
+        matches!(
+            self,
+            ProblemType::StopSignViolation | ProblemType::NearMiss | ProblemType::HardBraking
+        )
+    }
+
     pub fn name(self) -> &'static str {
         match self {
             ProblemType::IntersectionDelay => "delays",
@@ -147,6 +203,9 @@ impl ProblemType {
                 "where pedestrians cross arterial intersections"
             }
             ProblemType::PedestrianOvercrowding => "where pedestrians are over-crowded",
+            ProblemType::StopSignViolation => "where drivers run stop signs",
+            ProblemType::NearMiss => "near-misses between vehicles and pedestrians",
+            ProblemType::HardBraking => "where vehicles brake hard for pedestrians",
         }
     }
 }
@@ -166,9 +225,14 @@ impl Analytics {
             problems_per_trip: BTreeMap::new(),
             trip_log: Vec::new(),
             intersection_delays: BTreeMap::new(),
+            ramp_meter_releases: BTreeMap::new(),
+            border_entry_delays: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
+            alert_log: VecDeque::new(),
+            alert_counts: BTreeMap::new(),
             alerts: Vec::new(),
+            run_metadata: None,
             record_anything,
         }
     }
@@ -266,6 +330,19 @@ impl Analytics {
             self.finished_trips.push((time, id, mode, None));
         }
 
+        if let Event::RampMeterReleased(r, waited) = ev {
+            self.ramp_meter_releases
+                .entry(r)
+                .or_insert_with(Vec::new)
+                .push((time, waited));
+        }
+        if let Event::BorderEntryDelayed(trip, i, waited) = ev {
+            self.border_entry_delays
+                .entry(i)
+                .or_insert_with(Vec::new)
+                .push((time, trip, waited));
+        }
+
         // Intersection delay
         if let Event::IntersectionDelayMeasured(trip_id, turn_id, agent, delay) = ev {
             let threshold = match agent {
@@ -358,8 +435,13 @@ impl Analytics {
             Event::PathAmended(path) => {
                 self.record_demand(&path, map);
             }
-            Event::Alert(loc, msg) => {
-                self.alerts.push((time, loc, msg));
+            Event::Alert(location, category, msg) => {
+                self.alert(Alert {
+                    time,
+                    location,
+                    category,
+                    msg,
+                });
             }
             Event::ProblemEncountered(trip, problem) => {
                 self.problems_per_trip
@@ -477,6 +559,116 @@ impl Analytics {
         trips
     }
 
+    /// Finds every driving or biking trip phase that crossed a road sometime during a time window.
+    /// Paths aren't stored, so they're recalculated from the request of each phase. If calling on
+    /// prebaked Analytics, be careful to pass in an unedited map.
+    pub fn select_link(
+        &self,
+        map: &Map,
+        r: RoadID,
+        window: (Time, Time),
+        timer: &mut Timer,
+    ) -> Vec<(TripID, PathRequest, PathV2)> {
+        let overlaps = |start: Time, end: Time| start <= window.1 && end >= window.0;
+
+        let mut candidates = Vec::new();
+        let mut current_phase: BTreeMap<TripID, (Time, Option<PathRequest>, TripPhaseType)> =
+            BTreeMap::new();
+        for (t, id, maybe_req, phase_type) in &self.trip_log {
+            if let Some((start, Some(req), prev_type)) = current_phase.remove(id) {
+                if (prev_type == TripPhaseType::Driving || prev_type == TripPhaseType::Biking)
+                    && overlaps(start, *t)
+                {
+                    candidates.push((*id, req));
+                }
+            }
+            current_phase.insert(*id, (*t, maybe_req.clone(), *phase_type));
+        }
+        // Phases still in progress
+        for (id, (start, maybe_req, phase_type)) in current_phase {
+            if let Some(req) = maybe_req {
+                if (phase_type == TripPhaseType::Driving || phase_type == TripPhaseType::Biking)
+                    && overlaps(start, window.1)
+                {
+                    candidates.push((id, req));
+                }
+            }
+        }
+
+        timer
+            .parallelize("calculate paths", candidates, |(id, req)| {
+                let path = map.pathfind_v2(req.clone()).ok()?;
+                if path.get_steps().iter().any(|step| match step {
+                    PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) => dr.road == r,
+                    _ => false,
+                }) {
+                    Some((id, req, path))
+                } else {
+                    None
+                }
+            })
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Queue up an alert for the `AlertHandler`, and remember it if recording anything. Alerts
+    /// raised by breakpoints go here directly, so they work even without analytics.
+    pub(crate) fn alert(&mut self, alert: Alert) {
+        if self.record_anything {
+            *self.alert_counts.entry(alert.category).or_insert(0) += 1;
+            self.alert_log.push_back(alert.clone());
+            if self.alert_log.len() > MAX_ALERT_LOG {
+                self.alert_log.pop_front();
+            }
+        }
+        self.alerts.push(alert);
+    }
+
+    /// How many vehicles a ramp meter has released before some time, and how long they waited on
+    /// average
+    pub fn ramp_meter_summary(&self, ramp: RoadID, now: Time) -> (usize, Duration) {
+        let mut count = 0;
+        let mut total = Duration::ZERO;
+        for (_, waited) in self
+            .ramp_meter_releases
+            .get(&ramp)
+            .into_iter()
+            .flatten()
+            .take_while(|(t, _)| *t <= now)
+        {
+            count += 1;
+            total += *waited;
+        }
+        if count == 0 {
+            (0, Duration::ZERO)
+        } else {
+            (count, total / (count as f64))
+        }
+    }
+
+    /// How many vehicles waited off-map to enter at a border before some time, and how long they
+    /// waited on average
+    pub fn border_entry_summary(&self, i: IntersectionID, now: Time) -> (usize, Duration) {
+        let mut count = 0;
+        let mut total = Duration::ZERO;
+        for (_, _, waited) in self
+            .border_entry_delays
+            .get(&i)
+            .into_iter()
+            .flatten()
+            .take_while(|(t, _, _)| *t <= now)
+        {
+            count += 1;
+            total += *waited;
+        }
+        if count == 0 {
+            (0, Duration::ZERO)
+        } else {
+            (count, total / (count as f64))
+        }
+    }
+
     pub fn active_agents(&self, now: Time) -> Vec<(Time, usize)> {
         let mut starts_stops: Vec<(Time, bool)> = Vec::new();
         for t in self.started_trips.values() {
@@ -608,7 +800,10 @@ impl Analytics {
                             }
                         }
                     }
-                    Problem::ArterialIntersectionCrossing(t) => t.parent,
+                    Problem::ArterialIntersectionCrossing(t)
+                    | Problem::StopSignViolation(t)
+                    | Problem::NearMiss(t, _)
+                    | Problem::HardBraking(t) => t.parent,
                 };
                 if id == i {
                     raw_per_type
@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::{Distance, LonLat, Pt2D, Speed, Time};
+use map_model::{BuildingID, Map};
+
+use crate::{CarID, PersonID, SidewalkSpot, Vehicle, VehicleSpec, VehicleType, BIKE_LENGTH};
+
+/// Walking trips shorter than this, measured in a straight line, won't bother with bike share.
+pub const MIN_BIKE_SHARE_TRIP_DIST: Distance = Distance::const_meters(1000.0);
+/// How far somebody is willing to walk to pick up or drop off a shared bike.
+pub const MAX_WALK_TO_BIKE_SHARE: Distance = Distance::const_meters(400.0);
+
+/// A set of bike share stations, as designed by the player. This is independent of the map's
+/// IDs, so it can be stored separately and applied to any simulation.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BikeShareNetwork {
+    pub stations: Vec<BikeShareStation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BikeShareStation {
+    pub name: String,
+    /// Stored in GPS coordinates, so the station still makes sense after the map is regenerated.
+    pub pt: LonLat,
+    /// How many docks the station has
+    pub capacity: usize,
+    /// How many bikes are docked at the start of the day
+    pub initial_bikes: usize,
+}
+
+impl BikeShareNetwork {
+    /// Where the player's stations for a map are stored
+    pub fn path(name: &MapName) -> String {
+        abstio::path_player(format!(
+            "bike_share/{}/{}/{}.json",
+            name.city.country, name.city.city, name.map
+        ))
+    }
+}
+
+/// The state of one bike share station during the simulation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BikeShareStationState {
+    pub name: String,
+    /// Bikes are picked up and dropped off at this building's bike rack.
+    pub bldg: BuildingID,
+    pub capacity: usize,
+    pub initial_bikes: usize,
+    /// This can exceed capacity, if people return bikes to a full station.
+    pub bikes: usize,
+    pub log: Vec<(Time, BikeShareActivity)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BikeShareActivity {
+    Pickup,
+    Return,
+    /// Somebody wanted a bike, but the station was empty, so they walked instead.
+    EmptyStation,
+    /// Somebody returned a bike to a full station. They leave it there anyway, over capacity.
+    FullStation,
+}
+
+/// Tracks shared bikes docked at stations and being ridden. Walking trips that're long enough and
+/// start and end near a station switch to riding a shared bike in between, if one is available
+/// when they reach the station.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct BikeShareSimState {
+    stations: Vec<BikeShareStationState>,
+    /// Shared bikes that've been reserved for a trip or are being ridden, mapped to the index of
+    /// the station they'll be picked up from and returned to.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    bikes: BTreeMap<CarID, (usize, usize)>,
+}
+
+impl BikeShareSimState {
+    pub fn new(map: &Map, network: Option<&BikeShareNetwork>) -> BikeShareSimState {
+        let mut stations = Vec::new();
+        for station in network.map(|n| n.stations.iter()).into_iter().flatten() {
+            let pt = station.pt.to_pt(map.get_gps_bounds());
+            if !map.get_boundary_polygon().contains_pt(pt) {
+                continue;
+            }
+            // Snap to the closest building that bikes can reach
+            let mut bldgs: Vec<(Distance, BuildingID)> = map
+                .all_buildings()
+                .iter()
+                .map(|b| (b.label_center.dist_to(pt), b.id))
+                .collect();
+            bldgs.sort();
+            if let Some((_, b)) = bldgs
+                .into_iter()
+                .find(|(_, b)| SidewalkSpot::bike_rack(*b, map).is_some())
+            {
+                stations.push(BikeShareStationState {
+                    name: station.name.clone(),
+                    bldg: b,
+                    capacity: station.capacity,
+                    initial_bikes: station.initial_bikes,
+                    bikes: station.initial_bikes,
+                    log: Vec::new(),
+                });
+            }
+        }
+        BikeShareSimState {
+            stations,
+            bikes: BTreeMap::new(),
+        }
+    }
+
+    /// If this walking trip should use bike share, returns the stations to pick up and drop off
+    /// the bike.
+    pub fn plan(
+        &self,
+        start: &SidewalkSpot,
+        goal: &SidewalkSpot,
+        map: &Map,
+    ) -> Option<(usize, usize)> {
+        let pt1 = start.sidewalk_pos.pt(map);
+        let pt2 = goal.sidewalk_pos.pt(map);
+        let trip_dist = pt1.dist_to(pt2);
+        if trip_dist < MIN_BIKE_SHARE_TRIP_DIST {
+            return None;
+        }
+        let pickup = self.closest_station(pt1, map)?;
+        let dropoff = self.closest_station(pt2, map)?;
+        if pickup.0 == dropoff.0 || pickup.1 + dropoff.1 > trip_dist / 2.0 {
+            return None;
+        }
+        Some((pickup.0, dropoff.0))
+    }
+
+    fn closest_station(&self, pt: Pt2D, map: &Map) -> Option<(usize, Distance)> {
+        self.stations
+            .iter()
+            .enumerate()
+            .map(|(idx, s)| (idx, map.get_b(s.bldg).sidewalk_pos.pt(map).dist_to(pt)))
+            .filter(|(_, dist)| *dist <= MAX_WALK_TO_BIKE_SHARE)
+            .min_by_key(|(_, dist)| *dist)
+    }
+
+    pub fn station_bldg(&self, idx: usize) -> BuildingID {
+        self.stations[idx].bldg
+    }
+
+    /// Remember which stations a shared bike will be used between.
+    pub fn reserve(&mut self, bike: CarID, pickup: BuildingID, dropoff: BuildingID) {
+        let idx = |b| self.stations.iter().position(|s| s.bldg == b).unwrap();
+        self.bikes.insert(bike, (idx(pickup), idx(dropoff)));
+    }
+
+    pub fn is_shared(&self, bike: CarID) -> bool {
+        self.bikes.contains_key(&bike)
+    }
+
+    /// Take a bike from the station, if there's one available. Otherwise the bike is forgotten.
+    pub fn pickup(&mut self, now: Time, bike: CarID, rider: PersonID) -> Option<Vehicle> {
+        let station = &mut self.stations[self.bikes[&bike].0];
+        if station.bikes == 0 {
+            station.log.push((now, BikeShareActivity::EmptyStation));
+            self.bikes.remove(&bike);
+            return None;
+        }
+        station.bikes -= 1;
+        station.log.push((now, BikeShareActivity::Pickup));
+        Some(
+            VehicleSpec {
+                vehicle_type: VehicleType::Bike,
+                length: BIKE_LENGTH,
+                max_speed: Some(Speed::miles_per_hour(10.0)),
+            }
+            .make(bike, Some(rider)),
+        )
+    }
+
+    pub fn dock(&mut self, now: Time, bike: CarID) {
+        let (_, dropoff) = self.bikes.remove(&bike).unwrap();
+        let station = &mut self.stations[dropoff];
+        station.log.push((
+            now,
+            if station.bikes >= station.capacity {
+                BikeShareActivity::FullStation
+            } else {
+                BikeShareActivity::Return
+            },
+        ));
+        station.bikes += 1;
+    }
+
+    /// The trip using a shared bike was cancelled, so the bike vanishes with the rider.
+    pub fn abandon(&mut self, bike: CarID) {
+        self.bikes.remove(&bike);
+    }
+
+    pub fn get_stations(&self) -> &Vec<BikeShareStationState> {
+        &self.stations
+    }
+}
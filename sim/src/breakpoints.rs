@@ -0,0 +1,149 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Time};
+use map_model::{IntersectionID, LaneID, Map, Traversable};
+
+use crate::{AlertLocation, DrivingSimState, Event, IntersectionSimState, TripID};
+
+/// A condition that pauses the simulation when it becomes true. Breakpoints are checked against
+/// events as they're dispatched, so having a few doesn't slow anything down.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Breakpoint {
+    /// Somebody has been waiting at an intersection for at least this long. Nothing happens while
+    /// an agent is stuck, so there's no event to watch; this is checked once per simulated second.
+    AgentWaitsLongerThan(Duration),
+    /// More than this many vehicles are on one lane
+    LaneQueueExceeds(LaneID, usize),
+    /// The trip starts a turn through the intersection
+    TripReachesIntersection(TripID, IntersectionID),
+}
+
+impl Breakpoint {
+    pub fn describe(&self) -> String {
+        match self {
+            Breakpoint::AgentWaitsLongerThan(d) => format!("any agent waits longer than {}", d),
+            Breakpoint::LaneQueueExceeds(l, max) => format!("{} has more than {} vehicles", l, max),
+            Breakpoint::TripReachesIntersection(t, i) => format!("{} reaches {}", t, i),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Breakpoints {
+    pub list: Vec<Breakpoint>,
+    waiting: WaitWatcher,
+}
+
+impl Breakpoints {
+    /// If the event trips a breakpoint, describe where and why.
+    pub fn handle_event(
+        &self,
+        ev: &Event,
+        map: &Map,
+        driving: &DrivingSimState,
+    ) -> Option<(AlertLocation, String)> {
+        let (trip, on) = match ev {
+            Event::AgentEntersTraversable(_, trip, on, _) => (trip, on),
+            _ => {
+                return None;
+            }
+        };
+        for bp in &self.list {
+            match (bp, on) {
+                (Breakpoint::LaneQueueExceeds(l, max), Traversable::Lane(on_lane))
+                    if l == on_lane =>
+                {
+                    // Only pause when the queue first grows past the limit
+                    let count = driving.num_cars_on_lane(*l);
+                    if count == max + 1 {
+                        return Some((
+                            AlertLocation::Intersection(map.get_l(*l).dst_i),
+                            format!("Breakpoint: {} vehicles on {}", count, l),
+                        ));
+                    }
+                }
+                (Breakpoint::TripReachesIntersection(t, i), Traversable::Turn(turn))
+                    if *trip == Some(*t) && turn.parent == *i =>
+                {
+                    return Some((
+                        AlertLocation::Intersection(*i),
+                        format!("Breakpoint: {} reached {}", t, i),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    pub fn check_waiting(
+        &mut self,
+        now: Time,
+        intersections: &IntersectionSimState,
+    ) -> Vec<(AlertLocation, String)> {
+        let threshold = match self
+            .list
+            .iter()
+            .filter_map(|bp| match bp {
+                Breakpoint::AgentWaitsLongerThan(d) => Some(*d),
+                _ => None,
+            })
+            .min()
+        {
+            Some(d) => d,
+            None => {
+                return Vec::new();
+            }
+        };
+        self.waiting
+            .newly_delayed(now, Duration::seconds(1.0), threshold, intersections)
+            .into_iter()
+            .map(|(i, since)| {
+                (
+                    AlertLocation::Intersection(i),
+                    format!(
+                        "Breakpoint: somebody has waited at {} since {}",
+                        i,
+                        since.ampm_tostring()
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Notices intersections where somebody has waited too long. Nothing happens while an agent is
+/// stuck, so this has to be checked periodically.
+#[derive(Clone, Default)]
+pub(crate) struct WaitWatcher {
+    last_check: Time,
+    /// Only report once per jammed intersection, so resuming doesn't immediately pause again
+    already_waiting: BTreeSet<IntersectionID>,
+}
+
+impl WaitWatcher {
+    /// Intersections where somebody has started waiting longer than the threshold since the last
+    /// check, and when they started waiting. Only checks once per `period` of simulated time.
+    pub fn newly_delayed(
+        &mut self,
+        now: Time,
+        period: Duration,
+        threshold: Duration,
+        intersections: &IntersectionSimState,
+    ) -> Vec<(IntersectionID, Time)> {
+        if now - self.last_check < period {
+            return Vec::new();
+        }
+        self.last_check = now;
+
+        let delayed = intersections.delayed_intersections(now, threshold);
+        self.already_waiting
+            .retain(|i| delayed.iter().any(|(delayed_i, _)| delayed_i == i));
+        delayed
+            .into_iter()
+            .filter(|(i, _)| self.already_waiting.insert(*i))
+            .collect()
+    }
+}
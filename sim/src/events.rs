@@ -1,9 +1,11 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-use geom::Duration;
+use geom::{Duration, Time};
 use map_model::{
-    BuildingID, IntersectionID, LaneID, Map, Path, PathRequest, TransitRouteID, TransitStopID,
-    Traversable, TurnID,
+    BuildingID, IntersectionID, LaneID, Map, Path, PathRequest, RoadID, TransitRouteID,
+    TransitStopID, Traversable, TurnID,
 };
 use synthpop::TripMode;
 
@@ -45,6 +47,10 @@ pub enum Event {
     AgentEntersTraversable(AgentID, Option<TripID>, Traversable, Option<usize>),
     /// TripID, TurnID (Where the delay was encountered), Time spent waiting at that turn
     IntersectionDelayMeasured(TripID, TurnID, AgentID, Duration),
+    /// A ramp meter on this road let a vehicle onto the motorway, after it waited this long
+    RampMeterReleased(RoadID, Duration),
+    /// A vehicle waited off-map this long before entering at a border
+    BorderEntryDelayed(TripID, IntersectionID, Duration),
 
     TripFinished {
         trip: TripID,
@@ -59,7 +65,7 @@ pub enum Event {
     /// to plumb info into Analytics is Event.
     PathAmended(Path),
 
-    Alert(AlertLocation, String),
+    Alert(AlertLocation, AlertCategory, String),
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -70,6 +76,91 @@ pub enum AlertLocation {
     Building(BuildingID),
 }
 
+/// What kind of unusual thing happened, so alerts that are expected can be muted
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+pub enum AlertCategory {
+    /// A trip was cancelled, often because there was no room to spawn a vehicle
+    TripCancelled,
+    /// A driver couldn't find parking anywhere reachable
+    NoParking,
+    /// Somebody has waited at an intersection for a long time
+    StuckAgent,
+    /// Vehicles waiting on each other in a cycle, so one blocks the box to break gridlock
+    TurnConflictCycle,
+    /// Something unusual about a traffic signal, like an actuated stage being extended
+    SignalTiming,
+    /// Somebody's next trip had to wait for their previous one to finish
+    DelayedTrip,
+    /// A breakpoint was hit
+    Breakpoint,
+}
+
+impl AlertCategory {
+    pub fn all() -> Vec<AlertCategory> {
+        vec![
+            AlertCategory::TripCancelled,
+            AlertCategory::NoParking,
+            AlertCategory::StuckAgent,
+            AlertCategory::TurnConflictCycle,
+            AlertCategory::SignalTiming,
+            AlertCategory::DelayedTrip,
+            AlertCategory::Breakpoint,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            AlertCategory::TripCancelled => "cancelled trips",
+            AlertCategory::NoParking => "no parking",
+            AlertCategory::StuckAgent => "stuck agents",
+            AlertCategory::TurnConflictCycle => "turn conflict cycles",
+            AlertCategory::SignalTiming => "signal timing",
+            AlertCategory::DelayedTrip => "delayed trips",
+            AlertCategory::Breakpoint => "breakpoints",
+        }
+    }
+
+    pub fn severity(self) -> AlertSeverity {
+        match self {
+            AlertCategory::TripCancelled | AlertCategory::NoParking => AlertSeverity::Error,
+            AlertCategory::StuckAgent | AlertCategory::TurnConflictCycle => AlertSeverity::Warning,
+            AlertCategory::SignalTiming
+            | AlertCategory::DelayedTrip
+            | AlertCategory::Breakpoint => AlertSeverity::Info,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    /// Something went wrong for an agent, like their trip being cancelled
+    Error,
+}
+
+/// Something unusual that happened during the simulation
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub time: Time,
+    pub location: AlertLocation,
+    pub category: AlertCategory,
+    pub msg: String,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Alert at {} ({:?}, {}): {}",
+            self.time,
+            self.location,
+            self.category.describe(),
+            self.msg
+        )
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum TripPhaseType {
     Driving,
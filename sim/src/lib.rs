@@ -22,7 +22,7 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::{Distance, Speed, Time};
+use geom::{Distance, Duration, Speed, Time};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathConstraints, Position,
     TransitRouteID, TransitStopID,
@@ -35,8 +35,15 @@ pub use crate::render::{
 };
 
 pub use self::analytics::{Analytics, Problem, ProblemType, SlidingWindow, TripPhase};
+pub(crate) use self::bike_share::BikeShareSimState;
+pub use self::bike_share::{
+    BikeShareActivity, BikeShareNetwork, BikeShareStation, BikeShareStationState,
+    MAX_WALK_TO_BIKE_SHARE, MIN_BIKE_SHARE_TRIP_DIST,
+};
+pub use self::breakpoints::Breakpoint;
+pub(crate) use self::breakpoints::{Breakpoints, WaitWatcher};
 pub(crate) use self::events::Event;
-pub use self::events::{AlertLocation, TripPhaseType};
+pub use self::events::{Alert, AlertCategory, AlertLocation, AlertSeverity, TripPhaseType};
 pub use self::make::SimFlags;
 pub(crate) use self::make::{StartTripArgs, TripSpec};
 pub(crate) use self::mechanics::{
@@ -44,29 +51,37 @@ pub(crate) use self::mechanics::{
 };
 pub(crate) use self::pandemic::PandemicModel;
 pub use self::prebake::PrebakeSummary;
+pub use self::provenance::RunMetadata;
 pub(crate) use self::recorder::TrafficRecorder;
 pub(crate) use self::router::{ActionAtEnd, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
 pub use self::sim::{
-    count_parked_cars_per_bldg, rand_dist, AgentProperties, AlertHandler, DelayCause, Sim,
-    SimCallback, SimOptions,
+    count_parked_cars_per_bldg, rand_dist, AgentProperties, AgentState, AlertHandler, DelayCause,
+    DelayStep, Sim, SimCallback, SimOptions,
 };
+pub use self::trace::{Divergence, EventTrace, TraceEntry};
 pub(crate) use self::transit::TransitSimState;
-pub use self::trips::{CommutersVehiclesCounts, Person, PersonState, TripInfo, TripResult};
+pub use self::trips::{
+    CommutersVehiclesCounts, Person, PersonState, PlannedLeg, TripInfo, TripResult,
+};
 pub(crate) use self::trips::{TripLeg, TripManager};
 pub use synthpop::make::{fork_rng, BorderSpawnOverTime, ScenarioGenerator, SpawnOverTime};
 
 mod analytics;
+mod bike_share;
+mod breakpoints;
 mod events;
 mod make;
 mod mechanics;
 mod pandemic;
 pub mod prebake;
+mod provenance;
 mod recorder;
 mod render;
 mod router;
 mod scheduler;
 mod sim;
+mod trace;
 mod transit;
 mod trips;
 
@@ -86,6 +101,10 @@ pub(crate) const FOLLOWING_DISTANCE: Distance = Distance::const_meters(1.0);
 /// Getting too close to EPSILON_DIST can lead to get_draw_car having no geometry at all.
 pub(crate) const SPAWN_DIST: Distance = Distance::const_meters(0.05);
 
+/// After dwelling at a pull-out stop and finding a gap in traffic, how long a transit vehicle takes
+/// to merge back into the travel lane. It blocks the lane during this time.
+pub const TIME_TO_MERGE_FROM_PULL_OUT: Duration = Duration::const_seconds(5.0);
+
 // TODO Implement Eq, Hash, Ord manually to guarantee this.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct CarID {
@@ -174,6 +193,10 @@ impl AgentID {
     pub(crate) fn is_pedestrian(&self) -> bool {
         matches!(self, AgentID::Pedestrian(_))
     }
+
+    pub(crate) fn is_bike(&self) -> bool {
+        matches!(self, AgentID::Car(c) if c.vehicle_type == VehicleType::Bike)
+    }
 }
 
 impl fmt::Display for AgentID {
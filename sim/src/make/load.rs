@@ -115,6 +115,7 @@ impl SimFlags {
                 opts.run_name = scenario.scenario_name.clone();
             }
             let mut sim = Sim::new(&map, opts);
+            sim.record_rng_seed(self.rng_seed);
             sim.instantiate(&scenario, &map, &mut rng, timer);
 
             (map, sim, rng)
@@ -48,6 +48,15 @@ pub(crate) enum TripSpec {
         start: BuildingID,
         goal: DrivingGoal,
     },
+    /// Walk to a bike share station, ride a shared bike to another station, and walk from there.
+    UsingBikeShare {
+        start: SidewalkSpot,
+        goal: SidewalkSpot,
+        /// Not a vehicle owned by the person; it's created when they pick it up
+        bike: CarID,
+        pickup: BuildingID,
+        dropoff: BuildingID,
+    },
     UsingTransit {
         start: SidewalkSpot,
         goal: SidewalkSpot,
@@ -183,6 +192,32 @@ impl TripSpec {
                     .into_plan(map);
                 }
             }
+            TripSpec::UsingBikeShare {
+                start,
+                goal,
+                bike,
+                pickup,
+                dropoff,
+            } => {
+                match (
+                    SidewalkSpot::bike_rack(*pickup, map),
+                    SidewalkSpot::bike_rack(*dropoff, map),
+                ) {
+                    (Some(pickup_spot), Some(_)) => {
+                        legs.push(TripLeg::Walk(pickup_spot));
+                        legs.push(TripLeg::Drive(*bike, DrivingGoal::ParkNear(*dropoff)));
+                        legs.push(TripLeg::Walk(goal.clone()));
+                    }
+                    _ => {
+                        info!("Can't bike between stations at {} and {}", pickup, dropoff);
+                        return TripSpec::JustWalking {
+                            start: start.clone(),
+                            goal: goal.clone(),
+                        }
+                        .into_plan(map);
+                    }
+                }
+            }
             TripSpec::UsingTransit {
                 route,
                 stop1,
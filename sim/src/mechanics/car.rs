@@ -1,9 +1,9 @@
-use std::collections::{BTreeSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use serde::{Deserialize, Serialize};
 
-use geom::{Distance, Duration, PolyLine, Time, EPSILON_DIST};
-use map_model::{Direction, LaneID, Map, Traversable};
+use geom::{Distance, Duration, PolyLine, Speed, Time, EPSILON_DIST};
+use map_model::{Direction, LaneID, Map, RoadID, Traversable};
 
 use crate::{
     CarID, CarStatus, DistanceInterval, DrawCarInput, Intent, ParkingSpot, PersonID, Router,
@@ -33,7 +33,15 @@ pub(crate) struct Car {
 
 impl Car {
     /// Assumes the current head of the path is the thing to cross.
-    pub fn crossing_state(&self, start_dist: Distance, start_time: Time, map: &Map) -> CarState {
+    /// Managed motorways may currently post a different speed limit than usual, so pass in
+    /// `posted_limits`.
+    pub fn crossing_state(
+        &self,
+        start_dist: Distance,
+        start_time: Time,
+        map: &Map,
+        posted_limits: &BTreeMap<RoadID, Speed>,
+    ) -> CarState {
         let end_dist = if self.router.last_step() {
             self.router.get_end_dist()
         } else {
@@ -47,7 +55,7 @@ impl Car {
         }
 
         let dist_int = DistanceInterval::new_driving(start_dist, end_dist);
-        self.crossing_state_with_end_dist(dist_int, start_time, map)
+        self.crossing_state_with_end_dist(dist_int, start_time, map, posted_limits)
     }
 
     pub fn crossing_state_with_end_dist(
@@ -55,17 +63,18 @@ impl Car {
         dist_int: DistanceInterval,
         start_time: Time,
         map: &Map,
+        posted_limits: &BTreeMap<RoadID, Speed>,
     ) -> CarState {
-        let (speed, percent_incline) = self
-            .router
-            .get_path()
-            .current_step()
-            .max_speed_and_incline_along(
-                self.vehicle.max_speed,
-                self.vehicle.vehicle_type.to_constraints(),
-                map,
-            );
-        let dt = (dist_int.end - dist_int.start) / speed;
+        let step = self.router.get_path().current_step();
+        let constraints = self.vehicle.vehicle_type.to_constraints();
+        let (speed, percent_incline) = step.max_speed_and_incline_with_posted_limits(
+            self.vehicle.max_speed,
+            constraints,
+            map,
+            posted_limits,
+        );
+        let dt = (dist_int.end - dist_int.start) / speed
+            + step.traffic_calming_delay(dist_int.start, dist_int.end, speed, constraints, map);
         CarState::Crossing {
             time_int: TimeInterval::new(start_time, start_time + dt),
             dist_int,
@@ -3,25 +3,34 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_hashmap, serialize_hashmap, FixedMap, IndexableKey};
-use geom::{Distance, Duration, PolyLine, Time};
-use map_model::{DrivingSide, IntersectionID, LaneID, Map, Path, PathStep, Position, Traversable};
+use geom::{Distance, Duration, PolyLine, Speed, Time};
+use map_model::{
+    DrivingSide, IntersectionID, LaneID, Map, Path, PathStep, Position, RoadID, StopLayout,
+    Traversable, TurnID,
+};
 
 use crate::mechanics::car::{Car, CarState};
-use crate::mechanics::queue::{Queue, QueueEntry, Queued};
+use crate::mechanics::queue::{vehicle_density, Queue, QueueEntry, Queued};
 use crate::sim::Ctx;
 use crate::{
-    ActionAtEnd, AgentID, AgentProperties, CarID, CarStatus, Command, CreateCar, DelayCause,
-    DistanceInterval, DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim,
+    ActionAtEnd, AgentID, AgentProperties, AgentState, CarID, CarStatus, Command, CreateCar,
+    DelayCause, DistanceInterval, DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim,
     ParkingSpot, PersonID, Problem, SimOptions, TimeInterval, TransitSimState, TripID, TripManager,
     UnzoomedAgent, Vehicle, VehicleType, WalkingSimState, FOLLOWING_DISTANCE, MAX_CAR_LENGTH,
+    TIME_TO_MERGE_FROM_PULL_OUT,
 };
 
-const TIME_TO_WAIT_AT_BUS_STOP: Duration = Duration::const_seconds(10.0);
 const TIME_TO_CHANGE_LANES: Duration = Duration::const_seconds(1.0);
 
 // TODO Do something else.
 pub const BLIND_RETRY_TO_CREEP_FORWARDS: Duration = Duration::const_seconds(0.1);
 pub const BLIND_RETRY_TO_REACH_END_DIST: Duration = Duration::const_seconds(5.0);
+const BLIND_RETRY_TO_MERGE: Duration = Duration::const_seconds(1.0);
+/// When `SimOptions::limit_border_inflow` is on, vehicles appearing at a border enter each lane at
+/// most this often, roughly a lane's capacity. Everybody else waits off-map.
+const BORDER_ENTRY_HEADWAY: Duration = Duration::const_seconds(2.0);
+/// How often managed motorways check traffic and their schedule to post a new limit
+const VARIABLE_SPEED_LIMIT_UPDATE: Duration = Duration::const_seconds(60.0);
 
 /// Simulates vehicles!
 #[derive(Serialize, Deserialize, Clone)]
@@ -42,9 +51,31 @@ pub(crate) struct DrivingSimState {
     events: Vec<Event>,
 
     waiting_to_spawn: BTreeMap<CarID, (Position, Option<PersonID>)>,
+    /// Transit vehicles dwelling at pull-out stops aren't in any queue, so traffic can pass them.
+    /// They're still in `cars`, in the IdlingAtStop state.
+    pulled_over: BTreeSet<CarID>,
 
     recalc_lanechanging: bool,
     handle_uber_turns: bool,
+    /// Managed motorways currently posting a different limit than the road's normal one. Adding
+    /// this and `congested_roads` broke existing savestates.
+    posted_speed_limits: BTreeMap<RoadID, Speed>,
+    /// Each on-ramp, and the motorway it joins
+    on_ramps: BTreeMap<RoadID, RoadID>,
+    /// The limits vehicles actually drive at, when they differ from the road's normal one. These
+    /// are the posted limits, plus on-ramps, where vehicles speed up to join the motorway.
+    /// Routing doesn't see either.
+    driving_speed_limits: BTreeMap<RoadID, Speed>,
+    /// Roads with a variable speed limit currently lowered because of congestion
+    congested_roads: BTreeSet<RoadID>,
+    /// When did a vehicle last appear on each lane leading away from a border?
+    border_entries: BTreeMap<LaneID, Time>,
+    /// Vehicles queued off-map at a border, and when they first tried to enter
+    border_queue: BTreeMap<CarID, Time>,
+    /// For each lane leading away from a border, the vehicles queued to enter it, in the order
+    /// they arrived
+    border_queue_order: BTreeMap<LaneID, VecDeque<CarID>>,
+    limit_border_inflow: bool,
 
     time_to_unpark_onstreet: Duration,
     time_to_park_onstreet: Duration,
@@ -62,6 +93,15 @@ impl DrivingSimState {
             recalc_lanechanging: !opts.dont_recalc_lanechanging,
             handle_uber_turns: !opts.dont_handle_uber_turns,
             waiting_to_spawn: BTreeMap::new(),
+            pulled_over: BTreeSet::new(),
+            posted_speed_limits: BTreeMap::new(),
+            on_ramps: find_on_ramps(map),
+            driving_speed_limits: BTreeMap::new(),
+            congested_roads: BTreeSet::new(),
+            border_entries: BTreeMap::new(),
+            border_queue: BTreeMap::new(),
+            border_queue_order: BTreeMap::new(),
+            limit_border_inflow: opts.limit_border_inflow,
 
             time_to_unpark_onstreet: Duration::seconds(10.0),
             time_to_park_onstreet: Duration::seconds(15.0),
@@ -85,6 +125,7 @@ impl DrivingSimState {
                 sim.queues.insert(q.id, q);
             }
         }
+        sim.update_driving_speed_limits(map);
 
         sim
     }
@@ -97,6 +138,7 @@ impl DrivingSimState {
         ctx: &mut Ctx,
     ) -> Option<CreateCar> {
         let first_lane = params.router.head().as_lane();
+        let entering_at_border = entering_at_border(&params, ctx.map);
         let mut start_dist = params.router.get_path().get_req().start.dist_along();
         if let Some(ref p) = params.maybe_parked_car {
             // If we're exiting a driveway, make the front of the vehicle wind up in the correct
@@ -149,6 +191,9 @@ impl DrivingSimState {
                 // parking. Just skip over it, until we properly model them.
                 continue;
             }
+            if self.queues[&Traversable::Lane(lane)].draining {
+                return Some(params);
+            }
             if let Some(idx) = self.queues[&Traversable::Lane(lane)].can_block_from_driveway(
                 // This is before adjusting for the length of the vehicle exiting the driveway
                 &pos,
@@ -168,6 +213,7 @@ impl DrivingSimState {
         if !ctx
             .intersections
             .nobody_headed_towards(first_lane, ctx.map.get_l(first_lane).src_i)
+            || self.queues[&Traversable::Lane(first_lane)].draining
         {
             return Some(params);
         }
@@ -266,7 +312,8 @@ impl DrivingSimState {
                     }
                 }
 
-                car.state = car.crossing_state(start_dist, now, ctx.map);
+                car.state =
+                    car.crossing_state(start_dist, now, ctx.map, &self.driving_speed_limits);
                 start_crossing = true;
             }
             ctx.scheduler
@@ -276,6 +323,17 @@ impl DrivingSimState {
                 .unwrap()
                 .insert_car_at_idx(idx, &car);
             self.waiting_to_spawn.remove(&car.vehicle.id);
+            if let Some(i) = entering_at_border {
+                self.border_entries.insert(first_lane, now);
+                if let Some(first_try) = self.leave_border_queue(car.vehicle.id, first_lane) {
+                    if let Some((trip, _)) = car.trip_and_person {
+                        if now > first_try {
+                            self.events
+                                .push(Event::BorderEntryDelayed(trip, i, now - first_try));
+                        }
+                    }
+                }
+            }
 
             if start_crossing {
                 // Don't call this earlier where we set crossing_state, because we're not in the
@@ -292,8 +350,75 @@ impl DrivingSimState {
 
     /// If start_car_on_lane fails and a retry is scheduled, this is an idempotent way to mark the
     /// vehicle as active, but waiting to spawn.
-    pub fn vehicle_waiting_to_spawn(&mut self, id: CarID, pos: Position, person: Option<PersonID>) {
-        self.waiting_to_spawn.insert(id, (pos, person));
+    pub fn vehicle_waiting_to_spawn(&mut self, now: Time, params: &CreateCar, map: &Map) {
+        let id = params.vehicle.id;
+        self.waiting_to_spawn.insert(
+            id,
+            (
+                params.router.get_path().get_req().start,
+                params.trip_and_person.map(|(_, p)| p),
+            ),
+        );
+        if entering_at_border(params, map).is_some() && !self.border_queue.contains_key(&id) {
+            self.border_queue.insert(id, now);
+            self.border_queue_order
+                .entry(params.router.head().as_lane())
+                .or_insert_with(VecDeque::new)
+                .push_back(id);
+        }
+    }
+
+    /// Before calling start_car_on_lane, check if a vehicle appearing at a border has to wait
+    /// off-map for the lane's entry headway, or for vehicles that arrived before it. If so,
+    /// returns when to try again.
+    pub fn border_entry_delayed_until(
+        &self,
+        now: Time,
+        params: &CreateCar,
+        map: &Map,
+    ) -> Option<Time> {
+        entering_at_border(params, map)?;
+        if !self.limit_border_inflow {
+            return None;
+        }
+        let lane = params.router.head().as_lane();
+        let next = self
+            .border_entries
+            .get(&lane)
+            .map(|t| *t + BORDER_ENTRY_HEADWAY)
+            .unwrap_or(now)
+            .max(now);
+        // A vehicle arriving now goes to the back of the line
+        let num_ahead = self
+            .border_queue_order
+            .get(&lane)
+            .map(|queue| {
+                queue
+                    .iter()
+                    .position(|id| *id == params.vehicle.id)
+                    .unwrap_or(queue.len())
+            })
+            .unwrap_or(0);
+        if num_ahead > 0 {
+            // Everybody ahead enters first, one per headway
+            Some(next + BORDER_ENTRY_HEADWAY * (num_ahead as f64))
+        } else if now < next {
+            Some(next)
+        } else {
+            None
+        }
+    }
+
+    /// Stop tracking a vehicle queued off-map at a border, returning when it first tried to enter.
+    fn leave_border_queue(&mut self, id: CarID, lane: LaneID) -> Option<Time> {
+        let first_try = self.border_queue.remove(&id)?;
+        if let Some(queue) = self.border_queue_order.get_mut(&lane) {
+            queue.retain(|x| *x != id);
+            if queue.is_empty() {
+                self.border_queue_order.remove(&lane);
+            }
+        }
+        Some(first_try)
     }
 
     /// State transitions for this car:
@@ -325,6 +450,11 @@ impl DrivingSimState {
         transit: &mut TransitSimState,
         walking: &mut WalkingSimState,
     ) {
+        if self.pulled_over.contains(&id) {
+            self.try_merge_from_pull_out(id, now, ctx);
+            return;
+        }
+
         let mut need_distances = {
             let car = &self.cars[&id];
             match car.state {
@@ -399,6 +529,7 @@ impl DrivingSimState {
                             &self.queues,
                             ctx.map,
                             self.handle_uber_turns,
+                            now,
                         );
                     }
                     ctx.scheduler.push(now, Command::UpdateCar(car.vehicle.id));
@@ -478,7 +609,7 @@ impl DrivingSimState {
                         &mut self.events,
                     );
                 }
-                car.state = car.crossing_state(front, now, ctx.map);
+                car.state = car.crossing_state(front, now, ctx.map, &self.driving_speed_limits);
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                 self.new_crossing_state(ctx, car);
@@ -506,6 +637,9 @@ impl DrivingSimState {
                         // Don't schedule a retry here.
                         return false;
                     }
+                    let problems = ctx
+                        .intersections
+                        .take_problems(AgentID::Car(car.vehicle.id));
                     if let Some((trip, _)) = car.trip_and_person {
                         self.events.push(Event::IntersectionDelayMeasured(
                             trip,
@@ -513,6 +647,9 @@ impl DrivingSimState {
                             AgentID::Car(car.vehicle.id),
                             now - blocked_since,
                         ));
+                        for problem in problems {
+                            self.events.push(Event::ProblemEncountered(trip, problem));
+                        }
                     }
                 }
 
@@ -532,7 +669,8 @@ impl DrivingSimState {
                     &mut self.events,
                 );
                 car.total_blocked_time += now - blocked_since;
-                car.state = car.crossing_state(Distance::ZERO, now, ctx.map);
+                car.state =
+                    car.crossing_state(Distance::ZERO, now, ctx.map, &self.driving_speed_limits);
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                 self.events.push(Event::AgentEntersTraversable(
@@ -560,6 +698,7 @@ impl DrivingSimState {
                         ),
                         now,
                         ctx.map,
+                        &self.driving_speed_limits,
                     )
                     .get_end_time(),
                     Command::UpdateLaggyHead(car.vehicle.id),
@@ -694,7 +833,8 @@ impl DrivingSimState {
                     }
                     Some(ActionAtEnd::GotoLaneEnd) => {
                         car.total_blocked_time += now - blocked_since;
-                        car.state = car.crossing_state(our_dist, now, ctx.map);
+                        car.state =
+                            car.crossing_state(our_dist, now, ctx.map, &self.driving_speed_limits);
                         ctx.scheduler
                             .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                         self.new_crossing_state(ctx, car);
@@ -714,13 +854,22 @@ impl DrivingSimState {
                     }
                     Some(ActionAtEnd::BusAtStop) => {
                         car.total_blocked_time += now - blocked_since;
-                        if transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx) {
+                        if let Some(dwell_time) =
+                            transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx)
+                        {
                             car.state = CarState::IdlingAtStop(
                                 our_dist,
-                                TimeInterval::new(now, now + TIME_TO_WAIT_AT_BUS_STOP),
+                                TimeInterval::new(now, now + dwell_time),
                             );
                             ctx.scheduler
                                 .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+
+                            let stop = transit.current_stop(car.vehicle.id).unwrap();
+                            if ctx.map.get_ts(stop).layout == StopLayout::PullOut {
+                                // Get out of the way while dwelling
+                                self.remove_car_from_queue(car, dists, idx, now, ctx);
+                                self.pulled_over.insert(car.vehicle.id);
+                            }
                             true
                         } else {
                             // Vanishing at a border
@@ -776,7 +925,7 @@ impl DrivingSimState {
                 car.router = transit.bus_departed_from_stop(car.vehicle.id, ctx.map);
                 self.events
                     .push(Event::PathAmended(car.router.get_path().clone()));
-                car.state = car.crossing_state(dist, now, ctx.map);
+                car.state = car.crossing_state(dist, now, ctx.map, &self.driving_speed_limits);
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                 self.new_crossing_state(ctx, car);
@@ -791,7 +940,17 @@ impl DrivingSimState {
     /// Abruptly remove a vehicle from the simulation. They may be in any arbitrary state, like in
     /// the middle of a turn or parking.
     pub fn delete_car(&mut self, c: CarID, now: Time, ctx: &mut Ctx) -> Vehicle {
-        self.waiting_to_spawn.remove(&c);
+        if let Some((pos, _)) = self.waiting_to_spawn.remove(&c) {
+            self.leave_border_queue(c, pos.lane());
+        }
+
+        if self.pulled_over.remove(&c) {
+            // Not in any queue, but the intersections may still be tracking it
+            let car = self.cars.remove(&c).unwrap();
+            self.forget_car(&car, ctx);
+            ctx.scheduler.cancel(Command::UpdateCar(c));
+            return car.vehicle;
+        }
 
         let dists = self.queues[&self.cars[&c].router.head()].get_car_positions(
             now,
@@ -807,7 +966,7 @@ impl DrivingSimState {
         // Hacks to delete cars that're mid-turn
         if let Traversable::Turn(t) = car.router.head() {
             let queue = self.queues.get_mut(&car.router.head()).unwrap();
-            // delete_car_internal will call free_reserved_space, so this is necessary to balance
+            // remove_car_from_queue will call free_reserved_space, so this is necessary to balance
             // that.
             queue.reserved_length += car.vehicle.length + FOLLOWING_DISTANCE;
             ctx.intersections.agent_deleted_mid_turn(AgentID::Car(c), t);
@@ -816,18 +975,23 @@ impl DrivingSimState {
             let queue = self.queues.get_mut(&car.router.next()).unwrap();
             queue.free_reserved_space(&car);
         }
+        self.forget_car(&car, ctx);
+        self.remove_car_from_queue(&mut car, &dists, idx, now, ctx);
+        // remove_car_from_queue cancels UpdateLaggyHead
+        ctx.scheduler.cancel(Command::UpdateCar(c));
+        car.vehicle
+    }
+
+    /// Cleanup for a car being deleted, whether or not it's in a queue
+    fn forget_car(&self, car: &Car, ctx: &mut Ctx) {
+        let c = car.vehicle.id;
         if let Some(Traversable::Turn(t)) = car.router.maybe_next() {
             ctx.intersections.cancel_request(AgentID::Car(c), t);
         }
-
         if car.router.last_step() {
             ctx.parking.unreserve_spot(c);
         }
-
-        self.delete_car_internal(&mut car, dists, idx, now, ctx);
-        // delete_car_internal cancels UpdateLaggyHead
-        ctx.scheduler.cancel(Command::UpdateCar(c));
-        car.vehicle
+        ctx.intersections.vehicle_gone(c);
     }
 
     fn delete_car_internal(
@@ -837,6 +1001,20 @@ impl DrivingSimState {
         idx: usize,
         now: Time,
         ctx: &mut Ctx,
+    ) {
+        ctx.intersections.vehicle_gone(car.vehicle.id);
+        self.remove_car_from_queue(car, &dists, idx, now, ctx);
+    }
+
+    /// Take a car out of its queue, and any queues its back is still in, letting the follower
+    /// proceed.
+    fn remove_car_from_queue(
+        &mut self,
+        car: &mut Car,
+        dists: &[QueueEntry],
+        idx: usize,
+        now: Time,
+        ctx: &mut Ctx,
     ) {
         {
             let queue = self.queues.get_mut(&car.router.head()).unwrap();
@@ -853,8 +1031,6 @@ impl DrivingSimState {
             }
         }
 
-        ctx.intersections.vehicle_gone(car.vehicle.id);
-
         // We might be vanishing while partly clipping into other stuff.
         self.trim_last_steps(car, now, car.last_steps.len(), ctx);
 
@@ -862,7 +1038,45 @@ impl DrivingSimState {
         ctx.scheduler
             .cancel(Command::UpdateLaggyHead(car.vehicle.id));
 
-        self.update_follower(idx, &dists, now, ctx);
+        self.update_follower(idx, dists, now, ctx);
+    }
+
+    /// A transit vehicle finished dwelling at a pull-out stop. If there's a gap in traffic, it
+    /// re-enters the lane and blocks it while merging, then departs normally. Otherwise it keeps
+    /// waiting.
+    fn try_merge_from_pull_out(&mut self, id: CarID, now: Time, ctx: &mut Ctx) {
+        let car = &self.cars[&id];
+        let dist = match car.state {
+            CarState::IdlingAtStop(dist, _) => dist,
+            _ => unreachable!(),
+        };
+        let on = car.router.head();
+        match self.queues[&on].get_idx_to_insert_car(
+            dist,
+            car.vehicle.length,
+            now,
+            &self.cars,
+            &self.queues,
+        ) {
+            Some(idx) => {
+                let car = self.cars.get_mut(&id).unwrap();
+                self.queues
+                    .get_mut(&on)
+                    .unwrap()
+                    .insert_car_at_idx(idx, car);
+                self.pulled_over.remove(&id);
+                car.state = CarState::IdlingAtStop(
+                    dist,
+                    TimeInterval::new(now, now + TIME_TO_MERGE_FROM_PULL_OUT),
+                );
+                ctx.scheduler
+                    .push(car.state.get_end_time(), Command::UpdateCar(id));
+            }
+            None => {
+                ctx.scheduler
+                    .push(now + BLIND_RETRY_TO_MERGE, Command::UpdateCar(id));
+            }
+        }
     }
 
     /// After a leader (maybe an active vehicle, maybe a static blockage) gets out of the way,
@@ -900,7 +1114,12 @@ impl DrivingSimState {
 
                     // Prevent them from jumping forwards.
                     follower.total_blocked_time += now - blocked_since;
-                    follower.state = follower.crossing_state(follower_dist, now, ctx.map);
+                    follower.state = follower.crossing_state(
+                        follower_dist,
+                        now,
+                        ctx.map,
+                        &self.driving_speed_limits,
+                    );
                     ctx.scheduler.update(
                         follower.state.get_end_time(),
                         Command::UpdateCar(follower_id),
@@ -912,7 +1131,12 @@ impl DrivingSimState {
                     // If the follower was still Crossing, they might not've been blocked by the
                     // leader yet. But recalculating their Crossing state isn't necessarily a no-op
                     // -- this could prevent them from suddenly warping past a blockage.
-                    follower.state = follower.crossing_state(follower_dist, now, ctx.map);
+                    follower.state = follower.crossing_state(
+                        follower_dist,
+                        now,
+                        ctx.map,
+                        &self.driving_speed_limits,
+                    );
                     ctx.scheduler.update(
                         follower.state.get_end_time(),
                         Command::UpdateCar(follower_id),
@@ -935,6 +1159,7 @@ impl DrivingSimState {
                         DistanceInterval::new_driving(follower_dist, ctx.map.get_l(to).length()),
                         now,
                         ctx.map,
+                        &self.driving_speed_limits,
                     ) {
                         CarState::Crossing {
                             time_int, dist_int, ..
@@ -1006,6 +1231,7 @@ impl DrivingSimState {
                     ),
                     now,
                     ctx.map,
+                    &self.driving_speed_limits,
                 )
                 .get_end_time();
             // Sometimes due to rounding, retry_at will be exactly time, but we really need to
@@ -1079,6 +1305,7 @@ impl DrivingSimState {
                                             &self.queues,
                                             ctx.map,
                                             self.handle_uber_turns,
+                                            now,
                                         );
                                     }
                                     ctx.scheduler
@@ -1150,6 +1377,9 @@ impl DrivingSimState {
             {
                 continue;
             }
+            if self.queues[&Traversable::Lane(target_lane.id)].draining {
+                continue;
+            }
             return Some(target_lane.id);
         }
 
@@ -1186,6 +1416,7 @@ impl DrivingSimState {
             DistanceInterval::new_driving(front_target_queue, ctx.map.get_l(target_lane).length()),
             now,
             ctx.map,
+            &self.driving_speed_limits,
         ) {
             CarState::Crossing {
                 time_int, dist_int, ..
@@ -1289,6 +1520,111 @@ impl DrivingSimState {
         for key in new_queues {
             self.queues.insert(key, Queue::new(key, map));
         }
+
+        // Any closed reversible lanes are open again, until the schedule is checked again
+        for queue in self.queues.values_mut() {
+            queue.draining = false;
+        }
+
+        self.on_ramps = find_on_ramps(map);
+        self.update_driving_speed_limits(map);
+    }
+
+    /// The map has reversible lanes pointing the way they were edited. While their schedule
+    /// points them the other way, close them: vehicles already on them finish, but nobody new
+    /// enters. Returns when the next lane changes direction.
+    pub fn update_reversible_lanes(&mut self, now: Time, map: &Map) -> Option<Time> {
+        let mut next_switch: Option<Time> = None;
+        for r in map.all_roads() {
+            for (idx, schedule) in &r.reversible_lanes {
+                if let Some(t) = schedule.next_switch_after(now) {
+                    next_switch = Some(next_switch.map(|x| x.min(t)).unwrap_or(t));
+                }
+                let lane = &r.lanes[*idx];
+                if let Some(queue) = self.queues.get_mut(&Traversable::Lane(lane.id)) {
+                    queue.draining = schedule
+                        .direction_at(now)
+                        .map(|dir| dir != lane.dir)
+                        .unwrap_or(false);
+                }
+            }
+        }
+        next_switch
+    }
+
+    /// Is this reversible lane closed, because its schedule points it the other way right now?
+    pub fn is_lane_closed(&self, l: LaneID) -> bool {
+        self.queues
+            .get(&Traversable::Lane(l))
+            .map(|q| q.draining)
+            .unwrap_or(false)
+    }
+
+    /// Managed motorways change their speed limit by time of day or when they get congested.
+    /// Vehicles use the new limit starting with the next lane or turn they enter. Returns when to
+    /// check again.
+    pub fn update_variable_speed_limits(&mut self, now: Time, map: &Map) -> Option<Time> {
+        self.posted_speed_limits.clear();
+        let mut any = false;
+        for r in map.all_roads() {
+            let control = match r.variable_speed_limit {
+                Some(ref control) => control,
+                None => continue,
+            };
+            any = true;
+            let density = vehicle_density(
+                &self.queues,
+                r.lanes
+                    .iter()
+                    .filter(|l| l.is_driving() || l.is_bus())
+                    .map(|l| l.id),
+            );
+            let congested = control.is_congested(density, self.congested_roads.contains(&r.id));
+            if congested {
+                self.congested_roads.insert(r.id);
+            } else {
+                self.congested_roads.remove(&r.id);
+            }
+
+            let limit = control.limit_at(now, r.speed_limit, congested);
+            if limit != r.speed_limit {
+                self.posted_speed_limits.insert(r.id, limit);
+            }
+        }
+        // Forget about roads whose control was removed by an edit
+        self.congested_roads
+            .retain(|r| map.get_r(*r).variable_speed_limit.is_some());
+        self.update_driving_speed_limits(map);
+
+        if any {
+            Some(now + VARIABLE_SPEED_LIMIT_UPDATE)
+        } else {
+            None
+        }
+    }
+
+    fn update_driving_speed_limits(&mut self, map: &Map) {
+        self.driving_speed_limits = self.posted_speed_limits.clone();
+        for (ramp, motorway) in &self.on_ramps {
+            let limit = |r: &RoadID| {
+                self.posted_speed_limits
+                    .get(r)
+                    .copied()
+                    .unwrap_or(map.get_r(*r).speed_limit)
+            };
+            // The on-ramp acts as an acceleration lane. Speeding up steadily from the ramp's limit
+            // to the motorway's, the average is halfway between them.
+            let (from, to) = (limit(ramp), limit(motorway));
+            if to > from {
+                self.driving_speed_limits
+                    .insert(*ramp, from + (to - from) * 0.5);
+            }
+        }
+    }
+
+    /// The limit a managed motorway currently posts, if it's different than the road's normal one
+    pub fn posted_speed_limit(&self, r: RoadID) -> Option<Speed> {
+        self.posted_speed_limits.get(&r).copied()
     }
 
     fn new_crossing_state(&self, ctx: &mut Ctx, car: &Car) {
@@ -1306,6 +1642,64 @@ impl DrivingSimState {
 
 // Queries
 impl DrivingSimState {
+    /// Vehicles still waiting off-map to enter at each border, and how long they've waited so far
+    pub fn border_queues(
+        &self,
+        now: Time,
+        map: &Map,
+    ) -> BTreeMap<IntersectionID, Vec<(CarID, Duration)>> {
+        let mut queues = BTreeMap::new();
+        for (id, first_try) in &self.border_queue {
+            if let Some((pos, _)) = self.waiting_to_spawn.get(id) {
+                queues
+                    .entry(map.get_l(pos.lane()).src_i)
+                    .or_insert_with(Vec::new)
+                    .push((*id, now - *first_try));
+            }
+        }
+        queues
+    }
+
+    /// Can somebody cross the road at the same spot as this position, without any vehicle reaching
+    /// that spot before they're done?
+    pub fn gap_to_cross_road(
+        &self,
+        now: Time,
+        pos: Position,
+        time_to_cross: Duration,
+        map: &Map,
+    ) -> bool {
+        let road = map.get_parent(pos.lane());
+        let limit = self
+            .posted_speed_limits
+            .get(&road.id)
+            .copied()
+            .unwrap_or(road.speed_limit);
+        let reach = limit * time_to_cross;
+        for lane in &road.lanes {
+            let queue = match self.queues.get(&Traversable::Lane(lane.id)) {
+                Some(q) => q,
+                None => continue,
+            };
+            if queue.get_active_cars().is_empty() {
+                continue;
+            }
+            let dist = pos.equiv_pos(lane.id, map).dist_along();
+            if queue
+                .get_car_positions(now, &self.cars, &self.queues)
+                .into_iter()
+                .any(|entry| {
+                    matches!(entry.member, Queued::Vehicle(_))
+                        && entry.back <= dist
+                        && entry.front + reach >= dist
+                })
+            {
+                return false;
+            }
+        }
+        true
+    }
+
     /// Note the ordering of results is non-deterministic!
     pub fn get_unzoomed_agents(&self, now: Time, map: &Map) -> Vec<UnzoomedAgent> {
         let mut result = Vec::new();
@@ -1342,6 +1736,20 @@ impl DrivingSimState {
                 parking: false,
             });
         }
+        for id in &self.pulled_over {
+            let car = &self.cars[id];
+            result.push(UnzoomedAgent {
+                id: AgentID::Car(*id),
+                pos: car
+                    .router
+                    .head()
+                    .get_polyline(map)
+                    .must_dist_along(self.get_car_front(now, car))
+                    .0,
+                person: None,
+                parking: false,
+            });
+        }
 
         result
     }
@@ -1377,6 +1785,9 @@ impl DrivingSimState {
                     }),
             );
         }
+        for id in &self.pulled_over {
+            result.push(self.get_draw_pulled_over(*id, now, map, transit));
+        }
         result
     }
 
@@ -1400,6 +1811,22 @@ impl DrivingSimState {
         on: Traversable,
         map: &Map,
         transit: &TransitSimState,
+    ) -> Vec<DrawCarInput> {
+        let mut result = self.get_draw_queued_cars_on(now, on, map, transit);
+        for id in &self.pulled_over {
+            if self.cars[id].router.head() == on {
+                result.push(self.get_draw_pulled_over(*id, now, map, transit));
+            }
+        }
+        result
+    }
+
+    fn get_draw_queued_cars_on(
+        &self,
+        now: Time,
+        on: Traversable,
+        map: &Map,
+        transit: &TransitSimState,
     ) -> Vec<DrawCarInput> {
         match self.queues.get(&on) {
             Some(q) => q
@@ -1452,6 +1879,27 @@ impl DrivingSimState {
         }
     }
 
+    /// Pulled-over vehicles are drawn beside their lane, towards the curb.
+    fn get_draw_pulled_over(
+        &self,
+        id: CarID,
+        now: Time,
+        map: &Map,
+        transit: &TransitSimState,
+    ) -> DrawCarInput {
+        let car = &self.cars[&id];
+        let mut draw = car.get_draw_car(self.get_car_front(now, car), now, map, transit);
+        let width = map.get_l(car.router.head().as_lane()).width;
+        let shifted = match map.get_config().driving_side {
+            DrivingSide::Right => draw.body.shift_right(width),
+            DrivingSide::Left => draw.body.shift_left(width),
+        };
+        if let Ok(body) = shifted {
+            draw.body = body;
+        }
+        draw
+    }
+
     pub fn debug_car_json(&self, id: CarID) -> String {
         if let Some(ref car) = self.cars.get(&id) {
             abstutil::to_json(car)
@@ -1474,6 +1922,31 @@ impl DrivingSimState {
         }
     }
 
+    pub fn agent_state(&self, id: CarID) -> Option<AgentState> {
+        if self.waiting_to_spawn.contains_key(&id) {
+            return Some(AgentState::WaitingToSpawn);
+        }
+        let car = self.cars.get(&id)?;
+        Some(match car.state {
+            CarState::Crossing { .. } => {
+                if car.router.is_parking() {
+                    AgentState::LookingForParking
+                } else {
+                    AgentState::Moving(car.router.head())
+                }
+            }
+            CarState::ChangingLanes { from, to, .. } => AgentState::ChangingLanes { from, to },
+            CarState::Queued { .. } => AgentState::Queued(car.router.head()),
+            CarState::WaitingToAdvance { .. } => match car.router.maybe_next() {
+                Some(Traversable::Turn(t)) => AgentState::WaitingForTurn(t),
+                _ => AgentState::Queued(car.router.head()),
+            },
+            CarState::Unparking { .. } => AgentState::Unparking,
+            CarState::Parking(_, _, _) => AgentState::Parking,
+            CarState::IdlingAtStop(_, _) => AgentState::AtTransitStop,
+        })
+    }
+
     pub fn agent_properties(&self, id: CarID, now: Time) -> AgentProperties {
         if let Some(car) = self.cars.get(&id) {
             let path = car.router.get_path();
@@ -1499,13 +1972,17 @@ impl DrivingSimState {
         } else {
             for car in self.waiting_to_spawn.keys() {
                 if id == *car {
-                    // If the vehicle is waiting to spawn, we don't have any stats on them yet.  We
-                    // could track when they originally tried to spawn and use for a few of these
-                    // fields, but we should also make sure that delay gets recorded later.
+                    // If the vehicle is waiting to spawn, we don't have any stats on them yet,
+                    // except for how long they've been queued at a border.
+                    let waiting = self
+                        .border_queue
+                        .get(&id)
+                        .map(|first_try| now - *first_try)
+                        .unwrap_or(Duration::ZERO);
                     return AgentProperties {
-                        total_time: Duration::ZERO,
-                        waiting_here: Duration::ZERO,
-                        total_waiting: Duration::ZERO,
+                        total_time: waiting,
+                        waiting_here: waiting,
+                        total_waiting: waiting,
                         dist_crossed: Distance::ZERO,
                         total_dist: Distance::ZERO,
                     };
@@ -1601,6 +2078,14 @@ impl DrivingSimState {
         }
     }
 
+    /// How many cars are on a lane right now, whether they're moving or queued
+    pub fn num_cars_on_lane(&self, l: LaneID) -> usize {
+        self.queues
+            .get(&Traversable::Lane(l))
+            .map(|q| q.get_active_cars().len())
+            .unwrap_or(0)
+    }
+
     pub fn debug_queue_lengths(&self, l: LaneID) -> Option<(Distance, Distance)> {
         let queue = self.queues.get(&Traversable::Lane(l))?;
         Some((queue.reserved_length, queue.geom_len))
@@ -1646,7 +2131,52 @@ impl DrivingSimState {
         graph
     }
 
+    /// Like `get_blocked_by_graph`, but only for one agent. Also returns the conflicting turn of
+    /// whoever is blocking them, if that's the reason.
+    pub fn get_blocked_by(
+        &self,
+        now: Time,
+        map: &Map,
+        intersections: &IntersectionSimState,
+        agent: AgentID,
+    ) -> Option<(Duration, DelayCause, Option<TurnID>)> {
+        // Waiting at an intersection takes precedence, just like in the full graph
+        if let Some(result) =
+            intersections.get_blocked_by(now, agent, map, &self.cars, &self.queues)
+        {
+            return Some(result);
+        }
+
+        let car = match agent {
+            AgentID::Car(car) => self.cars.get(&car)?,
+            _ => {
+                return None;
+            }
+        };
+        let cars = self.queues.get(&car.router.head())?.get_active_cars();
+        match cars.iter().position(|c| *c == car.vehicle.id)? {
+            0 => {
+                let head = self.queues[&car.router.head()].laggy_head?;
+                Some((
+                    self.cars[&head].state.time_spent_waiting(now),
+                    DelayCause::Agent(AgentID::Car(head)),
+                    None,
+                ))
+            }
+            idx => Some((
+                car.state.time_spent_waiting(now),
+                DelayCause::Agent(AgentID::Car(cars[idx - 1])),
+                None,
+            )),
+        }
+    }
+
     fn get_car_front(&self, now: Time, car: &Car) -> Distance {
+        if self.pulled_over.contains(&car.vehicle.id) {
+            if let CarState::IdlingAtStop(dist, _) = car.state {
+                return dist;
+            }
+        }
         self.queues[&car.router.head()]
             .get_car_positions(now, &self.cars, &self.queues)
             .into_iter()
@@ -1694,6 +2224,27 @@ impl DrivingSimState {
     }
 }
 
+/// Is this vehicle appearing at the very start of a lane leading away from a border?
+fn entering_at_border(params: &CreateCar, map: &Map) -> Option<IntersectionID> {
+    if params.maybe_parked_car.is_some() {
+        return None;
+    }
+    let start = params.router.get_path().get_req().start;
+    let lane = map.get_l(start.lane());
+    if start.dist_along() == Distance::ZERO && map.get_i(lane.src_i).is_border() {
+        Some(lane.src_i)
+    } else {
+        None
+    }
+}
+
+fn find_on_ramps(map: &Map) -> BTreeMap<RoadID, RoadID> {
+    map.all_roads()
+        .iter()
+        .filter_map(|r| r.on_ramp_joins(map).map(|motorway| (r.id, motorway)))
+        .collect()
+}
+
 // This implementation relies on the fact that car IDs are unique just by their number. Vehicle
 // type is also in there, but during lookup, it'll be ignored!
 impl IndexableKey for CarID {
@@ -1,22 +1,29 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, prettyprint_usize, serialize_btreemap, FixedMap};
 use geom::{Duration, Time};
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, LaneID, Map, StageType,
-    Traversable, TurnID, TurnPriority, TurnType, UberTurn,
+    osm, ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, LaneID, Map, RoadID,
+    StageType, Traversable, TurnID, TurnPriority, TurnType, UberTurn,
 };
 
 use crate::mechanics::car::{Car, CarState};
-use crate::mechanics::Queue;
+use crate::mechanics::{vehicle_density, Queue};
 use crate::{
-    AgentID, AlertLocation, CarID, Command, DelayCause, Event, Scheduler, SimOptions, Speed,
+    AgentID, AlertCategory, AlertLocation, CarID, Command, DelayCause, Event, Problem, Scheduler,
+    SimOptions, Speed, VehicleType,
 };
 
 const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
 const WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL: Duration = Duration::const_seconds(0.2);
+/// When a vehicle and pedestrian start conflicting turns within this long of the other finishing,
+/// it's a near-miss. Studies of post-encroachment time usually treat anything under 1.5s as a
+/// serious conflict.
+const NEAR_MISS_THRESHOLD: Duration = Duration::const_seconds(1.5);
+/// A vehicle reaching a crossing this soon after a pedestrian stepped into it has to brake hard.
+const HARD_BRAKING_THRESHOLD: Duration = Duration::const_seconds(2.0);
 
 /// Manages conflicts at intersections. When an agent has reached the end of a lane, they call
 /// maybe_start_turn to make a Request. Based on the intersection type (stop sign, traffic signal,
@@ -34,6 +41,15 @@ pub(crate) struct IntersectionSimState {
     break_turn_conflict_cycles: bool,
     handle_uber_turns: bool,
     disable_turn_conflicts: bool,
+    rolling_stop_pct: usize,
+    stop_sign_violation_pct: usize,
+    // Requests that would cut off somebody who reached a stop sign first. If the turn starts, the
+    // violation is reported.
+    stop_sign_violations: BTreeSet<Request>,
+    // Problems detected here, waiting for the agent's trip to be charged with them
+    new_problems: BTreeMap<AgentID, Vec<Problem>>,
+    // The last time each ramp meter, keyed by the on-ramp, let a vehicle through
+    ramp_meter_released: BTreeMap<RoadID, Time>,
     // (x, y) means x is blocked by y. It's a many-to-many relationship. TODO Better data
     // structure.
     blocked_by: BTreeSet<(CarID, CarID)>,
@@ -78,6 +94,17 @@ struct State {
     )]
     leader_eta: BTreeMap<LaneID, (Request, Time)>,
 
+    // Turns from an on-ramp onto a motorway, which zipper merge instead of following the stop
+    // sign. Empty if this isn't a motorway merge.
+    merging_turns: BTreeSet<TurnID>,
+    // At motorway merges, the lane that the last vehicle merging into each lane came from
+    last_merged_from: BTreeMap<LaneID, LaneID>,
+
+    // For surrogate safety measures, the turns started and finished recently. Older entries are
+    // dropped.
+    recently_started: VecDeque<(Request, Time)>,
+    recently_finished: VecDeque<(Request, Time)>,
+
     signal: Option<SignalState>,
 }
 
@@ -97,6 +124,16 @@ struct Request {
     turn: TurnID,
 }
 
+/// How a driver treats a stop sign
+#[derive(Clone, Copy, PartialEq)]
+enum StopCompliance {
+    FullStop,
+    /// Only stop fully if somebody else got there first
+    RollingStop,
+    /// Never stop
+    Violation,
+}
+
 // Mutations
 impl IntersectionSimState {
     pub fn new(map: &Map, scheduler: &mut Scheduler, opts: &SimOptions) -> IntersectionSimState {
@@ -107,6 +144,11 @@ impl IntersectionSimState {
             break_turn_conflict_cycles: !opts.dont_break_turn_conflict_cycles,
             handle_uber_turns: !opts.dont_handle_uber_turns,
             disable_turn_conflicts: opts.disable_turn_conflicts,
+            rolling_stop_pct: opts.rolling_stop_pct,
+            stop_sign_violation_pct: opts.stop_sign_violation_pct,
+            stop_sign_violations: BTreeSet::new(),
+            new_problems: BTreeMap::new(),
+            ramp_meter_released: BTreeMap::new(),
             blocked_by: BTreeSet::new(),
             events: Vec::new(),
 
@@ -125,6 +167,10 @@ impl IntersectionSimState {
                 waiting: BTreeMap::new(),
                 reserved: BTreeSet::new(),
                 uber_turn_neighbors: Vec::new(),
+                merging_turns: motorway_merging_turns(i, map),
+                last_merged_from: BTreeMap::new(),
+                recently_started: VecDeque::new(),
+                recently_finished: VecDeque::new(),
                 signal: None,
                 leader_eta: BTreeMap::new(),
             };
@@ -151,6 +197,12 @@ impl IntersectionSimState {
     ) {
         let state = self.state.get_mut(&turn.parent).unwrap();
         assert!(state.accepted.remove(&Request { agent, turn }));
+        if map.get_t(turn).turn_type != TurnType::SharedSidewalkCorner {
+            forget_older_than(&mut state.recently_finished, now, NEAR_MISS_THRESHOLD);
+            state
+                .recently_finished
+                .push_back((Request { agent, turn }, now));
+        }
 
         state.reserved.remove(&Request { agent, turn });
         if !handling_live_edits && map.get_t(turn).turn_type != TurnType::SharedSidewalkCorner {
@@ -175,6 +227,8 @@ impl IntersectionSimState {
     pub fn cancel_request(&mut self, agent: AgentID, turn: TurnID) {
         let state = self.state.get_mut(&turn.parent).unwrap();
         state.waiting.remove(&Request { agent, turn });
+        self.stop_sign_violations.remove(&Request { agent, turn });
+        self.new_problems.remove(&agent);
         if self.break_turn_conflict_cycles {
             if let AgentID::Car(car) = agent {
                 self.blocked_by.retain(|(c1, c2)| *c1 != car && *c2 != car);
@@ -192,9 +246,16 @@ impl IntersectionSimState {
         self.wakeup_waiting(now, i, scheduler, map);
     }
 
+    /// After a turn starts, returns any problems the agent encountered getting there, like running
+    /// a stop sign or a near-miss.
+    pub fn take_problems(&mut self, agent: AgentID) -> Vec<Problem> {
+        self.new_problems.remove(&agent).unwrap_or_default()
+    }
+
     /// Vanished at border, stopped biking, etc -- a vehicle disappeared, and didn't have one last
     /// turn.
     pub fn vehicle_gone(&mut self, car: CarID) {
+        self.new_problems.remove(&AgentID::Car(car));
         self.blocked_by.retain(|(c1, c2)| *c1 != car && *c2 != car);
     }
 
@@ -232,11 +293,10 @@ impl IntersectionSimState {
             }
         } else if let Some(signal) = map.maybe_get_traffic_signal(i) {
             let current_stage = self.state[&i].signal.as_ref().unwrap().current_stage;
-            let stage = &signal.stages[current_stage];
             let reserved = &self.state[&i].reserved;
             let i = map.get_i(i);
             for (req, _, _) in all {
-                match stage.get_priority_of_turn(req.turn, i) {
+                match signal.get_priority_of_turn(current_stage, req.turn, req.agent.is_bike(), i) {
                     TurnPriority::Protected => {
                         protected.push(req);
                     }
@@ -328,7 +388,8 @@ impl IntersectionSimState {
         let duration: Duration;
         // Switch to a new stage?
         assert_eq!(now, signal_state.stage_ends_at);
-        let old_stage = &signal.stages[signal_state.current_stage];
+        let old_idx = signal_state.current_stage;
+        let old_stage = &signal.stages[old_idx];
         match old_stage.stage_type {
             StageType::Fixed(_) => {
                 duration = advance(signal_state, signal, i, !ped_waiting);
@@ -344,6 +405,7 @@ impl IntersectionSimState {
                 {
                     self.events.push(Event::Alert(
                         AlertLocation::Intersection(id),
+                        AlertCategory::SignalTiming,
                         format!(
                             "exhausted a variable stage {},{},{},{}",
                             min, delay, additional, signal_state.extensions_count
@@ -357,7 +419,8 @@ impl IntersectionSimState {
                     }
                     // Should we only allow protected to extend or any not banned?
                     // currently only the protected demand control extended.
-                    old_stage.get_priority_of_turn(req.turn, i) != TurnPriority::Protected
+                    signal.get_priority_of_turn(old_idx, req.turn, req.agent.is_bike(), i)
+                        != TurnPriority::Protected
                 }) {
                     signal_state.extensions_count = 0;
                     duration = advance(signal_state, signal, i, !ped_waiting);
@@ -366,6 +429,7 @@ impl IntersectionSimState {
                     duration = delay;
                     self.events.push(Event::Alert(
                         AlertLocation::Intersection(id),
+                        AlertCategory::SignalTiming,
                         format!(
                             "Extending a variable stage {},{},{},{}",
                             min, delay, additional, signal_state.extensions_count
@@ -435,6 +499,8 @@ impl IntersectionSimState {
 
         if repeat_request {
             self.total_repeat_requests += 1;
+        } else if !agent.is_pedestrian() {
+            self.check_hard_braking(&req, now, map);
         }
 
         let shared_sidewalk_corner =
@@ -464,15 +530,30 @@ impl IntersectionSimState {
                 if !self.traffic_signal_policy(&req, map, signal, speed, now, None) && false {
                     self.events.push(Event::Alert(
                         AlertLocation::Intersection(req.turn.parent),
+                        AlertCategory::SignalTiming,
                         format!("Running a red light inside an uber-turn: {:?}", req),
                     ));
                 }
             }
 
             true
+        } else if readonly_pair
+            .map(|(_, queues)| queues[&Traversable::Lane(turn.dst)].draining)
+            .unwrap_or(false)
+        {
+            // The target is a reversible lane, closed for now
+            false
+        } else if !self.ramp_meter_allows(&req, now, map, readonly_pair.map(|(_, q)| q), scheduler)
+        {
+            // Waiting for the ramp meter to turn green
+            false
         } else if self.use_freeform_policy_everywhere {
             // If we made it this far, we don't conflict with an accepted turn
             true
+        } else if let Some(allowed) =
+            self.merge_policy(&req, now, map, readonly_pair.map(|(_, q)| q))
+        {
+            allowed
         } else if let Some(signal) = map.maybe_get_traffic_signal(turn.parent) {
             self.traffic_signal_policy(&req, map, signal, speed, now, Some(scheduler))
         } else if let Some(sign) = map.maybe_get_stop_sign(turn.parent) {
@@ -584,16 +665,88 @@ impl IntersectionSimState {
         // TODO For now, we're only interested in signals, and there's too much raw data to store
         // for stop signs too.
         let state = self.state.get_mut(&turn.parent).unwrap();
-        state.waiting.remove(&req).unwrap();
-        state.accepted.insert(req);
+        let (waiting_since, _) = state.waiting.remove(&req).unwrap();
+        state.accepted.insert(req.clone());
+        if !agent.is_pedestrian() && !state.merging_turns.is_empty() {
+            // Remember vehicles continuing along the motorway too, so the on-ramp gets a turn
+            state.last_merged_from.insert(turn.dst, turn.src);
+        }
         if self.break_turn_conflict_cycles {
             if let AgentID::Car(car) = agent {
                 self.blocked_by.retain(|(c, _)| *c != car);
             }
         }
+        if self.stop_sign_violations.remove(&req) {
+            self.new_problems
+                .entry(agent)
+                .or_insert_with(Vec::new)
+                .push(Problem::StopSignViolation(turn));
+        }
+        let src = map.get_parent(turn.src);
+        if src.ramp_meter.is_some() && !agent.is_pedestrian() {
+            self.ramp_meter_released.insert(src.id, now);
+            self.events
+                .push(Event::RampMeterReleased(src.id, now - waiting_since));
+        }
+        self.check_near_miss(req, now, map);
         true
     }
 
+    /// If a vehicle arrives at a crossing that a pedestrian just started, it has to stop suddenly.
+    /// This is a rough proxy; the vehicle may have been creeping forward in a queue.
+    fn check_hard_braking(&mut self, req: &Request, now: Time, map: &Map) {
+        let our_turn = map.get_t(req.turn);
+        if our_turn.turn_type == TurnType::SharedSidewalkCorner {
+            return;
+        }
+        let state = self.state.get_mut(&req.turn.parent).unwrap();
+        forget_older_than(&mut state.recently_started, now, HARD_BRAKING_THRESHOLD);
+        if let Some((ped, _)) = state.recently_started.iter().find(|(other, _)| {
+            other.agent.is_pedestrian()
+                && state.accepted.contains(other)
+                && our_turn.conflicts_with(map.get_t(other.turn))
+        }) {
+            self.new_problems
+                .entry(req.agent)
+                .or_insert_with(Vec::new)
+                .push(Problem::HardBraking(ped.turn));
+        }
+    }
+
+    /// Measure the post-encroachment time between a pedestrian and vehicle using conflicting
+    /// turns: how long after one finished did the other start?
+    fn check_near_miss(&mut self, req: Request, now: Time, map: &Map) {
+        let our_turn = map.get_t(req.turn);
+        if our_turn.turn_type == TurnType::SharedSidewalkCorner {
+            return;
+        }
+        let state = self.state.get_mut(&req.turn.parent).unwrap();
+        forget_older_than(&mut state.recently_finished, now, NEAR_MISS_THRESHOLD);
+        let closest = state
+            .recently_finished
+            .iter()
+            .filter(|(other, _)| {
+                other.agent.is_pedestrian() != req.agent.is_pedestrian()
+                    && our_turn.conflicts_with(map.get_t(other.turn))
+            })
+            .max_by_key(|(_, finished)| *finished);
+        if let Some((other, finished)) = closest {
+            // Always blame the crossing
+            let crossing = if req.agent.is_pedestrian() {
+                req.turn
+            } else {
+                other.turn
+            };
+            self.new_problems
+                .entry(req.agent)
+                .or_insert_with(Vec::new)
+                .push(Problem::NearMiss(crossing, now - *finished));
+        }
+
+        forget_older_than(&mut state.recently_started, now, HARD_BRAKING_THRESHOLD);
+        state.recently_started.push_back((req, now));
+    }
+
     pub fn collect_events(&mut self) -> Vec<Event> {
         std::mem::take(&mut self.events)
     }
@@ -640,7 +793,12 @@ impl IntersectionSimState {
         }
     }
 
-    pub fn handle_live_edits(&self, map: &Map) {
+    pub fn handle_live_edits(&mut self, map: &Map) {
+        // Edits can change lanes or intersection control at a motorway merge
+        for state in self.state.values_mut() {
+            state.merging_turns = motorway_merging_turns(map.get_i(state.id), map);
+        }
+
         // Just sanity check that we don't have any references to deleted turns
         let mut errors = Vec::new();
         for state in self.state.values() {
@@ -786,42 +944,75 @@ impl IntersectionSimState {
         cars: &FixedMap<CarID, Car>,
         queues: &HashMap<Traversable, Queue>,
     ) {
+        for state in self.state.values() {
+            for req in state.waiting.keys() {
+                let (waiting, cause, _) = self.explain_request(state, req, now, map, cars, queues);
+                graph.insert(req.agent, (waiting, cause));
+            }
+        }
+    }
+
+    /// Like `populate_blocked_by`, but only for one agent waiting at some intersection. Also
+    /// returns the conflicting turn of whoever is blocking them, if that's the reason.
+    pub fn get_blocked_by(
+        &self,
+        now: Time,
+        agent: AgentID,
+        map: &Map,
+        cars: &FixedMap<CarID, Car>,
+        queues: &HashMap<Traversable, Queue>,
+    ) -> Option<(Duration, DelayCause, Option<TurnID>)> {
+        for state in self.state.values() {
+            if let Some(req) = state.waiting.keys().find(|req| req.agent == agent) {
+                return Some(self.explain_request(state, req, now, map, cars, queues));
+            }
+        }
+        None
+    }
+
+    fn explain_request(
+        &self,
+        state: &State,
+        req: &Request,
+        now: Time,
+        map: &Map,
+        cars: &FixedMap<CarID, Car>,
+        queues: &HashMap<Traversable, Queue>,
+    ) -> (Duration, DelayCause, Option<TurnID>) {
         // Don't use self.blocked_by -- that gets complicated with uber-turns and such.
         //
         // This also assumes default values for handle_uber_turns, disable_turn_conflicts, etc!
-        for state in self.state.values() {
-            for (req, (started_at, _)) in &state.waiting {
-                let turn = map.get_t(req.turn);
-                // In the absence of other explanations, the agent must be pausing at a stop sign
-                // or before making an unprotected movement, aka, in the middle of
-                // WAIT_AT_STOP_SIGN or WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL. Or they're waiting for
-                // a signal to change.
-                let mut cause = DelayCause::Intersection(state.id);
-                if let Some(other) = state.accepted.iter().find(|other| {
-                    turn.conflicts_with(map.get_t(other.turn)) || turn.id == other.turn
-                }) {
-                    cause = DelayCause::Agent(other.agent);
-                } else if let AgentID::Car(car) = req.agent {
-                    let queue = &queues[&Traversable::Lane(req.turn.dst)];
-                    let car = cars.get(&car).unwrap();
-                    if !queue.room_for_car(car) {
-                        // TODO Or it's reserved due to an uber turn or something
-                        let blocker = queue
-                            .get_active_cars()
-                            .last()
-                            .cloned()
-                            .or(queue.laggy_head)
-                            .unwrap();
-                        cause = DelayCause::Agent(AgentID::Car(blocker));
-                    } else if let Some(ut) = car.router.get_path().about_to_start_ut() {
-                        if let Some(blocker) = self.check_for_conflicts_before_uber_turn(ut, map) {
-                            cause = DelayCause::Agent(blocker);
-                        }
-                    }
+        let waiting = now - state.waiting[req].0;
+        let turn = map.get_t(req.turn);
+        if let Some(other) = state
+            .accepted
+            .iter()
+            .find(|other| turn.conflicts_with(map.get_t(other.turn)) || turn.id == other.turn)
+        {
+            return (waiting, DelayCause::Agent(other.agent), Some(other.turn));
+        }
+        if let AgentID::Car(car) = req.agent {
+            let queue = &queues[&Traversable::Lane(req.turn.dst)];
+            let car = cars.get(&car).unwrap();
+            if !queue.room_for_car(car) {
+                // TODO Or it's reserved due to an uber turn or something
+                let blocker = queue
+                    .get_active_cars()
+                    .last()
+                    .cloned()
+                    .or(queue.laggy_head)
+                    .unwrap();
+                return (waiting, DelayCause::Agent(AgentID::Car(blocker)), None);
+            } else if let Some(ut) = car.router.get_path().about_to_start_ut() {
+                if let Some(blocker) = self.check_for_conflicts_before_uber_turn(ut, map) {
+                    return (waiting, DelayCause::Agent(blocker), None);
                 }
-                graph.insert(req.agent, (now - *started_at, cause));
             }
         }
+        // In the absence of other explanations, the agent must be pausing at a stop sign or before
+        // making an unprotected movement, aka, in the middle of WAIT_AT_STOP_SIGN or
+        // WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL. Or they're waiting for a signal to change.
+        (waiting, DelayCause::Intersection(state.id), None)
     }
 
     /// See if any agent is currently performing a turn that conflicts with an uber-turn. Doesn't
@@ -842,6 +1033,88 @@ impl IntersectionSimState {
 
 // Stuff to support maybe_start_turn
 impl IntersectionSimState {
+    /// Ramp meters let one vehicle at a time onto the motorway. If it's too soon after the last
+    /// one, wake up the agent when the meter turns green again.
+    fn ramp_meter_allows(
+        &self,
+        req: &Request,
+        now: Time,
+        map: &Map,
+        queues: Option<&HashMap<Traversable, Queue>>,
+        scheduler: &mut Scheduler,
+    ) -> bool {
+        match self.ramp_meter_green_at(req, map, queues) {
+            Some(green_at) if now < green_at => {
+                scheduler.update(green_at, Command::update_agent(req.agent));
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// When does the ramp meter in front of this request next let a vehicle through? None if
+    /// there's no meter, or it hasn't released anybody yet.
+    fn ramp_meter_green_at(
+        &self,
+        req: &Request,
+        map: &Map,
+        queues: Option<&HashMap<Traversable, Queue>>,
+    ) -> Option<Time> {
+        if req.agent.is_pedestrian() {
+            return None;
+        }
+        let ramp = map.get_parent(req.turn.src);
+        let meter = ramp.ramp_meter.as_ref()?;
+        let last_release = *self.ramp_meter_released.get(&ramp.id)?;
+
+        // Measure the motorway lanes just past the ramp
+        let dir = map.get_l(req.turn.dst).dir;
+        let density = queues
+            .map(|queues| {
+                vehicle_density(
+                    queues,
+                    map.get_parent(req.turn.dst)
+                        .lanes
+                        .iter()
+                        .filter(|l| l.dir == dir && l.is_driving())
+                        .map(|l| l.id),
+                )
+            })
+            .unwrap_or(0.0);
+        Some(last_release + meter.cycle_for(density))
+    }
+
+    /// Where an on-ramp joins a motorway, vehicles on the ramp zipper merge. They don't stop
+    /// first, but when somebody else wants the same lane, they take turns. Returns None for any
+    /// other movement, which the intersection's usual control handles.
+    fn merge_policy(
+        &self,
+        req: &Request,
+        now: Time,
+        map: &Map,
+        queues: Option<&HashMap<Traversable, Queue>>,
+    ) -> Option<bool> {
+        let state = &self.state[&req.turn.parent];
+        if !state.merging_turns.contains(&req.turn) {
+            return None;
+        }
+        if state.last_merged_from.get(&req.turn.dst) != Some(&req.turn.src) {
+            return Some(true);
+        }
+        // Somebody from our lane merged last. If anybody else is ready to go into the same lane,
+        // it's their turn. When they finish, everybody waiting gets woken up.
+        let someone_else_ready = state.waiting.keys().any(|other| {
+            other.turn.dst == req.turn.dst
+                && other.turn.src != req.turn.src
+                && !other.agent.is_pedestrian()
+                && self
+                    .ramp_meter_green_at(other, map, queues)
+                    .map(|t| t <= now)
+                    .unwrap_or(true)
+        });
+        Some(!someone_else_ready)
+    }
+
     fn stop_sign_policy(
         &mut self,
         req: &Request,
@@ -855,14 +1128,30 @@ impl IntersectionSimState {
         assert!(our_priority != TurnPriority::Banned);
         let (our_time, _) = self.state[&req.turn.parent].waiting[req];
 
-        if our_priority == TurnPriority::Yield && now < our_time + WAIT_AT_STOP_SIGN {
-            // Since we have "ownership" of scheduling for req.agent, don't need to use
-            // scheduler.update.
-            scheduler.push(
-                our_time + WAIT_AT_STOP_SIGN,
-                Command::update_agent(req.agent),
-            );
-            return false;
+        self.stop_sign_violations.remove(req);
+        if our_priority == TurnPriority::Yield {
+            let wait = match self.stop_compliance(req.agent) {
+                StopCompliance::FullStop => WAIT_AT_STOP_SIGN,
+                StopCompliance::RollingStop => {
+                    if self.someone_else_was_first(req, our_time, map) {
+                        WAIT_AT_STOP_SIGN
+                    } else {
+                        Duration::ZERO
+                    }
+                }
+                StopCompliance::Violation => {
+                    if self.someone_else_was_first(req, our_time, map) {
+                        self.stop_sign_violations.insert(req.clone());
+                    }
+                    Duration::ZERO
+                }
+            };
+            if now < our_time + wait {
+                // Since we have "ownership" of scheduling for req.agent, don't need to use
+                // scheduler.update.
+                scheduler.push(our_time + wait, Command::update_agent(req.agent));
+                return false;
+            }
         }
 
         // Once upon a time, we'd make sure that this request doesn't conflict with another in
@@ -915,6 +1204,40 @@ impl IntersectionSimState {
         true
     }
 
+    /// Each driver consistently follows stop signs the same way. Pedestrians and cyclists always
+    /// stop.
+    fn stop_compliance(&self, agent: AgentID) -> StopCompliance {
+        let car = match agent {
+            AgentID::Car(car) if car.vehicle_type == VehicleType::Car => car,
+            _ => {
+                return StopCompliance::FullStop;
+            }
+        };
+        // Spread out consecutive IDs deterministically, instead of using an RNG
+        let pct = ((car.id as u64).wrapping_mul(2_654_435_761) % 100) as usize;
+        if pct < self.stop_sign_violation_pct {
+            StopCompliance::Violation
+        } else if pct < self.stop_sign_violation_pct + self.rolling_stop_pct {
+            StopCompliance::RollingStop
+        } else {
+            StopCompliance::FullStop
+        }
+    }
+
+    /// Is anybody with a conflicting turn already waiting at the stop sign from before this
+    /// request?
+    fn someone_else_was_first(&self, req: &Request, our_time: Time, map: &Map) -> bool {
+        let our_turn = map.get_t(req.turn);
+        self.state[&req.turn.parent]
+            .waiting
+            .iter()
+            .any(|(other_req, (other_time, _))| {
+                other_req.agent != req.agent
+                    && *other_time < our_time
+                    && our_turn.conflicts_with(map.get_t(other_req.turn))
+            })
+    }
+
     fn traffic_signal_policy(
         &mut self,
         req: &Request,
@@ -932,13 +1255,51 @@ impl IntersectionSimState {
         let full_stage_duration = stage.stage_type.simple_duration();
         let remaining_stage_time = signal_state.stage_ends_at - now;
         let (our_time, _) = state.waiting[req];
+        let i = map.get_i(state.id);
+        let bike = req.agent.is_bike();
+        let movement = i.turn_to_movement(req.turn).0;
 
         // Can't go at all this stage.
-        let our_priority = stage.get_priority_of_turn(req.turn, map.get_i(state.id));
+        let our_priority =
+            signal.get_priority_of_turn(signal_state.current_stage, req.turn, bike, i);
         if our_priority == TurnPriority::Banned {
             return false;
         }
 
+        // Wait out a leading or lagging interval. Extensions only happen after the stage's normal
+        // duration, when any interval is long over.
+        let elapsed = if signal_state.extensions_count > 0 {
+            full_stage_duration
+        } else {
+            full_stage_duration - remaining_stage_time
+        };
+        if let Some(wait) =
+            signal.lead_lag_wait(signal_state.current_stage, movement, bike, elapsed)
+        {
+            if let Some(s) = scheduler {
+                s.push(now + wait, Command::update_agent(req.agent));
+            }
+            return false;
+        }
+
+        // Let vehicles that entered on the previous stage's yellow clear the intersection
+        if let Some(wait) = signal.all_red_wait(signal_state.current_stage, movement, bike, elapsed)
+        {
+            if let Some(s) = scheduler {
+                s.push(now + wait, Command::update_agent(req.agent));
+            }
+            return false;
+        }
+        // Don't start on a yellow. When the stage changes, waiting agents are woken up.
+        if signal.is_yellow(
+            signal_state.current_stage,
+            movement,
+            bike,
+            remaining_stage_time,
+        ) {
+            return false;
+        }
+
         if our_priority == TurnPriority::Yield
             && now < our_time + WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL
         {
@@ -1008,6 +1369,7 @@ impl IntersectionSimState {
                                 // Allow the conflicting turn!
                                 self.events.push(Event::Alert(
                                     AlertLocation::Intersection(req.turn.parent),
+                                    AlertCategory::TurnConflictCycle,
                                     format!(
                                         "{} found turn conflict cycle involving {:?}",
                                         req.agent, cycle
@@ -1040,6 +1402,7 @@ impl IntersectionSimState {
                         {
                             self.events.push(Event::Alert(
                                 AlertLocation::Intersection(req.turn.parent),
+                                AlertCategory::TurnConflictCycle,
                                 format!(
                                     "{} waking up {}, who's blocking it as part of a cycle",
                                     req.agent, other.agent
@@ -1134,6 +1497,34 @@ impl SignalState {
     }
 }
 
+/// Turns from an on-ramp onto a motorway. Traffic signals handle merges like anything else.
+fn motorway_merging_turns(i: &Intersection, map: &Map) -> BTreeSet<TurnID> {
+    if i.is_traffic_signal() {
+        return BTreeSet::new();
+    }
+    i.turns
+        .iter()
+        .filter(|t| {
+            map.get_parent(t.id.src).is_on_ramp(map)
+                && map
+                    .get_parent(t.id.dst)
+                    .osm_tags
+                    .is(osm::HIGHWAY, "motorway")
+        })
+        .map(|t| t.id)
+        .collect()
+}
+
+fn forget_older_than(list: &mut VecDeque<(Request, Time)>, now: Time, threshold: Duration) {
+    while list
+        .front()
+        .map(|(_, time)| now - *time > threshold)
+        .unwrap_or(false)
+    {
+        list.pop_front();
+    }
+}
+
 fn allow_block_the_box(i: &Intersection) -> bool {
     // Degenerate intersections are often just artifacts of how roads are split up in OSM. Allow
     // vehicles to get stuck in them, since the only possible thing they could block is pedestrians
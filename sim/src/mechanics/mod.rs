@@ -1,7 +1,7 @@
 pub(crate) use self::driving::DrivingSimState;
 pub(crate) use self::intersection::IntersectionSimState;
 pub(crate) use self::parking::{ParkingSim, ParkingSimState};
-pub(crate) use self::queue::Queue;
+pub(crate) use self::queue::{vehicle_density, Queue};
 pub(crate) use self::walking::WalkingSimState;
 
 mod car;
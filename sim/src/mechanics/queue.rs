@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use abstutil::FixedMap;
 use geom::{Distance, Time};
-use map_model::{Map, Position, Traversable};
+use map_model::{LaneID, Map, Position, Traversable};
 
 use crate::mechanics::car::{Car, CarState};
 use crate::{CarID, VehicleType, FOLLOWING_DISTANCE};
@@ -42,6 +42,9 @@ pub(crate) struct Queue {
     /// this length first. This is unused for turns themselves. This value can exceed geom_len
     /// (for the edge case of ONE long car on a short queue).
     pub reserved_length: Distance,
+    /// A reversible lane is closed, because its schedule points it the other way. Nobody new may
+    /// enter, but anybody already on it finishes.
+    pub draining: bool,
 }
 
 /// A member of a `Queue`.
@@ -81,6 +84,7 @@ impl Queue {
             laggy_head: None,
             geom_len: id.get_polyline(map).length(),
             reserved_length: Distance::ZERO,
+            draining: false,
         }
     }
 
@@ -625,3 +629,23 @@ fn dump_cars(dists: &[QueueEntry], cars: &FixedMap<CarID, Car>, id: Traversable,
     }
     println!();
 }
+
+/// Vehicles per kilometer of each lane, over some group of lanes
+pub(crate) fn vehicle_density<I: Iterator<Item = LaneID>>(
+    queues: &HashMap<Traversable, Queue>,
+    lanes: I,
+) -> f64 {
+    let mut vehicles = 0;
+    let mut lane_km = 0.0;
+    for lane in lanes {
+        if let Some(queue) = queues.get(&Traversable::Lane(lane)) {
+            vehicles += queue.get_active_cars().len();
+            lane_km += queue.geom_len.inner_meters() / 1000.0;
+        }
+    }
+    if lane_km == 0.0 {
+        0.0
+    } else {
+        vehicles as f64 / lane_km
+    }
+}
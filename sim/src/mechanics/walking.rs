@@ -5,22 +5,29 @@ use serde::{Deserialize, Serialize};
 use abstutil::{deserialize_multimap, serialize_multimap, FixedMap, IndexableKey, MultiMap};
 use geom::{Distance, Duration, Line, PolyLine, Speed, Time};
 use map_model::{
-    BuildingID, DrivingSide, IntersectionID, Map, ParkingLotID, Path, PathConstraints, PathStep,
-    RoadID, TransitRouteID, Traversable,
+    BuildingID, DrivingSide, IntersectionID, LaneID, Map, ParkingLotID, Path, PathConstraints,
+    PathStep, Position, RoadID, TransitRouteID, Traversable,
 };
 
 use crate::sim::Ctx;
 use crate::{
-    pedestrian_body_radius, AgentID, AgentProperties, Command, CommutersVehiclesCounts,
-    CreatePedestrian, DistanceInterval, DrawPedCrowdInput, DrawPedestrianInput, Event, Intent,
-    IntersectionSimState, ParkedCar, ParkingSpot, PedCrowdLocation, PedestrianID, PersonID,
-    Problem, Scheduler, SidewalkPOI, SidewalkSpot, TimeInterval, TransitSimState, TripID,
-    TripManager, UnzoomedAgent,
+    pedestrian_body_radius, AgentID, AgentProperties, AgentState, Command, CommutersVehiclesCounts,
+    CreatePedestrian, DistanceInterval, DrawPedCrowdInput, DrawPedestrianInput, DrivingSimState,
+    Event, Intent, IntersectionSimState, ParkedCar, ParkingSpot, PedCrowdLocation, PedestrianID,
+    PersonID, Problem, Scheduler, SidewalkPOI, SidewalkSpot, SimOptions, TimeInterval,
+    TransitSimState, TripID, TripManager, UnzoomedAgent,
 };
 
 const TIME_TO_START_BIKING: Duration = Duration::const_seconds(30.0);
 const TIME_TO_FINISH_BIKING: Duration = Duration::const_seconds(45.0);
 
+/// Pedestrians only cross midblock on roads with a speed limit up to about 30mph...
+const MAX_JAYWALKING_SPEED_LIMIT: Speed = Speed::const_meters_per_second(13.5);
+/// ... and when they don't have to walk further than this to reach the other side.
+const MAX_JAYWALKING_DISTANCE: Distance = Distance::const_meters(15.0);
+/// The gap in traffic has to be this much longer than the time needed to cross.
+const JAYWALKING_SAFETY_MARGIN: Duration = Duration::const_seconds(3.0);
+
 /// Simulates pedestrians. Unlike vehicles, pedestrians can move bidirectionally on sidewalks and
 /// just "ghost" through each other. There's no queueing or slowdown when many people are
 /// overlapping. They're simply grouped together into a DrawPedCrowdInput for rendering.
@@ -33,14 +40,16 @@ pub(crate) struct WalkingSimState {
     )]
     peds_per_traversable: MultiMap<Traversable, PedestrianID>,
     events: Vec<Event>,
+    allow_jaywalking: bool,
 }
 
 impl WalkingSimState {
-    pub fn new() -> WalkingSimState {
+    pub fn new(opts: &SimOptions) -> WalkingSimState {
         WalkingSimState {
             peds: FixedMap::new(),
             peds_per_traversable: MultiMap::new(),
             events: Vec::new(),
+            allow_jaywalking: opts.allow_jaywalking,
         }
     }
 
@@ -100,6 +109,7 @@ impl WalkingSimState {
                 now,
                 map,
                 &mut self.events,
+                self.allow_jaywalking,
             ),
         };
 
@@ -134,6 +144,7 @@ impl WalkingSimState {
         ctx: &mut Ctx,
         trips: &mut TripManager,
         transit: &mut TransitSimState,
+        driving: &DrivingSimState,
     ) {
         let mut ped = self.peds.get_mut(&id).unwrap();
         match ped.state {
@@ -220,6 +231,39 @@ impl WalkingSimState {
                         SidewalkPOI::SuddenlyAppear => unreachable!(),
                         SidewalkPOI::DeferredParkingSpot => unreachable!(),
                     }
+                } else if let Some(to) = ped.jaywalking_target(dist_int.end, ctx.map) {
+                    let dist = dist_int.end;
+                    let from = Position::new(ped.path.current_step().as_lane(), dist);
+                    match Line::new(from.pt(ctx.map), to.pt(ctx.map)) {
+                        Ok(line)
+                            if driving.gap_to_cross_road(
+                                now,
+                                from,
+                                line.length() / ped.speed + JAYWALKING_SAFETY_MARGIN,
+                                ctx.map,
+                            ) =>
+                        {
+                            ped.state = PedState::CrossingMidblock(
+                                dist,
+                                to,
+                                line.clone(),
+                                TimeInterval::new(now, now + line.length() / ped.speed),
+                            );
+                        }
+                        _ => {
+                            // No gap in traffic, so keep going to the proper crossing
+                            ped.state = ped.crossing_state(
+                                &self.peds_per_traversable,
+                                dist,
+                                now,
+                                ctx.map,
+                                &mut self.events,
+                                false,
+                            );
+                        }
+                    }
+                    ctx.scheduler
+                        .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
                 } else {
                     if let PathStep::Turn(t) | PathStep::ContraflowTurn(t) = ped.path.current_step()
                     {
@@ -241,6 +285,7 @@ impl WalkingSimState {
                         &mut self.peds_per_traversable,
                         &mut self.events,
                         ctx.scheduler,
+                        self.allow_jaywalking,
                     ) {
                         ctx.scheduler
                             .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
@@ -258,6 +303,7 @@ impl WalkingSimState {
                     &mut self.peds_per_traversable,
                     &mut self.events,
                     ctx.scheduler,
+                    self.allow_jaywalking,
                 ) {
                     ctx.scheduler
                         .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
@@ -270,6 +316,31 @@ impl WalkingSimState {
                     ));
                 }
             }
+            PedState::CrossingMidblock(_, to, _, _) => {
+                // Skip the rest of this sidewalk and the crossing, landing on the other side
+                self.peds_per_traversable
+                    .remove(ped.path.current_step().as_traversable(), ped.id);
+                ped.path.shift(ctx.map);
+                ped.path.shift(ctx.map);
+                ped.state = ped.crossing_state(
+                    &self.peds_per_traversable,
+                    to.dist_along(),
+                    now,
+                    ctx.map,
+                    &mut self.events,
+                    self.allow_jaywalking,
+                );
+                self.peds_per_traversable
+                    .insert(ped.path.current_step().as_traversable(), ped.id);
+                self.events.push(Event::AgentEntersTraversable(
+                    AgentID::Pedestrian(ped.id),
+                    Some(ped.trip),
+                    ped.path.current_step().as_traversable(),
+                    None,
+                ));
+                ctx.scheduler
+                    .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
+            }
             PedState::LeavingBuilding(b, _) => {
                 ped.state = ped.crossing_state(
                     &self.peds_per_traversable,
@@ -277,6 +348,7 @@ impl WalkingSimState {
                     now,
                     ctx.map,
                     &mut self.events,
+                    self.allow_jaywalking,
                 );
                 ctx.scheduler
                     .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
@@ -301,6 +373,7 @@ impl WalkingSimState {
                     now,
                     ctx.map,
                     &mut self.events,
+                    self.allow_jaywalking,
                 );
                 ctx.scheduler
                     .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
@@ -341,6 +414,7 @@ impl WalkingSimState {
                     now,
                     ctx.map,
                     &mut self.events,
+                    self.allow_jaywalking,
                 );
                 ctx.scheduler
                     .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
@@ -388,6 +462,27 @@ impl WalkingSimState {
         }
     }
 
+    pub fn agent_state(&self, id: PedestrianID) -> Option<AgentState> {
+        let p = self.peds.get(&id)?;
+        Some(match p.state {
+            PedState::Crossing { .. } => AgentState::Moving(p.path.current_step().as_traversable()),
+            PedState::WaitingToTurn(_, _) => {
+                AgentState::WaitingForTurn(p.path.next_step().as_turn())
+            }
+            PedState::LeavingBuilding(_, _) | PedState::LeavingParkingLot(_, _) => {
+                AgentState::LeavingBuilding
+            }
+            PedState::EnteringBuilding(_, _) | PedState::EnteringParkingLot(_, _) => {
+                AgentState::EnteringBuilding
+            }
+            PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _) => {
+                AgentState::SwitchingModes
+            }
+            PedState::WaitingForBus(route, _) => AgentState::WaitingForTransit(route),
+            PedState::CrossingMidblock(_, _, _, _) => AgentState::Jaywalking,
+        })
+    }
+
     pub fn agent_properties(&self, map: &Map, id: PedestrianID, now: Time) -> AgentProperties {
         let p = &self.peds[&id];
 
@@ -410,6 +505,7 @@ impl WalkingSimState {
             | PedState::FinishingBiking(_, _, _) => Distance::ZERO,
             // In all of these cases, we haven't shifted the PathStep that led us to this state yet
             PedState::WaitingToTurn(_, _)
+            | PedState::CrossingMidblock(_, _, _, _)
             | PedState::EnteringBuilding(_, _)
             | PedState::EnteringParkingLot(_, _)
             | PedState::StartingToBike(_, _, _)
@@ -470,6 +566,7 @@ impl WalkingSimState {
         let mut backwards: Vec<(PedestrianID, Distance)> = Vec::new();
         let mut bldg_driveway: MultiMap<BuildingID, (PedestrianID, Distance)> = MultiMap::new();
         let mut lot_driveway: MultiMap<ParkingLotID, (PedestrianID, Distance)> = MultiMap::new();
+        let mut loners: Vec<DrawPedestrianInput> = Vec::new();
 
         for id in self.peds_per_traversable.get(on) {
             let ped = &self.peds[id];
@@ -512,11 +609,14 @@ impl WalkingSimState {
                     // The backwards half of the sidewalk is closer to the road.
                     backwards.push((*id, dist));
                 }
+                // Out in the road, not part of any crowd on the sidewalk
+                PedState::CrossingMidblock(_, _, _, _) => {
+                    loners.push(ped.get_draw_ped(now, map));
+                }
             }
         }
 
         let mut crowds: Vec<DrawPedCrowdInput> = Vec::new();
-        let mut loners: Vec<DrawPedestrianInput> = Vec::new();
 
         // For each group, sort by distance along. Attempt to bundle into intervals.
         for (mut group, location, on_len) in vec![
@@ -680,8 +780,16 @@ impl Pedestrian {
         start_time: Time,
         map: &Map,
         events: &mut Vec<Event>,
+        allow_jaywalking: bool,
     ) -> PedState {
-        let end_dist = if self.path.is_last_step() {
+        let jaywalk_at = if allow_jaywalking {
+            self.plan_jaywalking(start_dist, map)
+        } else {
+            None
+        };
+        let end_dist = if let Some(dist) = jaywalk_at {
+            dist
+        } else if self.path.is_last_step() {
             self.goal.sidewalk_pos.dist_along()
         } else {
             // TODO PathStep should have a end_dist... or end_pos
@@ -739,9 +847,86 @@ impl Pedestrian {
             PedState::StartingToBike(ref spot, _, _) => spot.sidewalk_pos.dist_along(),
             PedState::FinishingBiking(ref spot, _, _) => spot.sidewalk_pos.dist_along(),
             PedState::WaitingForBus(_, _) => self.goal.sidewalk_pos.dist_along(),
+            PedState::CrossingMidblock(dist, _, _, _) => dist,
+        }
+    }
+
+    /// If the path goes to the end of this sidewalk, crosses the same road, then heads back along
+    /// the other side, returns the other sidewalk.
+    fn backtracking_crossing(&self, map: &Map) -> Option<LaneID> {
+        let steps = self.path.get_steps();
+        let here = match steps.get(0)? {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) => *l,
+            _ => {
+                return None;
+            }
+        };
+        match steps.get(1)? {
+            PathStep::Turn(t) | PathStep::ContraflowTurn(t)
+                if map.get_t(*t).turn_type.pedestrian_crossing() => {}
+            _ => {
+                return None;
+            }
+        }
+        match steps.get(2)? {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) if l.road == here.road => Some(*l),
+            _ => None,
+        }
+    }
+
+    /// Where this pedestrian is heading along the current sidewalk, when they don't cut across
+    fn end_of_sidewalk(&self, map: &Map) -> Distance {
+        match self.path.current_step() {
+            PathStep::Lane(l) => map.get_l(l).length(),
+            _ => Distance::ZERO,
         }
     }
 
+    /// Instead of walking to a crossing and back, pedestrians may cut across slow, narrow roads.
+    /// Returns how far along the current sidewalk to cross, starting from `start_dist`.
+    fn plan_jaywalking(&self, start_dist: Distance, map: &Map) -> Option<Distance> {
+        let other_side = self.backtracking_crossing(map)?;
+        if map.get_parent(other_side).speed_limit > MAX_JAYWALKING_SPEED_LIMIT {
+            return None;
+        }
+        let here = self.path.current_step().as_lane();
+        let end_dist = self.end_of_sidewalk(map);
+
+        // Cross right away, unless the destination is further along on the other side
+        let mut dist = start_dist;
+        if self.path.get_steps().len() == 3 {
+            let goal = self.goal.sidewalk_pos.equiv_pos(here, map).dist_along();
+            let (low, high) = if start_dist < end_dist {
+                (start_dist, end_dist)
+            } else {
+                (end_dist, start_dist)
+            };
+            if low <= goal && goal <= high {
+                dist = goal;
+            }
+        }
+        if dist == end_dist {
+            return None;
+        }
+
+        let from = Position::new(here, dist);
+        let to = from.equiv_pos(other_side, map);
+        if from.pt(map).dist_to(to.pt(map)) > MAX_JAYWALKING_DISTANCE {
+            return None;
+        }
+        Some(dist)
+    }
+
+    /// After reaching `dist` along the current sidewalk, where on the other side of the road should
+    /// this pedestrian cross to? None if they're not jaywalking here.
+    fn jaywalking_target(&self, dist: Distance, map: &Map) -> Option<Position> {
+        let other_side = self.backtracking_crossing(map)?;
+        if dist == self.end_of_sidewalk(map) {
+            return None;
+        }
+        Some(Position::new(self.path.current_step().as_lane(), dist).equiv_pos(other_side, map))
+    }
+
     fn get_draw_ped(&self, now: Time, map: &Map) -> DrawPedestrianInput {
         let on = self.path.current_step().as_traversable();
         let err = format!("at {}, {}'s position is broken", now, self.id);
@@ -838,7 +1023,8 @@ impl Pedestrian {
                     .unwrap_or_else(|_| line.pt1()),
                 line.angle(),
             ),
-            PedState::FinishingBiking(_, ref line, ref time_int) => (
+            PedState::FinishingBiking(_, ref line, ref time_int)
+            | PedState::CrossingMidblock(_, _, ref line, ref time_int) => (
                 line.percent_along(time_int.percent(now))
                     .unwrap_or_else(|_| line.pt1()),
                 line.angle(),
@@ -881,6 +1067,7 @@ impl Pedestrian {
         peds_per_traversable: &mut MultiMap<Traversable, PedestrianID>,
         events: &mut Vec<Event>,
         scheduler: &mut Scheduler,
+        allow_jaywalking: bool,
     ) -> bool {
         if let PathStep::Turn(t) | PathStep::ContraflowTurn(t) = self.path.next_step() {
             if !intersections.maybe_start_turn(
@@ -898,6 +1085,9 @@ impl Pedestrian {
             ) {
                 return false;
             }
+            for problem in intersections.take_problems(AgentID::Pedestrian(self.id)) {
+                events.push(Event::ProblemEncountered(self.trip, problem));
+            }
         }
 
         peds_per_traversable.remove(self.path.current_step().as_traversable(), self.id);
@@ -908,7 +1098,14 @@ impl Pedestrian {
             PathStep::Turn(_) => Distance::ZERO,
             PathStep::ContraflowTurn(t) => map.get_t(t).geom.length(),
         };
-        self.state = self.crossing_state(peds_per_traversable, start_dist, now, map, events);
+        self.state = self.crossing_state(
+            peds_per_traversable,
+            start_dist,
+            now,
+            map,
+            events,
+            allow_jaywalking,
+        );
         peds_per_traversable.insert(self.path.current_step().as_traversable(), self.id);
         events.push(Event::AgentEntersTraversable(
             AgentID::Pedestrian(self.id),
@@ -936,6 +1133,8 @@ enum PedState {
     StartingToBike(SidewalkSpot, Line, TimeInterval),
     FinishingBiking(SidewalkSpot, Line, TimeInterval),
     WaitingForBus(TransitRouteID, Time),
+    /// Jaywalking from some distance along the current sidewalk to the other side of the road
+    CrossingMidblock(Distance, Position, Line, TimeInterval),
 }
 
 impl PedState {
@@ -950,6 +1149,7 @@ impl PedState {
             PedState::StartingToBike(_, _, ref time_int) => time_int.end,
             PedState::FinishingBiking(_, _, ref time_int) => time_int.end,
             PedState::WaitingForBus(_, _) => unreachable!(),
+            PedState::CrossingMidblock(_, _, _, ref time_int) => time_int.end,
         }
     }
 
@@ -19,7 +19,9 @@ pub fn prebake(map: &Map, scenario: Scenario, timer: &mut Timer) -> PrebakeSumma
     opts.alerts = AlertHandler::Silence;
     let mut sim = Sim::new(map, opts);
     // Bit of an abuse of this, but just need to fix the rng seed.
-    let mut rng = SimFlags::for_test("prebaked").make_rng();
+    let flags = SimFlags::for_test("prebaked");
+    let mut rng = flags.make_rng();
+    sim.record_rng_seed(flags.rng_seed);
     sim.instantiate(&scenario, map, &mut rng, timer);
 
     // Run until a few hours after the end of the day. Some trips start close to midnight, and we
@@ -32,7 +34,7 @@ pub fn prebake(map: &Map, scenario: Scenario, timer: &mut Timer) -> PrebakeSumma
     );
     abstio::write_binary(
         abstio::path_prebaked_results(&scenario.map_name, &scenario.scenario_name),
-        sim.get_analytics(),
+        sim.get_analytics_with_metadata(),
     );
     // TODO Remove the num_agents check once transit isn't as broken. In sao_miguel_paulista,
     // people wait for a bus that stops running at midnight.
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use map_model::Map;
+
+use crate::SimOptions;
+
+/// Everything needed to reproduce a simulation run. This is stamped into savestates, prebaked
+/// results, and exported CSV files, so results are only compared when they came from comparable
+/// runs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub map_name: MapName,
+    /// The md5sum of the map file, according to the data manifest. None if the map isn't listed
+    /// there, like when it was imported locally.
+    pub map_checksum: Option<String>,
+    pub edits_name: String,
+    /// None when the map isn't edited
+    pub edits_checksum: Option<String>,
+    pub scenario_name: Option<String>,
+    /// None if the caller didn't record the seed used to instantiate the scenario
+    pub rng_seed: Option<u64>,
+    /// What the simulation was created with, after any per-map overrides
+    pub sim_options: SimOptions,
+    /// The git commit the simulation was built from
+    pub code_version: String,
+}
+
+impl RunMetadata {
+    pub(crate) fn new(map: &Map, sim_options: SimOptions) -> RunMetadata {
+        let mut metadata = RunMetadata {
+            map_name: map.get_name().clone(),
+            map_checksum: None,
+            edits_name: String::new(),
+            edits_checksum: None,
+            scenario_name: None,
+            rng_seed: None,
+            sim_options,
+            code_version: env!("ABST_GIT_COMMIT").to_string(),
+        };
+        metadata.edits_changed(map);
+        metadata
+    }
+
+    pub(crate) fn edits_changed(&mut self, map: &Map) {
+        let edits = map.get_edits();
+        self.edits_name = edits.edits_name.clone();
+        self.edits_checksum = if edits.commands.is_empty() {
+            None
+        } else {
+            Some(edits.get_checksum(map))
+        };
+    }
+
+    /// Looking up the map in the data manifest is slow, so only do it when writing something out.
+    pub(crate) fn fill_in_map_checksum(&mut self) {
+        if self.map_checksum.is_none() {
+            self.map_checksum = lookup_map_checksum(&self.map_name);
+        }
+    }
+
+    /// One "key: value" line per field
+    pub fn describe(&self) -> Vec<String> {
+        let or_none = |x: &Option<String>| x.clone().unwrap_or_else(|| "none".to_string());
+        vec![
+            format!("map: {}", self.map_name.path()),
+            format!("map checksum: {}", or_none(&self.map_checksum)),
+            format!("edits: {}", self.edits_name),
+            format!("edits checksum: {}", or_none(&self.edits_checksum)),
+            format!("scenario: {}", or_none(&self.scenario_name)),
+            format!(
+                "rng seed: {}",
+                or_none(&self.rng_seed.map(|x| x.to_string()))
+            ),
+            format!(
+                "sim options: {}",
+                abstutil::to_json_terse(&self.sim_options)
+            ),
+            format!("code version: {}", self.code_version),
+        ]
+    }
+
+    /// The description as comment lines, to start a CSV file with. Most CSV readers can skip
+    /// these; pandas needs `comment='#'`.
+    pub fn to_csv_comment(&self) -> String {
+        let mut out = String::new();
+        for line in self.describe() {
+            out.push_str("# ");
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn lookup_map_checksum(name: &MapName) -> Option<String> {
+    // Native builds might not have the manifest, so don't crash
+    #[cfg(not(target_arch = "wasm32"))]
+    let manifest: abstio::Manifest = abstio::maybe_read_json(
+        abstio::path("MANIFEST.json"),
+        &mut abstutil::Timer::throwaway(),
+    )
+    .ok()?;
+    #[cfg(target_arch = "wasm32")]
+    let manifest = abstio::Manifest::load();
+
+    let path = name.path();
+    // Manifest entries start with "data/"
+    let key = &path[path.find("data/system/")?..];
+    manifest
+        .entries
+        .get(key)
+        .map(|entry| entry.checksum.clone())
+}
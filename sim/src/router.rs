@@ -5,16 +5,16 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use geom::Distance;
+use geom::{Distance, Time};
 use map_model::{
-    BuildingID, IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep,
+    BuildingID, IntersectionID, Lane, LaneID, Map, Path, PathConstraints, PathRequest, PathStep,
     Position, Traversable, Turn, TurnID,
 };
 
 use crate::mechanics::Queue;
 use crate::{
-    AlertLocation, CarID, Event, ParkingSim, ParkingSimState, ParkingSpot, PersonID, SidewalkSpot,
-    TripID, TripPhaseType, Vehicle, VehicleType,
+    AlertCategory, AlertLocation, CarID, Event, ParkingSim, ParkingSimState, ParkingSpot, PersonID,
+    SidewalkSpot, TripID, TripPhaseType, Vehicle, VehicleType,
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -285,6 +285,7 @@ impl Router {
                             if let Some((_, p)) = trip_and_person {
                                 events.push(Event::Alert(
                                     AlertLocation::Person(p),
+                                    AlertCategory::NoParking,
                                     format!(
                                         "{} can't find parking on {} or anywhere reachable from \
                                          it. Possibly we're just totally out of parking space!",
@@ -326,6 +327,7 @@ impl Router {
         queues: &HashMap<Traversable, Queue>,
         map: &Map,
         handle_uber_turns: bool,
+        now: Time,
     ) {
         // if we're already in the uber-turn, we're committed, but if we're about to enter one, lock
         // in the best path through it now.
@@ -353,9 +355,23 @@ impl Router {
             let next_parent = map.get_l(next_lane).src_i;
             let constraints = self.owner.vehicle_type.to_constraints();
 
-            let compute_cost = |turn1: &Turn, lane: LaneID| {
-                let (lt, lc, mut slow_lane) = turn1.penalty(constraints, map);
-                let (vehicles, mut bike) = queues[&Traversable::Lane(lane)].target_lane_penalty();
+            let compute_cost = |turn1: &Turn, lane: &Lane, turn2: &Turn| {
+                let (lt, lc, mut slow_lane) = turn1.penalty(constraints, Some(now), map);
+                let queue = &queues[&Traversable::Lane(lane.id)];
+                let (vehicles, mut bike) = queue.target_lane_penalty();
+                // A closed reversible lane can't be entered, so avoid it first of all
+                let draining = usize::from(queue.draining);
+                // Next get out of lanes restricted right now, including the one the path
+                // originally picked. If no other lane works, stay put rather than getting stuck.
+                let restricted = usize::from(
+                    lane.get_restriction(map)
+                        .map(|r| {
+                            r.active_at(now)
+                                && (!r.allows_vehicle(constraints, map)
+                                    || !r.allows_turn(turn2.turn_type))
+                        })
+                        .unwrap_or(false),
+                );
 
                 // The magic happens here. We have different penalties:
                 //
@@ -378,7 +394,7 @@ impl Router {
                     slow_lane = 0;
                 }
 
-                (lt, bike, slow_lane, vehicles + lc)
+                (draining, restricted, lt, bike, slow_lane, vehicles + lc)
             };
 
             // Look for other candidates, and assign a cost to each.
@@ -405,10 +421,10 @@ impl Router {
                     };
                     let turn2 = map.maybe_get_t(t2)?;
 
-                    Some((turn1, l.id, turn2))
+                    Some((turn1, l, turn2))
                 })
                 .map(|(turn1, l, turn2)| {
-                    let cost = compute_cost(turn1, l);
+                    let cost = compute_cost(turn1, l, turn2);
                     if turn1.id == current_turn {
                         original_cost = Some(cost);
                     }
@@ -433,12 +449,12 @@ impl Router {
             if best_cost < original_cost {
                 debug!(
                     "changing lanes {:?} -> {:?}, cost: {:?} -> {:?}",
-                    orig_target_lane, best_lane, original_cost, best_cost
+                    orig_target_lane, best_lane.id, original_cost, best_cost
                 );
                 self.path
                     .modify_step(1 + segment * 2, PathStep::Turn(turn1.id), map);
                 self.path
-                    .modify_step(2 + segment * 2, PathStep::Lane(best_lane), map);
+                    .modify_step(2 + segment * 2, PathStep::Lane(best_lane.id), map);
                 self.path
                     .modify_step(3 + segment * 2, PathStep::Turn(turn2.id), map);
             }
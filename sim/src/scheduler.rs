@@ -26,6 +26,10 @@ pub(crate) enum Command {
     Pandemic(pandemic::Cmd),
     /// The Time is redundant, just used to dedupe commands
     StartBus(TransitRouteID, Time),
+    /// Close or reopen reversible lanes as their schedule changes direction
+    ReverseLanes,
+    /// Managed motorways post a new speed limit
+    UpdateSpeedLimits,
 }
 
 impl Command {
@@ -37,6 +41,16 @@ impl Command {
         }
     }
 
+    /// A short description of the command, without any of the larger payloads
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            Command::SpawnCar(ref create, _) => format!("SpawnCar({})", create.vehicle.id),
+            Command::SpawnPed(ref create) => format!("SpawnPed({})", create.id),
+            Command::StartTrip(id, _) => format!("StartTrip({})", id),
+            _ => format!("{:?}", self),
+        }
+    }
+
     fn to_type(&self) -> CommandType {
         match self {
             Command::SpawnCar(ref create, _) => CommandType::Car(create.vehicle.id),
@@ -49,6 +63,8 @@ impl Command {
             Command::Callback(_) => CommandType::Callback,
             Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
             Command::StartBus(r, t) => CommandType::StartBus(*r, *t),
+            Command::ReverseLanes => CommandType::ReverseLanes,
+            Command::UpdateSpeedLimits => CommandType::UpdateSpeedLimits,
         }
     }
 
@@ -64,6 +80,8 @@ impl Command {
             Command::Callback(_) => SimpleCommandType::Callback,
             Command::Pandemic(_) => SimpleCommandType::Pandemic,
             Command::StartBus(_, _) => SimpleCommandType::StartBus,
+            Command::ReverseLanes => SimpleCommandType::ReverseLanes,
+            Command::UpdateSpeedLimits => SimpleCommandType::UpdateSpeedLimits,
         }
     }
 }
@@ -80,6 +98,8 @@ enum CommandType {
     Callback,
     Pandemic(pandemic::Cmd),
     StartBus(TransitRouteID, Time),
+    ReverseLanes,
+    UpdateSpeedLimits,
 }
 
 /// A more compressed form of CommandType, just used for keeping stats on event processing.
@@ -93,6 +113,8 @@ enum SimpleCommandType {
     Callback,
     Pandemic,
     StartBus,
+    ReverseLanes,
+    UpdateSpeedLimits,
 }
 
 /// The priority queue driving the discrete event simulation. Different pieces of the simulation
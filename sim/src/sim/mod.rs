@@ -1,6 +1,6 @@
 // This file has a jumbled mess of queries, setup, and mutating methods.
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeSet, HashSet, VecDeque};
 
 use anyhow::Result;
 use instant::Instant;
@@ -14,19 +14,21 @@ use abstutil::{prettyprint_usize, serialized_size_bytes, Timer};
 use geom::{Distance, Duration, Speed, Time};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathConstraints, PathRequest,
-    Position, TransitRoute, Traversable,
+    Position, RoadID, TransitRoute, Traversable,
 };
 use synthpop::OrigPersonID;
 
-pub use self::queries::{AgentProperties, DelayCause};
+pub use self::queries::{AgentProperties, AgentState, DelayCause, DelayStep};
 // TODO Super weird for both of these to wind up here
 pub use self::scenario::{count_parked_cars_per_bldg, rand_dist};
 use crate::{
-    AgentID, AlertLocation, Analytics, CarID, Command, CreateCar, DrivingSimState, Event,
+    AgentID, Alert, AlertCategory, AlertLocation, Analytics, BikeShareNetwork, BikeShareSimState,
+    Breakpoint, Breakpoints, CarID, Command, CreateCar, DrivingSimState, Event, EventTrace,
     IntersectionSimState, PandemicModel, ParkedCar, ParkingSim, ParkingSimState, ParkingSpot,
-    Person, PersonID, Router, Scheduler, SidewalkPOI, SidewalkSpot, StartTripArgs, TrafficRecorder,
-    TransitSimState, TripID, TripInfo, TripManager, TripPhaseType, Vehicle, VehicleSpec,
-    VehicleType, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
+    Person, PersonID, Router, RunMetadata, Scheduler, SidewalkPOI, SidewalkSpot, StartTripArgs,
+    TrafficRecorder, TransitSimState, TripID, TripInfo, TripManager, TripPhaseType, Vehicle,
+    VehicleSpec, VehicleType, WaitWatcher, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH,
+    MIN_CAR_LENGTH,
 };
 
 mod queries;
@@ -34,8 +36,15 @@ mod scenario;
 
 // TODO Do something else.
 const BLIND_RETRY_TO_SPAWN: Duration = Duration::const_seconds(5.0);
+/// Raise an alert when somebody has waited at one intersection this long
+const STUCK_AGENT_THRESHOLD: Duration = Duration::const_seconds(15.0 * 60.0);
+// Printed alerts are kept around for a UI to show, but nothing has to collect them
+const MAX_PRINTED_ALERTS: usize = 100;
 
 /// The Sim ties together all the pieces of the simulation. Its main property is the current time.
+///
+/// Savestates are stored with bincode, so any change to the serialized fields here or in the
+/// pieces breaks existing savestates.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Sim {
     driving: DrivingSimState,
@@ -62,9 +71,22 @@ pub struct Sim {
     // This is created interactively, and there's no reason to preserve one for savestates.
     #[serde(skip_serializing, skip_deserializing)]
     recorder: Option<TrafficRecorder>,
+    // Only used to debug nondeterminism
+    #[serde(skip_serializing, skip_deserializing)]
+    event_trace: Option<EventTrace>,
+    #[serde(skip_serializing, skip_deserializing)]
+    breakpoints: Breakpoints,
 
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
+    #[serde(skip_serializing, skip_deserializing)]
+    muted_alerts: BTreeSet<AlertCategory>,
+    #[serde(skip_serializing, skip_deserializing)]
+    stuck_agents: WaitWatcher,
+    #[serde(skip_serializing, skip_deserializing)]
+    printed_alerts: VecDeque<Alert>,
+    /// How this run was set up, so it can be reproduced later
+    metadata: RunMetadata,
 }
 
 pub(crate) struct Ctx<'a> {
@@ -78,7 +100,7 @@ pub(crate) struct Ctx<'a> {
 }
 
 /// Options controlling the traffic simulation.
-#[derive(Clone, StructOpt)]
+#[derive(Clone, StructOpt, Serialize, Deserialize)]
 pub struct SimOptions {
     /// Used to distinguish savestates for running the same scenario.
     #[structopt(long, default_value = "unnamed")]
@@ -108,6 +130,7 @@ pub struct SimOptions {
     /// Enable an experimental SEIR pandemic model. This requires an RNG seed, which can be the
     /// same or different from the one used for the rest of the simulation.
     #[structopt(long, parse(try_from_str = parse_rng))]
+    #[serde(skip)]
     pub enable_pandemic_model: Option<XorShiftRng>,
     /// When a warning is encountered during simulation, specifies how to respond.
     #[structopt(long, parse(try_from_str = parse_alert_handler), default_value = "print")]
@@ -127,6 +150,38 @@ pub struct SimOptions {
     /// quickly.
     #[structopt(long)]
     pub skip_analytics: bool,
+    /// Let pedestrians cross slow, narrow roads midblock when there's a gap in traffic, instead of
+    /// walking to the nearest crossing and back.
+    #[structopt(long)]
+    #[serde(default)]
+    pub allow_jaywalking: bool,
+    /// The percent of drivers who roll through stop signs without fully stopping, as long as
+    /// nobody else got there first. This raises the capacity of stop-controlled intersections.
+    #[structopt(long, default_value = "0")]
+    #[serde(default)]
+    pub rolling_stop_pct: usize,
+    /// The percent of drivers who go through stop signs without stopping, even when somebody else
+    /// got there first. Each time they cut somebody off is recorded as a problem for the trip.
+    #[structopt(long, default_value = "0")]
+    #[serde(default)]
+    pub stop_sign_violation_pct: usize,
+    /// Simulate a bike share system, using stations from this JSON file. Long walking trips
+    /// starting and ending near a station will ride a shared bike, if one is available.
+    #[structopt(long, parse(try_from_str = parse_bike_share))]
+    #[serde(skip)]
+    pub bike_share: Option<BikeShareNetwork>,
+    /// Only let one vehicle every few seconds appear on each lane leading away from a border, like
+    /// the lane's capacity. The rest wait off-map, in the order they arrived. By default, any
+    /// number of vehicles can appear at a border at once, if there's room.
+    #[structopt(long)]
+    #[serde(default)]
+    pub limit_border_inflow: bool,
+    /// Kinds of alerts to ignore. They're still recorded in analytics, but not printed and they
+    /// don't pause the simulation. Every cancelled trip raises an alert, so those are muted by
+    /// default.
+    #[structopt(skip = default_muted_alerts())]
+    #[serde(default = "default_muted_alerts")]
+    pub muted_alerts: BTreeSet<AlertCategory>,
 }
 
 impl SimOptions {
@@ -143,6 +198,50 @@ impl SimOptions {
             infinite_parking: false,
             disable_turn_conflicts: false,
             skip_analytics: false,
+            allow_jaywalking: false,
+            rolling_stop_pct: 0,
+            stop_sign_violation_pct: 0,
+            bike_share: None,
+            limit_border_inflow: false,
+            muted_alerts: default_muted_alerts(),
+        }
+    }
+}
+
+fn default_muted_alerts() -> BTreeSet<AlertCategory> {
+    let mut muted = BTreeSet::new();
+    muted.insert(AlertCategory::TripCancelled);
+    muted
+}
+
+impl SimOptions {
+    /// Fill in options left at their default with choices saved from before. Anything explicitly
+    /// turned on, like from a command line flag, stays on.
+    pub fn fill_in_from(&mut self, saved: &SimOptions) {
+        if self.run_name == "unnamed" {
+            self.run_name = saved.run_name.clone();
+        }
+        self.use_freeform_policy_everywhere |= saved.use_freeform_policy_everywhere;
+        self.allow_block_the_box |= saved.allow_block_the_box;
+        self.dont_recalc_lanechanging |= saved.dont_recalc_lanechanging;
+        self.dont_break_turn_conflict_cycles |= saved.dont_break_turn_conflict_cycles;
+        self.dont_handle_uber_turns |= saved.dont_handle_uber_turns;
+        self.infinite_parking |= saved.infinite_parking;
+        self.disable_turn_conflicts |= saved.disable_turn_conflicts;
+        self.skip_analytics |= saved.skip_analytics;
+        self.allow_jaywalking |= saved.allow_jaywalking;
+        self.limit_border_inflow |= saved.limit_border_inflow;
+        if self.rolling_stop_pct == 0 {
+            self.rolling_stop_pct = saved.rolling_stop_pct;
+        }
+        if self.stop_sign_violation_pct == 0 {
+            self.stop_sign_violation_pct = saved.stop_sign_violation_pct;
+        }
+        if let AlertHandler::Print = self.alerts {
+            self.alerts = saved.alerts.clone();
+        }
+        if self.muted_alerts == default_muted_alerts() {
+            self.muted_alerts = saved.muted_alerts.clone();
         }
     }
 }
@@ -153,12 +252,16 @@ impl Default for SimOptions {
     }
 }
 
+fn parse_bike_share(path: &str) -> Result<BikeShareNetwork> {
+    abstio::maybe_read_json(path.to_string(), &mut Timer::throwaway())
+}
+
 fn parse_rng(x: &str) -> Result<XorShiftRng> {
     let seed: u64 = x.parse()?;
     Ok(XorShiftRng::seed_from_u64(seed))
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum AlertHandler {
     /// Just print the alert to STDOUT
     Print,
@@ -206,13 +309,21 @@ impl Sim {
             opts.allow_block_the_box = true;
         }
 
+        if has_reversible_lanes(map) {
+            scheduler.push(Time::START_OF_DAY, Command::ReverseLanes);
+        }
+        if has_variable_speed_limits(map) {
+            scheduler.push(Time::START_OF_DAY, Command::UpdateSpeedLimits);
+        }
+
         Sim {
+            metadata: RunMetadata::new(map, opts.clone()),
             driving: DrivingSimState::new(map, &opts),
             parking: ParkingSimState::new(map, opts.infinite_parking, &mut timer),
-            walking: WalkingSimState::new(),
+            walking: WalkingSimState::new(&opts),
             intersections: IntersectionSimState::new(map, &mut scheduler, &opts),
             transit: TransitSimState::new(map),
-            trips: TripManager::new(),
+            trips: TripManager::new(BikeShareSimState::new(map, opts.bike_share.as_ref())),
             pandemic: opts.enable_pandemic_model.map(PandemicModel::new),
             scheduler,
             time: Time::START_OF_DAY,
@@ -223,9 +334,14 @@ impl Sim {
             step_count: 0,
             highlighted_people: None,
             alerts: opts.alerts,
+            muted_alerts: opts.muted_alerts,
+            stuck_agents: WaitWatcher::default(),
+            printed_alerts: VecDeque::new(),
 
             analytics: Analytics::new(!opts.skip_analytics),
             recorder: None,
+            event_trace: None,
+            breakpoints: Breakpoints::default(),
         }
     }
 
@@ -253,7 +369,7 @@ impl Sim {
             m.initialize(self.trips.get_all_people(), &mut self.scheduler);
         }
 
-        self.dispatch_events(Vec::new(), map);
+        self.dispatch_events(Vec::new(), "spawning trips", map);
     }
 
     pub fn get_free_onstreet_spots(&self, l: LaneID) -> Vec<ParkingSpot> {
@@ -434,6 +550,11 @@ impl Sim {
         self.time = time;
         let mut events = Vec::new();
         let mut halt = false;
+        let cause = if self.event_trace.is_some() {
+            cmd.describe()
+        } else {
+            String::new()
+        };
 
         let mut ctx = Ctx {
             parking: &mut self.parking,
@@ -488,25 +609,30 @@ impl Sim {
                     let maybe_parked_car = create_car.maybe_parked_car.clone();
                     let req = create_car.router.get_path().get_req().clone();
 
-                    if let Some(create_car) = self
-                        .driving
-                        .start_car_on_lane(self.time, create_car, &mut ctx)
-                    {
+                    // Vehicles held back at a border always wait off-map, even if they wouldn't
+                    // retry when there's no room.
+                    let border_retry =
+                        self.driving
+                            .border_entry_delayed_until(self.time, &create_car, ctx.map);
+                    let failed = if border_retry.is_some() {
+                        Some(create_car)
+                    } else {
+                        self.driving
+                            .start_car_on_lane(self.time, create_car, &mut ctx)
+                    };
+                    if let Some(create_car) = failed {
                         // Starting the car failed for some reason.
-                        if retry_if_no_room {
+                        if retry_if_no_room || border_retry.is_some() {
                             // Although the agent isn't on the map yet, they're trying.
                             if let Some((trip, _)) = trip_and_person {
                                 self.trips.agent_starting_trip_leg(AgentID::Car(id), trip);
                             }
-                            self.driving.vehicle_waiting_to_spawn(
-                                id,
-                                req.start,
-                                trip_and_person.map(|(_, p)| p),
-                            );
+                            self.driving
+                                .vehicle_waiting_to_spawn(self.time, &create_car, ctx.map);
 
                             // TODO Record this in the trip log
                             self.scheduler.push(
-                                self.time + BLIND_RETRY_TO_SPAWN,
+                                border_retry.unwrap_or(self.time + BLIND_RETRY_TO_SPAWN),
                                 Command::SpawnCar(create_car, retry_if_no_room),
                             );
                         } else if let Some((trip, person)) = create_car.trip_and_person {
@@ -612,6 +738,7 @@ impl Sim {
                     &mut ctx,
                     &mut self.trips,
                     &mut self.transit,
+                    &self.driving,
                 );
             }
             Command::UpdateIntersection(i) => {
@@ -634,31 +761,102 @@ impl Sim {
             Command::StartBus(r, _) => {
                 self.start_bus(map.get_tr(r), map);
             }
+            Command::ReverseLanes => {
+                if let Some(t) = self.driving.update_reversible_lanes(self.time, map) {
+                    self.scheduler.push(t, Command::ReverseLanes);
+                }
+            }
+            Command::UpdateSpeedLimits => {
+                if let Some(t) = self.driving.update_variable_speed_limits(self.time, map) {
+                    self.scheduler.push(t, Command::UpdateSpeedLimits);
+                }
+            }
         }
 
         // Record events at precisely the time they occur.
-        self.dispatch_events(events, map);
+        if self.dispatch_events(events, &cause, map) {
+            halt = true;
+        }
+        if !self.breakpoints.list.is_empty() {
+            for (location, msg) in self
+                .breakpoints
+                .check_waiting(self.time, &self.intersections)
+            {
+                self.analytics.alert(Alert {
+                    time: self.time,
+                    location,
+                    category: AlertCategory::Breakpoint,
+                    msg,
+                });
+                if !self.muted_alerts.contains(&AlertCategory::Breakpoint) {
+                    halt = true;
+                }
+            }
+        }
+        if !self.muted_alerts.contains(&AlertCategory::StuckAgent) {
+            for (i, since) in self.stuck_agents.newly_delayed(
+                self.time,
+                Duration::minutes(1),
+                STUCK_AGENT_THRESHOLD,
+                &self.intersections,
+            ) {
+                self.analytics.alert(Alert {
+                    time: self.time,
+                    location: AlertLocation::Intersection(i),
+                    category: AlertCategory::StuckAgent,
+                    msg: format!(
+                        "somebody has waited at {} since {}",
+                        i,
+                        since.ampm_tostring()
+                    ),
+                });
+            }
+        }
 
         halt
     }
 
-    fn dispatch_events(&mut self, mut events: Vec<Event>, map: &Map) {
-        events.extend(self.trips.collect_events());
-        events.extend(self.transit.collect_events());
-        events.extend(self.driving.collect_events());
-        events.extend(self.walking.collect_events());
-        events.extend(self.intersections.collect_events());
-        events.extend(self.parking.collect_events());
-        for ev in events {
+    /// Returns true if a breakpoint was hit, unless breakpoints are muted.
+    fn dispatch_events(&mut self, events: Vec<Event>, cause: &str, map: &Map) -> bool {
+        let mut hit_breakpoint = false;
+        // Remember where each event came from, in case it's being traced
+        let all_events = vec![
+            ("sim", events),
+            ("trips", self.trips.collect_events()),
+            ("transit", self.transit.collect_events()),
+            ("driving", self.driving.collect_events()),
+            ("walking", self.walking.collect_events()),
+            ("intersections", self.intersections.collect_events()),
+            ("parking", self.parking.collect_events()),
+        ];
+        for (source, ev) in all_events
+            .into_iter()
+            .flat_map(|(source, events)| events.into_iter().map(move |ev| (source, ev)))
+        {
+            if let Some(ref mut trace) = self.event_trace {
+                trace.record(self.time, self.step_count, cause, source, &ev);
+            }
             if let Some(ref mut m) = self.pandemic {
                 m.handle_event(self.time, &ev, &mut self.scheduler);
             }
             if let Some(ref mut r) = self.recorder {
                 r.handle_event(self.time, &ev, map, &self.driving);
             }
+            if let Some((location, msg)) = self.breakpoints.handle_event(&ev, map, &self.driving) {
+                self.analytics.alert(Alert {
+                    time: self.time,
+                    location,
+                    category: AlertCategory::Breakpoint,
+                    msg,
+                });
+                if !self.muted_alerts.contains(&AlertCategory::Breakpoint) {
+                    hit_breakpoint = true;
+                }
+            }
 
             self.analytics.event(ev, self.time, map);
         }
+        hit_breakpoint
     }
 
     pub fn timed_step(
@@ -677,23 +875,8 @@ impl Sim {
             if self.minimal_step(map, end_time - self.time, maybe_cb) {
                 break;
             }
-            if !self.analytics.alerts.is_empty() {
-                match self.alerts {
-                    AlertHandler::Print => {
-                        for (t, loc, msg) in self.analytics.alerts.drain(..) {
-                            println!("Alert at {} ({:?}): {}", t, loc, msg);
-                        }
-                    }
-                    AlertHandler::Block => {
-                        for (t, loc, msg) in &self.analytics.alerts {
-                            println!("Alert at {} ({:?}): {}", t, loc, msg);
-                        }
-                        break;
-                    }
-                    AlertHandler::Silence => {
-                        self.analytics.alerts.clear();
-                    }
-                }
+            if self.handle_alerts() {
+                break;
             }
             if Duration::realtime_elapsed(last_update) >= Duration::seconds(1.0) {
                 // TODO Not timer?
@@ -731,23 +914,42 @@ impl Sim {
             if self.minimal_step(map, end_time - self.time, maybe_cb) {
                 break;
             }
-            if !self.analytics.alerts.is_empty() {
-                match self.alerts {
-                    AlertHandler::Print => {
-                        for (t, loc, msg) in self.analytics.alerts.drain(..) {
-                            println!("Alert at {} ({:?}): {}", t, loc, msg);
-                        }
-                    }
-                    AlertHandler::Block => {
-                        for (t, loc, msg) in &self.analytics.alerts {
-                            println!("Alert at {} ({:?}): {}", t, loc, msg);
-                        }
-                        break;
-                    }
-                    AlertHandler::Silence => {
-                        self.analytics.alerts.clear();
-                    }
+            if self.handle_alerts() {
+                break;
+            }
+        }
+    }
+
+    /// Respond to new alerts as the `AlertHandler` says. Returns true if the simulation should stop
+    /// until the UI calls `clear_alerts`.
+    fn handle_alerts(&mut self) -> bool {
+        let muted = &self.muted_alerts;
+        self.analytics
+            .alerts
+            .retain(|alert| !muted.contains(&alert.category));
+        if self.analytics.alerts.is_empty() {
+            return false;
+        }
+        match self.alerts {
+            AlertHandler::Print => {
+                for alert in self.analytics.alerts.drain(..) {
+                    println!("{}", alert);
+                    self.printed_alerts.push_back(alert);
+                }
+                while self.printed_alerts.len() > MAX_PRINTED_ALERTS {
+                    self.printed_alerts.pop_front();
                 }
+                false
+            }
+            AlertHandler::Block => {
+                for alert in &self.analytics.alerts {
+                    println!("{}", alert);
+                }
+                true
+            }
+            AlertHandler::Silence => {
+                self.analytics.alerts.clear();
+                false
             }
         }
     }
@@ -767,8 +969,7 @@ impl Sim {
     }
 
     fn save_path(&self, base_time: Time) -> String {
-        // If we wanted to be even more reproducible, we'd encode RNG seed, version of code, etc,
-        // but that's overkill right now.
+        // The savestate itself records the RNG seed, version of code, etc in its RunMetadata
         abstio::path_save(
             &self.map_name,
             &self.edits_name,
@@ -810,6 +1011,7 @@ impl Sim {
             );
         }
 
+        self.metadata.fill_in_map_checksum();
         let path = self.save_path(self.time);
         abstio::write_binary(path.clone(), self);
 
@@ -836,13 +1038,53 @@ impl Sim {
             .handle_live_edited_traffic_signals(self.time, map, &mut self.scheduler)
     }
 
+    /// Reversible lanes change direction by time of day. The map has them pointing one way; while
+    /// their schedule points them the other way, the simulation closes them.
+    pub fn is_lane_closed(&self, l: LaneID) -> bool {
+        self.driving.is_lane_closed(l)
+    }
+
+    /// Managed motorways change their speed limit by time of day or congestion. This is the limit
+    /// a road currently posts, if it's different than the normal one.
+    pub fn posted_speed_limit(&self, r: RoadID) -> Option<Speed> {
+        self.driving.posted_speed_limit(r)
+    }
+
     /// Respond to arbitrary map edits without resetting the simulation. Returns the number of
     /// (trips cancelled, parked cars displaced).
     pub fn handle_live_edits(&mut self, map: &Map, timer: &mut Timer) -> (usize, usize) {
         self.edits_name = map.get_edits().edits_name.clone();
+        self.metadata.edits_changed(map);
 
         let (affected, num_parked_cars) = self.find_trips_affected_by_live_edits(map, timer);
         let num_trips_cancelled = affected.len();
+        self.cancel_trips_for_live_edits(affected, "map edited without reset", map);
+
+        self.driving.handle_live_edits(map);
+        self.intersections.handle_live_edits(map);
+        // The edits may have added, removed, or pointed reversible lanes the other way, or changed
+        // managed motorways
+        if has_reversible_lanes(map) {
+            self.scheduler.update(self.time, Command::ReverseLanes);
+        } else {
+            self.scheduler.cancel(Command::ReverseLanes);
+        }
+        if has_variable_speed_limits(map) {
+            self.scheduler.update(self.time, Command::UpdateSpeedLimits);
+        } else {
+            self.scheduler.cancel(Command::UpdateSpeedLimits);
+            self.driving.update_variable_speed_limits(self.time, map);
+        }
+
+        (num_trips_cancelled, num_parked_cars)
+    }
+
+    fn cancel_trips_for_live_edits(
+        &mut self,
+        affected: BTreeSet<(AgentID, TripID)>,
+        reason: &str,
+        map: &Map,
+    ) {
         let affected_agents: BTreeSet<AgentID> = affected.iter().map(|(a, _)| *a).collect();
 
         // V1: Just cancel every trip crossing an affected area.
@@ -863,7 +1105,7 @@ impl Sim {
                     self.trips.cancel_trip(
                         self.time,
                         trip,
-                        "map edited without reset".to_string(),
+                        reason.to_string(),
                         Some(vehicle),
                         &mut ctx,
                     );
@@ -871,24 +1113,14 @@ impl Sim {
                 }
                 AgentID::Pedestrian(ped) => {
                     self.walking.delete_ped(ped, &mut ctx);
-                    self.trips.cancel_trip(
-                        self.time,
-                        trip,
-                        "map edited without reset".to_string(),
-                        None,
-                        &mut ctx,
-                    );
+                    self.trips
+                        .cancel_trip(self.time, trip, reason.to_string(), None, &mut ctx);
                     self.trips
                         .trip_abruptly_cancelled(trip, AgentID::Pedestrian(ped));
                 }
                 AgentID::BusPassenger(_, _) => unreachable!(),
             }
         }
-
-        self.driving.handle_live_edits(map);
-        self.intersections.handle_live_edits(map);
-
-        (num_trips_cancelled, num_parked_cars)
     }
 
     /// Returns (trips affected, number of parked cars displaced)
@@ -902,7 +1134,6 @@ impl Sim {
         // TODO Handle changes to access restrictions
 
         {
-            // Find every active trip whose path crosses a modified lane or intersection
             let (edited_lanes, _) = map.get_edits().changed_lanes(map);
             let mut closed_intersections = HashSet::new();
             for i in map.get_edits().original_intersections.keys() {
@@ -910,29 +1141,7 @@ impl Sim {
                     closed_intersections.insert(*i);
                 }
             }
-            for (a, trip) in self.trips.active_agents_and_trips() {
-                if let Some(path) = self.get_path(*a) {
-                    if path
-                        .get_steps()
-                        .iter()
-                        .any(|step| match step.as_traversable() {
-                            Traversable::Lane(l) => edited_lanes.contains(&l),
-                            Traversable::Turn(t) => {
-                                closed_intersections.contains(&t.parent)
-                                    || edited_lanes.contains(&t.src)
-                                    || edited_lanes.contains(&t.dst)
-                            }
-                        })
-                    {
-                        affected.insert((*a, *trip));
-                    }
-                }
-            }
-
-            affected.extend(
-                self.driving
-                    .find_vehicles_affected_by_live_edits(&closed_intersections, &edited_lanes),
-            );
+            affected.extend(self.find_trips_crossing(&edited_lanes, &closed_intersections));
         }
 
         let num_evicted = {
@@ -957,6 +1166,50 @@ impl Sim {
 
         (affected, num_evicted)
     }
+
+    /// Find every active trip whose path crosses a modified lane or intersection
+    fn find_trips_crossing(
+        &self,
+        edited_lanes: &BTreeSet<LaneID>,
+        closed_intersections: &HashSet<IntersectionID>,
+    ) -> BTreeSet<(AgentID, TripID)> {
+        let mut affected = BTreeSet::new();
+        for (a, trip) in self.trips.active_agents_and_trips() {
+            if let Some(path) = self.get_path(*a) {
+                if path
+                    .get_steps()
+                    .iter()
+                    .any(|step| match step.as_traversable() {
+                        Traversable::Lane(l) => edited_lanes.contains(&l),
+                        Traversable::Turn(t) => {
+                            closed_intersections.contains(&t.parent)
+                                || edited_lanes.contains(&t.src)
+                                || edited_lanes.contains(&t.dst)
+                        }
+                    })
+                {
+                    affected.insert((*a, *trip));
+                }
+            }
+        }
+        affected.extend(
+            self.driving
+                .find_vehicles_affected_by_live_edits(closed_intersections, edited_lanes),
+        );
+        affected
+    }
+}
+
+fn has_reversible_lanes(map: &Map) -> bool {
+    map.all_roads()
+        .iter()
+        .any(|r| !r.reversible_lanes.is_empty())
+}
+
+fn has_variable_speed_limits(map: &Map) -> bool {
+    map.all_roads()
+        .iter()
+        .any(|r| r.variable_speed_limit.is_some())
 }
 
 // Invasive debugging
@@ -983,9 +1236,25 @@ impl Sim {
         }
     }
 
-    pub fn clear_alerts(&mut self) -> Vec<(Time, AlertLocation, String)> {
+    pub fn clear_alerts(&mut self) -> Vec<Alert> {
         std::mem::take(&mut self.analytics.alerts)
     }
+
+    /// Alerts that were printed without stopping the simulation, since the last call. Only the
+    /// most recent few are kept.
+    pub fn take_printed_alerts(&mut self) -> Vec<Alert> {
+        self.printed_alerts.drain(..).collect()
+    }
+
+    /// Stop printing or pausing for some kinds of alerts. They're still recorded in
+    /// `Analytics::alert_log`.
+    pub fn set_muted_alerts(&mut self, muted: BTreeSet<AlertCategory>) {
+        self.muted_alerts = muted;
+    }
+
+    pub fn get_muted_alerts(&self) -> &BTreeSet<AlertCategory> {
+        &self.muted_alerts
+    }
 }
 
 // Callbacks
@@ -1025,6 +1294,61 @@ impl Sim {
     }
 }
 
+// Tracing events
+impl Sim {
+    /// Start recording every event the simulation produces. Call this before `instantiate`.
+    pub fn enable_event_trace(&mut self) {
+        self.event_trace = Some(EventTrace::new());
+    }
+
+    pub fn get_event_trace(&self) -> Option<&EventTrace> {
+        self.event_trace.as_ref()
+    }
+}
+
+impl Sim {
+    pub fn get_options(&self) -> &SimOptions {
+        &self.metadata.sim_options
+    }
+
+    /// Describes how this run was set up. Stamp this into anything exported, so results can be
+    /// reproduced.
+    pub fn run_metadata(&self) -> RunMetadata {
+        let mut metadata = self.metadata.clone();
+        metadata.fill_in_map_checksum();
+        metadata
+    }
+
+    /// The RNG is consumed while instantiating a scenario, so callers have to say what seed they
+    /// started with.
+    pub fn record_rng_seed(&mut self, seed: u64) {
+        self.metadata.rng_seed = Some(seed);
+    }
+
+    /// The analytics collected so far, stamped with how this run was set up
+    pub fn get_analytics_with_metadata(&mut self) -> &Analytics {
+        self.analytics.run_metadata = Some(self.run_metadata());
+        &self.analytics
+    }
+}
+
+// Breakpoints
+impl Sim {
+    /// Halt the simulation when something happens. Hitting a breakpoint also raises an alert
+    /// explaining it.
+    pub fn add_breakpoint(&mut self, bp: Breakpoint) {
+        self.breakpoints.list.push(bp);
+    }
+
+    pub fn remove_breakpoint(&mut self, idx: usize) {
+        self.breakpoints.list.remove(idx);
+    }
+
+    pub fn get_breakpoints(&self) -> &Vec<Breakpoint> {
+        &self.breakpoints.list
+    }
+}
+
 // Managing highlighted people
 impl Sim {
     pub fn set_highlighted_people(&mut self, people: BTreeSet<PersonID>) {
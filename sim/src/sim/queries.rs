@@ -14,9 +14,10 @@ use synthpop::{OrigPersonID, Scenario, TripMode};
 
 use crate::analytics::SlidingWindow;
 use crate::{
-    AgentID, AgentType, Analytics, CarID, CommutersVehiclesCounts, DrawCarInput, DrawPedCrowdInput,
-    DrawPedestrianInput, PandemicModel, ParkedCar, ParkingSim, PedestrianID, Person, PersonID,
-    PersonState, Sim, TripEndpoint, TripID, TripInfo, TripResult, UnzoomedAgent, VehicleType,
+    AgentID, AgentType, Analytics, BikeShareStationState, CarID, CommutersVehiclesCounts,
+    DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput, PandemicModel, ParkedCar, ParkingSim,
+    PedestrianID, Person, PersonID, PersonState, PlannedLeg, Sim, TripEndpoint, TripID, TripInfo,
+    TripResult, UnzoomedAgent, VehicleType,
 };
 
 // TODO Many of these just delegate to an inner piece. This is unorganized and hard to maintain.
@@ -96,6 +97,15 @@ impl Sim {
         }
     }
 
+    /// None if the agent doesn't exist
+    pub fn agent_state(&self, id: AgentID) -> Option<AgentState> {
+        match id {
+            AgentID::Car(id) => self.driving.agent_state(id),
+            AgentID::Pedestrian(id) => self.walking.agent_state(id),
+            AgentID::BusPassenger(_, bus) => Some(AgentState::RidingTransit(bus)),
+        }
+    }
+
     pub fn num_transit_passengers(&self, car: CarID) -> usize {
         self.transit.get_passengers(car).len()
     }
@@ -117,6 +127,25 @@ impl Sim {
         self.trips.num_active_agents()
     }
 
+    /// Trips still waiting off-map to enter at each border, and how long they've waited so far.
+    /// This demand hasn't been served yet.
+    pub fn border_queues(&self, map: &Map) -> BTreeMap<IntersectionID, Vec<(TripID, Duration)>> {
+        self.driving
+            .border_queues(self.time, map)
+            .into_iter()
+            .map(|(i, cars)| {
+                (
+                    i,
+                    cars.into_iter()
+                        .filter_map(|(car, waiting)| {
+                            Some((self.agent_to_trip(AgentID::Car(car))?, waiting))
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
     pub fn agent_to_trip(&self, id: AgentID) -> Option<TripID> {
         self.trips.agent_to_trip(id)
     }
@@ -291,6 +320,43 @@ impl Sim {
             .get_blocked_by_graph(self.time, map, &self.intersections)
     }
 
+    /// Explain why an agent is delayed, by following the chain of who's blocking whom. The first
+    /// entry describes the agent itself. The chain ends with somebody waiting at an intersection,
+    /// somebody who isn't blocked, or somebody already in the chain, meaning there's gridlock.
+    ///
+    /// This only looks at the agents in the chain, so it's cheap enough to call often.
+    pub fn explain_delay(&self, map: &Map, id: AgentID) -> Vec<DelayStep> {
+        let mut chain = Vec::new();
+        let mut seen = BTreeSet::new();
+        let mut current = id;
+        loop {
+            seen.insert(current);
+            let (waiting, cause, conflicting_turn) =
+                match self
+                    .driving
+                    .get_blocked_by(self.time, map, &self.intersections, current)
+                {
+                    Some((waiting, cause, turn)) => (waiting, Some(cause), turn),
+                    None => (Duration::ZERO, None, None),
+                };
+            chain.push(DelayStep {
+                agent: current,
+                state: self.agent_state(current),
+                waiting,
+                cause: cause.clone(),
+                conflicting_turn,
+            });
+            match cause {
+                Some(DelayCause::Agent(next)) if !seen.contains(&next) => {
+                    current = next;
+                }
+                _ => {
+                    return chain;
+                }
+            }
+        }
+    }
+
     /// (bus, stop index it's coming from, percent to next stop, location)
     pub fn status_of_buses(
         &self,
@@ -386,6 +452,16 @@ impl Sim {
         self.transit.get_people_waiting_at_stop(at)
     }
 
+    /// Describes the legs of an ongoing trip that haven't started yet.
+    pub fn get_planned_legs(&self, id: TripID, map: &Map) -> Vec<PlannedLeg> {
+        self.trips.get_planned_legs(id, map, &self.parking)
+    }
+
+    /// Empty if the simulation isn't modelling bike share
+    pub fn get_bike_share_stations(&self) -> &Vec<BikeShareStationState> {
+        self.trips.get_bike_share_stations()
+    }
+
     pub fn generate_scenario(&self, map: &Map, name: String) -> Scenario {
         self.trips.generate_scenario(map, name)
     }
@@ -548,3 +624,49 @@ pub enum DelayCause {
     /// Waiting on a traffic signal to change, or pausing at a stop sign before proceeding
     Intersection(IntersectionID),
 }
+
+/// One step in the chain explaining why an agent is delayed
+#[derive(Debug, Clone, Serialize)]
+pub struct DelayStep {
+    pub agent: AgentID,
+    /// None if the agent has vanished
+    pub state: Option<AgentState>,
+    /// How long this agent has been stuck in one place
+    pub waiting: Duration,
+    /// Who or what this agent is waiting for. None if it isn't blocked.
+    pub cause: Option<DelayCause>,
+    /// If the agent is waiting for somebody else to finish a turn that conflicts with theirs,
+    /// that turn
+    pub conflicting_turn: Option<TurnID>,
+}
+
+/// What is an agent doing right now? This simplifies each agent's internal state machine, to help
+/// explain its behavior.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum AgentState {
+    /// Not on the map yet, because there's no room to start, or it's queued off-map at a border
+    WaitingToSpawn,
+    Moving(Traversable),
+    ChangingLanes {
+        from: LaneID,
+        to: LaneID,
+    },
+    /// Stopped behind somebody
+    Queued(Traversable),
+    /// At the end of a lane or sidewalk, waiting to start a turn
+    WaitingForTurn(TurnID),
+    /// Driving around near the destination, looking for a free spot
+    LookingForParking,
+    Parking,
+    Unparking,
+    /// A transit vehicle picking up and dropping off passengers
+    AtTransitStop,
+    EnteringBuilding,
+    LeavingBuilding,
+    WaitingForTransit(TransitRouteID),
+    RidingTransit(CarID),
+    /// Crossing a road away from any crosswalk
+    Jaywalking,
+    /// Getting on or off a bike
+    SwitchingModes,
+}
@@ -38,6 +38,7 @@ impl Sim {
     ) {
         // Any case where map edits could change the calls to the RNG, we have to fork.
         self.set_run_name(scenario.scenario_name.clone());
+        self.metadata.scenario_name = Some(scenario.scenario_name.clone());
 
         timer.start(format!("Instantiating {}", scenario.scenario_name));
 
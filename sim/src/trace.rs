@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use geom::Time;
+
+use crate::Event;
+
+/// A record of every event the simulation dispatched, in order. Two runs of the same scenario with
+/// the same RNG seed should produce identical traces; comparing them finds the first place where
+/// some nondeterminism crept in.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct EventTrace {
+    pub entries: Vec<TraceEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub time: Time,
+    /// How many steps the simulation had taken when this event happened
+    pub step: usize,
+    /// The scheduler command being handled when the event happened
+    pub cause: String,
+    /// The part of the simulation that produced the event, like "driving" or "intersections"
+    pub source: String,
+    /// The event itself, formatted with `Debug`
+    pub event: String,
+}
+
+/// Where two traces first disagree. One side is missing when a trace ended early.
+#[derive(Debug)]
+pub struct Divergence {
+    pub index: usize,
+    pub expected: Option<TraceEntry>,
+    pub actual: Option<TraceEntry>,
+}
+
+impl EventTrace {
+    pub fn new() -> EventTrace {
+        EventTrace::default()
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        time: Time,
+        step: usize,
+        cause: &str,
+        source: &str,
+        ev: &Event,
+    ) {
+        self.entries.push(TraceEntry {
+            time,
+            step,
+            cause: cause.to_string(),
+            source: source.to_string(),
+            event: format!("{:?}", ev),
+        });
+    }
+
+    /// Compares this trace against what's expected, starting from `start_idx`. Returns the first
+    /// entry that differs. If one trace is just shorter than the other, that only counts as a
+    /// divergence when `complete` is true; otherwise the shorter run might simply not be done yet.
+    pub fn first_divergence(
+        &self,
+        expected: &EventTrace,
+        start_idx: usize,
+        complete: bool,
+    ) -> Option<Divergence> {
+        let len = self.entries.len().min(expected.entries.len());
+        for index in start_idx..len {
+            if self.entries[index] != expected.entries[index] {
+                return Some(Divergence {
+                    index,
+                    expected: Some(expected.entries[index].clone()),
+                    actual: Some(self.entries[index].clone()),
+                });
+            }
+        }
+        if complete && self.entries.len() != expected.entries.len() {
+            return Some(Divergence {
+                index: len,
+                expected: expected.entries.get(len).cloned(),
+                actual: self.entries.get(len).cloned(),
+            });
+        }
+        None
+    }
+}
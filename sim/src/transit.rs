@@ -3,7 +3,7 @@ use std::collections::{BTreeMap, BTreeSet};
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::Time;
+use geom::{Duration, Time};
 use map_model::{Map, Path, TransitRoute, TransitRouteID, TransitStopID};
 
 use crate::sim::Ctx;
@@ -119,8 +119,8 @@ impl TransitSimState {
         );
     }
 
-    /// If true, the bus is idling. If false, the bus actually arrived at a border and should now
-    /// vanish.
+    /// If Some, the bus dwells at the stop for this long, depending on how many passengers board
+    /// and alight. If None, the bus actually arrived at a border and should now vanish.
     ///
     /// TODO Misnomer -- callback from Router::follow_bus_route
     pub fn bus_arrived_at_stop(
@@ -130,7 +130,7 @@ impl TransitSimState {
         trips: &mut TripManager,
         walking: &mut WalkingSimState,
         ctx: &mut Ctx,
-    ) -> bool {
+    ) -> Option<Duration> {
         let mut bus = self.buses.get_mut(&id).unwrap();
         match bus.state {
             BusState::DrivingToStop(stop_idx) => {
@@ -140,9 +140,11 @@ impl TransitSimState {
                     .push(Event::BusArrivedAtStop(id, bus.route, stop1));
 
                 // Deboard existing passengers.
+                let mut alightings = 0;
                 let mut still_riding = Vec::new();
                 for (person, maybe_stop2) in bus.passengers.drain(..) {
                     if Some(stop1) == maybe_stop2 {
+                        alightings += 1;
                         trips.person_left_bus(now, person, bus.car, ctx);
                         self.events.push(Event::PassengerAlightsTransit(
                             person, bus.car, bus.route, stop1,
@@ -154,11 +156,13 @@ impl TransitSimState {
                 bus.passengers = still_riding;
 
                 // Board new passengers.
+                let mut boardings = 0;
                 let mut still_waiting = Vec::new();
                 for (ped, route, maybe_stop2, started_waiting) in
                     self.peds_waiting.remove(&stop1).unwrap()
                 {
                     if bus.route == route {
+                        boardings += 1;
                         let (trip, person) = trips.ped_boarded_bus(
                             now,
                             ped,
@@ -189,7 +193,12 @@ impl TransitSimState {
                     }
                 }
                 self.peds_waiting.insert(stop1, still_waiting);
-                true
+                Some(
+                    ctx.map
+                        .get_tr(bus.route)
+                        .boarding
+                        .dwell_time(boardings, alightings),
+                )
             }
             BusState::DrivingOffMap => {
                 self.routes
@@ -208,7 +217,7 @@ impl TransitSimState {
                     trips.transit_rider_reached_border(now, person, id, ctx);
                 }
                 bus.state = BusState::Finished;
-                false
+                None
             }
             BusState::AtStop(_) | BusState::Finished => unreachable!(),
         }
@@ -297,6 +306,15 @@ impl TransitSimState {
         self.buses[&bus].route
     }
 
+    /// The stop where a bus is currently dwelling, if any
+    pub fn current_stop(&self, bus: CarID) -> Option<TransitStopID> {
+        let bus = &self.buses[&bus];
+        match bus.state {
+            BusState::AtStop(stop_idx) => Some(self.routes[&bus.route].stops[stop_idx]),
+            _ => None,
+        }
+    }
+
     /// also stop idx that the bus is coming from
     pub fn buses_for_route(&self, route: TransitRouteID) -> Vec<(CarID, Option<usize>)> {
         if let Some(r) = self.routes.get(&route) {
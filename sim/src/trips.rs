@@ -14,10 +14,11 @@ use synthpop::{
 
 use crate::sim::Ctx;
 use crate::{
-    AgentID, AgentType, AlertLocation, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal,
-    Event, ParkedCar, ParkingSim, ParkingSpot, PedestrianID, PersonID, SidewalkPOI, SidewalkSpot,
-    StartTripArgs, TransitSimState, TripID, TripPhaseType, TripSpec, Vehicle, VehicleSpec,
-    VehicleType, WalkingSimState,
+    AgentID, AgentType, AlertCategory, AlertLocation, BikeShareSimState, BikeShareStationState,
+    CarID, Command, CreateCar, CreatePedestrian, DrivingGoal, Event, ParkedCar, ParkingSim,
+    ParkingSimState, ParkingSpot, PedestrianID, PersonID, SidewalkPOI, SidewalkSpot, StartTripArgs,
+    TransitSimState, TripID, TripPhaseType, TripSpec, Vehicle, VehicleSpec, VehicleType,
+    WalkingSimState,
 };
 
 /// Manages people, each of which executes some trips through the day. Each trip is further broken
@@ -39,19 +40,21 @@ pub(crate) struct TripManager {
     unfinished_trips: usize,
 
     car_id_counter: usize,
+    bike_share: BikeShareSimState,
 
     events: Vec<Event>,
 }
 
 // Initialization
 impl TripManager {
-    pub fn new() -> TripManager {
+    pub fn new(bike_share: BikeShareSimState) -> TripManager {
         TripManager {
             trips: Vec::new(),
             people: Vec::new(),
             active_trip_mode: BTreeMap::new(),
             unfinished_trips: 0,
             car_id_counter: 0,
+            bike_share,
             events: Vec::new(),
         }
     }
@@ -142,6 +145,7 @@ impl TripManager {
             if false {
                 self.events.push(Event::Alert(
                     AlertLocation::Person(person.id),
+                    AlertCategory::DelayedTrip,
                     format!(
                         "{} is still doing a trip, so not starting {} yet",
                         person.id, trip
@@ -174,6 +178,29 @@ impl TripManager {
                 error: error.to_string(),
             },
         };
+        // Long walking trips near bike share stations ride a shared bike for part of the way
+        let spec = match spec {
+            TripSpec::JustWalking { start, goal } => {
+                match self.bike_share.plan(&start, &goal, ctx.map) {
+                    Some((pickup, dropoff)) => {
+                        let bike = CarID {
+                            id: self.car_id_counter,
+                            vehicle_type: VehicleType::Bike,
+                        };
+                        self.car_id_counter += 1;
+                        TripSpec::UsingBikeShare {
+                            start,
+                            goal,
+                            bike,
+                            pickup: self.bike_share.station_bldg(pickup),
+                            dropoff: self.bike_share.station_bldg(dropoff),
+                        }
+                    }
+                    None => TripSpec::JustWalking { start, goal },
+                }
+            }
+            spec => spec,
+        };
         // to_plan might actually change the TripSpec
         let (spec, legs) = spec.into_plan(ctx.map);
         assert!(self.trips[trip.0].legs.is_empty());
@@ -329,6 +356,61 @@ impl TripManager {
                     }
                 }
             }
+            TripSpec::UsingBikeShare {
+                start,
+                bike,
+                pickup,
+                dropoff,
+                ..
+            } => {
+                assert_eq!(
+                    person.state,
+                    match start.connection {
+                        SidewalkPOI::Building(b) => PersonState::Inside(b),
+                        SidewalkPOI::Border(i) => {
+                            self.events.push(Event::PersonEntersMap(
+                                person.id,
+                                AgentID::Pedestrian(person.ped),
+                                i,
+                            ));
+                            PersonState::OffMap
+                        }
+                        SidewalkPOI::SuddenlyAppear => {
+                            self.events.push(Event::PersonEntersMap(
+                                person.id,
+                                AgentID::Pedestrian(person.ped),
+                                ctx.map.get_l(start.sidewalk_pos.lane()).src_i,
+                            ));
+                            PersonState::OffMap
+                        }
+                        _ => unreachable!(),
+                    }
+                );
+                person.state = PersonState::Trip(trip);
+                self.bike_share.reserve(bike, pickup, dropoff);
+
+                let walk_to = SidewalkSpot::bike_rack(pickup, ctx.map).unwrap();
+                let req = PathRequest::walking(start.sidewalk_pos, walk_to.sidewalk_pos);
+                match ctx.map.pathfind(req) {
+                    Ok(path) => {
+                        ctx.scheduler.push(
+                            now,
+                            Command::SpawnPed(CreatePedestrian {
+                                id: person.ped,
+                                speed: person.ped_speed,
+                                start,
+                                goal: walk_to,
+                                path,
+                                trip,
+                                person: person.id,
+                            }),
+                        );
+                    }
+                    Err(err) => {
+                        self.cancel_trip(now, trip, err.to_string(), None, ctx);
+                    }
+                }
+            }
             TripSpec::UsingBike { start, .. } => {
                 assert_eq!(person.state, PersonState::Inside(start));
                 person.state = PersonState::Trip(trip);
@@ -592,6 +674,21 @@ impl TripManager {
             SidewalkPOI::BikeRack(p) => p,
             _ => unreachable!(),
         };
+        let vehicle = if self.bike_share.is_shared(bike) {
+            match self.bike_share.pickup(now, bike, trip.person) {
+                Some(vehicle) => vehicle,
+                None => {
+                    // The station is empty, so just walk the rest of the way
+                    trip.legs.pop_front();
+                    let id = trip.id;
+                    let start = SidewalkSpot::suddenly_appear(spot.sidewalk_pos, ctx.map);
+                    self.spawn_ped(now, id, start, ctx);
+                    return;
+                }
+            }
+        } else {
+            self.people[trip.person.0].get_vehicle(bike)
+        };
 
         let end = if let Some(end) = drive_to.goal_pos(PathConstraints::Bike, ctx.map) {
             end
@@ -624,12 +721,7 @@ impl TripManager {
                 ctx.scheduler.push(
                     now,
                     Command::SpawnCar(
-                        CreateCar::for_appearing(
-                            self.people[trip.person.0].get_vehicle(bike),
-                            router,
-                            trip.id,
-                            trip.person,
-                        ),
+                        CreateCar::for_appearing(vehicle, router, trip.id, trip.person),
                         true,
                     ),
                 );
@@ -664,6 +756,9 @@ impl TripManager {
             }
             _ => unreachable!(),
         };
+        if self.bike_share.is_shared(bike) {
+            self.bike_share.dock(now, bike);
+        }
 
         let id = trip.id;
         self.spawn_ped(now, id, bike_rack, ctx);
@@ -920,6 +1015,7 @@ impl TripManager {
         if false {
             self.events.push(Event::Alert(
                 AlertLocation::Person(person.id),
+                AlertCategory::DelayedTrip,
                 format!(
                     "{} just freed up, so starting delayed trip {}",
                     person.id, trip
@@ -983,10 +1079,20 @@ impl TripManager {
     ) {
         let trip = &mut self.trips[id.0];
         self.unfinished_trips -= 1;
+        self.events.push(Event::Alert(
+            AlertLocation::Person(trip.person),
+            AlertCategory::TripCancelled,
+            format!("{} cancelled: {}", id, reason),
+        ));
         trip.info.cancellation_reason = Some(reason);
         self.events
             .push(Event::TripCancelled(trip.id, trip.info.mode));
         let person = trip.person;
+        for leg in &trip.legs {
+            if let TripLeg::Drive(c, _) = leg {
+                self.bike_share.abandon(*c);
+            }
+        }
 
         // Maintain consistentency for anyone listening to events
         if let PersonState::Inside(b) = self.people[person.0].state {
@@ -1032,6 +1138,7 @@ impl TripManager {
                     {
                         self.events.push(Event::Alert(
                             AlertLocation::Person(person),
+                            AlertCategory::TripCancelled,
                             format!(
                                 "{} had a trip cancelled, and their car was warped to {:?}",
                                 person, spot
@@ -1046,6 +1153,7 @@ impl TripManager {
                     } else {
                         self.events.push(Event::Alert(
                             AlertLocation::Person(person),
+                            AlertCategory::TripCancelled,
                             format!(
                                 "{} had a trip cancelled, but nowhere to warp their car! Sucks.",
                                 person
@@ -1134,6 +1242,98 @@ impl TripManager {
             self.unfinished_trips,
         )
     }
+
+    /// Describes the legs of an ongoing trip after the current one.
+    pub fn get_planned_legs(
+        &self,
+        id: TripID,
+        map: &Map,
+        parking: &ParkingSimState,
+    ) -> Vec<PlannedLeg> {
+        let trip = &self.trips[id.0];
+        let mut planned = Vec::new();
+        if trip.finished_at.is_some() || trip.info.cancellation_reason.is_some() {
+            return planned;
+        }
+
+        // Where the previous leg ends. Before a driving leg, this is where the vehicle starts.
+        let mut prev_end: Option<Position> = None;
+        let mut at_stop: Option<TransitStopID> = None;
+        for (idx, leg) in trip.legs.iter().enumerate() {
+            let (mode, req, end) = match leg {
+                TripLeg::Walk(spot) => {
+                    let (goal, end) = match spot.connection {
+                        SidewalkPOI::DeferredParkingSpot => match trip.legs.get(idx + 1) {
+                            Some(TripLeg::Drive(car, _)) => match parking.lookup_parked_car(*car) {
+                                Some(p) => (
+                                    Some(parking.spot_to_sidewalk_pos(p.spot, map)),
+                                    Some(parking.spot_to_driving_pos(p.spot, &p.vehicle, map)),
+                                ),
+                                None => (None, None),
+                            },
+                            _ => (None, None),
+                        },
+                        SidewalkPOI::BikeRack(driving_pos) => {
+                            (Some(spot.sidewalk_pos), Some(driving_pos))
+                        }
+                        _ => (Some(spot.sidewalk_pos), Some(spot.sidewalk_pos)),
+                    };
+                    at_stop = match spot.connection {
+                        SidewalkPOI::TransitStop(ts) => Some(ts),
+                        _ => None,
+                    };
+                    let req = prev_end
+                        .zip(goal)
+                        .map(|(start, goal)| PathRequest::walking(start, goal));
+                    (TripMode::Walk, req, end)
+                }
+                TripLeg::Drive(car, goal) => {
+                    let (mode, constraints) = if car.vehicle_type == VehicleType::Bike {
+                        (TripMode::Bike, PathConstraints::Bike)
+                    } else {
+                        (TripMode::Drive, PathConstraints::Car)
+                    };
+                    let req = prev_end
+                        .zip(goal.goal_pos(constraints, map))
+                        .map(|(start, end)| PathRequest::vehicle(start, end, constraints));
+                    // Bikes stop at a known rack, but where cars will park isn't known yet
+                    let end = match goal {
+                        DrivingGoal::ParkNear(b) if mode == TripMode::Bike => {
+                            SidewalkSpot::bike_rack(*b, map).map(|spot| spot.sidewalk_pos)
+                        }
+                        _ => None,
+                    };
+                    (mode, req, end)
+                }
+                TripLeg::RideBus(route, maybe_stop2) => {
+                    let req = at_stop.zip(*maybe_stop2).map(|(stop1, stop2)| {
+                        PathRequest::vehicle(
+                            map.get_ts(stop1).driving_pos,
+                            map.get_ts(stop2).driving_pos,
+                            map.get_tr(*route).route_type,
+                        )
+                    });
+                    at_stop = *maybe_stop2;
+                    (
+                        TripMode::Transit,
+                        req,
+                        maybe_stop2.map(|ts| map.get_ts(ts).sidewalk_pos),
+                    )
+                }
+            };
+            // The first leg is already underway
+            if idx > 0 {
+                planned.push(PlannedLeg { mode, req });
+            }
+            prev_end = end;
+        }
+        planned
+    }
+
+    pub fn get_bike_share_stations(&self) -> &Vec<BikeShareStationState> {
+        self.bike_share.get_stations()
+    }
+
     pub fn num_agents(&self, transit: &TransitSimState) -> Counter<AgentType> {
         let mut cnt = Counter::new();
         for a in self.active_trip_mode.keys() {
@@ -1343,6 +1543,15 @@ impl Trip {
     }
 }
 
+/// A leg of an ongoing trip that hasn't started yet
+#[derive(Clone, Debug)]
+pub struct PlannedLeg {
+    pub mode: TripMode,
+    /// None if where the leg starts or ends isn't known yet -- like walking from wherever a car
+    /// winds up parking, or riding transit off the map.
+    pub req: Option<PathRequest>,
+}
+
 /// These don't specify where the leg starts, since it might be unknown -- like when we drive and
 /// don't know where we'll wind up parking.
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
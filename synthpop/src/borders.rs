@@ -1,4 +1,4 @@
-use geom::{LonLat, Pt2D};
+use geom::{Duration, LonLat, Pt2D, Speed};
 use map_model::osm::RoadRank;
 use map_model::{Intersection, IntersectionID, Map, PathConstraints};
 
@@ -24,6 +24,9 @@ pub struct MapBorder {
     /// Based on the classification of the connecting road, a weight for how likely this border is
     /// to be used for traffic.
     pub weight: usize,
+    /// How fast vehicles probably move on the roads outside the map leading to this border, also
+    /// based on the classification of the connecting road.
+    pub off_map_speed: Speed,
 }
 
 impl MapBorders {
@@ -94,10 +97,10 @@ impl MapBorder {
     fn new(map: &Map, i: &Intersection) -> Self {
         // TODO Mostly untuned, and agnostic to TripMode
         let road = map.get_r(*i.roads.iter().next().unwrap());
-        let mut weight = match road.get_rank() {
-            RoadRank::Local => 3,
-            RoadRank::Arterial => 5,
-            RoadRank::Highway => 8,
+        let (mut weight, off_map_speed) = match road.get_rank() {
+            RoadRank::Local => (3, Speed::km_per_hour(30.0)),
+            RoadRank::Arterial => (5, Speed::km_per_hour(50.0)),
+            RoadRank::Highway => (8, Speed::km_per_hour(90.0)),
         };
         // TODO We should consider more values for RoadRank
         if road.is_service() {
@@ -110,6 +113,22 @@ impl MapBorder {
             pos,
             gps_pos: pos.to_gps(map.get_gps_bounds()),
             weight,
+            off_map_speed,
         }
     }
+
+    /// Estimate how long it takes to travel between some point off-map and this border. The roads
+    /// outside the map aren't known, so this uses the straight-line distance, with an allowance
+    /// for the route not being straight.
+    pub fn off_map_travel_time(&self, pt: Pt2D, mode: TripMode) -> Duration {
+        // Typical ratio of network to straight-line distance
+        let dist = 1.3 * self.pos.dist_to(pt);
+        let speed = match mode {
+            TripMode::Walk => Speed::km_per_hour(4.5),
+            TripMode::Bike => Speed::km_per_hour(15.0),
+            // Off-map, transit riders are probably on a bus or train using the same roads
+            TripMode::Transit | TripMode::Drive => self.off_map_speed,
+        };
+        dist / speed
+    }
 }
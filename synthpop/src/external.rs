@@ -4,7 +4,8 @@
 use anyhow::Result;
 use serde::Deserialize;
 
-use geom::{Distance, FindClosest, LonLat, Time};
+use abstutil::prettyprint_usize;
+use geom::{Distance, Duration, FindClosest, LonLat, PolyLine, Time};
 use map_model::Map;
 
 use crate::{IndividTrip, MapBorders, PersonSpec, TripEndpoint, TripMode, TripPurpose};
@@ -32,10 +33,14 @@ pub enum ExternalTripEndpoint {
 impl ExternalPerson {
     /// Import external scenario data. The main difference between `ExternalPerson` and
     /// `PersonSpec` is a way to specify endpoints by a `LonLat`. This is snapped to the nearest
-    /// building. If the point is outside of the map boundary, it's snapped to the nearest border
-    /// (by Euclidean distance -- the network outside the given map isn't known). Failure happens
-    /// if a point is within the map, but not close enough to any buildings. If `skip_problems` is
-    /// true, then those failures are logged; otherwise this panics at the first problem.
+    /// building. If the point is outside of the map boundary, it's snapped to the border that's
+    /// quickest to reach (estimating travel time by straight-line distance and the kind of road at
+    /// the border -- the network outside the given map isn't known). Trips starting off-map depart
+    /// later by that travel time, so they re-enter the map at a realistic time. Trips entirely
+    /// off-map are skipped. Failure happens if a point is within the map, but not close enough to
+    /// any buildings, or if reaching the border would delay a trip past the person's next one. If
+    /// `skip_problems` is true, then those failures are logged; otherwise this panics at the first
+    /// problem.
     pub fn import(
         map: &Map,
         input: Vec<ExternalPerson>,
@@ -46,37 +51,50 @@ impl ExternalPerson {
             closest.add_polygon(TripEndpoint::Building(b.id), &b.polygon);
         }
         let borders = MapBorders::new(map);
+        let off_map_pt = |endpt: &ExternalTripEndpoint| match endpt {
+            ExternalTripEndpoint::Position(gps) => {
+                let pt = gps.to_pt(map.get_gps_bounds());
+                if map.get_boundary_polygon().contains_pt(pt) {
+                    None
+                } else {
+                    Some(pt)
+                }
+            }
+            ExternalTripEndpoint::TripEndpoint(_) => None,
+        };
 
+        // Also returns the time needed to travel between an off-map point and its border
         let lookup_pt = |endpt, is_origin, mode| match endpt {
-            ExternalTripEndpoint::TripEndpoint(endpt) => Ok(endpt),
+            ExternalTripEndpoint::TripEndpoint(endpt) => Ok((endpt, Duration::ZERO)),
             ExternalTripEndpoint::Position(gps) => {
                 let pt = gps.to_pt(map.get_gps_bounds());
                 if map.get_boundary_polygon().contains_pt(pt) {
                     match closest.closest_pt(pt, Distance::meters(100.0)) {
-                        Some((x, _)) => Ok(x),
+                        Some((x, _)) => Ok((x, Duration::ZERO)),
                         None => Err(anyhow!("No building within 100m of {}", gps)),
                     }
                 } else {
                     let (incoming, outgoing) = borders.for_mode(mode);
                     let candidates = if is_origin { incoming } else { outgoing };
-                    Ok(TripEndpoint::Border(
-                        candidates
-                            .iter()
-                            .min_by_key(|border| border.gps_pos.fast_dist(gps))
-                            .ok_or_else(|| anyhow!("No border for {}", mode.ongoing_verb()))?
-                            .i,
-                    ))
+                    let (border, time) = candidates
+                        .iter()
+                        .map(|border| (border.i, border.off_map_travel_time(pt, mode)))
+                        .min_by_key(|(_, time)| *time)
+                        .ok_or_else(|| anyhow!("No border for {}", mode.ongoing_verb()))?;
+                    Ok((TripEndpoint::Border(border), time))
                 }
             }
         };
 
         let mut results = Vec::new();
+        let mut skipped_off_map = 0;
         for person in input {
             let mut spec = PersonSpec {
                 orig_id: None,
                 trips: Vec::new(),
             };
-            for trip in person.trips {
+            let departures: Vec<Time> = person.trips.iter().map(|t| t.departure).collect();
+            for (idx, trip) in person.trips.into_iter().enumerate() {
                 if trip.departure < Time::START_OF_DAY {
                     if skip_problems {
                         warn!(
@@ -89,35 +107,86 @@ impl ExternalPerson {
                     }
                 }
 
-                spec.trips.push(IndividTrip::new(
-                    trip.departure,
-                    trip.purpose,
-                    match lookup_pt(trip.origin, true, trip.mode) {
-                        Ok(endpt) => endpt,
-                        Err(err) => {
-                            if skip_problems {
-                                warn!("Skipping person: {}", err);
-                                continue;
-                            } else {
-                                return Err(err);
-                            }
+                // Trips that don't touch the map at all happen entirely off-map. The person
+                // leaves the map on an earlier trip and comes back on a later one.
+                if let (Some(pt1), Some(pt2)) =
+                    (off_map_pt(&trip.origin), off_map_pt(&trip.destination))
+                {
+                    if !PolyLine::new(vec![pt1, pt2])
+                        .map(|pl| map.get_boundary_polygon().intersects_polyline(&pl))
+                        .unwrap_or(false)
+                    {
+                        skipped_off_map += 1;
+                        continue;
+                    }
+                }
+
+                let (origin, time_to_enter) = match lookup_pt(trip.origin, true, trip.mode) {
+                    Ok(pair) => pair,
+                    Err(err) => {
+                        if skip_problems {
+                            warn!("Skipping person: {}", err);
+                            continue;
+                        } else {
+                            return Err(err);
                         }
-                    },
-                    match lookup_pt(trip.destination, false, trip.mode) {
-                        Ok(endpt) => endpt,
-                        Err(err) => {
-                            if skip_problems {
-                                warn!("Skipping person: {}", err);
-                                continue;
-                            } else {
-                                return Err(err);
-                            }
+                    }
+                };
+                let (destination, _) = match lookup_pt(trip.destination, false, trip.mode) {
+                    Ok(pair) => pair,
+                    Err(err) => {
+                        if skip_problems {
+                            warn!("Skipping person: {}", err);
+                            continue;
+                        } else {
+                            return Err(err);
+                        }
+                    }
+                };
+                // A trip passing through a corner of the map may enter and leave by the same
+                // border. Then it's effectively off-map too.
+                if origin == destination && matches!(origin, TripEndpoint::Border(_)) {
+                    skipped_off_map += 1;
+                    continue;
+                }
+
+                // Reaching the border takes time. If the person wouldn't make it onto the map
+                // before their next trip, the schedule doesn't make sense.
+                let departure = trip.departure + time_to_enter;
+                if let Some(next) = departures.get(idx + 1) {
+                    if departure >= *next {
+                        let err = anyhow!(
+                            "Trip departing at {} takes {} to reach the map, but the next trip \
+                             departs at {}",
+                            trip.departure,
+                            time_to_enter,
+                            next
+                        );
+                        if skip_problems {
+                            warn!("Skipping trip: {}", err);
+                            continue;
+                        } else {
+                            return Err(err);
                         }
-                    },
+                    }
+                }
+                spec.trips.push(IndividTrip::new(
+                    departure,
+                    trip.purpose,
+                    origin,
+                    destination,
                     trip.mode,
                 ));
             }
-            results.push(spec);
+            if !spec.trips.is_empty() {
+                results.push(spec);
+            }
+        }
+        if skipped_off_map > 0 {
+            info!(
+                "Skipped {} trips happening entirely off-map",
+                prettyprint_usize(skipped_off_map)
+            );
         }
         Ok(results)
     }
@@ -19,16 +19,20 @@ pub use self::borders::{MapBorder, MapBorders};
 pub use self::counts::TrafficCounts;
 pub use self::endpoint::TripEndpoint;
 pub use self::external::{ExternalPerson, ExternalTrip, ExternalTripEndpoint};
+pub use self::merge::{DemandTotals, MergeReport};
 pub use self::modifier::ScenarioModifier;
 pub use self::scenario::{IndividTrip, PersonSpec, Scenario, TripPurpose};
+pub use self::validate::{ScenarioIssue, ScenarioProblem, ValidationReport};
 
 mod borders;
 mod counts;
 mod endpoint;
 mod external;
 pub mod make;
+mod merge;
 mod modifier;
 mod scenario;
+mod validate;
 
 /// How does a trip primarily happen?
 ///
@@ -1,7 +1,8 @@
 //! An activity model creates "people" that follow a set schedule of activities through the day.
 //! Each activity (like shopping, working, sleeping) lasts some time, and requires the person to go
 //! somewhere at some time. This is an extremely simple activity model that just uses data inferred
-//! from OSM.
+//! from OSM. Buildings are classified by their landuse and amenities into homes, workplaces,
+//! schools, and places to run errands, and trips are sent to them with matching purposes.
 
 use anyhow::Result;
 use rand::seq::SliceRandom;
@@ -10,7 +11,9 @@ use rand_xorshift::XorShiftRng;
 
 use abstutil::{prettyprint_usize, Timer};
 use geom::{Distance, Duration, Time};
-use map_model::{BuildingID, BuildingType, Map, PathConstraints, PathRequest};
+use map_model::{
+    Amenity, AmenityType, Building, BuildingID, BuildingType, Map, PathConstraints, PathRequest,
+};
 
 use crate::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
 
@@ -21,11 +24,27 @@ impl ScenarioGenerator {
     pub fn proletariat_robot(map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) -> Scenario {
         let mut residents: Vec<BuildingID> = Vec::new();
         let mut workers: Vec<BuildingID> = Vec::new();
+        // One entry per student
+        let mut students: Vec<BuildingID> = Vec::new();
+        // One entry per amenity, so buildings with more shops attract more errands
+        let mut errands: Vec<(BuildingID, TripPurpose)> = Vec::new();
 
         let mut num_bldg_residential = 0;
         let mut num_bldg_commercial = 0;
         let mut num_bldg_mixed_residential_commercial = 0;
         for b in map.all_buildings() {
+            if is_school(b) {
+                let num_students = (b.levels * b.polygon.area() / 10.0) as usize;
+                for _ in 0..num_students {
+                    students.push(b.id);
+                }
+            }
+            for amenity in &b.amenities {
+                if let Some(purpose) = errand_purpose(amenity) {
+                    errands.push((b.id, purpose));
+                }
+            }
+
             match b.bldg_type {
                 BuildingType::Residential { num_residents, .. } => {
                     for _ in 0..num_residents {
@@ -54,8 +73,9 @@ impl ScenarioGenerator {
 
         residents.shuffle(rng);
         workers.shuffle(rng);
+        students.shuffle(rng);
 
-        let mut s = Scenario::empty(map, "random people going to and from work");
+        let mut s = Scenario::empty(map, "random people going to and from work and school");
         // Include all buses/trains
         s.only_seed_buses = None;
 
@@ -84,12 +104,18 @@ impl ScenarioGenerator {
             upper_bound_prob,
             f64::max(lower_bound_prob, workers_cap as f64 / num_trips as f64),
         );
+        // Also arbitrary guesses. Only local residents go to school here.
+        let prob_student = 0.15;
+        let prob_errand = 0.3;
 
         debug!(
-            "BUILDINGS - workplaces: {}, residences: {}, mixed: {}",
+            "BUILDINGS - workplaces: {}, residences: {}, mixed: {}, student capacity: {}, \
+             errand destinations: {}",
             prettyprint_usize(num_bldg_commercial),
             prettyprint_usize(num_bldg_residential),
-            prettyprint_usize(num_bldg_mixed_residential_commercial)
+            prettyprint_usize(num_bldg_mixed_residential_commercial),
+            prettyprint_usize(students.len()),
+            prettyprint_usize(errands.len())
         );
         debug!(
             "CAPACITY - workers_cap: {}, residents_cap: {}, prob_local_worker: {:.1}%, \
@@ -104,6 +130,8 @@ impl ScenarioGenerator {
         let mut num_trips_commuting_in = 0;
         let mut num_trips_commuting_out = 0;
         let mut num_trips_passthru = 0;
+        let mut num_students = 0;
+        let mut num_errands = 0;
         timer.start("create people");
 
         // Only consider two-way intersections, so the agent can return the same way
@@ -134,14 +162,23 @@ impl ScenarioGenerator {
                     *commuter_borders.choose(rng)?
                 };
 
-                let work = if is_local_worker {
+                let is_student = matches!(home, TripEndpoint::Building(_))
+                    && !students.is_empty()
+                    && rng.gen_bool(prob_student);
+                let (work, purpose) = if is_student {
+                    num_students += 1;
+                    (
+                        TripEndpoint::Building(students.pop().unwrap()),
+                        TripPurpose::School,
+                    )
+                } else if is_local_worker {
                     if let Some(workplace) = workers.pop() {
-                        TripEndpoint::Building(workplace)
+                        (TripEndpoint::Building(workplace), TripPurpose::Work)
                     } else {
-                        *commuter_borders.choose(rng)?
+                        (*commuter_borders.choose(rng)?, TripPurpose::Work)
                     }
                 } else {
-                    *commuter_borders.choose(rng)?
+                    (*commuter_borders.choose(rng)?, TripPurpose::Work)
                 };
 
                 match (&home, &work) {
@@ -161,7 +198,21 @@ impl ScenarioGenerator {
                     (_, TripEndpoint::SuddenlyAppear(_)) => unreachable!(),
                 };
 
-                Some((home, work, fork_rng(rng)))
+                // Stop somewhere on the way home. People just passing through the map don't.
+                let errand = if !errands.is_empty()
+                    && !matches!(
+                        (&home, &work),
+                        (TripEndpoint::Border(_), TripEndpoint::Border(_))
+                    )
+                    && rng.gen_bool(prob_errand)
+                {
+                    num_errands += 1;
+                    Some(choose_errand(&errands, work, map, rng))
+                } else {
+                    None
+                };
+
+                Some((home, work, purpose, errand, fork_rng(rng)))
             })
             .collect();
 
@@ -170,7 +221,9 @@ impl ScenarioGenerator {
                 .parallelize(
                     "create people: making PersonSpec from endpoints",
                     person_params,
-                    |(home, work, mut rng)| match create_prole(home, work, map, &mut rng) {
+                    |(home, work, purpose, errand, mut rng)| match create_prole(
+                        home, work, purpose, errand, map, &mut rng,
+                    ) {
                         Ok(person) => Some(person),
                         Err(e) => {
                             trace!("Unable to create person. error: {}", e);
@@ -186,23 +239,85 @@ impl ScenarioGenerator {
 
         info!(
             "TRIPS - total: {}, local: {}, commuting_in: {}, commuting_out: {}, passthru: {}, \
-             errored: {}, leftover_resident_capacity: {}, leftover_worker_capacity: {}",
+             students: {}, errands: {}, errored: {}, leftover_resident_capacity: {}, \
+             leftover_worker_capacity: {}, leftover_student_capacity: {}",
             prettyprint_usize(num_trips),
             prettyprint_usize(num_trips_local),
             prettyprint_usize(num_trips_commuting_in),
             prettyprint_usize(num_trips_commuting_out),
             prettyprint_usize(num_trips_passthru),
+            prettyprint_usize(num_students),
+            prettyprint_usize(num_errands),
             prettyprint_usize(num_trips - s.people.len()),
             prettyprint_usize(residents.len()),
             prettyprint_usize(workers.len()),
+            prettyprint_usize(students.len()),
         );
         s
     }
 }
 
-fn create_prole(
+/// Schools, universities, and childcare, by amenity or by the land they're on
+pub(crate) fn is_school(b: &Building) -> bool {
+    b.has_amenity(AmenityType::School)
+        || b.has_amenity(AmenityType::University)
+        || b.has_amenity(AmenityType::Childcare)
+        || b.osm_tags.is_any("building", vec!["school", "university"])
+        || b.osm_tags.is("landuse", "education")
+}
+
+/// Why somebody would stop at this amenity on the way home, if at all
+pub(crate) fn errand_purpose(amenity: &Amenity) -> Option<TripPurpose> {
+    match AmenityType::categorize(&amenity.amenity_type)? {
+        AmenityType::Bar | AmenityType::Cafe | AmenityType::FastFood | AmenityType::Food => {
+            Some(TripPurpose::Meal)
+        }
+        AmenityType::Beauty
+        | AmenityType::Bike
+        | AmenityType::ConvenienceStore
+        | AmenityType::Laundry
+        | AmenityType::Pet
+        | AmenityType::Shopping
+        | AmenityType::Supermarket => Some(TripPurpose::Shopping),
+        AmenityType::Bank | AmenityType::CarRepair | AmenityType::PostOffice => {
+            Some(TripPurpose::PersonalBusiness)
+        }
+        AmenityType::Medical => Some(TripPurpose::Medical),
+        AmenityType::Culture
+        | AmenityType::Exercise
+        | AmenityType::GreenSpace
+        | AmenityType::Playground
+        | AmenityType::Pool => Some(TripPurpose::Recreation),
+        AmenityType::Library | AmenityType::Religious => Some(TripPurpose::Social),
+        AmenityType::CarShare
+        | AmenityType::Childcare
+        | AmenityType::Hotel
+        | AmenityType::School
+        | AmenityType::Tourism
+        | AmenityType::University => None,
+    }
+}
+
+/// Pick a few random errand destinations, and go to the one closest to work. Busier places are
+/// more likely to be picked, since every amenity is a separate candidate.
+pub(crate) fn choose_errand(
+    errands: &[(BuildingID, TripPurpose)],
+    work: TripEndpoint,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> (BuildingID, TripPurpose) {
+    let work_pt = work.pt(map);
+    (0..3)
+        .map(|_| *errands.choose(rng).unwrap())
+        .min_by_key(|(b, _)| map.get_b(*b).polygon.center().dist_to(work_pt))
+        .unwrap()
+}
+
+pub(crate) fn create_prole(
     home: TripEndpoint,
     work: TripEndpoint,
+    purpose: TripPurpose,
+    errand: Option<(BuildingID, TripPurpose)>,
     map: &Map,
     rng: &mut XorShiftRng,
 ) -> Result<PersonSpec> {
@@ -242,16 +357,33 @@ fn create_prole(
     // TODO This will cause a single morning and afternoon rush. Outside of these times,
     // it'll be really quiet. Probably want a normal distribution centered around these
     // peak times, but with a long tail.
-    let mut depart_am = rand_time(
-        rng,
-        Time::START_OF_DAY + Duration::hours(7),
-        Time::START_OF_DAY + Duration::hours(10),
-    );
-    let mut depart_pm = rand_time(
-        rng,
-        Time::START_OF_DAY + Duration::hours(17),
-        Time::START_OF_DAY + Duration::hours(19),
-    );
+    let (mut depart_am, mut depart_pm) = if matches!(purpose, TripPurpose::School) {
+        (
+            rand_time(
+                rng,
+                Time::START_OF_DAY + Duration::hours(7),
+                Time::START_OF_DAY + Duration::hours(8) + Duration::minutes(30),
+            ),
+            rand_time(
+                rng,
+                Time::START_OF_DAY + Duration::hours(14) + Duration::minutes(30),
+                Time::START_OF_DAY + Duration::hours(16),
+            ),
+        )
+    } else {
+        (
+            rand_time(
+                rng,
+                Time::START_OF_DAY + Duration::hours(7),
+                Time::START_OF_DAY + Duration::hours(10),
+            ),
+            rand_time(
+                rng,
+                Time::START_OF_DAY + Duration::hours(17),
+                Time::START_OF_DAY + Duration::hours(19),
+            ),
+        )
+    };
 
     if rng.gen_bool(0.1) {
         // hacky hack to get some background traffic
@@ -267,16 +399,47 @@ fn create_prole(
         );
     }
 
+    let mut trips = vec![IndividTrip::new(depart_am, purpose, home, work, mode)];
+    match errand {
+        Some((b, errand_purpose))
+            if TripEndpoint::Building(b) != home && TripEndpoint::Building(b) != work =>
+        {
+            let errand = TripEndpoint::Building(b);
+            trips.push(IndividTrip::new(
+                depart_pm,
+                errand_purpose,
+                work,
+                errand,
+                mode,
+            ));
+            // Linger there for a while before heading home
+            let depart_errand = depart_pm + Duration::minutes(rng.gen_range(20..90));
+            trips.push(IndividTrip::new(
+                depart_errand,
+                TripPurpose::Home,
+                errand,
+                home,
+                mode,
+            ));
+        }
+        _ => {
+            trips.push(IndividTrip::new(
+                depart_pm,
+                TripPurpose::Home,
+                work,
+                home,
+                mode,
+            ));
+        }
+    }
+
     Ok(PersonSpec {
         orig_id: None,
-        trips: vec![
-            IndividTrip::new(depart_am, TripPurpose::Work, home, work, mode),
-            IndividTrip::new(depart_pm, TripPurpose::Home, work, home, mode),
-        ],
+        trips,
     })
 }
 
-fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
+pub(crate) fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
     // TODO Make this probabilistic
     // for example probability of walking currently has massive differences
     // at thresholds, it would be nicer to change this gradually
@@ -315,7 +478,7 @@ fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
     TripMode::Drive
 }
 
-fn rand_time(rng: &mut XorShiftRng, low: Time, high: Time) -> Time {
+pub(crate) fn rand_time(rng: &mut XorShiftRng, low: Time, high: Time) -> Time {
     assert!(high > low);
     Time::START_OF_DAY + Duration::seconds(rng.gen_range(low.inner_seconds()..high.inner_seconds()))
 }
@@ -0,0 +1,187 @@
+//! When a map is clipped from a larger city, lots of traffic just passes through or starts and ends
+//! somewhere off-map. This generates that external demand from how many vehicles use each border
+//! every hour, either guessed from the class of road or supplied by the user.
+
+use std::collections::BTreeMap;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstutil::prettyprint_usize;
+use geom::{Duration, Time};
+use map_model::osm::RoadRank;
+use map_model::{BuildingID, Intersection, IntersectionID, Map, PathConstraints};
+
+use crate::make::ScenarioGenerator;
+use crate::{IndividTrip, PersonSpec, TripEndpoint, TripMode, TripPurpose};
+
+/// Relative traffic volume for each hour of the day, with a morning and evening peak
+const HOURLY_PROFILE: [f64; 24] = [
+    0.1, 0.06, 0.05, 0.05, 0.1, 0.3, 0.7, 1.0, 0.95, 0.7, 0.6, 0.65, 0.7, 0.65, 0.7, 0.8, 0.95,
+    1.0, 0.8, 0.6, 0.45, 0.35, 0.25, 0.15,
+];
+
+/// How many vehicles enter and leave the map through one border, per hour of the day.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BorderDemand {
+    pub i: IntersectionID,
+    /// Indexed by the hour of the day
+    pub inflow_per_hour: Vec<usize>,
+    /// Indexed by the hour of the day
+    pub outflow_per_hour: Vec<usize>,
+}
+
+impl BorderDemand {
+    /// Guesses demand from the class of road and how many lanes cross the border. `scale` adjusts
+    /// everything, since a small map probably can't absorb a full day of highway traffic.
+    pub fn from_road_class(map: &Map, i: &Intersection, scale: f64) -> BorderDemand {
+        let road = map.get_r(*i.roads.iter().next().unwrap());
+        // Vehicles per lane during the peak hour
+        let peak_per_lane = if road.is_service() {
+            10.0
+        } else {
+            match road.get_rank() {
+                RoadRank::Local => 50.0,
+                RoadRank::Arterial => 400.0,
+                RoadRank::Highway => 1000.0,
+            }
+        };
+        let per_hour = |num_lanes: usize| -> Vec<usize> {
+            HOURLY_PROFILE
+                .iter()
+                .map(|pct| (scale * peak_per_lane * pct * (num_lanes as f64)).round() as usize)
+                .collect()
+        };
+        BorderDemand {
+            i: i.id,
+            inflow_per_hour: per_hour(i.get_outgoing_lanes(map, PathConstraints::Car).len()),
+            outflow_per_hour: per_hour(i.get_incoming_lanes(map, PathConstraints::Car).len()),
+        }
+    }
+
+    /// Guesses demand for every border of the map from its road class.
+    pub fn all_from_road_class(map: &Map, scale: f64) -> Vec<BorderDemand> {
+        map.all_intersections()
+            .iter()
+            .filter(|i| i.is_border())
+            .map(|i| BorderDemand::from_road_class(map, i, scale))
+            .collect()
+    }
+}
+
+impl ScenarioGenerator {
+    /// Creates one driving trip per vehicle entering or leaving the map. Some of the vehicles
+    /// entering just pass through, leaving by another border that hour. Everybody else starts or
+    /// ends at a building, weighted by floor area.
+    pub fn external_demand(
+        map: &Map,
+        demand: &[BorderDemand],
+        pct_through: f64,
+        rng: &mut XorShiftRng,
+    ) -> Vec<PersonSpec> {
+        let bldgs: Vec<BuildingID> = map.all_buildings().iter().map(|b| b.id).collect();
+        let bldg_weights = WeightedIndex::new(
+            map.all_buildings()
+                .iter()
+                .map(|b| b.levels.max(1.0) * b.polygon.area()),
+        )
+        .ok();
+
+        let mut people = Vec::new();
+        // How many vehicles passing through leave by each border, per hour
+        let mut through: BTreeMap<(IntersectionID, usize), usize> = BTreeMap::new();
+        let mut num_through = 0;
+        let mut num_inbound = 0;
+        let mut num_outbound = 0;
+
+        for border in demand {
+            for (hour, inflow) in border.inflow_per_hour.iter().enumerate() {
+                // Other borders with traffic leaving this hour
+                let exits: Vec<(IntersectionID, usize)> = demand
+                    .iter()
+                    .filter(|d| d.i != border.i)
+                    .filter_map(|d| {
+                        let n = d.outflow_per_hour.get(hour).cloned().unwrap_or(0);
+                        if n > 0 {
+                            Some((d.i, n))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                let exit_weights = WeightedIndex::new(exits.iter().map(|(_, n)| *n)).ok();
+
+                for _ in 0..*inflow {
+                    let depart = rand_time_in_hour(rng, hour);
+                    let destination = match exit_weights {
+                        Some(ref weights) if rng.gen_bool(pct_through) => {
+                            let exit = exits[weights.sample(rng)].0;
+                            *through.entry((exit, hour)).or_insert(0) += 1;
+                            num_through += 1;
+                            TripEndpoint::Border(exit)
+                        }
+                        _ => {
+                            if let Some(ref weights) = bldg_weights {
+                                num_inbound += 1;
+                                TripEndpoint::Building(bldgs[weights.sample(rng)])
+                            } else {
+                                continue;
+                            }
+                        }
+                    };
+                    people.push(single_trip(
+                        depart,
+                        TripEndpoint::Border(border.i),
+                        destination,
+                    ));
+                }
+            }
+        }
+
+        // Whatever's left of each border's outflow starts somewhere on the map
+        if let Some(ref weights) = bldg_weights {
+            for border in demand {
+                for (hour, outflow) in border.outflow_per_hour.iter().enumerate() {
+                    let already = through.get(&(border.i, hour)).cloned().unwrap_or(0);
+                    for _ in 0..outflow.saturating_sub(already) {
+                        num_outbound += 1;
+                        people.push(single_trip(
+                            rand_time_in_hour(rng, hour),
+                            TripEndpoint::Building(bldgs[weights.sample(rng)]),
+                            TripEndpoint::Border(border.i),
+                        ));
+                    }
+                }
+            }
+        }
+
+        info!(
+            "External demand: {} passing through, {} entering, {} leaving",
+            prettyprint_usize(num_through),
+            prettyprint_usize(num_inbound),
+            prettyprint_usize(num_outbound)
+        );
+        people
+    }
+}
+
+fn single_trip(depart: Time, from: TripEndpoint, to: TripEndpoint) -> PersonSpec {
+    PersonSpec {
+        orig_id: None,
+        trips: vec![IndividTrip::new(
+            depart,
+            TripPurpose::Work,
+            from,
+            to,
+            TripMode::Drive,
+        )],
+    }
+}
+
+fn rand_time_in_hour(rng: &mut XorShiftRng, hour: usize) -> Time {
+    Time::START_OF_DAY
+        + Duration::hours(hour)
+        + Duration::seconds(rng.gen_range(0.0..Duration::hours(1).inner_seconds()))
+}
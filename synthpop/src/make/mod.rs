@@ -3,10 +3,14 @@
 use rand::{RngCore, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
+pub use self::border_demand::BorderDemand;
 pub use self::generator::{BorderSpawnOverTime, ScenarioGenerator, SpawnOverTime};
+pub use self::population::{PopulationOptions, ZoneTarget};
 
 mod activity_model;
+mod border_demand;
 mod generator;
+mod population;
 
 /// Need to explain this trick -- basically keeps consistency between two different simulations when
 /// each one might make slightly different sequences of calls to the RNG.
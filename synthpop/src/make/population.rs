@@ -0,0 +1,321 @@
+//! Synthesizes a full-day population for places without any travel survey, starting only from how
+//! many people live and work in each zone. Households are spread over residential buildings and
+//! jobs over commercial buildings, both weighted by floor area, and then everybody gets a simple
+//! schedule like the proletariat robot's.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Polygon, Time};
+use map_model::{BuildingID, BuildingType, Map, PathConstraints, PathRequest};
+
+use crate::make::activity_model::{
+    choose_errand, create_prole, errand_purpose, is_school, rand_time, select_trip_mode,
+};
+use crate::make::{fork_rng, ScenarioGenerator};
+use crate::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripPurpose};
+
+/// How many people live and work in some zone, like a census tract.
+pub struct ZoneTarget {
+    pub name: String,
+    pub polygon: Polygon,
+    pub population: usize,
+    /// How many jobs are located in this zone
+    pub employment: usize,
+}
+
+/// Arbitrarily chosen parameters for synthesizing people. These vary by city.
+pub struct PopulationOptions {
+    /// (Number of people, relative weight)
+    pub household_sizes: Vec<(usize, f64)>,
+    /// Everybody in a household except for the first person might be a child
+    pub pct_children: f64,
+    /// How many adults have a job, on or off the map
+    pub pct_employed: f64,
+    /// How many adults without a job run an errand in the middle of the day
+    pub pct_errands: f64,
+}
+
+impl Default for PopulationOptions {
+    fn default() -> PopulationOptions {
+        PopulationOptions {
+            household_sizes: vec![(1, 0.28), (2, 0.35), (3, 0.15), (4, 0.14), (5, 0.08)],
+            pct_children: 0.3,
+            pct_employed: 0.7,
+            pct_errands: 0.5,
+        }
+    }
+}
+
+/// What somebody will do during the day
+enum Plan {
+    Commute(TripEndpoint, TripEndpoint, TripPurpose),
+    Errand(BuildingID, BuildingID, TripPurpose),
+}
+
+impl ScenarioGenerator {
+    /// Creates households in each zone until the population target is met, then gives each adult
+    /// a job in some zone's commercial buildings, or off-map once those run out. Any jobs left
+    /// over are filled by people commuting in from borders. Children go to school if there's one
+    /// on the map.
+    pub fn from_zone_targets(
+        map: &Map,
+        zones: Vec<ZoneTarget>,
+        opts: PopulationOptions,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
+        let mut s = Scenario::empty(map, "synthesized population");
+        // Include all buses/trains
+        s.only_seed_buses = None;
+
+        // Per zone, weighted by floor area
+        let mut homes_per_zone: Vec<Vec<(BuildingID, f64)>> = vec![Vec::new(); zones.len()];
+        let mut workplaces_per_zone: Vec<Vec<(BuildingID, f64)>> = vec![Vec::new(); zones.len()];
+        let mut schools: Vec<BuildingID> = Vec::new();
+        let mut errands: Vec<(BuildingID, TripPurpose)> = Vec::new();
+        timer.start_iter("match buildings to zones", map.all_buildings().len());
+        for b in map.all_buildings() {
+            timer.next();
+            if is_school(b) {
+                schools.push(b.id);
+            }
+            for amenity in &b.amenities {
+                if let Some(purpose) = errand_purpose(amenity) {
+                    errands.push((b.id, purpose));
+                }
+            }
+
+            let center = b.polygon.center();
+            // Assume zones don't overlap
+            if let Some(idx) = zones.iter().position(|z| z.polygon.contains_pt(center)) {
+                let floor_area = b.levels.max(1.0) * b.polygon.area();
+                let homes = &mut homes_per_zone[idx];
+                let workplaces = &mut workplaces_per_zone[idx];
+                match b.bldg_type {
+                    BuildingType::Residential { .. } => {
+                        homes.push((b.id, floor_area));
+                    }
+                    BuildingType::ResidentialCommercial(_, _) => {
+                        homes.push((b.id, floor_area));
+                        workplaces.push((b.id, floor_area));
+                    }
+                    BuildingType::Commercial(_) => {
+                        workplaces.push((b.id, floor_area));
+                    }
+                    BuildingType::Empty => {}
+                }
+            }
+        }
+
+        // Only consider two-way borders, so people can return the same way they came
+        let commuter_borders: Vec<TripEndpoint> = map
+            .all_outgoing_borders()
+            .into_iter()
+            .filter(|b| b.is_incoming_border())
+            .map(|b| TripEndpoint::Border(b.id))
+            .collect();
+
+        // If a zone only partly overlaps the map, scale down its targets by the overlapping area
+        let pct_overlap: Vec<f64> = zones
+            .iter()
+            .map(|z| {
+                let mut overlapping_area = 0.0;
+                if let Ok(list) = z.polygon.intersection(map.get_boundary_polygon()) {
+                    for p in list {
+                        overlapping_area += p.area();
+                    }
+                }
+                (overlapping_area / z.polygon.area()).min(1.0)
+            })
+            .collect();
+
+        let mut jobs: Vec<BuildingID> = Vec::new();
+        for ((zone, workplaces), pct) in zones.iter().zip(&workplaces_per_zone).zip(&pct_overlap) {
+            if workplaces.is_empty() {
+                warn!(
+                    "Zone {} has no commercial buildings for its jobs",
+                    zone.name
+                );
+                continue;
+            }
+            for _ in 0..((zone.employment as f64) * pct) as usize {
+                jobs.push(workplaces.choose_weighted(rng, |(_, n)| *n).unwrap().0);
+            }
+        }
+        jobs.shuffle(rng);
+
+        let mut plans: Vec<Plan> = Vec::new();
+        let mut num_households = 0;
+        let mut num_residents = 0;
+        let mut num_students = 0;
+        let mut num_work_remote = 0;
+        let mut num_stay_home = 0;
+        for ((zone, homes), pct) in zones.iter().zip(&homes_per_zone).zip(&pct_overlap) {
+            if homes.is_empty() {
+                warn!("Zone {} has no residential buildings", zone.name);
+                continue;
+            }
+            let population = ((zone.population as f64) * pct) as usize;
+            let mut placed = 0;
+            while placed < population {
+                let size = opts
+                    .household_sizes
+                    .choose_weighted(rng, |(_, weight)| *weight)
+                    .unwrap()
+                    .0
+                    .min(population - placed);
+                let home = homes.choose_weighted(rng, |(_, n)| *n).unwrap().0;
+                num_households += 1;
+                placed += size;
+
+                for idx in 0..size {
+                    num_residents += 1;
+                    if idx > 0 && rng.gen_bool(opts.pct_children) {
+                        if let Some(school) = closest_school(&schools, home, map, rng) {
+                            num_students += 1;
+                            plans.push(Plan::Commute(
+                                TripEndpoint::Building(home),
+                                TripEndpoint::Building(school),
+                                TripPurpose::School,
+                            ));
+                        } else {
+                            num_stay_home += 1;
+                        }
+                    } else if rng.gen_bool(opts.pct_employed) {
+                        let work = if let Some(b) = jobs.pop() {
+                            TripEndpoint::Building(b)
+                        } else if let Some(border) = commuter_borders.choose(rng) {
+                            num_work_remote += 1;
+                            *border
+                        } else {
+                            continue;
+                        };
+                        plans.push(Plan::Commute(
+                            TripEndpoint::Building(home),
+                            work,
+                            TripPurpose::Work,
+                        ));
+                    } else if !errands.is_empty() && rng.gen_bool(opts.pct_errands) {
+                        let (b, purpose) =
+                            choose_errand(&errands, TripEndpoint::Building(home), map, rng);
+                        plans.push(Plan::Errand(home, b, purpose));
+                    } else {
+                        num_stay_home += 1;
+                    }
+                }
+            }
+        }
+
+        // Whoever else works here lives off-map
+        let num_commute_in = if commuter_borders.is_empty() {
+            0
+        } else {
+            jobs.len()
+        };
+        for b in jobs.drain(..) {
+            if let Some(border) = commuter_borders.choose(rng) {
+                plans.push(Plan::Commute(
+                    *border,
+                    TripEndpoint::Building(b),
+                    TripPurpose::Work,
+                ));
+            }
+        }
+
+        let plans = plans
+            .into_iter()
+            .map(|plan| (plan, fork_rng(rng)))
+            .collect();
+        s.people.extend(
+            timer
+                .parallelize("synthesize people", plans, |(plan, mut rng)| match plan {
+                    Plan::Commute(home, work, purpose) => {
+                        match create_prole(home, work, purpose, None, map, &mut rng) {
+                            Ok(person) => Some(person),
+                            Err(e) => {
+                                trace!("Unable to create person. error: {}", e);
+                                None
+                            }
+                        }
+                    }
+                    Plan::Errand(home, errand, purpose) => {
+                        create_errand_runner(home, errand, purpose, map, &mut rng)
+                    }
+                })
+                .into_iter()
+                .flatten(),
+        );
+
+        info!(
+            "POPULATION - households: {}, residents: {}, students: {}, working off-map: {}, \
+             commuting in: {}, staying home: {}, people with trips: {}",
+            prettyprint_usize(num_households),
+            prettyprint_usize(num_residents),
+            prettyprint_usize(num_students),
+            prettyprint_usize(num_work_remote),
+            prettyprint_usize(num_commute_in),
+            prettyprint_usize(num_stay_home),
+            prettyprint_usize(s.people.len()),
+        );
+        s
+    }
+}
+
+/// Pick a few random schools and go to the closest one
+fn closest_school(
+    schools: &[BuildingID],
+    home: BuildingID,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> Option<BuildingID> {
+    let home_pt = map.get_b(home).polygon.center();
+    (0..3)
+        .filter_map(|_| schools.choose(rng).cloned())
+        .min_by_key(|b| map.get_b(*b).polygon.center().dist_to(home_pt))
+}
+
+/// Somebody who leaves home once in the middle of the day
+fn create_errand_runner(
+    home: BuildingID,
+    errand: BuildingID,
+    purpose: TripPurpose,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> Option<PersonSpec> {
+    if home == errand {
+        return None;
+    }
+    let dist = PathRequest::between_buildings(map, home, errand, PathConstraints::Pedestrian)
+        .and_then(|req| map.pathfind(req).ok())?
+        .total_length();
+    let mode = select_trip_mode(dist, rng);
+
+    let depart = rand_time(
+        rng,
+        Time::START_OF_DAY + Duration::hours(10),
+        Time::START_OF_DAY + Duration::hours(16),
+    );
+    let return_home = depart + Duration::minutes(rng.gen_range(20..120));
+    Some(PersonSpec {
+        orig_id: None,
+        trips: vec![
+            IndividTrip::new(
+                depart,
+                purpose,
+                TripEndpoint::Building(home),
+                TripEndpoint::Building(errand),
+                mode,
+            ),
+            IndividTrip::new(
+                return_home,
+                TripPurpose::Home,
+                TripEndpoint::Building(errand),
+                TripEndpoint::Building(home),
+                mode,
+            ),
+        ],
+    })
+}
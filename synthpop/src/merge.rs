@@ -0,0 +1,258 @@
+//! Overlay several scenarios, like a special event on top of a normal weekday.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstutil::prettyprint_usize;
+
+use crate::{OrigPersonID, Scenario, TripMode};
+
+/// How much travel demand a scenario has
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DemandTotals {
+    pub scenario_name: String,
+    pub people: usize,
+    pub trips: usize,
+    pub trips_per_mode: BTreeMap<TripMode, usize>,
+}
+
+impl DemandTotals {
+    pub fn new(scenario: &Scenario) -> DemandTotals {
+        let mut trips_per_mode = BTreeMap::new();
+        for trip in scenario.all_trips() {
+            *trips_per_mode.entry(trip.mode).or_insert(0) += 1;
+        }
+        DemandTotals {
+            scenario_name: scenario.scenario_name.clone(),
+            people: scenario.people.len(),
+            trips: trips_per_mode.values().sum(),
+            trips_per_mode,
+        }
+    }
+
+    fn describe(&self) -> String {
+        let modes: Vec<String> = self
+            .trips_per_mode
+            .iter()
+            .map(|(mode, count)| format!("{} {}", prettyprint_usize(*count), mode.noun()))
+            .collect();
+        format!(
+            "{}: {} people, {} trips ({})",
+            self.scenario_name,
+            prettyprint_usize(self.people),
+            prettyprint_usize(self.trips),
+            modes.join(", ")
+        )
+    }
+}
+
+/// The result of `Scenario::merge`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub inputs: Vec<DemandTotals>,
+    pub combined: DemandTotals,
+    /// People whose original ID was already used by an earlier scenario, and got a new one
+    pub renumbered_people: usize,
+}
+
+impl MergeReport {
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.inputs.iter().map(|x| x.describe()).collect();
+        lines.push(format!("Combined {}", self.combined.describe()));
+        if self.renumbered_people > 0 {
+            lines.push(format!(
+                "{} people had IDs used by an earlier scenario and were renumbered",
+                prettyprint_usize(self.renumbered_people)
+            ));
+        }
+        lines
+    }
+}
+
+impl Scenario {
+    /// Combine scenarios for the same map into one with everybody from each, in order. People keep
+    /// their trips exactly; if somebody's original ID is already used by an earlier scenario, they
+    /// get a new one. Buses needed by any input are seeded. The result can't be named like one of
+    /// the inputs, since saving it would overwrite that scenario.
+    pub fn merge(name: &str, scenarios: Vec<Scenario>) -> Result<(Scenario, MergeReport)> {
+        let map_name = match scenarios.get(0) {
+            Some(s) => s.map_name.clone(),
+            None => bail!("No scenarios to merge"),
+        };
+        if let Some(s) = scenarios.iter().find(|s| s.map_name != map_name) {
+            bail!(
+                "{} is for {}, but {} is for {}",
+                s.scenario_name,
+                s.map_name.describe(),
+                scenarios[0].scenario_name,
+                map_name.describe()
+            );
+        }
+        if scenarios.iter().any(|s| s.scenario_name == name) {
+            bail!(
+                "Can't name the merged scenario {}, since that's one of the inputs",
+                name
+            );
+        }
+
+        let inputs: Vec<DemandTotals> = scenarios.iter().map(DemandTotals::new).collect();
+        // Renumbered people get a household that isn't used anywhere
+        let mut next_household = scenarios
+            .iter()
+            .flat_map(|s| s.people.iter().filter_map(|p| p.orig_id))
+            .map(|id| id.0 + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut result = Scenario {
+            scenario_name: name.to_string(),
+            map_name,
+            people: Vec::new(),
+            only_seed_buses: Some(BTreeSet::new()),
+        };
+        let mut used_ids = BTreeSet::new();
+        let mut renumbered_people = 0;
+        for scenario in scenarios {
+            result.only_seed_buses = match (result.only_seed_buses, scenario.only_seed_buses) {
+                (Some(mut routes1), Some(routes2)) => {
+                    routes1.extend(routes2);
+                    Some(routes1)
+                }
+                // None means all buses
+                _ => None,
+            };
+
+            // Only check against earlier scenarios; duplicates within one scenario were already
+            // there
+            let mut new_ids = BTreeSet::new();
+            for mut person in scenario.people {
+                if let Some(id) = person.orig_id {
+                    if used_ids.contains(&id) {
+                        let new_id = OrigPersonID(next_household, id.1);
+                        next_household += 1;
+                        renumbered_people += 1;
+                        person.orig_id = Some(new_id);
+                        new_ids.insert(new_id);
+                    } else {
+                        new_ids.insert(id);
+                    }
+                }
+                result.people.push(person);
+            }
+            used_ids.extend(new_ids);
+        }
+
+        let report = MergeReport {
+            inputs,
+            combined: DemandTotals::new(&result),
+            renumbered_people,
+        };
+        Ok((result, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use abstio::MapName;
+    use geom::Time;
+    use map_model::BuildingID;
+
+    use super::*;
+    use crate::{IndividTrip, PersonSpec, TripEndpoint, TripPurpose};
+
+    fn scenario(name: &str, people: Vec<(usize, TripMode)>, buses: Option<&str>) -> Scenario {
+        Scenario {
+            scenario_name: name.to_string(),
+            map_name: MapName::seattle("montlake"),
+            people: people
+                .into_iter()
+                .map(|(id, mode)| PersonSpec {
+                    orig_id: Some(OrigPersonID(id, 0)),
+                    trips: vec![IndividTrip::new(
+                        Time::START_OF_DAY,
+                        TripPurpose::Work,
+                        TripEndpoint::Building(BuildingID(1)),
+                        TripEndpoint::Building(BuildingID(2)),
+                        mode,
+                    )],
+                })
+                .collect(),
+            only_seed_buses: buses.map(|route| vec![route.to_string()].into_iter().collect()),
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let weekday = scenario(
+            "weekday",
+            vec![(1, TripMode::Drive), (2, TripMode::Walk)],
+            Some("43"),
+        );
+        let game = scenario(
+            "game",
+            vec![(2, TripMode::Transit), (3, TripMode::Bike)],
+            Some("48"),
+        );
+        let (merged, report) = Scenario::merge("game_day", vec![weekday, game]).unwrap();
+
+        assert_eq!(merged.scenario_name, "game_day");
+        assert_eq!(merged.people.len(), 4);
+        // The second person #2 collides with the weekday's, so they get an unused household
+        let ids: Vec<OrigPersonID> = merged.people.iter().filter_map(|p| p.orig_id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                OrigPersonID(1, 0),
+                OrigPersonID(2, 0),
+                OrigPersonID(4, 0),
+                OrigPersonID(3, 0)
+            ]
+        );
+        assert_eq!(report.renumbered_people, 1);
+        assert_eq!(
+            merged.only_seed_buses,
+            Some(
+                vec!["43".to_string(), "48".to_string()]
+                    .into_iter()
+                    .collect()
+            )
+        );
+
+        assert_eq!(report.inputs.len(), 2);
+        assert_eq!(report.combined.people, 4);
+        assert_eq!(report.combined.trips, 4);
+        assert_eq!(report.combined.trips_per_mode[&TripMode::Walk], 1);
+
+        // Any input seeding all buses means the result does too
+        let (merged, _) = Scenario::merge(
+            "both",
+            vec![
+                scenario("a", vec![], Some("43")),
+                scenario("b", vec![], None),
+            ],
+        )
+        .unwrap();
+        assert_eq!(merged.only_seed_buses, None);
+    }
+
+    #[test]
+    fn test_merge_errors() {
+        assert!(Scenario::merge("x", Vec::new()).is_err());
+
+        // Saving would overwrite an input
+        assert!(Scenario::merge(
+            "weekday",
+            vec![
+                scenario("weekday", vec![], None),
+                scenario("game", vec![], None)
+            ]
+        )
+        .is_err());
+
+        let mut elsewhere = scenario("game", vec![], None);
+        elsewhere.map_name = MapName::seattle("downtown");
+        assert!(Scenario::merge("x", vec![scenario("weekday", vec![], None), elsewhere]).is_err());
+    }
+}
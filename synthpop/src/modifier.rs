@@ -7,10 +7,10 @@ use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Duration, Time};
+use geom::{Duration, LonLat, Ring, Time};
 use map_model::Map;
 
-use crate::{Scenario, TripMode};
+use crate::{IndividTrip, Scenario, TripMode};
 
 /// Transforms an existing Scenario before instantiating it.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
@@ -29,6 +29,19 @@ pub enum ScenarioModifier {
     },
     /// Scenario name
     AddExtraTrips(String),
+    /// Only keep this percentage of people. The choice is deterministic and doesn't depend on the
+    /// RNG seed. Raising the percentage only adds people, so results from small samples are
+    /// comparable to bigger ones.
+    SamplePeople(usize),
+    /// Only keep trips departing in this time window. People without any are removed.
+    TimeWindow(Time, Time),
+    /// Only keep trips starting or ending inside an area. People without any are removed.
+    TouchingArea {
+        /// Just used to describe the modifier
+        name: String,
+        /// Stored in GPS coordinates, so the area still makes sense after the map is regenerated
+        boundary: Vec<LonLat>,
+    },
 }
 
 impl ScenarioModifier {
@@ -96,6 +109,39 @@ impl ScenarioModifier {
                 }
                 s
             }
+            ScenarioModifier::SamplePeople(pct) => {
+                let mut idx = 0;
+                s.people.retain(|_| {
+                    idx += 1;
+                    stable_hash(idx - 1) % 100 < *pct as u64
+                });
+                s
+            }
+            ScenarioModifier::TimeWindow(start, end) => {
+                keep_trips(&mut s, |trip| trip.depart >= *start && trip.depart <= *end);
+                s
+            }
+            ScenarioModifier::TouchingArea { name, boundary } => {
+                let mut pts = map.get_gps_bounds().convert(boundary);
+                if let (Some(first), Some(last)) = (pts.first().cloned(), pts.last()) {
+                    if first != *last {
+                        pts.push(first);
+                    }
+                }
+                match Ring::deduping_new(pts) {
+                    Ok(ring) => {
+                        let polygon = ring.into_polygon();
+                        keep_trips(&mut s, |trip| {
+                            polygon.contains_pt(trip.origin.pt(map))
+                                || polygon.contains_pt(trip.destination.pt(map))
+                        });
+                    }
+                    Err(err) => {
+                        warn!("Not filtering trips, because {} is invalid: {}", name, err);
+                    }
+                }
+                s
+            }
         }
     }
 
@@ -124,8 +170,46 @@ impl ScenarioModifier {
                 to_mode.map(|m| m.verb())
             ),
             ScenarioModifier::AddExtraTrips(name) => format!("Add extra trips from {}", name),
+            ScenarioModifier::SamplePeople(pct) => format!("only simulate {}% of people", pct),
+            ScenarioModifier::TimeWindow(start, end) => format!(
+                "only keep trips departing between {} and {}",
+                start.ampm_tostring(),
+                end.ampm_tostring()
+            ),
+            ScenarioModifier::TouchingArea { name, .. } => {
+                format!("only keep trips starting or ending in {}", name)
+            }
+        }
+    }
+}
+
+/// Scrambles a person's index into a fixed pseudo-random number, using
+/// <https://prng.di.unimi.it/splitmix64.c>.
+fn stable_hash(idx: usize) -> u64 {
+    let mut x = (idx as u64).wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// For each person, keep everything from their first to their last matching trip. Trips in between
+/// are kept too, so schedules stay continuous. People without any matching trips are removed.
+fn keep_trips<F: Fn(&IndividTrip) -> bool>(s: &mut Scenario, matches: F) {
+    for person in &mut s.people {
+        match (
+            person.trips.iter().position(&matches),
+            person.trips.iter().rposition(&matches),
+        ) {
+            (Some(first), Some(last)) => {
+                person.trips.truncate(last + 1);
+                person.trips.drain(..first);
+            }
+            _ => {
+                person.trips.clear();
+            }
         }
     }
+    s.people.retain(|person| !person.trips.is_empty());
 }
 
 // Utter hack. Blindly repeats all trips taken by each person every day.
@@ -0,0 +1,477 @@
+//! Check a scenario against a map before simulating it, and optionally fix what's possible.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Counter, Timer};
+use geom::{Distance, Duration, FindClosest, Time};
+use map_model::{BuildingID, IntersectionID, Map};
+
+use crate::{PersonSpec, Scenario, TripEndpoint, TripMode};
+
+/// When repairing, only consider moving a trip to buildings this close to the original
+const MAX_SNAP_DIST: Distance = Distance::const_meters(500.0);
+/// And only try this many of the closest
+const MAX_SNAP_CANDIDATES: usize = 10;
+
+/// Something wrong with a person's trips
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ScenarioProblem {
+    /// The map doesn't have this building. The scenario was probably made for a different version
+    /// of the map.
+    MissingBuilding(BuildingID),
+    /// The map doesn't have this intersection, or it isn't a border
+    MissingBorder(IntersectionID),
+    /// There's no path between the trip's endpoints using this mode
+    ImpossibleMode(TripMode),
+    /// The trip starts after the day is over
+    DepartsAfterMidnight(Time),
+    /// The person's trips don't fit together
+    BadSchedule(String),
+}
+
+impl ScenarioProblem {
+    /// A short description of the kind of problem, without details
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ScenarioProblem::MissingBuilding(_) => "missing building",
+            ScenarioProblem::MissingBorder(_) => "missing border",
+            ScenarioProblem::ImpossibleMode(_) => "impossible mode",
+            ScenarioProblem::DepartsAfterMidnight(_) => "departs after midnight",
+            ScenarioProblem::BadSchedule(_) => "bad schedule",
+        }
+    }
+}
+
+impl fmt::Display for ScenarioProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScenarioProblem::MissingBuilding(b) => write!(f, "{} isn't on the map", b),
+            ScenarioProblem::MissingBorder(i) => write!(f, "{} isn't a border of the map", i),
+            ScenarioProblem::ImpossibleMode(mode) => {
+                write!(f, "there's no way to {} between the endpoints", mode.verb())
+            }
+            ScenarioProblem::DepartsAfterMidnight(t) => write!(f, "departs at {}", t),
+            ScenarioProblem::BadSchedule(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// One problem, found with one person
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScenarioIssue {
+    /// Indexes into `Scenario::people` of the scenario that was checked
+    pub person: usize,
+    /// Indexes into that person's trips. None for problems with the whole schedule.
+    pub trip: Option<usize>,
+    pub problem: ScenarioProblem,
+    /// Did `Scenario::repair` fix this? If not, the person was removed.
+    pub repaired: bool,
+}
+
+impl fmt::Display for ScenarioIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Person #{}", self.person + 1)?;
+        if let Some(trip) = self.trip {
+            write!(f, ", trip #{}", trip + 1)?;
+        }
+        write!(f, ": {}", self.problem)?;
+        if self.repaired {
+            write!(f, " (repaired)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of validating or repairing a scenario
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub people_checked: usize,
+    pub trips_checked: usize,
+    pub issues: Vec<ScenarioIssue>,
+    /// Only set by `Scenario::repair`; the people with problems that couldn't be fixed.
+    pub people_removed: usize,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Describe how many of each kind of problem there are
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Checked {} people with {} trips",
+            prettyprint_usize(self.people_checked),
+            prettyprint_usize(self.trips_checked)
+        )];
+        if self.is_ok() {
+            lines.push("No problems found".to_string());
+            return lines;
+        }
+        let mut kinds = Counter::new();
+        for issue in &self.issues {
+            kinds.inc(issue.problem.kind());
+        }
+        for (kind, count) in kinds.consume() {
+            lines.push(format!("{}: {}", kind, prettyprint_usize(count)));
+        }
+        if self.people_removed > 0 {
+            lines.push(format!(
+                "{} people couldn't be repaired and were removed",
+                prettyprint_usize(self.people_removed)
+            ));
+        }
+        lines
+    }
+}
+
+impl Scenario {
+    /// Check every person and trip against the map, without changing anything. This pathfinds for
+    /// every trip, so it takes a while for large scenarios.
+    pub fn validate(&self, map: &Map, timer: &mut Timer) -> ValidationReport {
+        let issues = timer
+            .parallelize(
+                "validate people",
+                self.people.iter().enumerate().collect(),
+                |(idx, person)| check_person(idx, person, map),
+            )
+            .into_iter()
+            .flatten()
+            .collect();
+        ValidationReport {
+            people_checked: self.people.len(),
+            trips_checked: self.all_trips().count(),
+            issues,
+            people_removed: 0,
+        }
+    }
+
+    /// Validate the scenario, then fix what's possible. Trips departing after midnight are
+    /// dropped. Buildings missing from the map are replaced by the building closest to the other
+    /// end of the trip where it works, and trips impossible with their mode are moved to the
+    /// nearest building where they work. Anybody with other problems is removed.
+    pub fn repair(mut self, map: &Map, timer: &mut Timer) -> (Scenario, ValidationReport) {
+        let mut report = self.validate(map, timer);
+        if report.is_ok() {
+            return (self, report);
+        }
+
+        let mut buildings = FindClosest::new(map.get_bounds());
+        for b in map.all_buildings() {
+            buildings.add_polygon(b.id, &b.polygon);
+        }
+
+        let mut issues_per_person: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (idx, issue) in report.issues.iter().enumerate() {
+            issues_per_person
+                .entry(issue.person)
+                .or_insert_with(Vec::new)
+                .push(idx);
+        }
+
+        let mut remove = BTreeSet::new();
+        timer.start_iter("repair people", issues_per_person.len());
+        for (person_idx, issues) in issues_per_person {
+            timer.next();
+            let person = &mut self.people[person_idx];
+            // Schedules are in order, so late trips are all at the end
+            let keep = person
+                .trips
+                .iter()
+                .take_while(|trip| trip.depart < end_of_day())
+                .count();
+            let mut all_repaired = true;
+            for idx in &issues {
+                let issue = &mut report.issues[*idx];
+                issue.repaired = match issue.problem {
+                    ScenarioProblem::DepartsAfterMidnight(_) => true,
+                    ScenarioProblem::MissingBuilding(b) => {
+                        let trip = issue.trip.unwrap();
+                        trip >= keep || replace_missing_building(person, trip, b, map, &buildings)
+                    }
+                    ScenarioProblem::ImpossibleMode(_) => {
+                        let trip = issue.trip.unwrap();
+                        trip >= keep || resnap(person, trip, map, &buildings)
+                    }
+                    _ => false,
+                };
+                all_repaired &= issue.repaired;
+            }
+            person.trips.truncate(keep);
+
+            // Moving one trip may have broken the one before or after it
+            if !all_repaired
+                || person.trips.is_empty()
+                || !check_person(person_idx, person, map).is_empty()
+            {
+                remove.insert(person_idx);
+                for idx in issues {
+                    report.issues[idx].repaired = false;
+                }
+            }
+        }
+
+        report.people_removed = remove.len();
+        let mut idx = 0;
+        self.people.retain(|_| {
+            idx += 1;
+            !remove.contains(&(idx - 1))
+        });
+        (self, report)
+    }
+}
+
+fn end_of_day() -> Time {
+    Time::START_OF_DAY + Duration::hours(24)
+}
+
+fn check_person(person_idx: usize, person: &PersonSpec, map: &Map) -> Vec<ScenarioIssue> {
+    let mut issues = Vec::new();
+    let mut add = |trip: Option<usize>, problem: ScenarioProblem| {
+        issues.push(ScenarioIssue {
+            person: person_idx,
+            trip,
+            problem,
+            repaired: false,
+        })
+    };
+
+    if let Err(err) = person.check_schedule() {
+        add(None, ScenarioProblem::BadSchedule(err.to_string()));
+    }
+    for (trip_idx, trip) in person.trips.iter().enumerate() {
+        if trip.depart >= end_of_day() {
+            add(
+                Some(trip_idx),
+                ScenarioProblem::DepartsAfterMidnight(trip.depart),
+            );
+        }
+
+        let mut endpoints_exist = true;
+        for endpoint in [trip.origin, trip.destination] {
+            match endpoint {
+                TripEndpoint::Building(b) => {
+                    if map.maybe_get_b(b).is_none() {
+                        add(Some(trip_idx), ScenarioProblem::MissingBuilding(b));
+                        endpoints_exist = false;
+                    }
+                }
+                TripEndpoint::Border(i) => {
+                    if !map.maybe_get_i(i).map(|i| i.is_border()).unwrap_or(false) {
+                        add(Some(trip_idx), ScenarioProblem::MissingBorder(i));
+                        endpoints_exist = false;
+                    }
+                }
+                TripEndpoint::SuddenlyAppear(_) => {}
+            }
+        }
+        if endpoints_exist && !has_path(trip.origin, trip.destination, trip.mode, map) {
+            add(Some(trip_idx), ScenarioProblem::ImpossibleMode(trip.mode));
+        }
+    }
+    issues
+}
+
+fn has_path(from: TripEndpoint, to: TripEndpoint, mode: TripMode, map: &Map) -> bool {
+    TripEndpoint::path_req(from, to, mode, map)
+        .map(|req| map.pathfind(req).is_ok())
+        .unwrap_or(false)
+}
+
+/// Replace a building that isn't on the map, everywhere the person uses it. There's no way to
+/// know where it was, so pick the building closest to the trip's other endpoint where the trip
+/// works.
+fn replace_missing_building(
+    person: &mut PersonSpec,
+    idx: usize,
+    missing: BuildingID,
+    map: &Map,
+    buildings: &FindClosest<BuildingID>,
+) -> bool {
+    let trip = &person.trips[idx];
+    let (origin, destination, mode) = (trip.origin, trip.destination, trip.mode);
+    let old = TripEndpoint::Building(missing);
+    if origin != old && destination != old {
+        // An earlier issue with the same building already replaced it
+        return true;
+    }
+    let anchor = if origin == old { destination } else { origin };
+    let anchor_pt = match anchor {
+        TripEndpoint::Building(b) => map.maybe_get_b(b).map(|b| b.polygon.center()),
+        TripEndpoint::Border(i) => map
+            .maybe_get_i(i)
+            .filter(|i| i.is_border())
+            .map(|i| i.polygon.center()),
+        TripEndpoint::SuddenlyAppear(pos) => Some(pos.pt(map)),
+    };
+    // Both ends are broken; there's nothing to snap near
+    let anchor_pt = match anchor_pt {
+        Some(pt) => pt,
+        None => return false,
+    };
+
+    let mut candidates = buildings.all_close_pts(anchor_pt, MAX_SNAP_DIST);
+    candidates.sort_by_key(|(_, _, dist)| *dist);
+    for (candidate, _, _) in candidates
+        .into_iter()
+        .filter(|(candidate, _, _)| anchor != TripEndpoint::Building(*candidate))
+        .take(MAX_SNAP_CANDIDATES)
+    {
+        let new = TripEndpoint::Building(candidate);
+        let works = if origin == old {
+            has_path(new, destination, mode, map)
+        } else {
+            has_path(origin, new, mode, map)
+        };
+        if works {
+            replace_endpoint(person, old, new);
+            return true;
+        }
+    }
+    false
+}
+
+fn replace_endpoint(person: &mut PersonSpec, old: TripEndpoint, new: TripEndpoint) {
+    for trip in &mut person.trips {
+        if trip.origin == old {
+            trip.origin = new;
+        }
+        if trip.destination == old {
+            trip.destination = new;
+        }
+    }
+}
+
+/// Try to move one of a trip's buildings to a nearby one where the trip works. The adjacent trip
+/// using the same building moves too, so the schedule stays continuous.
+fn resnap(
+    person: &mut PersonSpec,
+    idx: usize,
+    map: &Map,
+    buildings: &FindClosest<BuildingID>,
+) -> bool {
+    let (origin, destination, mode) = {
+        let trip = &person.trips[idx];
+        (trip.origin, trip.destination, trip.mode)
+    };
+    for from in [true, false] {
+        let old = if from { origin } else { destination };
+        let b = match old {
+            TripEndpoint::Building(b) => b,
+            _ => continue,
+        };
+        let mut candidates = buildings.all_close_pts(map.get_b(b).polygon.center(), MAX_SNAP_DIST);
+        candidates.sort_by_key(|(_, _, dist)| *dist);
+        for (candidate, _, _) in candidates
+            .into_iter()
+            .filter(|(candidate, _, _)| *candidate != b)
+            .take(MAX_SNAP_CANDIDATES)
+        {
+            let new = TripEndpoint::Building(candidate);
+            let works = if from {
+                has_path(new, destination, mode, map)
+            } else {
+                has_path(origin, new, mode, map)
+            };
+            if !works {
+                continue;
+            }
+            if from {
+                person.trips[idx].origin = new;
+                if idx > 0 && person.trips[idx - 1].destination == old {
+                    person.trips[idx - 1].destination = new;
+                }
+            } else {
+                person.trips[idx].destination = new;
+                if let Some(next) = person.trips.get_mut(idx + 1) {
+                    if next.origin == old {
+                        next.origin = new;
+                    }
+                }
+            }
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IndividTrip, TripPurpose};
+
+    fn trip(hours: usize, from: usize, to: usize) -> IndividTrip {
+        IndividTrip::new(
+            Time::START_OF_DAY + Duration::hours(hours),
+            TripPurpose::Shopping,
+            TripEndpoint::Building(BuildingID(from)),
+            TripEndpoint::Building(BuildingID(to)),
+            TripMode::Walk,
+        )
+    }
+
+    #[test]
+    fn test_replace_endpoint() {
+        let mut person = PersonSpec {
+            orig_id: None,
+            trips: vec![trip(8, 1, 2), trip(12, 2, 3), trip(17, 3, 2)],
+        };
+        replace_endpoint(
+            &mut person,
+            TripEndpoint::Building(BuildingID(2)),
+            TripEndpoint::Building(BuildingID(5)),
+        );
+        let pairs: Vec<(TripEndpoint, TripEndpoint)> = person
+            .trips
+            .iter()
+            .map(|t| (t.origin, t.destination))
+            .collect();
+        let b = |id| TripEndpoint::Building(BuildingID(id));
+        assert_eq!(pairs, vec![(b(1), b(5)), (b(5), b(3)), (b(3), b(5))]);
+        assert!(person.check_schedule().is_ok());
+    }
+
+    #[test]
+    fn test_summary() {
+        let issue = |person, problem, repaired| ScenarioIssue {
+            person,
+            trip: Some(0),
+            problem,
+            repaired,
+        };
+        let report = ValidationReport {
+            people_checked: 3,
+            trips_checked: 5,
+            issues: vec![
+                issue(0, ScenarioProblem::MissingBuilding(BuildingID(7)), true),
+                issue(1, ScenarioProblem::MissingBuilding(BuildingID(8)), true),
+                issue(2, ScenarioProblem::MissingBorder(IntersectionID(9)), false),
+            ],
+            people_removed: 1,
+        };
+        assert_eq!(
+            report.summary(),
+            vec![
+                "Checked 3 people with 5 trips".to_string(),
+                "missing border: 1".to_string(),
+                "missing building: 2".to_string(),
+                "1 people couldn't be repaired and were removed".to_string(),
+            ]
+        );
+        assert_eq!(
+            report.issues[0].to_string(),
+            "Person #1, trip #1: Building #7 isn't on the map (repaired)"
+        );
+
+        assert_eq!(
+            ValidationReport::default().summary(),
+            vec![
+                "Checked 0 people with 0 trips".to_string(),
+                "No problems found".to_string()
+            ]
+        );
+    }
+}
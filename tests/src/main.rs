@@ -8,10 +8,15 @@ use rand::seq::SliceRandom;
 
 use abstio::{CityName, MapName};
 use abstutil::Timer;
-use geom::{Duration, Time};
-use map_model::{IntersectionID, LaneType, Map, Perimeter, RoadID};
+use geom::{Duration, LonLat, Speed, Time};
+use map_model::{
+    BuildingID, EditCmd, IntersectionID, LaneType, Map, OriginalRoad, Perimeter, RoadID, TurnType,
+};
 use sim::{AlertHandler, PrebakeSummary, Sim, SimFlags, SimOptions};
-use synthpop::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+use synthpop::{
+    ExternalPerson, ExternalTrip, ExternalTripEndpoint, IndividTrip, PersonSpec, Scenario,
+    TripEndpoint, TripMode, TripPurpose,
+};
 
 fn main() -> Result<()> {
     abstutil::logger::setup();
@@ -20,6 +25,22 @@ fn main() -> Result<()> {
         "../tests/input/lane_selection.osm",
     )))?;
     test_map_importer()?;
+    test_dual_carriageway_consolidation()?;
+    test_reversible_lanes()?;
+    test_turning_circle_after_width_edit()?;
+    test_slip_lane_stop_signs()?;
+    test_u_turn_policy()?;
+    test_snap_cycleways()?;
+    test_border_inflow()?;
+    test_ramp_meter()?;
+    test_off_map_travel_time()?;
+    test_scenario_repair()?;
+    test_edits_repair()?;
+    test_driveways()?;
+    test_stop_sign_compliance()?;
+    test_jaywalking()?;
+    test_near_misses()?;
+    test_variable_speed_limits()?;
     check_proposals()?;
     if false {
         ab_test_spurious_diff()?;
@@ -43,6 +64,7 @@ fn test_map_importer() -> Result<()> {
         "divided_highway_split",
         "left_turn_and_bike_lane",
         "multiple_left_turn_lanes",
+        "turn_lanes",
     ] {
         // TODO It's kind of a hack to reference the crate's directory relative to the data dir.
         let map = import_map(abstio::path(format!("../tests/input/{}.osm", name)));
@@ -58,17 +80,1078 @@ fn test_map_importer() -> Result<()> {
 
 /// Run the contents of a .osm through the full map importer with default options.
 fn import_map(path: String) -> Map {
+    import_map_with_opts(path, map_model::RawToMapOptions::default())
+}
+
+fn import_map_with_opts(path: String, opts: map_model::RawToMapOptions) -> Map {
+    import_map_with_all_opts(path, convert_osm::Options::default(), opts)
+}
+
+fn import_map_with_all_opts(
+    path: String,
+    convert_opts: convert_osm::Options,
+    opts: map_model::RawToMapOptions,
+) -> Map {
     let mut timer = Timer::new("convert synthetic map");
     let name = MapName::new("zz", "oneshot", &abstutil::basename(&path));
     let clip = None;
-    let raw = convert_osm::convert(
-        path,
-        name,
-        clip,
-        convert_osm::Options::default(),
-        &mut timer,
+    let raw = convert_osm::convert(path, name, clip, convert_opts, &mut timer);
+    Map::create_from_raw(raw, opts, &mut timer)
+}
+
+/// Where two divided roads cross, the four small intersections should become one, with straight
+/// movements through it and no U-turns between the carriageways of one road.
+fn test_dual_carriageway_consolidation() -> Result<()> {
+    let mut opts = map_model::RawToMapOptions::default();
+    opts.consolidate_dual_carriageways = true;
+    let map = import_map_with_opts(
+        abstio::path("../tests/input/dual_carriageway_crossing.osm"),
+        opts,
     );
-    Map::create_from_raw(raw, map_model::RawToMapOptions::default(), &mut timer)
+
+    let complex: Vec<_> = map
+        .all_intersections()
+        .iter()
+        .filter(|i| i.roads.len() > 2)
+        .collect();
+    if complex.len() != 1 {
+        bail!(
+            "Expected one consolidated intersection, but got {}",
+            complex.len()
+        );
+    }
+    let i = complex[0];
+    for r in i.get_sorted_incoming_roads(&map) {
+        let name = map.get_r(r).get_name(None);
+        if !i.turns.iter().any(|t| {
+            t.id.src.road == r
+                && t.turn_type == TurnType::Straight
+                && map.get_r(t.id.dst.road).get_name(None) == name
+        }) {
+            bail!("No through movement from {} ({}) at {}", r, name, i.id);
+        }
+    }
+    if let Some(t) = i.turns.iter().find(|t| t.turn_type == TurnType::UTurn) {
+        bail!("{} shouldn't be a U-turn", t.id);
+    }
+
+    dump_turn_goldenfile(&map)
+}
+
+/// A reversible lane should close while its schedule points it the other way, and reopen on its
+/// own, just by running the simulation.
+fn test_reversible_lanes() -> Result<()> {
+    let mut map = import_map(abstio::path("../tests/input/lane_selection.osm"));
+    let mut timer = Timer::throwaway();
+    let lane = map.all_lanes().find(|l| l.is_driving()).unwrap();
+    let (l, dir) = (lane.id, lane.dir);
+
+    let at = |mins: usize| Time::START_OF_DAY + Duration::minutes(mins);
+    let mut edits = map.get_edits().clone();
+    edits.commands.push(map.edit_road_cmd(l.road, |new| {
+        new.reversible_lanes.insert(
+            l.offset,
+            map_model::LaneReversalSchedule {
+                switches: vec![(at(10), dir.opposite()), (at(20), dir)],
+            },
+        );
+    }));
+    map.must_apply_edits(edits, &mut timer);
+    map.recalculate_pathfinding_after_edits(&mut timer);
+
+    let mut opts = SimOptions::new("test_reversible_lanes");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(&map, opts);
+    for (mins, closed) in [(5, false), (15, true), (25, false)] {
+        sim.timed_step(&map, at(mins) - sim.time(), &mut None, &mut timer);
+        if sim.is_lane_closed(l) != closed {
+            bail!(
+                "At {}, {} should be {}",
+                sim.time(),
+                l,
+                if closed { "closed" } else { "open" }
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Widening the road into a turning circle recalculates the intersection geometry, which shouldn't
+/// lose the bulb.
+fn test_turning_circle_after_width_edit() -> Result<()> {
+    let mut map = import_map(abstio::path("../tests/input/turning_circle.osm"));
+    let mut timer = Timer::throwaway();
+    let i = match map.all_intersections().iter().find(|i| i.turning_circle) {
+        Some(i) => i.id,
+        None => bail!("No turning circle imported"),
+    };
+    // The bulb is at least 10m in radius
+    let check = |map: &Map, when: &str| -> Result<()> {
+        let bounds = map.get_i(i).polygon.get_bounds();
+        if bounds.width() < 19.0 || bounds.height() < 19.0 {
+            bail!(
+                "{} {} isn't a turning circle anymore: {:?}",
+                when,
+                i,
+                bounds
+            );
+        }
+        Ok(())
+    };
+    check(&map, "After import,")?;
+
+    let r = map.get_i(i).roads[0];
+    let mut edits = map.get_edits().clone();
+    edits.commands.push(map.edit_road_cmd(r, |new| {
+        for spec in &mut new.lanes_ltr {
+            if spec.lt == LaneType::Driving {
+                spec.width = spec.width * 1.5;
+            }
+        }
+    }));
+    map.must_apply_edits(edits, &mut timer);
+    check(&map, "After widening the road,")
+}
+
+/// A slip lane yields where it merges back, without changing who stops on the other roads there.
+/// Turning it around means it isn't a slip lane anymore.
+fn test_slip_lane_stop_signs() -> Result<()> {
+    let mut map = import_map(abstio::path("../tests/input/slip_lane.osm"));
+    let mut timer = Timer::throwaway();
+    let way = |id: i64| {
+        map.all_roads()
+            .iter()
+            .find(|r| r.orig_id.osm_way_id.0 == id)
+            .unwrap()
+            .id
+    };
+    let (slip, east, side) = (way(106), way(104), way(107));
+    if map.get_r(slip).slip_lane.is_none() {
+        bail!("{} wasn't detected as a slip lane", slip);
+    }
+
+    let merge = map.get_r(slip).dst_i;
+    let ss = map.get_stop_sign(merge);
+    for (r, must_stop) in [(slip, true), (east, false), (side, true)] {
+        if ss.roads[&r].must_stop != must_stop {
+            bail!(
+                "At {}, {} should {}stop",
+                merge,
+                r,
+                if must_stop { "" } else { "not " }
+            );
+        }
+    }
+
+    let mut edits = map.get_edits().clone();
+    edits.commands.push(map.edit_road_cmd(slip, |new| {
+        for spec in &mut new.lanes_ltr {
+            spec.dir = spec.dir.opposite();
+        }
+    }));
+    map.must_apply_edits(edits, &mut timer);
+    if map.get_r(slip).slip_lane.is_some() {
+        bail!("{} is still a slip lane after reversing it", slip);
+    }
+    if map.get_stop_sign(merge).roads.contains_key(&slip) {
+        bail!("{} doesn't lead into {} anymore", slip, merge);
+    }
+    Ok(())
+}
+
+/// An ordinary crossroads bans U-turns by default. Allowing them only adds a U-turn from the
+/// innermost lane of each road, without touching any other movement.
+fn test_u_turn_policy() -> Result<()> {
+    let mut map = import_map(abstio::path("../tests/input/u_turns.osm"));
+    let mut timer = Timer::throwaway();
+    let i = match map.all_intersections().iter().find(|i| i.roads.len() == 4) {
+        Some(i) => i.id,
+        None => bail!("No crossroads imported"),
+    };
+    // (U-turns back onto the same road, every other vehicle turn)
+    let count_turns = |map: &Map| {
+        let mut u_turns = 0;
+        let mut others = 0;
+        for turn in &map.get_i(i).turns {
+            if !map.get_l(turn.id.src).lane_type.is_for_moving_vehicles() {
+                continue;
+            }
+            if turn.turn_type == TurnType::UTurn && turn.id.src.road == turn.id.dst.road {
+                u_turns += 1;
+            } else {
+                others += 1;
+            }
+        }
+        (u_turns, others)
+    };
+
+    if map.get_i(i).allow_u_turns {
+        bail!("{} allows U-turns by default, but has no median", i);
+    }
+    let (u_turns, others) = count_turns(&map);
+    if u_turns != 0 {
+        bail!("{} has {} U-turns, but bans them", i, u_turns);
+    }
+
+    let mut edits = map.get_edits().clone();
+    edits.commands.push(EditCmd::ChangeUTurns {
+        i,
+        old: false,
+        new: true,
+    });
+    map.must_apply_edits(edits, &mut timer);
+    // Every road has one driving lane each way
+    if count_turns(&map) != (4, others) {
+        bail!(
+            "After allowing U-turns at {}, expected 4 of them and {} other turns, but got {:?}",
+            i,
+            others,
+            count_turns(&map)
+        );
+    }
+
+    let mut edits = map.get_edits().clone();
+    edits.commands.clear();
+    map.must_apply_edits(edits, &mut timer);
+    if count_turns(&map) != (0, others) {
+        bail!(
+            "Reverting the edit at {} left {:?} turns",
+            i,
+            count_turns(&map)
+        );
+    }
+    Ok(())
+}
+
+/// A cycle track mapped next to a road becomes a protected bike lane on the correct side of it.
+fn test_snap_cycleways() -> Result<()> {
+    let mut convert_opts = convert_osm::Options::default();
+    convert_opts.snap_cycleways = true;
+    let map = import_map_with_all_opts(
+        abstio::path("../tests/input/snap_cycleways.osm"),
+        convert_opts,
+        map_model::RawToMapOptions::default(),
+    );
+
+    if map.all_roads().len() != 1 {
+        bail!(
+            "The cycleway should've been removed, but there are {} roads",
+            map.all_roads().len()
+        );
+    }
+    let road = &map.all_roads()[0];
+    let middle_y = |lt: LaneType| {
+        road.lanes
+            .iter()
+            .filter(|l| l.lane_type == lt)
+            .map(|l| l.lane_center_pts.middle().y())
+            .collect::<Vec<_>>()
+    };
+    let lane_types: Vec<LaneType> = road.lanes.iter().map(|l| l.lane_type).collect();
+    let bike_lanes = middle_y(LaneType::Biking);
+    let driving_lanes = middle_y(LaneType::Driving);
+    if bike_lanes.len() != 2 || driving_lanes.len() != 2 {
+        bail!(
+            "Expected 2 bike and 2 driving lanes, but got {:?}",
+            lane_types
+        );
+    }
+    // The cycleway was north of the road, where y is smaller
+    let northmost_driving = driving_lanes.into_iter().fold(f64::MAX, f64::min);
+    if bike_lanes.into_iter().any(|y| y >= northmost_driving) {
+        bail!(
+            "The bike lanes were added on the wrong side: {:?}",
+            lane_types
+        );
+    }
+    if !road
+        .lanes
+        .iter()
+        .any(|l| l.lane_type == LaneType::Buffer(map_model::BufferType::Curb))
+    {
+        bail!("No curb separates the bike lanes: {:?}", lane_types);
+    }
+    Ok(())
+}
+
+/// When border inflow is limited, vehicles arriving at a border faster than the lane's capacity
+/// wait off-map and enter one at a time, in the order they arrived.
+fn test_border_inflow() -> Result<()> {
+    let map = import_map(abstio::path("../tests/input/u_turns.osm"));
+    let mut borders: Vec<&map_model::Intersection> = map
+        .all_intersections()
+        .iter()
+        .filter(|i| i.is_border())
+        .collect();
+    borders.sort_by_key(|i| i.polygon.center().x() as isize);
+    let (west, east) = match (borders.first(), borders.last()) {
+        (Some(west), Some(east)) if borders.len() == 4 => (west.id, east.id),
+        _ => bail!("Expected 4 borders, found {}", borders.len()),
+    };
+
+    // Much faster than the headway at borders
+    let num_cars = 6;
+    let mut scenario = Scenario::empty(&map, "border_inflow");
+    for idx in 0..num_cars {
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            trips: vec![IndividTrip::new(
+                Time::START_OF_DAY + Duration::seconds(idx as f64),
+                TripPurpose::Shopping,
+                TripEndpoint::Border(west),
+                TripEndpoint::Border(east),
+                TripMode::Drive,
+            )],
+        });
+    }
+
+    // Returns (when each delayed vehicle entered, which trip) at the west border
+    let run = |limit_border_inflow: bool| {
+        let mut opts = SimOptions::new("test_border_inflow");
+        opts.alerts = AlertHandler::Silence;
+        opts.limit_border_inflow = limit_border_inflow;
+        let mut sim = Sim::new(&map, opts);
+        let mut rng = SimFlags::for_test("test_border_inflow").make_rng();
+        sim.instantiate(&scenario, &map, &mut rng, &mut Timer::throwaway());
+        while !sim.is_done() {
+            sim.tiny_step(&map, &mut None);
+        }
+        sim.get_analytics()
+            .border_entry_delays
+            .get(&west)
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(time, trip, _)| (time, trip))
+            .collect::<Vec<_>>()
+    };
+
+    let limited = run(true);
+    // The first vehicle enters right away
+    if limited.len() != num_cars - 1 {
+        bail!(
+            "With limited inflow, {} vehicles waited at the border, but expected {}",
+            limited.len(),
+            num_cars - 1
+        );
+    }
+    for pair in limited.windows(2) {
+        if pair[1].1 < pair[0].1 {
+            bail!(
+                "{} entered before {}, who arrived first",
+                pair[1].1,
+                pair[0].1
+            );
+        }
+        if pair[1].0 - pair[0].0 < Duration::seconds(2.0) {
+            bail!("{} and {} entered too closely", pair[0].1, pair[1].1);
+        }
+    }
+
+    // By default, vehicles only wait when there's no room
+    let unlimited = run(false);
+    if unlimited.len() >= limited.len() {
+        bail!(
+            "With unlimited inflow, {} vehicles still waited at the border",
+            unlimited.len()
+        );
+    }
+    Ok(())
+}
+
+/// A ramp meter lets one vehicle at a time onto the motorway, no more often than its cycle.
+fn test_ramp_meter() -> Result<()> {
+    let mut map = import_map(abstio::path("../tests/input/on_ramp.osm"));
+    let ramp = match map.all_roads().iter().find(|r| r.is_on_ramp(&map)) {
+        Some(r) => r.id,
+        None => bail!("No on-ramp imported"),
+    };
+    let cycle = Duration::seconds(10.0);
+    let mut edits = map.get_edits().clone();
+    edits.commands.push(map.edit_road_cmd(ramp, |new| {
+        new.ramp_meter = Some(map_model::RampMeter {
+            cycle,
+            target_density: None,
+        });
+    }));
+    map.must_apply_edits(edits, &mut Timer::throwaway());
+
+    let mut borders: Vec<&map_model::Intersection> = map
+        .all_intersections()
+        .iter()
+        .filter(|i| i.is_border())
+        .collect();
+    // The ramp starts at the southernmost border, and the motorway ends at the easternmost
+    borders.sort_by_key(|i| i.polygon.center().y() as isize);
+    let from = borders.last().unwrap().id;
+    borders.sort_by_key(|i| i.polygon.center().x() as isize);
+    let to = borders.last().unwrap().id;
+
+    // Vehicles arrive at the meter faster than it releases them
+    let num_cars = 5;
+    let mut scenario = Scenario::empty(&map, "ramp_meter");
+    for idx in 0..num_cars {
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            trips: vec![IndividTrip::new(
+                Time::START_OF_DAY + Duration::seconds(2.0 * idx as f64),
+                TripPurpose::Shopping,
+                TripEndpoint::Border(from),
+                TripEndpoint::Border(to),
+                TripMode::Drive,
+            )],
+        });
+    }
+    let mut opts = SimOptions::new("test_ramp_meter");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(&map, opts);
+    let mut rng = SimFlags::for_test("test_ramp_meter").make_rng();
+    sim.instantiate(&scenario, &map, &mut rng, &mut Timer::throwaway());
+    while !sim.is_done() {
+        sim.tiny_step(&map, &mut None);
+    }
+
+    let analytics = sim.get_analytics();
+    let releases = analytics
+        .ramp_meter_releases
+        .get(&ramp)
+        .cloned()
+        .unwrap_or_default();
+    if releases.len() != num_cars {
+        bail!(
+            "The ramp meter released {} vehicles, but expected {}",
+            releases.len(),
+            num_cars
+        );
+    }
+    for pair in releases.windows(2) {
+        if pair[1].0 - pair[0].0 < cycle {
+            bail!(
+                "The ramp meter released vehicles at {} and {}, less than {} apart",
+                pair[0].0,
+                pair[1].0,
+                cycle
+            );
+        }
+    }
+    let (count, avg_wait) = analytics.ramp_meter_summary(ramp, sim.time());
+    if count != num_cars || avg_wait == Duration::ZERO {
+        bail!(
+            "The ramp meter summary says {} vehicles waited {} on average",
+            count,
+            avg_wait
+        );
+    }
+    Ok(())
+}
+
+/// Trips starting off-map enter at the nearest border, departing later by the time needed to get
+/// there.
+fn test_off_map_travel_time() -> Result<()> {
+    let map = import_map(abstio::path("../tests/input/u_turns.osm"));
+    let mut borders: Vec<&map_model::Intersection> = map
+        .all_intersections()
+        .iter()
+        .filter(|i| i.is_border())
+        .collect();
+    borders.sort_by_key(|i| i.polygon.center().x() as isize);
+    let (west, east) = match (borders.first(), borders.last()) {
+        (Some(west), Some(east)) if borders.len() == 4 => (west.id, east.id),
+        _ => bail!("Expected 4 borders, found {}", borders.len()),
+    };
+
+    // Along the main road, some distance west or east of the map
+    let off_map = |lon: f64| ExternalTripEndpoint::Position(LonLat::new(lon, 0.0005));
+    let trip = |departure: Time, from: f64, to: f64| ExternalTrip {
+        departure,
+        origin: off_map(from),
+        destination: off_map(to),
+        mode: TripMode::Drive,
+        purpose: TripPurpose::Work,
+    };
+    let seven = Time::START_OF_DAY + Duration::hours(7);
+    let input = || {
+        vec![
+            // About 1km away from the west border
+            ExternalPerson {
+                trips: vec![trip(seven, -0.01, 0.011)],
+            },
+            // About 11km away, so the first trip can't reach the map before the second one starts
+            ExternalPerson {
+                trips: vec![
+                    trip(seven, -0.1, 0.011),
+                    trip(seven + Duration::minutes(10), 0.011, -0.1),
+                ],
+            },
+        ]
+    };
+
+    let people = ExternalPerson::import(&map, input(), true)?;
+    if people.len() != 2 || people[0].trips.len() != 1 || people[1].trips.len() != 1 {
+        bail!(
+            "Expected 2 people with 1 trip each, got {:?}",
+            people.iter().map(|p| p.trips.len()).collect::<Vec<_>>()
+        );
+    }
+    let near = &people[0].trips[0];
+    if near.origin != TripEndpoint::Border(west) || near.destination != TripEndpoint::Border(east) {
+        bail!(
+            "Trip snapped to {:?} and {:?}, not the west and east borders",
+            near.origin,
+            near.destination
+        );
+    }
+    // 1.3 times the straight-line distance, at 30km/h off-map
+    if near.depart < seven + Duration::minutes(2) || near.depart > seven + Duration::minutes(4) {
+        bail!("Trip 1km off-map enters at {}", near.depart);
+    }
+    if people[1].trips[0].origin != TripEndpoint::Border(east) {
+        bail!(
+            "The trip from far away wasn't the one skipped; one from {:?} remains",
+            people[1].trips[0].origin
+        );
+    }
+
+    if ExternalPerson::import(&map, input(), false).is_ok() {
+        bail!("A trip delayed past the next one wasn't reported as a problem");
+    }
+    Ok(())
+}
+
+/// Validate and repair a scenario with people going to buildings and borders that don't exist.
+fn test_scenario_repair() -> Result<()> {
+    let map = import_map(abstio::path("../tests/input/scenario_repair.osm"));
+    let mut timer = Timer::throwaway();
+    if map.all_buildings().len() < 2 {
+        bail!("Only {} buildings imported", map.all_buildings().len());
+    }
+    let home = TripEndpoint::Building(map.all_buildings()[0].id);
+    let missing = TripEndpoint::Building(BuildingID(map.all_buildings().len() + 10));
+    let trip = |hours: usize, from: TripEndpoint, to: TripEndpoint| {
+        IndividTrip::new(
+            Time::START_OF_DAY + Duration::hours(hours),
+            TripPurpose::Shopping,
+            from,
+            to,
+            TripMode::Walk,
+        )
+    };
+
+    let mut scenario = Scenario::empty(&map, "repair");
+    for trips in [
+        // Goes to a building that isn't on the map and comes back
+        vec![trip(8, home, missing), trip(17, missing, home)],
+        // The second trip is too late
+        vec![trip(8, home, missing), trip(25, missing, home)],
+        // There's no border with this ID
+        vec![trip(8, TripEndpoint::Border(IntersectionID(999)), home)],
+    ] {
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            trips,
+        });
+    }
+
+    let report = scenario.validate(&map, &mut timer);
+    let kinds: Vec<&str> = report.issues.iter().map(|i| i.problem.kind()).collect();
+    if kinds
+        != vec![
+            "missing building",
+            "missing building",
+            "missing building",
+            "departs after midnight",
+            "missing building",
+            "missing border",
+        ]
+    {
+        bail!("Unexpected problems: {:?}", kinds);
+    }
+
+    let (scenario, report) = scenario.repair(&map, &mut timer);
+    if report.people_removed != 1 || scenario.people.len() != 2 {
+        bail!(
+            "Expected only the person from the missing border to be removed, got {} left",
+            scenario.people.len()
+        );
+    }
+    let snapped = scenario.people[0].trips[0].destination;
+    if snapped == missing || snapped == home {
+        bail!("The missing building was re-snapped to {:?}", snapped);
+    }
+    if scenario.people[0].trips[1].origin != snapped {
+        bail!(
+            "Only one trip was re-snapped: {:?}",
+            scenario.people[0].trips
+        );
+    }
+    if scenario.people[1].trips.len() != 1 {
+        bail!("The trip after midnight wasn't dropped");
+    }
+    if !scenario.validate(&map, &mut timer).is_ok() {
+        bail!("The repaired scenario still has problems");
+    }
+    Ok(())
+}
+
+/// Make edits on one version of a map, then load them on a newer one where the OSM node IDs changed
+/// and a way ID moved to a different street.
+fn test_edits_repair() -> Result<()> {
+    let before = import_map(abstio::path("../tests/input/edits_repair_before.osm"));
+    let after = import_map(abstio::path("../tests/input/edits_repair_after.osm"));
+    let find = |map: &Map, way: i64, i1: i64, i2: i64| {
+        map.find_r_by_osm_id(OriginalRoad::new(way, (i1, i2)))
+    };
+
+    let mut edits = before.get_edits().clone();
+    // The side street's other end is clipped to the map boundary, so only match its way
+    let side_street = before
+        .all_roads()
+        .iter()
+        .find(|r| r.orig_id.osm_way_id.0 == 101)
+        .unwrap()
+        .id;
+    for r in [find(&before, 100, 2, 3)?, side_street] {
+        edits.commands.push(before.edit_road_cmd(r, |new| {
+            new.speed_limit = Speed::miles_per_hour(15.0);
+        }));
+    }
+    let (edits, report) = edits.to_permanent(&before).into_edits_repaired(&after);
+
+    // The middle of the main street has new endpoints, but it's in the same place
+    if report.adapted.len() != 1 || edits.commands.len() != 1 {
+        bail!("Expected one adapted command: {:?}", report.describe());
+    }
+    match edits.commands[0] {
+        EditCmd::ChangeRoad { r, .. } if r == find(&after, 100, 12, 13)? => {}
+        ref cmd => bail!(
+            "The main street edit was matched to the wrong road: {:?}",
+            cmd
+        ),
+    }
+    // Way 101 still touches node 5, but it's a different street now
+    if report.dropped.len() != 1 {
+        bail!(
+            "Expected the side street edit to be dropped: {:?}",
+            report.describe()
+        );
+    }
+    Ok(())
+}
+
+/// Driveways are only imported as roads when asked. Then drivers can't cut through them, they're
+/// slow, and leaving one means stopping for the street.
+fn test_driveways() -> Result<()> {
+    let path = abstio::path("../tests/input/driveway.osm");
+    let find_driveway = |map: &Map| {
+        map.all_roads()
+            .iter()
+            .find(|r| r.orig_id.osm_way_id.0 == 102)
+            .map(|r| r.id)
+    };
+    if find_driveway(&import_map(path.clone())).is_some() {
+        bail!("The driveway was imported as a road by default");
+    }
+
+    let mut convert_opts = convert_osm::Options::default();
+    convert_opts.include_driveways = true;
+    let map = import_map_with_all_opts(path, convert_opts, map_model::RawToMapOptions::default());
+    let driveway = match find_driveway(&map) {
+        Some(r) => map.get_r(r),
+        None => bail!("The driveway wasn't imported"),
+    };
+    if !driveway.is_driveway_or_alley() {
+        bail!("{} isn't recognized as a driveway", driveway.id);
+    }
+    if driveway
+        .access_restrictions
+        .allow_through_traffic
+        .contains(map_model::PathConstraints::Car)
+    {
+        bail!("Cars can use {} as a shortcut", driveway.id);
+    }
+    if driveway.speed_limit > Speed::km_per_hour(10.1) {
+        bail!(
+            "{} has a speed limit of {:?}",
+            driveway.id,
+            driveway.speed_limit
+        );
+    }
+
+    // The other end is a dead-end
+    let i = match [driveway.src_i, driveway.dst_i]
+        .into_iter()
+        .map(|i| map.get_i(i))
+        .find(|i| i.roads.len() == 3)
+    {
+        Some(i) => i,
+        None => bail!("{} doesn't meet the street", driveway.id),
+    };
+    if !i.is_stop_sign() {
+        bail!(
+            "{} where the driveway meets the street isn't a stop sign",
+            i.id
+        );
+    }
+    for (r, cfg) in &map.get_stop_sign(i.id).roads {
+        if cfg.must_stop != (*r == driveway.id) {
+            bail!(
+                "At {}, {} should {}stop",
+                i.id,
+                r,
+                if cfg.must_stop { "not " } else { "" }
+            );
+        }
+    }
+    Ok(())
+}
+
+/// At an all-way stop, drivers who roll through save time, and drivers who never stop cut off
+/// somebody who got there first, which is reported as a problem.
+fn test_stop_sign_compliance() -> Result<()> {
+    let mut map = import_map(abstio::path("../tests/input/u_turns.osm"));
+    let i = match map.all_intersections().iter().find(|i| i.roads.len() == 4) {
+        Some(i) => i.id,
+        None => bail!("No crossroads imported"),
+    };
+    if !map.get_i(i).is_stop_sign() {
+        bail!("{} isn't a stop sign", i);
+    }
+    let mut ss = map.get_stop_sign(i).clone();
+    for cfg in ss.roads.values_mut() {
+        cfg.must_stop = true;
+    }
+    let mut edits = map.get_edits().clone();
+    edits.commands.push(EditCmd::ChangeIntersection {
+        i,
+        old: map.get_i_edit(i),
+        new: map_model::EditIntersection::StopSign(ss),
+    });
+    map.must_apply_edits(edits, &mut Timer::throwaway());
+
+    let mut borders: Vec<&map_model::Intersection> = map
+        .all_intersections()
+        .iter()
+        .filter(|i| i.is_border())
+        .collect();
+    if borders.len() != 4 {
+        bail!("Expected 4 borders, found {}", borders.len());
+    }
+    borders.sort_by_key(|i| i.polygon.center().x() as isize);
+    let (west, east) = (borders[0].id, borders[3].id);
+    borders.sort_by_key(|i| i.polygon.center().y() as isize);
+    let (north, south) = (borders[0].id, borders[3].id);
+
+    // Both streets are busy at the same time, so drivers often find somebody already waiting
+    let mut scenario = Scenario::empty(&map, "stop_sign_compliance");
+    for idx in 0..10 {
+        for (from, to) in [(west, east), (south, north)] {
+            scenario.people.push(PersonSpec {
+                orig_id: None,
+                trips: vec![IndividTrip::new(
+                    Time::START_OF_DAY + Duration::seconds(3.0 * idx as f64),
+                    TripPurpose::Shopping,
+                    TripEndpoint::Border(from),
+                    TripEndpoint::Border(to),
+                    TripMode::Drive,
+                )],
+            });
+        }
+    }
+
+    // Returns (number of stop sign violations, total time for all trips)
+    let run = |rolling_stop_pct: usize, stop_sign_violation_pct: usize| {
+        let mut opts = SimOptions::new("test_stop_sign_compliance");
+        opts.alerts = AlertHandler::Silence;
+        opts.rolling_stop_pct = rolling_stop_pct;
+        opts.stop_sign_violation_pct = stop_sign_violation_pct;
+        let mut sim = Sim::new(&map, opts);
+        let mut rng = SimFlags::for_test("test_stop_sign_compliance").make_rng();
+        sim.instantiate(&scenario, &map, &mut rng, &mut Timer::throwaway());
+        while !sim.is_done() {
+            sim.tiny_step(&map, &mut None);
+        }
+        let analytics = sim.get_analytics();
+        let violations = analytics
+            .problems_per_trip
+            .values()
+            .flatten()
+            .filter(|(_, problem)| matches!(problem, sim::Problem::StopSignViolation(_)))
+            .count();
+        let total_time: Duration = analytics
+            .finished_trips
+            .iter()
+            .filter_map(|(_, _, _, dt)| *dt)
+            .sum();
+        (violations, total_time)
+    };
+
+    let (violations, full_stops) = run(0, 0);
+    if violations != 0 {
+        bail!("{} violations when everybody stops", violations);
+    }
+    let (violations, rolling_stops) = run(100, 0);
+    if violations != 0 {
+        bail!("{} violations when everybody rolls through", violations);
+    }
+    if rolling_stops >= full_stops {
+        bail!(
+            "Rolling stops took {}, but full stops only {}",
+            rolling_stops,
+            full_stops
+        );
+    }
+    let (violations, _) = run(0, 100);
+    if violations == 0 {
+        bail!("Nobody cut anybody off when nobody stops");
+    }
+    Ok(())
+}
+
+/// Visiting the house across a slow street is quicker when pedestrians can cross midblock, instead
+/// of walking to the corner and back. On a fast street, they still use the crossing.
+fn test_jaywalking() -> Result<()> {
+    let mut map = import_map(abstio::path("../tests/input/jaywalking.osm"));
+    if map.all_buildings().len() != 2 {
+        bail!("Expected 2 houses, found {}", map.all_buildings().len());
+    }
+    let mut houses: Vec<BuildingID> = map.all_buildings().iter().map(|b| b.id).collect();
+    houses.sort_by_key(|b| map.get_b(*b).polygon.center().y() as isize);
+    // North to south
+    let mut scenario = Scenario::empty(&map, "jaywalking");
+    scenario.people.push(PersonSpec {
+        orig_id: None,
+        trips: vec![IndividTrip::new(
+            Time::START_OF_DAY,
+            TripPurpose::Shopping,
+            TripEndpoint::Building(houses[0]),
+            TripEndpoint::Building(houses[1]),
+            TripMode::Walk,
+        )],
+    });
+
+    let run = |map: &Map, allow_jaywalking: bool| -> Result<Duration> {
+        let mut opts = SimOptions::new("test_jaywalking");
+        opts.alerts = AlertHandler::Silence;
+        opts.allow_jaywalking = allow_jaywalking;
+        let mut sim = Sim::new(map, opts);
+        let mut rng = SimFlags::for_test("test_jaywalking").make_rng();
+        sim.instantiate(&scenario, map, &mut rng, &mut Timer::throwaway());
+        while !sim.is_done() {
+            sim.tiny_step(map, &mut None);
+        }
+        match sim.get_analytics().finished_trips.get(0) {
+            Some((_, _, _, Some(dt))) => Ok(*dt),
+            _ => bail!("The walking trip didn't finish"),
+        }
+    };
+
+    let crossing = run(&map, false)?;
+    let jaywalking = run(&map, true)?;
+    // The corner is about 50m away, so going there and back takes more than a minute
+    if jaywalking + Duration::seconds(30.0) > crossing {
+        bail!(
+            "Jaywalking took {}, but using the crossing only took {}",
+            jaywalking,
+            crossing
+        );
+    }
+
+    let street = map
+        .all_roads()
+        .iter()
+        .find(|r| r.orig_id.osm_way_id.0 == 100)
+        .unwrap()
+        .id;
+    let mut edits = map.get_edits().clone();
+    edits.commands.push(map.edit_road_cmd(street, |new| {
+        new.speed_limit = Speed::miles_per_hour(45.0);
+    }));
+    map.must_apply_edits(edits, &mut Timer::throwaway());
+    let fast_street = run(&map, true)?;
+    if fast_street != crossing {
+        bail!(
+            "On a fast street, the trip took {} with jaywalking allowed, but {} without",
+            fast_street,
+            crossing
+        );
+    }
+    Ok(())
+}
+
+/// When pedestrians cross right behind a steady stream of vehicles, near-misses are recorded on the
+/// crosswalk. Vehicles alone never have them.
+fn test_near_misses() -> Result<()> {
+    let map = import_map(abstio::path("../tests/input/jaywalking.osm"));
+    let mut houses: Vec<BuildingID> = map.all_buildings().iter().map(|b| b.id).collect();
+    houses.sort_by_key(|b| map.get_b(*b).polygon.center().y() as isize);
+    let mut borders: Vec<&map_model::Intersection> = map
+        .all_intersections()
+        .iter()
+        .filter(|i| i.is_border())
+        .collect();
+    borders.sort_by_key(|i| i.polygon.center().x() as isize);
+    let (west, east) = match (houses.len(), borders.first(), borders.last()) {
+        (2, Some(west), Some(east)) if borders.len() == 4 => (west.id, east.id),
+        _ => bail!(
+            "Expected 2 houses and 4 borders, found {} and {}",
+            houses.len(),
+            borders.len()
+        ),
+    };
+
+    let trip = |seconds: f64, from: TripEndpoint, to: TripEndpoint, mode: TripMode| PersonSpec {
+        orig_id: None,
+        trips: vec![IndividTrip::new(
+            Time::START_OF_DAY + Duration::seconds(seconds),
+            TripPurpose::Shopping,
+            from,
+            to,
+            mode,
+        )],
+    };
+    // Drivers head east through the crossroads, while pedestrians cross the street they came from
+    let mut scenario = Scenario::empty(&map, "near_misses");
+    for idx in 0..20 {
+        scenario.people.push(trip(
+            2.0 * idx as f64,
+            TripEndpoint::Border(west),
+            TripEndpoint::Border(east),
+            TripMode::Drive,
+        ));
+    }
+    let drivers_only = scenario.clone();
+    for idx in 0..10 {
+        scenario.people.push(trip(
+            4.0 * idx as f64,
+            TripEndpoint::Building(houses[0]),
+            TripEndpoint::Building(houses[1]),
+            TripMode::Walk,
+        ));
+    }
+
+    // Returns the near-misses, and whether anybody braked hard
+    let run = |scenario: &Scenario| {
+        let mut opts = SimOptions::new("test_near_misses");
+        opts.alerts = AlertHandler::Silence;
+        let mut sim = Sim::new(&map, opts);
+        let mut rng = SimFlags::for_test("test_near_misses").make_rng();
+        sim.instantiate(scenario, &map, &mut rng, &mut Timer::throwaway());
+        while !sim.is_done() {
+            sim.tiny_step(&map, &mut None);
+        }
+        let mut near_misses = Vec::new();
+        let mut hard_braking = false;
+        for (_, problem) in sim.get_analytics().problems_per_trip.values().flatten() {
+            match problem {
+                sim::Problem::NearMiss(t, pet) => near_misses.push((*t, *pet)),
+                sim::Problem::HardBraking(_) => hard_braking = true,
+                _ => {}
+            }
+        }
+        (near_misses, hard_braking)
+    };
+
+    let (near_misses, hard_braking) = run(&drivers_only);
+    if !near_misses.is_empty() || hard_braking {
+        bail!(
+            "With only vehicles, found {} near-misses and hard braking: {}",
+            near_misses.len(),
+            hard_braking
+        );
+    }
+
+    let (near_misses, _) = run(&scenario);
+    if near_misses.is_empty() {
+        bail!("Pedestrians crossing right behind vehicles had no near-misses");
+    }
+    for (t, pet) in near_misses {
+        if !map.get_t(t).turn_type.pedestrian_crossing() {
+            bail!("A near-miss was blamed on {}, which isn't a crossing", t);
+        }
+        if pet > Duration::seconds(1.5) {
+            bail!(
+                "A near-miss at {} had a post-encroachment time of {}",
+                t,
+                pet
+            );
+        }
+    }
+    Ok(())
+}
+
+/// A managed road posts its scheduled limit while the simulation runs, and vehicles driving then
+/// are slower.
+fn test_variable_speed_limits() -> Result<()> {
+    let mut map = import_map(abstio::path("../tests/input/scenario_repair.osm"));
+    let road = match map.all_roads().get(0) {
+        Some(r) if map.all_roads().len() == 1 => r.id,
+        _ => bail!("Expected 1 road, found {}", map.all_roads().len()),
+    };
+    let (west, east) = (map.get_r(road).src_i, map.get_r(road).dst_i);
+    let hour = |h: usize| Time::START_OF_DAY + Duration::hours(h);
+    let slow = Speed::miles_per_hour(10.0);
+    let mut edits = map.get_edits().clone();
+    edits.commands.push(map.edit_road_cmd(road, |new| {
+        new.speed_limit = Speed::miles_per_hour(30.0);
+        new.variable_speed_limit = Some(map_model::VariableSpeedLimit {
+            schedule: vec![(hour(7), Some(slow)), (hour(9), None)],
+            congested_density: None,
+            congested_limit: slow,
+        });
+    }));
+    map.must_apply_edits(edits, &mut Timer::throwaway());
+
+    let mut scenario = Scenario::empty(&map, "variable_speed_limits");
+    for h in [6, 8] {
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            trips: vec![IndividTrip::new(
+                hour(h),
+                TripPurpose::Work,
+                TripEndpoint::Border(west),
+                TripEndpoint::Border(east),
+                TripMode::Drive,
+            )],
+        });
+    }
+
+    let mut opts = SimOptions::new("test_variable_speed_limits");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(&map, opts);
+    let mut rng = SimFlags::for_test("test_variable_speed_limits").make_rng();
+    let mut timer = Timer::throwaway();
+    sim.instantiate(&scenario, &map, &mut rng, &mut timer);
+    for (h, expected) in [(6, None), (8, Some(slow)), (10, None)] {
+        sim.timed_step(
+            &map,
+            hour(h) + Duration::minutes(1) - sim.time(),
+            &mut None,
+            &mut timer,
+        );
+        if sim.posted_speed_limit(road) != expected {
+            bail!(
+                "At {}, {} posts {:?}, but expected {:?}",
+                sim.time(),
+                road,
+                sim.posted_speed_limit(road),
+                expected
+            );
+        }
+    }
+
+    let durations: Vec<Duration> = sim
+        .get_analytics()
+        .finished_trips
+        .iter()
+        .filter_map(|(_, _, _, dt)| *dt)
+        .collect();
+    match durations[..] {
+        [early, rush_hour] if rush_hour > early => Ok(()),
+        _ => bail!(
+            "Expected the trip during the lower limit to be slower: {:?}",
+            durations
+        ),
+    }
 }
 
 /// Verify what turns are generated by writing (from lane, to lane, turn type).
@@ -115,9 +1198,13 @@ fn check_proposals() -> Result<()> {
             Ok(perma) => {
                 let map = map_model::Map::load_synchronously(perma.map_name.path(), &mut timer);
                 if let Err(err) = perma.clone().into_edits(&map) {
+                    let (edits, report) = perma.into_edits_repaired(&map);
+                    for line in report.describe() {
+                        println!("{}", line);
+                    }
                     abstio::write_json(
                         "repair_attempt.json".to_string(),
-                        &perma.into_edits_permissive(&map).to_permanent(&map),
+                        &edits.to_permanent(&map),
                     );
                     anyhow::bail!("{} is out-of-date: {}", name, err);
                 }
@@ -1,5 +1,12 @@
 //! A representation of traffic signal configuration that references OpenStreetMap IDs and is
 //! hopefully robust to minor edits over time.
+//!
+//! This is also the format for exchanging timing plans with other tools. A single signal is a
+//! JSON-serialized `TrafficSignal`. Timing for many signals, like everything an agency manages in
+//! one area, is a JSON list of `TrafficSignal`s. Signals are matched to A/B Street intersections by
+//! `intersection_osm_node_id`, and movements by the OSM ways and nodes in `DirectedRoad`, so a file
+//! exported from one version of a map can usually be imported into another. All durations are in
+//! whole seconds.
 
 use std::collections::BTreeSet;
 
@@ -16,6 +23,10 @@ pub struct TrafficSignal {
     /// order of ascending `start_time_seconds`, the first plan must begin at `0` (midnight), and
     /// the last plan must not start after 24 hours.
     pub plans: Vec<Plan>,
+    /// Movements with a separate signal head for cyclists. Cyclists making these movements follow
+    /// `Stage::bike_turns`, not the signal for general traffic.
+    #[serde(default)]
+    pub bike_signals: BTreeSet<Turn>,
 }
 
 /// A plan describes how a traffic signal is configured during some period of time. Multiple plans
@@ -47,6 +58,25 @@ pub struct Stage {
     pub permitted_turns: BTreeSet<Turn>,
     /// The stage lasts this long before moving to the next one.
     pub stage_type: StageType,
+    /// During this stage, cyclists can perform these turns, protected by their own signal. Every
+    /// turn here must be listed in `TrafficSignal::bike_signals`.
+    #[serde(default)]
+    pub bike_turns: BTreeSet<Turn>,
+    /// Crosswalks and turns with a bike signal can start before or after general traffic.
+    #[serde(default)]
+    pub lead_lag: Option<LeadLagInterval>,
+}
+
+/// Part of a stage when only some movements can go, measured in seconds from the start of the
+/// stage.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LeadLagInterval {
+    /// Pedestrians and cyclists with their own signal get a head start on general traffic, so
+    /// they're more visible to turning vehicles.
+    Leading(usize),
+    /// General traffic goes first, letting turning vehicles clear before pedestrians and cyclists
+    /// with their own signal start.
+    Lagging(usize),
 }
 
 /// How long a stage lasts before moving to the next one.
@@ -1,7 +1,7 @@
 #version 410
 
-// (x offset, y offset, zoom)
-uniform vec3 transform;
+// (x offset, y offset, zoom, vertical scale)
+uniform vec4 transform;
 // (window width, window height, z value)
 uniform vec3 window;
 uniform vec2 texture_scale;
@@ -3,8 +3,8 @@
 precision mediump float;
 precision mediump sampler2D;
 
-// (x offset, y offset, zoom)
-uniform vec3 transform;
+// (x offset, y offset, zoom, vertical scale)
+uniform vec4 transform;
 // (window width, window height, z value)
 uniform vec3 window;
 uniform vec2 texture_scale;
@@ -1,27 +1,43 @@
 #version 410
 
-// (x offset, y offset, zoom)
-uniform vec3 transform;
+// (x offset, y offset, zoom, vertical scale)
+uniform vec4 transform;
 // (window width, window height, z value)
 uniform vec3 window;
 uniform vec2 texture_scale;
+// Multiplied with the alpha of everything drawn, to fade layers
+uniform float opacity;
 // textures grid
 uniform sampler2DArray textures;
 
 layout (location = 0) in vec3 position;
 layout (location = 1) in vec4 color;
 layout (location = 2) in float texture_index;
+// Only varies when drawing an InstancedDrawable. Otherwise these are constant (0, 0, 0, 1) and
+// (1, 1, 1, 1), which leave the vertex alone.
+// (x offset, y offset, scale * sin(angle), scale * cos(angle))
+layout (location = 3) in vec4 instance_transform;
+// Multiplied with the vertex color
+layout (location = 4) in vec4 instance_color;
 
 out vec4 fs_color;
 out vec3 fs_texture_coord;
 void main() {
-    fs_color = color;
+    fs_color = color * instance_color * vec4(1.0, 1.0, 1.0, opacity);
+
+    // Rotate and scale around the origin, then translate
+    float sin_scaled = instance_transform[2];
+    float cos_scaled = instance_transform[3];
+    vec2 pos = vec2(
+        cos_scaled * position[0] - sin_scaled * position[1] + instance_transform[0],
+        sin_scaled * position[0] + cos_scaled * position[1] + instance_transform[1]
+    );
 
     float zoom = transform[2];
 
     // This is map_to_screen
-    float screen_x = (position[0] * zoom) - transform[0];
-    float screen_y = (position[1] * zoom) - transform[1];
+    float screen_x = (pos[0] * zoom) - transform[0];
+    float screen_y = (pos[1] * zoom * transform[3]) - transform[1];
 
     // Translate position to normalized device coordinates (NDC)
     float x = (screen_x / window[0] * 2.0) - 1.0;
@@ -40,7 +56,7 @@ void main() {
     // Note the y inversion
     gl_Position = vec4(x, -y, z, 1.0);
 
-    float t_x = ((position[0] * zoom)) / texture_scale[0] / zoom;
-    float t_y = ((position[1] * zoom)) / texture_scale[1] / zoom;
+    float t_x = ((pos[0] * zoom)) / texture_scale[0] / zoom;
+    float t_y = ((pos[1] * zoom)) / texture_scale[1] / zoom;
     fs_texture_coord = vec3(vec2(t_x, t_y), texture_index);
 }
@@ -3,11 +3,13 @@
 precision mediump float;
 precision mediump sampler2D;
 
-// (x offset, y offset, zoom)
-uniform vec3 transform;
+// (x offset, y offset, zoom, vertical scale)
+uniform vec4 transform;
 // (window width, window height, z value)
 uniform vec3 window;
 uniform vec2 texture_scale;
+// Multiplied with the alpha of everything drawn, to fade layers
+uniform float opacity;
 
 // in
 attribute vec3 position;
@@ -18,13 +20,13 @@ attribute float texture_index;
 varying vec4 fs_color;
 varying vec3 fs_texture_coord;
 void main() {
-    fs_color = color;
+    fs_color = color * vec4(1.0, 1.0, 1.0, opacity);
 
     float zoom = transform[2];
 
     // This is map_to_screen
     float screen_x = (position[0] * zoom) - transform[0];
-    float screen_y = (position[1] * zoom) - transform[1];
+    float screen_y = (position[1] * zoom * transform[3]) - transform[1];
 
     // Translate position to normalized device coordinates (NDC)
     float x = (screen_x / window[0] * 2.0) - 1.0;
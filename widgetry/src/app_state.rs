@@ -7,6 +7,8 @@
 
 use abstutil::CloneableAny;
 
+use crate::tools::toast::ToastStack;
+use crate::tools::{Confirm, ToastHistory};
 use crate::{Canvas, Color, EventCtx, GfxCtx, Outcome, Panel};
 
 /// Any data that should last the entire lifetime of the application should be stored in the struct
@@ -14,6 +16,8 @@ use crate::{Canvas, Color, EventCtx, GfxCtx, Outcome, Panel};
 pub trait SharedAppState {
     /// Before `State::event` is called, call this.
     fn before_event(&mut self) {}
+    /// After `State::event` is called and any transition happens, call this.
+    fn after_event(&mut self, _: &mut EventCtx) {}
     /// When DrawBaselayer::DefaultDraw is called, run this.
     fn draw_default(&self, _: &mut GfxCtx) {}
 
@@ -21,6 +25,11 @@ pub trait SharedAppState {
     fn dump_before_abort(&self, _: &Canvas) {}
     /// Called before a normal exit, like window close
     fn before_quit(&self, _: &Canvas) {}
+    /// If closing the window now would lose something, describe it. The user is asked to confirm
+    /// before quitting.
+    fn unsaved_work(&self) -> Option<String> {
+        None
+    }
 
     /// If widgetry determines the video card is low on memory, this may be called. The application
     /// should make its best effort to delete any unused Drawables.
@@ -31,12 +40,44 @@ pub(crate) struct App<A: SharedAppState> {
     /// A stack of states
     pub(crate) states: Vec<Box<dyn State<A>>>,
     pub(crate) shared_app_state: A,
+    /// Notifications float above every state
+    pub(crate) toasts: ToastStack,
+    /// The window was closed while there was unsaved work
+    pub(crate) quit_requested: bool,
 }
 
 impl<A: 'static + SharedAppState> App<A> {
     pub(crate) fn event(&mut self, ctx: &mut EventCtx) {
         self.shared_app_state.before_event();
 
+        if std::mem::take(&mut self.quit_requested) {
+            if let Some(msg) = self.shared_app_state.unsaved_work() {
+                let transition = Transition::Push(Confirm::new_state(
+                    ctx,
+                    "Quit without saving?",
+                    vec![msg],
+                    "Quit anyway",
+                    Box::new(|ctx, app: &mut A| {
+                        app.before_quit(ctx.canvas);
+                        std::process::exit(0)
+                    }),
+                ));
+                if self.execute_transition(ctx, transition) {
+                    ctx.no_op_event(true, |ctx| self.event(ctx));
+                }
+                return;
+            }
+        }
+
+        self.toasts.update(ctx);
+        if self.toasts.event(ctx) {
+            let transition = Transition::Push(ToastHistory::new_state(ctx));
+            if self.execute_transition(ctx, transition) {
+                ctx.no_op_event(true, |ctx| self.event(ctx));
+            }
+            return;
+        }
+
         let transition = self
             .states
             .last_mut()
@@ -48,6 +89,9 @@ impl<A: 'static + SharedAppState> App<A> {
             // tutorial mode immediately pushes on a Warper.) So just recurse.
             ctx.no_op_event(true, |ctx| self.event(ctx));
         }
+        // Show anything new right away
+        self.toasts.update(ctx);
+        self.shared_app_state.after_event(ctx);
     }
 
     pub(crate) fn draw(&self, g: &mut GfxCtx) {
@@ -84,6 +128,12 @@ impl<A: 'static + SharedAppState> App<A> {
             }
         }
         state.draw(g, &self.shared_app_state);
+        self.toasts.draw(g);
+    }
+
+    /// True if the user is already being asked whether to quit.
+    pub(crate) fn confirming_quit(&self) -> bool {
+        self.states.last().unwrap().is::<Confirm<A>>()
     }
 
     /// If true, then the top-most state on the stack needs to be "woken up" with a fake mouseover
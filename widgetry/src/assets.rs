@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::time::SystemTime;
 
 use lru::LruCache;
 use usvg::fontdb;
@@ -7,16 +8,18 @@ use usvg::Options;
 
 use geom::Bounds;
 
-use crate::text::Font;
+use crate::text::{Font, FontMetrics};
 use crate::{text, EventCtx, GeomBatch, GfxCtx, Prerender, Style};
 
 // TODO We don't need refcell maybe? Can we take &mut Assets?
 pub struct Assets {
     pub default_line_height: RefCell<f64>,
     text_cache: RefCell<LruCache<String, GeomBatch>>,
-    line_height_cache: RefCell<HashMap<(Font, usize), f64>>,
+    metrics_cache: RefCell<HashMap<(Font, usize), FontMetrics>>,
     // Keyed by filename
     svg_cache: RefCell<HashMap<String, (GeomBatch, Bounds)>>,
+    // Only filled out in debug builds, to notice when SVG files change
+    svg_modified: RefCell<HashMap<String, SystemTime>>,
     font_to_id: HashMap<Font, fontdb::ID>,
     extra_fonts: RefCell<HashSet<String>>,
     pub(crate) style: RefCell<Style>,
@@ -45,8 +48,9 @@ impl Assets {
         let mut a = Assets {
             default_line_height: RefCell::new(0.0),
             text_cache: RefCell::new(LruCache::new(500)),
-            line_height_cache: RefCell::new(HashMap::new()),
+            metrics_cache: RefCell::new(HashMap::new()),
             svg_cache: RefCell::new(HashMap::new()),
+            svg_modified: RefCell::new(HashMap::new()),
             font_to_id: HashMap::new(),
             extra_fonts: RefCell::new(HashSet::new()),
             text_opts: RefCell::new(Options::default()),
@@ -107,30 +111,38 @@ impl Assets {
         // font.
     }
 
+    /// How much vertical space a line of text in this font needs, including spacing between lines
     pub fn line_height(&self, font: Font, font_size: usize) -> f64 {
+        self.font_metrics(font, font_size).line_height()
+    }
+
+    /// The vertical metrics of a font at some size, in pixels
+    pub fn font_metrics(&self, font: Font, font_size: usize) -> FontMetrics {
         let key = (font, font_size);
-        if let Some(height) = self.line_height_cache.borrow().get(&key) {
-            return *height;
+        if let Some(metrics) = self.metrics_cache.borrow().get(&key) {
+            return *metrics;
         }
 
-        // This seems to be missing line_gap, and line_gap is 0, so manually adjust here.
-        let line_height = self
+        let metrics = self
             .text_opts
             .borrow()
             .fontdb
             .with_face_data(self.font_to_id[&font], |data, face_index| {
-                let font = ttf_parser::Face::from_slice(data, face_index).unwrap();
-                let units_per_em = font.units_per_em();
-                let ascent = font.ascender();
-                let descent = font.descender();
-                let scale = (font_size as f64) / (units_per_em as f64);
-                ((ascent - descent) as f64) * scale
+                let face = ttf_parser::Face::from_slice(data, face_index).unwrap();
+                let scale = (font_size as f64) / (face.units_per_em() as f64);
+                // ttf_parser prefers the typographic metrics from the OS/2 table when the font
+                // asks for that, and falls back to the hhea table
+                FontMetrics {
+                    ascent: (face.ascender() as f64) * scale,
+                    // Negative in the font, since it's below the baseline
+                    descent: -(face.descender() as f64) * scale,
+                    line_gap: (face.line_gap() as f64) * scale,
+                }
             })
             .unwrap();
-        let height = text::SCALE_LINE_HEIGHT * line_height;
 
-        self.line_height_cache.borrow_mut().insert(key, height);
-        height
+        self.metrics_cache.borrow_mut().insert(key, metrics);
+        metrics
     }
 
     #[allow(clippy::ptr_arg)] // &[str] does not work with `LruCache`
@@ -153,6 +165,42 @@ impl Assets {
     pub fn cache_svg(&self, key: String, geom: GeomBatch, bounds: Bounds) {
         self.svg_cache.borrow_mut().insert(key, (geom, bounds));
     }
+
+    /// In debug builds, remember when an SVG file last changed, so `reload_changed_svgs` can
+    /// notice edits. The filename is relative to the data directory, like apps pass to
+    /// `read_svg`.
+    pub(crate) fn watch_svg(&self, filename: &str) {
+        if cfg!(debug_assertions) && !cfg!(target_arch = "wasm32") {
+            if let Ok(modified) =
+                fs_err::metadata(abstio::path(filename)).and_then(|m| m.modified())
+            {
+                self.svg_modified
+                    .borrow_mut()
+                    .insert(filename.to_string(), modified);
+            }
+        }
+    }
+
+    /// Forgets every cached SVG file that's changed since it was loaded, so the next use reads it
+    /// again. Returns the changed files. Only does anything in debug builds.
+    pub(crate) fn reload_changed_svgs(&self) -> Vec<String> {
+        let mut changed = Vec::new();
+        for (filename, last_modified) in self.svg_modified.borrow_mut().iter_mut() {
+            if let Ok(modified) =
+                fs_err::metadata(abstio::path(filename)).and_then(|m| m.modified())
+            {
+                if modified != *last_modified {
+                    *last_modified = modified;
+                    changed.push(filename.clone());
+                }
+            }
+        }
+        let mut cache = self.svg_cache.borrow_mut();
+        for filename in &changed {
+            cache.remove(&format!("file://{}", filename));
+        }
+        changed
+    }
 }
 
 impl std::convert::AsRef<Assets> for GfxCtx<'_> {
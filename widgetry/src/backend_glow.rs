@@ -4,7 +4,10 @@ use std::rc::Rc;
 use glow::HasContext;
 
 use crate::drawing::Uniforms;
-use crate::{Canvas, Color, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenRectangle};
+use crate::{
+    Canvas, Color, EventCtx, GeomBatch, GfxCtx, Instance, PreparedInstances, Prerender, ScreenDims,
+    ScreenRectangle,
+};
 
 #[cfg(feature = "native-backend")]
 pub use crate::backend_glow_native::setup;
@@ -41,6 +44,11 @@ pub(crate) unsafe fn build_program(
 
     gl.use_program(Some(program));
 
+    // Everything besides an InstancedDrawable uses these constant per-instance attributes: no
+    // transformation and no change to the color.
+    gl.vertex_attrib_4_f32(3, 0.0, 0.0, 0.0, 1.0);
+    gl.vertex_attrib_4_f32(4, 1.0, 1.0, 1.0, 1.0);
+
     gl.enable(glow::SCISSOR_TEST);
     gl.enable(glow::DEPTH_TEST);
     gl.depth_func(glow::LEQUAL);
@@ -76,6 +84,7 @@ pub struct GfxCtxInnards<'a> {
     current_clip: Option<[i32; 4]>,
     transform_location: <glow::Context as glow::HasContext>::UniformLocation,
     window_location: <glow::Context as glow::HasContext>::UniformLocation,
+    opacity_location: <glow::Context as glow::HasContext>::UniformLocation,
 }
 
 impl<'a> GfxCtxInnards<'a> {
@@ -83,10 +92,11 @@ impl<'a> GfxCtxInnards<'a> {
         gl: &'a glow::Context,
         program: &'a <glow::Context as glow::HasContext>::Program,
     ) -> Self {
-        let (transform_location, window_location) = unsafe {
+        let (transform_location, window_location, opacity_location) = unsafe {
             (
                 gl.get_uniform_location(*program, "transform").unwrap(),
                 gl.get_uniform_location(*program, "window").unwrap(),
+                gl.get_uniform_location(*program, "opacity").unwrap(),
             )
         };
         GfxCtxInnards {
@@ -94,6 +104,7 @@ impl<'a> GfxCtxInnards<'a> {
             current_clip: None,
             transform_location,
             window_location,
+            opacity_location,
         }
     }
 
@@ -107,13 +118,20 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
-    pub fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, _: &PrerenderInnards) {
+    fn set_uniforms(&mut self, uniforms: &Uniforms) {
         unsafe {
             self.gl
-                .uniform_3_f32_slice(Some(&self.transform_location), &uniforms.transform);
+                .uniform_4_f32_slice(Some(&self.transform_location), &uniforms.transform);
             self.gl
                 .uniform_3_f32_slice(Some(&self.window_location), &uniforms.window);
+            self.gl
+                .uniform_1_f32(Some(&self.opacity_location), uniforms.opacity);
+        }
+    }
 
+    pub fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, _: &PrerenderInnards) {
+        self.set_uniforms(uniforms);
+        unsafe {
             self.gl.bind_vertex_array(Some(obj.vert_array.id));
             self.gl
                 .draw_elements(glow::TRIANGLES, obj.num_indices, glow::UNSIGNED_INT, 0);
@@ -121,6 +139,53 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
+    /// The caller must handle `InstancedDrawable::expand` first, when instancing isn't supported.
+    pub fn redraw_instanced(
+        &mut self,
+        obj: &InstancedDrawable,
+        instances: &[Instance],
+        uniforms: &Uniforms,
+    ) {
+        let data: Vec<[f32; 8]> = instances
+            .iter()
+            .map(|instance| {
+                let (sin, cos) = instance.angle.normalized_radians().sin_cos();
+                [
+                    instance.pt.x() as f32,
+                    instance.pt.y() as f32,
+                    (instance.scale * sin) as f32,
+                    (instance.scale * cos) as f32,
+                    instance.color.r,
+                    instance.color.g,
+                    instance.color.b,
+                    instance.color.a,
+                ]
+            })
+            .collect();
+
+        self.set_uniforms(uniforms);
+        unsafe {
+            self.gl
+                .bind_buffer(glow::ARRAY_BUFFER, Some(obj.instance_buffer.id));
+            self.gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                data.align_to::<u8>().1,
+                glow::STREAM_DRAW,
+            );
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+
+            self.gl.bind_vertex_array(Some(obj.mesh.vert_array.id));
+            self.gl.draw_elements_instanced(
+                glow::TRIANGLES,
+                obj.mesh.num_indices,
+                glow::UNSIGNED_INT,
+                0,
+                data.len() as i32,
+            );
+            self.gl.bind_vertex_array(None);
+        }
+    }
+
     pub fn enable_clipping(&mut self, rect: ScreenRectangle, scale_factor: f64, canvas: &Canvas) {
         assert!(self.current_clip.is_none());
         // The scissor rectangle is in units of physical pixles, as opposed to logical pixels
@@ -194,6 +259,52 @@ impl Drawable {
     }
 }
 
+/// One shape uploaded to the GPU once, then drawn many times per frame with different positions,
+/// rotations, and colors. Only the small list of instances is uploaded per frame, which is much
+/// cheaper than uploading a `GeomBatch` with every copy of the shape. Create by calling
+/// `ctx.upload_instanced(batch)` with the shape centered on the origin, then draw with
+/// `g.draw_instances`.
+pub struct InstancedDrawable {
+    mesh: Drawable,
+    /// Rewritten every time this is drawn
+    instance_buffer: Buffer,
+    /// WebGL 1 can't draw instances, so the copies are made on the CPU instead
+    fallback: Option<GeomBatch>,
+}
+
+impl Drop for InstancedDrawable {
+    #[inline]
+    fn drop(&mut self) {
+        self.instance_buffer.destroy(&self.mesh.gl);
+    }
+}
+
+impl InstancedDrawable {
+    pub fn draw(&self, g: &mut GfxCtx, instances: &[Instance]) {
+        g.draw_instances(self, instances);
+    }
+
+    /// Prepare instances that'll be drawn many times without changing
+    pub fn prepare(&self, prerender: &Prerender, instances: Vec<Instance>) -> PreparedInstances {
+        match self.expand(&instances) {
+            Some(batch) => PreparedInstances {
+                instances: Vec::new(),
+                expanded: Some(prerender.upload(batch)),
+            },
+            None => PreparedInstances {
+                instances,
+                expanded: None,
+            },
+        }
+    }
+
+    /// If instancing isn't supported, copies the shape for every instance.
+    pub(crate) fn expand(&self, instances: &[Instance]) -> Option<GeomBatch> {
+        let mesh = self.fallback.as_ref()?;
+        Some(mesh.copy_for_instances(instances))
+    }
+}
+
 struct VertexArray {
     id: <glow::Context as glow::HasContext>::VertexArray,
     was_destroyed: bool,
@@ -384,6 +495,43 @@ impl PrerenderInnards {
         }
     }
 
+    pub fn actually_upload_instanced(&self, batch: GeomBatch) -> InstancedDrawable {
+        let fallback = if self.is_gl2 {
+            None
+        } else {
+            Some(batch.clone())
+        };
+        let mesh = self.actually_upload(true, batch);
+        let instance_buffer = Buffer::new(&self.gl);
+
+        if self.is_gl2 {
+            unsafe {
+                self.gl.bind_vertex_array(Some(mesh.vert_array.id));
+                self.gl
+                    .bind_buffer(glow::ARRAY_BUFFER, Some(instance_buffer.id));
+
+                // instance_transform and instance_color are both vec4, and advance once per
+                // instance, not per vertex
+                let stride = 8 * std::mem::size_of::<f32>() as i32;
+                for (i, offset) in [(3, 0), (4, 4 * std::mem::size_of::<f32>() as i32)] {
+                    self.gl.enable_vertex_attrib_array(i);
+                    self.gl
+                        .vertex_attrib_pointer_f32(i, 4, glow::FLOAT, false, stride, offset);
+                    self.gl.vertex_attrib_divisor(i, 1);
+                }
+
+                self.gl.bind_vertex_array(None);
+                self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+            }
+        }
+
+        InstancedDrawable {
+            mesh,
+            instance_buffer,
+            fallback,
+        }
+    }
+
     pub(crate) fn window(&self) -> &winit::window::Window {
         self.window_adapter.as_ref().expect("no window").window()
     }
@@ -1,8 +1,10 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use anyhow::Result;
 use wasm_bindgen::JsCast;
+use winit::event::{Touch, TouchPhase};
+use winit::event_loop::EventLoopProxy;
 use winit::platform::web::WindowExtWebSys;
 
 use crate::assets::Assets;
@@ -61,6 +63,8 @@ pub fn setup(settings: &Settings) -> (PrerenderInnards, winit::event_loop::Event
         closure.forget();
     }
 
+    listen_for_touches(&root_element, &canvas, event_loop.create_proxy());
+
     // First try WebGL 2.0 context.
     // WebGL 2.0 isn't supported by default on macOS Safari, or any iOS browser (which are all just
     // Safari wrappers).
@@ -109,6 +113,83 @@ pub fn setup(settings: &Settings) -> (PrerenderInnards, winit::event_loop::Event
     )
 }
 
+thread_local! {
+    static TOUCHES: RefCell<Vec<Touch>> = RefCell::new(Vec::new());
+}
+
+/// This version of winit doesn't produce touch events on the web, so listen for them directly.
+/// They're buffered here and the event loop is woken up to `take_touches`.
+fn listen_for_touches(
+    root_element: &web_sys::Element,
+    canvas: &web_sys::HtmlCanvasElement,
+    proxy: EventLoopProxy<()>,
+) {
+    // Stop the browser from scrolling or zooming the page instead
+    canvas.style().set_property("touch-action", "none").unwrap();
+
+    // winit turns pointer events from fingers into mouse events. Stop them before they reach the
+    // canvas, so a finger isn't handled twice.
+    for name in ["pointerdown", "pointermove", "pointerup", "pointercancel"] {
+        let closure = wasm_bindgen::closure::Closure::wrap(Box::new(|e: web_sys::PointerEvent| {
+            if e.pointer_type() == "touch" {
+                e.stop_propagation();
+            }
+        }) as Box<dyn FnMut(_)>);
+        root_element
+            .add_event_listener_with_callback_and_bool(name, closure.as_ref().unchecked_ref(), true)
+            .unwrap();
+        closure.forget();
+    }
+
+    for (name, phase) in [
+        ("touchstart", TouchPhase::Started),
+        ("touchmove", TouchPhase::Moved),
+        ("touchend", TouchPhase::Ended),
+        ("touchcancel", TouchPhase::Cancelled),
+    ] {
+        let canvas_for_closure = canvas.clone();
+        let proxy = proxy.clone();
+        let closure =
+            wasm_bindgen::closure::Closure::wrap(Box::new(move |e: web_sys::TouchEvent| {
+                // Also stops the browser from emulating mouse events
+                e.prevent_default();
+                let rect = canvas_for_closure.get_bounding_client_rect();
+                let scale_factor = web_sys::window().unwrap().device_pixel_ratio();
+                let changed = e.changed_touches();
+                TOUCHES.with(|touches| {
+                    let mut touches = touches.borrow_mut();
+                    for i in 0..changed.length() {
+                        if let Some(touch) = changed.get(i) {
+                            let location = winit::dpi::LogicalPosition::new(
+                                f64::from(touch.client_x()) - rect.left(),
+                                f64::from(touch.client_y()) - rect.top(),
+                            );
+                            touches.push(Touch {
+                                // There's no public way to make a real one
+                                device_id: unsafe { winit::event::DeviceId::dummy() },
+                                phase,
+                                location: location.to_physical(scale_factor),
+                                force: None,
+                                id: touch.identifier() as u64,
+                            });
+                        }
+                    }
+                });
+                // The event loop only wakes up for its own events
+                let _ = proxy.send_event(());
+            }) as Box<dyn FnMut(_)>);
+        canvas
+            .add_event_listener_with_callback(name, closure.as_ref().unchecked_ref())
+            .unwrap();
+        closure.forget();
+    }
+}
+
+/// Everything that's happened to fingers since the last call
+pub fn take_touches() -> Vec<Touch> {
+    TOUCHES.with(|touches| std::mem::take(&mut *touches.borrow_mut()))
+}
+
 fn webgl2_program(gl: glow::Context) -> Result<(glow::Context, glow::Program)> {
     let program = unsafe {
         build_program(
@@ -203,6 +284,7 @@ impl RenderOnly {
             style: &mut self.style,
             updates_requested: vec![],
             canvas_movement_called: false,
+            button_clicked: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
         }
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use geom::{Bounds, Pt2D};
 
+use crate::tools::toast::ToastLog;
 use crate::{Key, ScreenDims, ScreenPt, ScreenRectangle, UpdateType, UserInput};
 
 // Click and release counts as a normal click, not a drag, if the distance between click and
@@ -12,6 +13,10 @@ use crate::{Key, ScreenDims, ScreenPt, ScreenRectangle, UpdateType, UserInput};
 const DRAG_THRESHOLD: f64 = 5.0;
 
 const PAN_SPEED: f64 = 15.0;
+// For a gamepad stick pushed all the way, in screen pixels per second
+const GAMEPAD_PAN_SPEED: f64 = 800.0;
+// For a gamepad trigger held down, in zoom steps per second
+const GAMEPAD_ZOOM_SPEED: f64 = 10.0;
 
 const PANNING_THRESHOLD: f64 = 25.0;
 
@@ -21,6 +26,9 @@ pub struct Canvas {
     pub cam_x: f64,
     pub cam_y: f64,
     pub cam_zoom: f64,
+    /// Map-space is squashed vertically by this much, to look at the map from an angle instead
+    /// of straight down. 1.0 is straight down.
+    pub vertical_scale: f64,
 
     // TODO Should this become Option<ScreenPt>?
     pub(crate) cursor: ScreenPt,
@@ -39,6 +47,17 @@ pub struct Canvas {
 
     // TODO Bit weird and hacky to mutate inside of draw() calls.
     pub(crate) covered_areas: RefCell<Vec<ScreenRectangle>>,
+    // Scrollable areas nested inside panels handle the mouse wheel themselves. Also filled out
+    // while drawing.
+    pub(crate) nested_scroll_areas: RefCell<Vec<ScreenRectangle>>,
+    // While handling events for widgets that're only partly visible, the cursor only counts when
+    // it's inside all of these.
+    pub(crate) hit_test_clips: Vec<ScreenRectangle>,
+    // The most recent notifications
+    pub(crate) toasts: ToastLog,
+    // How much any gamepads want to pan in each direction and zoom, all in [-1, 1]. Always 0
+    // without the gamepad feature.
+    pub(crate) gamepad_motion: (f64, f64, f64),
 
     // Kind of just widgetry state awkwardly stuck here...
     pub(crate) keys_held: HashSet<Key>,
@@ -80,6 +99,7 @@ impl Canvas {
             cam_x: 0.0,
             cam_y: 0.0,
             cam_zoom: 1.0,
+            vertical_scale: 1.0,
 
             cursor: ScreenPt::new(0.0, 0.0),
             window_has_cursor: true,
@@ -94,6 +114,10 @@ impl Canvas {
             settings,
 
             covered_areas: RefCell::new(Vec::new()),
+            nested_scroll_areas: RefCell::new(Vec::new()),
+            hit_test_clips: Vec::new(),
+            toasts: ToastLog::new(),
+            gamepad_motion: (0.0, 0.0, 0.0),
 
             keys_held: HashSet::new(),
         }
@@ -140,6 +164,10 @@ impl Canvas {
                 }
             }
 
+            if let Some(ratio) = input.get_pinch() {
+                self.zoom_to(self.cam_zoom * ratio, self.cursor);
+            }
+
             if self.settings.keys_to_pan {
                 if input.pressed(Key::LeftArrow) {
                     self.cam_x -= PAN_SPEED;
@@ -172,6 +200,18 @@ impl Canvas {
             }
         }
 
+        // The gamepad moves the camera even when the cursor is over a panel, since there might not
+        // be a mouse at all
+        if let Some(dt) = input.nonblocking_is_update_event() {
+            let (pan_x, pan_y, zoom) = self.gamepad_motion;
+            let dt = dt.inner_seconds();
+            self.cam_x += pan_x * GAMEPAD_PAN_SPEED * dt;
+            self.cam_y += pan_y * GAMEPAD_PAN_SPEED * dt;
+            if zoom != 0.0 {
+                self.zoom(zoom * GAMEPAD_ZOOM_SPEED * dt, self.center_to_screen_pt());
+            }
+        }
+
         // If we start the drag on the map and move the mouse off the map, keep dragging.
         if let Some((click, orig)) = self.drag_canvas_from {
             let pt = self.get_cursor();
@@ -219,12 +259,17 @@ impl Canvas {
     }
 
     pub fn zoom(&mut self, delta: f64, focus: ScreenPt) {
+        let new_zoom = 1.1_f64.powf(
+            self.cam_zoom.log(1.1) + delta * (self.settings.canvas_scroll_speed as f64 / 10.0),
+        );
+        self.zoom_to(new_zoom, focus);
+    }
+
+    /// Change the zoom level, keeping the focus point in the same place on the screen.
+    pub fn zoom_to(&mut self, new_zoom: f64, focus: ScreenPt) {
         let old_zoom = self.cam_zoom;
         // By popular request, some limits ;)
-        self.cam_zoom = 1.1_f64
-            .powf(old_zoom.log(1.1) + delta * (self.settings.canvas_scroll_speed as f64 / 10.0))
-            .max(self.min_zoom())
-            .min(self.max_zoom());
+        self.cam_zoom = new_zoom.max(self.min_zoom()).min(self.max_zoom());
 
         // Make screen_to_map of the focus point still point to the same thing after
         // zooming.
@@ -234,6 +279,14 @@ impl Canvas {
 
     pub(crate) fn start_drawing(&self) {
         self.covered_areas.borrow_mut().clear();
+        self.nested_scroll_areas.borrow_mut().clear();
+    }
+
+    pub(crate) fn is_over_nested_scroll_area(&self, pt: ScreenPt) -> bool {
+        self.nested_scroll_areas
+            .borrow()
+            .iter()
+            .any(|rect| rect.contains(pt))
     }
 
     // TODO Only public for the OSD. :(
@@ -247,7 +300,13 @@ impl Canvas {
     }
 
     pub fn get_cursor_in_screen_space(&self) -> Option<ScreenPt> {
-        if self.window_has_cursor && self.get_cursor_in_map_space().is_none() {
+        if self.window_has_cursor
+            && self.get_cursor_in_map_space().is_none()
+            && self
+                .hit_test_clips
+                .iter()
+                .all(|rect| rect.contains(self.cursor))
+        {
             Some(self.get_cursor())
         } else {
             None
@@ -273,7 +332,7 @@ impl Canvas {
     pub fn screen_to_map(&self, pt: ScreenPt) -> Pt2D {
         Pt2D::new(
             (pt.x + self.cam_x) / self.cam_zoom,
-            (pt.y + self.cam_y) / self.cam_zoom,
+            (pt.y + self.cam_y) / (self.cam_zoom * self.vertical_scale),
         )
     }
 
@@ -287,13 +346,13 @@ impl Canvas {
 
     pub fn center_on_map_pt(&mut self, pt: Pt2D) {
         self.cam_x = (pt.x() * self.cam_zoom) - (self.window_width / 2.0);
-        self.cam_y = (pt.y() * self.cam_zoom) - (self.window_height / 2.0);
+        self.cam_y = (pt.y() * self.cam_zoom * self.vertical_scale) - (self.window_height / 2.0);
     }
 
     pub fn map_to_screen(&self, pt: Pt2D) -> ScreenPt {
         ScreenPt::new(
             (pt.x() * self.cam_zoom) - self.cam_x,
-            (pt.y() * self.cam_zoom) - self.cam_y,
+            (pt.y() * self.cam_zoom * self.vertical_scale) - self.cam_y,
         )
     }
 
@@ -1,12 +1,12 @@
 use std::cell::Cell;
 
-use geom::{Bounds, Polygon, Pt2D, Tessellation};
+use geom::{Angle, Bounds, Polygon, Pt2D, Tessellation};
 
 use crate::assets::Assets;
 use crate::backend::{GfxCtxInnards, PrerenderInnards};
 use crate::{
-    Canvas, Color, Drawable, EventCtx, GeomBatch, Key, ScreenDims, ScreenPt, ScreenRectangle,
-    Style, Text,
+    Canvas, Color, Drawable, EventCtx, GeomBatch, InstancedDrawable, Key, ScreenDims, ScreenPt,
+    ScreenRectangle, Style, Text,
 };
 
 // We organize major layers of the app with whole number z values, with lower values being more on
@@ -21,10 +21,12 @@ pub(crate) const TOOLTIP_Z: f32 = -2.0;
 
 #[derive(Debug)]
 pub struct Uniforms {
-    /// (cam_x, cam_y, cam_zoom)
-    pub transform: [f32; 3],
+    /// (cam_x, cam_y, cam_zoom, vertical_scale)
+    pub transform: [f32; 4],
     /// (window_width, window_height, Z values)
     pub window: [f32; 3],
+    /// Multiplied with the alpha of everything drawn
+    pub opacity: f32,
 }
 
 impl Uniforms {
@@ -34,19 +36,43 @@ impl Uniforms {
                 canvas.cam_x as f32,
                 canvas.cam_y as f32,
                 canvas.cam_zoom as f32,
+                canvas.vertical_scale as f32,
             ],
             window: [
                 canvas.window_width as f32,
                 canvas.window_height as f32,
                 MAPSPACE_Z,
             ],
+            opacity: 1.0,
         }
     }
 }
 
+/// Where and how to draw one copy of an `InstancedDrawable`
+#[derive(Clone, Copy, Debug)]
+pub struct Instance {
+    /// The shape's origin is moved here
+    pub pt: Pt2D,
+    /// Rotation around the shape's origin
+    pub angle: Angle,
+    pub scale: f64,
+    /// Multiplied with the shape's colors, so a white shape takes on exactly this color
+    pub color: Color,
+}
+
+/// Instances that don't change between frames, ready to draw with one `InstancedDrawable`. Where
+/// instancing isn't supported, the copies are uploaded once when preparing, instead of every
+/// frame. Create with `InstancedDrawable::prepare` and draw with `g.draw_prepared_instances`.
+pub struct PreparedInstances {
+    pub(crate) instances: Vec<Instance>,
+    pub(crate) expanded: Option<Drawable>,
+}
+
 pub struct GfxCtx<'a> {
     pub(crate) inner: GfxCtxInnards<'a>,
     uniforms: Uniforms,
+    // Only applies to map-space drawing; forks reset it
+    opacity: f32,
 
     screencap_mode: bool,
     pub(crate) naming_hint: Option<String>,
@@ -58,6 +84,9 @@ pub struct GfxCtx<'a> {
 
     pub(crate) num_draw_calls: usize,
     pub(crate) num_forks: usize,
+
+    // Clipping can be nested, like for a scrollable area inside a scrollable panel
+    clip_stack: Vec<ScreenRectangle>,
 }
 
 impl<'a> GfxCtx<'a> {
@@ -71,6 +100,7 @@ impl<'a> GfxCtx<'a> {
         GfxCtx {
             inner: prerender.inner.draw_new_frame(),
             uniforms,
+            opacity: 1.0,
             canvas,
             style,
             prerender,
@@ -78,6 +108,7 @@ impl<'a> GfxCtx<'a> {
             num_forks: 0,
             screencap_mode,
             naming_hint: None,
+            clip_stack: Vec::new(),
         }
     }
 
@@ -95,32 +126,69 @@ impl<'a> GfxCtx<'a> {
         let cam_x = (top_left_map.x() * zoom) - top_left_screen.x;
         let cam_y = (top_left_map.y() * zoom) - top_left_screen.y;
 
-        self.uniforms.transform = [cam_x as f32, cam_y as f32, zoom as f32];
+        self.uniforms.transform = [cam_x as f32, cam_y as f32, zoom as f32, 1.0];
         self.uniforms.window = [
             self.canvas.window_width as f32,
             self.canvas.window_height as f32,
             z.unwrap_or(SCREENSPACE_Z),
         ];
+        self.uniforms.opacity = 1.0;
         self.num_forks += 1;
     }
 
     pub fn fork_screenspace(&mut self) {
-        self.uniforms.transform = [0.0, 0.0, 1.0];
+        self.uniforms.transform = [0.0, 0.0, 1.0, 1.0];
         self.uniforms.window = [
             self.canvas.window_width as f32,
             self.canvas.window_height as f32,
             SCREENSPACE_Z,
         ];
+        self.uniforms.opacity = 1.0;
         self.num_forks += 1;
     }
 
     pub fn unfork(&mut self) {
         self.uniforms = Uniforms::new(self.canvas);
+        self.uniforms.opacity = self.opacity;
         self.num_forks += 1;
 
         // println!("{:?}", backtrace::Backtrace::new());
     }
 
+    /// Fades everything drawn afterwards, with 0.0 being invisible and 1.0 fully opaque. Forking
+    /// to draw panels or other things in screen-space temporarily resets this, so only map-space
+    /// drawing is faded. Callers should set this back to 1.0 when they're done.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.opacity = opacity.clamp(0.0, 1.0) as f32;
+        self.uniforms.opacity = self.opacity;
+    }
+
+    pub fn draw_instances(&mut self, obj: &InstancedDrawable, instances: &[Instance]) {
+        if instances.is_empty() {
+            return;
+        }
+        if let Some(batch) = obj.expand(instances) {
+            let draw = self.prerender.upload_temporary(batch);
+            self.redraw(&draw);
+            return;
+        }
+        self.inner.redraw_instanced(obj, instances, &self.uniforms);
+        self.num_draw_calls += 1;
+    }
+
+    /// Like `draw_instances`, without uploading anything when instancing isn't supported
+    pub fn draw_prepared_instances(
+        &mut self,
+        obj: &InstancedDrawable,
+        prepared: &PreparedInstances,
+    ) {
+        if let Some(ref draw) = prepared.expanded {
+            self.redraw(draw);
+        } else {
+            self.draw_instances(obj, &prepared.instances);
+        }
+    }
+
     pub fn clear(&mut self, color: Color) {
         self.inner.clear(color);
     }
@@ -146,14 +214,31 @@ impl<'a> GfxCtx<'a> {
     }
 
     // TODO Stateful API :(
+    /// Only draw inside this rectangle, until `disable_clipping` is called. If clipping is already
+    /// enabled, only the overlap of both rectangles is drawn.
     pub fn enable_clipping(&mut self, rect: ScreenRectangle) {
         let scale_factor = self.prerender.get_scale_factor();
-        self.inner.enable_clipping(rect, scale_factor, self.canvas);
+        let rect = if let Some(outer) = self.clip_stack.last() {
+            let rect = rect.intersection(outer);
+            self.inner.disable_clipping(scale_factor, self.canvas);
+            rect
+        } else {
+            rect
+        };
+        self.inner
+            .enable_clipping(rect.clone(), scale_factor, self.canvas);
+        self.clip_stack.push(rect);
     }
 
+    /// Undoes the most recent `enable_clipping`.
     pub fn disable_clipping(&mut self) {
         let scale_factor = self.prerender.get_scale_factor();
         self.inner.disable_clipping(scale_factor, self.canvas);
+        self.clip_stack.pop();
+        if let Some(outer) = self.clip_stack.last() {
+            self.inner
+                .enable_clipping(outer.clone(), scale_factor, self.canvas);
+        }
     }
 
     // Canvas stuff.
@@ -190,7 +275,7 @@ impl<'a> GfxCtx<'a> {
         batch.append(txt_batch.translate(pt.x + pad, pt.y + pad));
 
         // fork_screenspace, but with an even more prominent Z
-        self.uniforms.transform = [0.0, 0.0, 1.0];
+        self.uniforms.transform = [0.0, 0.0, 1.0, 1.0];
         self.uniforms.window = [
             self.canvas.window_width as f32,
             self.canvas.window_height as f32,
@@ -264,6 +349,12 @@ impl Prerender {
         self.actually_upload(true, batch)
     }
 
+    /// Upload one shape, centered on the origin, to draw many copies of with `g.draw_instances`.
+    pub fn upload_instanced(&self, batch: GeomBatch) -> InstancedDrawable {
+        self.num_uploads.set(self.num_uploads.get() + 1);
+        self.inner.actually_upload_instanced(batch)
+    }
+
     pub(crate) fn upload_temporary(&self, batch: GeomBatch) -> Drawable {
         self.actually_upload(false, batch)
     }
@@ -11,7 +11,7 @@ use crate::{EventCtx, Line, ScreenDims, ScreenPt, TextSpan};
 // Ideally the delay would be a little more tolerant - e.g. 500ms, but because we don't actually
 // have a way to indicate that a single click was handled (and thus *shouldn't* be counted as part of a double click)
 // it's too easy to have false positives.
-const MAX_DOUBLE_CLICK_DURATION: instant::Duration = instant::Duration::from_millis(300);
+pub(crate) const MAX_DOUBLE_CLICK_DURATION: instant::Duration = instant::Duration::from_millis(300);
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Event {
@@ -45,6 +45,9 @@ pub enum Event {
     WindowLostCursor,
     WindowGainedCursor,
     MouseWheelScroll(f64, f64),
+    /// Two fingers on a touchscreen moved apart (more than 1) or together (less than 1) by this
+    /// ratio. The cursor is between them.
+    Pinch(f64),
     WindowResized(ScreenDims),
 }
 
@@ -5,10 +5,11 @@ use instant::Instant;
 use abstutil::{elapsed_seconds, Timer, TimerSink};
 use geom::{Percent, Polygon};
 
+use crate::tools::Toast;
 use crate::{
     svg, Canvas, CanvasSettings, Color, Drawable, Event, GeomBatch, GfxCtx, HorizontalAlignment,
-    Key, Line, Panel, PanelDims, Prerender, ScreenDims, Style, Text, UserInput, VerticalAlignment,
-    Widget,
+    InstancedDrawable, Key, Line, Panel, PanelDims, Prerender, ScreenDims, Style, Text, UserInput,
+    VerticalAlignment, Widget,
 };
 
 #[derive(Clone, PartialEq, Debug)]
@@ -21,6 +22,10 @@ pub enum UpdateType {
         zoom: f64,
         dims: ScreenDims,
     },
+    /// Save just what's on the screen right now to a PNG file
+    ScreenCaptureCurrentShot {
+        path: String,
+    },
 }
 
 pub struct EventCtx<'a> {
@@ -32,6 +37,8 @@ pub struct EventCtx<'a> {
     pub(crate) style: &'a mut Style,
     pub(crate) updates_requested: Vec<UpdateType>,
     pub(crate) canvas_movement_called: bool,
+    /// Some button in some panel was clicked during this event
+    pub(crate) button_clicked: bool,
 
     /// This widget (in some panel) exclusively owns focus. Don't modify.
     pub(crate) focus_owned_by: Option<String>,
@@ -59,6 +66,12 @@ impl<'a> EventCtx<'a> {
         f(self, &mut timer)
     }
 
+    /// True if any button was clicked while handling this event. Useful for feedback that doesn't
+    /// care which button it was.
+    pub fn any_button_clicked(&self) -> bool {
+        self.button_clicked
+    }
+
     pub fn request_update(&mut self, update_type: UpdateType) {
         self.updates_requested.push(update_type);
     }
@@ -88,6 +101,7 @@ impl<'a> EventCtx<'a> {
             style: self.style,
             updates_requested: vec![],
             canvas_movement_called: false,
+            button_clicked: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
         };
@@ -132,6 +146,10 @@ impl<'a> EventCtx<'a> {
         self.prerender.upload(batch)
     }
 
+    pub fn upload_instanced(&self, batch: GeomBatch) -> InstancedDrawable {
+        self.prerender.upload_instanced(batch)
+    }
+
     pub(crate) fn cursor_clickable(&mut self) {
         self.prerender
             .inner
@@ -154,6 +172,16 @@ impl<'a> EventCtx<'a> {
         self.style
     }
 
+    /// Show a notification without interrupting the current state.
+    pub fn show_toast(&mut self, toast: Toast) {
+        self.canvas.toasts.push(toast);
+    }
+
+    /// The most recent notifications, oldest first.
+    pub fn toast_history(&self) -> impl Iterator<Item = &Toast> {
+        self.canvas.toasts.iter()
+    }
+
     pub fn set_style(&mut self, style: Style) {
         *self.prerender.assets.style.borrow_mut() = style.clone();
         self.prerender.assets.clear_text_cache();
@@ -298,6 +326,7 @@ impl<'a> LoadingScreen<'a> {
             style: &mut self.style,
             updates_requested: vec![],
             canvas_movement_called: false,
+            button_clicked: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
         };
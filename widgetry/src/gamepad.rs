@@ -0,0 +1,89 @@
+//! Game controllers are sturdier than a mouse for kiosks and exhibitions. The D-pad acts like the
+//! arrow keys and the face buttons like enter and escape, so menus and dialogs can be navigated.
+//! The left stick pans the canvas, and the triggers zoom.
+
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+use crate::{Event, Key};
+
+/// Ignore small stick movements, since they rarely rest exactly at 0
+const DEAD_ZONE: f32 = 0.2;
+
+pub(crate) struct GamepadInput {
+    gilrs: Gilrs,
+}
+
+impl GamepadInput {
+    pub fn new() -> Option<GamepadInput> {
+        match Gilrs::new() {
+            Ok(gilrs) => Some(GamepadInput { gilrs }),
+            Err(err) => {
+                warn!("Gamepads won't work: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Translates button presses since the last call into key events.
+    pub fn poll(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        while let Some(ev) = self.gilrs.next_event() {
+            match ev.event {
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(key) = button_to_key(button) {
+                        events.push(Event::KeyPress(key));
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(key) = button_to_key(button) {
+                        events.push(Event::KeyRelease(key));
+                    }
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+
+    /// Returns how much to pan the camera in each direction and how much to zoom, all in [-1, 1].
+    pub fn camera_motion(&self) -> (f64, f64, f64) {
+        let mut pan_x = 0.0;
+        let mut pan_y = 0.0;
+        let mut zoom = 0.0;
+        for (_, gamepad) in self.gilrs.gamepads() {
+            let x = gamepad.value(Axis::LeftStickX);
+            if x.abs() > DEAD_ZONE {
+                pan_x += x as f64;
+            }
+            // Pushing the stick up is positive, but screen-space Y points down
+            let y = gamepad.value(Axis::LeftStickY);
+            if y.abs() > DEAD_ZONE {
+                pan_y -= y as f64;
+            }
+            if gamepad.is_pressed(Button::RightTrigger2) || gamepad.is_pressed(Button::RightTrigger)
+            {
+                zoom += 1.0;
+            }
+            if gamepad.is_pressed(Button::LeftTrigger2) || gamepad.is_pressed(Button::LeftTrigger) {
+                zoom -= 1.0;
+            }
+        }
+        (
+            pan_x.clamp(-1.0, 1.0),
+            pan_y.clamp(-1.0, 1.0),
+            zoom.clamp(-1.0, 1.0),
+        )
+    }
+}
+
+fn button_to_key(button: Button) -> Option<Key> {
+    match button {
+        Button::DPadUp => Some(Key::UpArrow),
+        Button::DPadDown => Some(Key::DownArrow),
+        Button::DPadLeft => Some(Key::LeftArrow),
+        Button::DPadRight => Some(Key::RightArrow),
+        Button::South | Button::Start => Some(Key::Enter),
+        Button::East | Button::Select => Some(Key::Escape),
+        _ => None,
+    }
+}
@@ -1,11 +1,12 @@
 use geom::{Angle, Bounds, GPSBounds, Polygon, Pt2D, Tessellation};
 
 use crate::{
-    svg, Color, DeferDraw, Drawable, EventCtx, Fill, GfxCtx, JustDraw, Prerender, ScreenDims,
-    Widget,
+    svg, Color, DeferDraw, Drawable, EventCtx, Fill, GfxCtx, Instance, JustDraw, Prerender,
+    ScreenDims, Widget, ICON_ACCENT,
 };
 
 pub mod geom_batch_stack;
+pub mod pattern;
 
 /// A mutable builder for a group of colored tessellated polygons.
 #[derive(Clone)]
@@ -168,6 +169,12 @@ impl GeomBatch {
         svg::load_svg_from_bytes_uncached(raw).unwrap().0
     }
 
+    /// Like `load_svg_bytes_uncached`, but for SVGs that might be malformed, like ones downloaded
+    /// from somewhere.
+    pub fn try_load_svg_bytes_uncached(raw: &[u8]) -> anyhow::Result<GeomBatch> {
+        Ok(svg::load_svg_from_bytes_uncached(raw)?.0)
+    }
+
     /// Transforms all colors in a batch.
     pub fn color(mut self, transformation: RewriteColor) -> GeomBatch {
         for (fancy, _, _) in &mut self.list {
@@ -235,6 +242,34 @@ impl GeomBatch {
         self
     }
 
+    /// Makes a copy of this batch for every instance, the same way `g.draw_instances` would. Use
+    /// for shapes normally drawn with instancing that also need to go into a regular batch.
+    pub fn copy_for_instances(&self, instances: &[Instance]) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        for instance in instances {
+            for (fill, mut poly, z) in self.list.iter().cloned() {
+                poly.inplace_multi_transform(
+                    instance.scale,
+                    instance.pt.x(),
+                    instance.pt.y(),
+                    instance.angle,
+                    instance.pt,
+                );
+                let fill = match fill {
+                    Fill::Color(c) => Fill::Color(Color::rgba_f(
+                        c.r * instance.color.r,
+                        c.g * instance.color.g,
+                        c.b * instance.color.b,
+                        c.a * instance.color.a,
+                    )),
+                    fill => fill,
+                };
+                batch.push_with_z(fill, poly, z);
+            }
+        }
+        batch
+    }
+
     /// Scales the batch by some factor.
     pub fn scale(self, factor: f64) -> GeomBatch {
         self.scale_xy(factor, factor)
@@ -327,7 +362,7 @@ pub enum RewriteColor {
     /// Change every instance of the first color to the second
     Change(Color, Color),
     /// Change all colors to the specified value. For this to be interesting, the batch shouldn't
-    /// be a solid block of color. This does not modify Color::CLEAR.
+    /// be a solid block of color. This does not modify Color::CLEAR or `ICON_ACCENT`.
     ChangeAll(Color),
     /// Change the alpha value of all colors to this value.
     ChangeAlpha(f32),
@@ -353,7 +388,7 @@ impl RewriteColor {
                 }
             }
             RewriteColor::ChangeAll(to) => {
-                if c == Color::CLEAR {
+                if c == Color::CLEAR || c == ICON_ACCENT {
                     c
                 } else {
                     *to
@@ -0,0 +1,84 @@
+use geom::{Angle, Distance, Line, PolyLine, Polygon};
+
+use crate::{Color, GeomBatch};
+
+/// Stripes drawn on top of a fill color, so that categories can be told apart without relying on
+/// color alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    Solid,
+    Stripes,
+    BackStripes,
+    HorizontalStripes,
+    CrossHatch,
+}
+
+impl Pattern {
+    /// Cycles through every pattern, starting with `Solid`. Useful for assigning one to each
+    /// category of some data.
+    pub fn nth(idx: usize) -> Pattern {
+        let all = [
+            Pattern::Solid,
+            Pattern::Stripes,
+            Pattern::BackStripes,
+            Pattern::CrossHatch,
+            Pattern::HorizontalStripes,
+        ];
+        all[idx % all.len()]
+    }
+
+    fn angles(self) -> Vec<Angle> {
+        match self {
+            Pattern::Solid => Vec::new(),
+            Pattern::Stripes => vec![Angle::degrees(45.0)],
+            Pattern::BackStripes => vec![Angle::degrees(-45.0)],
+            Pattern::HorizontalStripes => vec![Angle::degrees(0.0)],
+            Pattern::CrossHatch => vec![Angle::degrees(45.0), Angle::degrees(-45.0)],
+        }
+    }
+}
+
+impl GeomBatch {
+    /// Fills a polygon with a color, then draws the pattern over it. `spacing` is the distance
+    /// between stripes, in the same units as the polygon.
+    pub fn push_pattern(&mut self, color: Color, polygon: Polygon, pattern: Pattern, spacing: f64) {
+        let stripes = pattern_stripes(&polygon, pattern, spacing);
+        self.push(color, polygon);
+        // Darken the color for the stripes, so they show up on both light and dark fills
+        let stripe_color = color.lerp(Color::BLACK, 0.5).alpha(color.a);
+        self.extend(stripe_color, stripes);
+    }
+}
+
+/// Returns thin polygons covering the parts of parallel lines inside the polygon. Holes are
+/// ignored.
+fn pattern_stripes(polygon: &Polygon, pattern: Pattern, spacing: f64) -> Vec<Polygon> {
+    let mut stripes = Vec::new();
+    let bounds = polygon.get_bounds();
+    let center = bounds.center();
+    // Long enough to cross the whole polygon at any angle
+    let radius = Distance::meters((bounds.width().powi(2) + bounds.height().powi(2)).sqrt());
+    let n = (radius.inner_meters() / spacing).ceil() as isize;
+    let thickness = Distance::meters(spacing / 3.0);
+    let ring = polygon.get_outer_ring();
+
+    for angle in pattern.angles() {
+        for i in -n..=n {
+            let middle = center.project_away(
+                Distance::meters(i as f64 * spacing),
+                angle.rotate_degs(90.0),
+            );
+            let pt1 = middle.project_away(radius, angle.opposite());
+            let pt2 = middle.project_away(radius, angle);
+            let mut hits = ring.all_intersections(&PolyLine::unchecked_new(vec![pt1, pt2]));
+            // Going along the line, every pair of crossings is a piece inside the polygon
+            hits.sort_by_key(|pt| pt1.dist_to(*pt));
+            for pair in hits.chunks_exact(2) {
+                if let Ok(line) = Line::new(pair[0], pair[1]) {
+                    stripes.push(line.make_polygons(thickness));
+                }
+            }
+        }
+    }
+    stripes
+}
@@ -105,6 +105,13 @@ impl UserInput {
         None
     }
 
+    pub fn get_pinch(&self) -> Option<f64> {
+        if let Event::Pinch(ratio) = self.event {
+            return Some(ratio);
+        }
+        None
+    }
+
     pub fn is_window_resized(&self) -> bool {
         matches!(self.event, Event::WindowResized(_))
     }
@@ -5,6 +5,7 @@
 //! TODO inline pictures of some of these
 //!
 //! * [`Autocomplete`] - select predefined value by combining text entry with menus
+//! * [`BoxPlot`] - compare distributions by their quartiles
 //! * [`Button`] - clickable buttons with keybindings and tooltips
 //! * [`Toggle`] - checkboxes, switches, and other toggles
 //! * [`CompareTimes`] - a scatter plot specialized for comparing times
@@ -12,12 +13,15 @@
 //! * [`DrawWithTooltips`] - draw static geometry, with mouse tooltips in certain regions
 //! * [`Dropdown`] - a button that expands into a menu
 //! * [`FanChart`] - visualize a range of values over time
+//! * [`HistogramPlot`] - count values in bins, optionally comparing a few distributions
 //! * [`Filler`] - just carve out space in the layout for something else
+//! * [`InteractivePlot`] - a line plot with hover readouts, zooming, panning, and toggleable series
 //! * [`JustDraw`] (argh private) - just draw text, `GeomBatch`es, SVGs
 //! * [`LinePlot`] - visualize 2 variables with a line plot
 //! * [`Menu`] - select something from a menu, with keybindings
 //! * [`PersistentSplit`] - a button with a dropdown to change its state
 //! * [`ScatterPlot`] - visualize 2 variables with a scatter plot
+//! * [`ScrollArea`] - scroll a tall column of widgets, optionally with a sticky header
 //! * [`Slider`] - horizontal and vertical sliders
 //! * [`Spinner`] - numeric input with up/down buttons
 //! * [`table::Table`] - rows and columns, supporting filtering and pagination
@@ -33,32 +37,37 @@ extern crate anyhow;
 extern crate log;
 
 pub use crate::app_state::{DrawBaselayer, SharedAppState, SimpleState, State, Transition};
-pub use crate::backend::Drawable;
+pub use crate::backend::{Drawable, InstancedDrawable};
 pub use crate::canvas::{Canvas, CanvasSettings, HorizontalAlignment, VerticalAlignment};
 pub use crate::color::{Color, Fill, LinearGradient, Texture};
-pub use crate::drawing::{GfxCtx, Prerender};
+pub use crate::drawing::{GfxCtx, Instance, PreparedInstances, Prerender};
 pub use crate::event::{hotkeys, lctrl, Event, Key, MultiKey};
 pub use crate::event_ctx::{EventCtx, UpdateType};
 pub use crate::geom::geom_batch_stack::{
     Alignment as StackAlignment, Axis as StackAxis, GeomBatchStack,
 };
+pub use crate::geom::pattern::Pattern;
 pub use crate::geom::{GeomBatch, RewriteColor};
 pub use crate::input::UserInput;
 pub use crate::runner::{run, Settings};
 pub use crate::screen_geom::{ScreenDims, ScreenPt, ScreenRectangle};
-pub use crate::style::{ButtonStyle, OutlineStyle, Style};
-pub use crate::text::{Font, Line, Text, TextExt, TextSpan};
-pub use crate::tools::warper::Warper;
+pub use crate::style::{ButtonStyle, OutlineStyle, Style, ICON_ACCENT};
+pub use crate::text::{Font, FontMetrics, Line, Text, TextExt, TextSpan};
+pub use crate::tools::warper::{Easing, Warper};
 pub use crate::tools::Cached;
 pub use crate::widgets::autocomplete::Autocomplete;
 pub(crate) use crate::widgets::button::Button;
 pub use crate::widgets::button::ButtonBuilder;
 pub use crate::widgets::compare_times::CompareTimes;
+pub use crate::widgets::distribution::{
+    Binning, BoxPlot, Distribution, HistogramOptions, HistogramPlot, PairedDisplay,
+};
 pub use crate::widgets::drag_drop::DragDrop;
 pub(crate) use crate::widgets::dropdown::Dropdown;
 pub use crate::widgets::fan_chart::FanChart;
 pub use crate::widgets::filler::Filler;
 pub use crate::widgets::image::{Image, ImageSource};
+pub use crate::widgets::interactive_plot::InteractivePlot;
 pub use crate::widgets::just_draw::DrawWithTooltips;
 pub(crate) use crate::widgets::just_draw::{DeferDraw, JustDraw};
 pub use crate::widgets::line_plot::LinePlot;
@@ -66,6 +75,7 @@ pub use crate::widgets::menu::Menu;
 pub use crate::widgets::persistent_split::PersistentSplit;
 pub use crate::widgets::plots::{PlotOptions, Series};
 pub use crate::widgets::scatter_plot::ScatterPlot;
+pub use crate::widgets::scroll_area::ScrollArea;
 pub use crate::widgets::slider::Slider;
 pub use crate::widgets::spinner::{RoundedF64, Spinner};
 pub use crate::widgets::stash::Stash;
@@ -92,6 +102,8 @@ mod color;
 mod drawing;
 mod event;
 mod event_ctx;
+#[cfg(feature = "gamepad")]
+mod gamepad;
 mod geom;
 mod input;
 pub mod mapspace;
@@ -101,6 +113,7 @@ mod style;
 mod svg;
 mod text;
 pub mod tools;
+mod touch;
 mod widgets;
 
 mod backend {
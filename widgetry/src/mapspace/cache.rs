@@ -0,0 +1,52 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// Keeps uploaded geometry around, keyed by a hash of whatever it was built from. Asking for
+/// something with the same key again returns the existing upload, without tessellating or uploading
+/// anything.
+///
+/// The key can't capture everything -- usually the map geometry itself isn't part of it. When that
+/// changes, call `mark_dirty`, and everything is discarded before the next lookup.
+pub struct DrawCache<T> {
+    entries: HashMap<u64, Rc<T>>,
+    dirty: bool,
+}
+
+impl<T> DrawCache<T> {
+    pub fn new() -> DrawCache<T> {
+        DrawCache {
+            entries: HashMap::new(),
+            dirty: false,
+        }
+    }
+
+    /// Hashes anything describing the contents into a key
+    pub fn key<K: Hash>(contents: &K) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns the cached value for `key`, or builds and remembers it.
+    pub fn get_or_build<F: FnOnce() -> T>(&mut self, key: u64, build: F) -> Rc<T> {
+        if self.dirty {
+            self.entries.clear();
+            self.dirty = false;
+        }
+        self.entries
+            .entry(key)
+            .or_insert_with(|| Rc::new(build()))
+            .clone()
+    }
+
+    /// Whatever the cached values were built from changed in a way the keys don't capture.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+}
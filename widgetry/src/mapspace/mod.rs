@@ -1,9 +1,11 @@
+mod cache;
 mod unzoomed;
 mod world;
 
 use geom::Polygon;
 
 use crate::{Drawable, EventCtx, Fill, GeomBatch, GfxCtx, RewriteColor};
+pub use cache::DrawCache;
 pub use unzoomed::{DrawCustomUnzoomedShapes, DrawUnzoomedShapes, PerZoom};
 pub use world::{DummyID, ObjectID, World, WorldOutcome};
 
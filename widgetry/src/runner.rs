@@ -10,13 +10,22 @@ use geom::Duration;
 
 use crate::app_state::App;
 use crate::assets::Assets;
-use crate::tools::screenshot::screenshot_everything;
+#[cfg(feature = "gamepad")]
+use crate::gamepad::GamepadInput;
+use crate::tools::screenshot::{screenshot_current, screenshot_everything};
+use crate::tools::toast::ToastStack;
+use crate::touch::TouchTracker;
 use crate::{
     Canvas, CanvasSettings, Event, EventCtx, GfxCtx, Prerender, SharedAppState, Style, Text,
     UpdateType, UserInput,
 };
 
 const UPDATE_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(1000 / 30);
+// While idle, how often to check for gamepad input, since it doesn't wake up the event loop
+#[cfg(feature = "gamepad")]
+const GAMEPAD_POLL_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(50);
+// In debug builds, how often to check if SVG files have changed
+const SVG_RELOAD_FREQUENCY: std::time::Duration = std::time::Duration::from_secs(1);
 // Manually enable and then check STDOUT
 const DEBUG_PERFORMANCE: bool = false;
 
@@ -110,6 +119,7 @@ impl<A: 'static + SharedAppState> State<A> {
                 style: &mut self.style,
                 updates_requested: vec![],
                 canvas_movement_called: false,
+                button_clicked: false,
 
                 focus_owned_by: self.focus_owned_by.take(),
                 // If the widget owning focus doesn't renew it, then it'll expire by the end of
@@ -372,6 +382,7 @@ pub fn run<
             style: &mut style,
             updates_requested: vec![],
             canvas_movement_called: false,
+            button_clicked: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
         };
@@ -390,6 +401,8 @@ pub fn run<
     let app = App {
         states,
         shared_app_state,
+        toasts: ToastStack::new(),
+        quit_requested: false,
     };
     timer.done();
 
@@ -406,27 +419,59 @@ pub fn run<
     let mut last_update = Instant::now();
     // The user will not manage to click immediately after the window opens, so this initial value is simpler than an `Option<Instant>`
     let mut previous_left_click_at = Instant::now();
+    let mut last_svg_check = Instant::now();
+    let mut touch_tracker = TouchTracker::new();
+    #[cfg(feature = "gamepad")]
+    let mut gamepad = GamepadInput::new();
+    // While a gamepad is panning or zooming, keep updating, even if nothing else asks for it
+    #[allow(unused_mut)]
+    let mut gamepad_moving = false;
     event_loop.run(move |event, _, control_flow| {
         if dump_raw_events {
             debug!("Event: {:?}", event);
         }
-        let ev = match event {
+        let events = match event {
             winit::event::Event::WindowEvent {
                 event: winit::event::WindowEvent::CloseRequested,
                 ..
             } => {
-                // ControlFlow::Exit cleanly shuts things down, meaning on larger maps, lots of
-                // GPU stuff is dropped. Better to just abort violently and let the OS clean
-                // up.
-                state.app.shared_app_state.before_quit(&state.canvas);
-                std::process::exit(0);
+                // Closing the window a second time while being asked just quits
+                if state.app.shared_app_state.unsaved_work().is_some()
+                    && !state.app.confirming_quit()
+                {
+                    state.app.quit_requested = true;
+                    vec![Event::NoOp]
+                } else {
+                    // ControlFlow::Exit cleanly shuts things down, meaning on larger maps, lots of
+                    // GPU stuff is dropped. Better to just abort violently and let the OS clean
+                    // up.
+                    state.app.shared_app_state.before_quit(&state.canvas);
+                    std::process::exit(0);
+                }
+            }
+            winit::event::Event::WindowEvent {
+                event: winit::event::WindowEvent::Touch(touch),
+                ..
+            } => touch_tracker.event(touch, prerender.get_scale_factor()),
+            // The web backend wakes up the event loop when fingers do something
+            #[cfg(feature = "wasm-backend")]
+            winit::event::Event::UserEvent(()) => {
+                let scale_factor = prerender.get_scale_factor();
+                let events: Vec<Event> = crate::backend_glow_wasm::take_touches()
+                    .into_iter()
+                    .flat_map(|touch| touch_tracker.event(touch, scale_factor))
+                    .collect();
+                if events.is_empty() {
+                    return;
+                }
+                events
             }
             winit::event::Event::WindowEvent { event, .. } => {
                 let scale_factor = prerender.get_scale_factor();
                 if let Some(ev) =
                     Event::from_winit_event(event, scale_factor, previous_left_click_at)
                 {
-                    ev
+                    vec![ev]
                 } else {
                     // Don't touch control_flow if we got an irrelevant event
                     return;
@@ -438,69 +483,124 @@ pub fn run<
                 return;
             }
             winit::event::Event::MainEventsCleared => {
+                // A finger held still doesn't wake up the event loop, but the deadline below does
+                let mut events = touch_tracker.poll(Instant::now());
+                #[cfg(feature = "gamepad")]
+                if let Some(ref mut gamepad) = gamepad {
+                    events.extend(gamepad.poll());
+                    state.canvas.gamepad_motion = gamepad.camera_motion();
+                    gamepad_moving = state.canvas.gamepad_motion != (0.0, 0.0, 0.0);
+                    if gamepad_moving && !running {
+                        last_update = Instant::now();
+                        running = true;
+                    }
+                    if !running {
+                        *control_flow = winit::event_loop::ControlFlow::WaitUntil(
+                            Instant::now() + GAMEPAD_POLL_FREQUENCY,
+                        );
+                    }
+                }
+
                 // We might've switched to InputOnly after the WaitUntil was requested.
                 if running {
-                    Event::Update(Duration::realtime_elapsed(last_update))
-                } else {
+                    events.push(Event::Update(Duration::realtime_elapsed(last_update)));
+                }
+                if events.is_empty() {
                     return;
                 }
+                events
             }
             _ => {
                 return;
             }
         };
 
-        // We want a max of UPDATE_FREQUENCY between updates, so measure the update time before
-        // doing the work (which takes time).
-        match ev {
-            Event::Update(_) => {
-                last_update = Instant::now();
-                *control_flow =
-                    winit::event_loop::ControlFlow::WaitUntil(Instant::now() + UPDATE_FREQUENCY);
+        // A touch gesture might turn into a few events
+        for ev in events {
+            // We want a max of UPDATE_FREQUENCY between updates, so measure the update time before
+            // doing the work (which takes time).
+            match ev {
+                Event::Update(_) => {
+                    last_update = Instant::now();
+                    *control_flow = winit::event_loop::ControlFlow::WaitUntil(
+                        Instant::now() + UPDATE_FREQUENCY,
+                    );
+                }
+                Event::LeftMouseButtonUp {
+                    is_double_click: false,
+                } => {
+                    previous_left_click_at = Instant::now();
+                }
+                _ => {}
             }
-            Event::LeftMouseButtonUp {
-                is_double_click: false,
-            } => {
-                previous_left_click_at = Instant::now();
+
+            // Let people edit icons while the app is running. Anything built after this uses the
+            // new version.
+            if cfg!(debug_assertions) && last_svg_check.elapsed() >= SVG_RELOAD_FREQUENCY {
+                last_svg_check = Instant::now();
+                for filename in prerender.assets.reload_changed_svgs() {
+                    info!("Reloaded {}", filename);
+                }
             }
-            _ => {}
-        }
 
-        let (mut updates, input_used) = state.event(ev, &prerender);
+            let (mut updates, input_used) = state.event(ev, &prerender);
 
-        if input_used {
-            prerender.request_redraw();
-        }
+            if input_used {
+                prerender.request_redraw();
+            }
 
-        if updates.is_empty() {
-            updates.push(UpdateType::InputOnly);
-        }
-        for update in updates {
-            match update {
-                UpdateType::InputOnly => {
-                    running = false;
-                    *control_flow = winit::event_loop::ControlFlow::Wait;
-                }
-                UpdateType::Game => {
-                    // If we just unpaused, then don't act as if lots of time has passed.
-                    if !running {
-                        last_update = Instant::now();
-                        *control_flow = winit::event_loop::ControlFlow::WaitUntil(
-                            Instant::now() + UPDATE_FREQUENCY,
-                        );
+            if updates.is_empty() {
+                updates.push(UpdateType::InputOnly);
+            }
+            for update in updates {
+                match update {
+                    UpdateType::InputOnly => {
+                        if !gamepad_moving {
+                            running = false;
+                            *control_flow = winit::event_loop::ControlFlow::Wait;
+                        }
                     }
-
-                    running = true;
-                }
-                UpdateType::Pan => {}
-                UpdateType::ScreenCaptureEverything { dir, zoom, dims } => {
-                    if let Err(err) =
-                        screenshot_everything(&mut state, &dir, &prerender, zoom, dims)
-                    {
-                        error!("Couldn't screenshot everything: {}", err);
+                    UpdateType::Game => {
+                        // If we just unpaused, then don't act as if lots of time has passed.
+                        if !running {
+                            last_update = Instant::now();
+                            *control_flow = winit::event_loop::ControlFlow::WaitUntil(
+                                Instant::now() + UPDATE_FREQUENCY,
+                            );
+                        }
+
+                        running = true;
+                    }
+                    UpdateType::Pan => {}
+                    UpdateType::ScreenCaptureEverything { dir, zoom, dims } => {
+                        if let Err(err) =
+                            screenshot_everything(&mut state, &dir, &prerender, zoom, dims)
+                        {
+                            error!("Couldn't screenshot everything: {}", err);
+                        }
+                    }
+                    UpdateType::ScreenCaptureCurrentShot { path } => {
+                        if let Err(err) = screenshot_current(&mut state, &path, &prerender) {
+                            error!("Couldn't screenshot {}: {}", path, err);
+                        }
                     }
                 }
             }
         }
+
+        // Wake up to notice a long-press
+        if let Some(deadline) = touch_tracker.long_press_deadline() {
+            if !running {
+                *control_flow = winit::event_loop::ControlFlow::WaitUntil(deadline);
+            }
+        }
+
+        // Gamepads don't wake up the event loop, so keep polling them. This is sooner than any
+        // long-press deadline, and touches are polled then too.
+        #[cfg(feature = "gamepad")]
+        if !running && gamepad.is_some() {
+            *control_flow =
+                winit::event_loop::ControlFlow::WaitUntil(Instant::now() + GAMEPAD_POLL_FREQUENCY);
+        }
     });
 }
@@ -72,6 +72,18 @@ impl ScreenRectangle {
         pt.x >= self.x1 && pt.x <= self.x2 && pt.y >= self.y1 && pt.y <= self.y2
     }
 
+    /// The overlap of two rectangles. If they don't overlap, the result has no area.
+    pub fn intersection(&self, other: &ScreenRectangle) -> ScreenRectangle {
+        let x1 = self.x1.max(other.x1);
+        let y1 = self.y1.max(other.y1);
+        ScreenRectangle {
+            x1,
+            y1,
+            x2: self.x2.min(other.x2).max(x1),
+            y2: self.y2.min(other.y2).max(y1),
+        }
+    }
+
     pub fn pt_to_percent(&self, pt: ScreenPt) -> Option<(f64, f64)> {
         if self.contains(pt) {
             Some((
@@ -9,6 +9,8 @@ pub struct Style {
     pub field_bg: Color,
     pub dropdown_border: Color,
     pub icon_fg: Color,
+    /// Replaces `ICON_ACCENT` in icons
+    pub icon_accent: Color,
     pub primary_fg: Color,
     pub text_primary_color: Color,
     pub text_secondary_color: Color,
@@ -31,6 +33,10 @@ pub struct Style {
 
 pub type OutlineStyle = (f64, Color);
 
+/// Icons are recolored to `Style::icon_fg`, except for parts drawn in this color (#FF00FF), which
+/// become `Style::icon_accent`. That way one SVG file can match every theme.
+pub const ICON_ACCENT: Color = Color::rgb_f(1.0, 0.0, 1.0);
+
 pub static DEFAULT_OUTLINE_THICKNESS: f64 = 2.0;
 
 // This is #EE702E, called "ab_orange_1" in Figma
@@ -162,6 +168,7 @@ impl Style {
             section_outline: (2.0, Color::WHITE.shade(0.1)),
             loading_tips: Text::new(),
             icon_fg: hex("#4C4C4C"),
+            icon_accent: AB_ORANGE_1,
             primary_fg: AB_ORANGE_1,
             text_primary_color: hex("#4C4C4C"),
             text_secondary_color: hex("#4C4C4C").tint(0.2),
@@ -200,6 +207,7 @@ impl Style {
             section_outline: (DEFAULT_OUTLINE_THICKNESS, navy.shade(0.2)),
             loading_tips: Text::new(),
             icon_fg: Color::WHITE,
+            icon_accent: AB_ORANGE_1,
             primary_fg: AB_ORANGE_1,
             text_primary_color: Color::WHITE,
             text_secondary_color: Color::WHITE.shade(0.2),
@@ -21,6 +21,7 @@ pub fn load_svg(prerender: &Prerender, filename: &str) -> (GeomBatch, Bounds) {
     }
 
     let bytes = (prerender.assets.read_svg)(filename);
+    prerender.assets.watch_svg(filename);
     load_svg_from_bytes_uncached(&bytes)
         .map(|(batch, bounds)| {
             prerender.assets.cache_svg(cache_key, batch.clone(), bounds);
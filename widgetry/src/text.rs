@@ -13,8 +13,45 @@ use crate::{
 pub const DEFAULT_FONT: Font = Font::OverpassRegular;
 pub const DEFAULT_FONT_SIZE: usize = 21;
 
+/// Lines are at least this much taller than the glyphs, even if the font itself asks for less
+/// space between lines
 pub const SCALE_LINE_HEIGHT: f64 = 1.2;
 
+/// Vertical metrics of a font at some size, in pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FontMetrics {
+    /// How far the tallest glyphs reach above the baseline
+    pub ascent: f64,
+    /// How far the lowest glyphs reach below the baseline. Positive.
+    pub descent: f64,
+    /// Extra space the font asks for between lines
+    pub line_gap: f64,
+}
+
+impl FontMetrics {
+    /// The height of one line. Any space beyond the glyphs themselves is split evenly above and
+    /// below, like CSS does.
+    pub fn line_height(&self) -> f64 {
+        let glyphs = self.ascent + self.descent;
+        (glyphs + self.line_gap).max(SCALE_LINE_HEIGHT * glyphs)
+    }
+
+    /// From the top of a line, how far down the baseline is
+    pub fn baseline(&self) -> f64 {
+        let glyphs = self.ascent + self.descent;
+        (self.line_height() - glyphs) / 2.0 + self.ascent
+    }
+
+    /// Combines the metrics of all fonts on one line
+    fn max(self, other: FontMetrics) -> FontMetrics {
+        FontMetrics {
+            ascent: self.ascent.max(other.ascent),
+            descent: self.descent.max(other.descent),
+            line_gap: self.line_gap.max(other.line_gap),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Font {
     BungeeInlineRegular,
@@ -336,11 +373,15 @@ impl Text {
         // https://www.oreilly.com/library/view/svg-text-layout/9781491933817/ch04.html
         for (line_color, line) in self.lines {
             // In case size changes mid-line, take the max of every span.
-            // (f64 isn't Ord, so no max(), so do this manually.)
-            let mut line_height = 0.0_f64;
+            let mut metrics = FontMetrics {
+                ascent: 0.0,
+                descent: 0.0,
+                line_gap: 0.0,
+            };
             for span in &line {
-                line_height = line_height.max(assets.line_height(span.font, span.size));
+                metrics = metrics.max(assets.font_metrics(span.font, span.size));
             }
+            let line_height = metrics.line_height();
 
             let line_batch = render_line(line, tolerance, assets);
             let line_dims = if line_batch.is_empty() {
@@ -358,11 +399,10 @@ impl Text {
                 );
             }
 
-            y += line_dims.height;
+            // The SVG puts the baseline at 0
+            master_batch.append(line_batch.translate(0.0, y + metrics.baseline()));
 
-            // Add all of the padding at the bottom of the line.
-            let offset = line_height / SCALE_LINE_HEIGHT * 0.2;
-            master_batch.append(line_batch.translate(0.0, y - offset));
+            y += line_dims.height;
 
             max_width = max_width.max(line_dims.width);
         }
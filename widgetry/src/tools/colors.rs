@@ -1,6 +1,6 @@
 use geom::{Circle, Distance, Line, Polygon, Pt2D, Tessellation};
 
-use crate::{Color, EventCtx, Fill, GeomBatch, Line, LinearGradient, Text, Widget};
+use crate::{Color, EventCtx, Fill, GeomBatch, Line, LinearGradient, Pattern, Text, Widget};
 
 pub struct ColorLegend {}
 
@@ -18,6 +18,21 @@ impl ColorLegend {
         ])
     }
 
+    /// Like `row`, but shows a square filled with a pattern, matching `GeomBatch::push_pattern`.
+    pub fn row_with_pattern(
+        ctx: &EventCtx,
+        color: Color,
+        pattern: Pattern,
+        label: impl AsRef<str>,
+    ) -> Widget {
+        let mut batch = GeomBatch::new();
+        batch.push_pattern(color, Polygon::rectangle(30.0, 30.0), pattern, 8.0);
+        Widget::row(vec![
+            batch.into_widget(ctx).centered_vert(),
+            Text::from(label).wrap_to_pct(ctx, 35).into_widget(ctx),
+        ])
+    }
+
     pub fn gradient_with_width<I: Into<String>>(
         ctx: &mut EventCtx,
         scale: &ColorScale,
@@ -164,6 +179,16 @@ impl DivergingScale {
             labels,
         )
     }
+
+    /// Like `make_legend`, but labels the low, middle, and high values of the range.
+    pub fn make_legend_with_values(
+        self,
+        ctx: &mut EventCtx,
+        fmt: impl Fn(f64) -> String,
+    ) -> Widget {
+        let labels = vec![fmt(self.min), fmt(self.avg), fmt(self.max)];
+        self.make_legend(ctx, labels)
+    }
 }
 
 pub struct ColorScale(pub Vec<Color>);
@@ -174,6 +199,22 @@ impl ColorScale {
         self.0[low].lerp(self.0[low + 1], pct)
     }
 
+    /// Makes a legend for this scale covering values from `min` to `max`, labelled at every color
+    /// stop (or at least the ends and middle). `fmt` should express each value with its units.
+    pub fn make_legend(
+        &self,
+        ctx: &mut EventCtx,
+        min: f64,
+        max: f64,
+        fmt: impl Fn(f64) -> String,
+    ) -> Widget {
+        let n = self.0.len().max(3);
+        let labels = (0..n)
+            .map(|i| fmt(min + (max - min) * (i as f64) / ((n - 1) as f64)))
+            .collect();
+        ColorLegend::gradient(ctx, self, labels)
+    }
+
     #[allow(unused)]
     pub fn from_colorous(gradient: colorous::Gradient) -> ColorScale {
         let n = 7;
@@ -0,0 +1,80 @@
+use crate::{
+    DrawBaselayer, EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, Text, Transition, Widget,
+};
+
+/// Ask the user to confirm something destructive before doing it.
+pub struct Confirm<A> {
+    panel: Panel,
+    cb: Option<Box<dyn FnOnce(&mut EventCtx, &mut A) -> Transition<A>>>,
+}
+
+impl<A: 'static> Confirm<A> {
+    /// If the user confirms, this dialog is closed and the callback runs. The transition it returns
+    /// applies to the state underneath the dialog.
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        title: &str,
+        lines: Vec<impl AsRef<str>>,
+        confirm_label: &str,
+        cb: Box<dyn FnOnce(&mut EventCtx, &mut A) -> Transition<A>>,
+    ) -> Box<dyn State<A>> {
+        let mut txt = Text::new();
+        for l in lines {
+            txt.add_line(l);
+        }
+        Box::new(Confirm {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line(title).small_heading().into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                txt.into_widget(ctx),
+                Widget::row(vec![
+                    ctx.style()
+                        .btn_outline
+                        .text("Cancel")
+                        .hotkey(Key::Escape)
+                        .build_def(ctx),
+                    ctx.style()
+                        .btn_solid_destructive
+                        .text(confirm_label)
+                        .hotkey(Key::Enter)
+                        .build_widget(ctx, "confirm"),
+                ])
+                .align_right(),
+            ]))
+            .build(ctx),
+            cb: Some(cb),
+        })
+    }
+}
+
+impl<A: 'static> State<A> for Confirm<A> {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut A) -> Transition<A> {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" | "Cancel" => Transition::Pop,
+                "confirm" => {
+                    let transition = (self.cb.take().unwrap())(ctx, app);
+                    Transition::Multi(vec![Transition::Pop, transition])
+                }
+                _ => unreachable!(),
+            },
+            _ => {
+                if ctx.normal_left_click() && ctx.canvas.get_cursor_in_screen_space().is_none() {
+                    return Transition::Pop;
+                }
+                Transition::Keep
+            }
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &A) {
+        super::grey_out_map(g);
+        self.panel.draw(g);
+    }
+}
@@ -457,3 +457,52 @@ where
         self.panel.draw(g);
     }
 }
+
+/// Runs futures without blocking the UI, unlike `FutureLoader`. The caller polls the receiver
+/// from its own `event` and should keep calling `ctx.request_update` until the result arrives.
+pub struct Background {
+    // Dropping the Runtime cancels any tasks, so it has to live as long as the caller does.
+    #[cfg(not(target_arch = "wasm32"))]
+    runtime: Runtime,
+}
+
+impl Background {
+    pub fn new() -> Background {
+        Background {
+            #[cfg(not(target_arch = "wasm32"))]
+            runtime: Runtime::new().unwrap(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn spawn<T: 'static>(
+        &self,
+        future: Pin<Box<dyn Future<Output = T>>>,
+    ) -> oneshot::Receiver<T> {
+        let (tx, rx) = oneshot::channel();
+        wasm_bindgen_futures::spawn_local(async move {
+            // The caller may have stopped listening
+            tx.send(future.await).ok();
+        });
+        rx
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn<T: 'static + Send>(
+        &self,
+        future: Pin<Box<dyn Send + Future<Output = T>>>,
+    ) -> oneshot::Receiver<T> {
+        let (tx, rx) = oneshot::channel();
+        self.runtime.spawn(async move {
+            // The caller may have stopped listening
+            tx.send(future.await).ok();
+        });
+        rx
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::new()
+    }
+}
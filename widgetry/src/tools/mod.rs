@@ -1,10 +1,13 @@
 mod choose_something;
 mod colors;
+mod confirm;
 mod lasso;
 mod load;
 mod popup;
 mod prompt_input;
 pub(crate) mod screenshot;
+pub(crate) mod toast;
+mod tour;
 mod url;
 pub(crate) mod warper;
 
@@ -12,10 +15,13 @@ use anyhow::Result;
 
 pub use choose_something::ChooseSomething;
 pub use colors::{ColorLegend, ColorScale, DivergingScale};
+pub use confirm::Confirm;
 pub use lasso::{Lasso, PolyLineLasso};
-pub use load::{FileLoader, FutureLoader, RawBytes};
+pub use load::{Background, FileLoader, FutureLoader, RawBytes};
 pub use popup::PopupMsg;
 pub use prompt_input::PromptInput;
+pub use toast::{Severity, Toast, ToastHistory};
+pub use tour::{Tour, TourStop};
 pub use url::URLManager;
 
 use crate::{Color, GfxCtx};
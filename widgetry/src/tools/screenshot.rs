@@ -71,3 +71,18 @@ pub(crate) fn screenshot_everything<A: 'static + SharedAppState>(
     state.canvas.cam_y = orig_y;
     Ok(())
 }
+
+/// Take a screenshot of the whole window, without moving the camera.
+pub(crate) fn screenshot_current<A: 'static + SharedAppState>(
+    state: &mut State<A>,
+    path: &str,
+    prerender: &Prerender,
+) -> anyhow::Result<()> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        fs_err::create_dir_all(parent)?;
+    }
+    state.draw(prerender, true);
+    prerender
+        .inner
+        .screencap(state.canvas.get_window_dims(), path.to_string())
+}
@@ -0,0 +1,332 @@
+use std::collections::VecDeque;
+
+use instant::Instant;
+
+use abstutil::elapsed_seconds;
+use geom::Duration;
+
+use crate::{
+    Color, DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text,
+    TextExt, Transition, UpdateType, VerticalAlignment, Widget,
+};
+
+/// At most this many notifications are on screen at once. Older ones are still in the history.
+const MAX_VISIBLE: usize = 3;
+/// Only remember this many notifications. Something like an alert firing all day shouldn't grow
+/// forever.
+const MAX_HISTORY: usize = 100;
+
+/// How important a notification is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn color(self) -> Color {
+        match self {
+            Severity::Info => Color::hex("#5D9BFF"),
+            Severity::Success => Color::hex("#7FBA49"),
+            Severity::Warning => Color::hex("#F2C037"),
+            Severity::Error => Color::hex("#EB3223"),
+        }
+    }
+
+    fn default_timeout(self) -> Option<Duration> {
+        match self {
+            Severity::Info | Severity::Success => Some(Duration::seconds(5.0)),
+            Severity::Warning => Some(Duration::seconds(10.0)),
+            // Errors stay until they're dismissed
+            Severity::Error => None,
+        }
+    }
+}
+
+/// A notification that doesn't interrupt what the user is doing, unlike `PopupMsg`. It's shown at
+/// the top of the screen for a while, then remains in a history that `ToastHistory` displays.
+///
+/// Show one with `EventCtx::show_toast`.
+#[derive(Clone)]
+pub struct Toast {
+    pub severity: Severity,
+    pub title: String,
+    pub lines: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+impl Toast {
+    pub fn new(severity: Severity, title: &str, lines: Vec<impl AsRef<str>>) -> Toast {
+        Toast {
+            severity,
+            title: title.to_string(),
+            lines: lines.into_iter().map(|l| l.as_ref().to_string()).collect(),
+            timeout: severity.default_timeout(),
+        }
+    }
+
+    /// Override how long the notification is shown. `None` means it stays until the user dismisses
+    /// it.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Toast {
+        self.timeout = timeout;
+        self
+    }
+
+    fn to_widget(&self, ctx: &EventCtx, dismiss_action: Option<String>) -> Widget {
+        let mut txt = Text::from(Line(&self.title).small_heading());
+        for l in &self.lines {
+            txt.add_line(Line(l));
+        }
+        let mut row = vec![txt.wrap_to_pct(ctx, 30).into_widget(ctx)];
+        if let Some(action) = dismiss_action {
+            row.push(
+                ctx.style()
+                    .btn_close()
+                    .build_widget(ctx, action)
+                    .align_right(),
+            );
+        }
+        Widget::row(row)
+            .padding(8)
+            .outline((2.0, self.severity.color()))
+    }
+}
+
+/// The most recent notifications, oldest first. Each one keeps the same index even after older
+/// ones are forgotten.
+pub(crate) struct ToastLog {
+    toasts: VecDeque<Toast>,
+    /// How many of the oldest toasts have been forgotten
+    num_forgotten: usize,
+}
+
+impl ToastLog {
+    pub fn new() -> ToastLog {
+        ToastLog {
+            toasts: VecDeque::new(),
+            num_forgotten: 0,
+        }
+    }
+
+    pub fn push(&mut self, toast: Toast) {
+        self.toasts.push_back(toast);
+        if self.toasts.len() > MAX_HISTORY {
+            self.toasts.pop_front();
+            self.num_forgotten += 1;
+        }
+    }
+
+    /// `None` if the toast has been forgotten
+    pub fn get(&self, idx: usize) -> Option<&Toast> {
+        self.toasts.get(idx.checked_sub(self.num_forgotten)?)
+    }
+
+    /// How many toasts have ever been shown, including forgotten ones
+    pub fn total(&self) -> usize {
+        self.num_forgotten + self.toasts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Toast> {
+        self.toasts.iter()
+    }
+}
+
+/// Manages the notifications currently on screen. Recent toasts live in the `Canvas`; this just
+/// tracks which ones are visible.
+pub(crate) struct ToastStack {
+    /// How many toasts from the history have been seen
+    num_seen: usize,
+    /// Indices into the history and when each was first shown
+    visible: Vec<(usize, Instant)>,
+    panel: Option<Panel>,
+}
+
+impl ToastStack {
+    pub fn new() -> ToastStack {
+        ToastStack {
+            num_seen: 0,
+            visible: Vec::new(),
+            panel: None,
+        }
+    }
+
+    /// Show new toasts and expire old ones.
+    pub fn update(&mut self, ctx: &mut EventCtx) {
+        let mut changed = false;
+
+        let num_toasts = ctx.canvas.toasts.total();
+        for idx in self.num_seen..num_toasts {
+            self.visible.push((idx, Instant::now()));
+            changed = true;
+        }
+        self.num_seen = num_toasts;
+        if self.visible.len() > MAX_VISIBLE {
+            self.visible.drain(0..self.visible.len() - MAX_VISIBLE);
+        }
+
+        let toasts = &ctx.canvas.toasts;
+        let before = self.visible.len();
+        self.visible.retain(
+            |(idx, shown_at)| match toasts.get(*idx).map(|t| t.timeout) {
+                Some(Some(timeout)) => elapsed_seconds(*shown_at) < timeout.inner_seconds(),
+                Some(None) => true,
+                None => false,
+            },
+        );
+        changed |= self.visible.len() != before;
+
+        if changed {
+            self.recreate_panel(ctx);
+        }
+        if self
+            .visible
+            .iter()
+            .any(|(idx, _)| ctx.canvas.toasts.get(*idx).unwrap().timeout.is_some())
+        {
+            // Keep waking up to expire them
+            ctx.request_update(UpdateType::Game);
+        }
+    }
+
+    /// Returns true if the user asked to see the full history.
+    pub fn event(&mut self, ctx: &mut EventCtx) -> bool {
+        let panel = match self.panel {
+            Some(ref mut panel) => panel,
+            None => {
+                return false;
+            }
+        };
+        if let Outcome::Clicked(x) = panel.event(ctx) {
+            if x == "notification history" {
+                return true;
+            }
+            let dismiss = x
+                .strip_prefix("dismiss notification ")
+                .unwrap()
+                .parse::<usize>()
+                .unwrap();
+            self.visible.retain(|(idx, _)| *idx != dismiss);
+            self.recreate_panel(ctx);
+        }
+        false
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        if let Some(ref panel) = self.panel {
+            panel.draw(g);
+        }
+    }
+
+    fn recreate_panel(&mut self, ctx: &mut EventCtx) {
+        if self.visible.is_empty() {
+            self.panel = None;
+            return;
+        }
+
+        let mut col = Vec::new();
+        for (idx, _) in &self.visible {
+            col.push(
+                ctx.canvas
+                    .toasts
+                    .get(*idx)
+                    .unwrap()
+                    .to_widget(ctx, Some(format!("dismiss notification {}", idx))),
+            );
+        }
+        col.push(
+            ctx.style()
+                .btn_plain
+                .text("notification history")
+                .build_def(ctx)
+                .align_right(),
+        );
+        self.panel = Some(
+            Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+                .ignore_initial_events()
+                .build(ctx),
+        );
+    }
+}
+
+/// Lists the recent notifications, newest first.
+pub struct ToastHistory {
+    panel: Panel,
+}
+
+impl ToastHistory {
+    pub fn new_state<A>(ctx: &mut EventCtx) -> Box<dyn State<A>> {
+        let mut col = vec![Widget::row(vec![
+            Line("Notifications").small_heading().into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        if ctx.canvas.toasts.is_empty() {
+            col.push("Nothing yet".text_widget(ctx));
+        }
+        for toast in ctx.canvas.toasts.iter().rev() {
+            col.push(toast.to_widget(ctx, None));
+        }
+        Box::new(ToastHistory {
+            panel: Panel::new_builder(Widget::col(col)).build(ctx),
+        })
+    }
+}
+
+impl<A> State<A> for ToastHistory {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut A) -> Transition<A> {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                _ => unreachable!(),
+            },
+            _ => {
+                if ctx.normal_left_click() && ctx.canvas.get_cursor_in_screen_space().is_none() {
+                    return Transition::Pop;
+                }
+                Transition::Keep
+            }
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &A) {
+        super::grey_out_map(g);
+        self.panel.draw(g);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut log = ToastLog::new();
+        for i in 0..MAX_HISTORY + 5 {
+            log.push(Toast::new(
+                Severity::Info,
+                &i.to_string(),
+                Vec::<String>::new(),
+            ));
+        }
+        assert_eq!(log.total(), MAX_HISTORY + 5);
+        assert_eq!(log.iter().count(), MAX_HISTORY);
+        // The oldest are forgotten, but the rest keep their index
+        assert!(log.get(4).is_none());
+        assert_eq!(log.get(5).unwrap().title, "5");
+        assert_eq!(
+            log.get(MAX_HISTORY + 4).unwrap().title,
+            (MAX_HISTORY + 4).to_string()
+        );
+        assert!(log.get(MAX_HISTORY + 5).is_none());
+    }
+}
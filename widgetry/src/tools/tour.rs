@@ -0,0 +1,141 @@
+use geom::{Duration, Pt2D};
+
+use crate::tools::warper::{Easing, Warper};
+use crate::{
+    DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text,
+    Transition, VerticalAlignment, Widget,
+};
+
+/// One place to visit on a `Tour`.
+pub struct TourStop {
+    pub pt: Pt2D,
+    /// If specified, zoom to this level. Otherwise keep the current zoom.
+    pub cam_zoom: Option<f64>,
+    pub caption: Text,
+}
+
+impl TourStop {
+    pub fn new(pt: Pt2D, cam_zoom: Option<f64>, caption: impl Into<Text>) -> TourStop {
+        TourStop {
+            pt,
+            cam_zoom,
+            caption: caption.into(),
+        }
+    }
+}
+
+/// Steps through a scripted list of places, smoothly moving the camera to each one and showing a
+/// caption. The user can go back and forth, and pan around in between.
+pub struct Tour {
+    title: String,
+    stops: Vec<TourStop>,
+    current: usize,
+    warper: Option<Warper>,
+    panel: Panel,
+}
+
+impl Tour {
+    pub fn new_state<A>(
+        ctx: &mut EventCtx,
+        title: &str,
+        stops: Vec<TourStop>,
+    ) -> Box<dyn State<A>> {
+        assert!(!stops.is_empty());
+        let mut tour = Tour {
+            title: title.to_string(),
+            stops,
+            current: 0,
+            warper: None,
+            panel: Panel::empty(ctx),
+        };
+        tour.go_to(ctx, 0);
+        Box::new(tour)
+    }
+
+    fn go_to(&mut self, ctx: &mut EventCtx, idx: usize) {
+        self.current = idx;
+        let stop = &self.stops[idx];
+        self.warper = Some(
+            Warper::new(ctx, stop.pt, stop.cam_zoom)
+                .easing(Easing::EaseInOut)
+                .duration(Duration::seconds(1.5)),
+        );
+        self.panel = self.make_panel(ctx);
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx) -> Panel {
+        let last = self.stops.len() - 1;
+        Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line(&self.title).small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            self.stops[self.current]
+                .caption
+                .clone()
+                .wrap_to_pct(ctx, 40)
+                .into_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_prev()
+                    .disabled(self.current == 0)
+                    .hotkey(Key::LeftArrow)
+                    .build_widget(ctx, "previous"),
+                Line(format!("{} / {}", self.current + 1, self.stops.len()))
+                    .into_widget(ctx)
+                    .centered_vert(),
+                if self.current == last {
+                    ctx.style()
+                        .btn_solid_primary
+                        .text("Finish")
+                        .hotkey(Key::Enter)
+                        .build_widget(ctx, "finish")
+                } else {
+                    ctx.style()
+                        .btn_next()
+                        .hotkey(Key::RightArrow)
+                        .build_widget(ctx, "next")
+                },
+            ])
+            .centered_horiz(),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Bottom)
+        .build(ctx)
+    }
+}
+
+impl<A> State<A> for Tour {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut A) -> Transition<A> {
+        if let Some(ref warper) = self.warper {
+            if !warper.event(ctx) {
+                self.warper = None;
+            }
+        } else {
+            ctx.canvas_movement();
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" | "finish" => {
+                    return Transition::Pop;
+                }
+                "previous" => {
+                    self.go_to(ctx, self.current - 1);
+                }
+                "next" => {
+                    self.go_to(ctx, self.current + 1);
+                }
+                _ => unreachable!(),
+            }
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &A) {
+        self.panel.draw(g);
+    }
+}
@@ -1,13 +1,39 @@
 use instant::Instant;
 
-use geom::{Line, Pt2D};
+use geom::{Duration, Line, Pt2D};
 
 use crate::{EventCtx, UpdateType};
 
+/// How a camera animation speeds up and slows down. Maps the fraction of time elapsed to the
+/// fraction of the distance covered, both in [0, 1].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    /// Start slowly, speed up, then slow down at the end
+    EaseInOut,
+    /// Start quickly, then slow down at the end
+    EaseOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+        }
+    }
+}
+
+/// Smoothly moves the camera to a point, optionally changing the zoom too. Call `event` every
+/// event until it returns false.
 pub struct Warper {
     started: Instant,
     line: Option<Line>,
     cam_zoom: (f64, f64),
+    easing: Easing,
+    duration: Option<Duration>,
 }
 
 impl Warper {
@@ -17,9 +43,23 @@ impl Warper {
             started: Instant::now(),
             line: Line::new(ctx.canvas.center_to_map_pt(), pt).ok(),
             cam_zoom: (z, target_cam_zoom.unwrap_or(z)),
+            easing: Easing::EaseInOut,
+            duration: None,
         }
     }
 
+    pub fn easing(mut self, easing: Easing) -> Warper {
+        self.easing = easing;
+        self
+    }
+
+    /// By default, the animation time depends on the distance, up to half a second. Slower
+    /// animations are easier to follow, like during a presentation.
+    pub fn duration(mut self, duration: Duration) -> Warper {
+        self.duration = Some(duration);
+        self
+    }
+
     // false means done
     pub fn event(&self, ctx: &mut EventCtx) -> bool {
         // Actually nothing for us to do
@@ -37,15 +77,17 @@ impl Warper {
 
         const MAX_ANIMATION_TIME_S: f64 = 0.5;
         const ANIMATION_SPEED: f64 = 200.0;
-        let total_time = if let Some(ref line) = self.line {
+        let total_time = if let Some(duration) = self.duration {
+            duration.inner_seconds()
+        } else if let Some(ref line) = self.line {
             (line.length().inner_meters() / ANIMATION_SPEED).min(MAX_ANIMATION_TIME_S)
         } else {
             MAX_ANIMATION_TIME_S
         };
-        let percent = abstutil::elapsed_seconds(self.started) / total_time;
+        let time_pct = abstutil::elapsed_seconds(self.started) / total_time;
 
         let orig_center = ctx.canvas.center_to_map_pt();
-        if percent >= 1.0 || ctx.input.any_pressed().is_some() {
+        if time_pct >= 1.0 || ctx.input.any_pressed().is_some() {
             ctx.canvas.cam_zoom = self.cam_zoom.1;
             if let Some(ref line) = self.line {
                 ctx.canvas.center_on_map_pt(line.pt2());
@@ -54,7 +96,10 @@ impl Warper {
             }
             false
         } else {
-            ctx.canvas.cam_zoom = self.cam_zoom.0 + percent * (self.cam_zoom.1 - self.cam_zoom.0);
+            let percent = self.easing.apply(time_pct);
+            // Zooming feels uniform when the scale changes by the same ratio over time
+            let (z1, z2) = self.cam_zoom;
+            ctx.canvas.cam_zoom = z1 * (z2 / z1).powf(percent);
             if let Some(ref line) = self.line {
                 ctx.canvas
                     .center_on_map_pt(line.percent_along(percent).unwrap());
@@ -0,0 +1,368 @@
+//! Touchscreens don't have a mouse, so translate gestures into the mouse events that the rest of
+//! widgetry already understands:
+//!
+//! - a tap is a left click, and two quick taps are a double click
+//! - dragging one finger holds down the left button, which pans the canvas
+//! - pinching two fingers zooms around the point between them
+//! - holding one finger still shows whatever's hovered, and after a moment, right clicks
+
+use std::collections::BTreeMap;
+
+use instant::Instant;
+use winit::event::{Touch, TouchPhase};
+
+use crate::event::MAX_DOUBLE_CLICK_DURATION;
+use crate::{Event, ScreenPt};
+
+/// A finger moving less than this many pixels is still a tap
+const TAP_SLOP: f64 = 10.0;
+/// Holding a finger still at least this long is a long-press
+const LONG_PRESS: instant::Duration = instant::Duration::from_millis(500);
+
+pub(crate) struct TouchTracker {
+    /// Every finger currently down, keyed by winit's ID
+    fingers: BTreeMap<u64, ScreenPt>,
+    gesture: Gesture,
+    last_tap: Instant,
+}
+
+enum Gesture {
+    Idle,
+    /// One finger is down, but it's not clear yet if it's a tap, drag, or long-press
+    Pressed {
+        id: u64,
+        start: ScreenPt,
+        at: Instant,
+    },
+    /// One finger acts like the left mouse button held down
+    Dragging {
+        id: u64,
+    },
+    /// One finger was held down long enough to right click. Lifting it does nothing more.
+    LongPressed {
+        id: u64,
+    },
+    /// Two fingers are zooming
+    Pinching {
+        last_dist: f64,
+    },
+    /// A gesture was interrupted or ended, but some fingers are still down. Ignore them until
+    /// they're all lifted.
+    Finishing,
+}
+
+impl TouchTracker {
+    pub fn new() -> TouchTracker {
+        TouchTracker {
+            fingers: BTreeMap::new(),
+            gesture: Gesture::Idle,
+            last_tap: Instant::now(),
+        }
+    }
+
+    pub fn event(&mut self, touch: Touch, scale_factor: f64) -> Vec<Event> {
+        let pt: ScreenPt = touch.location.to_logical(scale_factor).into();
+        self.handle(touch.id, touch.phase, pt, Instant::now())
+    }
+
+    /// Nothing happens when a finger is held still, so the event loop has to wake up at this time
+    /// and call `poll`.
+    pub fn long_press_deadline(&self) -> Option<Instant> {
+        match self.gesture {
+            Gesture::Pressed { at, .. } => Some(at + LONG_PRESS),
+            _ => None,
+        }
+    }
+
+    /// Right click once a finger has been held down long enough
+    pub fn poll(&mut self, now: Instant) -> Vec<Event> {
+        match self.gesture {
+            Gesture::Pressed { id, at, .. } if now >= at + LONG_PRESS => {
+                self.gesture = Gesture::LongPressed { id };
+                vec![Event::RightMouseButtonDown, Event::RightMouseButtonUp]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn handle(&mut self, id: u64, phase: TouchPhase, pt: ScreenPt, now: Instant) -> Vec<Event> {
+        match phase {
+            TouchPhase::Started => {
+                self.fingers.insert(id, pt);
+                self.finger_down(id, pt, now)
+            }
+            TouchPhase::Moved => {
+                self.fingers.insert(id, pt);
+                self.finger_moved(id, pt)
+            }
+            TouchPhase::Ended => {
+                self.fingers.remove(&id);
+                self.finger_up(id, false, now)
+            }
+            TouchPhase::Cancelled => {
+                self.fingers.remove(&id);
+                self.finger_up(id, true, now)
+            }
+        }
+    }
+
+    fn finger_down(&mut self, id: u64, pt: ScreenPt, now: Instant) -> Vec<Event> {
+        match self.gesture {
+            Gesture::Idle => {
+                self.gesture = Gesture::Pressed {
+                    id,
+                    start: pt,
+                    at: now,
+                };
+                // Hover whatever's under the finger
+                vec![Event::MouseMovedTo(pt)]
+            }
+            Gesture::Pressed { .. } => {
+                self.start_pinch();
+                Vec::new()
+            }
+            Gesture::Dragging { .. } => {
+                self.start_pinch();
+                vec![Event::LeftMouseButtonUp {
+                    is_double_click: false,
+                }]
+            }
+            Gesture::LongPressed { .. } => {
+                self.start_pinch();
+                Vec::new()
+            }
+            Gesture::Pinching { .. } | Gesture::Finishing => Vec::new(),
+        }
+    }
+
+    fn finger_moved(&mut self, id: u64, pt: ScreenPt) -> Vec<Event> {
+        match self.gesture {
+            Gesture::Pressed {
+                id: first, start, ..
+            } if first == id => {
+                if dist(start, pt) < TAP_SLOP {
+                    return Vec::new();
+                }
+                self.gesture = Gesture::Dragging { id };
+                // The cursor is still at the start
+                vec![Event::LeftMouseButtonDown, Event::MouseMovedTo(pt)]
+            }
+            Gesture::Dragging { id: first } if first == id => vec![Event::MouseMovedTo(pt)],
+            Gesture::Pinching { last_dist } => {
+                if let Some((new_dist, midpoint)) = self.two_fingers() {
+                    self.gesture = Gesture::Pinching {
+                        last_dist: new_dist,
+                    };
+                    if last_dist > 0.0 && new_dist > 0.0 {
+                        return vec![
+                            Event::MouseMovedTo(midpoint),
+                            Event::Pinch(new_dist / last_dist),
+                        ];
+                    }
+                }
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn finger_up(&mut self, id: u64, cancelled: bool, now: Instant) -> Vec<Event> {
+        // If the event loop didn't wake up in time, the long-press still wins
+        let mut events = self.poll(now);
+        events.extend(match self.gesture {
+            Gesture::Pressed { id: first, .. } if first == id && !cancelled => {
+                let is_double_click = now - self.last_tap <= MAX_DOUBLE_CLICK_DURATION;
+                if !is_double_click {
+                    self.last_tap = now;
+                }
+                vec![
+                    Event::LeftMouseButtonDown,
+                    Event::LeftMouseButtonUp { is_double_click },
+                ]
+            }
+            Gesture::Dragging { id: first } if first == id => vec![Event::LeftMouseButtonUp {
+                is_double_click: false,
+            }],
+            _ => Vec::new(),
+        });
+        self.gesture = if self.fingers.is_empty() {
+            Gesture::Idle
+        } else {
+            Gesture::Finishing
+        };
+        events
+    }
+
+    fn start_pinch(&mut self) {
+        self.gesture = match self.two_fingers() {
+            Some((last_dist, _)) => Gesture::Pinching { last_dist },
+            None => Gesture::Finishing,
+        };
+    }
+
+    /// The distance between the first two fingers, and the point between them
+    fn two_fingers(&self) -> Option<(f64, ScreenPt)> {
+        let mut iter = self.fingers.values();
+        let pt1 = *iter.next()?;
+        let pt2 = *iter.next()?;
+        Some((
+            dist(pt1, pt2),
+            ScreenPt::new((pt1.x + pt2.x) / 2.0, (pt1.y + pt2.y) / 2.0),
+        ))
+    }
+}
+
+fn dist(pt1: ScreenPt, pt2: ScreenPt) -> f64 {
+    ((pt1.x - pt2.x).powi(2) + (pt1.y - pt2.y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pt(x: f64, y: f64) -> ScreenPt {
+        ScreenPt::new(x, y)
+    }
+
+    fn ms(start: Instant, millis: u64) -> Instant {
+        start + instant::Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_taps() {
+        let mut touch = TouchTracker::new();
+        let start = touch.last_tap + instant::Duration::from_secs(1);
+
+        assert_eq!(
+            touch.handle(1, TouchPhase::Started, pt(10.0, 10.0), start),
+            vec![Event::MouseMovedTo(pt(10.0, 10.0))]
+        );
+        // A little jitter is still a tap
+        assert!(touch
+            .handle(1, TouchPhase::Moved, pt(12.0, 11.0), ms(start, 50))
+            .is_empty());
+        assert_eq!(
+            touch.handle(1, TouchPhase::Ended, pt(12.0, 11.0), ms(start, 100)),
+            vec![
+                Event::LeftMouseButtonDown,
+                Event::LeftMouseButtonUp {
+                    is_double_click: false
+                }
+            ]
+        );
+
+        // A second quick tap is a double click
+        touch.handle(2, TouchPhase::Started, pt(10.0, 10.0), ms(start, 200));
+        assert_eq!(
+            touch.handle(2, TouchPhase::Ended, pt(10.0, 10.0), ms(start, 250)),
+            vec![
+                Event::LeftMouseButtonDown,
+                Event::LeftMouseButtonUp {
+                    is_double_click: true
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drag() {
+        let mut touch = TouchTracker::new();
+        let start = Instant::now();
+
+        touch.handle(1, TouchPhase::Started, pt(10.0, 10.0), start);
+        assert_eq!(
+            touch.handle(1, TouchPhase::Moved, pt(50.0, 10.0), ms(start, 50)),
+            vec![
+                Event::LeftMouseButtonDown,
+                Event::MouseMovedTo(pt(50.0, 10.0))
+            ]
+        );
+        assert_eq!(
+            touch.handle(1, TouchPhase::Moved, pt(60.0, 10.0), ms(start, 100)),
+            vec![Event::MouseMovedTo(pt(60.0, 10.0))]
+        );
+        // Dragging never turns into a long-press
+        assert!(touch.poll(ms(start, 1000)).is_empty());
+        assert_eq!(
+            touch.handle(1, TouchPhase::Ended, pt(60.0, 10.0), ms(start, 1100)),
+            vec![Event::LeftMouseButtonUp {
+                is_double_click: false
+            }]
+        );
+    }
+
+    #[test]
+    fn test_long_press() {
+        let mut touch = TouchTracker::new();
+        let start = Instant::now();
+
+        touch.handle(1, TouchPhase::Started, pt(10.0, 10.0), start);
+        assert_eq!(touch.long_press_deadline(), Some(start + LONG_PRESS));
+        assert!(touch.poll(ms(start, 100)).is_empty());
+        // The right click happens while the finger is still down
+        assert_eq!(
+            touch.poll(start + LONG_PRESS),
+            vec![Event::RightMouseButtonDown, Event::RightMouseButtonUp]
+        );
+        assert_eq!(touch.long_press_deadline(), None);
+        assert!(touch.poll(ms(start, 2000)).is_empty());
+        assert!(touch
+            .handle(1, TouchPhase::Ended, pt(10.0, 10.0), ms(start, 2000))
+            .is_empty());
+
+        // If nothing polled in time, lifting the finger still right clicks
+        touch.handle(2, TouchPhase::Started, pt(10.0, 10.0), ms(start, 3000));
+        assert_eq!(
+            touch.handle(2, TouchPhase::Ended, pt(10.0, 10.0), ms(start, 4000)),
+            vec![Event::RightMouseButtonDown, Event::RightMouseButtonUp]
+        );
+    }
+
+    #[test]
+    fn test_pinch() {
+        let mut touch = TouchTracker::new();
+        let start = Instant::now();
+
+        touch.handle(1, TouchPhase::Started, pt(0.0, 0.0), start);
+        assert!(touch
+            .handle(2, TouchPhase::Started, pt(100.0, 0.0), ms(start, 10))
+            .is_empty());
+        assert_eq!(
+            touch.handle(2, TouchPhase::Moved, pt(200.0, 0.0), ms(start, 50)),
+            vec![Event::MouseMovedTo(pt(100.0, 0.0)), Event::Pinch(2.0)]
+        );
+        // Lifting one finger ends the pinch, without clicking or panning
+        assert!(touch
+            .handle(2, TouchPhase::Ended, pt(200.0, 0.0), ms(start, 100))
+            .is_empty());
+        assert!(touch
+            .handle(1, TouchPhase::Moved, pt(50.0, 50.0), ms(start, 150))
+            .is_empty());
+        assert!(touch.poll(ms(start, 2000)).is_empty());
+        assert!(touch
+            .handle(1, TouchPhase::Ended, pt(50.0, 50.0), ms(start, 2000))
+            .is_empty());
+        assert!(touch.fingers.is_empty());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut touch = TouchTracker::new();
+        let start = Instant::now();
+
+        touch.handle(1, TouchPhase::Started, pt(10.0, 10.0), start);
+        assert!(touch
+            .handle(1, TouchPhase::Cancelled, pt(10.0, 10.0), ms(start, 50))
+            .is_empty());
+
+        touch.handle(1, TouchPhase::Started, pt(10.0, 10.0), ms(start, 100));
+        touch.handle(1, TouchPhase::Moved, pt(50.0, 10.0), ms(start, 150));
+        // A drag that gets cancelled still has to release the button
+        assert_eq!(
+            touch.handle(1, TouchPhase::Cancelled, pt(50.0, 10.0), ms(start, 200)),
+            vec![Event::LeftMouseButtonUp {
+                is_double_click: false
+            }]
+        );
+    }
+}
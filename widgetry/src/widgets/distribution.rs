@@ -0,0 +1,384 @@
+//! Widgets to see how values are spread out, and to compare a few distributions -- usually the same
+//! measurement before and after some change.
+
+use abstutil::prettyprint_usize;
+use geom::{Circle, Distance, Polygon, Pt2D, UnitFmt};
+
+use crate::widgets::plots::Axis;
+use crate::{
+    Color, DrawWithTooltips, EventCtx, GeomBatch, Line, ScreenDims, Text, TextExt, Widget,
+};
+
+pub struct Distribution<T> {
+    pub label: String,
+    pub color: Color,
+    pub values: Vec<T>,
+}
+
+#[derive(Clone)]
+pub enum Binning<T> {
+    /// Split everything from zero to the largest value into this many equally wide bins
+    Count(usize),
+    /// Explicit lower edges of each bin, sorted. The last bin includes everything past its edge.
+    Edges(Vec<T>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PairedDisplay {
+    /// In each bin, draw a bar per distribution next to each other
+    SideBySide,
+    /// Draw translucent bars on top of each other
+    Overlay,
+    /// Only for exactly two distributions. In each bin, draw one bar showing how the count changed
+    /// from the first distribution to the second.
+    Difference,
+}
+
+pub struct HistogramOptions<T> {
+    pub binning: Binning<T>,
+    pub paired: PairedDisplay,
+    pub dims: Option<ScreenDims>,
+}
+
+impl<T> HistogramOptions<T> {
+    pub fn new(binning: Binning<T>) -> HistogramOptions<T> {
+        HistogramOptions {
+            binning,
+            paired: PairedDisplay::SideBySide,
+            dims: None,
+        }
+    }
+}
+
+/// Counts how many values fall into each bin, with a bar per bin. Hovering on a bin shows the
+/// exact counts.
+pub struct HistogramPlot;
+
+impl HistogramPlot {
+    pub fn new_widget<T: Axis<T>>(
+        ctx: &EventCtx,
+        distributions: Vec<Distribution<T>>,
+        opts: HistogramOptions<T>,
+        unit_fmt: UnitFmt,
+    ) -> Widget {
+        if distributions.iter().all(|d| d.values.is_empty()) {
+            return "No data yet".text_widget(ctx);
+        }
+        let dims = opts.dims.unwrap_or_else(|| default_dims(ctx));
+        let paired = if opts.paired == PairedDisplay::Difference && distributions.len() != 2 {
+            PairedDisplay::SideBySide
+        } else {
+            opts.paired
+        };
+
+        // (lower, upper) for each bin
+        let bins: Vec<(f64, Option<f64>)> = match opts.binning {
+            Binning::Count(num_bins) => {
+                let max = max_value(&distributions).max(T::zero().to_f64() + 1.0);
+                let width = max / (num_bins.max(1) as f64);
+                (0..num_bins.max(1))
+                    .map(|i| ((i as f64) * width, Some(((i + 1) as f64) * width)))
+                    .collect()
+            }
+            Binning::Edges(edges) => {
+                let edges: Vec<f64> = edges.into_iter().map(|x| x.to_f64()).collect();
+                (0..edges.len())
+                    .map(|i| (edges[i], edges.get(i + 1).cloned()))
+                    .collect()
+            }
+        };
+        if bins.is_empty() {
+            return Widget::nothing();
+        }
+        let counts: Vec<Vec<usize>> = distributions
+            .iter()
+            .map(|d| {
+                let mut counts = vec![0; bins.len()];
+                for x in &d.values {
+                    let x = x.to_f64();
+                    let idx = bins.iter().rposition(|(lower, _)| x >= *lower).unwrap_or(0);
+                    counts[idx] += 1;
+                }
+                counts
+            })
+            .collect();
+
+        let mut batch = GeomBatch::new();
+        batch.autocrop_dims = false;
+        let bin_width = dims.width / (bins.len() as f64);
+        let mut tooltips = Vec::new();
+        match paired {
+            PairedDisplay::SideBySide | PairedDisplay::Overlay => {
+                let max_count = counts.iter().flatten().max().cloned().unwrap_or(0).max(1);
+                for (d_idx, d) in distributions.iter().enumerate() {
+                    let (width, offset, color) = if paired == PairedDisplay::SideBySide {
+                        let width = 0.8 * bin_width / (distributions.len() as f64);
+                        (width, 0.1 * bin_width + (d_idx as f64) * width, d.color)
+                    } else {
+                        (0.8 * bin_width, 0.1 * bin_width, d.color.alpha(0.5))
+                    };
+                    for (bin, count) in counts[d_idx].iter().enumerate() {
+                        if *count == 0 {
+                            continue;
+                        }
+                        let height = (*count as f64) / (max_count as f64) * dims.height;
+                        batch.push(
+                            color,
+                            Polygon::rectangle(width, height)
+                                .translate((bin as f64) * bin_width + offset, dims.height - height),
+                        );
+                    }
+                }
+            }
+            PairedDisplay::Difference => {
+                let diffs: Vec<isize> = counts[0]
+                    .iter()
+                    .zip(counts[1].iter())
+                    .map(|(before, after)| (*after as isize) - (*before as isize))
+                    .collect();
+                let max_diff = diffs.iter().map(|x| x.abs()).max().unwrap_or(0).max(1);
+                let baseline = dims.height / 2.0;
+                for (bin, diff) in diffs.into_iter().enumerate() {
+                    if diff == 0 {
+                        continue;
+                    }
+                    let height = (diff.abs() as f64) / (max_diff as f64) * baseline;
+                    let (color, y) = if diff > 0 {
+                        (distributions[1].color, baseline - height)
+                    } else {
+                        (distributions[0].color, baseline)
+                    };
+                    batch.push(
+                        color,
+                        Polygon::rectangle(0.8 * bin_width, height)
+                            .translate((bin as f64) * bin_width + 0.1 * bin_width, y),
+                    );
+                }
+                batch.push(
+                    ctx.style().text_secondary_color,
+                    Polygon::rectangle(dims.width, 1.0).translate(0.0, baseline),
+                );
+            }
+        }
+
+        for (bin, (lower, upper)) in bins.iter().enumerate() {
+            let mut txt = Text::from(Line(describe_bin::<T>(*lower, *upper, &unit_fmt)));
+            for (d, counts) in distributions.iter().zip(counts.iter()) {
+                txt.add_line(
+                    Line(format!(
+                        "{}: {} ({}%)",
+                        d.label,
+                        prettyprint_usize(counts[bin]),
+                        pct(counts[bin], d.values.len())
+                    ))
+                    .fg(d.color),
+                );
+            }
+            tooltips.push((
+                Polygon::rectangle(bin_width, dims.height).translate((bin as f64) * bin_width, 0.0),
+                txt,
+                None,
+            ));
+        }
+
+        // Label at most a handful of bin edges, so they don't overlap
+        let label_every = (bins.len() as f64 / 5.0).ceil().max(1.0) as usize;
+        let mut x_axis = GeomBatch::new();
+        x_axis.autocrop_dims = false;
+        for (bin, (lower, _)) in bins.iter().enumerate().step_by(label_every) {
+            let label =
+                Text::from(Line(T::zero().from_f64(*lower).prettyprint(&unit_fmt)).secondary())
+                    .render_autocropped(ctx);
+            let label_width = label.get_dims().width;
+            x_axis.append(
+                label.translate(
+                    ((bin as f64) * bin_width - label_width / 2.0)
+                        .max(0.0)
+                        .min(dims.width - label_width),
+                    0.0,
+                ),
+            );
+        }
+
+        Widget::col(vec![
+            legend(ctx, &distributions),
+            DrawWithTooltips::new_widget(
+                ctx,
+                batch,
+                tooltips,
+                Box::new(|hitbox| GeomBatch::from(vec![(Color::WHITE.alpha(0.2), hitbox.clone())])),
+            ),
+            x_axis.into_widget(ctx),
+        ])
+    }
+}
+
+/// Summarizes each distribution by its quartiles, drawn as horizontal boxes sharing one scale.
+/// The whiskers reach the minimum and maximum, and the dot is the mean.
+pub struct BoxPlot;
+
+impl BoxPlot {
+    pub fn new_widget<T: Axis<T>>(
+        ctx: &EventCtx,
+        distributions: Vec<Distribution<T>>,
+        dims: Option<ScreenDims>,
+        unit_fmt: UnitFmt,
+    ) -> Widget {
+        if distributions.iter().all(|d| d.values.is_empty()) {
+            return "No data yet".text_widget(ctx);
+        }
+        let width = dims.unwrap_or_else(|| default_dims(ctx)).width;
+        let row_height = 40.0;
+        let label_width = distributions
+            .iter()
+            .map(|d| Text::from(Line(&d.label)).rendered_width(ctx))
+            .fold(0.0, f64::max)
+            + 10.0;
+        let plot_width = width - label_width;
+        let max = max_value(&distributions).max(T::zero().to_f64() + 1.0);
+        let to_x = |x: f64| label_width + x / max * plot_width;
+
+        let mut batch = GeomBatch::new();
+        batch.autocrop_dims = false;
+        let mut tooltips = Vec::new();
+        for (idx, d) in distributions.iter().enumerate() {
+            let y1 = (idx as f64) * row_height;
+            let center = y1 + row_height / 2.0;
+            let label = Text::from(Line(&d.label)).render_autocropped(ctx);
+            let label_height = label.get_dims().height;
+            batch.append(label.translate(0.0, center - label_height / 2.0));
+
+            let mut values: Vec<f64> = d.values.iter().map(|x| x.to_f64()).collect();
+            if values.is_empty() {
+                continue;
+            }
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let percentile = |p: f64| values[(p * ((values.len() - 1) as f64)).round() as usize];
+            let (min, p25, median, p75, max) = (
+                values[0],
+                percentile(0.25),
+                percentile(0.5),
+                percentile(0.75),
+                values[values.len() - 1],
+            );
+            let mean = values.iter().sum::<f64>() / (values.len() as f64);
+
+            // Whisker
+            batch.push(
+                d.color,
+                Polygon::rectangle(to_x(max) - to_x(min), 2.0).translate(to_x(min), center - 1.0),
+            );
+            for x in [min, max] {
+                batch.push(
+                    d.color,
+                    Polygon::rectangle(2.0, row_height / 2.0)
+                        .translate(to_x(x) - 1.0, center - row_height / 4.0),
+                );
+            }
+            // Box
+            let box_height = 0.6 * row_height;
+            let rect = Polygon::rectangle((to_x(p75) - to_x(p25)).max(2.0), box_height)
+                .translate(to_x(p25), center - box_height / 2.0);
+            batch.push(d.color.alpha(0.5), rect.clone());
+            batch.push(d.color, rect.to_outline(Distance::meters(1.0)));
+            batch.push(
+                d.color,
+                Polygon::rectangle(3.0, box_height)
+                    .translate(to_x(median) - 1.5, center - box_height / 2.0),
+            );
+            batch.push(
+                Color::WHITE,
+                Circle::new(Pt2D::new(to_x(mean), center), Distance::meters(4.0)).to_polygon(),
+            );
+
+            let fmt = |x: f64| T::zero().from_f64(x).prettyprint(&unit_fmt);
+            tooltips.push((
+                Polygon::rectangle(width, row_height).translate(0.0, y1),
+                Text::from_multiline(vec![
+                    Line(format!("{} ({})", d.label, prettyprint_usize(values.len()))).fg(d.color),
+                    Line(format!("Minimum: {}", fmt(min))),
+                    Line(format!("25th percentile: {}", fmt(p25))),
+                    Line(format!("Median: {}", fmt(median))),
+                    Line(format!("75th percentile: {}", fmt(p75))),
+                    Line(format!("Maximum: {}", fmt(max))),
+                    Line(format!("Mean: {}", fmt(mean))),
+                ]),
+                None,
+            ));
+        }
+
+        let mut x_axis = GeomBatch::new();
+        x_axis.autocrop_dims = false;
+        for pct in [0.0, 0.5, 1.0] {
+            let label =
+                Text::from(Line(T::zero().from_f64(pct * max).prettyprint(&unit_fmt)).secondary())
+                    .render_autocropped(ctx);
+            let w = label.get_dims().width;
+            x_axis.append(label.translate((to_x(pct * max) - w / 2.0).min(width - w), 0.0));
+        }
+
+        Widget::col(vec![
+            DrawWithTooltips::new_widget(
+                ctx,
+                batch,
+                tooltips,
+                Box::new(|hitbox| GeomBatch::from(vec![(Color::WHITE.alpha(0.1), hitbox.clone())])),
+            ),
+            x_axis.into_widget(ctx),
+        ])
+    }
+}
+
+fn default_dims(ctx: &EventCtx) -> ScreenDims {
+    ScreenDims::new(
+        0.3 * ctx.canvas.window_width,
+        0.2 * ctx.canvas.window_height,
+    )
+}
+
+fn max_value<T: Axis<T>>(distributions: &[Distribution<T>]) -> f64 {
+    distributions
+        .iter()
+        .flat_map(|d| d.values.iter().max())
+        .max()
+        .map(|x| x.to_f64())
+        .unwrap_or(0.0)
+}
+
+fn describe_bin<T: Axis<T>>(lower: f64, upper: Option<f64>, unit_fmt: &UnitFmt) -> String {
+    let fmt = |x: f64| T::zero().from_f64(x).prettyprint(unit_fmt);
+    match upper {
+        Some(upper) => format!("{} to {}", fmt(lower), fmt(upper)),
+        None => format!("at least {}", fmt(lower)),
+    }
+}
+
+fn pct(count: usize, total: usize) -> usize {
+    if total == 0 {
+        0
+    } else {
+        ((count as f64) / (total as f64) * 100.0).round() as usize
+    }
+}
+
+fn legend<T>(ctx: &EventCtx, distributions: &[Distribution<T>]) -> Widget {
+    let radius = 10.0;
+    Widget::custom_row(
+        distributions
+            .iter()
+            .map(|d| {
+                Widget::row(vec![
+                    GeomBatch::from(vec![(
+                        d.color,
+                        Circle::new(Pt2D::new(radius, radius), Distance::meters(radius))
+                            .to_polygon(),
+                    )])
+                    .into_widget(ctx)
+                    .centered_vert(),
+                    d.label.clone().text_widget(ctx),
+                ])
+                .margin_right(16)
+            })
+            .collect(),
+    )
+}
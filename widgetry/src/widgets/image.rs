@@ -1,6 +1,6 @@
 use crate::{
     Color, ContentMode, CornerRounding, DrawWithTooltips, EdgeInsets, EventCtx, GeomBatch,
-    JustDraw, RewriteColor, ScreenDims, ScreenPt, Text, Widget,
+    JustDraw, RewriteColor, ScreenDims, ScreenPt, Text, Widget, ICON_ACCENT,
 };
 use geom::{Bounds, Polygon, Pt2D};
 
@@ -253,10 +253,12 @@ impl<'a, 'c> Image<'a, 'c> {
         self.source.as_ref().map(|source| {
             let (mut image_batch, image_bounds) = source.load(ctx.prerender);
 
-            image_batch = image_batch.color(
-                self.color
-                    .unwrap_or_else(|| RewriteColor::ChangeAll(ctx.style().icon_fg)),
-            );
+            image_batch = image_batch
+                .color(
+                    self.color
+                        .unwrap_or_else(|| RewriteColor::ChangeAll(ctx.style().icon_fg)),
+                )
+                .color(RewriteColor::Change(ICON_ACCENT, ctx.style().icon_accent));
 
             match self.dims {
                 None => {
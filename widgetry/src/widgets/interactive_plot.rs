@@ -0,0 +1,575 @@
+use std::collections::HashSet;
+
+use geom::{Circle, Distance, Polygon, Pt2D, UnitFmt};
+
+use crate::widgets::plots::{thick_lineseries, Axis, PlotOptions, Series};
+use crate::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, ScreenDims, ScreenPt,
+    ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
+};
+
+const LEGEND_ROW_HEIGHT: f64 = 30.0;
+const Y_AXIS_WIDTH: f64 = 80.0;
+const X_AXIS_HEIGHT: f64 = 30.0;
+const NUM_TICKS: usize = 4;
+/// Dragging less than this many pixels is just a click, not a zoom
+const MIN_DRAG: f64 = 5.0;
+/// Each arrow key press pans by this fraction of the visible range
+const PAN_STEP: f64 = 0.1;
+
+/// A line plot that can be explored. Hovering shows the value of every series at that X, dragging
+/// a rectangle zooms in, and double-clicking zooms back out. When zoomed in, the arrow keys or
+/// horizontal scrolling pan. If the plot is filterable, clicking a series in the legend hides it.
+///
+/// Unlike `LinePlot`, the legend and axes are drawn by this widget itself, since they change while
+/// zooming.
+pub struct InteractivePlot<X: Axis<X>, Y: Axis<Y>> {
+    name: String,
+    series: Vec<Series<X, Y>>,
+    hidden: HashSet<String>,
+    filterable: bool,
+    unit_fmt: UnitFmt,
+
+    // In the units of each axis, from `Axis::to_f64`
+    full: View,
+    view: View,
+
+    draw: Drawable,
+    // Relative to top_left. The legend is laid out once, then just redrawn.
+    legend: Vec<(String, Color, ScreenRectangle)>,
+    reset_zoom: Option<ScreenRectangle>,
+    legend_height: f64,
+    plot_dims: ScreenDims,
+
+    hovering: Option<Hovering>,
+    dragging_from: Option<Pt2D>,
+    draw_drag: Option<Drawable>,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct View {
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+}
+
+struct Hovering {
+    tooltip: Text,
+    draw: Drawable,
+}
+
+impl<X: Axis<X>, Y: Axis<Y>> InteractivePlot<X, Y> {
+    /// `label` names the widget. It produces `Outcome::Changed(label)` when a series is hidden or
+    /// shown.
+    pub fn new_widget(
+        ctx: &EventCtx,
+        label: &str,
+        series: Vec<Series<X, Y>>,
+        opts: PlotOptions<X, Y>,
+        unit_fmt: UnitFmt,
+    ) -> Widget {
+        // Assume min_x is X::zero() and min_y is Y::zero()
+        let max_x = opts.max_x.unwrap_or_else(|| {
+            series
+                .iter()
+                .filter_map(|s| s.pts.last().map(|(x, _)| *x))
+                .max()
+                .unwrap_or_else(X::zero)
+        });
+        let max_y = opts.max_y.unwrap_or_else(|| {
+            series
+                .iter()
+                .filter_map(|s| s.pts.iter().map(|(_, y)| *y).max())
+                .max()
+                .unwrap_or_else(Y::zero)
+        });
+        let min_x = X::zero().to_f64();
+        let min_y = Y::zero().to_f64();
+        // Avoid dividing by zero when there's no data yet
+        let full = View {
+            min_x,
+            max_x: max_x.to_f64().max(min_x + 1.0),
+            min_y,
+            max_y: max_y.to_f64().max(min_y + 1.0),
+        };
+
+        // TODO: somehow stretch to fill their container.
+        let plot_dims = opts.dims.unwrap_or_else(|| {
+            ScreenDims::new(
+                0.23 * ctx.canvas.window_width,
+                0.2 * ctx.canvas.window_height,
+            )
+        });
+        let width = Y_AXIS_WIDTH + plot_dims.width;
+
+        // Lay out the legend, wrapping entries onto more rows as needed
+        let mut legend = Vec::new();
+        let mut x = 0.0;
+        let mut y = 0.0;
+        for s in &series {
+            if legend.iter().any(|(label, _, _)| label == &s.label) {
+                continue;
+            }
+            let entry_width = LEGEND_ROW_HEIGHT + Text::from(Line(&s.label)).rendered_width(ctx);
+            if x > 0.0 && x + entry_width > width {
+                x = 0.0;
+                y += LEGEND_ROW_HEIGHT;
+            }
+            legend.push((
+                s.label.clone(),
+                s.color,
+                ScreenRectangle {
+                    x1: x,
+                    y1: y,
+                    x2: x + entry_width,
+                    y2: y + LEGEND_ROW_HEIGHT,
+                },
+            ));
+            x += entry_width + 15.0;
+        }
+        let legend_height = if legend.is_empty() {
+            0.0
+        } else {
+            y + LEGEND_ROW_HEIGHT + 10.0
+        };
+
+        let mut plot = InteractivePlot {
+            name: label.to_string(),
+            series,
+            hidden: opts.disabled,
+            filterable: opts.filterable,
+            unit_fmt,
+
+            full,
+            view: full,
+
+            draw: Drawable::empty(ctx),
+            legend,
+            reset_zoom: None,
+            legend_height,
+            plot_dims,
+
+            hovering: None,
+            dragging_from: None,
+            draw_drag: None,
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(width, legend_height + plot_dims.height + X_AXIS_HEIGHT),
+        };
+        plot.recalc(ctx);
+        Widget::new(Box::new(plot)).named(label)
+    }
+
+    /// The labels of series the player has hidden
+    pub fn hidden_series(&self) -> &HashSet<String> {
+        &self.hidden
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.view != self.full
+    }
+
+    pub fn reset_zoom(&mut self, ctx: &EventCtx) {
+        self.view = self.full;
+        self.recalc(ctx);
+    }
+
+    fn recalc(&mut self, ctx: &EventCtx) {
+        let mut batch = GeomBatch::new();
+        let grid_color = Color::hex("#7C7C7C");
+        let thickness = 1.0;
+
+        // Evenly spaced grid lines, labelled with the values at the current zoom
+        for i in 0..=NUM_TICKS {
+            let pct = (i as f64) / (NUM_TICKS as f64);
+
+            let y = self.legend_height + (1.0 - pct) * self.plot_dims.height;
+            batch.push(
+                grid_color,
+                Polygon::rectangle(self.plot_dims.width, thickness)
+                    .translate(Y_AXIS_WIDTH, y - thickness / 2.0),
+            );
+            let value = self.view.min_y + pct * (self.view.max_y - self.view.min_y);
+            let label = Text::from(Y::zero().from_f64(value).prettyprint(&self.unit_fmt))
+                .render_autocropped(ctx);
+            let dims = label.get_dims();
+            batch.append(label.translate(
+                Y_AXIS_WIDTH - dims.width - 10.0,
+                (y - dims.height / 2.0).max(self.legend_height),
+            ));
+
+            let x = Y_AXIS_WIDTH + pct * self.plot_dims.width;
+            batch.push(
+                grid_color,
+                Polygon::rectangle(thickness, self.plot_dims.height)
+                    .translate(x - thickness / 2.0, self.legend_height),
+            );
+            let value = self.view.min_x + pct * (self.view.max_x - self.view.min_x);
+            let label = Text::from(X::zero().from_f64(value).prettyprint(&self.unit_fmt))
+                .render_autocropped(ctx);
+            let dims = label.get_dims();
+            batch.append(
+                label.translate(
+                    (x - dims.width / 2.0)
+                        .max(0.0)
+                        .min(self.dims.width - dims.width),
+                    self.legend_height + self.plot_dims.height + 10.0,
+                ),
+            );
+        }
+
+        for s in &self.series {
+            if self.hidden.contains(&s.label) {
+                continue;
+            }
+            let pts = self.downsample(&s.pts);
+            if pts.len() >= 2 {
+                batch.push(s.color, thick_lineseries(pts, Distance::meters(3.0)));
+            }
+        }
+
+        for (label, color, rect) in &self.legend {
+            let hidden = self.hidden.contains(label);
+            let radius = 8.0;
+            batch.push(
+                if hidden { Color::grey(0.5) } else { *color },
+                Circle::new(
+                    Pt2D::new(rect.x1 + radius, rect.y1 + LEGEND_ROW_HEIGHT / 2.0),
+                    Distance::meters(radius),
+                )
+                .to_polygon(),
+            );
+            let mut line = Line(label);
+            if hidden {
+                line = line.fg(Color::grey(0.5));
+            }
+            let txt = Text::from(line).render(ctx);
+            let dims = txt.get_dims();
+            batch.append(txt.translate(
+                rect.x1 + LEGEND_ROW_HEIGHT,
+                rect.y1 + (LEGEND_ROW_HEIGHT - dims.height) / 2.0,
+            ));
+        }
+
+        self.reset_zoom = None;
+        if self.is_zoomed() {
+            let txt = Text::from(Line("Reset zoom").underlined())
+                .bg(Color::BLACK.alpha(0.7))
+                .render(ctx);
+            let dims = txt.get_dims();
+            let x1 = self.dims.width - dims.width - 5.0;
+            let y1 = self.legend_height + 5.0;
+            batch.append(txt.translate(x1, y1));
+            self.reset_zoom = Some(ScreenRectangle {
+                x1,
+                y1,
+                x2: x1 + dims.width,
+                y2: y1 + dims.height,
+            });
+        }
+
+        self.draw = ctx.upload(batch);
+    }
+
+    /// Transforms the points that're visible into pixels relative to top_left. Long series have
+    /// many more points than there are pixels, so per column of pixels, only the first, last,
+    /// lowest, and highest points are kept. Unlike averaging, this keeps spikes visible.
+    fn downsample(&self, pts: &[(X, Y)]) -> Vec<Pt2D> {
+        // Also include the point just outside the view on each side, so the line reaches the edge.
+        let start = pts
+            .partition_point(|(x, _)| x.to_f64() < self.view.min_x)
+            .saturating_sub(1);
+        let end = (pts.partition_point(|(x, _)| x.to_f64() <= self.view.max_x) + 1).min(pts.len());
+
+        let mut result = Vec::new();
+        let mut column: Vec<Pt2D> = Vec::new();
+        for (x, y) in &pts[start..end] {
+            let pt = self.to_pixels(x.to_f64(), y.to_f64());
+            if column
+                .last()
+                .map(|last| last.x().floor() != pt.x().floor())
+                .unwrap_or(false)
+            {
+                flush_column(&mut column, &mut result);
+            }
+            column.push(pt);
+        }
+        flush_column(&mut column, &mut result);
+        result
+    }
+
+    /// Clamped to the plot area. Points outside it get squashed onto the edge, which distorts the
+    /// slope of the line a bit there, but keeps it from drawing over the axes.
+    fn to_pixels(&self, x: f64, y: f64) -> Pt2D {
+        let pct_x = (x - self.view.min_x) / (self.view.max_x - self.view.min_x);
+        let pct_y = (y - self.view.min_y) / (self.view.max_y - self.view.min_y);
+        Pt2D::new(
+            Y_AXIS_WIDTH + pct_x.max(0.0).min(1.0) * self.plot_dims.width,
+            // Y inversion! :D
+            self.legend_height + (1.0 - pct_y.max(0.0).min(1.0)) * self.plot_dims.height,
+        )
+    }
+
+    fn from_pixels(&self, pt: Pt2D) -> (f64, f64) {
+        let pct_x = (pt.x() - Y_AXIS_WIDTH) / self.plot_dims.width;
+        let pct_y = 1.0 - (pt.y() - self.legend_height) / self.plot_dims.height;
+        (
+            self.view.min_x + pct_x * (self.view.max_x - self.view.min_x),
+            self.view.min_y + pct_y * (self.view.max_y - self.view.min_y),
+        )
+    }
+
+    fn in_plot(&self, pt: Pt2D) -> bool {
+        pt.x() >= Y_AXIS_WIDTH
+            && pt.x() <= Y_AXIS_WIDTH + self.plot_dims.width
+            && pt.y() >= self.legend_height
+            && pt.y() <= self.legend_height + self.plot_dims.height
+    }
+
+    fn clamp_to_plot(&self, pt: Pt2D) -> Pt2D {
+        Pt2D::new(
+            pt.x()
+                .max(Y_AXIS_WIDTH)
+                .min(Y_AXIS_WIDTH + self.plot_dims.width),
+            pt.y()
+                .max(self.legend_height)
+                .min(self.legend_height + self.plot_dims.height),
+        )
+    }
+
+    fn update_hovering(&mut self, ctx: &EventCtx, cursor: Pt2D) {
+        let (x, _) = self.from_pixels(cursor);
+        let mut txt = Text::from(Line(X::zero().from_f64(x).prettyprint(&self.unit_fmt)));
+        let mut batch = GeomBatch::new();
+        batch.push(
+            Color::WHITE.alpha(0.5),
+            Polygon::rectangle(1.0, self.plot_dims.height)
+                .translate(cursor.x(), self.legend_height),
+        );
+        for s in &self.series {
+            if self.hidden.contains(&s.label) || s.pts.is_empty() {
+                continue;
+            }
+            // The point with the closest X
+            let idx = s.pts.partition_point(|(pt_x, _)| pt_x.to_f64() < x);
+            let (pt_x, pt_y) = if idx == 0 {
+                s.pts[0]
+            } else if idx == s.pts.len() {
+                s.pts[idx - 1]
+            } else if x - s.pts[idx - 1].0.to_f64() < s.pts[idx].0.to_f64() - x {
+                s.pts[idx - 1]
+            } else {
+                s.pts[idx]
+            };
+            txt.add_line(
+                Line(format!("{}: {}", s.label, pt_y.prettyprint(&self.unit_fmt))).fg(s.color),
+            );
+            batch.push(
+                s.color,
+                Circle::new(
+                    self.to_pixels(pt_x.to_f64(), pt_y.to_f64()),
+                    Distance::meters(5.0),
+                )
+                .to_polygon(),
+            );
+        }
+        self.hovering = Some(Hovering {
+            tooltip: txt,
+            draw: ctx.upload(batch),
+        });
+    }
+
+    fn zoom_to(&mut self, ctx: &EventCtx, pt1: Pt2D, pt2: Pt2D) {
+        if (pt1.x() - pt2.x()).abs() < MIN_DRAG || (pt1.y() - pt2.y()).abs() < MIN_DRAG {
+            return;
+        }
+        let (x1, y1) = self.from_pixels(pt1);
+        let (x2, y2) = self.from_pixels(pt2);
+        self.view = View {
+            min_x: x1.min(x2),
+            max_x: x1.max(x2),
+            min_y: y1.min(y2),
+            max_y: y1.max(y2),
+        };
+        self.recalc(ctx);
+    }
+
+    /// Shift the view by some fraction of its width, without leaving the data
+    fn pan(&mut self, ctx: &EventCtx, fraction: f64) {
+        let width = self.view.max_x - self.view.min_x;
+        let min_x = (self.view.min_x + fraction * width)
+            .max(self.full.min_x)
+            .min(self.full.max_x - width);
+        if min_x != self.view.min_x {
+            self.view.min_x = min_x;
+            self.view.max_x = min_x + width;
+            self.hovering = None;
+            self.recalc(ctx);
+        }
+    }
+}
+
+fn flush_column(column: &mut Vec<Pt2D>, result: &mut Vec<Pt2D>) {
+    if column.len() <= 4 {
+        result.append(column);
+        return;
+    }
+    let mut keep = vec![0, column.len() - 1];
+    let mut lowest = 0;
+    let mut highest = 0;
+    for (idx, pt) in column.iter().enumerate() {
+        // Y is inverted
+        if pt.y() > column[lowest].y() {
+            lowest = idx;
+        }
+        if pt.y() < column[highest].y() {
+            highest = idx;
+        }
+    }
+    keep.push(lowest);
+    keep.push(highest);
+    keep.sort_unstable();
+    keep.dedup();
+    result.extend(keep.into_iter().map(|idx| column[idx]));
+    column.clear();
+}
+
+impl<X: Axis<X>, Y: Axis<Y>> WidgetImpl for InteractivePlot<X, Y> {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        let cursor = ctx
+            .canvas
+            .get_cursor_in_screen_space()
+            .map(|pt| Pt2D::new(pt.x - self.top_left.x, pt.y - self.top_left.y));
+
+        if let Some(from) = self.dragging_from {
+            if ctx.input.left_mouse_button_released() {
+                self.dragging_from = None;
+                self.draw_drag = None;
+                if ctx.input.left_mouse_double_clicked() {
+                    self.reset_zoom(ctx);
+                } else if let Some(to) = cursor {
+                    self.zoom_to(ctx, from, self.clamp_to_plot(to));
+                }
+            } else if ctx.input.get_moved_mouse().is_some() {
+                if let Some(to) = cursor {
+                    let to = self.clamp_to_plot(to);
+                    if let Some(rect) = Polygon::rectangle_two_corners(from, to) {
+                        self.draw_drag = Some(
+                            ctx.upload(GeomBatch::from(vec![(Color::WHITE.alpha(0.3), rect)])),
+                        );
+                    }
+                }
+            }
+            return;
+        }
+
+        let cursor = match cursor {
+            Some(pt) => pt,
+            None => {
+                self.hovering = None;
+                return;
+            }
+        };
+        let in_plot = self.in_plot(cursor);
+
+        if ctx.redo_mouseover() {
+            self.hovering = None;
+            if in_plot {
+                self.update_hovering(ctx, cursor);
+            }
+        }
+
+        if self
+            .reset_zoom
+            .as_ref()
+            .map(|rect| rect.contains(ScreenPt::new(cursor.x(), cursor.y())))
+            .unwrap_or(false)
+        {
+            if ctx.normal_left_click() {
+                self.reset_zoom(ctx);
+            }
+            return;
+        }
+
+        if self.filterable {
+            if let Some(label) = self
+                .legend
+                .iter()
+                .find(|(_, _, rect)| rect.contains(ScreenPt::new(cursor.x(), cursor.y())))
+                .map(|(label, _, _)| label.clone())
+            {
+                if ctx.normal_left_click() {
+                    if !self.hidden.remove(&label) {
+                        self.hidden.insert(label);
+                    }
+                    self.recalc(ctx);
+                    output.outcome = Outcome::Changed(self.name.clone());
+                }
+                return;
+            }
+        }
+
+        if !in_plot {
+            return;
+        }
+        if ctx.input.left_mouse_button_pressed() {
+            self.dragging_from = Some(cursor);
+            self.hovering = None;
+            return;
+        }
+        if self.is_zoomed() {
+            if ctx.input.pressed(Key::LeftArrow) {
+                self.pan(ctx, -PAN_STEP);
+            } else if ctx.input.pressed(Key::RightArrow) {
+                self.pan(ctx, PAN_STEP);
+            } else if let Some((dx, _)) = ctx.input.get_mouse_scroll() {
+                if dx != 0.0 {
+                    self.pan(ctx, dx * PAN_STEP);
+                }
+            }
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+        if let Some(ref draw) = self.draw_drag {
+            g.redraw_at(self.top_left, draw);
+        }
+
+        if let Some(ref hovering) = self.hovering {
+            g.redraw_at(self.top_left, &hovering.draw);
+            g.fork_screenspace();
+            g.draw_mouse_tooltip(hovering.tooltip.clone());
+            g.unfork();
+        }
+    }
+
+    fn can_restore(&self) -> bool {
+        true
+    }
+
+    fn restore(&mut self, ctx: &mut EventCtx, prev: &dyn WidgetImpl) {
+        let prev = prev.downcast_ref::<InteractivePlot<X, Y>>().unwrap();
+        self.hidden = prev.hidden.clone();
+        // The data may have grown since, so only keep the zoom if it still fits
+        if prev.is_zoomed()
+            && prev.view.min_x >= self.full.min_x
+            && prev.view.max_x <= self.full.max_x
+        {
+            self.view = prev.view;
+        }
+        self.recalc(ctx);
+    }
+}
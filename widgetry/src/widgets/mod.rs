@@ -12,6 +12,7 @@ use geom::{CornerRadii, Distance, Percent, Polygon};
 
 use crate::widgets::containers::{Container, Nothing};
 pub use crate::widgets::panel::{Panel, PanelBuilder, PanelDims};
+use crate::widgets::scroll_area::ScrollArea;
 use crate::{
     Button, Choice, Color, DeferDraw, Drawable, Dropdown, EventCtx, GeomBatch, GfxCtx, JustDraw,
     OutlineStyle, ScreenDims, ScreenPt, ScreenRectangle, Text, Toggle,
@@ -21,11 +22,13 @@ pub mod autocomplete;
 pub mod button;
 pub mod compare_times;
 pub mod containers;
+pub mod distribution;
 pub mod drag_drop;
 pub mod dropdown;
 pub mod fan_chart;
 pub mod filler;
 pub mod image;
+pub mod interactive_plot;
 pub mod just_draw;
 pub mod line_plot;
 pub mod menu;
@@ -33,6 +36,7 @@ mod panel;
 pub mod persistent_split;
 pub mod plots;
 pub mod scatter_plot;
+pub mod scroll_area;
 pub mod slider;
 pub mod spinner;
 pub mod stash;
@@ -705,6 +709,8 @@ impl Widget {
                     defer_draw,
                 );
             }
+        } else if let Some(area) = self.widget.downcast_mut::<ScrollArea>() {
+            area.set_pos_and_layout(ctx, top_left);
         } else {
             self.widget.set_pos(top_left);
         }
@@ -722,6 +728,14 @@ impl Widget {
             for w in &container.members {
                 w.get_all_click_actions(actions);
             }
+        } else if let Some(area) = self.widget.downcast_ref::<ScrollArea>() {
+            for panel in area.panels() {
+                for action in panel.get_all_click_actions() {
+                    if !actions.insert(action.clone()) {
+                        panic!("Two buttons in one Panel both use action {}", action);
+                    }
+                }
+            }
         }
     }
 
@@ -740,6 +754,8 @@ impl Widget {
                     return Some(a);
                 }
             }
+        } else if let Some(area) = self.widget.downcast_ref::<ScrollArea>() {
+            return area.panels().find_map(|panel| panel.currently_hovering());
         }
         None
     }
@@ -786,6 +802,8 @@ impl Widget {
                     return Some(w);
                 }
             }
+        } else if let Some(area) = self.widget.downcast_ref::<ScrollArea>() {
+            return area.find(name);
         }
 
         None
@@ -801,6 +819,8 @@ impl Widget {
                     return Some(w);
                 }
             }
+        } else if let Some(area) = self.widget.downcast_mut::<ScrollArea>() {
+            return area.find_mut(name);
         }
 
         None
@@ -56,11 +56,13 @@ impl Panel {
             PanelDims::MaxPercent(pct) => self.contents_dims.width.min(pct * canvas_dims.width),
             PanelDims::ExactPercent(pct) => pct * canvas_dims.width,
             PanelDims::ExactPixels(x) => x,
+            PanelDims::MaxPixels(x) => self.contents_dims.width.min(x),
         };
         let height = match self.dims_y {
             PanelDims::MaxPercent(pct) => self.contents_dims.height.min(pct * canvas_dims.height),
             PanelDims::ExactPercent(pct) => pct * canvas_dims.height,
             PanelDims::ExactPixels(x) => x,
+            PanelDims::MaxPixels(x) => self.contents_dims.height.min(x),
         };
         self.container_dims = ScreenDims::new(width, height);
     }
@@ -226,7 +228,7 @@ impl Panel {
         self.cached_flexbox = Some((taffy, nodes, effective_dims));
     }
 
-    fn scroll_offset(&self) -> (f64, f64) {
+    pub(crate) fn scroll_offset(&self) -> (f64, f64) {
         let x = if self.scrollable_x {
             self.slider("horiz scrollbar").get_percent()
                 * (self.contents_dims.width - self.container_dims.width).max(0.0)
@@ -267,7 +269,7 @@ impl Panel {
         changed
     }
 
-    fn set_scroll_offset(&mut self, ctx: &EventCtx, offset: (f64, f64)) {
+    pub(crate) fn set_scroll_offset(&mut self, ctx: &EventCtx, offset: (f64, f64)) {
         if self.update_scroll_sliders(ctx, offset) {
             self.recompute_layout_if_needed(ctx, false);
         }
@@ -278,7 +280,9 @@ impl Panel {
             && ctx
                 .canvas
                 .get_cursor_in_screen_space()
-                .map(|pt| self.top_level.rect.contains(pt))
+                .map(|pt| {
+                    self.top_level.rect.contains(pt) && !ctx.canvas.is_over_nested_scroll_area(pt)
+                })
                 .unwrap_or(false)
         {
             if let Some((dx, dy)) = ctx.input.get_mouse_scroll() {
@@ -311,6 +315,10 @@ impl Panel {
             self.recompute_layout_if_needed(ctx, true);
         }
 
+        if let Outcome::Clicked(_) | Outcome::ClickCustom(_) = output.outcome {
+            ctx.button_clicked = true;
+        }
+
         // Remember this for the next event
         if let Outcome::Focused(ref id) = output.outcome {
             assert!(ctx.next_focus_owned_by.is_none());
@@ -543,6 +551,34 @@ impl Panel {
         // TODO Recompute layout and fire no_op_event?
     }
 
+    /// Moves a panel nested inside a widget, like a `ScrollArea`, to a new position.
+    pub(crate) fn set_top_left(&mut self, ctx: &EventCtx, top_left: ScreenPt) {
+        self.horiz = HorizontalAlignment::Percent(top_left.x / ctx.canvas.window_width);
+        self.vert = VerticalAlignment::Percent(top_left.y / ctx.canvas.window_height);
+        self.recompute_layout_if_needed(ctx, false);
+    }
+
+    /// How much space the panel takes on screen, including scrollbars, but not contents scrolled
+    /// out of view.
+    pub(crate) fn visible_dims(&self) -> ScreenDims {
+        let mut dims = self.container_dims;
+        if self.scrollable_y {
+            dims.width += slider::SCROLLBAR_BG_WIDTH;
+        }
+        if self.scrollable_x {
+            dims.height += slider::SCROLLBAR_BG_WIDTH;
+        }
+        dims
+    }
+
+    pub(crate) fn is_scrollable(&self) -> bool {
+        self.scrollable_x || self.scrollable_y
+    }
+
+    pub(crate) fn find_widget_mut(&mut self, name: &str) -> Option<&mut Widget> {
+        self.top_level.find_mut(name)
+    }
+
     /// All margins/padding/etc from the previous widget are retained. The ID is set on the new
     /// widget; no need to do that yourself.
     pub fn replace(&mut self, ctx: &mut EventCtx, id: &str, mut new: Widget) {
@@ -591,6 +627,8 @@ pub enum PanelDims {
     MaxPercent(f64),
     ExactPercent(f64),
     ExactPixels(f64),
+    /// Shrink to fit the contents, but scroll past this many pixels
+    MaxPixels(f64),
 }
 
 impl PanelBuilder {
@@ -617,7 +655,7 @@ impl PanelBuilder {
             cached_flexbox: None,
         };
         match self.dims_x {
-            PanelDims::MaxPercent(_) => {}
+            PanelDims::MaxPercent(_) | PanelDims::MaxPixels(_) => {}
             PanelDims::ExactPercent(pct) => {
                 // Don't set size, because then scrolling breaks -- the actual size has to be based
                 // on the contents.
@@ -629,7 +667,7 @@ impl PanelBuilder {
             }
         }
         match self.dims_y {
-            PanelDims::MaxPercent(_) => {}
+            PanelDims::MaxPercent(_) | PanelDims::MaxPixels(_) => {}
             PanelDims::ExactPercent(pct) => {
                 panel.top_level.layout.style.min_size.height =
                     Dimension::Points((pct * ctx.canvas.window_height) as f32);
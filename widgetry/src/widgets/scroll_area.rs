@@ -0,0 +1,232 @@
+use crate::widgets::panel::PanelDims;
+use crate::widgets::slider::SCROLLBAR_BG_WIDTH;
+use crate::{
+    EventCtx, GfxCtx, Outcome, Panel, ScreenDims, ScreenPt, ScreenRectangle, Widget, WidgetImpl,
+    WidgetOutput,
+};
+
+/// Dragging less than this many pixels is still just a click
+const MIN_DRAG: f64 = 5.0;
+
+/// Wraps a column of widgets that might be too tall to fit, scrolling with the mouse wheel, by
+/// dragging the contents, or with a scrollbar. An optional header stays at the top while the rest
+/// scrolls underneath it.
+///
+/// The header and contents are nested panels. Widgets inside them can be found from the outer
+/// panel as usual, and outcomes from them are returned by the outer panel.
+pub struct ScrollArea {
+    header: Option<Panel>,
+    body: Panel,
+    drag: Option<Drag>,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+struct Drag {
+    cursor_y: f64,
+    offset: (f64, f64),
+    moved: bool,
+}
+
+impl ScrollArea {
+    /// `label` names the widget, so that scrolling can be restored when the outer panel is
+    /// rebuilt.
+    pub fn new_widget(
+        ctx: &mut EventCtx,
+        label: &str,
+        header: Option<Widget>,
+        body: Widget,
+        max_height: f64,
+    ) -> Widget {
+        let header = header.map(|header| {
+            Panel::new_builder(header)
+                .ignore_initial_events()
+                .build_custom(ctx)
+        });
+        let header_height = header
+            .as_ref()
+            .map(|panel| panel.visible_dims().height)
+            .unwrap_or(0.0);
+        let body = Panel::new_builder(body)
+            .dims_height(PanelDims::MaxPixels((max_height - header_height).max(0.0)))
+            .ignore_initial_events()
+            .build_custom(ctx);
+
+        let mut area = ScrollArea {
+            header,
+            body,
+            drag: None,
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(0.0, 0.0),
+        };
+        area.dims = area.calculate_dims();
+        Widget::new(Box::new(area)).named(label)
+    }
+
+    fn calculate_dims(&self) -> ScreenDims {
+        let body = self.body.visible_dims();
+        match self.header {
+            Some(ref header) => {
+                let header = header.visible_dims();
+                ScreenDims::new(header.width.max(body.width), header.height + body.height)
+            }
+            None => body,
+        }
+    }
+
+    fn body_rect(&self) -> ScreenRectangle {
+        let body = self.body.visible_dims();
+        ScreenRectangle::top_left(
+            ScreenPt::new(
+                self.top_left.x,
+                self.top_left.y + self.dims.height - body.height,
+            ),
+            ScreenDims::new(self.dims.width, body.height),
+        )
+    }
+
+    /// Unlike `set_pos`, the nested panels need to be laid out again.
+    pub(crate) fn set_pos_and_layout(&mut self, ctx: &EventCtx, top_left: ScreenPt) {
+        self.top_left = top_left;
+        if let Some(ref mut header) = self.header {
+            header.set_top_left(ctx, top_left);
+        }
+        let rect = self.body_rect();
+        self.body.set_top_left(ctx, ScreenPt::new(rect.x1, rect.y1));
+    }
+
+    pub(crate) fn find(&self, name: &str) -> Option<&Widget> {
+        self.header
+            .as_ref()
+            .and_then(|header| header.maybe_find_widget(name))
+            .or_else(|| self.body.maybe_find_widget(name))
+    }
+
+    pub(crate) fn find_mut(&mut self, name: &str) -> Option<&mut Widget> {
+        if let Some(ref mut header) = self.header {
+            if let Some(w) = header.find_widget_mut(name) {
+                return Some(w);
+            }
+        }
+        self.body.find_widget_mut(name)
+    }
+
+    pub(crate) fn panels(&self) -> impl Iterator<Item = &Panel> {
+        self.header.iter().chain(std::iter::once(&self.body))
+    }
+
+    /// Returns true if the event was used to scroll
+    fn scroll_event(&mut self, ctx: &mut EventCtx) -> bool {
+        if !self.body.is_scrollable() {
+            return false;
+        }
+        let cursor = ctx.canvas.get_cursor_in_screen_space();
+        let over_body = cursor
+            .map(|pt| self.body_rect().contains(pt))
+            .unwrap_or(false);
+
+        if over_body {
+            if let Some((dx, dy)) = ctx.input.get_mouse_scroll() {
+                let speed = ctx.canvas.settings.gui_scroll_speed as f64;
+                let (x, y) = self.body.scroll_offset();
+                self.body
+                    .set_scroll_offset(ctx, (x + dx * speed, y - dy * speed));
+                return true;
+            }
+        }
+
+        if let Some(ref mut drag) = self.drag {
+            if ctx.input.left_mouse_button_released() {
+                let moved = drag.moved;
+                self.drag = None;
+                if moved {
+                    // Don't let a button under the cursor think it was clicked
+                    ctx.input.consume_event();
+                    return true;
+                }
+                return false;
+            }
+            if ctx.input.get_moved_mouse().is_some() {
+                let dy = ctx.canvas.get_cursor().y - drag.cursor_y;
+                if drag.moved || dy.abs() >= MIN_DRAG {
+                    drag.moved = true;
+                    let offset = (drag.offset.0, drag.offset.1 - dy);
+                    self.body.set_scroll_offset(ctx, offset);
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        // Leave the scrollbar alone
+        let over_contents = cursor
+            .map(|pt| pt.x < self.top_left.x + self.dims.width - SCROLLBAR_BG_WIDTH)
+            .unwrap_or(false);
+        if over_body && over_contents && ctx.input.left_mouse_button_pressed() {
+            self.drag = Some(Drag {
+                cursor_y: ctx.canvas.get_cursor().y,
+                offset: self.body.scroll_offset(),
+                moved: false,
+            });
+        }
+        false
+    }
+}
+
+impl WidgetImpl for ScrollArea {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if self.scroll_event(ctx) {
+            return;
+        }
+
+        let mut outcome = match self.header {
+            Some(ref mut header) => header.event(ctx),
+            None => Outcome::Nothing,
+        };
+        if matches!(outcome, Outcome::Nothing) {
+            // Widgets scrolled out of view shouldn't respond to the mouse
+            ctx.canvas.hit_test_clips.push(self.body_rect());
+            outcome = self.body.event(ctx);
+            ctx.canvas.hit_test_clips.pop();
+        }
+        // The nested panel already took care of focus
+        if !matches!(outcome, Outcome::Nothing | Outcome::Focused(_)) {
+            output.outcome = outcome;
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        if let Some(ref header) = self.header {
+            header.draw(g);
+        }
+        self.body.draw(g);
+        if self.body.is_scrollable() {
+            g.canvas
+                .nested_scroll_areas
+                .borrow_mut()
+                .push(self.body_rect());
+        }
+    }
+
+    fn can_restore(&self) -> bool {
+        true
+    }
+
+    fn restore(&mut self, ctx: &mut EventCtx, prev: &dyn WidgetImpl) {
+        let prev = prev.downcast_ref::<ScrollArea>().unwrap();
+        if let (Some(header), Some(prev_header)) = (self.header.as_mut(), prev.header.as_ref()) {
+            header.restore(ctx, prev_header);
+        }
+        self.body.restore(ctx, &prev.body);
+    }
+}